@@ -0,0 +1,291 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod stats;
+use self::stats::{Samples, Summary};
+
+use super::common::{self, emit_error};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gpiocdev::line::{EdgeDetection, Offset, Value};
+use gpiocdev::request::{Builder, Request};
+use gpiocdev::AbiVersion;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The chip with the lines to exercise
+    ///
+    /// The chip may be identified by number, name, or path.
+    /// e.g. the following all select the same chip:
+    ///     --chip 0
+    ///     --chip gpiochip0
+    ///     --chip /dev/gpiochip0
+    #[arg(short, long, value_name = "chip", verbatim_doc_comment)]
+    chip: String,
+
+    /// The offsets of the lines to exercise
+    ///
+    /// The first line is driven as an output for the set benchmark, then
+    /// read as an input for the get benchmark.
+    ///
+    /// If a second line is provided then the first is assumed to be looped
+    /// back to the second, and the latency from setting the first to reading
+    /// the edge event from the second is measured.
+    ///
+    /// With --sim, the last line is pulled by the gpio-sim instead, so a
+    /// single line suffices for the edge benchmark.
+    #[arg(value_name = "offset", num_args = 1..=2, required = true)]
+    offsets: Vec<Offset>,
+
+    /// The time period to run each benchmark
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(short = 'd', long, value_name = "period", default_value = "1s", value_parser = common::parse_duration)]
+    duration: Duration,
+
+    /// The number of operations performed before measurement starts
+    #[arg(short = 'w', long, value_name = "count", default_value = "100")]
+    warmup: u32,
+
+    /// The time to wait for each edge event in the edge benchmark
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(long, value_name = "period", default_value = "100ms", value_parser = common::parse_duration)]
+    edge_timeout: Duration,
+
+    /// The chip is a gpio-sim, so edges can be generated by the simulator
+    ///
+    /// The edge benchmark measures the latency from pulling the input line,
+    /// via the sim sysfs attribute, to reading the edge event in userspace.
+    #[arg(long)]
+    sim: bool,
+
+    /// The consumer label applied to requested lines.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-bench"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Err(e) => {
+            emit_error(&opts.emit, &e);
+            false
+        }
+        Ok(summaries) => {
+            emit_summaries(opts, &summaries);
+            true
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<Vec<Summary>> {
+    let path = common::chip_lookup_from_id(&opts.chip)?;
+    let abiv = common::actual_abi_version(&opts.uapi_opts)?;
+    let bench = Bench { opts, path, abiv };
+    let mut summaries = vec![bench.set()?, bench.get()?];
+    if opts.sim {
+        summaries.push(bench.sim_edge()?);
+    } else if opts.offsets.len() > 1 {
+        summaries.push(bench.edge()?);
+    }
+    Ok(summaries)
+}
+
+struct Bench<'a> {
+    opts: &'a Opts,
+    path: PathBuf,
+    abiv: AbiVersion,
+}
+
+impl Bench<'_> {
+    fn builder(&self) -> Builder {
+        new_builder(&self.path, self.abiv, &self.opts.consumer)
+    }
+
+    // Time the operation, repeated until the benchmark period has elapsed.
+    fn run<F>(&self, name: &'static str, mut op: F) -> Result<Summary>
+    where
+        F: FnMut() -> Result<Duration>,
+    {
+        for _ in 0..self.opts.warmup {
+            op()?;
+        }
+        let mut samples = Samples::default();
+        let start = Instant::now();
+        loop {
+            samples.add(op()?);
+            let elapsed = start.elapsed();
+            if elapsed >= self.opts.duration {
+                return Ok(samples.summary(name, elapsed));
+            }
+        }
+    }
+
+    // The latency and throughput of setting the value of an output line.
+    fn set(&self) -> Result<Summary> {
+        let offset = self.opts.offsets[0];
+        let req = self
+            .builder()
+            .with_line(offset)
+            .as_output(Value::Inactive)
+            .request()
+            .context("failed to request output line")?;
+        let mut value = Value::Inactive;
+        self.run("set", || {
+            value = !value;
+            let start = Instant::now();
+            req.set_value(offset, value)
+                .context("failed to set line value")?;
+            Ok(start.elapsed())
+        })
+    }
+
+    // The latency and throughput of reading the value of an input line.
+    fn get(&self) -> Result<Summary> {
+        let offset = self.opts.offsets[0];
+        let req = self
+            .builder()
+            .with_line(offset)
+            .as_input()
+            .request()
+            .context("failed to request input line")?;
+        self.run("get", || {
+            let start = Instant::now();
+            req.value(offset).context("failed to read line value")?;
+            Ok(start.elapsed())
+        })
+    }
+
+    // The latency from setting an output to reading the edge event from an input
+    // looped back to it.
+    fn edge(&self) -> Result<Summary> {
+        let (out, input) = (self.opts.offsets[0], self.opts.offsets[1]);
+        // separate requests, as uAPI v1 cannot mix directions in one request
+        let oreq = self
+            .builder()
+            .with_line(out)
+            .as_output(Value::Inactive)
+            .request()
+            .context("failed to request output line")?;
+        let ireq = self
+            .builder()
+            .with_line(input)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .context("failed to request edge detection line")?;
+        let mut value = Value::Inactive;
+        self.run("edge", || {
+            value = !value;
+            let start = Instant::now();
+            oreq.set_value(out, value)
+                .context("failed to set line value")?;
+            if !ireq
+                .wait_edge_event(self.opts.edge_timeout)
+                .context("failed to wait for edge event")?
+            {
+                bail!(
+                    "no edge event received on line {} - is line {} looped back to it?",
+                    input,
+                    out
+                );
+            }
+            ireq.read_edge_event()
+                .context("failed to read edge event")?;
+            Ok(start.elapsed())
+        })
+    }
+
+    // The latency from the gpio-sim pulling an input to reading the edge event.
+    fn sim_edge(&self) -> Result<Summary> {
+        let input = *self.opts.offsets.last().unwrap();
+        let pull = sim_pull_path(&self.path, input)?;
+        let pull = OpenOptions::new()
+            .write(true)
+            .open(&pull)
+            .with_context(|| format!("failed to open sim pull {}", pull.display()))?;
+        pull.write_at(b"pull-down", 0)
+            .context("failed to set sim pull")?;
+        let ireq = self
+            .builder()
+            .with_line(input)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .context("failed to request edge detection line")?;
+        let mut value = Value::Inactive;
+        self.run("edge", || {
+            value = !value;
+            let level: &[u8] = match value {
+                Value::Active => b"pull-up",
+                Value::Inactive => b"pull-down",
+            };
+            let start = Instant::now();
+            pull.write_at(level, 0).context("failed to set sim pull")?;
+            if !ireq
+                .wait_edge_event(self.opts.edge_timeout)
+                .context("failed to wait for edge event")?
+            {
+                bail!("no edge event received on line {}", input);
+            }
+            ireq.read_edge_event()
+                .context("failed to read edge event")?;
+            Ok(start.elapsed())
+        })
+    }
+}
+
+// The sysfs attribute controlling the pull of a gpio-sim line.
+fn sim_pull_path(chip: &Path, offset: Offset) -> Result<PathBuf> {
+    let chip =
+        fs::canonicalize(chip).with_context(|| format!("failed to resolve {}", chip.display()))?;
+    let name = chip.file_name().unwrap_or_default();
+    let pull = Path::new("/sys/bus/gpio/devices")
+        .join(name)
+        .join(format!("sim_gpio{}", offset))
+        .join("pull");
+    if !pull.exists() {
+        bail!(
+            "{} is not a gpio-sim with line {}",
+            name.to_string_lossy(),
+            offset
+        );
+    }
+    Ok(pull)
+}
+
+#[cfg_attr(
+    not(all(feature = "uapi_v1", feature = "uapi_v2")),
+    allow(unused_variables)
+)]
+fn new_builder(path: &Path, abiv: AbiVersion, consumer: &str) -> Builder {
+    let mut bld = Request::builder();
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(abiv);
+    bld.on_chip(path).with_consumer(consumer);
+    bld
+}
+
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+fn emit_summaries(opts: &Opts, summaries: &[Summary]) {
+    #[cfg(feature = "json")]
+    if opts.emit.json {
+        println!("{}", serde_json::to_string(summaries).unwrap());
+        return;
+    }
+    println!("{}", stats::format_table(summaries));
+}
@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+// The latencies of the operations in one benchmark.
+//
+// The latencies are recorded in a histogram, rather than individually, so the
+// memory used is fixed however long the benchmark runs.
+// The min, mean and max are exact, while the percentiles are accurate to the
+// width of the bucket containing them, which is within 1/64 of the latency.
+#[derive(Debug)]
+pub(super) struct Samples {
+    buckets: Vec<u64>,
+    count: u64,
+    total_ns: u128,
+    min_ns: u64,
+    max_ns: u64,
+}
+
+impl Default for Samples {
+    fn default() -> Self {
+        Samples {
+            buckets: vec![0; bucket_idx(u64::MAX) + 1],
+            count: 0,
+            total_ns: 0,
+            min_ns: u64::MAX,
+            max_ns: 0,
+        }
+    }
+}
+
+impl Samples {
+    pub(super) fn add(&mut self, latency: Duration) {
+        let ns = latency.as_nanos().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_idx(ns)] += 1;
+        self.count += 1;
+        self.total_ns += ns as u128;
+        self.min_ns = self.min_ns.min(ns);
+        self.max_ns = self.max_ns.max(ns);
+    }
+
+    // Summarize the latencies of operations performed over the elapsed period.
+    pub(super) fn summary(self, name: &'static str, elapsed: Duration) -> Summary {
+        let mut summary = Summary {
+            name,
+            count: self.count,
+            rate: 0,
+            min_ns: None,
+            mean_ns: None,
+            p99_ns: None,
+            max_ns: None,
+        };
+        if self.count == 0 {
+            return summary;
+        }
+        if !elapsed.is_zero() {
+            summary.rate = (self.count as u128 * 1_000_000_000 / elapsed.as_nanos()) as u64;
+        }
+        summary.min_ns = Some(self.min_ns);
+        summary.mean_ns = Some((self.total_ns / self.count as u128) as u64);
+        summary.p99_ns = Some(self.percentile(99));
+        summary.max_ns = Some(self.max_ns);
+        summary
+    }
+
+    // The nearest-rank percentile, as the upper bound of the bucket containing it,
+    // limited to the range of the samples.
+    fn percentile(&self, pc: u64) -> u64 {
+        let rank = percentile_idx(self.count, pc) + 1;
+        let mut seen = 0;
+        for (idx, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return bucket_max(idx).clamp(self.min_ns, self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+}
+
+// The number of bits used to subdivide each power of two into buckets.
+const SUB_BUCKET_BITS: u32 = 6;
+
+// The index of the histogram bucket containing the latency.
+//
+// Latencies below 2^SUB_BUCKET_BITS each have their own bucket, while those
+// above are split into 2^SUB_BUCKET_BITS buckets per power of two.
+fn bucket_idx(ns: u64) -> usize {
+    let sub_buckets = 1 << SUB_BUCKET_BITS;
+    if ns < sub_buckets {
+        return ns as usize;
+    }
+    let exp = 63 - ns.leading_zeros();
+    let sub = (ns >> (exp - SUB_BUCKET_BITS)) - sub_buckets;
+    ((exp - SUB_BUCKET_BITS + 1) as u64 * sub_buckets + sub) as usize
+}
+
+// The largest latency in the histogram bucket.
+fn bucket_max(idx: usize) -> u64 {
+    let sub_buckets = 1 << SUB_BUCKET_BITS;
+    if idx < sub_buckets {
+        return idx as u64;
+    }
+    let exp = (idx / sub_buckets) as u32 + SUB_BUCKET_BITS - 1;
+    let sub = (idx % sub_buckets + sub_buckets) as u64;
+    let shift = exp - SUB_BUCKET_BITS;
+    // computed from the next bucket, less one, so does not overflow for the last
+    ((sub << shift) - 1) + (1 << shift)
+}
+
+// The index of the nearest-rank percentile in count sorted samples.
+fn percentile_idx(count: u64, pc: u64) -> u64 {
+    (count * pc).div_ceil(100).max(1) - 1
+}
+
+// The results of one benchmark.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub(super) struct Summary {
+    pub(super) name: &'static str,
+    pub(super) count: u64,
+    // operations per second
+    pub(super) rate: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(super) min_ns: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(super) mean_ns: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(super) p99_ns: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(super) max_ns: Option<u64>,
+}
+
+// Format the summaries as a table, one row per benchmark.
+pub(super) fn format_table(summaries: &[Summary]) -> String {
+    let format_ns = |ns: Option<u64>| match ns {
+        Some(ns) => format!("{:?}", Duration::from_nanos(ns)),
+        None => "-".to_string(),
+    };
+    let mut text = format!(
+        "{:5}  {:>9}  {:>9}  {:>12}  {:>12}  {:>12}  {:>12}",
+        "bench", "count", "rate/s", "min", "mean", "p99", "max"
+    );
+    for s in summaries {
+        _ = write!(
+            text,
+            "\n{:5}  {:>9}  {:>9}  {:>12}  {:>12}  {:>12}  {:>12}",
+            s.name,
+            s.count,
+            s.rate,
+            format_ns(s.min_ns),
+            format_ns(s.mean_ns),
+            format_ns(s.p99_ns),
+            format_ns(s.max_ns)
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(us: &[u64]) -> Samples {
+        let mut s = Samples::default();
+        for us in us {
+            s.add(Duration::from_micros(*us));
+        }
+        s
+    }
+
+    #[test]
+    fn summary() {
+        let s = samples(&[30, 10, 20, 40]);
+        assert_eq!(
+            s.summary("get", Duration::from_millis(2)),
+            Summary {
+                name: "get",
+                count: 4,
+                rate: 2000,
+                min_ns: Some(10_000),
+                mean_ns: Some(25_000),
+                p99_ns: Some(40_000),
+                max_ns: Some(40_000),
+            }
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let s = Samples::default();
+        assert_eq!(
+            s.summary("edge", Duration::from_secs(1)),
+            Summary {
+                name: "edge",
+                count: 0,
+                rate: 0,
+                min_ns: None,
+                mean_ns: None,
+                p99_ns: None,
+                max_ns: None,
+            }
+        );
+    }
+
+    #[test]
+    fn p99() {
+        // 1..=1000us, so the 99th percentile is the 990th sample, reported
+        // as the upper bound of its bucket
+        let us: Vec<u64> = (1..=1000).rev().collect();
+        let summary = samples(&us).summary("set", Duration::from_secs(1));
+        let p99 = summary.p99_ns.unwrap();
+        assert!((990_000..990_000 + 990_000 / 64).contains(&p99), "{}", p99);
+        assert_eq!(summary.min_ns, Some(1_000));
+        assert_eq!(summary.mean_ns, Some(500_500));
+        assert_eq!(summary.max_ns, Some(1_000_000));
+        assert_eq!(summary.rate, 1000);
+
+        // with fewer than 100 samples the 99th percentile is the max
+        let summary = samples(&[5, 1, 3]).summary("set", Duration::from_secs(1));
+        assert_eq!(summary.p99_ns, Some(5_000));
+    }
+
+    #[test]
+    fn percentile_idx() {
+        assert_eq!(super::percentile_idx(1, 99), 0);
+        assert_eq!(super::percentile_idx(100, 99), 98);
+        assert_eq!(super::percentile_idx(101, 99), 99);
+        assert_eq!(super::percentile_idx(200, 50), 99);
+    }
+
+    #[test]
+    fn buckets() {
+        use super::{bucket_idx, bucket_max};
+
+        // exact below 64ns
+        for ns in 0..64 {
+            assert_eq!(bucket_idx(ns), ns as usize);
+            assert_eq!(bucket_max(ns as usize), ns);
+        }
+        assert_eq!(bucket_idx(64), 64);
+        assert_eq!(bucket_idx(127), 127);
+        assert_eq!(bucket_idx(128), 128);
+        assert_eq!(bucket_idx(129), 128);
+        assert_eq!(bucket_max(128), 129);
+        assert_eq!(bucket_idx(u64::MAX), 3775);
+        assert_eq!(bucket_max(3775), u64::MAX);
+
+        // each latency is within 1/64 of the bucket bounds
+        for ns in [65, 1000, 999_999, 1 << 40, (1 << 40) + 12345, u64::MAX / 3] {
+            let idx = bucket_idx(ns);
+            assert!(ns <= bucket_max(idx));
+            assert!(bucket_max(idx) - ns <= ns / 64);
+            assert!(bucket_max(idx - 1) < ns);
+        }
+    }
+
+    #[test]
+    fn table() {
+        let summaries = vec![
+            samples(&[2, 4]).summary("set", Duration::from_micros(10)),
+            Samples::default().summary("edge", Duration::from_secs(1)),
+        ];
+        assert_eq!(
+            format_table(&summaries),
+            "bench      count     rate/s           min          mean           p99           max\n\
+             set            2     200000           2µs           3µs           4µs           4µs\n\
+             edge           0          0             -             -             -             -"
+        );
+    }
+}
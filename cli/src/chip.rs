@@ -2,10 +2,11 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::common::{self, format_error, EmitOpts};
+use super::common::{self, emit_error, format_error, EmitOpts};
 use anyhow::Result;
 use clap::Parser;
-use gpiocdev::chip::Info;
+use gpiocdev::chip::{ChipEvent, ChipWatcher, Info};
+use gpiocdev::AbiVersion;
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
 use std::path::Path;
@@ -25,11 +26,31 @@ pub struct Opts {
     #[arg(value_name = "chip", verbatim_doc_comment)]
     chips: Vec<String>,
 
+    /// Report the uAPI ABI versions supported by each chip
+    #[arg(long)]
+    show_abi: bool,
+
+    /// Watch for chips being added or removed, after reporting the chips present
+    #[arg(short, long, conflicts_with = "chips")]
+    watch: bool,
+
     #[command(flatten)]
     emit: common::EmitOpts,
 }
 
 pub fn cmd(opts: &Opts) -> bool {
+    // watch before reporting the chips present, so no changes are missed.
+    let watcher = if opts.watch {
+        match gpiocdev::chips_watch() {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                emit_error(&opts.emit, &e.into());
+                return false;
+            }
+        }
+    } else {
+        None
+    };
     let mut res = CmdResult {
         opts: opts.emit,
         ..Default::default()
@@ -38,7 +59,7 @@ pub fn cmd(opts: &Opts) -> bool {
         match &common::all_chip_paths() {
             Ok(pp) => {
                 for p in pp {
-                    res.push(chip_info(p));
+                    res.push(chip_info(p, opts.show_abi));
                 }
             }
             Err(e) => {
@@ -47,20 +68,111 @@ pub fn cmd(opts: &Opts) -> bool {
         }
     } else {
         for id in &opts.chips {
-            res.push(chip_info_from_id(id));
+            res.push(chip_info_from_id(id, opts.show_abi));
         }
     };
     res.emit();
+    if let Some(watcher) = watcher {
+        return watch(watcher, opts);
+    }
     res.errors.is_empty()
 }
 
+// Report chips being added or removed, until an error occurs.
+fn watch(watcher: ChipWatcher, opts: &Opts) -> bool {
+    for evt in watcher {
+        match evt {
+            Ok(ChipEvent::Added(p)) => match chip_info(&p, opts.show_abi) {
+                Ok(ci) => emit_change(&opts.emit, &ChipChange::Added(ci)),
+                Err(e) => emit_error(&opts.emit, &e),
+            },
+            Ok(ChipEvent::Removed(p)) => {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                emit_change(&opts.emit, &ChipChange::Removed(name.into_owned()));
+            }
+            Err(e) => {
+                emit_error(&opts.emit, &e.into());
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+enum ChipChange {
+    Added(ChipInfo),
+    // The name of the removed chip.
+    Removed(String),
+}
+
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+fn emit_change(opts: &EmitOpts, change: &ChipChange) {
+    #[cfg(feature = "json")]
+    if opts.json {
+        println!("{}", serde_json::to_string(change).unwrap());
+        return;
+    }
+    match change {
+        ChipChange::Added(ci) => println!("added {}", format_chip(ci)),
+        ChipChange::Removed(name) => println!("removed {}", name),
+    }
+}
+
+fn format_chip(ci: &ChipInfo) -> String {
+    let abi = match &ci.abi {
+        Some(abi) => {
+            let versions: Vec<&str> = abi
+                .iter()
+                .map(|abiv| match abiv {
+                    AbiVersion::V1 => "v1",
+                    AbiVersion::V2 => "v2",
+                })
+                .collect();
+            format!(" abi={}", versions.join(","))
+        }
+        None => String::new(),
+    };
+    format!(
+        "{} [{}] ({} lines){}",
+        common::format_chip_name(&ci.info.name),
+        ci.info.label,
+        ci.info.num_lines,
+        abi
+    )
+}
+
 // report error and fail overall operation if id does not correspond to a gpiochip.
-fn chip_info_from_id(id: &str) -> Result<Info> {
-    chip_info(&common::chip_lookup_from_id(id)?)
+fn chip_info_from_id(id: &str, show_abi: bool) -> Result<ChipInfo> {
+    chip_info(&common::chip_lookup_from_id(id)?, show_abi)
 }
 
-fn chip_info(p: &Path) -> Result<Info> {
-    Ok(common::chip_from_path(p, gpiocdev::AbiVersion::V2)?.info()?)
+fn chip_info(p: &Path, show_abi: bool) -> Result<ChipInfo> {
+    let c = common::chip_from_path(p, AbiVersion::V2)?;
+    let abi = if show_abi {
+        Some(
+            [AbiVersion::V1, AbiVersion::V2]
+                .into_iter()
+                .filter(|abiv| c.supports_abi_version(*abiv).is_ok())
+                .collect(),
+        )
+    } else {
+        None
+    };
+    Ok(ChipInfo {
+        info: c.info()?,
+        abi,
+    })
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct ChipInfo {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    info: Info,
+    // The ABI versions supported by the chip, if requested.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    abi: Option<Vec<AbiVersion>>,
 }
 
 #[derive(Default)]
@@ -69,13 +181,13 @@ struct CmdResult {
     #[cfg_attr(feature = "serde", serde(skip))]
     opts: EmitOpts,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
-    chips: Vec<Info>,
+    chips: Vec<ChipInfo>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     errors: Vec<String>,
 }
 
 impl CmdResult {
-    fn push(&mut self, r: Result<Info>) {
+    fn push(&mut self, r: Result<ChipInfo>) {
         match r {
             Ok(i) => self.chips.push(i),
             Err(e) => self.push_error(&e),
@@ -96,12 +208,7 @@ impl CmdResult {
 
     fn print(&self) {
         for ci in &self.chips {
-            println!(
-                "{} [{}] ({} lines)",
-                common::format_chip_name(&ci.name),
-                ci.label,
-                ci.num_lines
-            );
+            println!("{}", format_chip(ci));
         }
         for e in &self.errors {
             eprintln!("{e}");
@@ -2,16 +2,27 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+pub mod glob;
+
 mod resolver;
-pub use self::resolver::{ChipInfo, Resolver};
+pub use self::resolver::{ChipInfo, ChipOffset, LineInfo, Resolver};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::{Parser, ValueEnum};
-use gpiocdev::chip::{chips, is_chip, Chip};
-use gpiocdev::line::{Bias, Drive, EdgeDetection};
-use gpiocdev::request::Config;
+use gpiocdev::chip::{self, chips, is_chip, Chip};
+use gpiocdev::line::{Bias, Drive, EdgeDetection, EventTimestamp, Offset};
+use gpiocdev::request::{Config, ErrorKind};
 use gpiocdev::AbiVersion;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::cmp;
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 // common helper functions
@@ -34,26 +45,45 @@ pub fn chip_from_path(p: &Path, _abiv: AbiVersion) -> Result<Chip> {
 
 #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
 pub fn actual_abi_version(opts: &UapiOpts) -> Result<AbiVersion> {
-    Ok(match opts.abi_version {
+    let abiv = match opts.abi_version.version() {
         Some(abiv) => {
-            let abiv = abiv.into();
             gpiocdev::supports_abi_version(abiv)?;
             abiv
         }
         None => gpiocdev::detect_abi_version()?,
-    })
+    };
+    show_abi_version(opts, abiv, opts.abi_version == AbiVersionFlags::Auto);
+    Ok(abiv)
 }
 
 #[cfg(not(feature = "uapi_v2"))]
-pub fn actual_abi_version(_opts: &UapiOpts) -> Result<AbiVersion> {
+pub fn actual_abi_version(opts: &UapiOpts) -> Result<AbiVersion> {
+    show_abi_version(opts, AbiVersion::V1, false);
     Ok(AbiVersion::V1)
 }
 
 #[cfg(not(feature = "uapi_v1"))]
-pub fn actual_abi_version(_opts: &UapiOpts) -> Result<AbiVersion> {
+pub fn actual_abi_version(opts: &UapiOpts) -> Result<AbiVersion> {
+    show_abi_version(opts, AbiVersion::V2, false);
     Ok(AbiVersion::V2)
 }
 
+// Report the ABI version to stderr, if requested, the first time it is determined.
+fn show_abi_version(opts: &UapiOpts, abiv: AbiVersion, detected: bool) {
+    static SHOWN: AtomicBool = AtomicBool::new(false);
+    if opts.show_abi && !SHOWN.swap(true, Ordering::Relaxed) {
+        eprintln!("{}", abi_version_report(abiv, detected));
+    }
+}
+
+fn abi_version_report(abiv: AbiVersion, detected: bool) -> String {
+    if detected {
+        format!("using {} (detected)", abiv)
+    } else {
+        format!("using {}", abiv)
+    }
+}
+
 fn chip_path_from_id(id: &str) -> PathBuf {
     if id.chars().all(char::is_numeric) {
         // from number
@@ -76,36 +106,105 @@ pub fn chip_lookup_from_id(id: &str) -> Result<PathBuf> {
 
 #[derive(Debug, Eq, PartialEq, thiserror::Error)]
 pub enum ParseDurationError {
-    #[error("'{0}' unknown units - use 's', 'ms' or 'us'.")]
+    #[error("'{0}' unknown units - use 'h', 'm', 's', 'ms' or 'us'.")]
     Units(String),
     #[error("'{0}' must start with a digit")]
     NoDigits(String),
+    #[error("'{0}' contains a malformed number")]
+    Number(String),
+    #[error("'{0}' must not be negative")]
+    Negative(String),
+    #[error("'{0}' is too large")]
+    Overflow(String),
+    #[error("'{0}' must be greater than zero")]
+    Zero(String),
 }
 
+/// Parse a duration from one or more components, each a number and units.
+///
+/// The numbers may be fractional, and the units may be 'h', 'm', 's', 'ms' or 'us',
+/// e.g. "1.5s", "2m30s" or "1s500ms".
+/// A bare number is taken as milliseconds.
+///
+/// Fractional values are rounded to the nearest nanosecond.
 pub fn parse_duration(s: &str) -> std::result::Result<Duration, ParseDurationError> {
-    if s == "0" {
-        return Ok(Duration::ZERO);
-    }
-    let t = match s.find(|c: char| !c.is_ascii_digit()) {
-        Some(0) => return Err(ParseDurationError::NoDigits(s.to_string())),
-        Some(n) => {
-            let (num, units) = s.split_at(n);
-            let t = num.parse::<u64>().unwrap();
-            t * match units {
-                "us" => 1000,
-                "ms" => 1000000,
-                "s" => 1000000000,
-                _ => return Err(ParseDurationError::Units(s.to_string())),
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    let max_nanos = Duration::MAX.as_nanos();
+    if s.starts_with('-') {
+        return Err(ParseDurationError::Negative(s.to_string()));
+    }
+    let is_digit = |c: char| c.is_ascii_digit();
+    let mut nanos: u128 = 0;
+    let mut rest = s;
+    loop {
+        let len = rest.find(|c| !is_digit(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(ParseDurationError::NoDigits(s.to_string()));
+        }
+        let (int, tail) = rest.split_at(len);
+        let (frac, tail) = match tail.strip_prefix('.') {
+            Some(tail) => {
+                let len = tail.find(|c| !is_digit(c)).unwrap_or(tail.len());
+                if len == 0 {
+                    return Err(ParseDurationError::Number(s.to_string()));
+                }
+                tail.split_at(len)
             }
+            None => ("", tail),
+        };
+        let len = tail.find(is_digit).unwrap_or(tail.len());
+        let (units, tail) = tail.split_at(len);
+        let unit_nanos: u128 = match units {
+            // a bare number is only valid by itself
+            "" if tail.is_empty() && rest.len() == s.len() => 1_000_000,
+            "h" => 3600 * NANOS_PER_SEC,
+            "m" => 60 * NANOS_PER_SEC,
+            "s" => NANOS_PER_SEC,
+            "ms" => 1_000_000,
+            "us" => 1_000,
+            _ => return Err(ParseDurationError::Units(s.to_string())),
+        };
+        nanos = component_nanos(int, frac, unit_nanos)
+            .and_then(|n| n.checked_add(nanos))
+            .filter(|n| *n <= max_nanos)
+            .ok_or_else(|| ParseDurationError::Overflow(s.to_string()))?;
+        if tail.is_empty() {
+            break;
         }
-        None => s.parse::<u64>().unwrap() * 1000000,
-    };
-    Ok(Duration::from_nanos(t))
+        rest = tail;
+    }
+    Ok(Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    ))
+}
+
+/// Parse a duration, as per [`parse_duration`], that must be greater than zero.
+pub fn parse_nonzero_duration(s: &str) -> std::result::Result<Duration, ParseDurationError> {
+    match parse_duration(s)? {
+        d if d.is_zero() => Err(ParseDurationError::Zero(s.to_string())),
+        d => Ok(d),
+    }
+}
+
+// The nanoseconds in a duration component, rounded to the nearest nanosecond.
+//
+// Returns None on overflow.
+fn component_nanos(int: &str, frac: &str, unit_nanos: u128) -> Option<u128> {
+    let nanos = int.parse::<u128>().ok()?.checked_mul(unit_nanos)?;
+    // digits beyond this are too fine to affect the nearest nanosecond
+    let frac = &frac[..cmp::min(frac.len(), 19)];
+    if frac.is_empty() {
+        return Some(nanos);
+    }
+    let scale = 10_u128.pow(frac.len() as u32);
+    let frac_nanos = (frac.parse::<u128>().unwrap() * unit_nanos + scale / 2) / scale;
+    nanos.checked_add(frac_nanos)
 }
 
 // common command line parser options
 
-#[derive(Debug, Parser)]
+#[derive(Clone, Debug, Parser)]
 /// Options to control the selection of lines.
 pub struct LineOpts {
     /// Restrict scope to the lines on this chip
@@ -137,18 +236,42 @@ pub struct LineOpts {
     /// With --by-name set the lines are always identified by name, never as offsets.
     #[arg(long)]
     pub by_name: bool,
+
+    /// Lines are shell-style glob patterns matched against line names
+    ///
+    /// Patterns may contain '*', '?' and '[...]' character classes,
+    /// and are expanded to all matching lines, sorted by chip then offset.
+    ///
+    /// It is an error for a pattern to match no lines.
+    #[arg(long)]
+    pub glob: bool,
+
+    /// Line names are matched ignoring ASCII case
+    ///
+    /// This also applies to the patterns matched with --glob.
+    ///
+    /// With --strict, lines with names differing only in case are not unique.
+    #[arg(long)]
+    pub ignore_case: bool,
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
 pub enum AbiVersionFlags {
+    // detect the latest version supported by the kernel
+    #[default]
+    Auto,
     V1,
     V2,
 }
-impl From<AbiVersionFlags> for gpiocdev::AbiVersion {
-    fn from(b: AbiVersionFlags) -> Self {
-        match b {
-            AbiVersionFlags::V1 => gpiocdev::AbiVersion::V1,
-            AbiVersionFlags::V2 => gpiocdev::AbiVersion::V2,
+
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+impl AbiVersionFlags {
+    /// The selected version, or None if it is to be detected.
+    pub fn version(self) -> Option<gpiocdev::AbiVersion> {
+        match self {
+            AbiVersionFlags::Auto => None,
+            AbiVersionFlags::V1 => Some(gpiocdev::AbiVersion::V1),
+            AbiVersionFlags::V2 => Some(gpiocdev::AbiVersion::V2),
         }
     }
 }
@@ -156,7 +279,7 @@ impl From<AbiVersionFlags> for gpiocdev::AbiVersion {
 #[derive(Clone, Copy, Debug, Default, Parser)]
 pub struct EmitOpts {
     #[arg(from_global)]
-    pub verbose: bool,
+    pub verbose: u8,
 
     /// Emit output in JSON format
     #[cfg(feature = "json")]
@@ -168,6 +291,28 @@ pub struct EmitOpts {
     pub quoted: bool,
 }
 
+/// The exit code returned when a chip is removed while its lines are requested.
+pub const CHIP_REMOVED_EXIT_CODE: u8 = 3;
+
+/// Returns true if the error was caused by the removal of a chip.
+pub fn is_chip_removed(e: &anyhow::Error) -> bool {
+    e.chain().any(|c| {
+        matches!(
+            c.downcast_ref::<gpiocdev::Error>(),
+            Some(gpiocdev::Error::GpioChip(_, chip::ErrorKind::Removed))
+        )
+    })
+}
+
+/// The exit code for a command that failed with the error.
+pub fn exit_code(e: &anyhow::Error) -> ExitCode {
+    if is_chip_removed(e) {
+        ExitCode::from(CHIP_REMOVED_EXIT_CODE)
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
 pub fn emit_error(opts: &EmitOpts, e: &anyhow::Error) {
     let e_str = format_error(opts, e);
     #[cfg(feature = "json")]
@@ -179,11 +324,21 @@ pub fn emit_error(opts: &EmitOpts, e: &anyhow::Error) {
 }
 
 pub fn format_error(opts: &EmitOpts, e: &anyhow::Error) -> String {
-    if opts.verbose {
+    let mut e_str = if opts.verbose > 0 {
         format!("{:#}", e)
     } else {
         format!("{}", e)
+    };
+    if let Some(gpiocdev::Error::Request(path, ErrorKind::LineBusy(offset))) =
+        e.root_cause().downcast_ref::<gpiocdev::Error>()
+    {
+        e_str.push_str(&format!(
+            " (use 'gpiocdev line -c {} {}' to identify the current consumer)",
+            path.display(),
+            offset
+        ));
     }
+    e_str
 }
 
 #[derive(Debug, Default, Parser)]
@@ -197,14 +352,23 @@ pub struct UapiOpts {
         value_name = "version",
         env = "GPIOCDEV_ABI_VERSION",
         value_enum,
-        ignore_case = true
+        ignore_case = true,
+        default_value_t
     )]
-    pub abi_version: Option<AbiVersionFlags>,
+    pub abi_version: AbiVersionFlags,
+
+    /// Report the uAPI ABI version being used to stderr
+    #[arg(long)]
+    pub show_abi: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct ActiveLowOpts {
     /// Treat the line as active-low when determining value
+    ///
+    /// The logical value is the inverse of the physical level.
+    /// For input lines this only affects how the level is interpreted,
+    /// not the line itself.
     #[arg(short = 'l', long)]
     pub active_low: bool,
 }
@@ -216,7 +380,12 @@ impl ActiveLowOpts {
     }
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Deserialize, serde_derive::Serialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum BiasFlags {
     PullUp,
     PullDown,
@@ -231,6 +400,15 @@ impl From<BiasFlags> for Bias {
         }
     }
 }
+impl From<Bias> for BiasFlags {
+    fn from(b: Bias) -> Self {
+        match b {
+            Bias::PullUp => BiasFlags::PullUp,
+            Bias::PullDown => BiasFlags::PullDown,
+            Bias::Disabled => BiasFlags::Disabled,
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, Parser)]
 pub struct BiasOpts {
@@ -248,7 +426,12 @@ impl BiasOpts {
     }
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Deserialize, serde_derive::Serialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum DriveFlags {
     PushPull,
     OpenDrain,
@@ -263,28 +446,29 @@ impl From<DriveFlags> for Drive {
         }
     }
 }
+impl From<Drive> for DriveFlags {
+    fn from(b: Drive) -> Self {
+        match b {
+            Drive::PushPull => DriveFlags::PushPull,
+            Drive::OpenDrain => DriveFlags::OpenDrain,
+            Drive::OpenSource => DriveFlags::OpenSource,
+        }
+    }
+}
 #[derive(Clone, Copy, Debug, Parser)]
 pub struct DriveOpts {
     /// How the lines should be driven
-    #[arg(
-        short,
-        long,
-        value_name = "drive",
-        default_value = "push-pull",
-        value_enum,
-        ignore_case = true
-    )]
+    ///
+    /// By default the lines are driven push-pull.
+    #[arg(short, long, value_name = "drive", value_enum, ignore_case = true)]
     pub drive: Option<DriveFlags>,
 }
-impl DriveOpts {
-    pub fn apply(self, r: &mut Config) {
-        if let Some(drive) = self.drive {
-            r.with_drive(drive.into());
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Deserialize, serde_derive::Serialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum EdgeFlags {
     Rising,
     Falling,
@@ -299,83 +483,408 @@ impl From<EdgeFlags> for EdgeDetection {
         }
     }
 }
+impl From<EdgeDetection> for EdgeFlags {
+    fn from(b: EdgeDetection) -> Self {
+        match b {
+            EdgeDetection::RisingEdge => EdgeFlags::Rising,
+            EdgeDetection::FallingEdge => EdgeFlags::Falling,
+            EdgeDetection::BothEdges => EdgeFlags::Both,
+        }
+    }
+}
 #[derive(Clone, Copy, Debug, Parser)]
 pub struct EdgeOpts {
     /// Which edges should be detected and reported
+    ///
+    /// By default both edges are detected.
+    #[arg(short, long, value_name = "edges", value_enum, ignore_case = true)]
+    pub edges: Option<EdgeFlags>,
+}
+impl EdgeOpts {
+    pub fn apply(self, r: &mut Config) {
+        if let Some(edges) = self.edges {
+            r.with_edge_detection(Some(edges.into()));
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Parser)]
+pub struct RetryOpts {
+    /// Retry the request up to this many times if a line is busy
+    ///
+    /// This allows for lines that are briefly held by another consumer,
+    /// such as a driver that is about to release the line.
+    ///
+    /// Only requests that fail because a line is busy are retried.
+    #[arg(long, value_name = "count", default_value_t = 0)]
+    pub retry: u32,
+
+    /// The delay before each retry of a busy request
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
     #[arg(
-        short,
         long,
-        value_name = "edges",
-        value_enum,
-        default_value = "both",
-        ignore_case = true
+        value_name = "period",
+        default_value = "100ms",
+        requires = "retry",
+        value_parser = parse_duration
     )]
-    pub edges: EdgeFlags,
+    pub retry_delay: Duration,
 }
-impl EdgeOpts {
-    pub fn apply(self, r: &mut Config) {
-        r.with_edge_detection(Some(self.edges.into()));
+
+#[derive(Clone, Copy, Debug, Default, Parser)]
+pub struct ReadyOpts {
+    /// Notify readiness via the file descriptor once all lines have been requested
+    ///
+    /// "READY=1" and a newline are written to the file descriptor, which is then
+    /// closed. The file descriptor must be open for writing, and is typically
+    /// the write end of a pipe inherited from the parent process.
+    #[arg(long, value_name = "fd")]
+    pub ready_fd: Option<RawFd>,
+}
+impl ReadyOpts {
+    /// Check that the file descriptor, if any, is open for writing.
+    pub fn check(&self) -> Result<()> {
+        if let Some(fd) = self.ready_fd {
+            // SAFETY: F_GETFL does not modify the fd, and fails if the fd is not open.
+            let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+            if flags == -1 {
+                bail!(Error::InvalidReadyFd(fd, "not open".to_string()));
+            }
+            if flags & libc::O_ACCMODE == libc::O_RDONLY {
+                bail!(Error::InvalidReadyFd(fd, "not writable".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the readiness notification to the file descriptor, if any, then close it.
+    pub fn notify(&self) -> Result<()> {
+        use std::io::Write;
+
+        if let Some(fd) = self.ready_fd {
+            // SAFETY: the fd was checked to be open, and is not otherwise used.
+            let mut f = unsafe { File::from_raw_fd(fd) };
+            f.write_all(b"READY=1\n")
+                .with_context(|| format!("failed to notify readiness via fd {}", fd))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct DaemonOpts {
+    /// Detach from the controlling terminal once the lines have been requested.
+    #[arg(short = 'z', long)]
+    pub daemonize: bool,
+
+    /// Write the PID of the daemonized process to the specified file.
+    ///
+    /// The file is removed when the process exits in response to a SIGINT or SIGTERM.
+    #[arg(long, value_name = "path", requires = "daemonize")]
+    pub pidfile: Option<PathBuf>,
+
+    /// Redirect the stdout and stderr of the daemonized process to the specified file.
+    ///
+    /// The file is appended to if it already exists.
+    #[arg(long, value_name = "path", requires = "daemonize")]
+    pub log_file: Option<PathBuf>,
+}
+impl DaemonOpts {
+    /// Check that the readiness notification survives detaching.
+    pub fn check(&self, ready_opts: &ReadyOpts) -> Result<()> {
+        if self.daemonize && ready_opts.ready_fd.is_some_and(|fd| fd <= 2) {
+            // the standard streams are redirected when detaching
+            bail!("--ready-fd cannot be a standard stream when daemonizing");
+        }
+        Ok(())
+    }
+
+    /// Detach from the controlling terminal, if requested, and arrange for the
+    /// process to exit cleanly on SIGINT or SIGTERM.
+    ///
+    /// The lines must already be requested or watched, as the parent exits once
+    /// the daemon has started.
+    pub fn daemonize(&self) -> Result<()> {
+        if !self.daemonize {
+            return Ok(());
+        }
+        let mut d = daemonize::Daemonize::new();
+        // the daemon changes its working directory to /, so relative paths must be
+        // resolved beforehand.
+        let pidfile = match &self.pidfile {
+            Some(p) => Some(std::env::current_dir()?.join(p)),
+            None => None,
+        };
+        if let Some(p) = &pidfile {
+            d = d.pid_file(p);
+        }
+        if let Some(p) = &self.log_file {
+            let f = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(p)
+                .with_context(|| format!("failed to open log file {}", p.display()))?;
+            d = d.stdout(f.try_clone()?).stderr(f);
+        }
+        d.start()?;
+
+        let mut signals = Signals::new([SIGINT, SIGTERM])?;
+        std::thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                if let Some(p) = pidfile {
+                    _ = fs::remove_file(p);
+                }
+                // exiting closes the requests, releasing the lines.
+                std::process::exit(0);
+            }
+        });
+        Ok(())
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Parser)]
+pub struct ReattachOpts {
+    /// Re-request the lines if their chip is removed and re-added, or on SIGHUP
+    ///
+    /// The lines are resolved by name against the current chips, so may be found
+    /// on a different chip, then requested with the same configuration and the
+    /// last known values.
+    ///
+    /// The lines must be identified by name alone, without --chip, as the chip
+    /// may be renumbered, and the meaning of offsets may change, when a chip
+    /// is re-added.
+    #[arg(long)]
+    pub auto_reattach: bool,
+}
+impl ReattachOpts {
+    /// Check that the lines are not restricted to a chip, if reattaching.
+    ///
+    /// Without a chip the lines can only be identified by name.
+    pub fn check(&self, line_opts: &LineOpts) -> Result<()> {
+        if let (true, Some(chip)) = (self.auto_reattach, &line_opts.chip) {
+            bail!(Error::NotReattachable(chip.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// The period between attempts to reattach lines.
+const REATTACH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Repeat the request of the lines until it succeeds, then log the reattachment.
+///
+/// Failures are logged as warnings, though only when they differ from the
+/// previous failure, as the chip may take some time to be re-added.
+pub fn reattach<T, F>(lines: &[String], mut request: F) -> T
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut last_err = None;
+    loop {
+        match request() {
+            Ok(t) => {
+                let lines: Vec<String> = lines.iter().map(|l| format!("'{}'", l)).collect();
+                eprintln!("reattached lines {}", lines.join(", "));
+                return t;
+            }
+            Err(e) => {
+                let e = format!("{:#}", e);
+                if last_err.as_ref() != Some(&e) {
+                    eprintln!("warning: unable to reattach lines: {}", e);
+                    last_err = Some(e);
+                }
+                std::thread::sleep(REATTACH_INTERVAL);
+            }
+        }
+    }
+}
+
+/// A source that becomes readable when the process receives a SIGHUP.
+///
+/// Registering the source replaces the default SIGHUP action of terminating the process.
+pub struct Hangup(UnixStream);
+impl Hangup {
+    pub fn new() -> Result<Hangup> {
+        let (rx, tx) = UnixStream::pair().context("failed to create SIGHUP pipe")?;
+        rx.set_nonblocking(true)?;
+        signal_hook::low_level::pipe::register(SIGHUP, tx).context("failed to handle SIGHUP")?;
+        Ok(Hangup(rx))
+    }
+
+    /// Discard any pending hangups.
+    pub fn clear(&mut self) {
+        let mut buf = [0; 16];
+        while matches!(self.0.read(&mut buf), Ok(n) if n > 0) {}
+    }
+}
+impl AsRawFd for Hangup {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// Print a banner listing the lines, prefixed by the action being performed on them.
+pub fn print_banner(action: &str, lines: &[String]) {
+    use std::io::Write;
+
+    if lines.len() > 1 {
+        print!("{} lines ", action);
+
+        for l in lines.iter().take(lines.len() - 1) {
+            print!("'{}', ", l);
+        }
+
+        println!("and '{}'...", lines[lines.len() - 1]);
+    } else {
+        println!("{} line '{}'...", action, lines[0]);
+    }
+    _ = std::io::stdout().flush();
+}
+
 #[derive(Clone, Debug, Parser)]
 pub struct LinesOpts {}
 
 pub fn stringify_attrs(li: &gpiocdev::line::Info, quoted: bool) -> String {
-    use gpiocdev::line::{Direction, EventClock};
-
-    let mut attrs = Vec::new();
-    match li.direction {
-        gpiocdev::line::Direction::Input => attrs.push("input"),
-        Direction::Output => attrs.push("output"),
-    }
-    if li.active_low {
-        attrs.push("active-low");
-    }
-    match li.drive {
-        None => (),
-        Some(Drive::PushPull) => (),
-        Some(Drive::OpenDrain) => attrs.push("drive=open-drain"),
-        Some(Drive::OpenSource) => attrs.push("drive=open-source"),
-    }
-    match li.bias {
-        None => (),
-        Some(Bias::PullUp) => attrs.push("bias=pull-up"),
-        Some(Bias::PullDown) => attrs.push("bias=pull-down"),
-        Some(Bias::Disabled) => attrs.push("bias=disabled"),
-    }
-    match li.edge_detection {
-        None => (),
-        Some(EdgeDetection::RisingEdge) => attrs.push("edges=rising"),
-        Some(EdgeDetection::FallingEdge) => attrs.push("edges=falling"),
-        Some(EdgeDetection::BothEdges) => attrs.push("edges=both"),
-    }
-    match li.event_clock {
-        None => (),                        // Not present for v1.
-        Some(EventClock::Monotonic) => (), // default for ABI v2
-        Some(EventClock::Realtime) => attrs.push("event-clock=realtime"),
-        Some(EventClock::Hte) => attrs.push("event-clock=hte"),
-    }
-    let db;
-    if li.debounce_period.is_some() {
-        db = format!("debounce-period={:?}", li.debounce_period.unwrap());
-        attrs.push(&db);
-    }
-    let consumer;
-    if li.used {
-        if li.consumer.is_empty() {
-            consumer = String::from("consumer=kernel");
-        } else if quoted {
-            consumer = format!("consumer=\"{}\"", li.consumer);
-        } else {
-            consumer = format!("consumer={}", li.consumer);
-        }
-        attrs.push(&consumer);
-    }
-    attrs.join(" ")
+    LineAttrs::from(li).to_text(quoted)
+}
+
+/// The attributes of a line, as used by both the text and JSON formatters.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+pub struct LineAttrs<'a> {
+    pub direction: &'static str,
+    pub active_low: bool,
+    pub drive: Option<&'static str>,
+    pub bias: Option<&'static str>,
+    pub edge_detection: Option<&'static str>,
+    pub event_clock: Option<&'static str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "debounce_period_us",
+            serialize_with = "serialize_micros",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub debounce_period: Option<Duration>,
+    pub used: bool,
+    pub consumer: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub unknown_flags: Option<u64>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_micros<S: serde::Serializer>(
+    d: &Option<Duration>,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match d {
+        Some(d) => s.serialize_u128(d.as_micros()),
+        None => s.serialize_none(),
+    }
+}
+
+impl<'a> From<&'a gpiocdev::line::Info> for LineAttrs<'a> {
+    fn from(li: &'a gpiocdev::line::Info) -> Self {
+        use gpiocdev::line::{Direction, EventClock};
+
+        LineAttrs {
+            direction: match li.direction {
+                Direction::Input => "input",
+                Direction::Output => "output",
+            },
+            active_low: li.active_low,
+            drive: li.drive.map(|d| match d {
+                Drive::PushPull => "push-pull",
+                Drive::OpenDrain => "open-drain",
+                Drive::OpenSource => "open-source",
+            }),
+            bias: li.bias.map(|b| match b {
+                Bias::PullUp => "pull-up",
+                Bias::PullDown => "pull-down",
+                Bias::Disabled => "disabled",
+            }),
+            edge_detection: li.edge_detection.map(|e| match e {
+                EdgeDetection::RisingEdge => "rising",
+                EdgeDetection::FallingEdge => "falling",
+                EdgeDetection::BothEdges => "both",
+            }),
+            event_clock: li.event_clock.map(|c| match c {
+                EventClock::Monotonic => "monotonic",
+                EventClock::Realtime => "realtime",
+                EventClock::Hte => "hte",
+            }),
+            debounce_period: li.debounce_period,
+            used: li.used,
+            consumer: if li.used {
+                Some(li.consumer.as_str())
+            } else {
+                None
+            },
+            unknown_flags: if li.unknown_flags() != 0 {
+                Some(li.unknown_flags())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl LineAttrs<'_> {
+    /// The consumer as displayed in text, with kernel consumers identified as such.
+    pub fn consumer_text(&self, quoted: bool) -> Option<String> {
+        self.consumer.map(|c| {
+            if c.is_empty() {
+                String::from("kernel")
+            } else if quoted {
+                format!("\"{}\"", c)
+            } else {
+                c.to_string()
+            }
+        })
+    }
+
+    /// The attributes as a space separated list, omitting default values.
+    pub fn to_text(&self, quoted: bool) -> String {
+        let mut attrs = vec![self.direction.to_string()];
+        if self.active_low {
+            attrs.push("active-low".into());
+        }
+        match self.drive {
+            None | Some("push-pull") => (),
+            Some(drive) => attrs.push(format!("drive={drive}")),
+        }
+        if let Some(bias) = self.bias {
+            attrs.push(format!("bias={bias}"));
+        }
+        if let Some(edges) = self.edge_detection {
+            attrs.push(format!("edges={edges}"));
+        }
+        match self.event_clock {
+            None => (),              // Not present for v1.
+            Some("monotonic") => (), // default for ABI v2
+            Some(clock) => attrs.push(format!("event-clock={clock}")),
+        }
+        if let Some(period) = self.debounce_period {
+            attrs.push(format!("debounce-period={:?}", period));
+        }
+        if let Some(consumer) = self.consumer_text(quoted) {
+            attrs.push(format!("consumer={consumer}"));
+        }
+        if let Some(flags) = self.unknown_flags {
+            attrs.push(format!("unknown(0x{flags:x})"));
+        }
+        attrs.join(" ")
+    }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TimeFmt {
+    // nanoseconds
+    Raw,
+    // seconds since the base, which is the first event, once known
+    Relative(Option<u64>),
     Seconds,
     Localtime,
     Utc,
@@ -395,6 +904,11 @@ pub fn format_time(evtime: u64, timefmt: &TimeFmt) -> String {
     let ts_sec = (evtime / 1000000000) as i64;
     let ts_nsec = (evtime % 1000000000) as u32;
     match timefmt {
+        TimeFmt::Raw => format!("{}", evtime),
+        TimeFmt::Relative(base) => {
+            let rel = evtime.saturating_sub(base.unwrap_or(evtime));
+            format!("{}.{:09}", rel / 1000000000, rel % 1000000000)
+        }
         TimeFmt::Seconds => format!("{}.{:09}", ts_sec, ts_nsec),
         TimeFmt::Localtime => {
             let t = Local.timestamp_opt(ts_sec, ts_nsec).unwrap();
@@ -408,6 +922,31 @@ pub fn format_time(evtime: u64, timefmt: &TimeFmt) -> String {
     }
 }
 
+// Format an edge event timestamp, using the clock that provided it where possible.
+pub fn format_timestamp(ts: EventTimestamp, timefmt: &TimeFmt) -> String {
+    use chrono::{DateTime, Local, Utc};
+
+    match (timefmt, ts.as_system_time()) {
+        (TimeFmt::Relative(base), _) => {
+            let base = EventTimestamp {
+                ns: base.unwrap_or(ts.ns),
+                ..ts
+            };
+            let rel = ts - base;
+            format!("{}.{:09}", rel.as_secs(), rel.subsec_nanos())
+        }
+        (TimeFmt::Localtime, Some(t)) => {
+            format!("{}", DateTime::<Local>::from(t).format("%FT%T%.9f"))
+        }
+        (TimeFmt::Utc, Some(t)) => {
+            format!("{}", DateTime::<Utc>::from(t).format("%FT%T%.9fZ"))
+        }
+        // wall-clock formats fallback to the raw timestamp if the clock is not
+        // CLOCK_REALTIME, such as for uAPI v1 on kernels since 5.7.
+        _ => format_time(ts.ns, timefmt),
+    }
+}
+
 /// Errors returned by cli functions.
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
 pub enum Error {
@@ -420,18 +959,487 @@ pub enum Error {
     #[error("line '{0}' is repeated")]
     RepeatedLine(String),
 
-    #[error("cannot find line '{0}'")]
-    NoSuchLine(String),
+    #[error("cannot find line '{0}'{suffix}", suffix = did_you_mean(.1))]
+    NoSuchLine(String, Vec<String>),
+
+    #[error("no lines match pattern '{0}'")]
+    NoMatchingLines(String),
 
     #[error("offset {0} is out of range on chip '{1}'")]
     OffsetOutOfRange(String, String),
+
+    #[error("offset range '{0}' is descending")]
+    DescendingRange(String),
+
+    #[error("ready fd {0} is {1}")]
+    InvalidReadyFd(RawFd, String),
+
+    #[error("--chip cannot be used with --auto-reattach, as chip '{0}' may be renumbered")]
+    NotReattachable(String),
+}
+
+// The suffix suggesting alternatives for a line that cannot be found.
+fn did_you_mean(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => format!("; did you mean '{}'?", name),
+        [names @ .., last] => {
+            let names: Vec<String> = names.iter().map(|n| format!("'{}'", n)).collect();
+            format!("; did you mean {} or '{}'?", names.join(", "), last)
+        }
+    }
+}
+
+/// The maximum number of names suggested for a line that cannot be found.
+const SUGGESTIONS_MAX: usize = 3;
+
+/// Find the names closest to a line id, to suggest when the line cannot be found.
+///
+/// Names within an edit distance of a third of the length of the id are
+/// returned, closest first, with ties ordered by name.
+///
+/// The distance ignores ASCII case, so names differing from the id only in
+/// case are the closest.
+pub fn closest_names<'a, I>(id: &str, names: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = cmp::max(1, id.chars().count() / 3);
+    let folded = id.to_ascii_lowercase();
+    let mut candidates: Vec<(usize, &str)> = names
+        .into_iter()
+        .filter(|name| !name.is_empty() && *name != id)
+        .map(|name| (edit_distance(&folded, &name.to_ascii_lowercase()), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .take(SUGGESTIONS_MAX)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // the distances from the prefix of a to each prefix of b
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diag
+            } else {
+                1 + cmp::min(diag, cmp::min(above, row[j]))
+            };
+            diag = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// A line identifier that expands to a set of offsets on a chip.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OffsetRange {
+    /// All lines on the chip.
+    All,
+    /// The offsets from the first to the last, inclusive.
+    Span(Offset, Offset),
+}
+
+/// Parse a line identifier as an offset range, such as "3-9", or the "all" keyword.
+///
+/// Returns None if the identifier is neither.
+pub fn parse_offset_range(s: &str) -> Result<Option<OffsetRange>> {
+    if s == "all" {
+        return Ok(Some(OffsetRange::All));
+    }
+    let (first, last) = match s.split_once('-') {
+        Some((first, last)) => match (first.parse::<Offset>(), last.parse::<Offset>()) {
+            (Ok(first), Ok(last)) => (first, last),
+            _ => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    if last < first {
+        return Err(anyhow!(Error::DescendingRange(s.to_owned())));
+    }
+    Ok(Some(OffsetRange::Span(first, last)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn abi_version_flags() {
+        assert_eq!(AbiVersionFlags::default(), AbiVersionFlags::Auto);
+        assert_eq!(AbiVersionFlags::Auto.version(), None);
+        assert_eq!(AbiVersionFlags::V1.version(), Some(AbiVersion::V1));
+        assert_eq!(AbiVersionFlags::V2.version(), Some(AbiVersion::V2));
+    }
+
+    #[test]
+    fn abi_version_report() {
+        use super::abi_version_report;
+
+        assert_eq!(
+            abi_version_report(AbiVersion::V1, false),
+            "using uAPI ABI v1"
+        );
+        assert_eq!(
+            abi_version_report(AbiVersion::V2, true),
+            "using uAPI ABI v2 (detected)"
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "uapi_v2"))]
+    fn actual_abi_version_v1_only() {
+        // no detection required when only v1 is compiled in
+        let opts = UapiOpts::default();
+        assert_eq!(actual_abi_version(&opts).unwrap(), AbiVersion::V1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "uapi_v1"))]
+    fn actual_abi_version_v2_only() {
+        // no detection required when only v2 is compiled in
+        let opts = UapiOpts::default();
+        assert_eq!(actual_abi_version(&opts).unwrap(), AbiVersion::V2);
+    }
+
+    #[test]
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn abi_version_detected_by_default() {
+        // the version is detected unless explicitly selected
+        let opts = UapiOpts::parse_from(["test"]);
+        assert_eq!(opts.abi_version, AbiVersionFlags::Auto);
+        assert_eq!(opts.abi_version.version(), None);
+        let opts = UapiOpts::parse_from(["test", "--abi-version", "v1", "--show-abi"]);
+        assert_eq!(opts.abi_version.version(), Some(AbiVersion::V1));
+        assert!(opts.show_abi);
+    }
+
+    #[test]
+    fn format_time() {
+        use super::{format_time, TimeFmt};
+        use chrono::{Local, TimeZone};
+
+        let evtime = 1_700_000_123_456_789_012;
+        assert_eq!(format_time(evtime, &TimeFmt::Raw), "1700000123456789012");
+        assert_eq!(
+            format_time(evtime, &TimeFmt::Seconds),
+            "1700000123.456789012"
+        );
+        assert_eq!(
+            format_time(evtime, &TimeFmt::Utc),
+            "2023-11-14T22:15:23.456789012Z"
+        );
+        assert_eq!(
+            format_time(evtime, &TimeFmt::Localtime),
+            Local
+                .timestamp_opt(1_700_000_123, 456_789_012)
+                .unwrap()
+                .format("%FT%T%.9f")
+                .to_string()
+        );
+        // base not yet known, so this is the first event
+        assert_eq!(format_time(evtime, &TimeFmt::Relative(None)), "0.000000000");
+        assert_eq!(
+            format_time(evtime, &TimeFmt::Relative(Some(evtime - 2_500_000_001))),
+            "2.500000001"
+        );
+        // events preceding the base are clamped
+        assert_eq!(
+            format_time(evtime, &TimeFmt::Relative(Some(evtime + 1))),
+            "0.000000000"
+        );
+    }
+
+    #[test]
+    fn format_timestamp() {
+        use super::{format_timestamp, TimeFmt};
+        use gpiocdev::line::EventClock;
+
+        let rt = EventTimestamp {
+            clock: EventClock::Realtime,
+            ns: 1_700_000_123_456_789_012,
+        };
+        assert_eq!(
+            format_timestamp(rt, &TimeFmt::Utc),
+            "2023-11-14T22:15:23.456789012Z"
+        );
+        assert_eq!(
+            format_timestamp(rt, &TimeFmt::Seconds),
+            "1700000123.456789012"
+        );
+        assert_eq!(format_timestamp(rt, &TimeFmt::Raw), "1700000123456789012");
+        assert_eq!(
+            format_timestamp(rt, &TimeFmt::Relative(Some(rt.ns - 2_500_000_001))),
+            "2.500000001"
+        );
+        assert_eq!(
+            format_timestamp(rt, &TimeFmt::Relative(None)),
+            "0.000000000"
+        );
+        // wall-clock from an unknown clock uses the raw timestamp
+        let mono = EventTimestamp {
+            clock: EventClock::Monotonic,
+            ..rt
+        };
+        assert_eq!(
+            format_timestamp(mono, &TimeFmt::Utc),
+            "2023-11-14T22:15:23.456789012Z"
+        );
+        assert_eq!(
+            format_timestamp(mono, &TimeFmt::Relative(Some(mono.ns + 1))),
+            "0.000000000"
+        );
+    }
+
+    mod line_attrs {
+        use super::*;
+        use gpiocdev::line::{Direction, EventClock, Info};
+
+        fn info() -> Info {
+            let mut info = Info::default();
+            info.offset = 3;
+            info.name = "banana".into();
+            info.consumer = "peeler".into();
+            info.used = true;
+            info.direction = Direction::Output;
+            info.active_low = true;
+            info.drive = Some(Drive::OpenDrain);
+            info.bias = Some(Bias::PullUp);
+            info.edge_detection = Some(EdgeDetection::BothEdges);
+            info.event_clock = Some(EventClock::Realtime);
+            info.debounce_period = Some(Duration::from_micros(1500));
+            info
+        }
+
+        #[test]
+        fn from_info() {
+            let li = info();
+            assert_eq!(
+                LineAttrs::from(&li),
+                LineAttrs {
+                    direction: "output",
+                    active_low: true,
+                    drive: Some("open-drain"),
+                    bias: Some("pull-up"),
+                    edge_detection: Some("both"),
+                    event_clock: Some("realtime"),
+                    debounce_period: Some(Duration::from_micros(1500)),
+                    used: true,
+                    consumer: Some("peeler"),
+                    unknown_flags: None,
+                }
+            );
+            let mut li = info();
+            li.used = false;
+            assert_eq!(LineAttrs::from(&li).consumer, None);
+        }
+
+        #[test]
+        fn to_text() {
+            let li = info();
+            assert_eq!(
+                LineAttrs::from(&li).to_text(false),
+                "output active-low drive=open-drain bias=pull-up edges=both \
+                event-clock=realtime debounce-period=1.5ms consumer=peeler"
+            );
+            assert_eq!(
+                stringify_attrs(&li, true),
+                "output active-low drive=open-drain bias=pull-up edges=both \
+                event-clock=realtime debounce-period=1.5ms consumer=\"peeler\""
+            );
+            // defaults are omitted
+            let mut li = info();
+            li.direction = Direction::Input;
+            li.active_low = false;
+            li.drive = Some(Drive::PushPull);
+            li.bias = None;
+            li.edge_detection = None;
+            li.event_clock = Some(EventClock::Monotonic);
+            li.debounce_period = None;
+            li.consumer = "".into();
+            assert_eq!(stringify_attrs(&li, false), "input consumer=kernel");
+            li.used = false;
+            assert_eq!(stringify_attrs(&li, false), "input");
+            // unknown flags are not omitted
+            let mut attrs = LineAttrs::from(&li);
+            attrs.unknown_flags = Some(0x6000);
+            assert_eq!(attrs.to_text(false), "input unknown(0x6000)");
+            let li = info();
+            let mut attrs = LineAttrs::from(&li);
+            attrs.unknown_flags = Some(0x2000);
+            assert_eq!(
+                attrs.to_text(false),
+                "output active-low drive=open-drain bias=pull-up edges=both \
+                event-clock=realtime debounce-period=1.5ms consumer=peeler unknown(0x2000)"
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "json")]
+        fn to_json() {
+            let li = info();
+            assert_eq!(
+                serde_json::to_string(&LineAttrs::from(&li)).unwrap(),
+                "{\"direction\":\"output\",\"active_low\":true,\"drive\":\"open-drain\",\
+                \"bias\":\"pull-up\",\"edge_detection\":\"both\",\"event_clock\":\"realtime\",\
+                \"debounce_period_us\":1500,\"used\":true,\"consumer\":\"peeler\"}"
+            );
+            let mut li = info();
+            li.used = false;
+            li.drive = None;
+            li.debounce_period = None;
+            assert_eq!(
+                serde_json::to_string(&LineAttrs::from(&li)).unwrap(),
+                "{\"direction\":\"output\",\"active_low\":true,\"drive\":null,\
+                \"bias\":\"pull-up\",\"edge_detection\":\"both\",\"event_clock\":\"realtime\",\
+                \"used\":false,\"consumer\":null}"
+            );
+            let mut li = info();
+            li.used = false;
+            li.drive = None;
+            li.debounce_period = None;
+            let mut attrs = LineAttrs::from(&li);
+            attrs.unknown_flags = Some(0x2000);
+            assert_eq!(
+                serde_json::to_string(&attrs).unwrap(),
+                "{\"direction\":\"output\",\"active_low\":true,\"drive\":null,\
+                \"bias\":\"pull-up\",\"edge_detection\":\"both\",\"event_clock\":\"realtime\",\
+                \"used\":false,\"consumer\":null,\"unknown_flags\":8192}"
+            );
+        }
+    }
+
+    mod suggest {
+        use super::{closest_names, edit_distance, Error};
+
+        #[test]
+        fn distance() {
+            assert_eq!(edit_distance("", ""), 0);
+            assert_eq!(edit_distance("GPIO17", "GPIO17"), 0);
+            assert_eq!(edit_distance("GPI017", "GPIO17"), 1);
+            assert_eq!(edit_distance("GPIO7", "GPIO17"), 1);
+            assert_eq!(edit_distance("GPIO177", "GPIO17"), 1);
+            assert_eq!(edit_distance("GIPO17", "GPIO17"), 2);
+            assert_eq!(edit_distance("", "LED"), 3);
+            assert_eq!(edit_distance("kitten", "sitting"), 3);
+        }
+
+        #[test]
+        fn ranking() {
+            let names = [
+                "GPIO1", "GPIO7", "GPIO16", "GPIO17", "GPIO18", "GPIO27", "LED", "", "GPIO17",
+            ];
+            // closest first, then by name, without repeats
+            assert_eq!(
+                closest_names("GPI017", names),
+                vec!["GPIO17", "GPIO1", "GPIO16"]
+            );
+            assert_eq!(
+                closest_names("GPIO19", names),
+                vec!["GPIO1", "GPIO16", "GPIO17"]
+            );
+            assert_eq!(closest_names("LEDs", names), vec!["LED"]);
+            assert_eq!(closest_names("LEE", names), vec!["LED"]);
+            // too distant to be useful
+            assert!(closest_names("button", names).is_empty());
+            assert!(closest_names("LD", ["LEDS"]).is_empty());
+            // the id itself is not a suggestion
+            assert!(closest_names("LED", ["LED"]).is_empty());
+            // names differing only in case are closest
+            assert_eq!(
+                closest_names("GPIO17", ["GPIO16", "Gpio17", "gpio18"]),
+                vec!["Gpio17", "GPIO16", "gpio18"]
+            );
+        }
+
+        #[test]
+        fn no_such_line() {
+            let err = |names: &[&str]| {
+                Error::NoSuchLine(
+                    "GPI017".into(),
+                    names.iter().map(|n| n.to_string()).collect(),
+                )
+                .to_string()
+            };
+            assert_eq!(err(&[]), "cannot find line 'GPI017'");
+            assert_eq!(
+                err(&["GPIO17"]),
+                "cannot find line 'GPI017'; did you mean 'GPIO17'?"
+            );
+            assert_eq!(
+                err(&["GPIO17", "GPIO16", "GPIO18"]),
+                "cannot find line 'GPI017'; did you mean 'GPIO17', 'GPIO16' or 'GPIO18'?"
+            );
+        }
+    }
+
+    #[test]
+    fn reattach_check() {
+        let mut line_opts = LineOpts {
+            chip: None,
+            strict: false,
+            by_name: false,
+            glob: false,
+            ignore_case: false,
+        };
+        let mut opts = ReattachOpts::default();
+        // not reattaching
+        line_opts.chip = Some("gpiochip0".into());
+        assert!(opts.check(&line_opts).is_ok());
+
+        opts.auto_reattach = true;
+        for by_name in [false, true] {
+            line_opts.by_name = by_name;
+            assert_eq!(
+                opts.check(&line_opts).unwrap_err().to_string(),
+                "--chip cannot be used with --auto-reattach, as chip 'gpiochip0' may be renumbered"
+            );
+        }
+        // without a chip the lines can only be names
+        line_opts.chip = None;
+        assert!(opts.check(&line_opts).is_ok());
+    }
+
     mod parse {
+        #[test]
+        fn offset_range() {
+            use super::{parse_offset_range, OffsetRange};
+
+            assert_eq!(parse_offset_range("all").unwrap(), Some(OffsetRange::All));
+            assert_eq!(
+                parse_offset_range("3-9").unwrap(),
+                Some(OffsetRange::Span(3, 9))
+            );
+            assert_eq!(
+                parse_offset_range("4-4").unwrap(),
+                Some(OffsetRange::Span(4, 4))
+            );
+            assert_eq!(
+                parse_offset_range("9-3").unwrap_err().to_string(),
+                "offset range '9-3' is descending"
+            );
+            // not ranges
+            assert_eq!(parse_offset_range("3").unwrap(), None);
+            assert_eq!(parse_offset_range("ALL").unwrap(), None);
+            assert_eq!(parse_offset_range("LED-3").unwrap(), None);
+            assert_eq!(parse_offset_range("3-").unwrap(), None);
+            assert_eq!(parse_offset_range("-3").unwrap(), None);
+            assert_eq!(parse_offset_range("3-9-12").unwrap(), None);
+            assert_eq!(parse_offset_range("").unwrap(), None);
+        }
+
         #[test]
         fn duration() {
             use super::{parse_duration, ParseDurationError};
@@ -451,5 +1459,126 @@ mod tests {
                 ParseDurationError::NoDigits("bad".to_string())
             );
         }
+
+        #[test]
+        fn duration_units() {
+            use super::parse_duration;
+            use std::time::Duration;
+
+            assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+            assert_eq!(parse_duration("250").unwrap(), Duration::from_millis(250));
+            assert_eq!(parse_duration("1m").unwrap(), Duration::from_secs(60));
+            assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+            assert_eq!(parse_duration("007s").unwrap(), Duration::from_secs(7));
+        }
+
+        #[test]
+        fn duration_fractional() {
+            use super::parse_duration;
+            use std::time::Duration;
+
+            assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+            assert_eq!(parse_duration("0.5").unwrap(), Duration::from_micros(500));
+            assert_eq!(parse_duration("1.5us").unwrap(), Duration::from_nanos(1500));
+            assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+            assert_eq!(parse_duration("0.25h").unwrap(), Duration::from_secs(900));
+            assert_eq!(
+                parse_duration("0.123456789s").unwrap(),
+                Duration::from_nanos(123456789)
+            );
+            // exact where the units allow
+            assert_eq!(parse_duration("0.1h").unwrap(), Duration::from_secs(360));
+            // rounded to the nearest nanosecond
+            assert_eq!(parse_duration("0.0004us").unwrap(), Duration::ZERO);
+            assert_eq!(parse_duration("0.0005us").unwrap(), Duration::from_nanos(1));
+            assert_eq!(
+                parse_duration("0.3333333333333333333333s").unwrap(),
+                Duration::from_nanos(333333333)
+            );
+            assert_eq!(
+                parse_duration("0.6666666666666666666666s").unwrap(),
+                Duration::from_nanos(666666667)
+            );
+        }
+
+        #[test]
+        fn duration_compound() {
+            use super::parse_duration;
+            use std::time::Duration;
+
+            assert_eq!(parse_duration("2m30s").unwrap(), Duration::from_secs(150));
+            assert_eq!(
+                parse_duration("1s500ms").unwrap(),
+                Duration::from_millis(1500)
+            );
+            assert_eq!(
+                parse_duration("1h2m3s4ms5us").unwrap(),
+                Duration::new(3723, 4_005_000)
+            );
+            assert_eq!(
+                parse_duration("1.5m0.5s").unwrap(),
+                Duration::from_millis(90500)
+            );
+            // components are summed, whatever the order
+            assert_eq!(parse_duration("30s2m").unwrap(), Duration::from_secs(150));
+        }
+
+        #[test]
+        fn duration_invalid() {
+            use super::{parse_duration, ParseDurationError};
+            use std::time::Duration;
+
+            let err = |s: &str| parse_duration(s).unwrap_err();
+            assert_eq!(err(""), ParseDurationError::NoDigits("".to_string()));
+            assert_eq!(err("s"), ParseDurationError::NoDigits("s".to_string()));
+            assert_eq!(err(".5s"), ParseDurationError::NoDigits(".5s".to_string()));
+            assert_eq!(err("1sms"), ParseDurationError::Units("1sms".to_string()));
+            assert_eq!(err("1x"), ParseDurationError::Units("1x".to_string()));
+            // bare numbers are only valid by themselves
+            assert_eq!(err("1s500"), ParseDurationError::Units("1s500".to_string()));
+            assert_eq!(err("1.s"), ParseDurationError::Number("1.s".to_string()));
+            assert_eq!(
+                err("1.5.5s"),
+                ParseDurationError::Units("1.5.5s".to_string())
+            );
+            assert_eq!(err("-1s"), ParseDurationError::Negative("-1s".to_string()));
+            assert_eq!(err("-5"), ParseDurationError::Negative("-5".to_string()));
+            assert_eq!(err("-5").to_string(), "'-5' must not be negative");
+
+            // the limits of Duration
+            let max = format!("{}s", u64::MAX);
+            assert_eq!(parse_duration(&max).unwrap(), Duration::new(u64::MAX, 0));
+            let max = format!("{}.999999999s", u64::MAX);
+            assert_eq!(parse_duration(&max).unwrap(), Duration::MAX);
+            let over = format!("{}.9999999995s", u64::MAX);
+            assert_eq!(err(&over), ParseDurationError::Overflow(over.clone()));
+            let over = format!("{}s999999999us", u64::MAX);
+            assert_eq!(err(&over), ParseDurationError::Overflow(over.clone()));
+            let over = format!("{}h", u64::MAX);
+            assert_eq!(err(&over), ParseDurationError::Overflow(over.clone()));
+            let over = "9".repeat(50) + "us";
+            assert_eq!(err(&over), ParseDurationError::Overflow(over.clone()));
+            assert_eq!(err(&over).to_string(), format!("'{}' is too large", over));
+        }
+
+        #[test]
+        fn nonzero_duration() {
+            use super::{parse_nonzero_duration, ParseDurationError};
+            use std::time::Duration;
+
+            assert_eq!(
+                parse_nonzero_duration("1us").unwrap(),
+                Duration::from_micros(1)
+            );
+            assert_eq!(
+                parse_nonzero_duration("5").unwrap(),
+                Duration::from_millis(5)
+            );
+            let err = |s: &str| parse_nonzero_duration(s).unwrap_err();
+            assert_eq!(err("0"), ParseDurationError::Zero("0".to_string()));
+            assert_eq!(err("0s0ms"), ParseDurationError::Zero("0s0ms".to_string()));
+            assert_eq!(err("0").to_string(), "'0' must be greater than zero");
+            assert_eq!(err("-1"), ParseDurationError::Negative("-1".to_string()));
+        }
     }
 }
@@ -0,0 +1,178 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Shell-style glob matching for line names.
+//
+// Supports:
+//   '*' to match any sequence of characters, including the empty sequence,
+//   '?' to match any single character,
+//   '[...]' to match any character in the class, which may contain ranges
+//   such as 'a-z', and is negated if it starts with '!' or '^'.
+//
+// A '[' that is not closed is matched literally.
+
+/// Returns true if the id contains any glob special characters.
+pub fn is_pattern(id: &str) -> bool {
+    id.contains(['*', '?', '['])
+}
+
+/// Returns true if the name matches the glob pattern.
+pub fn is_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // the position of the last '*' in the pattern, and the name position it was tried at.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() {
+            match p[pi] {
+                '*' => {
+                    backtrack = Some((pi, ni));
+                    pi += 1;
+                    continue;
+                }
+                '?' => {
+                    pi += 1;
+                    ni += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, len)) = match_class(&p[pi..], n[ni]) {
+                        if matched {
+                            pi += len;
+                            ni += 1;
+                            continue;
+                        }
+                    } else if n[ni] == '[' {
+                        pi += 1;
+                        ni += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == n[ni] {
+                        pi += 1;
+                        ni += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        // mismatch, so extend the sequence matched by the last '*', if any
+        match backtrack {
+            Some((bpi, bni)) => {
+                backtrack = Some((bpi, bni + 1));
+                pi = bpi + 1;
+                ni = bni + 1;
+            }
+            None => return false,
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+// Match a character against the class at the start of the pattern.
+//
+// Returns whether the character matched and the length of the class in the
+// pattern, or None if the class is not terminated.
+fn match_class(p: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(p.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let lo = *p.get(i)?;
+        if lo == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+        if p.get(i + 1) == Some(&'-') && p.get(i + 2).is_some_and(|&hi| hi != ']') {
+            let hi = p[i + 2];
+            if lo <= c && c <= hi {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if lo == c {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern() {
+        assert!(is_pattern("LED*"));
+        assert!(is_pattern("LED?"));
+        assert!(is_pattern("LED[0-3]"));
+        assert!(!is_pattern("LED0"));
+        assert!(!is_pattern("17"));
+    }
+
+    #[test]
+    fn literal() {
+        assert!(is_match("LED0", "LED0"));
+        assert!(!is_match("LED0", "LED1"));
+        assert!(!is_match("LED0", "LED00"));
+        assert!(!is_match("LED00", "LED0"));
+        assert!(is_match("", ""));
+        assert!(!is_match("", "LED0"));
+    }
+
+    #[test]
+    fn star() {
+        assert!(is_match("LED*", "LED"));
+        assert!(is_match("LED*", "LED0"));
+        assert!(is_match("LED*", "LED_BLUE"));
+        assert!(!is_match("LED*", "BLED0"));
+        assert!(is_match("*", ""));
+        assert!(is_match("*", "anything"));
+        assert!(is_match("*_EN", "PWR_EN"));
+        assert!(!is_match("*_EN", "PWR_ENABLE"));
+        assert!(is_match("*LED*", "STATUS_LED_RED"));
+        assert!(is_match("a*b*c", "aXbYbZc"));
+        assert!(!is_match("a*b*c", "aXbYbZ"));
+        assert!(is_match("**", "x"));
+    }
+
+    #[test]
+    fn question() {
+        assert!(is_match("LED?", "LED0"));
+        assert!(!is_match("LED?", "LED"));
+        assert!(!is_match("LED?", "LED10"));
+        assert!(is_match("LED??", "LED10"));
+        assert!(is_match("?ED*", "LED10"));
+    }
+
+    #[test]
+    fn class() {
+        assert!(is_match("LED[0-3]", "LED0"));
+        assert!(is_match("LED[0-3]", "LED3"));
+        assert!(!is_match("LED[0-3]", "LED4"));
+        assert!(is_match("LED[0246]", "LED4"));
+        assert!(!is_match("LED[0246]", "LED5"));
+        assert!(is_match("LED[!0-3]", "LED4"));
+        assert!(!is_match("LED[!0-3]", "LED1"));
+        assert!(is_match("LED[^0-3]", "LED7"));
+        assert!(is_match("GPIO[a-cx]", "GPIOx"));
+        assert!(!is_match("GPIO[a-cx]", "GPIOd"));
+        assert!(is_match("LED[]]", "LED]"));
+        assert!(is_match("LED[0-]", "LED-"));
+        assert!(is_match("LED[0-9]*", "LED12_RED"));
+    }
+
+    #[test]
+    fn unterminated_class() {
+        assert!(is_match("LED[0", "LED[0"));
+        assert!(!is_match("LED[0", "LED0"));
+    }
+}
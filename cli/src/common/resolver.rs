@@ -2,14 +2,35 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::{actual_abi_version, Error, LineOpts, UapiOpts};
-use anyhow::anyhow;
+use super::{
+    actual_abi_version, closest_names, glob, parse_offset_range, Error, LineOpts, OffsetRange,
+    UapiOpts,
+};
+use anyhow::{anyhow, Context, Result};
 use gpiocdev::chip::Chip;
 use gpiocdev::line::{Info, Offset, OffsetMap};
 use gpiocdev::AbiVersion;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 
+// Whether a line id matches the name of a line.
+fn name_matches(id: &str, name: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        id.eq_ignore_ascii_case(name)
+    } else {
+        id == name
+    }
+}
+
+// Whether a glob pattern matches the name of a line.
+fn glob_name_matches(pattern: &str, name: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        glob::is_match(&pattern.to_ascii_lowercase(), &name.to_ascii_lowercase())
+    } else {
+        glob::is_match(pattern, name)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ChipOffset {
     // This is the idx into the Vec<ChipInfo>, not a system gpiochip#.
@@ -17,7 +38,7 @@ pub struct ChipOffset {
     pub offset: Offset,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ChipInfo {
     pub path: PathBuf,
     pub name: String,
@@ -51,6 +72,8 @@ pub struct Resolver {
     pub errors: Vec<anyhow::Error>,
     // ABI version being used
     pub abiv: AbiVersion,
+    // the names of the lines scanned, for suggesting alternatives to lines not found
+    names: Vec<String>,
 }
 
 impl Resolver {
@@ -67,6 +90,186 @@ impl Resolver {
         )
     }
 
+    /// Expand any offset ranges, then any glob patterns, in the lines.
+    pub fn expand_lines(
+        lines: &[String],
+        line_opts: &LineOpts,
+        uapi_opts: &UapiOpts,
+    ) -> Result<Vec<String>> {
+        let lines = Self::expand_ranges(lines, line_opts, uapi_opts)?;
+        Self::expand_globs(&lines, line_opts, uapi_opts)
+    }
+
+    /// Expand any offset ranges and the "all" keyword in the lines into offsets.
+    ///
+    /// The lines are returned unchanged unless --chip is provided and --by-name is not set.
+    ///
+    /// A range, such as "3-9", is replaced by the offsets it spans, inclusive,
+    /// and "all" is replaced by the offsets of all lines on the chip.
+    /// Overlapping ranges, and offsets covered by ranges, are not repeated.
+    /// Lines that are not ranges are otherwise passed through unchanged.
+    ///
+    /// It is an error for a range to be descending, or to extend beyond the
+    /// lines on the chip.
+    pub fn expand_ranges(
+        lines: &[String],
+        line_opts: &LineOpts,
+        uapi_opts: &UapiOpts,
+    ) -> Result<Vec<String>> {
+        let chip_id = match &line_opts.chip {
+            Some(chip_id) if !line_opts.by_name => chip_id,
+            _ => return Ok(lines.to_vec()),
+        };
+        // the name and number of lines of the chip, read when the first range is found
+        let mut chip_lines: Option<(String, u32)> = None;
+        let mut ranged = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut expanded: Vec<String> = Vec::new();
+        for id in lines {
+            let range = match parse_offset_range(id)? {
+                Some(range) => range,
+                None => {
+                    // an offset already covered by a range is not repeated
+                    if !id.parse::<Offset>().is_ok_and(|o| ranged.contains(&o)) {
+                        seen.insert(id.to_owned());
+                        expanded.push(id.to_owned());
+                    }
+                    continue;
+                }
+            };
+            let (name, num_lines) = match &chip_lines {
+                Some(cl) => cl,
+                None => {
+                    let path = super::chip_lookup_from_id(chip_id)?;
+                    let chip = super::chip_from_path(&path, actual_abi_version(uapi_opts)?)?;
+                    let num_lines = chip
+                        .info()
+                        .with_context(|| format!("unable to read info from {}", chip.name()))?
+                        .num_lines;
+                    chip_lines.insert((chip.name(), num_lines))
+                }
+            };
+            let (first, last) = match range {
+                OffsetRange::Span(first, last) => {
+                    // check the bounds before expanding, so a huge range is not
+                    // expanded only to be rejected when the lines are resolved.
+                    if last >= *num_lines {
+                        return Err(anyhow!(Error::OffsetOutOfRange(
+                            last.to_string(),
+                            name.to_owned()
+                        )));
+                    }
+                    (first, last)
+                }
+                OffsetRange::All => {
+                    if *num_lines == 0 {
+                        continue;
+                    }
+                    (0, num_lines - 1)
+                }
+            };
+            for offset in first..=last {
+                ranged.insert(offset);
+                let offset = offset.to_string();
+                if seen.insert(offset.clone()) {
+                    expanded.push(offset);
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Expand any glob patterns in the lines into the names of the matching lines.
+    ///
+    /// The lines are returned unchanged unless --glob is set.
+    ///
+    /// Each pattern is replaced by the names of all matching lines, sorted by
+    /// chip then offset. Names already present in the expanded lines are not
+    /// repeated. Lines that are not patterns are passed through unchanged.
+    ///
+    /// It is an error for a pattern to match no lines.
+    pub fn expand_globs(
+        lines: &[String],
+        line_opts: &LineOpts,
+        uapi_opts: &UapiOpts,
+    ) -> Result<Vec<String>> {
+        let mut expanded: Vec<String> = Vec::new();
+        for (id, matches) in lines
+            .iter()
+            .zip(Self::glob_matches(lines, line_opts, uapi_opts)?)
+        {
+            if glob::is_pattern(id) && line_opts.glob {
+                for name in matches {
+                    if !expanded.contains(&name) {
+                        expanded.push(name);
+                    }
+                }
+            } else {
+                expanded.extend(matches);
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Find the names of the lines matching each of the lines.
+    ///
+    /// Each line is matched to itself unless --glob is set and the line is a glob pattern,
+    /// in which case it is matched to the names of all matching lines, sorted by chip
+    /// then offset.
+    ///
+    /// It is an error for a pattern to match no lines.
+    pub fn glob_matches(
+        lines: &[String],
+        line_opts: &LineOpts,
+        uapi_opts: &UapiOpts,
+    ) -> Result<Vec<Vec<String>>> {
+        if !line_opts.glob || !lines.iter().any(|l| glob::is_pattern(l)) {
+            return Ok(lines.iter().map(|l| vec![l.to_owned()]).collect());
+        }
+        let chips = match &line_opts.chip {
+            Some(chip_id) => vec![super::chip_lookup_from_id(chip_id)?],
+            None => super::all_chip_paths()?,
+        };
+        let abiv = actual_abi_version(uapi_opts)?;
+        // the names of all named lines, in chip then offset order
+        let mut names = Vec::new();
+        for path in chips {
+            let chip = super::chip_from_path(&path, abiv)?;
+            for li in chip
+                .line_info_iter()
+                .with_context(|| format!("unable to read info from {}", chip.name()))?
+            {
+                let li =
+                    li.with_context(|| format!("unable to read line info from {}", chip.name()))?;
+                if !li.name.is_empty() {
+                    names.push(li.name);
+                }
+            }
+        }
+        let mut matches = Vec::new();
+        for id in lines {
+            if !glob::is_pattern(id) {
+                matches.push(vec![id.to_owned()]);
+                continue;
+            }
+            let mut m: Vec<String> = Vec::new();
+            for name in names
+                .iter()
+                .filter(|n| glob_name_matches(id, n, line_opts.ignore_case))
+            {
+                // a name may appear on multiple lines, but need only be matched once.
+                if !m.contains(name) {
+                    m.push(name.to_owned());
+                }
+            }
+            if m.is_empty() {
+                return Err(anyhow!(Error::NoMatchingLines(id.to_owned())));
+            }
+            matches.push(m);
+        }
+        Ok(matches)
+    }
+
     /// Core version which can also return the info for lines
     pub fn resolve_lines_with_info(
         lines: &[String],
@@ -156,50 +359,59 @@ impl Resolver {
                     break;
                 }
             }
-            // match by name
-            for offset in 0..kci.num_lines {
-                let li = match chip.line_info(offset) {
-                    Ok(li) => li,
-                    Err(e) => {
-                        r.errors.push(anyhow!(e).context(format!(
-                            "unable to read info for line {} from {}",
-                            offset,
-                            chip.name()
-                        )));
-                        // give up on the chip
-                        break;
-                    }
-                };
-                let mut save_info = uniq_lines.is_empty();
-                // save info for id by offset
-                if !offsets.is_empty() && offsets[0] == offset {
-                    save_info = true;
-                    offsets.pop_front();
-                }
-                for id in &uniq_lines {
-                    if id.as_str() == li.name.as_str() {
+            if uniq_lines.is_empty() {
+                // no lines specified, so all lines on the chip are relevant
+                r.get_all_info(&chip, chip_idx, with_info);
+                chip_used = with_info;
+            } else {
+                // match by name
+                for offset in 0..kci.num_lines {
+                    let li = match chip.line_info(offset) {
+                        Ok(li) => li,
+                        Err(e) => {
+                            r.errors.push(anyhow!(e).context(format!(
+                                "unable to read info for line {} from {}",
+                                offset,
+                                chip.name()
+                            )));
+                            // give up on the chip
+                            break;
+                        }
+                    };
+                    let mut save_info = false;
+                    // save info for id by offset
+                    if !offsets.is_empty() && offsets[0] == offset {
                         save_info = true;
-                        if !r.lines.contains_key(id) {
-                            chip_used = true;
-                            r.lines
-                                .insert(id.to_owned(), ChipOffset { chip_idx, offset });
+                        offsets.pop_front();
+                    }
+                    if !li.name.is_empty() {
+                        r.names.push(li.name.to_string());
+                    }
+                    for id in &uniq_lines {
+                        if name_matches(id, &li.name, line_opts.ignore_case) {
+                            save_info = true;
+                            if !r.lines.contains_key(id) {
+                                chip_used = true;
+                                r.lines
+                                    .insert(id.to_owned(), ChipOffset { chip_idx, offset });
 
-                            ci.named_lines.insert(offset, id.to_owned());
-                            if done(&r) {
-                                break;
+                                ci.named_lines.insert(offset, id.to_owned());
+                                if done(&r) {
+                                    break;
+                                }
+                            } else if line_opts.strict {
+                                r.push_error(Error::NonUniqueLine(id.into()));
+                                return r;
                             }
-                        } else if line_opts.strict {
-                            r.push_error(Error::NonUniqueLine(id.into()));
-                            return r;
                         }
                     }
-                }
-                if with_info && save_info {
-                    r.info.push(LineInfo {
-                        chip: chip_idx,
-                        info: li,
-                    });
-                    chip_used = true;
+                    if with_info && save_info {
+                        r.info.push(LineInfo {
+                            chip: chip_idx,
+                            info: li,
+                        });
+                        chip_used = true;
+                    }
                 }
             }
             // might still have some offsets that need info, so fill those in
@@ -217,6 +429,36 @@ impl Resolver {
         r
     }
 
+    fn get_all_info(&mut self, chip: &Chip, chip_idx: usize, with_info: bool) {
+        let infos = match chip.line_info_all() {
+            Ok(infos) => infos,
+            Err(e) => {
+                self.errors
+                    .push(anyhow!(e).context(format!("unable to read info from {}", chip.name())));
+                return;
+            }
+        };
+        for (offset, res) in infos.into_iter().enumerate() {
+            match res {
+                Ok(li) => {
+                    if with_info {
+                        self.info.push(LineInfo {
+                            chip: chip_idx,
+                            info: li,
+                        });
+                    }
+                }
+                Err(e) => {
+                    self.errors.push(anyhow!(e).context(format!(
+                        "unable to read info for line {} from {}",
+                        offset,
+                        chip.name()
+                    )));
+                }
+            }
+        }
+    }
+
     fn get_offset_info(&mut self, chip: &Chip, offsets: &VecDeque<Offset>) {
         for offset in offsets {
             match chip.line_info(*offset) {
@@ -266,7 +508,11 @@ impl Resolver {
                     chip.as_ref().unwrap().into(),
                 ));
             } else {
-                self.push_error(Error::NoSuchLine(id.into()));
+                let suggestions = closest_names(id, self.names.iter().map(|n| n.as_str()))
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                self.push_error(Error::NoSuchLine(id.into(), suggestions));
             }
         }
     }
@@ -0,0 +1,140 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use clap::Parser;
+use clap_complete::Shell;
+use gpiocdev::chip::{chips, Chip};
+use std::collections::BTreeSet;
+use std::io::Write;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The shell to generate completions for.
+    #[arg(value_name = "shell")]
+    shell: Shell,
+}
+
+pub fn cmd(opts: &Opts, cmd: &mut clap::Command) -> bool {
+    let mut stdout = std::io::stdout();
+    clap_complete::generate(opts.shell, cmd, BIN_NAME, &mut stdout);
+    let line_cmds = line_commands(cmd);
+    if let Some(dynamic) = dynamic_completions(opts.shell, &line_cmds) {
+        _ = stdout.write_all(dynamic.as_bytes());
+    }
+    true
+}
+
+#[derive(Debug, Parser)]
+pub struct LinesOpts {
+    /// The prefix of the line names to complete.
+    #[arg(value_name = "prefix", default_value = "")]
+    prefix: String,
+}
+
+/// Print the names of all lines on all chips that start with the prefix.
+///
+/// Chips that cannot be accessed are quietly skipped, as are lines
+/// whose info cannot be read, as the output is intended for shell
+/// completion and so is best effort.
+pub fn complete_lines(opts: &LinesOpts) -> bool {
+    for name in line_names_matching(&opts.prefix) {
+        println!("{name}");
+    }
+    true
+}
+
+fn line_names_matching(prefix: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let paths = match chips() {
+        Ok(pp) => pp,
+        Err(_) => return names,
+    };
+    for p in paths {
+        let chip = match Chip::from_path(&p) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        let iter = match chip.line_info_iter() {
+            Ok(i) => i,
+            Err(_) => continue,
+        };
+        for li in iter.flatten() {
+            if !li.name.is_empty() && li.name.starts_with(prefix) {
+                names.insert(li.name);
+            }
+        }
+    }
+    names
+}
+
+const BIN_NAME: &str = "gpiocdev";
+
+// The subcommands, and their aliases, that take line names as positional arguments,
+// as a '|' separated list.
+//
+// These are identified by the value name of their positional arguments, so
+// new subcommands are picked up without having to be listed here.
+fn line_commands(cmd: &clap::Command) -> String {
+    let mut cmds = Vec::new();
+    for sc in cmd.get_subcommands() {
+        let takes_lines = sc.get_positionals().any(|arg| {
+            arg.get_value_names()
+                .is_some_and(|names| names.iter().any(|n| n.as_str().starts_with("line")))
+        });
+        if takes_lines {
+            cmds.push(sc.get_name());
+            cmds.extend(sc.get_all_aliases());
+        }
+    }
+    cmds.join("|")
+}
+
+// Shell functions that extend the static completions with line names
+// provided by the hidden __complete-lines subcommand.
+fn dynamic_completions(shell: Shell, line_cmds: &str) -> Option<String> {
+    match shell {
+        Shell::Bash => Some(format!(
+            r#"
+_{bin}_lines() {{
+    _{bin} "$@"
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ ${{#COMP_WORDS[@]}} -gt 2 && "$cur" != -* ]]; then
+        case "${{COMP_WORDS[1]}}" in
+            {cmds})
+                local IFS=$'\n'
+                COMPREPLY+=( $({bin} __complete-lines "$cur" 2>/dev/null) )
+                ;;
+        esac
+    fi
+}}
+complete -F _{bin}_lines -o nosort -o bashdefault -o default {bin}
+"#,
+            bin = BIN_NAME,
+            cmds = line_cmds
+        )),
+        Shell::Zsh => Some(format!(
+            r#"
+_{bin}_lines() {{
+    _{bin} "$@"
+    if (( CURRENT > 2 )) && [[ "${{words[CURRENT]}}" != -* ]]; then
+        case "${{words[2]}}" in
+            {cmds})
+                compadd -- ${{(f)"$({bin} __complete-lines "${{words[CURRENT]}}" 2>/dev/null)"}}
+                ;;
+        esac
+    fi
+}}
+compdef _{bin}_lines {bin}
+"#,
+            bin = BIN_NAME,
+            cmds = line_cmds
+        )),
+        Shell::Fish => Some(format!(
+            "\ncomplete -c {bin} -n \"__fish_seen_subcommand_from {cmds}\" -f -a \"({bin} __complete-lines (commandline -ct) 2>/dev/null)\"\n",
+            bin = BIN_NAME,
+            cmds = line_cmds.replace('|', " ")
+        )),
+        _ => None,
+    }
+}
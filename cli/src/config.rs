@@ -0,0 +1,608 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Line configuration files, describing the lines to request declaratively.
+//
+// The file may be either TOML or JSON, as determined by the file extension.
+//
+// e.g.
+//
+//  consumer = "pins"
+//
+//  [lines.led]
+//  chip = "gpiochip0"
+//  offset = 17
+//  value = "on"
+//  drive = "open-drain"
+//
+//  [lines.button]
+//  name = "GPIO22"
+//  bias = "pull-up"
+//  active_low = true
+//  edge = "falling"
+//  debounce = "5ms"
+
+use super::common::{
+    self, BiasFlags, ChipInfo, DriveFlags, EdgeFlags, Error, LineOpts, Resolver, UapiOpts,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use gpiocdev::line::{self, Direction, EdgeDetection, Offset, Value};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+/// The contents of a line configuration file.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// The consumer label applied to requested lines.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consumer: Option<String>,
+
+    /// The lines, keyed by the identifier used to refer to them.
+    #[serde(default)]
+    pub lines: BTreeMap<String, LineSpec>,
+}
+
+/// The configuration for a line in a [`ConfigFile`].
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LineSpec {
+    /// The chip containing the line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chip: Option<String>,
+
+    /// The offset of the line on the chip.
+    ///
+    /// Requires the chip to be specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Offset>,
+
+    /// The name of the line.
+    ///
+    /// If neither the offset nor name are specified then the key is taken as the name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The direction of the line.
+    ///
+    /// If not specified then the line is an output if a value is provided,
+    /// else an input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<DirectionFlags>,
+
+    /// The initial value of an output line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<ValueSpec>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub active_low: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bias: Option<BiasFlags>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drive: Option<DriveFlags>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge: Option<EdgeFlags>,
+
+    /// The debounce period, taken as milliseconds unless otherwise specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debounce: Option<DurationSpec>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectionFlags {
+    Input,
+    Output,
+}
+
+/// A line value, as either a bool, 0 or 1, or a string as per the command line.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ValueSpec {
+    Bool(bool),
+    Int(u8),
+    Text(String),
+}
+
+impl ValueSpec {
+    fn value(&self) -> Result<Value> {
+        Ok(match self {
+            ValueSpec::Bool(false) | ValueSpec::Int(0) => Value::Inactive,
+            ValueSpec::Bool(true) | ValueSpec::Int(1) => Value::Active,
+            ValueSpec::Text(s) => s.parse()?,
+            ValueSpec::Int(v) => bail!("invalid line value: '{}'", v),
+        })
+    }
+}
+
+/// A time period, as either an integer number of milliseconds or a string
+/// as per the command line.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DurationSpec {
+    Millis(u64),
+    Text(String),
+}
+
+impl DurationSpec {
+    fn duration(&self) -> Result<Duration> {
+        Ok(match self {
+            DurationSpec::Millis(ms) => Duration::from_millis(*ms),
+            DurationSpec::Text(s) => common::parse_duration(s)?,
+        })
+    }
+}
+
+impl LineSpec {
+    /// The spec describing the current state of a line, as per its info.
+    ///
+    /// The value is only relevant for outputs, and is omitted if not known.
+    pub fn from_info(chip: &str, info: &line::Info, value: Option<Value>) -> LineSpec {
+        let mut spec = LineSpec {
+            chip: Some(chip.to_owned()),
+            offset: Some(info.offset),
+            active_low: info.active_low,
+            bias: info.bias.map(Into::into),
+            ..Default::default()
+        };
+        match info.direction {
+            Direction::Input => {
+                spec.direction = Some(DirectionFlags::Input);
+                spec.edge = info.edge_detection.map(Into::into);
+                spec.debounce = info
+                    .debounce_period
+                    .map(|d| DurationSpec::Text(format!("{}us", d.as_micros())));
+            }
+            Direction::Output => {
+                spec.direction = Some(DirectionFlags::Output);
+                spec.value = value.map(|v| {
+                    ValueSpec::Text(
+                        match v {
+                            Value::Active => "active",
+                            Value::Inactive => "inactive",
+                        }
+                        .to_owned(),
+                    )
+                });
+                spec.drive = info.drive.map(Into::into);
+            }
+        }
+        spec
+    }
+
+    /// The direction of the line, explicit or implied.
+    pub fn direction(&self) -> Direction {
+        match self.direction {
+            Some(DirectionFlags::Input) => Direction::Input,
+            Some(DirectionFlags::Output) => Direction::Output,
+            None if self.value.is_some() => Direction::Output,
+            None => Direction::Input,
+        }
+    }
+
+    /// The configuration for the line described by the spec.
+    pub fn line_config(&self) -> Result<line::Config> {
+        let mut cfg = line::Config {
+            active_low: self.active_low,
+            bias: self.bias.map(Into::into),
+            ..Default::default()
+        };
+        match self.direction() {
+            Direction::Input => {
+                if self.value.is_some() {
+                    bail!("value is only valid for output lines");
+                }
+                if self.drive.is_some() {
+                    bail!("drive is only valid for output lines");
+                }
+                cfg.with_edge_detection(self.edge.map(EdgeDetection::from));
+                if let Some(debounce) = &self.debounce {
+                    cfg.with_debounce_period(debounce.duration()?);
+                }
+            }
+            Direction::Output => {
+                if self.edge.is_some() {
+                    bail!("edge is only valid for input lines");
+                }
+                if self.debounce.is_some() {
+                    bail!("debounce is only valid for input lines");
+                }
+                let value = match &self.value {
+                    Some(v) => v.value()?,
+                    None => Value::Inactive,
+                };
+                cfg.as_output(value);
+                if let Some(drive) = self.drive {
+                    cfg.with_drive(drive.into());
+                }
+            }
+        }
+        Ok(cfg)
+    }
+}
+
+// The chip, and whether lines are identified by name, for a group of lines.
+type GroupKey<'a> = (Option<&'a str>, bool);
+
+impl ConfigFile {
+    /// Read the configuration from a file.
+    ///
+    /// Files with a .json extension are parsed as JSON, all others as TOML.
+    pub fn from_path(path: &Path) -> Result<ConfigFile> {
+        let s = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let res = if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&s)
+        } else {
+            Self::from_toml(&s)
+        };
+        res.with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    pub fn from_toml(s: &str) -> Result<ConfigFile> {
+        Ok(toml::from_str(s)?)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(s: &str) -> Result<ConfigFile> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    #[cfg(not(feature = "json"))]
+    pub fn from_json(_s: &str) -> Result<ConfigFile> {
+        bail!("JSON config files require the json feature")
+    }
+
+    /// The configuration for the lines with the given direction, keyed by line id.
+    pub fn line_configs(&self, direction: Direction) -> Result<Vec<(String, line::Config)>> {
+        let mut cfgs = Vec::new();
+        for (id, spec) in &self.lines {
+            if spec.direction() != direction {
+                continue;
+            }
+            let lc = spec
+                .line_config()
+                .with_context(|| format!("invalid config for line '{}'", id))?;
+            cfgs.push((id.to_owned(), lc));
+        }
+        if cfgs.is_empty() {
+            bail!(
+                "no {} lines in config",
+                match direction {
+                    Direction::Input => "input",
+                    Direction::Output => "output",
+                }
+            );
+        }
+        Ok(cfgs)
+    }
+
+    /// Find the (chip,offset) for the lines with the given ids.
+    ///
+    /// A chip specified in the `line_opts` overrides the chips in the file.
+    ///
+    /// The lines in the returned Resolver are keyed by id.
+    pub fn resolve(&self, ids: &[&str], line_opts: &LineOpts, uapi_opts: &UapiOpts) -> Resolver {
+        let mut r = Resolver::default();
+        // lines are resolved in groups sharing a chip and identification mode
+        let mut groups: BTreeMap<GroupKey, Vec<(&str, String)>> = BTreeMap::new();
+        for id in ids {
+            let spec = &self.lines[*id];
+            let chip = line_opts.chip.as_deref().or(spec.chip.as_deref());
+            let (by_name, line) = match (spec.offset, &spec.name) {
+                (Some(_), Some(_)) => {
+                    r.errors
+                        .push(anyhow!("line '{}' has both an offset and a name", id));
+                    continue;
+                }
+                (Some(offset), None) => {
+                    if chip.is_none() {
+                        r.errors
+                            .push(anyhow!("line '{}' has an offset but no chip", id));
+                        continue;
+                    }
+                    (false, offset.to_string())
+                }
+                (None, Some(name)) => (true, name.to_owned()),
+                (None, None) => (true, id.to_string()),
+            };
+            groups.entry((chip, by_name)).or_default().push((id, line));
+        }
+        for ((chip, by_name), lines) in groups {
+            let opts = LineOpts {
+                chip: chip.map(String::from),
+                by_name,
+                glob: false,
+                ..line_opts.clone()
+            };
+            let names: Vec<String> = lines.iter().map(|(_id, line)| line.to_owned()).collect();
+            let gr = Resolver::resolve_lines(&names, &opts, uapi_opts);
+            r.abiv = gr.abiv;
+            r.errors.extend(gr.errors);
+            for (id, line) in lines {
+                if let Some(co) = gr.lines.get(&line) {
+                    let chip_idx = chip_index(&mut r.chips, &gr.chips[co.chip_idx]);
+                    r.lines.insert(
+                        id.to_owned(),
+                        common::ChipOffset {
+                            chip_idx,
+                            offset: co.offset,
+                        },
+                    );
+                }
+            }
+        }
+        check_duplicates(&mut r);
+        r
+    }
+}
+
+// The index of the chip in the chips, adding it if necessary.
+fn chip_index(chips: &mut Vec<ChipInfo>, ci: &ChipInfo) -> usize {
+    match chips.iter().position(|c| c.path == ci.path) {
+        Some(idx) => idx,
+        None => {
+            chips.push(ci.clone());
+            chips.len() - 1
+        }
+    }
+}
+
+fn check_duplicates(r: &mut Resolver) {
+    let mut seen: HashMap<&common::ChipOffset, &str> = HashMap::new();
+    let mut ids: Vec<&String> = r.lines.keys().collect();
+    ids.sort();
+    for id in ids {
+        let co = &r.lines[id];
+        if let Some(prev) = seen.insert(co, id) {
+            r.errors
+                .push(Error::DuplicateLine(prev.to_string(), id.to_owned()).into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpiocdev::line::{Bias, Drive};
+
+    const SAMPLE_TOML: &str = include_str!("../examples/pins.toml");
+
+    #[test]
+    fn sample_toml() {
+        let cf = ConfigFile::from_toml(SAMPLE_TOML).unwrap();
+        assert_eq!(cf.consumer.as_deref(), Some("pins"));
+        assert_eq!(cf.lines.len(), 4);
+        let led = &cf.lines["led"];
+        assert_eq!(led.chip.as_deref(), Some("gpiochip0"));
+        assert_eq!(led.offset, Some(17));
+        assert_eq!(led.direction(), Direction::Output);
+        let button = &cf.lines["button"];
+        assert_eq!(button.name.as_deref(), Some("GPIO22"));
+        assert_eq!(button.direction(), Direction::Input);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn sample_json() {
+        let cf = ConfigFile::from_json(include_str!("../examples/pins.json")).unwrap();
+        assert_eq!(cf, ConfigFile::from_toml(SAMPLE_TOML).unwrap());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let err = ConfigFile::from_toml("consumer = \"pins\"\ncolour = \"red\"\n").unwrap_err();
+        assert!(err.to_string().contains("unknown field `colour`"));
+
+        let err = ConfigFile::from_toml("[lines.led]\noffset = 3\nspeed = 5\n").unwrap_err();
+        assert!(err.to_string().contains("unknown field `speed`"));
+    }
+
+    #[test]
+    fn line_config() {
+        let cf = ConfigFile::from_toml(SAMPLE_TOML).unwrap();
+
+        let lc = line::Config {
+            direction: Some(Direction::Output),
+            value: Some(Value::Active),
+            drive: Some(Drive::OpenDrain),
+            ..Default::default()
+        };
+        assert_eq!(cf.lines["led"].line_config().unwrap(), lc);
+
+        let lc = line::Config {
+            direction: Some(Direction::Input),
+            active_low: true,
+            bias: Some(Bias::PullUp),
+            edge_detection: Some(EdgeDetection::FallingEdge),
+            debounce_period: Some(Duration::from_millis(5)),
+            ..Default::default()
+        };
+        assert_eq!(cf.lines["button"].line_config().unwrap(), lc);
+
+        let lc = line::Config {
+            direction: Some(Direction::Output),
+            value: Some(Value::Inactive),
+            ..Default::default()
+        };
+        assert_eq!(cf.lines["relay"].line_config().unwrap(), lc);
+
+        let lc = line::Config {
+            direction: Some(Direction::Input),
+            edge_detection: Some(EdgeDetection::BothEdges),
+            debounce_period: Some(Duration::from_micros(500)),
+            ..Default::default()
+        };
+        assert_eq!(cf.lines["door"].line_config().unwrap(), lc);
+    }
+
+    #[test]
+    fn line_config_invalid() {
+        let cf = ConfigFile::from_toml(
+            r#"
+            [lines.a]
+            direction = "input"
+            value = 1
+            [lines.b]
+            value = "maybe"
+            [lines.c]
+            value = 1
+            edge = "both"
+            [lines.d]
+            debounce = "5 parsecs"
+            [lines.e]
+            value = 2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            cf.lines["a"].line_config().unwrap_err().to_string(),
+            "value is only valid for output lines"
+        );
+        assert_eq!(
+            cf.lines["b"].line_config().unwrap_err().to_string(),
+            "invalid line value: 'maybe'"
+        );
+        assert_eq!(
+            cf.lines["c"].line_config().unwrap_err().to_string(),
+            "edge is only valid for input lines"
+        );
+        assert!(cf.lines["d"].line_config().is_err());
+        assert_eq!(
+            cf.lines["e"].line_config().unwrap_err().to_string(),
+            "invalid line value: '2'"
+        );
+    }
+
+    #[test]
+    fn line_configs() {
+        let cf = ConfigFile::from_toml(SAMPLE_TOML).unwrap();
+        let ids = |d| -> Vec<String> {
+            cf.line_configs(d)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _lc)| id)
+                .collect()
+        };
+        assert_eq!(ids(Direction::Output), ["led", "relay"]);
+        assert_eq!(ids(Direction::Input), ["button", "door"]);
+
+        let cf = ConfigFile::from_toml("[lines.led]\nvalue = 1\n").unwrap();
+        assert_eq!(
+            cf.line_configs(Direction::Input).unwrap_err().to_string(),
+            "no input lines in config"
+        );
+    }
+
+    #[test]
+    fn from_info() {
+        let mut info = line::Info::default();
+        info.offset = 17;
+        info.name = "LED".into();
+        info.direction = Direction::Output;
+        info.drive = Some(Drive::OpenDrain);
+        let spec = LineSpec::from_info("gpiochip0", &info, Some(Value::Active));
+        assert_eq!(
+            spec,
+            LineSpec {
+                chip: Some("gpiochip0".into()),
+                offset: Some(17),
+                direction: Some(DirectionFlags::Output),
+                value: Some(ValueSpec::Text("active".into())),
+                drive: Some(DriveFlags::OpenDrain),
+                ..Default::default()
+            }
+        );
+        let lc = line::Config {
+            direction: Some(Direction::Output),
+            value: Some(Value::Active),
+            drive: Some(Drive::OpenDrain),
+            ..Default::default()
+        };
+        assert_eq!(spec.line_config().unwrap(), lc);
+
+        let mut info = line::Info::default();
+        info.offset = 22;
+        info.direction = Direction::Input;
+        info.active_low = true;
+        info.bias = Some(Bias::PullUp);
+        info.edge_detection = Some(EdgeDetection::FallingEdge);
+        info.debounce_period = Some(Duration::from_micros(1500));
+        let spec = LineSpec::from_info("gpiochip1", &info, None);
+        assert_eq!(spec.debounce, Some(DurationSpec::Text("1500us".into())));
+        let lc = line::Config {
+            direction: Some(Direction::Input),
+            active_low: true,
+            bias: Some(Bias::PullUp),
+            edge_detection: Some(EdgeDetection::FallingEdge),
+            debounce_period: Some(Duration::from_micros(1500)),
+            ..Default::default()
+        };
+        assert_eq!(spec.line_config().unwrap(), lc);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let mut cf = ConfigFile::default();
+        let mut info = line::Info::default();
+        info.offset = 3;
+        info.direction = Direction::Output;
+        info.bias = Some(Bias::PullDown);
+        cf.lines.insert(
+            "relay".into(),
+            LineSpec::from_info("gpiochip0", &info, Some(Value::Inactive)),
+        );
+        let mut info = line::Info::default();
+        info.offset = 4;
+        info.direction = Direction::Input;
+        info.edge_detection = Some(EdgeDetection::BothEdges);
+        cf.lines
+            .insert("door".into(), LineSpec::from_info("gpiochip0", &info, None));
+        let s = toml::to_string(&cf).unwrap();
+        assert_eq!(
+            s,
+            "[lines.door]\n\
+             chip = \"gpiochip0\"\n\
+             offset = 4\n\
+             direction = \"input\"\n\
+             edge = \"both\"\n\
+             \n\
+             [lines.relay]\n\
+             chip = \"gpiochip0\"\n\
+             offset = 3\n\
+             direction = \"output\"\n\
+             value = \"inactive\"\n\
+             bias = \"pull-down\"\n"
+        );
+        assert_eq!(ConfigFile::from_toml(&s).unwrap(), cf);
+    }
+
+    #[test]
+    fn resolve_offset_without_chip() {
+        let cf = ConfigFile::from_toml("[lines.led]\noffset = 3\nvalue = 1\n").unwrap();
+        let line_opts = LineOpts {
+            chip: None,
+            strict: false,
+            by_name: false,
+            glob: false,
+            ignore_case: false,
+        };
+        let r = cf.resolve(&["led"], &line_opts, &UapiOpts::default());
+        assert_eq!(r.errors.len(), 1);
+        assert_eq!(
+            r.errors[0].to_string(),
+            "line 'led' has an offset but no chip"
+        );
+        assert!(r.lines.is_empty());
+    }
+}
@@ -2,17 +2,33 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::common::{self, emit_error, format_error, format_time, ChipInfo, EmitOpts, TimeFmt};
-use anyhow::anyhow;
+mod reader;
+use self::reader::{EventReader, ReadValues};
+mod stats;
+use self::stats::Stats;
+use super::common::{
+    self, emit_error, format_error, format_timestamp, ChipInfo, EmitOpts, TimeFmt,
+};
+#[cfg(feature = "config")]
+use super::config::ConfigFile;
+use anyhow::{anyhow, Context};
 use clap::{Parser, ValueEnum};
-use gpiocdev::line::{EdgeEvent, EdgeKind, Offset};
+#[cfg(feature = "config")]
+use gpiocdev::line::Direction;
+use gpiocdev::line::{self, EdgeDetection, EdgeEvent, EdgeKind, Offset};
 use gpiocdev::request::{Config, Request};
 use mio::unix::SourceFd;
-use mio::{Events, Interest, Poll, Token};
+use mio::{Events, Interest, Poll, Registry, Token};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
 use std::os::unix::prelude::AsRawFd;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Parser)]
 #[command(aliases(["e", "mon"]))]
@@ -21,9 +37,25 @@ pub struct Opts {
     ///
     /// The lines are identified by name or optionally by offset if
     /// the --chip option is specified.
-    #[arg(value_name = "line", required = true)]
+    ///
+    /// With --chip, offset ranges such as 3-9, and the keyword all for
+    /// all lines on the chip, may also be used.
+    #[arg(value_name = "line")]
+    #[cfg_attr(not(feature = "config"), arg(required = true))]
+    #[cfg_attr(
+        feature = "config",
+        arg(required_unless_present = "config", conflicts_with = "config")
+    )]
     lines: Vec<String>,
 
+    /// Read the lines to monitor from a TOML or JSON configuration file
+    ///
+    /// The input lines described in the file are monitored.
+    /// Options provided on the command line override those from the file.
+    #[cfg(feature = "config")]
+    #[arg(long, value_name = "path", conflicts_with = "auto_reattach")]
+    config: Option<std::path::PathBuf>,
+
     /// Display a banner on successful startup
     #[arg(long)]
     banner: bool,
@@ -53,17 +85,62 @@ pub struct Opts {
     #[arg(long, value_name = "period", value_parser = common::parse_duration)]
     idle_timeout: Option<Duration>,
 
+    /// Exit after monitoring for the specified period.
+    ///
+    /// Combined with --num-events, the command exits on whichever occurs first.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(long, value_name = "period", value_parser = common::parse_duration)]
+    timeout: Option<Duration>,
+
     /// Exit after the specified number of events
     ///
     /// If not specified then monitoring will continue indefinitely.
-    #[arg(short, long, value_name = "num")]
+    #[arg(short, long, visible_alias = "max-events", value_name = "num")]
     num_events: Option<u32>,
 
+    /// Read the values of these lines when each event is received
+    ///
+    /// The values are appended to the event output, in the order listed.
+    ///
+    /// The lines are identified in the same way as the monitored lines,
+    /// and are separated by commas or provided by repeating the option.
+    /// Lines that are not monitored are requested as inputs.
+    ///
+    /// The values are read after the event is read, and before the next
+    /// event is processed, so they lag the edge by the latency of reading
+    /// the event and then the values - typically tens of microseconds,
+    /// but longer if events are queued.
+    /// Signals that change within that period may be misread.
+    #[arg(
+        long,
+        value_name = "line",
+        value_delimiter = ',',
+        conflicts_with_all = ["format", "quiet", "stats"]
+    )]
+    read_on_event: Vec<String>,
+
     /// Specify the source clock for event timestamps
     #[cfg(feature = "uapi_v2")]
     #[arg(short = 'E', long, value_name = "clock")]
     event_clock: Option<EventClock>,
 
+    /// The size of the kernel buffer for events on each chip.
+    ///
+    /// If events arrive faster than they are read then the oldest are discarded
+    /// once the buffer is full, and a warning is emitted.
+    ///
+    /// Defaults to 16 times the number of requested lines.
+    #[cfg(feature = "uapi_v2")]
+    #[arg(long, value_name = "num", value_parser = clap::value_parser!(u32).range(1..))]
+    event_buffer_size: Option<u32>,
+
+    /// Include the event sequence numbers in the output.
+    ///
+    /// The sequence numbers are only provided by uAPI v2.
+    #[arg(long)]
+    seqno: bool,
+
     /// Specify a custom output format
     ///
     /// Format specifiers:
@@ -84,6 +161,29 @@ pub struct Opts {
     )]
     format: Option<String>,
 
+    /// Specify the format of event timestamps
+    ///
+    /// The kernel timestamps events using the event clock, which is
+    /// CLOCK_MONOTONIC unless otherwise specified.
+    /// The wall-clock formats, utc and localtime, require CLOCK_REALTIME
+    /// so select that clock, unless --event-clock is provided.
+    /// Note that uAPI v1 cannot select the event clock, so the clock is
+    /// detected from the first event - CLOCK_REALTIME for kernels prior to 5.7.
+    ///
+    ///   raw        nanoseconds, as reported by the kernel
+    ///   relative   seconds since the first event
+    ///   seconds    seconds, as reported by the kernel (default)
+    ///   utc        wall-clock as UTC
+    ///   localtime  wall-clock as local time
+    #[arg(
+        long = "timefmt",
+        value_name = "fmt",
+        group = "timefmt",
+        hide_possible_values = true,
+        verbatim_doc_comment
+    )]
+    time_format: Option<TimeFmtFlags>,
+
     /// Format event timestamps as local time
     #[arg(long, group = "timefmt")]
     localtime: bool,
@@ -96,14 +196,46 @@ pub struct Opts {
     #[arg(short = 'q', long, groups = ["emit", "timefmt"], alias = "silent")]
     quiet: bool,
 
-    /// The consumer label applied to requested lines.
+    /// Report edge interval statistics every period, rather than the events
+    ///
+    /// The minimum, mean and maximum intervals between consecutive edges of
+    /// each kind are reported for each line, as a table, such as for
+    /// characterizing a PWM or clock signal.
+    /// The intervals are calculated from the event timestamps, and each
+    /// report covers the intervals ending since the previous report.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
     #[arg(
-        short = 'C',
         long,
-        value_name = "name",
-        default_value = "gpiocdev-edges"
+        value_name = "period",
+        groups = ["emit", "timefmt"],
+        value_parser = common::parse_nonzero_duration
     )]
-    consumer: String,
+    stats: Option<Duration>,
+
+    /// Append the events to a file rather than writing them to stdout
+    ///
+    /// The events are written in the same format as would be written to stdout,
+    /// and the file is flushed after each event.
+    ///
+    /// Errors writing to the file are reported and monitoring continues.
+    #[arg(short = 'o', long, value_name = "path", conflicts_with = "quiet")]
+    output: Option<PathBuf>,
+
+    /// The consumer label applied to requested lines.
+    ///
+    /// Defaults to "gpiocdev-edges", unless provided by a configuration file.
+    #[arg(short = 'C', long, value_name = "name")]
+    consumer: Option<String>,
+
+    #[command(flatten)]
+    ready_opts: common::ReadyOpts,
+
+    #[command(flatten)]
+    reattach_opts: common::ReattachOpts,
+
+    #[command(flatten)]
+    daemon_opts: common::DaemonOpts,
 
     #[command(flatten)]
     uapi_opts: common::UapiOpts,
@@ -113,6 +245,13 @@ pub struct Opts {
 }
 
 impl Opts {
+    fn consumer<'a>(&'a self, file_consumer: Option<&'a str>) -> &'a str {
+        self.consumer
+            .as_deref()
+            .or(file_consumer)
+            .unwrap_or("gpiocdev-edges")
+    }
+
     // mutate the config to match the configuration
     fn apply(&self, config: &mut Config) {
         #[cfg(feature = "uapi_v2")]
@@ -122,7 +261,7 @@ impl Opts {
             }
             if let Some(clock) = self.event_clock {
                 config.with_event_clock(Some(clock.into()));
-            } else if self.localtime || self.utc {
+            } else if self.is_wall_clock() {
                 config.with_event_clock(gpiocdev::line::EventClock::Realtime);
             }
         }
@@ -130,7 +269,7 @@ impl Opts {
         if self.event_clock.is_none() {
             // uapi v1 can't select clock, so we can't force Realtime for
             // utc or localtime - we just have to hope the kernel uses Realtime.
-            if let Some(super::common::AbiVersionFlags::V1) = self.uapi_opts.abi_version {
+            if self.uapi_opts.abi_version == super::common::AbiVersionFlags::V1 {
                 config.with_event_clock(None);
             }
         }
@@ -139,9 +278,21 @@ impl Opts {
         self.edge_opts.apply(config);
     }
 
+    #[cfg(feature = "uapi_v2")]
+    fn is_wall_clock(&self) -> bool {
+        self.localtime
+            || self.utc
+            || matches!(
+                self.time_format,
+                Some(TimeFmtFlags::Utc) | Some(TimeFmtFlags::Localtime)
+            )
+    }
+
     #[cfg(feature = "uapi_v2")]
     fn timefmt(&self) -> TimeFmt {
-        if self.localtime {
+        if let Some(tf) = self.time_format {
+            tf.into()
+        } else if self.localtime {
             TimeFmt::Localtime
         } else if self.utc || self.event_clock == Some(EventClock::Realtime) {
             TimeFmt::Utc
@@ -151,7 +302,9 @@ impl Opts {
     }
     #[cfg(not(feature = "uapi_v2"))]
     fn timefmt(&self) -> TimeFmt {
-        if self.localtime {
+        if let Some(tf) = self.time_format {
+            tf.into()
+        } else if self.localtime {
             TimeFmt::Localtime
         } else if self.utc {
             TimeFmt::Utc
@@ -161,6 +314,26 @@ impl Opts {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum TimeFmtFlags {
+    Raw,
+    Relative,
+    Seconds,
+    Utc,
+    Localtime,
+}
+impl From<TimeFmtFlags> for TimeFmt {
+    fn from(tf: TimeFmtFlags) -> Self {
+        match tf {
+            TimeFmtFlags::Raw => TimeFmt::Raw,
+            TimeFmtFlags::Relative => TimeFmt::Relative(None),
+            TimeFmtFlags::Seconds => TimeFmt::Seconds,
+            TimeFmtFlags::Utc => TimeFmt::Utc,
+            TimeFmtFlags::Localtime => TimeFmt::Localtime,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
 enum EventClock {
     Monotonic,
@@ -177,26 +350,75 @@ impl From<EventClock> for gpiocdev::line::EventClock {
     }
 }
 
-pub fn cmd(opts: &Opts) -> bool {
+// The number of events that may be read from a request in one read.
+const EVENT_BUFFER_CAPACITY: usize = 16;
+
+pub fn cmd(opts: &Opts) -> ExitCode {
     let res = do_cmd(opts);
     res.emit();
-    res.errors.is_empty()
+    if res.chip_removed {
+        ExitCode::from(common::CHIP_REMOVED_EXIT_CODE)
+    } else if res.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
 fn do_cmd(opts: &Opts) -> CmdResult {
-    use std::io::Write;
-
     let mut res = CmdResult {
         opts: opts.emit,
         ..Default::default()
     };
-    let r = common::Resolver::resolve_lines(&opts.lines, &opts.line_opts, &opts.uapi_opts);
+    if let Err(e) = opts
+        .ready_opts
+        .check()
+        .and_then(|_| opts.daemon_opts.check(&opts.ready_opts))
+        .and_then(|_| opts.reattach_opts.check(&opts.line_opts))
+    {
+        res.push_error(&e);
+        return res;
+    }
+    let ResolvedLines {
+        ids: lines,
+        mut r,
+        line_cfgs,
+        consumer: file_consumer,
+    } = match resolve_lines(opts) {
+        Ok(rl) => rl,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
     if !r.errors.is_empty() {
         for e in r.errors {
             res.push_error(&e);
         }
         return res;
     }
+    let read_lines = match EventReader::resolve(opts) {
+        Ok(rl) => rl,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
+    if let Some((_, rr)) = &read_lines {
+        if !rr.errors.is_empty() {
+            for e in &rr.errors {
+                res.push_error(e);
+            }
+            return res;
+        }
+    }
+    let mut output = match Output::new(opts.output.as_ref()) {
+        Ok(o) => o,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
     let mut poll = match Poll::new() {
         Ok(p) => p,
         Err(e) => {
@@ -205,51 +427,81 @@ fn do_cmd(opts: &Opts) -> CmdResult {
         }
     };
 
-    let mut reqs = Vec::new();
-    for (idx, ci) in r.chips.iter().enumerate() {
-        let mut cfg = Config::default();
-        opts.apply(&mut cfg);
-        let offsets: Vec<Offset> = r
-            .lines
-            .values()
-            .filter(|co| co.chip_idx == idx)
-            .map(|co| co.offset)
-            .collect();
-        cfg.with_lines(&offsets);
-        let mut bld = Request::from_config(cfg);
-        bld.on_chip(&ci.path).with_consumer(&opts.consumer);
-        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-        bld.using_abi_version(r.abiv);
-        match bld.request() {
-            Ok(req) => {
-                if let Err(e) = poll.registry().register(
-                    &mut SourceFd(&req.as_raw_fd()),
-                    Token(idx),
-                    Interest::READABLE,
-                ) {
-                    res.push_error(&anyhow!(e).context(format!(
-                        "failed register {:?} from {} with poll",
-                        offsets, ci.name
-                    )));
-                    return res;
+    let consumer = opts.consumer(file_consumer.as_deref()).to_owned();
+    let mut reqs = match request_lines(opts, &r, &line_cfgs, &consumer, poll.registry()) {
+        Ok(reqs) => reqs,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
+    let mut reader =
+        match read_lines.map(|(ids, rr)| EventReader::new(opts, ids, &rr, &r, &consumer)) {
+            Some(Ok(er)) => Some(er),
+            Some(Err(e)) => {
+                res.push_error(&e);
+                return res;
+            }
+            None => None,
+        };
+    let mut count = 0;
+    // the last event read from each request, for detecting discarded events
+    let mut last_edges: Vec<Option<EdgeEvent>> = vec![None; reqs.len()];
+    // buffer the events from each request, so bursts of events are read in bulk.
+    let mut bufs: Vec<_> = reqs
+        .iter()
+        .map(|req| req.new_edge_event_buffer(EVENT_BUFFER_CAPACITY))
+        .collect();
+    let mut events = Events::with_capacity(r.chips.len());
+    let mut timefmt = opts.timefmt();
+    let mut stats = opts.stats.map(|period| {
+        let mut stats_lines = Vec::new();
+        for id in &lines {
+            if let Some(co) = r.lines.get(id) {
+                if !stats_lines.iter().any(|(l, _, _)| l == id) {
+                    stats_lines.push((id.clone(), co.chip_idx, co.offset));
                 }
-                reqs.push(req);
             }
+        }
+        (Stats::new(stats_lines), period)
+    });
+    let mut hangup = None;
+    if opts.reattach_opts.auto_reattach {
+        match common::Hangup::new().and_then(|h| {
+            poll.registry()
+                .register(&mut SourceFd(&h.as_raw_fd()), HANGUP, Interest::READABLE)
+                .context("failed to register SIGHUP with poll")?;
+            Ok(h)
+        }) {
+            Ok(h) => hangup = Some(h),
             Err(e) => {
-                res.push_error(&anyhow!(e).context(format!(
-                    "failed to request lines {:?} from {}",
-                    offsets, ci.name
-                )));
+                res.push_error(&e);
                 return res;
             }
         }
     }
-    let mut count = 0;
-    let mut events = Events::with_capacity(r.chips.len());
-    let timefmt = opts.timefmt();
-    emit_banner(opts);
+    emit_banner(opts, &lines);
+    // the lines are requested before detaching, so the daemon retains them.
+    if let Err(e) = opts
+        .daemon_opts
+        .daemonize()
+        .and_then(|_| opts.ready_opts.notify())
+    {
+        res.push_error(&e);
+        return res;
+    }
+    // deadlines beyond the range of Instant are treated as no deadline
+    let mut last_event = Instant::now();
+    let mut next_report = opts.stats.and_then(|period| last_event.checked_add(period));
+    let end = opts.timeout.and_then(|d| last_event.checked_add(d));
     loop {
-        match poll.poll(&mut events, opts.idle_timeout) {
+        let idle_deadline = opts.idle_timeout.and_then(|d| last_event.checked_add(d));
+        let deadline = [idle_deadline, next_report, end]
+            .into_iter()
+            .flatten()
+            .min();
+        let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        match poll.poll(&mut events, timeout) {
             Err(e) => {
                 if e.kind() != std::io::ErrorKind::Interrupted {
                     res.push_error(&anyhow!(e));
@@ -257,40 +509,214 @@ fn do_cmd(opts: &Opts) -> CmdResult {
                 }
             }
             Ok(()) => {
-                if events.is_empty() {
+                let now = Instant::now();
+                if end.is_some_and(|end| now >= end) {
+                    if let Some((st, _)) = &mut stats {
+                        emit_stats(st, opts, &mut output);
+                    }
                     return res;
                 }
+                if let (Some(report), Some((st, period))) = (next_report, &mut stats) {
+                    if now >= report {
+                        emit_stats(st, opts, &mut output);
+                        // skip any missed reports
+                        let mut next = report.checked_add(*period);
+                        while next.is_some_and(|next| next <= now) {
+                            next = next.and_then(|next| next.checked_add(*period));
+                        }
+                        next_report = next;
+                    }
+                }
+                if events.is_empty() {
+                    if idle_deadline.is_some_and(|idle| now >= idle) {
+                        return res;
+                    }
+                    continue;
+                }
+                last_event = now;
+                let mut reattach = false;
                 for event in &events {
+                    if event.token() == HANGUP {
+                        if let Some(h) = hangup.as_mut() {
+                            h.clear();
+                        }
+                        reattach = true;
+                        continue;
+                    }
                     let idx: usize = event.token().into();
-                    while reqs[idx].has_edge_event().unwrap_or(false) {
-                        match reqs[idx].read_edge_event() {
+                    let buf = &mut bufs[idx];
+                    while buf.has_event().unwrap_or(false) {
+                        match buf.read_event() {
                             Ok(edge) => {
-                                emit_edge(edge, &r.chips[idx], opts, &timefmt);
+                                if timefmt == TimeFmt::Relative(None) {
+                                    timefmt = TimeFmt::Relative(Some(edge.timestamp_ns));
+                                }
+                                emit_lost_events(&edge, &last_edges[idx], &r.chips[idx]);
+                                last_edges[idx] = Some(edge.clone());
+                                match &mut stats {
+                                    Some((st, _)) => {
+                                        st.add(idx, edge.offset, edge.kind, edge.timestamp_ns)
+                                    }
+                                    None => {
+                                        // read before the next event is processed
+                                        let values = reader.as_ref().and_then(|er| {
+                                            er.read(&reqs)
+                                                .map_err(|e| emit_error(&opts.emit, &e))
+                                                .ok()
+                                        });
+                                        emit_edge(
+                                            edge,
+                                            values,
+                                            &r.chips[idx],
+                                            opts,
+                                            &timefmt,
+                                            &mut output,
+                                        )
+                                    }
+                                }
                                 if let Some(limit) = opts.num_events {
                                     count += 1;
                                     if count >= limit {
+                                        if let Some((st, _)) = &mut stats {
+                                            emit_stats(st, opts, &mut output);
+                                        }
                                         return res;
                                     }
                                 }
                             }
                             Err(e) => {
-                                emit_error(
-                                    &opts.emit,
-                                    &anyhow!(e).context(format!(
-                                        "failed to read event from {}",
-                                        r.chips[idx].name
-                                    )),
-                                );
+                                let e = anyhow!(e).context(format!(
+                                    "failed to read event from {}",
+                                    r.chips[idx].name
+                                ));
+                                // the request remains readable once the chip is removed.
+                                if common::is_chip_removed(&e) {
+                                    if !opts.reattach_opts.auto_reattach {
+                                        res.push_error(&e);
+                                        return res;
+                                    }
+                                    emit_error(&opts.emit, &e);
+                                    reattach = true;
+                                    break;
+                                }
+                                emit_error(&opts.emit, &e);
                             }
                         }
                     }
                 }
                 _ = std::io::stdout().flush();
+                if reattach {
+                    // release the lines, as they are still held after a SIGHUP.
+                    reqs.clear();
+                    drop(reader.take());
+                    (r, reqs, reader) =
+                        common::reattach(&lines, || reattach_lines(opts, poll.registry()));
+                    bufs = reqs
+                        .iter()
+                        .map(|req| req.new_edge_event_buffer(EVENT_BUFFER_CAPACITY))
+                        .collect();
+                    last_edges = vec![None; reqs.len()];
+                    if let Some((st, _)) = &mut stats {
+                        st.relocate(&r.lines);
+                    }
+                }
             }
         }
     }
 }
 
+// The token for the SIGHUP source, which cannot collide with the chip indices.
+const HANGUP: Token = Token(usize::MAX);
+
+// Request the lines on each chip, registering the requests with the poll using
+// the index of the chip as the token.
+fn request_lines(
+    opts: &Opts,
+    r: &common::Resolver,
+    line_cfgs: &HashMap<String, line::Config>,
+    consumer: &str,
+    registry: &Registry,
+) -> anyhow::Result<Vec<Request>> {
+    let mut reqs = Vec::new();
+    for (idx, ci) in r.chips.iter().enumerate() {
+        let mut cfg = Config::default();
+        // both edges unless otherwise configured
+        cfg.with_edge_detection(EdgeDetection::BothEdges);
+        let chip_lines: Vec<(&String, Offset)> = r
+            .lines
+            .iter()
+            .filter(|(_id, co)| co.chip_idx == idx)
+            .map(|(id, co)| (id, co.offset))
+            .collect();
+        for (id, offset) in &chip_lines {
+            cfg.with_line(*offset);
+            if let Some(lc) = line_cfgs.get(*id) {
+                cfg.from_line_config(lc);
+            }
+        }
+        let offsets: Vec<Offset> = chip_lines.iter().map(|(_id, offset)| *offset).collect();
+        // command line options override any config file
+        cfg.with_lines(&offsets);
+        opts.apply(&mut cfg);
+        let mut bld = Request::from_config(cfg);
+        bld.on_chip(&ci.path).with_consumer(consumer);
+        #[cfg(feature = "uapi_v2")]
+        if let Some(size) = opts.event_buffer_size {
+            bld.with_kernel_event_buffer_size(size);
+        }
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        bld.using_abi_version(r.abiv);
+        let req = bld.request().map_err(|e| {
+            anyhow!(e).context(format!(
+                "failed to request lines {:?} from {}",
+                offsets, ci.name
+            ))
+        })?;
+        registry
+            .register(
+                &mut SourceFd(&req.as_raw_fd()),
+                Token(idx),
+                Interest::READABLE,
+            )
+            .map_err(|e| {
+                anyhow!(e).context(format!(
+                    "failed register {:?} from {} with poll",
+                    offsets, ci.name
+                ))
+            })?;
+        reqs.push(req);
+    }
+    Ok(reqs)
+}
+
+// Resolve and request the lines again, including any lines read on each event.
+fn reattach_lines(
+    opts: &Opts,
+    registry: &Registry,
+) -> anyhow::Result<(common::Resolver, Vec<Request>, Option<EventReader>)> {
+    let ResolvedLines {
+        mut r,
+        line_cfgs,
+        consumer,
+        ..
+    } = resolve_lines(opts)?;
+    if !r.errors.is_empty() {
+        return Err(r.errors.remove(0));
+    }
+    let consumer = opts.consumer(consumer.as_deref());
+    let reqs = request_lines(opts, &r, &line_cfgs, consumer, registry)?;
+    let reader = match EventReader::resolve(opts)? {
+        Some((ids, mut rr)) => {
+            if !rr.errors.is_empty() {
+                return Err(rr.errors.remove(0));
+            }
+            Some(EventReader::new(opts, ids, &rr, &r, consumer)?)
+        }
+        None => None,
+    };
+    Ok((r, reqs, reader))
+}
+
 #[derive(Default)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 struct CmdResult {
@@ -298,6 +724,8 @@ struct CmdResult {
     opts: EmitOpts,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     errors: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    chip_removed: bool,
 }
 impl CmdResult {
     fn emit(&self) {
@@ -312,39 +740,118 @@ impl CmdResult {
     }
 
     fn push_error(&mut self, e: &anyhow::Error) {
+        self.chip_removed |= common::is_chip_removed(e);
         self.errors.push(format_error(&self.opts, e))
     }
 }
 
-fn emit_banner(opts: &Opts) {
+// The destination for the edge events.
+struct Output {
+    // the file to write the events to, instead of stdout.
+    file: Option<(PathBuf, BufWriter<File>)>,
+}
+
+impl Output {
+    fn new(path: Option<&PathBuf>) -> anyhow::Result<Output> {
+        let file = match path {
+            Some(path) => {
+                let f = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| {
+                        anyhow!(e).context(format!("failed to open '{}'", path.display()))
+                    })?;
+                Some((path.clone(), BufWriter::new(f)))
+            }
+            None => None,
+        };
+        Ok(Output { file })
+    }
+
+    // Write a line, reporting rather than returning any error so monitoring can continue.
+    fn write_line(&mut self, line: &str, opts: &EmitOpts) {
+        match &mut self.file {
+            Some((path, f)) => {
+                if let Err(e) = writeln!(f, "{}", line).and_then(|_| f.flush()) {
+                    emit_error(
+                        opts,
+                        &anyhow!(e).context(format!("failed to write to '{}'", path.display())),
+                    );
+                }
+            }
+            None => println!("{}", line),
+        }
+    }
+}
+
+fn emit_banner(opts: &Opts, lines: &[String]) {
     if !opts.banner {
         return;
     }
-    print_banner(&opts.lines)
+    common::print_banner("Monitoring", lines)
 }
 
-fn print_banner(lines: &[String]) {
-    use std::io::Write;
-
-    if lines.len() > 1 {
-        print!("Monitoring lines ");
+// The lines to monitor, and their configuration if read from a config file.
+struct ResolvedLines {
+    ids: Vec<String>,
+    r: common::Resolver,
+    line_cfgs: HashMap<String, line::Config>,
+    consumer: Option<String>,
+}
 
-        for l in lines.iter().take(lines.len() - 1) {
-            print!("'{}', ", l);
+fn resolve_lines(opts: &Opts) -> anyhow::Result<ResolvedLines> {
+    #[cfg(feature = "config")]
+    if let Some(path) = &opts.config {
+        let cf = ConfigFile::from_path(path)?;
+        let mut ids = Vec::new();
+        let mut line_cfgs = HashMap::new();
+        for (id, mut lc) in cf.line_configs(Direction::Input)? {
+            if lc.edge_detection.is_none() {
+                lc.with_edge_detection(EdgeDetection::BothEdges);
+            }
+            ids.push(id.clone());
+            line_cfgs.insert(id, lc);
         }
+        let id_refs: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+        let r = cf.resolve(&id_refs, &opts.line_opts, &opts.uapi_opts);
+        return Ok(ResolvedLines {
+            ids,
+            r,
+            line_cfgs,
+            consumer: cf.consumer,
+        });
+    }
+    let ids = common::Resolver::expand_lines(&opts.lines, &opts.line_opts, &opts.uapi_opts)?;
+    let r = common::Resolver::resolve_lines(&ids, &opts.line_opts, &opts.uapi_opts);
+    Ok(ResolvedLines {
+        ids,
+        r,
+        line_cfgs: HashMap::new(),
+        consumer: None,
+    })
+}
 
-        println!("and '{}'...", lines[lines.len() - 1]);
-    } else {
-        println!("Monitoring line '{}'...", lines[0]);
+fn emit_lost_events(edge: &EdgeEvent, prev: &Option<EdgeEvent>, ci: &ChipInfo) {
+    if let Some(lost) = edge.lost_since(prev.as_ref()) {
+        if lost > 0 {
+            eprintln!("warning: {} events lost on {}", lost, ci.name);
+        }
     }
-    _ = std::io::stdout().flush();
 }
 
-fn emit_edge(edge: EdgeEvent, ci: &ChipInfo, opts: &Opts, timefmt: &TimeFmt) {
+fn emit_edge(
+    edge: EdgeEvent,
+    values: Option<ReadValues>,
+    ci: &ChipInfo,
+    opts: &Opts,
+    timefmt: &TimeFmt,
+    output: &mut Output,
+) {
     if opts.quiet {
         return;
     }
-    let timestamp = format_time(edge.timestamp_ns, timefmt);
+    let timestamp = format_timestamp(edge.timestamp(), timefmt);
     let line_name = ci.line_name(&edge.offset).map(|x| x.into());
     let event = Event {
         #[cfg(feature = "json")]
@@ -352,17 +859,28 @@ fn emit_edge(edge: EdgeEvent, ci: &ChipInfo, opts: &Opts, timefmt: &TimeFmt) {
         name: line_name,
         edge,
         timestamp,
+        values,
     };
 
     #[cfg(feature = "json")]
     if opts.emit.json {
-        println!("{}", serde_json::to_string(&event).unwrap());
-        return;
+        return output.write_line(&serde_json::to_string(&event).unwrap(), &opts.emit);
     }
     if let Some(format) = &opts.format {
-        return print_edge_formatted(&event.edge, format, ci);
+        return output.write_line(&format_edge(&event.edge, format, ci), &opts.emit);
     }
-    event.print(ci, opts);
+    output.write_line(&event.to_text(ci, opts), &opts.emit);
+}
+
+// Emit the edge interval statistics accumulated since the previous report.
+fn emit_stats(stats: &mut Stats, opts: &Opts, output: &mut Output) {
+    let summaries = stats.report();
+    #[cfg(feature = "json")]
+    if opts.emit.json {
+        let report = serde_json::json!({ "stats": summaries });
+        return output.write_line(&report.to_string(), &opts.emit);
+    }
+    output.write_line(&stats::format_table(&summaries), &opts.emit);
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize))]
@@ -375,27 +893,41 @@ struct Event {
     #[cfg_attr(feature = "serde", serde(flatten))]
     edge: EdgeEvent,
     timestamp: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    values: Option<ReadValues>,
 }
 
 impl Event {
-    fn print(&self, ci: &ChipInfo, opts: &Opts) {
-        print!(
+    fn to_text(&self, ci: &ChipInfo, opts: &Opts) -> String {
+        let mut text = format!(
             "{}\t{:09}\t",
             self.timestamp,
             event_kind_name(self.edge.kind)
         );
+        // seqnos are only provided by uAPI v2, so zero if unavailable.
+        if (opts.seqno || opts.emit.verbose > 0) && self.edge.seqno != 0 {
+            _ = write!(
+                text,
+                "seqno={} line_seqno={}\t",
+                self.edge.seqno, self.edge.line_seqno
+            );
+        }
         if let Some(lname) = &self.name {
             if opts.line_opts.chip.is_some() {
-                print!("{} {} ", ci.name, self.edge.offset);
+                _ = write!(text, "{} {} ", ci.name, self.edge.offset);
             }
             if opts.emit.quoted {
-                println!("\"{}\"", lname);
+                _ = write!(text, "\"{}\"", lname);
             } else {
-                println!("{}", lname);
+                text.push_str(lname);
             }
         } else {
-            println!("{} {}", ci.name, self.edge.offset);
+            _ = write!(text, "{} {}", ci.name, self.edge.offset);
         }
+        if let Some(values) = &self.values {
+            _ = write!(text, "\t{}", values.to_text(opts.emit.quoted));
+        }
+        text
     }
 }
 
@@ -413,32 +945,36 @@ fn event_kind_num(kind: EdgeKind) -> u8 {
     }
 }
 
-fn print_edge_formatted(event: &EdgeEvent, format: &str, ci: &ChipInfo) {
+fn format_edge(event: &EdgeEvent, format: &str, ci: &ChipInfo) -> String {
+    let mut text = String::new();
     let mut escaped = false;
 
     for chr in format.chars() {
         if escaped {
             match chr {
-                '%' => print!("%"),
-                'c' => print!("{}", ci.name),
-                'e' => print!("{}", event_kind_num(event.kind)),
-                'E' => print!("{}", event_kind_name(event.kind)),
-                'l' => print!("{}", ci.line_name(&event.offset).unwrap_or("unnamed")),
-                'L' => print!("{}", format_time(event.timestamp_ns, &TimeFmt::Localtime)),
-                'o' => print!("{}", event.offset),
-                'S' => print!("{}", format_time(event.timestamp_ns, &TimeFmt::Seconds)),
-                'U' => print!("{}", format_time(event.timestamp_ns, &TimeFmt::Utc)),
-                x => print!("%{}", x),
+                '%' => text.push('%'),
+                'c' => text.push_str(&ci.name),
+                'e' => text.push_str(&event_kind_num(event.kind).to_string()),
+                'E' => text.push_str(event_kind_name(event.kind)),
+                'l' => text.push_str(ci.line_name(&event.offset).unwrap_or("unnamed")),
+                'L' => text.push_str(&format_timestamp(event.timestamp(), &TimeFmt::Localtime)),
+                'o' => text.push_str(&event.offset.to_string()),
+                'S' => text.push_str(&format_timestamp(event.timestamp(), &TimeFmt::Seconds)),
+                'U' => text.push_str(&format_timestamp(event.timestamp(), &TimeFmt::Utc)),
+                x => {
+                    text.push('%');
+                    text.push(x);
+                }
             }
             escaped = false;
         } else if chr == '%' {
             escaped = true;
         } else {
-            print!("{}", chr);
+            text.push(chr);
         }
     }
     if escaped {
-        print!("%");
+        text.push('%');
     }
-    println!();
+    text
 }
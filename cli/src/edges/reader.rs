@@ -0,0 +1,221 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Opts;
+use crate::common::Resolver;
+use anyhow::{anyhow, Result};
+use gpiocdev::line::{Offset, Value, Values};
+use gpiocdev::request::{Config, Request};
+use std::path::PathBuf;
+
+// Where the value of a line is read from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Source {
+    // the request for the monitored lines on the chip with the given index
+    Monitored(usize),
+    // the auxiliary input request with the given index
+    Aux(usize),
+}
+
+// A line read on each event.
+struct ReadLine {
+    id: String,
+    source: Source,
+    offset: Offset,
+}
+
+// Reads the values of lines when events are received.
+pub(super) struct EventReader {
+    // the lines to read, in the order they are reported
+    lines: Vec<ReadLine>,
+    // the input requests for lines that are not monitored
+    aux: Vec<Request>,
+}
+
+impl EventReader {
+    // Resolve the lines to read on each event.
+    //
+    // Returns None if there are no lines to read.
+    pub(super) fn resolve(opts: &Opts) -> Result<Option<(Vec<String>, Resolver)>> {
+        if opts.read_on_event.is_empty() {
+            return Ok(None);
+        }
+        let mut ids =
+            Resolver::expand_lines(&opts.read_on_event, &opts.line_opts, &opts.uapi_opts)?;
+        let mut seen = Vec::new();
+        ids.retain(|id| {
+            if seen.contains(id) {
+                return false;
+            }
+            seen.push(id.clone());
+            true
+        });
+        let r = Resolver::resolve_lines(&ids, &opts.line_opts, &opts.uapi_opts);
+        Ok(Some((ids, r)))
+    }
+
+    // Create the reader for the resolved lines.
+    //
+    // Lines that are monitored are read from the monitoring request, while the
+    // remaining lines are requested as inputs.
+    pub(super) fn new(
+        opts: &Opts,
+        ids: Vec<String>,
+        rr: &Resolver,
+        monitored: &Resolver,
+        consumer: &str,
+    ) -> Result<EventReader> {
+        let mut lines = Vec::new();
+        let mut aux_lines: Vec<(&PathBuf, Vec<Offset>)> = Vec::new();
+        for id in ids {
+            let co = rr.lines.get(&id).unwrap();
+            let path = &rr.chips[co.chip_idx].path;
+            let monitored_idx = monitored.chips.iter().position(|ci| &ci.path == path);
+            let is_monitored = monitored_idx.is_some_and(|idx| {
+                monitored
+                    .lines
+                    .values()
+                    .any(|mco| mco.chip_idx == idx && mco.offset == co.offset)
+            });
+            let source = if is_monitored {
+                Source::Monitored(monitored_idx.unwrap())
+            } else {
+                let aux_idx = match aux_lines.iter().position(|(p, _)| *p == path) {
+                    Some(idx) => idx,
+                    None => {
+                        aux_lines.push((path, Vec::new()));
+                        aux_lines.len() - 1
+                    }
+                };
+                let offsets = &mut aux_lines[aux_idx].1;
+                if !offsets.contains(&co.offset) {
+                    offsets.push(co.offset);
+                }
+                Source::Aux(aux_idx)
+            };
+            lines.push(ReadLine {
+                id,
+                source,
+                offset: co.offset,
+            });
+        }
+        let mut aux = Vec::new();
+        for (path, offsets) in aux_lines {
+            let mut cfg = Config::default();
+            cfg.with_lines(&offsets).as_input();
+            opts.active_low_opts.apply(&mut cfg);
+            opts.bias_opts.apply(&mut cfg);
+            let mut bld = Request::from_config(cfg);
+            bld.on_chip(path).with_consumer(consumer);
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            bld.using_abi_version(rr.abiv);
+            let req = bld.request().map_err(|e| {
+                anyhow!(e).context(format!(
+                    "failed to request lines {:?} from {}",
+                    offsets,
+                    path.display()
+                ))
+            })?;
+            aux.push(req);
+        }
+        Ok(EventReader { lines, aux })
+    }
+
+    // Read the values of the lines.
+    //
+    // Each request is read once, so lines from the same request are read together.
+    pub(super) fn read(&self, monitored: &[Request]) -> Result<ReadValues> {
+        let mut read: Vec<(Source, Values)> = Vec::new();
+        let mut values = Vec::with_capacity(self.lines.len());
+        for line in &self.lines {
+            let idx = match read.iter().position(|(s, _)| *s == line.source) {
+                Some(idx) => idx,
+                None => {
+                    let req = match line.source {
+                        Source::Monitored(idx) => &monitored[idx],
+                        Source::Aux(idx) => &self.aux[idx],
+                    };
+                    let mut vals = Values::default();
+                    req.values(&mut vals)
+                        .map_err(|e| anyhow!(e).context("failed to read line values"))?;
+                    read.push((line.source, vals));
+                    read.len() - 1
+                }
+            };
+            values.push((line.id.clone(), read[idx].1.get(line.offset)));
+        }
+        Ok(ReadValues(values))
+    }
+}
+
+// The values of the lines read when an event was received, in the order
+// the lines were listed.
+pub(super) struct ReadValues(Vec<(String, Option<Value>)>);
+
+impl ReadValues {
+    pub(super) fn to_text(&self, quoted: bool) -> String {
+        self.0
+            .iter()
+            .map(|(id, value)| {
+                let value = match value {
+                    Some(v) => v.to_string(),
+                    None => "unknown".to_string(),
+                };
+                if quoted {
+                    format!("\"{}\"={}", id, value)
+                } else {
+                    format!("{}={}", id, value)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ReadValues {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut s = serializer.serialize_map(Some(self.0.len()))?;
+        for (id, value) in &self.0 {
+            s.serialize_entry(id, value)?;
+        }
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> ReadValues {
+        ReadValues(vec![
+            ("D1".to_string(), Some(Value::Active)),
+            ("D0".to_string(), Some(Value::Inactive)),
+            ("5".to_string(), None),
+        ])
+    }
+
+    #[test]
+    fn to_text() {
+        assert_eq!(values().to_text(false), "D1=active D0=inactive 5=unknown");
+        assert_eq!(
+            values().to_text(true),
+            "\"D1\"=active \"D0\"=inactive \"5\"=unknown"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn to_json() {
+        // the lines retain their listed order
+        assert_eq!(
+            serde_json::to_string(&values()).unwrap(),
+            r#"{"D1":"Active","D0":"Inactive","5":null}"#
+        );
+    }
+}
@@ -0,0 +1,354 @@
+// SPDX-FileCopyrightText: 2021 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::event_kind_name;
+use crate::common::ChipOffset;
+use gpiocdev::line::{EdgeKind, Offset};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+// The statistics of the intervals between consecutive edges of one kind on a line.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Intervals {
+    count: u64,
+    min_ns: u64,
+    max_ns: u64,
+    // the sum of the intervals, which is wide enough to never overflow.
+    total_ns: u128,
+}
+
+impl Intervals {
+    fn add(&mut self, ns: u64) {
+        if self.count == 0 || ns < self.min_ns {
+            self.min_ns = ns;
+        }
+        if ns > self.max_ns {
+            self.max_ns = ns;
+        }
+        self.count += 1;
+        self.total_ns += ns as u128;
+    }
+
+    fn mean_ns(&self) -> u64 {
+        (self.total_ns / self.count as u128) as u64
+    }
+}
+
+// The edge statistics for a line, indexed by edge kind.
+#[derive(Default)]
+struct LineStats {
+    // the timestamp of the most recent edge of each kind
+    last_ns: [Option<u64>; 2],
+    intervals: [Intervals; 2],
+}
+
+fn kind_idx(kind: EdgeKind) -> usize {
+    match kind {
+        EdgeKind::Rising => 0,
+        EdgeKind::Falling => 1,
+    }
+}
+
+// Accumulates the intervals between consecutive edges of each kind on each line.
+pub(super) struct Stats {
+    // the lines, in reporting order, identified by chip index and offset.
+    lines: Vec<(String, usize, Offset)>,
+    stats: Vec<LineStats>,
+}
+
+impl Stats {
+    pub(super) fn new(lines: Vec<(String, usize, Offset)>) -> Stats {
+        let stats = lines.iter().map(|_| LineStats::default()).collect();
+        Stats { lines, stats }
+    }
+
+    // Add an edge to the statistics for the line.
+    //
+    // The first edge of each kind only provides the start of the first interval.
+    pub(super) fn add(
+        &mut self,
+        chip_idx: usize,
+        offset: Offset,
+        kind: EdgeKind,
+        timestamp_ns: u64,
+    ) {
+        let Some(idx) = self
+            .lines
+            .iter()
+            .position(|(_, ci, o)| *ci == chip_idx && *o == offset)
+        else {
+            return;
+        };
+        let ls = &mut self.stats[idx];
+        let kind = kind_idx(kind);
+        // the realtime clock may be stepped backwards, so such intervals are skipped.
+        if let Some(interval) = ls.last_ns[kind].and_then(|last| timestamp_ns.checked_sub(last)) {
+            ls.intervals[kind].add(interval);
+        }
+        ls.last_ns[kind] = Some(timestamp_ns);
+    }
+
+    // Update the location of the lines after they have been reattached.
+    //
+    // The last edges are discarded, as the lines were not monitored while detached,
+    // but the intervals since the previous report are retained.
+    pub(super) fn relocate(&mut self, lines: &HashMap<String, ChipOffset>) {
+        for ((id, chip_idx, offset), ls) in self.lines.iter_mut().zip(self.stats.iter_mut()) {
+            if let Some(co) = lines.get(id) {
+                *chip_idx = co.chip_idx;
+                *offset = co.offset;
+            }
+            ls.last_ns = Default::default();
+        }
+    }
+
+    // Return the summary of the intervals since the previous report.
+    //
+    // The intervals are reset, but the last edges are retained so intervals
+    // spanning reports are included in the next.
+    pub(super) fn report(&mut self) -> Vec<Summary> {
+        let mut summaries = Vec::new();
+        for ((id, _, _), ls) in self.lines.iter().zip(self.stats.iter_mut()) {
+            for kind in [EdgeKind::Rising, EdgeKind::Falling] {
+                let intervals = std::mem::take(&mut ls.intervals[kind_idx(kind)]);
+                let mut summary = Summary {
+                    line: id.clone(),
+                    edge: event_kind_name(kind),
+                    count: intervals.count,
+                    min_ns: None,
+                    mean_ns: None,
+                    max_ns: None,
+                };
+                if intervals.count > 0 {
+                    summary.min_ns = Some(intervals.min_ns);
+                    summary.mean_ns = Some(intervals.mean_ns());
+                    summary.max_ns = Some(intervals.max_ns);
+                }
+                summaries.push(summary);
+            }
+        }
+        summaries
+    }
+}
+
+// The statistics for the intervals between edges of one kind on a line.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub(super) struct Summary {
+    line: String,
+    edge: &'static str,
+    count: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    min_ns: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    mean_ns: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    max_ns: Option<u64>,
+}
+
+// Format the summaries as a table, one row per line and edge kind.
+pub(super) fn format_table(summaries: &[Summary]) -> String {
+    let width = summaries
+        .iter()
+        .map(|s| s.line.len())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap();
+    let format_ns = |ns: Option<u64>| match ns {
+        Some(ns) => format!("{:?}", Duration::from_nanos(ns)),
+        None => "-".to_string(),
+    };
+    let mut text = format!(
+        "{:width$}  {:7}  {:>8}  {:>12}  {:>12}  {:>12}",
+        "line", "edge", "count", "min", "mean", "max"
+    );
+    for s in summaries {
+        _ = write!(
+            text,
+            "\n{:width$}  {:7}  {:>8}  {:>12}  {:>12}  {:>12}",
+            s.line,
+            s.edge,
+            s.count,
+            format_ns(s.min_ns),
+            format_ns(s.mean_ns),
+            format_ns(s.max_ns)
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> Stats {
+        Stats::new(vec![("GPIO17".into(), 0, 17), ("GPIO22".into(), 1, 22)])
+    }
+
+    fn summary(line: &str, edge: &'static str, count: u64, ns: Option<(u64, u64, u64)>) -> Summary {
+        Summary {
+            line: line.into(),
+            edge,
+            count,
+            min_ns: ns.map(|ns| ns.0),
+            mean_ns: ns.map(|ns| ns.1),
+            max_ns: ns.map(|ns| ns.2),
+        }
+    }
+
+    #[test]
+    fn intervals() {
+        let mut s = stats();
+        // a 1kHz square wave with some jitter on GPIO17
+        for (ts, kind) in [
+            (1_000_000, EdgeKind::Rising),
+            (1_500_000, EdgeKind::Falling),
+            (2_000_100, EdgeKind::Rising),
+            (2_500_000, EdgeKind::Falling),
+            (2_999_900, EdgeKind::Rising),
+            (3_500_000, EdgeKind::Falling),
+        ] {
+            s.add(0, 17, kind, ts);
+        }
+        assert_eq!(
+            s.report(),
+            vec![
+                summary("GPIO17", "rising", 2, Some((999_800, 999_950, 1_000_100))),
+                summary(
+                    "GPIO17",
+                    "falling",
+                    2,
+                    Some((1_000_000, 1_000_000, 1_000_000))
+                ),
+                summary("GPIO22", "rising", 0, None),
+                summary("GPIO22", "falling", 0, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn relocate() {
+        let mut s = stats();
+        s.add(0, 17, EdgeKind::Rising, 1_000);
+        s.add(0, 17, EdgeKind::Rising, 3_000);
+        s.relocate(&HashMap::from([
+            (
+                "GPIO17".to_string(),
+                ChipOffset {
+                    chip_idx: 1,
+                    offset: 4,
+                },
+            ),
+            (
+                "GPIO22".to_string(),
+                ChipOffset {
+                    chip_idx: 0,
+                    offset: 22,
+                },
+            ),
+        ]));
+        // the old location is ignored
+        s.add(0, 17, EdgeKind::Rising, 4_000);
+        // the edge before the relocation does not start an interval
+        s.add(1, 4, EdgeKind::Rising, 1_000_000);
+        s.add(1, 4, EdgeKind::Rising, 1_002_000);
+        s.add(0, 22, EdgeKind::Falling, 7_000);
+        s.add(0, 22, EdgeKind::Falling, 8_000);
+        let r = s.report();
+        assert_eq!(
+            r[0],
+            summary("GPIO17", "rising", 2, Some((2_000, 2_000, 2_000)))
+        );
+        assert_eq!(
+            r[3],
+            summary("GPIO22", "falling", 1, Some((1_000, 1_000, 1_000)))
+        );
+    }
+
+    #[test]
+    fn first_event() {
+        let mut s = stats();
+        s.add(1, 22, EdgeKind::Rising, 5_000);
+        s.add(1, 22, EdgeKind::Falling, 6_000);
+        let r = s.report();
+        assert_eq!(r[2], summary("GPIO22", "rising", 0, None));
+        assert_eq!(r[3], summary("GPIO22", "falling", 0, None));
+    }
+
+    #[test]
+    fn intervals_span_reports() {
+        let mut s = stats();
+        s.add(0, 17, EdgeKind::Rising, 1_000);
+        s.add(0, 17, EdgeKind::Rising, 3_000);
+        assert_eq!(
+            s.report()[0],
+            summary("GPIO17", "rising", 1, Some((2_000, 2_000, 2_000)))
+        );
+        // the counts are reset...
+        assert_eq!(s.report()[0], summary("GPIO17", "rising", 0, None));
+        // ...but the last edge is retained.
+        s.add(0, 17, EdgeKind::Rising, 7_000);
+        assert_eq!(
+            s.report()[0],
+            summary("GPIO17", "rising", 1, Some((4_000, 4_000, 4_000)))
+        );
+    }
+
+    #[test]
+    fn large_timestamps() {
+        let mut s = stats();
+        let start = u64::MAX - 3 * 1_000_000_000;
+        for i in 0..4 {
+            s.add(0, 17, EdgeKind::Rising, start + i * 1_000_000_000);
+        }
+        let ns = 1_000_000_000;
+        assert_eq!(
+            s.report()[0],
+            summary("GPIO17", "rising", 3, Some((ns, ns, ns)))
+        );
+
+        // the sum of the intervals may exceed u64
+        let mut i = Intervals::default();
+        i.add(u64::MAX);
+        i.add(u64::MAX - 2);
+        assert_eq!(i.mean_ns(), u64::MAX - 1);
+    }
+
+    #[test]
+    fn timestamp_stepped_backwards() {
+        let mut s = stats();
+        s.add(0, 17, EdgeKind::Rising, 10_000);
+        s.add(0, 17, EdgeKind::Rising, 4_000);
+        s.add(0, 17, EdgeKind::Rising, 5_000);
+        assert_eq!(
+            s.report()[0],
+            summary("GPIO17", "rising", 1, Some((1_000, 1_000, 1_000)))
+        );
+    }
+
+    #[test]
+    fn unknown_line() {
+        let mut s = stats();
+        s.add(0, 22, EdgeKind::Rising, 1_000);
+        s.add(0, 22, EdgeKind::Rising, 2_000);
+        assert!(s.report().iter().all(|s| s.count == 0));
+    }
+
+    #[test]
+    fn table() {
+        let summaries = vec![
+            summary("GPIO17", "rising", 2, Some((999_800, 999_950, 1_000_100))),
+            summary("GPIO17", "falling", 0, None),
+        ];
+        assert_eq!(
+            format_table(&summaries),
+            "line    edge        count           min          mean           max\n\
+             GPIO17  rising          2       999.8µs      999.95µs      1.0001ms\n\
+             GPIO17  falling         0             -             -             -"
+        );
+    }
+}
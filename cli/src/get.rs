@@ -4,13 +4,17 @@
 
 use super::common::{self, format_error, EmitOpts};
 use anyhow::anyhow;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use gpiocdev::line::{Offset, Value, Values};
 use gpiocdev::request::{Config, Request};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::io::Write;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Parser)]
 #[command(alias("g"))]
@@ -19,6 +23,9 @@ pub struct Opts {
     ///
     /// The lines are identified by name or optionally by offset
     /// if the --chip option is provided.
+    ///
+    /// With --chip, offset ranges such as 3-9, and the keyword all for
+    /// all lines on the chip, may also be used.
     #[arg(value_name = "line", required = true)]
     line: Vec<String>,
 
@@ -34,6 +41,22 @@ pub struct Opts {
     #[arg(short, long)]
     as_is: bool,
 
+    /// Report the values from the line info, without requesting the lines
+    ///
+    /// This provides a passive view of lines held by other processes, which
+    /// cannot be requested, but the value can only be determined for output
+    /// lines, and only if the kernel reports the output values in the line info.
+    /// That requires uAPI v2, and is not supported by current mainline kernels.
+    /// Otherwise the value is reported as unknown.
+    ///
+    /// As the lines are not requested, options that configure the lines, or
+    /// hold the request, do not apply.
+    #[arg(
+        long,
+        conflicts_with_all = ["as_is", "active_low", "bias", "hold_period", "held", "linger"]
+    )]
+    unrequested: bool,
+
     #[command(flatten)]
     active_low_opts: common::ActiveLowOpts,
 
@@ -42,16 +65,105 @@ pub struct Opts {
 
     /// Wait between requesting the lines and reading the values
     ///
-    /// This provides time for any bias setting to take effect.
+    /// This provides time for any bias setting to take effect, such as a pull
+    /// charging the line.
     ///
     /// The period is taken as milliseconds unless otherwise specified.
-    #[arg(short = 'p', long, value_name = "period", value_parser = common::parse_duration)]
+    #[arg(
+        short = 'p',
+        long,
+        visible_alias = "settling-time",
+        value_name = "period",
+        value_parser = common::parse_duration
+    )]
     hold_period: Option<Duration>,
 
+    /// Keep the lines requested and re-read the values each time Enter is pressed
+    ///
+    /// The values are read and displayed on startup, then again for each line
+    /// read from stdin.
+    /// The command exits when stdin is closed.
+    ///
+    /// Holding the request avoids repeatedly requesting and releasing the lines,
+    /// which may disturb the line state, such as bias, on some hardware.
+    #[arg(long, group = "held")]
+    hold: bool,
+
+    /// Keep the lines requested and display timestamped values every --interval
+    ///
+    /// Each row of values is prefixed with the time since the first row, in seconds.
+    /// The command continues until interrupted, when the lines are released.
+    #[arg(long, group = "held", requires = "interval")]
+    watch: bool,
+
+    /// Keep the lines requested for a period after the values are displayed
+    ///
+    /// The lines, and so any bias, remain applied while subsequent measurements
+    /// are made, rather than being released as soon as the values are read.
+    /// The lines are released early if the command is interrupted.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(
+        long,
+        value_name = "period",
+        group = "linger",
+        conflicts_with = "held",
+        value_parser = common::parse_duration
+    )]
+    hold_for: Option<Duration>,
+
+    /// Keep the lines requested after the values are displayed, until interrupted
+    ///
+    /// The lines are released when a SIGINT or SIGTERM is received.
+    #[arg(long, group = "linger", conflicts_with = "held")]
+    hold_until_signal: bool,
+
+    /// With --hold, re-read the values periodically rather than on Enter
+    ///
+    /// With --watch, the period between rows.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(long, value_name = "period", requires = "held", value_parser = common::parse_duration)]
+    interval: Option<Duration>,
+
     /// Display line values as '0' (inactive) or '1' (active)
+    ///
+    /// The values are displayed in line order, without the line names.
     #[arg(long, group = "emit")]
     pub numeric: bool,
 
+    /// Display line values as 'active' or 'inactive', without the line names
+    ///
+    /// The values are displayed in line order, space separated.
+    #[arg(long, group = "emit")]
+    pub values_only: bool,
+
+    /// The format of the samples emitted by --watch
+    ///
+    /// The raw format writes a fixed size binary record per sample to stdout,
+    /// for efficiently piping samples into another program.
+    ///
+    /// Each record is 8 + ceil(num_lines / 8) bytes:
+    ///   bytes 0-7   the time the sample was taken, in nanoseconds from
+    ///               CLOCK_MONOTONIC, as a little-endian u64
+    ///   bytes 8..   the line values as a packed bitfield, in the order the
+    ///               lines were specified, ignoring any repeated lines
+    ///
+    /// The value of the nth line is bit (n % 8) of byte 8 + (n / 8), where bit 0
+    /// is the least significant bit, and is 1 if the line is active and 0 if
+    /// inactive. Any unused bits in the final byte are 0.
+    #[arg(
+        long,
+        value_name = "format",
+        group = "emit",
+        requires = "watch",
+        verbatim_doc_comment
+    )]
+    format: Option<OutputFormat>,
+
+    #[command(flatten)]
+    retry_opts: common::RetryOpts,
+
     #[command(flatten)]
     uapi_opts: common::UapiOpts,
 
@@ -63,6 +175,14 @@ pub struct Opts {
     emit: common::EmitOpts,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Timestamped rows of text, as per the other output options
+    Text,
+    /// Fixed size binary records
+    Raw,
+}
+
 impl Opts {
     // mutate the config to match the configuration
     fn apply(&self, config: &mut Config) {
@@ -75,81 +195,322 @@ impl Opts {
 }
 
 pub fn cmd(opts: &Opts) -> bool {
-    let res = do_cmd(opts);
+    if opts.unrequested {
+        return read_unrequested(opts);
+    }
+    let mut res = CmdResult {
+        ..Default::default()
+    };
+    // signals are caught before the values are emitted, so a signal sent once
+    // the values have been seen releases the lines cleanly.
+    let interrupted = if opts.hold_for.is_some() || opts.hold_until_signal {
+        match signal_channel() {
+            Ok(rx) => Some(rx),
+            Err(e) => {
+                res.push_error(&opts.emit, &e);
+                res.emit(opts);
+                return false;
+            }
+        }
+    } else {
+        None
+    };
+    let getter = Getter::new(opts, &mut res);
+    if let Some(period) = opts.hold_period {
+        thread::sleep(period);
+    }
+    if opts.watch && res.errors.is_empty() {
+        return getter.watch(opts);
+    }
+    getter.read_values(opts, &mut res);
     res.emit(opts);
-    res.errors.is_empty()
+    if !res.errors.is_empty() {
+        return false;
+    }
+    if opts.hold {
+        return getter.hold(opts);
+    }
+    if let Some(rx) = interrupted {
+        getter.linger(opts, &rx);
+    }
+    true
 }
 
-fn do_cmd(opts: &Opts) -> CmdResult {
-    let mut res = CmdResult {
-        ..Default::default()
+// Create a channel that receives a message when a SIGINT or SIGTERM is received.
+fn signal_channel() -> anyhow::Result<mpsc::Receiver<()>> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])
+        .map_err(|e| anyhow!(e).context("failed to handle signals"))?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            _ = tx.send(());
+        }
+    });
+    Ok(rx)
+}
+
+// The requested lines, which remain requested for the lifetime of the Getter.
+#[derive(Default)]
+struct Getter {
+    // the requested lines, with any globs expanded
+    lines: Vec<String>,
+    r: common::Resolver,
+    requests: Vec<Option<Request>>,
+}
+
+// Expand and resolve the lines, recording any errors in the result.
+fn resolve(opts: &Opts, res: &mut CmdResult) -> Option<(Vec<String>, common::Resolver)> {
+    let lines = match common::Resolver::expand_lines(&opts.line, &opts.line_opts, &opts.uapi_opts) {
+        Ok(lines) => lines,
+        Err(e) => {
+            res.push_error(&opts.emit, &e);
+            return None;
+        }
     };
-    let r = common::Resolver::resolve_lines(&opts.line, &opts.line_opts, &opts.uapi_opts);
+    res.lines = lines.clone();
+    let r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
     for e in &r.errors {
         res.push_error(&opts.emit, e);
     }
-    let mut requests = Vec::new();
-    for (idx, ci) in r.chips.iter().enumerate() {
-        let mut cfg = Config::default();
-        opts.apply(&mut cfg);
-        let offsets: Vec<Offset> = r
-            .lines
-            .values()
-            .filter(|co| co.chip_idx == idx)
-            .map(|co| co.offset)
-            .collect();
-        cfg.with_lines(&offsets);
-
-        let mut bld = Request::from_config(cfg);
-        bld.on_chip(&ci.path).with_consumer(&opts.consumer);
-        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-        bld.using_abi_version(r.abiv);
-        match bld.request() {
-            Ok(req) => {
-                requests.push(Some(req));
-            }
-            Err(e) => {
-                requests.push(None);
-                res.push_error(
-                    &opts.emit,
-                    &anyhow!(e).context(format!(
-                        "failed to request lines {:?} from {}",
-                        offsets, ci.name
-                    )),
-                );
+    Some((lines, r))
+}
+
+// Read and emit the output values reported in the line info, without requesting the lines.
+fn read_unrequested(opts: &Opts) -> bool {
+    let mut res = CmdResult::default();
+    if let Some((_, r)) = resolve(opts, &mut res) {
+        for (idx, ci) in r.chips.iter().enumerate() {
+            let chip = match common::chip_from_path(&ci.path, r.abiv) {
+                Ok(chip) => chip,
+                Err(e) => {
+                    res.push_error(&opts.emit, &e);
+                    continue;
+                }
+            };
+            for line in r.lines.iter().filter(|l| l.1.chip_idx == idx) {
+                match chip.line_output_value(line.1.offset) {
+                    Ok(value) => res.values.push(LineValue {
+                        id: line.0.to_string(),
+                        value,
+                    }),
+                    Err(e) => res.push_error(
+                        &opts.emit,
+                        &anyhow!(e).context(format!(
+                            "failed to read info for line {} from {}",
+                            line.1.offset, ci.name
+                        )),
+                    ),
+                }
             }
         }
     }
-    if let Some(period) = opts.hold_period {
-        thread::sleep(period);
-    }
-    for (idx, ci) in r.chips.iter().enumerate() {
-        if let Some(req) = &requests[idx] {
-            let mut values = Values::default();
-            match req.values(&mut values) {
-                Ok(()) => {
-                    for line in r.lines.iter().filter(|l| l.1.chip_idx == idx) {
-                        res.values.push(LineValue {
-                            id: line.0.to_string(),
-                            value: values.get(line.1.offset).unwrap(),
-                        });
-                    }
+    res.emit(opts);
+    res.errors.is_empty()
+}
+
+impl Getter {
+    fn new(opts: &Opts, res: &mut CmdResult) -> Getter {
+        let (lines, r) = match resolve(opts, res) {
+            Some(resolved) => resolved,
+            None => return Getter::default(),
+        };
+        let mut requests = Vec::new();
+        for (idx, ci) in r.chips.iter().enumerate() {
+            let mut cfg = Config::default();
+            opts.apply(&mut cfg);
+            let offsets: Vec<Offset> = r
+                .lines
+                .values()
+                .filter(|co| co.chip_idx == idx)
+                .map(|co| co.offset)
+                .collect();
+            cfg.with_lines(&offsets);
+
+            let mut bld = Request::from_config(cfg);
+            bld.on_chip(&ci.path)
+                .with_consumer(&opts.consumer)
+                .with_retry(opts.retry_opts.retry, opts.retry_opts.retry_delay);
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            bld.using_abi_version(r.abiv);
+            match bld.request() {
+                Ok(req) => {
+                    requests.push(Some(req));
                 }
                 Err(e) => {
+                    requests.push(None);
                     res.push_error(
                         &opts.emit,
-                        &anyhow!(e).context(format!("failed to read values from {}", ci.name)),
+                        &anyhow!(e).context(format!(
+                            "failed to request lines {:?} from {}",
+                            offsets, ci.name
+                        )),
                     );
                 }
             }
         }
+        Getter { lines, r, requests }
+    }
+
+    fn read_values(&self, opts: &Opts, res: &mut CmdResult) {
+        for (idx, ci) in self.r.chips.iter().enumerate() {
+            if let Some(req) = &self.requests[idx] {
+                let mut values = Values::default();
+                match req.values(&mut values) {
+                    Ok(()) => {
+                        for line in self.r.lines.iter().filter(|l| l.1.chip_idx == idx) {
+                            res.values.push(LineValue {
+                                id: line.0.to_string(),
+                                value: values.get(line.1.offset),
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        res.push_error(
+                            &opts.emit,
+                            &anyhow!(e).context(format!("failed to read values from {}", ci.name)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Read and emit timestamped values every interval until interrupted.
+    fn watch(&self, opts: &Opts) -> bool {
+        // required by --watch
+        let interval = opts.interval.unwrap();
+        let rx = match signal_channel() {
+            Ok(rx) => rx,
+            Err(e) => {
+                let mut res = CmdResult::default();
+                res.push_error(&opts.emit, &e);
+                res.emit(opts);
+                return false;
+            }
+        };
+        let raw = opts.format == Some(OutputFormat::Raw);
+        let start = Instant::now();
+        loop {
+            let elapsed = start.elapsed();
+            let timestamp_ns = if raw { monotonic_ns() } else { 0 };
+            let mut res = CmdResult {
+                lines: self.lines.clone(),
+                timestamp: Some(format!(
+                    "{}.{:09}",
+                    elapsed.as_secs(),
+                    elapsed.subsec_nanos()
+                )),
+                ..Default::default()
+            };
+            self.read_values(opts, &mut res);
+            if raw {
+                if let Err(e) = res.write_raw(timestamp_ns, &mut std::io::stdout().lock()) {
+                    res.push_error(&opts.emit, &anyhow!(e).context("failed to write sample"));
+                }
+                for e in &res.errors {
+                    eprintln!("{}", e);
+                }
+            } else {
+                res.emit(opts);
+            }
+            if !res.errors.is_empty() {
+                return false;
+            }
+            let next = next_sample(start, interval, Instant::now());
+            match rx.recv_timeout(next.saturating_duration_since(Instant::now())) {
+                Err(RecvTimeoutError::Timeout) => {}
+                // interrupted, so exit, releasing the lines
+                _ => return true,
+            }
+        }
+    }
+
+    // Keep the lines requested, without re-reading the values, for the
+    // --hold-for period or until interrupted.
+    fn linger(&self, opts: &Opts, interrupted: &mpsc::Receiver<()>) {
+        match opts.hold_for {
+            Some(period) => _ = interrupted.recv_timeout(period),
+            None => _ = interrupted.recv(),
+        }
+    }
+
+    // Hold the lines, re-reading and emitting the values each time a line is
+    // read from stdin, or at the interval if specified, until stdin is closed.
+    fn hold(&self, opts: &Opts) -> bool {
+        let stdin = std::io::stdin();
+        let mut buf = String::new();
+        loop {
+            match opts.interval {
+                Some(period) => thread::sleep(period),
+                None => {
+                    buf.clear();
+                    match stdin.read_line(&mut buf) {
+                        Ok(0) | Err(_) => return true,
+                        Ok(_) => {}
+                    }
+                }
+            }
+            let mut res = CmdResult {
+                lines: self.lines.clone(),
+                ..Default::default()
+            };
+            self.read_values(opts, &mut res);
+            res.emit(opts);
+            if !res.errors.is_empty() {
+                return false;
+            }
+        }
     }
-    res
+}
+
+// The time of the first sample after now, on the schedule of samples taken
+// every interval from the start.
+//
+// Samples that have already been missed are skipped.
+fn next_sample(start: Instant, interval: Duration, now: Instant) -> Instant {
+    if interval.is_zero() {
+        return now;
+    }
+    let elapsed = now.saturating_duration_since(start);
+    let samples = elapsed.as_nanos() / interval.as_nanos() + 1;
+    start + Duration::from_nanos((interval.as_nanos() * samples) as u64)
+}
+
+// The current CLOCK_MONOTONIC time, as used to timestamp raw samples.
+fn monotonic_ns() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is populated by clock_gettime, which cannot fail for CLOCK_MONOTONIC.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+// Encode a sample as a raw record, as described by --format.
+fn raw_record(timestamp_ns: u64, values: &[bool]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(8 + values.len().div_ceil(8));
+    record.extend_from_slice(&timestamp_ns.to_le_bytes());
+    for chunk in values.chunks(8) {
+        record.push(
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (bit, active)| byte | (u8::from(*active) << bit)),
+        );
+    }
+    record
 }
 
 #[derive(Default)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 struct CmdResult {
+    // the time since the first sample when watching
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    timestamp: Option<String>,
+    // the requested lines, with any globs expanded
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lines: Vec<String>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     values: Vec<LineValue>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
@@ -169,30 +530,57 @@ impl CmdResult {
         self.errors.push(format_error(opts, e))
     }
 
+    // Write the values as a raw record, in line order.
+    fn write_raw<W: Write>(&self, timestamp_ns: u64, out: &mut W) -> std::io::Result<()> {
+        let mut values = Vec::new();
+        let mut seen_lines = Vec::new();
+        for id in &self.lines {
+            if seen_lines.contains(id) {
+                continue;
+            }
+            seen_lines.push(id.clone());
+            let value = self
+                .values
+                .iter()
+                .find(|lv| &lv.id == id)
+                .and_then(|lv| lv.value);
+            values.push(value == Some(Value::Active));
+        }
+        out.write_all(&raw_record(timestamp_ns, &values))?;
+        out.flush()
+    }
+
     fn print(&self, opts: &Opts) {
         let mut print_values = Vec::new();
         let mut seen_lines = Vec::new();
-        for id in &opts.line {
+        for id in &self.lines {
             if seen_lines.contains(id) {
                 continue;
             }
             seen_lines.push(id.clone());
             for lv in &self.values {
                 if &lv.id == id {
-                    print_values.push(if opts.numeric {
-                        let v: u8 = lv.value.into();
-                        format!("{}", v)
+                    let value = match lv.value {
+                        Some(v) if opts.numeric => u8::from(v).to_string(),
+                        Some(v) => v.to_string(),
+                        None => "unknown".to_string(),
+                    };
+                    print_values.push(if opts.numeric || opts.values_only {
+                        value
                     } else if opts.emit.quoted {
-                        format!("\"{}\"={}", lv.id, lv.value)
+                        format!("\"{}\"={}", lv.id, value)
                     } else {
-                        format!("{}={}", lv.id, lv.value)
+                        format!("{}={}", lv.id, value)
                     });
                     break;
                 }
             }
         }
         if !print_values.is_empty() {
-            println!("{}", print_values.join(" "));
+            match &self.timestamp {
+                Some(ts) => println!("{}\t{}", ts, print_values.join(" ")),
+                None => println!("{}", print_values.join(" ")),
+            }
         }
         for e in &self.errors {
             eprintln!("{}", e);
@@ -202,7 +590,8 @@ impl CmdResult {
 
 struct LineValue {
     id: String,
-    value: Value,
+    // None if the value could not be determined.
+    value: Option<Value>,
 }
 #[cfg(feature = "serde")]
 impl serde::Serialize for LineValue {
@@ -216,3 +605,82 @@ impl serde::Serialize for LineValue {
         s.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_sample() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let next = |now| super::next_sample(start, ms(10), start + now) - start;
+
+        // on schedule
+        assert_eq!(next(ms(0)), ms(10));
+        assert_eq!(next(ms(3)), ms(10));
+        assert_eq!(next(ms(10)), ms(20));
+        assert_eq!(next(ms(19)), ms(20));
+        // missed samples are skipped
+        assert_eq!(next(ms(47)), ms(50));
+        // no interval samples continuously
+        let now = start + ms(5);
+        assert_eq!(super::next_sample(start, Duration::ZERO, now), now);
+    }
+
+    #[test]
+    fn raw_record() {
+        assert_eq!(super::raw_record(0, &[]), vec![0; 8]);
+        assert_eq!(
+            super::raw_record(0x0102_0304_0506_0708, &[true, false, true]),
+            vec![8, 7, 6, 5, 4, 3, 2, 1, 0b101]
+        );
+        let mut values = [false; 9];
+        values[7] = true;
+        values[8] = true;
+        assert_eq!(
+            super::raw_record(1, &values),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 0x80, 0x01]
+        );
+    }
+
+    #[test]
+    fn write_raw() {
+        use std::io::Read;
+        use std::os::unix::net::UnixStream;
+
+        let lv = |id: &str, value| LineValue {
+            id: id.to_string(),
+            value: Some(value),
+        };
+        let res = CmdResult {
+            // repeated lines are only encoded once
+            lines: ["a", "b", "c", "a"].iter().map(|s| s.to_string()).collect(),
+            values: vec![
+                lv("c", Value::Active),
+                lv("a", Value::Active),
+                lv("b", Value::Inactive),
+            ],
+            ..Default::default()
+        };
+        let (mut rx, mut tx) = UnixStream::pair().unwrap();
+        let timestamps = [1_000_000_000_u64, 1_010_000_000, u64::MAX];
+        for ts in timestamps {
+            res.write_raw(ts, &mut tx).unwrap();
+        }
+        drop(tx);
+
+        let mut records = Vec::new();
+        let mut record = [0; 9];
+        while rx.read_exact(&mut record).is_ok() {
+            let ts = u64::from_le_bytes(record[..8].try_into().unwrap());
+            let bits: Vec<bool> = (0..3).map(|n| record[8] & (1 << n) != 0).collect();
+            assert_eq!(record[8] & !0b111, 0);
+            records.push((ts, bits));
+        }
+        assert_eq!(records.len(), timestamps.len());
+        for (record, ts) in records.iter().zip(timestamps) {
+            assert_eq!(record, &(ts, vec![true, false, true]));
+        }
+    }
+}
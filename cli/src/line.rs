@@ -3,10 +3,14 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use super::common::{
-    self, emit_error, format_chip_name, stringify_attrs, EmitOpts, LineOpts, Resolver,
+    self, emit_error, format_chip_name, stringify_attrs, LineAttrs, LineOpts, Resolver,
 };
-use clap::Parser;
+use anyhow::anyhow;
+use clap::{Parser, ValueEnum};
+#[cfg(feature = "serde")]
 use gpiocdev::line::Info;
+#[cfg(feature = "procfs")]
+use gpiocdev::procfs::{Holders, ProcessTable};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
 
@@ -45,6 +49,19 @@ pub struct Opts {
     #[arg(long)]
     by_name: bool,
 
+    /// Lines are shell-style glob patterns matched against line names
+    ///
+    /// Patterns may contain '*', '?' and '[...]' character classes,
+    /// and are expanded to all matching lines, sorted by chip then offset.
+    ///
+    /// It is an error for a pattern to match no lines.
+    #[arg(long)]
+    glob: bool,
+
+    /// Line names are matched ignoring ASCII case
+    #[arg(long)]
+    ignore_case: bool,
+
     /// Check all lines - don't assume names are unique
     ///
     /// If not specified then the command stops when a matching line is found.
@@ -54,6 +71,45 @@ pub struct Opts {
     #[arg(short = 's', long)]
     strict: bool,
 
+    /// Only show lines that are in use
+    ///
+    /// Lines are in use if requested by a consumer, either in userspace or in the kernel.
+    #[arg(short = 'u', long)]
+    used: bool,
+
+    /// Identify the processes holding the lines that are in use
+    ///
+    /// The processes are found by searching /proc for line requests, or, if the
+    /// kernel does not report the lines in requests, for processes with the
+    /// chip open.
+    ///
+    /// Processes that cannot be searched, typically due to permissions, are skipped.
+    /// They are reported as warnings in verbose mode.
+    ///
+    /// Implied by --verbose.
+    #[cfg(feature = "procfs")]
+    #[arg(long)]
+    resolve_consumer: bool,
+
+    /// The format of the line info output
+    ///
+    /// The json format is an array of objects, one per line, while the
+    /// json-lines format is one object per output line.
+    ///
+    /// The objects contain the chip, offset, name and attributes of each line.
+    #[arg(long, value_name = "format", value_enum, default_value_t)]
+    #[cfg_attr(feature = "json", arg(conflicts_with = "json"))]
+    format: Format,
+
+    /// The fields to output for each line, in the order they are to be output
+    ///
+    /// Each line is output as a tab separated list of the selected fields.
+    /// Optional fields that are not set for the line are output as '-'.
+    ///
+    /// Only applies to the text format.
+    #[arg(long, value_name = "columns", value_enum, value_delimiter = ',')]
+    columns: Vec<Column>,
+
     #[command(flatten)]
     uapi_opts: common::UapiOpts,
 
@@ -61,21 +117,66 @@ pub struct Opts {
     emit: common::EmitOpts,
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum Format {
+    #[default]
+    Text,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "json")]
+    JsonLines,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Column {
+    Chip,
+    Offset,
+    Name,
+    Direction,
+    ActiveLow,
+    Drive,
+    Bias,
+    Edges,
+    EventClock,
+    Debounce,
+    Used,
+    Consumer,
+}
+
 pub fn cmd(opts: &Opts) -> bool {
+    if !opts.columns.is_empty() && opts.format != Format::Text {
+        emit_error(
+            &opts.emit,
+            &anyhow!("--columns only applies to the text format"),
+        );
+        return false;
+    }
     let line_opts = LineOpts {
         chip: opts.chip.clone(),
         strict: false, // to continue on multi-match
         by_name: opts.by_name,
+        glob: opts.glob,
+        ignore_case: opts.ignore_case,
+    };
+    let lines = match Resolver::expand_globs(&opts.lines, &line_opts, &opts.uapi_opts) {
+        Ok(lines) => lines,
+        Err(e) => {
+            emit_error(&opts.emit, &e);
+            return false;
+        }
     };
     let res = Cmd {
         opts,
         r: common::Resolver::resolve_lines_with_info(
-            &opts.lines,
+            &lines,
             &line_opts,
             &opts.uapi_opts,
             opts.strict, // --strict means exhaustive for `line`
             true,
         ),
+        lines,
+        #[cfg(feature = "procfs")]
+        procs: (opts.resolve_consumer || opts.emit.verbose > 0).then(|| scan_processes(&opts.emit)),
     };
     res.emit();
     res.is_success()
@@ -87,14 +188,50 @@ struct Cmd<'a> {
     opts: &'a Opts,
     #[cfg_attr(feature = "serde", serde(skip))]
     r: Resolver,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lines: Vec<String>,
+    #[cfg(feature = "procfs")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    procs: Option<ProcessTable>,
+}
+
+#[cfg(feature = "procfs")]
+fn scan_processes(opts: &common::EmitOpts) -> ProcessTable {
+    let pt = ProcessTable::scan();
+    if opts.verbose > 0 {
+        for e in pt.errors() {
+            eprintln!("warning: {}", e);
+        }
+    }
+    pt
 }
 
 impl Cmd<'_> {
     fn is_success(&self) -> bool {
         self.r.errors.is_empty()
-            && (self.opts.lines.is_empty()
-                || (self.opts.lines.len() == self.r.lines.len())
-                    && (self.opts.lines.len() == self.r.info.len()))
+            && (self.lines.is_empty()
+                || (self.lines.len() == self.r.lines.len())
+                    && (self.lines.len() == self.r.info.len()))
+    }
+
+    // The processes holding the line, if requested and the line is in use.
+    #[cfg(feature = "procfs")]
+    fn holders(&self, i: &common::LineInfo) -> Option<Holders> {
+        if !i.info.used {
+            return None;
+        }
+        let c = &self.r.chips[i.chip];
+        self.procs
+            .as_ref()?
+            .holders(&c.path, &c.name, i.info.offset)
+    }
+
+    // The info for the lines to be output.
+    fn info(&self) -> impl Iterator<Item = &common::LineInfo> {
+        self.r
+            .info
+            .iter()
+            .filter(|i| !self.opts.used || i.info.used)
     }
 
     fn emit(&self) {
@@ -103,7 +240,91 @@ impl Cmd<'_> {
             self.emit_json();
             return;
         }
-        self.print();
+        match self.opts.format {
+            #[cfg(feature = "json")]
+            Format::Json => self.emit_json_array(),
+            #[cfg(feature = "json")]
+            Format::JsonLines => self.emit_json_lines(),
+            Format::Text => {
+                if self.opts.columns.is_empty() {
+                    self.print();
+                } else {
+                    self.print_columns();
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn line_records(&self) -> impl Iterator<Item = LineRecord<'_>> {
+        self.info().map(|i| LineRecord {
+            chip: &self.r.chips[i.chip].name,
+            offset: i.info.offset,
+            name: &i.info.name,
+            attrs: LineAttrs::from(&i.info),
+            #[cfg(feature = "procfs")]
+            holders: self.holders(i),
+        })
+    }
+
+    #[cfg(feature = "json")]
+    fn emit_json_array(&self) {
+        let lines: Vec<LineRecord> = self.line_records().collect();
+        println!("{}", serde_json::to_string(&lines).unwrap());
+        self.emit_errors();
+    }
+
+    #[cfg(feature = "json")]
+    fn emit_json_lines(&self) {
+        for line in self.line_records() {
+            println!("{}", serde_json::to_string(&line).unwrap());
+        }
+        self.emit_errors();
+    }
+
+    fn emit_errors(&self) {
+        for e in &self.r.errors {
+            emit_error(&self.opts.emit, e);
+        }
+    }
+
+    fn print_columns(&self) {
+        let quoted = self.opts.emit.quoted;
+        for i in self.info() {
+            let li = &i.info;
+            let attrs = LineAttrs::from(li);
+            let fields: Vec<String> = self
+                .opts
+                .columns
+                .iter()
+                .map(|col| match col {
+                    Column::Chip => self.r.chips[i.chip].name.clone(),
+                    Column::Offset => li.offset.to_string(),
+                    Column::Name => {
+                        if li.name.is_empty() {
+                            "-".into()
+                        } else if quoted {
+                            format!("\"{}\"", li.name)
+                        } else {
+                            li.name.to_string()
+                        }
+                    }
+                    Column::Direction => attrs.direction.into(),
+                    Column::ActiveLow => attrs.active_low.to_string(),
+                    Column::Drive => attrs.drive.unwrap_or("-").into(),
+                    Column::Bias => attrs.bias.unwrap_or("-").into(),
+                    Column::Edges => attrs.edge_detection.unwrap_or("-").into(),
+                    Column::EventClock => attrs.event_clock.unwrap_or("-").into(),
+                    Column::Debounce => attrs
+                        .debounce_period
+                        .map_or("-".into(), |p| format!("{:?}", p)),
+                    Column::Used => attrs.used.to_string(),
+                    Column::Consumer => self.consumer_text(i, &attrs).unwrap_or("-".into()),
+                })
+                .collect();
+            println!("{}", fields.join("\t"));
+        }
+        self.emit_errors();
     }
 
     #[cfg(feature = "json")]
@@ -111,10 +332,12 @@ impl Cmd<'_> {
         let mut res = CmdResult {
             ..Default::default()
         };
-        for i in &self.r.info {
+        for i in self.info() {
             res.lines.push(LineInfo {
                 chip: &self.r.chips[i.chip].name,
                 info: &i.info,
+                #[cfg(feature = "procfs")]
+                holders: self.holders(i),
             });
         }
         for e in &self.r.errors {
@@ -124,22 +347,81 @@ impl Cmd<'_> {
     }
 
     fn print(&self) {
-        if self.opts.lines.is_empty() {
+        if self.lines.is_empty() {
             for idx in 0..self.r.chips.len() {
-                print_chip_lines(&self.r, idx, &self.opts.emit);
+                self.print_chip_lines(idx);
             }
         } else {
-            for info in &self.r.info {
-                print_line_info(
-                    &self.r.chips[info.chip].name,
-                    &info.info,
-                    self.opts.emit.quoted,
-                )
+            for info in self.info() {
+                self.print_line_info(info)
             }
         }
-        for e in &self.r.errors {
-            emit_error(&self.opts.emit, e);
+        self.emit_errors();
+    }
+
+    fn print_chip_lines(&self, idx: usize) {
+        let opts = &self.opts.emit;
+        let c = &self.r.chips[idx];
+        println!("{} - {} lines:", format_chip_name(&c.name), c.num_lines);
+        for info in self.info() {
+            if info.chip != idx {
+                continue;
+            }
+            let li = &info.info;
+            let lname = if li.name.is_empty() {
+                "unnamed".to_string()
+            } else if opts.quoted {
+                format!("\"{}\"", li.name)
+            } else {
+                li.name.to_string()
+            };
+            println!(
+                "\tline {:>3}:\t{:16}\t{}",
+                li.offset,
+                lname,
+                self.attrs_text(info),
+            );
+        }
+    }
+
+    fn print_line_info(&self, info: &common::LineInfo) {
+        let opts = &self.opts.emit;
+        let li = &info.info;
+        let lname = if li.name.is_empty() {
+            "unnamed".to_string()
+        } else if opts.quoted {
+            format!("\"{}\"", li.name)
+        } else {
+            li.name.to_string()
+        };
+        println!(
+            "{} {}\t{:16}\t{}",
+            format_chip_name(&self.r.chips[info.chip].name),
+            li.offset,
+            lname,
+            self.attrs_text(info),
+        );
+    }
+
+    // The line attributes, and if requested the processes holding the line.
+    fn attrs_text(&self, info: &common::LineInfo) -> String {
+        let attrs = stringify_attrs(&info.info, self.opts.emit.quoted);
+        #[cfg(feature = "procfs")]
+        if let Some(holders) = self.holders(info) {
+            return format!("{} {}", attrs, holders);
         }
+        attrs
+    }
+
+    // The consumer, and if requested the processes holding the line.
+    #[cfg_attr(not(feature = "procfs"), allow(unused_variables))]
+    fn consumer_text(&self, info: &common::LineInfo, attrs: &LineAttrs) -> Option<String> {
+        let consumer = attrs.consumer_text(self.opts.emit.quoted)?;
+        #[cfg(feature = "procfs")]
+        if let Some(holders) = self.holders(info) {
+            return Some(format!("{} {}", consumer, holders));
+        }
+        Some(consumer)
     }
 }
 
@@ -152,51 +434,26 @@ struct CmdResult<'a> {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     errors: Vec<String>,
 }
+#[cfg(feature = "json")]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct LineRecord<'a> {
+    chip: &'a str,
+    offset: gpiocdev::line::Offset,
+    name: &'a str,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    attrs: LineAttrs<'a>,
+    #[cfg(feature = "procfs")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    holders: Option<Holders>,
+}
+
 #[cfg(feature = "serde")]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 struct LineInfo<'a> {
     chip: &'a str,
     #[cfg_attr(feature = "serde", serde(flatten))]
     info: &'a Info,
-}
-
-fn print_chip_lines(r: &Resolver, idx: usize, opts: &EmitOpts) {
-    let c = &r.chips[idx];
-    println!("{} - {} lines:", format_chip_name(&c.name), c.num_lines);
-    for info in &r.info {
-        if info.chip != idx {
-            continue;
-        }
-        let li = &info.info;
-        let lname = if li.name.is_empty() {
-            "unnamed".to_string()
-        } else if opts.quoted {
-            format!("\"{}\"", li.name)
-        } else {
-            li.name.to_string()
-        };
-        println!(
-            "\tline {:>3}:\t{:16}\t{}",
-            li.offset,
-            lname,
-            stringify_attrs(li, opts.quoted),
-        );
-    }
-}
-
-fn print_line_info(chip_name: &str, li: &Info, quoted: bool) {
-    let lname = if li.name.is_empty() {
-        "unnamed".to_string()
-    } else if quoted {
-        format!("\"{}\"", li.name)
-    } else {
-        li.name.to_string()
-    };
-    println!(
-        "{} {}\t{:16}\t{}",
-        format_chip_name(chip_name),
-        li.offset,
-        lname,
-        stringify_attrs(li, quoted),
-    );
+    #[cfg(feature = "procfs")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    holders: Option<Holders>,
 }
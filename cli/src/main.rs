@@ -4,29 +4,48 @@
 
 //! A command line tool for accessing GPIO lines.
 
-use clap::Parser;
+use clap::{ArgAction, CommandFactory, Parser};
+use log::LevelFilter;
 use std::process::ExitCode;
 
+mod bench;
 mod chip;
 mod common;
+mod completions;
+#[cfg(feature = "config")]
+mod config;
 mod edges;
 mod get;
 mod line;
 mod notify;
 mod platform;
+mod pulse;
+#[cfg(feature = "config")]
+mod restore;
+#[cfg(feature = "config")]
+mod save;
 mod set;
 
 fn main() -> ExitCode {
     match Opts::try_parse() {
         Ok(opt) => {
+            init_logger(opt.verbose);
             let res = match opt.cmd {
+                Command::Bench(cfg) => bench::cmd(&cfg),
                 Command::Chip(cfg) => chip::cmd(&cfg),
-                Command::Edges(cfg) => edges::cmd(&cfg),
+                Command::Completions(cfg) => completions::cmd(&cfg, &mut Opts::command()),
+                Command::CompleteLines(cfg) => completions::complete_lines(&cfg),
+                Command::Edges(cfg) => return edges::cmd(&cfg),
                 Command::Get(cfg) => get::cmd(&cfg),
                 Command::Line(cfg) => line::cmd(&cfg),
-                Command::Set(cfg) => set::cmd(&cfg),
+                #[cfg(feature = "config")]
+                Command::Restore(cfg) => restore::cmd(&cfg),
+                #[cfg(feature = "config")]
+                Command::Save(cfg) => save::cmd(&cfg),
+                Command::Set(cfg) => return set::cmd(&cfg),
                 Command::Notify(cfg) => notify::cmd(&cfg),
                 Command::Platform(cfg) => platform::cmd(&cfg),
+                Command::Pulse(cfg) => pulse::cmd(&cfg),
             };
             return if res {
                 ExitCode::SUCCESS
@@ -47,16 +66,39 @@ fn main() -> ExitCode {
     propagate_version = true
 )]
 struct Opts {
-    /// Provide more detailed error messages.
-    #[arg(short = 'v', long, global = true, display_order = 800)]
-    pub verbose: bool,
+    /// Provide more detailed output and error messages.
+    ///
+    /// Repeat to also log the library activity to stderr, with -vv logging
+    /// requests, value accesses and reconfigurations, and -vvv also logging
+    /// edge events.
+    #[arg(short = 'v', long, global = true, action = ArgAction::Count, display_order = 800)]
+    pub verbose: u8,
 
     #[command(subcommand)]
     cmd: Command,
 }
 
+// Log to stderr at a level determined by the verbosity.
+//
+// The level may be overridden using the RUST_LOG environment variable.
+fn init_logger(verbose: u8) {
+    let level = match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+}
+
 #[derive(Parser)]
 enum Command {
+    /// Measure the latency and throughput of setting, getting and detecting edges on GPIO lines.
+    Bench(bench::Opts),
+
     /// Get information about GPIO chips.
     Chip(chip::Opts),
 
@@ -75,6 +117,24 @@ enum Command {
     /// Get information about the platform GPIO uAPI support.
     Platform(platform::Opts),
 
+    /// Generate a train of pulses on a GPIO line.
+    Pulse(pulse::Opts),
+
+    /// Restore the state of GPIO lines saved by save.
+    #[cfg(feature = "config")]
+    Restore(restore::Opts),
+
+    /// Save the state of the GPIO lines on a chip.
+    #[cfg(feature = "config")]
+    Save(save::Opts),
+
     /// Set the levels of GPIO lines.
     Set(set::Opts),
+
+    /// Generate shell completion scripts.
+    Completions(completions::Opts),
+
+    /// List line names starting with a prefix, for use by shell completion scripts.
+    #[command(name = "__complete-lines", hide = true)]
+    CompleteLines(completions::LinesOpts),
 }
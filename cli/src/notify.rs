@@ -5,12 +5,13 @@
 use super::common::{self, emit_error, format_error, format_time, ChipInfo, EmitOpts, TimeFmt};
 use anyhow::anyhow;
 use clap::{Parser, ValueEnum};
-use gpiocdev::line::{InfoChangeEvent, InfoChangeKind};
+use gpiocdev::line::{Info, InfoChangeEvent, InfoChangeKind, Offset};
 use libc::timespec;
 use mio::unix::SourceFd;
 use mio::{Events, Interest, Poll, Token};
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
+use std::collections::HashMap;
 use std::os::unix::prelude::AsRawFd;
 use std::time::Duration;
 
@@ -28,7 +29,12 @@ pub struct Opts {
     ///
     /// The lines are identified by name or optionally by
     /// offset if the --chip option is specified.
-    #[arg(value_name = "line", required = true)]
+    ///
+    /// If no lines are specified then all lines on the chip are watched.
+    /// Each line is watched individually, but the changes for all lines on
+    /// a chip are read from the one chip file, so watching chips with many
+    /// lines does not require additional file descriptors.
+    #[arg(value_name = "line", required_unless_present = "chip")]
     lines: Vec<String>,
 
     /// Specify the events to report.
@@ -59,6 +65,7 @@ pub struct Opts {
     ///   %E   event type ('requested', 'released' or 'reconfigured')
     ///   %a   line attributes
     ///   %C   consumer
+    ///   %R   attributes changed by a reconfigured event
     ///   %S   event timestamp as seconds
     ///   %U   event timestamp as UTC
     ///   %L   event timestamp as local time
@@ -83,6 +90,12 @@ pub struct Opts {
     #[arg(short = 'q', long, groups = ["emit", "timefmt"], alias = "silent")]
     quiet: bool,
 
+    #[command(flatten)]
+    ready_opts: common::ReadyOpts,
+
+    #[command(flatten)]
+    daemon_opts: common::DaemonOpts,
+
     #[command(flatten)]
     uapi_opts: common::UapiOpts,
 
@@ -131,7 +144,29 @@ fn do_cmd(opts: &Opts) -> CmdResult {
         opts: opts.emit,
         ..Default::default()
     };
-    let r = common::Resolver::resolve_lines(&opts.lines, &opts.line_opts, &opts.uapi_opts);
+    if let Err(e) = opts
+        .ready_opts
+        .check()
+        .and_then(|_| opts.daemon_opts.check(&opts.ready_opts))
+    {
+        res.push_error(&e);
+        return res;
+    }
+    // no lines is shorthand for all lines on the chip
+    let all = ["all".to_string()];
+    let ids = if opts.lines.is_empty() {
+        &all[..]
+    } else {
+        &opts.lines
+    };
+    let lines = match common::Resolver::expand_lines(ids, &opts.line_opts, &opts.uapi_opts) {
+        Ok(lines) => lines,
+        Err(e) => {
+            res.push_error(&e);
+            return res;
+        }
+    };
+    let r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
     if !r.errors.is_empty() {
         for e in r.errors {
             res.push_error(&e);
@@ -146,6 +181,9 @@ fn do_cmd(opts: &Opts) -> CmdResult {
         }
     };
     let mut chips = Vec::new();
+    // the most recent info for each watched line, by chip, to identify what
+    // changed when a line is reconfigured.
+    let mut watched: Vec<HashMap<Offset, Info>> = Vec::new();
     for (idx, ci) in r.chips.iter().enumerate() {
         let chip = match common::chip_from_path(&ci.path, r.abiv) {
             Ok(c) => c,
@@ -155,16 +193,20 @@ fn do_cmd(opts: &Opts) -> CmdResult {
             }
         };
 
+        let mut infos = HashMap::new();
         for offset in r
             .lines
             .values()
             .filter(|co| co.chip_idx == idx)
             .map(|co| co.offset)
         {
-            if let Err(e) = chip.watch_line_info(offset) {
-                res.push_error(
+            match chip.watch_line_info(offset) {
+                Ok(info) => {
+                    infos.insert(offset, info);
+                }
+                Err(e) => res.push_error(
                     &anyhow!(e).context(format!("failed to watch line {} on {}", offset, ci.name)),
-                );
+                ),
             }
         }
         if let Err(e) = poll.registry().register(
@@ -176,6 +218,7 @@ fn do_cmd(opts: &Opts) -> CmdResult {
             return res;
         }
         chips.push(chip);
+        watched.push(infos);
     }
     if !res.errors.is_empty() {
         return res;
@@ -183,7 +226,16 @@ fn do_cmd(opts: &Opts) -> CmdResult {
     let mut count = 0;
     let mut events = Events::with_capacity(r.chips.len());
     let timefmt = opts.timefmt();
-    emit_banner(opts);
+    emit_banner(opts, &lines, &r.chips);
+    // the lines are watched before detaching, so no changes are missed.
+    if let Err(e) = opts
+        .daemon_opts
+        .daemonize()
+        .and_then(|_| opts.ready_opts.notify())
+    {
+        res.push_error(&e);
+        return res;
+    }
     loop {
         match poll.poll(&mut events, opts.idle_timeout) {
             Err(e) => {
@@ -201,12 +253,22 @@ fn do_cmd(opts: &Opts) -> CmdResult {
                     while chips[idx].has_line_info_change_event().unwrap_or(false) {
                         match chips[idx].read_line_info_change_event() {
                             Ok(change) => {
+                                // track the info even for filtered events, so the
+                                // changes reported are relative to the previous event.
+                                let prev =
+                                    watched[idx].insert(change.info.offset, change.info.clone());
                                 if let Some(evtype) = opts.event {
                                     if change.kind != evtype.into() {
                                         continue;
                                     }
                                 }
-                                emit_change(change, &r.chips[idx], opts, &timefmt);
+                                let changed = match prev {
+                                    Some(prev) if change.kind == InfoChangeKind::Reconfigured => {
+                                        change.info.changed_fields(&prev)
+                                    }
+                                    _ => Vec::new(),
+                                };
+                                emit_change(change, changed, &r.chips[idx], opts, &timefmt);
                                 if let Some(limit) = opts.num_events {
                                     count += 1;
                                     if count >= limit {
@@ -257,31 +319,27 @@ impl CmdResult {
     }
 }
 
-fn emit_banner(opts: &Opts) {
+fn emit_banner(opts: &Opts, lines: &[String], chips: &[ChipInfo]) {
     if !opts.banner {
         return;
     }
-    print_banner(&opts.lines)
-}
+    if opts.lines.is_empty() {
+        use std::io::Write;
 
-fn print_banner(lines: &[String]) {
-    use std::io::Write;
-
-    if lines.len() > 1 {
-        print!("Watching lines ");
-
-        for l in lines.iter().take(lines.len() - 1) {
-            print!("'{}', ", l);
-        }
-
-        println!("and '{}'...", lines[lines.len() - 1]);
-    } else {
-        println!("Watching line '{}'...", lines[0]);
+        println!("Watching all lines on {}...", chips[0].name);
+        _ = std::io::stdout().flush();
+        return;
     }
-    _ = std::io::stdout().flush();
+    common::print_banner("Watching", lines)
 }
 
-fn emit_change(change: InfoChangeEvent, ci: &ChipInfo, opts: &Opts, timefmt: &TimeFmt) {
+fn emit_change(
+    change: InfoChangeEvent,
+    changed: Vec<&'static str>,
+    ci: &ChipInfo,
+    opts: &Opts,
+    timefmt: &TimeFmt,
+) {
     if opts.quiet {
         return;
     }
@@ -290,6 +348,7 @@ fn emit_change(change: InfoChangeEvent, ci: &ChipInfo, opts: &Opts, timefmt: &Ti
         #[cfg(feature = "json")]
         chip: ci.name.clone(),
         change,
+        changed,
         timestamp,
     };
 
@@ -299,7 +358,7 @@ fn emit_change(change: InfoChangeEvent, ci: &ChipInfo, opts: &Opts, timefmt: &Ti
         return;
     }
     if let Some(format) = &opts.format {
-        print_change_formatted(&event.change, format, ci, opts.emit.quoted);
+        print_change_formatted(&event, format, ci, opts.emit.quoted);
     } else {
         event.print(ci, opts);
     }
@@ -312,6 +371,9 @@ struct Event {
     chip: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
     change: InfoChangeEvent,
+    // The attributes changed by a reconfiguration.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    changed: Vec<&'static str>,
     timestamp: String,
 }
 
@@ -328,12 +390,17 @@ impl Event {
                 print!("{} {} ", ci.name, self.change.info.offset);
             }
             if opts.emit.quoted {
-                println!("\"{lname}\"");
+                print!("\"{lname}\"");
             } else {
-                println!("{lname}");
+                print!("{lname}");
             }
         } else {
-            println!("{} {}", ci.name, self.change.info.offset);
+            print!("{} {}", ci.name, self.change.info.offset);
+        }
+        if self.changed.is_empty() {
+            println!();
+        } else {
+            println!("\t{}", self.changed.join(", "));
         }
     }
 }
@@ -350,9 +417,11 @@ fn format_consumer(li: &gpiocdev::line::Info) -> &str {
     }
 }
 
-fn print_change_formatted(event: &InfoChangeEvent, format: &str, ci: &ChipInfo, quoted: bool) {
+fn print_change_formatted(event: &Event, format: &str, ci: &ChipInfo, quoted: bool) {
     use common::stringify_attrs;
 
+    let changed = &event.changed;
+    let event = &event.change;
     let mut escaped = false;
 
     for chr in format.chars() {
@@ -373,6 +442,7 @@ fn print_change_formatted(event: &InfoChangeEvent, format: &str, ci: &ChipInfo,
                     )
                 ),
                 'o' => print!("{}", event.info.offset),
+                'R' => print!("{}", changed.join(", ")),
                 'S' => print!("{}", format_time(event.timestamp_ns, &TimeFmt::Seconds)),
                 'U' => print!(
                     "{}",
@@ -77,7 +77,7 @@ impl Platform {
                 None => println!("{} is supported.", abi.version),
                 Some(k) => println!("{}", gpiocdev::Error::UnsupportedAbi(abi.version, k)),
             }
-            if self.opts.verbose {
+            if self.opts.verbose > 0 {
                 if abi.unsupported_by.is_none() && abi.missing_features.is_empty() {
                     println!("Kernel supports all {} features.", abi.version);
                 }
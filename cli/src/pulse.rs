@@ -0,0 +1,508 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{self, emit_error};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use gpiocdev::line::{Offset, Value};
+use gpiocdev::Request;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::cmp::{max, min};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The line to pulse
+    ///
+    /// The line is identified by name or optionally by offset
+    /// if the --chip option is provided.
+    #[arg(value_name = "line")]
+    line: String,
+
+    #[command(flatten)]
+    line_opts: common::LineOpts,
+
+    /// The number of pulses to generate
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "count",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    count: u32,
+
+    /// The time each pulse holds the line active
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(short, long, value_name = "period", value_parser = common::parse_duration)]
+    width: Duration,
+
+    /// The time from the start of one pulse to the start of the next
+    ///
+    /// Must be longer than the --width, and is required if the --count
+    /// is greater than one.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(short, long, value_name = "period", value_parser = common::parse_duration)]
+    period: Option<Duration>,
+
+    /// Report the achieved pulse timing when the pulse train is complete
+    #[arg(long)]
+    stats: bool,
+
+    #[command(flatten)]
+    active_low_opts: common::ActiveLowOpts,
+
+    #[command(flatten)]
+    drive_opts: common::DriveOpts,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    /// The consumer label applied to the requested line.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-pulse"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Err(e) => {
+            emit_error(&opts.emit, &e);
+            false
+        }
+        Ok(x) => x,
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<bool> {
+    let train = PulseTrain::new(opts.count, opts.width, opts.period)?;
+    let lines = [opts.line.to_owned()];
+    let r = common::Resolver::resolve_lines(&lines, &opts.line_opts, &opts.uapi_opts);
+    if !r.errors.is_empty() {
+        for e in &r.errors {
+            emit_error(&opts.emit, e);
+        }
+        return Ok(false);
+    }
+    let co = r.lines.get(&opts.line).unwrap();
+    let ci = &r.chips[co.chip_idx];
+    let mut bld = Request::builder();
+    bld.on_chip(&ci.path)
+        .with_consumer(&opts.consumer)
+        .with_line(co.offset)
+        .as_output(Value::Inactive);
+    if opts.active_low_opts.active_low {
+        bld.as_active_low();
+    }
+    if let Some(drive) = opts.drive_opts.drive {
+        bld.with_drive(drive.into());
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(r.abiv);
+    let req = bld
+        .request()
+        .with_context(|| format!("failed to request line {} from {}", opts.line, ci.name))?;
+
+    let resolution = measure_resolution(&req, co.offset)?;
+    if train.shortest() < resolution {
+        eprintln!(
+            "warning: the pulse timing is below the {:?} resolution measured for this system, \
+            so will be imprecise",
+            resolution
+        );
+    }
+
+    let edges = train.drive(&req, co.offset)?;
+    if opts.stats {
+        Stats::new(&train, &edges).emit(opts);
+    }
+    Ok(true)
+}
+
+// The number of rounds used to determine the timing resolution.
+const CALIBRATION_ROUNDS: u32 = 8;
+
+// The sleep used to determine the timing resolution.
+const CALIBRATION_SLEEP: Duration = Duration::from_micros(100);
+
+// The worst case latency of waking from a sleep and setting the line,
+// which limits the shortest period that can be reliably generated.
+//
+// The line is already inactive, so setting it inactive has no effect
+// on the line.
+fn measure_resolution(req: &Request, offset: Offset) -> Result<Duration> {
+    let mut worst = Duration::ZERO;
+    for _ in 0..CALIBRATION_ROUNDS {
+        let start = Instant::now();
+        thread::sleep(CALIBRATION_SLEEP);
+        req.set_value(offset, Value::Inactive)
+            .context("failed to set line value")?;
+        worst = max(worst, start.elapsed().saturating_sub(CALIBRATION_SLEEP));
+    }
+    Ok(worst)
+}
+
+struct PulseTrain {
+    count: u32,
+    width: Duration,
+    period: Duration,
+}
+
+// The scheduled and achieved times of a line transition.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    scheduled: Instant,
+    actual: Instant,
+}
+
+impl PulseTrain {
+    // Create a pulse train, validating the period against the width and count.
+    //
+    // The period is only optional for a single pulse.
+    fn new(count: u32, width: Duration, period: Option<Duration>) -> Result<PulseTrain> {
+        if width.is_zero() {
+            bail!("--width must be greater than zero");
+        }
+        let period = match period {
+            Some(period) if width >= period => bail!(
+                "--width ({:?}) must be less than --period ({:?})",
+                width,
+                period
+            ),
+            Some(period) => period,
+            // a lone pulse has no following pulse, so no period
+            None if count == 1 => width,
+            None => bail!("--period is required when --count is greater than one"),
+        };
+        if period.checked_mul(count).is_none() {
+            bail!("--period is too long for --count");
+        }
+        Ok(PulseTrain {
+            count,
+            width,
+            period,
+        })
+    }
+
+    // The shortest period the line is held at a value during the train.
+    fn shortest(&self) -> Duration {
+        match self.count {
+            1 => self.width,
+            _ => min(self.width, self.period - self.width),
+        }
+    }
+
+    // The scheduled times of the rising and falling edges of the pulse, relative
+    // to the start of the train.
+    //
+    // The schedule is absolute, rather than accumulating sleeps, so latencies
+    // do not accumulate over the train.
+    fn schedule(&self, pulse: u32) -> (Duration, Duration) {
+        let rise = self.period * pulse;
+        (rise, rise + self.width)
+    }
+
+    // Drive the pulse train on the line, returning the rising and falling
+    // edges of each pulse.
+    fn drive(&self, req: &Request, offset: Offset) -> Result<Vec<(Edge, Edge)>> {
+        let mut edges = Vec::with_capacity(self.count as usize);
+        let start = Instant::now();
+        let set = |scheduled: Instant, value: Value| -> Result<Edge> {
+            thread::sleep(scheduled.saturating_duration_since(Instant::now()));
+            req.set_value(offset, value)
+                .context("failed to set line value")?;
+            Ok(Edge {
+                scheduled,
+                actual: Instant::now(),
+            })
+        };
+        for pulse in 0..self.count {
+            let (rise, fall) = self.schedule(pulse);
+            let rise = set(start + rise, Value::Active)?;
+            let fall = set(start + fall, Value::Inactive)?;
+            edges.push((rise, fall));
+        }
+        Ok(edges)
+    }
+}
+
+// The minimum, mean and maximum of a set of periods.
+#[derive(Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Summary {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nanos"))]
+    min: Duration,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nanos"))]
+    mean: Duration,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nanos"))]
+    max: Duration,
+}
+
+impl Summary {
+    fn new<I: Iterator<Item = Duration>>(periods: I) -> Option<Summary> {
+        let mut s = Summary {
+            min: Duration::MAX,
+            ..Default::default()
+        };
+        let mut total = Duration::ZERO;
+        let mut count = 0;
+        for p in periods {
+            s.min = min(s.min, p);
+            s.max = max(s.max, p);
+            total += p;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        s.mean = total / count;
+        Some(s)
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "min {:?}, mean {:?}, max {:?}",
+            self.min, self.mean, self.max
+        )
+    }
+}
+
+// The achieved timing of a pulse train.
+#[derive(Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+struct Stats {
+    pulses: u32,
+    #[cfg_attr(feature = "serde", serde(rename = "width_ns"))]
+    width: Summary,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "period_ns", skip_serializing_if = "Option::is_none")
+    )]
+    period: Option<Summary>,
+    // the greatest delay of an edge from its scheduled time
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "max_lateness_ns", serialize_with = "serialize_nanos")
+    )]
+    max_lateness: Duration,
+}
+
+impl Stats {
+    fn new(train: &PulseTrain, edges: &[(Edge, Edge)]) -> Stats {
+        let late = |e: &Edge| e.actual.saturating_duration_since(e.scheduled);
+        Stats {
+            pulses: train.count,
+            width: Summary::new(
+                edges
+                    .iter()
+                    .map(|(rise, fall)| fall.actual.saturating_duration_since(rise.actual)),
+            )
+            .unwrap_or_default(),
+            period: Summary::new(
+                edges
+                    .windows(2)
+                    .map(|w| w[1].0.actual.saturating_duration_since(w[0].0.actual)),
+            ),
+            max_lateness: edges
+                .iter()
+                .map(|(rise, fall)| max(late(rise), late(fall)))
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "json"), allow(unused_variables))]
+    fn emit(&self, opts: &Opts) {
+        #[cfg(feature = "json")]
+        if opts.emit.json {
+            println!("{}", serde_json::to_string(self).unwrap());
+            return;
+        }
+        self.print()
+    }
+
+    fn print(&self) {
+        println!("pulses: {}", self.pulses);
+        println!("width: {}", self.width.to_text());
+        if let Some(period) = &self.period {
+            println!("period: {}", period.to_text());
+        }
+        println!("max lateness: {:?}", self.max_lateness);
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_nanos<S: serde::Serializer>(
+    d: &Duration,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_u128(d.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let ms = Duration::from_millis;
+        let train = PulseTrain::new(1, ms(10), None).unwrap();
+        assert_eq!(train.count, 1);
+        assert_eq!(train.width, ms(10));
+        assert_eq!(train.period, ms(10));
+
+        let train = PulseTrain::new(4, ms(10), Some(ms(25))).unwrap();
+        assert_eq!(train.count, 4);
+        assert_eq!(train.width, ms(10));
+        assert_eq!(train.period, ms(25));
+
+        let err = |count, width, period| {
+            PulseTrain::new(count, width, period)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            err(4, ms(10), None),
+            "--period is required when --count is greater than one"
+        );
+        assert_eq!(
+            err(4, ms(10), Some(ms(10))),
+            "--width (10ms) must be less than --period (10ms)"
+        );
+        assert_eq!(
+            err(1, ms(10), Some(ms(5))),
+            "--width (10ms) must be less than --period (5ms)"
+        );
+        assert_eq!(
+            err(4, ms(0), Some(ms(10))),
+            "--width must be greater than zero"
+        );
+        assert_eq!(
+            err(u32::MAX, ms(10), Some(Duration::from_secs(u64::MAX / 2))),
+            "--period is too long for --count"
+        );
+    }
+
+    #[test]
+    fn shortest() {
+        let ms = Duration::from_millis;
+        let train = PulseTrain::new(1, ms(10), None).unwrap();
+        assert_eq!(train.shortest(), ms(10));
+        let train = PulseTrain::new(3, ms(10), Some(ms(25))).unwrap();
+        assert_eq!(train.shortest(), ms(10));
+        let train = PulseTrain::new(3, ms(20), Some(ms(25))).unwrap();
+        assert_eq!(train.shortest(), ms(5));
+    }
+
+    #[test]
+    fn schedule() {
+        let train = PulseTrain {
+            count: 3,
+            width: Duration::from_millis(2),
+            period: Duration::from_millis(5),
+        };
+        assert_eq!(
+            train.schedule(0),
+            (Duration::ZERO, Duration::from_millis(2))
+        );
+        assert_eq!(
+            train.schedule(1),
+            (Duration::from_millis(5), Duration::from_millis(7))
+        );
+        assert_eq!(
+            train.schedule(2),
+            (Duration::from_millis(10), Duration::from_millis(12))
+        );
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(Summary::new([].into_iter()), None);
+        let ms = Duration::from_millis;
+        assert_eq!(
+            Summary::new([ms(3), ms(1), ms(5)].into_iter()),
+            Some(Summary {
+                min: ms(1),
+                mean: ms(3),
+                max: ms(5),
+            })
+        );
+        assert_eq!(
+            Summary::new([ms(2)].into_iter()).unwrap().to_text(),
+            "min 2ms, mean 2ms, max 2ms"
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let edge = |scheduled: u64, actual: u64| Edge {
+            scheduled: start + ms(scheduled),
+            actual: start + ms(actual),
+        };
+        let train = PulseTrain {
+            count: 2,
+            width: ms(2),
+            period: ms(5),
+        };
+        let edges = [(edge(0, 1), edge(2, 2)), (edge(5, 5), edge(7, 10))];
+        assert_eq!(
+            Stats::new(&train, &edges),
+            Stats {
+                pulses: 2,
+                width: Summary {
+                    min: ms(1),
+                    mean: ms(3),
+                    max: ms(5),
+                },
+                period: Some(Summary {
+                    min: ms(4),
+                    mean: ms(4),
+                    max: ms(4),
+                }),
+                max_lateness: ms(3),
+            }
+        );
+
+        // a single pulse has no period
+        let train = PulseTrain { count: 1, ..train };
+        let stats = Stats::new(&train, &edges[..1]);
+        assert_eq!(stats.period, None);
+        assert_eq!(stats.max_lateness, ms(1));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn stats_json() {
+        let ms = Duration::from_millis;
+        let stats = Stats {
+            pulses: 2,
+            width: Summary {
+                min: ms(1),
+                mean: ms(3),
+                max: ms(5),
+            },
+            period: None,
+            max_lateness: Duration::from_micros(3),
+        };
+        assert_eq!(
+            serde_json::to_string(&stats).unwrap(),
+            "{\"pulses\":2,\"width_ns\":{\"min\":1000000,\"mean\":3000000,\"max\":5000000},\
+            \"max_lateness_ns\":3000}"
+        );
+    }
+}
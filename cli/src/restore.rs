@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{self, emit_error, LineOpts};
+use super::config::ConfigFile;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use gpiocdev::line::{self, Direction};
+use gpiocdev::request::RequestGroup;
+use gpiocdev::FoundLine;
+use std::path::PathBuf;
+use std::thread;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The file containing the line state, as written by save
+    ///
+    /// Files with a .json extension are parsed as JSON, all others as TOML.
+    #[arg(value_name = "path")]
+    path: PathBuf,
+
+    /// Restore the lines to this chip, rather than the chip they were saved from
+    ///
+    /// The chip may be identified by number, name, or path.
+    #[arg(short, long, value_name = "chip")]
+    chip: Option<String>,
+
+    /// The consumer label applied to requested lines.
+    ///
+    /// Defaults to "gpiocdev-restore", unless provided by the file.
+    #[arg(short = 'C', long, value_name = "name")]
+    consumer: Option<String>,
+
+    /// Restore the lines then exit.
+    ///
+    /// As with set --persist, whether a line holds its value after release
+    /// depends on the GPIO driver, so a warning is emitted that the lines
+    /// may revert.
+    #[arg(long, conflicts_with = "daemonize")]
+    persist: bool,
+
+    #[command(flatten)]
+    daemon_opts: common::DaemonOpts,
+
+    #[command(flatten)]
+    ready_opts: common::ReadyOpts,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Err(e) => {
+            emit_error(&opts.emit, &e);
+            false
+        }
+        Ok(x) => x,
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<bool> {
+    opts.ready_opts.check()?;
+    opts.daemon_opts.check(&opts.ready_opts)?;
+    let cf = ConfigFile::from_path(&opts.path)?;
+    // only outputs are driven, so the config of inputs cannot be retained.
+    for (id, spec) in &cf.lines {
+        if spec.direction() == Direction::Input {
+            eprintln!("warning: line {} is an input, so is not restored", id);
+        }
+    }
+    let line_cfgs = cf.line_configs(Direction::Output)?;
+    let ids: Vec<&str> = line_cfgs.iter().map(|(id, _lc)| id.as_str()).collect();
+    let line_opts = LineOpts {
+        chip: opts.chip.clone(),
+        strict: false,
+        by_name: false,
+        glob: false,
+        ignore_case: false,
+    };
+    let r = cf.resolve(&ids, &line_opts, &opts.uapi_opts);
+    if !r.errors.is_empty() {
+        for e in &r.errors {
+            emit_error(&opts.emit, e);
+        }
+        return Ok(false);
+    }
+    let line_cfgs = unheld_lines(&r, line_cfgs)?;
+    if line_cfgs.is_empty() {
+        bail!("no lines to restore");
+    }
+    let mut bld = RequestGroup::builder();
+    bld.with_consumer(
+        opts.consumer
+            .as_deref()
+            .or(cf.consumer.as_deref())
+            .unwrap_or("gpiocdev-restore"),
+    );
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(r.abiv);
+    for (id, lc) in &line_cfgs {
+        let co = &r.lines[id];
+        let mut info = line::Info::default();
+        info.offset = co.offset;
+        let fl = FoundLine {
+            chip: r.chips[co.chip_idx].path.clone(),
+            info,
+        };
+        bld.with_found_line(id, &fl).from_line_config(lc);
+    }
+    let _group = bld
+        .request()
+        .context("failed to request and restore lines")?;
+    opts.daemon_opts.daemonize()?;
+    // in the daemon, if daemonizing, so the lines remain requested.
+    opts.ready_opts.notify()?;
+    if opts.persist {
+        eprintln!("{}", super::set::PERSIST_WARNING);
+        return Ok(true);
+    }
+    loop {
+        thread::park();
+    }
+}
+
+// Filter out the lines already held by other consumers, with a warning.
+fn unheld_lines(
+    r: &common::Resolver,
+    line_cfgs: Vec<(String, line::Config)>,
+) -> Result<Vec<(String, line::Config)>> {
+    let mut chips = Vec::new();
+    for ci in &r.chips {
+        chips.push(common::chip_from_path(&ci.path, r.abiv)?);
+    }
+    let mut unheld = Vec::new();
+    for (id, lc) in line_cfgs {
+        let co = &r.lines[&id];
+        let info = chips[co.chip_idx].line_info(co.offset).map_err(|e| {
+            anyhow!(e).context(format!(
+                "failed to read info for line {} from {}",
+                co.offset, r.chips[co.chip_idx].name
+            ))
+        })?;
+        if info.used {
+            eprintln!(
+                "warning: line {} is held by \"{}\", so is not restored",
+                id, info.consumer
+            );
+            continue;
+        }
+        unheld.push((id, lc));
+    }
+    Ok(unheld)
+}
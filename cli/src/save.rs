@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::common::{self, emit_error};
+use super::config::{ConfigFile, LineSpec};
+use anyhow::{Context, Result};
+use clap::Parser;
+use gpiocdev::chip::Chip;
+use gpiocdev::line::{Direction, Info, Offset, Values};
+use gpiocdev::request::Request;
+use gpiocdev::AbiVersion;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Parser)]
+pub struct Opts {
+    /// The chip with the lines to save
+    ///
+    /// The chip may be identified by number, name, or path.
+    /// e.g. the following all select the same chip:
+    ///     --chip 0
+    ///     --chip gpiochip0
+    ///     --chip /dev/gpiochip0
+    #[arg(short, long, value_name = "chip", verbatim_doc_comment)]
+    chip: String,
+
+    /// The consumer label applied to lines while reading their values.
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "name",
+        default_value = "gpiocdev-save"
+    )]
+    consumer: String,
+
+    #[command(flatten)]
+    uapi_opts: common::UapiOpts,
+
+    #[command(flatten)]
+    emit: common::EmitOpts,
+}
+
+pub fn cmd(opts: &Opts) -> bool {
+    match do_cmd(opts) {
+        Err(e) => {
+            emit_error(&opts.emit, &e);
+            false
+        }
+        Ok(cf) => {
+            emit(opts, &cf);
+            true
+        }
+    }
+}
+
+fn do_cmd(opts: &Opts) -> Result<ConfigFile> {
+    let path = common::chip_lookup_from_id(&opts.chip)?;
+    let abiv = common::actual_abi_version(&opts.uapi_opts)?;
+    let chip = common::chip_from_path(&path, abiv)?;
+    let name = chip.name();
+    let infos = chip
+        .line_info_iter()?
+        .collect::<gpiocdev::Result<Vec<Info>>>()
+        .with_context(|| format!("failed to read line info from {}", name))?;
+    let values = read_output_values(opts, &chip, abiv, &infos)?;
+    // lines are keyed by name, unless the name is not unique, or is empty.
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for info in &infos {
+        *names.entry(info.name.as_str()).or_default() += 1;
+    }
+    let mut cf = ConfigFile::default();
+    for info in &infos {
+        if info.used && info.direction == Direction::Output {
+            eprintln!(
+                "warning: line {} is an output held by \"{}\", so its value cannot be read and it is not saved",
+                info.offset, info.consumer
+            );
+            continue;
+        }
+        let key = if !info.name.is_empty() && names[info.name.as_str()] == 1 {
+            info.name.clone()
+        } else {
+            info.offset.to_string()
+        };
+        let spec = LineSpec::from_info(&name, info, values.get(info.offset));
+        cf.lines.insert(key, spec);
+    }
+    Ok(cf)
+}
+
+// Read the values of the output lines not held by other consumers.
+//
+// The lines are requested as-is, so their configuration is unchanged.
+fn read_output_values(
+    opts: &Opts,
+    chip: &Chip,
+    abiv: AbiVersion,
+    infos: &[Info],
+) -> Result<Values> {
+    let offsets: Vec<Offset> = infos
+        .iter()
+        .filter(|info| !info.used && info.direction == Direction::Output)
+        .map(|info| info.offset)
+        .collect();
+    let mut values = Values::default();
+    if offsets.is_empty() {
+        return Ok(values);
+    }
+    let req = request_as_is(chip.path(), abiv, &offsets, &opts.consumer)
+        .with_context(|| format!("failed to request output lines from {}", chip.name()))?;
+    req.values(&mut values)
+        .with_context(|| format!("failed to read output values from {}", chip.name()))?;
+    Ok(values)
+}
+
+#[cfg_attr(
+    not(all(feature = "uapi_v1", feature = "uapi_v2")),
+    allow(unused_variables)
+)]
+fn request_as_is(
+    path: &Path,
+    abiv: AbiVersion,
+    offsets: &[Offset],
+    consumer: &str,
+) -> gpiocdev::Result<Request> {
+    let mut bld = Request::builder();
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    bld.using_abi_version(abiv);
+    bld.on_chip(path)
+        .with_consumer(consumer)
+        .with_lines(offsets)
+        .as_is()
+        .request()
+}
+
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+fn emit(opts: &Opts, cf: &ConfigFile) {
+    #[cfg(feature = "json")]
+    if opts.emit.json {
+        println!("{}", serde_json::to_string_pretty(cf).unwrap());
+        return;
+    }
+    print!("{}", toml::to_string(cf).unwrap());
+}
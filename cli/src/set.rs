@@ -6,21 +6,33 @@ mod editor;
 use self::editor::{CommandWords, Editor};
 
 use super::common::{self, emit_error, EmitOpts, ParseDurationError};
+#[cfg(feature = "config")]
+use super::config::ConfigFile;
 use anyhow::{anyhow, bail, Context, Result};
-use clap::{Arg, ArgAction, Command, Parser};
-use daemonize::Daemonize;
-use gpiocdev::line::{Offset, Value, Values};
-use gpiocdev::request::{Config, Request};
+use clap::builder::EnumValueParser;
+use clap::{Arg, ArgAction, Command, Parser, ValueEnum};
+#[cfg(feature = "config")]
+use gpiocdev::line::Direction;
+use gpiocdev::line::{self, InfoChangeKind, Offset, ParseValueError, Value, Values};
+use gpiocdev::request::{Config, RequestGroup};
+use gpiocdev::FoundLine;
+use mio::unix::SourceFd;
+use mio::{Events, Interest, Poll, Token};
 use std::cmp;
 use std::collections::HashMap;
 use std::error::Error;
-use std::fmt;
+use std::os::unix::prelude::AsRawFd;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::str::FromStr;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Parser)]
 #[command(alias("s"))]
+// reattaching only applies when holding the lines
+#[command(group(clap::ArgGroup::new("mode").conflicts_with("auto_reattach")))]
 pub struct Opts {
     /// The line values.
     ///
@@ -28,11 +40,45 @@ pub struct Opts {
     /// format if the --chip option is provided.
     ///
     /// Values may be inactive/off/false/0 or active/on/true/1.
+    /// Values may also be as-is, to retain the current value of the line,
+    /// or toggle, to invert it.
     /// e.g.
     ///     GPIO17=on GPIO22=inactive
     ///     --chip gpiochip0 17=1 22=0
-    #[arg(value_name = "line=value", required = true, value_parser = parse_line_value, verbatim_doc_comment)]
-    line_values: Vec<(String, LineValue)>,
+    ///     GPIO17=as-is GPIO22=toggle
+    ///
+    /// The value may be followed by a comma separated list of attributes
+    /// that override the corresponding options for that line only.
+    /// The attributes are active-low, active-high, pull-up, pull-down,
+    /// bias-disabled, push-pull, open-drain and open-source.
+    /// e.g.
+    ///     GPIO17=1,active-low GPIO22=0
+    ///     --bias pull-up GPIO17=1,pull-down,open-drain GPIO22=0
+    ///
+    /// Lines are only driven once the current values have been read, so
+    /// with as-is or toggle any lines that were inputs briefly remain
+    /// inputs after being requested.
+    #[arg(value_name = "line=value", value_parser = parse_line_setting, verbatim_doc_comment)]
+    #[cfg_attr(
+        not(feature = "config"),
+        arg(required_unless_present = "sequence", conflicts_with = "sequence")
+    )]
+    #[cfg_attr(
+        feature = "config",
+        arg(
+            required_unless_present_any = ["config", "sequence"],
+            conflicts_with_all = ["config", "sequence"]
+        )
+    )]
+    line_values: Vec<(String, LineValue, LineOverrides)>,
+
+    /// Read the lines to set from a TOML or JSON configuration file
+    ///
+    /// The output lines described in the file are requested and set.
+    /// Options provided on the command line override those from the file.
+    #[cfg(feature = "config")]
+    #[arg(long, value_name = "path", conflicts_with = "auto_reattach")]
+    config: Option<PathBuf>,
 
     /// Display a banner on successful startup
     #[arg(long)]
@@ -54,7 +100,7 @@ pub struct Opts {
     ///
     /// Use the "help" command at the interactive prompt to get help for
     /// the supported commands.
-    #[arg(short, long, groups = ["mode", "terminal", "emit"])]
+    #[arg(short, long, groups = ["mode", "emit"], conflicts_with = "daemonize")]
     interactive: bool,
 
     /// The minimum time period to hold lines at the requested values.
@@ -63,6 +109,23 @@ pub struct Opts {
     #[arg(short = 'p', long, value_name = "period", value_parser = common::parse_duration)]
     hold_period: Option<Duration>,
 
+    /// Activate the lines one at a time, with the specified period between each line.
+    ///
+    /// This limits the inrush current when switching on many loads, such as relays,
+    /// at once.
+    /// The lines are requested as-is, so lines that are already active, such as relays
+    /// that are already on, are left active.
+    /// The remaining lines to be set active are set inactive, then activated in command
+    /// line order.
+    /// Lines to be set inactive are set once the lines are requested.
+    ///
+    /// This intentionally sacrifices setting the lines atomically.
+    /// Only applies to the initial values.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    #[arg(long, value_name = "period", value_parser = common::parse_duration)]
+    stagger: Option<Duration>,
+
     /// Toggle the lines after the specified time periods.
     ///
     /// The time periods are a comma separated list, and are taken as
@@ -83,13 +146,147 @@ pub struct Opts {
     #[arg(short = 't', long, value_name = "periods", value_parser = parse_time_sequence, group = "mode", verbatim_doc_comment)]
     toggle: Option<TimeSequence>,
 
-    /// Set line values then detach from the controlling terminal.
-    #[arg(short = 'z', long, group = "terminal")]
-    daemonize: bool,
+    /// Repeat the toggle sequence the specified number of times then exit.
+    ///
+    /// The lines are returned to their initial values before exiting.
+    ///
+    /// Cannot be used with a toggle sequence with a final zero period.
+    #[arg(
+        long = "loop",
+        value_name = "count",
+        requires = "toggle",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    loop_count: Option<u32>,
+
+    /// Drive a single pulse then exit.
+    ///
+    /// The lines are set to the requested values and held for the period,
+    /// then inverted once and the command exits.
+    /// The period is taken as milliseconds unless otherwise specified.
+    ///
+    /// If a --hold-period is also specified then the requested values are held
+    /// for the longer of the two periods, and the inverted values are held for
+    /// the --hold-period before exiting.
+    #[arg(long, value_name = "period", value_parser = common::parse_duration, group = "mode")]
+    pulse: Option<Duration>,
+
+    /// Drive the lines through a sequence of values then exit.
+    ///
+    /// The sequence is a ';' separated list of groups, alternating between
+    /// line values and the period to hold them.
+    /// The line values are a comma separated list of line=value pairs, and
+    /// the periods are taken as milliseconds unless otherwise specified.
+    /// The sequence must start with line values, which are used to request
+    /// the lines, so must include all the lines in the sequence.
+    ///
+    ///  e.g.
+    ///      -c gpiochip0 --sequence "17=1,22=0; 500ms; 17=0,22=1; 500ms; 17=0,22=0"
+    ///
+    /// The command exits after the final group, unless --repeat is specified.
+    #[arg(long, value_name = "sequence", value_parser = parse_sequence, group = "mode", verbatim_doc_comment)]
+    #[cfg_attr(feature = "config", arg(conflicts_with = "config"))]
+    sequence: Option<Sequence>,
+
+    /// Run the sequence the specified number of times, or forever, then exit.
+    #[arg(long, value_name = "count", requires = "sequence", value_parser = parse_repeat)]
+    repeat: Option<Repeat>,
+
+    /// Drive the lines with a pulse width modulated signal.
+    ///
+    /// The signal is specified as <freq>:<duty>, where the frequency is in Hz
+    /// and the duty cycle is the percentage of each cycle the line is active,
+    /// optionally suffixed with '%'.
+    /// A signal prefixed with a line, as <line>=<freq>:<duty>, applies to that line,
+    /// otherwise it applies to all requested lines without their own signal.
+    /// Lines requested active start with the active part of the cycle, and lines
+    /// requested inactive with the inactive part.
+    ///
+    ///  e.g.
+    ///      --pwm 50:25
+    ///      --pwm GPIO17=1000:12.5% --pwm GPIO22=10:50
+    ///
+    /// The periods are timed using sleeps, so are subject to scheduling latency,
+    /// typically tens of microseconds or more, so signals with short periods
+    /// will be imprecise.
+    #[arg(long, value_name = "[line=]freq:duty", value_parser = parse_pwm, group = "mode", conflicts_with = "hold_period", verbatim_doc_comment)]
+    pwm: Vec<PwmSpec>,
+
+    /// Set line values, hold them for any --hold-period, then exit.
+    ///
+    /// The lines are released when the command exits, and the GPIO character
+    /// device uAPI, as of Linux 6.x, provides no means to retain output values
+    /// beyond the release. Whether a line holds its value after release depends
+    /// on the GPIO driver, so a warning is emitted that the lines may revert.
+    /// Use --daemonize to keep the lines requested, and so driven, after the
+    /// command returns.
+    #[arg(long, group = "mode", conflicts_with = "daemonize")]
+    persist: bool,
+
+    /// Check the lines for conflicts with their existing configuration before requesting them.
+    ///
+    /// A line conflicts if it is already requested by another consumer, is an
+    /// input with edge detection enabled, or is configured with an active-low
+    /// or drive setting that contradicts the requested setting.
+    ///
+    /// By default conflicts are errors and the lines are not requested.
+    /// With --check-conflicts=warn the conflicts are reported as warnings and
+    /// the lines are requested regardless.
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "fail"
+    )]
+    check_conflicts: Option<ConflictMode>,
+
+    /// Wait for the lines to be released by their current consumers before requesting them.
+    ///
+    /// The lines are watched for info changes, so the lines are requested
+    /// as soon as the last of them is released.
+    #[arg(long)]
+    wait_for_free: bool,
+
+    /// The maximum time to wait for the lines to be released.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    /// By default the wait is indefinite.
+    #[arg(long, value_name = "period", requires = "wait_for_free", value_parser = common::parse_duration)]
+    wait_timeout: Option<Duration>,
+
+    /// Release the lines and exit after the lines have been held for the specified period.
+    ///
+    /// The period is taken as milliseconds unless otherwise specified.
+    /// By default the lines are held until the command is killed.
+    ///
+    /// Applies to holding the lines at the set values, including when daemonized,
+    /// and to --pwm.
+    #[arg(
+        long,
+        value_name = "period",
+        value_parser = common::parse_duration,
+        conflicts_with_all = ["interactive", "persist", "toggle", "pulse", "sequence"]
+    )]
+    timeout: Option<Duration>,
+
+    #[command(flatten)]
+    daemon_opts: common::DaemonOpts,
 
     /// The consumer label applied to requested lines.
-    #[arg(short = 'C', long, value_name = "name", default_value = "gpiocdev-set")]
-    consumer: String,
+    ///
+    /// Defaults to "gpiocdev-set", unless provided by a configuration file.
+    #[arg(short = 'C', long, value_name = "name")]
+    consumer: Option<String>,
+
+    #[command(flatten)]
+    retry_opts: common::RetryOpts,
+
+    #[command(flatten)]
+    ready_opts: common::ReadyOpts,
+
+    #[command(flatten)]
+    reattach_opts: common::ReattachOpts,
 
     #[command(flatten)]
     uapi_opts: common::UapiOpts,
@@ -98,56 +295,269 @@ pub struct Opts {
     emit: common::EmitOpts,
 }
 
+// How conflicts with the existing line configuration are handled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum ConflictMode {
+    // report the conflicts as errors and do not request the lines
+    Fail,
+    // report the conflicts as warnings and request the lines regardless
+    Warn,
+}
+
 impl Opts {
-    // mutate the config to match the configuration
-    fn apply(&self, config: &mut Config) {
-        self.active_low_opts.apply(config);
-        self.bias_opts.apply(config);
-        self.drive_opts.apply(config);
+    fn consumer<'a>(&'a self, file_consumer: Option<&'a str>) -> &'a str {
+        self.consumer
+            .as_deref()
+            .or(file_consumer)
+            .unwrap_or("gpiocdev-set")
+    }
+
+    // mutate the line config to match the configuration
+    fn apply(&self, lc: &mut line::Config) {
+        if self.active_low_opts.active_low {
+            lc.active_low = true;
+        }
+        if let Some(bias) = self.bias_opts.bias {
+            lc.bias = Some(bias.into());
+        }
+        if let Some(drive) = self.drive_opts.drive {
+            lc.with_drive(drive.into());
+        }
     }
 }
 
-pub fn cmd(opts: &Opts) -> bool {
+pub fn cmd(opts: &Opts) -> ExitCode {
     match do_cmd(opts) {
         Err(e) => {
             emit_error(&opts.emit, &e);
-            false
+            common::exit_code(&e)
         }
-        Ok(x) => x,
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
     }
 }
 
 fn do_cmd(opts: &Opts) -> Result<bool> {
+    if let Some(ts) = &opts.toggle {
+        ts.check_loop_count(opts.loop_count)?;
+    }
+    // clap ignores the requires as --sequence conflicts with the line values
+    if opts.repeat.is_some() && opts.sequence.is_none() {
+        bail!("--repeat requires --sequence");
+    }
+    opts.ready_opts.check()?;
+    opts.daemon_opts.check(&opts.ready_opts)?;
+    opts.reattach_opts.check(&opts.line_opts)?;
     let mut setter = Setter {
         hold_period: opts.hold_period,
+        timeout: opts.timeout,
         ..Default::default()
     };
     if !setter.request(opts)? {
         return Ok(false);
     }
     if opts.banner {
-        let line_ids: Vec<String> = opts
-            .line_values
-            .iter()
-            .map(|(l, _v)| l.to_owned())
-            .collect();
-        print_banner(&line_ids);
-    }
-    if opts.daemonize {
-        Daemonize::new().start()?;
+        common::print_banner("Setting", setter.group.lines());
     }
+    opts.daemon_opts.daemonize()?;
+    // in the daemon, if daemonizing, so the lines remain requested.
+    opts.ready_opts.notify()?;
     if let Some(ts) = &opts.toggle {
-        return setter.toggle(ts);
+        return setter.toggle(ts, opts.loop_count);
+    }
+    if let Some(period) = opts.pulse {
+        return setter.toggle(&TimeSequence::pulse(period), None);
+    }
+    if !opts.pwm.is_empty() {
+        return setter.pwm(&opts.pwm);
+    }
+    if let Some(seq) = &opts.sequence {
+        return setter.run_sequence(seq, opts.repeat);
     }
     setter.hold();
+    if opts.persist {
+        eprintln!("{}", PERSIST_WARNING);
+        return Ok(true);
+    }
     if opts.interactive {
         return setter.interact(opts);
     }
-    loop {
-        thread::park();
+    if opts.reattach_opts.auto_reattach {
+        return setter.hold_and_reattach(opts);
     }
+    setter.hold_until_removed()
 }
 
+// Check the requested lines for conflicts with their existing configuration.
+//
+// Returns false if the lines should not be requested.
+fn check_conflicts(
+    opts: &Opts,
+    mode: ConflictMode,
+    r: &common::Resolver,
+    requested: &[(String, line::Config)],
+) -> bool {
+    let mut errs = Vec::new();
+    let mut conflicts = Vec::new();
+    for (idx, ci) in r.chips.iter().enumerate() {
+        let lines: Vec<_> = requested
+            .iter()
+            .filter(|(id, _)| r.lines[id].chip_idx == idx)
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let chip = match common::chip_from_path(&ci.path, r.abiv) {
+            Ok(chip) => chip,
+            Err(e) => {
+                errs.push(e);
+                continue;
+            }
+        };
+        for (id, lc) in lines {
+            let offset = r.lines[id].offset;
+            match chip.line_info(offset) {
+                Ok(info) => conflicts.extend(line_conflicts(id, &info, lc)),
+                Err(e) => errs.push(anyhow!(e).context(format!(
+                    "failed to read info for line {} from {}",
+                    offset, ci.name
+                ))),
+            }
+        }
+    }
+    match mode {
+        ConflictMode::Fail => errs.extend(conflicts.into_iter().map(|c| anyhow!(c))),
+        ConflictMode::Warn => {
+            for c in conflicts {
+                eprintln!("warning: {}", c);
+            }
+        }
+    }
+    emit_errors(&opts.emit, &errs);
+    errs.is_empty()
+}
+
+// Wait until none of the lines are requested by another consumer.
+//
+// The chips are waited on in turn. Lines on a chip that are requested by
+// another consumer while waiting on that chip are added back to the wait.
+fn wait_for_free(r: &common::Resolver, ids: &[&str], timeout: Option<Duration>) -> Result<()> {
+    // a deadline beyond the range of Instant is treated as no deadline
+    let deadline = timeout.and_then(|t| Instant::now().checked_add(t));
+    for (idx, ci) in r.chips.iter().enumerate() {
+        let lines: Vec<(&str, Offset)> = ids
+            .iter()
+            .map(|id| (*id, &r.lines[*id]))
+            .filter(|(_, co)| co.chip_idx == idx)
+            .map(|(id, co)| (id, co.offset))
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let chip = common::chip_from_path(&ci.path, r.abiv)?;
+        let mut busy = Vec::new();
+        for (id, offset) in &lines {
+            let info = chip
+                .watch_line_info(*offset)
+                .with_context(|| format!("failed to watch line {} on {}", offset, ci.name))?;
+            if info.used {
+                busy.push(*id);
+            }
+        }
+        while !busy.is_empty() {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if !chip
+                    .wait_line_info_change_event(remaining)
+                    .context("failed to wait for line info change")?
+                {
+                    bail!(
+                        "timed out waiting for {} to be released",
+                        busy.iter()
+                            .map(|id| format!("line {}", id))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    );
+                }
+            }
+            let event = chip
+                .read_line_info_change_event()
+                .context("failed to read line info change")?;
+            let id = match lines
+                .iter()
+                .find(|(_, offset)| *offset == event.info.offset)
+            {
+                Some((id, _)) => *id,
+                None => continue,
+            };
+            match event.kind {
+                InfoChangeKind::Released => busy.retain(|b| *b != id),
+                InfoChangeKind::Requested if !busy.contains(&id) => busy.push(id),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// The conflicts between the existing configuration of a line and the requested configuration.
+fn line_conflicts(id: &str, info: &line::Info, lc: &line::Config) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    // the kernel resets the configuration of released lines, so only lines in use
+    // can have a contradictory configuration.
+    if !info.used {
+        return conflicts;
+    }
+    conflicts.push(format!(
+        "line {} is already requested by \"{}\"",
+        id, info.consumer
+    ));
+    if info.direction == line::Direction::Input && info.edge_detection.is_some() {
+        conflicts.push(format!(
+            "line {} is an input with edge detection enabled",
+            id
+        ));
+    }
+    let active_name = |active_low| {
+        if active_low {
+            "active-low"
+        } else {
+            "active-high"
+        }
+    };
+    if info.active_low != lc.active_low {
+        conflicts.push(format!(
+            "line {} is {}, but {} was requested",
+            id,
+            active_name(info.active_low),
+            active_name(lc.active_low)
+        ));
+    }
+    if info.direction == line::Direction::Output {
+        let existing = info.drive.unwrap_or_default();
+        let drive = lc.drive.unwrap_or_default();
+        if existing != drive {
+            conflicts.push(format!(
+                "line {} is {}, but {} was requested",
+                id,
+                drive_name(existing),
+                drive_name(drive)
+            ));
+        }
+    }
+    conflicts
+}
+
+fn drive_name(drive: line::Drive) -> &'static str {
+    match drive {
+        line::Drive::PushPull => "push-pull",
+        line::Drive::OpenDrain => "open-drain",
+        line::Drive::OpenSource => "open-source",
+    }
+}
+
+pub(crate) const PERSIST_WARNING: &str = "warning: the kernel does not retain output values after lines are released, so the lines may revert on exit - use --daemonize to keep the lines driven.";
+
 fn emit_errors(opts: &EmitOpts, errs: &[anyhow::Error]) {
     for e in errs {
         emit_error(opts, e);
@@ -156,81 +566,221 @@ fn emit_errors(opts: &EmitOpts, errs: &[anyhow::Error]) {
 
 #[derive(Default)]
 struct Setter {
-    // IDs of requested lines - in command line order
-    line_ids: Vec<String>,
-
     // Map from command line name to top level line details
     lines: HashMap<String, Line>,
 
-    // The list of chips containing requested lines
-    chips: Vec<common::ChipInfo>,
-
-    // The request on each chip
-    requests: Vec<Request>,
+    // The requested lines - in command line order
+    group: RequestGroup,
 
     // The minimum period to hold set values before applying the subsequent set
     hold_period: Option<Duration>,
 
+    // The period to hold the lines before releasing them, else indefinitely
+    timeout: Option<Duration>,
+
     // Flag indicating if last operation resulted in a hold
     last_held: bool,
+
+    // Flag indicating if values are displayed as '0' or '1' by get
+    numeric: bool,
 }
 
 impl Setter {
     fn request(&mut self, opts: &Opts) -> Result<bool> {
-        self.line_ids = opts
-            .line_values
+        #[cfg(feature = "config")]
+        if let Some(path) = &opts.config {
+            let cf = ConfigFile::from_path(path)?;
+            let line_cfgs = cf.line_configs(Direction::Output)?;
+            let ids: Vec<&str> = line_cfgs.iter().map(|(id, _lc)| id.as_str()).collect();
+            let r = cf.resolve(&ids, &opts.line_opts, &opts.uapi_opts);
+            return self.request_lines(
+                opts,
+                line_cfgs,
+                r,
+                cf.consumer.as_deref(),
+                &HashMap::new(),
+                &HashMap::new(),
+            );
+        }
+        // the lines are requested with the initial values of any sequence
+        let no_overrides = LineOverrides::default();
+        let line_values: Vec<(&String, &LineValue, &LineOverrides)> = match &opts.sequence {
+            Some(seq) => seq
+                .initial_values()
+                .iter()
+                .map(|(l, v)| (l, v, &no_overrides))
+                .collect(),
+            None => opts.line_values.iter().map(|(l, v, o)| (l, v, o)).collect(),
+        };
+        let ids: Vec<String> = line_values
             .iter()
-            .map(|(l, _v)| l.to_owned())
+            .map(|(l, _v, _o)| l.to_string())
             .collect();
-        let r = common::Resolver::resolve_lines(&self.line_ids, &opts.line_opts, &opts.uapi_opts);
+        let matches = common::Resolver::glob_matches(&ids, &opts.line_opts, &opts.uapi_opts)?;
+        let mut line_cfgs: Vec<(String, line::Config)> = Vec::new();
+        // lines set relative to their current value
+        let mut relative: HashMap<String, LineValue> = HashMap::new();
+        // lines with attributes overriding the command line options
+        let mut overrides: HashMap<String, LineOverrides> = HashMap::new();
+        for ((id, v, o), names) in line_values.into_iter().zip(matches) {
+            let is_glob = opts.line_opts.glob && common::glob::is_pattern(id);
+            for name in names {
+                // lines matched by an earlier pattern or line retain that value.
+                if is_glob && line_cfgs.iter().any(|(l, _lc)| l == &name) {
+                    continue;
+                }
+                let mut lc = line::Config::default();
+                match v {
+                    LineValue::Value(v) => {
+                        lc.as_output(*v);
+                    }
+                    _ => {
+                        lc.as_is();
+                        relative.insert(name.clone(), v.clone());
+                    }
+                }
+                if !o.is_empty() {
+                    overrides.insert(name.clone(), o.clone());
+                }
+                line_cfgs.push((name, lc));
+            }
+        }
+        let line_ids: Vec<String> = line_cfgs.iter().map(|(l, _lc)| l.to_owned()).collect();
+        let r = common::Resolver::resolve_lines(&line_ids, &opts.line_opts, &opts.uapi_opts);
+        self.request_lines(opts, line_cfgs, r, None, &relative, &overrides)
+    }
+
+    fn request_lines(
+        &mut self,
+        opts: &Opts,
+        line_cfgs: Vec<(String, line::Config)>,
+        r: common::Resolver,
+        file_consumer: Option<&str>,
+        relative: &HashMap<String, LineValue>,
+        overrides: &HashMap<String, LineOverrides>,
+    ) -> Result<bool> {
         if !r.errors.is_empty() {
             emit_errors(&opts.emit, &r.errors);
             return Ok(false);
         }
-        self.chips = r.chips;
+        if opts.wait_for_free {
+            let ids: Vec<&str> = line_cfgs.iter().map(|(id, _lc)| id.as_str()).collect();
+            wait_for_free(&r, &ids, opts.wait_timeout)?;
+        }
 
-        // find set of lines for each chip
-        for (id, v) in &opts.line_values {
-            let co = r.lines.get(id).unwrap();
+        let mut bld = RequestGroup::builder();
+        bld.with_consumer(opts.consumer(file_consumer))
+            .with_retry(opts.retry_opts.retry, opts.retry_opts.retry_delay);
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        bld.using_abi_version(r.abiv);
+        // the final config of the lines, if requested as-is
+        let mut cfgs: HashMap<String, line::Config> = HashMap::new();
+        // the requested config of the lines, for checking conflicts
+        let mut requested: Vec<(String, line::Config)> = Vec::new();
+        // the lines to be activated one at a time, in command line order
+        let mut staggered: Vec<String> = Vec::new();
+        // lines are requested as-is if they depend on their current values
+        let as_is = !relative.is_empty() || opts.stagger.is_some();
+        for (id, mut lc) in line_cfgs {
+            let co = r.lines.get(&id).unwrap();
+            let mut info = gpiocdev::line::Info::default();
+            info.offset = co.offset;
+            let fl = FoundLine {
+                chip: r.chips[co.chip_idx].path.clone(),
+                info,
+            };
+            // command line options override any config file
+            opts.apply(&mut lc);
+            // and per-line attributes override the command line options
+            if let Some(o) = overrides.get(&id) {
+                o.apply(&mut lc);
+            }
+            if opts.check_conflicts.is_some() {
+                requested.push((id.clone(), lc.clone()));
+            }
+            let value = lc.value.unwrap_or(Value::Inactive);
+            if !as_is {
+                bld.with_found_line(&id, &fl).from_line_config(&lc);
+            } else {
+                // all lines are requested as-is so they share a config with uAPI v1,
+                // and are driven once the current values are known.
+                let mut as_is = lc.clone();
+                as_is.as_is();
+                // bias requires a direction, so is applied with the reconfigure.
+                as_is.bias = None;
+                bld.with_found_line(&id, &fl).from_line_config(&as_is);
+                cfgs.insert(id.clone(), lc);
+            }
             self.lines.insert(
-                id.to_owned(),
+                id,
                 Line {
-                    chip_idx: co.chip_idx,
-                    offset: co.offset,
-                    value: v.0,
+                    value,
                     dirty: false,
                 },
             );
         }
-
-        // request the lines
-        for (idx, ci) in self.chips.iter().enumerate() {
-            let mut cfg = Config::default();
-            opts.apply(&mut cfg);
-            for line in self.lines.values() {
-                if line.chip_idx == idx {
-                    cfg.with_line(line.offset).as_output(line.value);
+        if let Some(mode) = opts.check_conflicts {
+            if !check_conflicts(opts, mode, &r, &requested) {
+                return Ok(false);
+            }
+        }
+        self.group = bld.request().context("failed to request and set lines")?;
+        if as_is {
+            let values = self
+                .group
+                .values()
+                .context("failed to read current line values")?;
+            for (id, lv) in relative {
+                if let (Some(line), Some(current)) = (self.lines.get_mut(id), values.get(id)) {
+                    line.value = lv.value(*current);
                 }
             }
-            let mut bld = Request::from_config(cfg);
-            bld.on_chip(&ci.path).with_consumer(&opts.consumer);
-            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-            bld.using_abi_version(r.abiv);
-            let req = bld
-                .request()
-                .with_context(|| format!("failed to request and set lines on {}", ci.name))?;
-            self.requests.push(req);
+            // lines that are already active are left active, rather than being staggered
+            let is_staggered = |id: &String| {
+                opts.stagger.is_some()
+                    && self.lines[id].value == Value::Active
+                    && values.get(id) != Some(&Value::Active)
+            };
+            for (id, lc) in cfgs.iter_mut() {
+                if is_staggered(id) {
+                    lc.as_output(Value::Inactive);
+                } else {
+                    lc.as_output(self.lines[id].value);
+                }
+            }
+            self.group
+                .reconfigure(&cfgs)
+                .context("failed to set lines")?;
+            // cfgs is unordered, so take the command line order from the group
+            staggered = self
+                .group
+                .lines()
+                .iter()
+                .filter(|id| is_staggered(id))
+                .cloned()
+                .collect();
+        }
+        if let Some(period) = opts.stagger {
+            self.stagger(&staggered, period)?;
         }
         Ok(true)
     }
 
+    // Activate the lines one at a time, with the period between each line.
+    fn stagger(&self, lines: &[String], period: Duration) -> Result<()> {
+        for (idx, id) in lines.iter().enumerate() {
+            if idx > 0 {
+                thread::sleep(period);
+            }
+            self.group
+                .set_value(id, Value::Active)
+                .context("failed to set lines")?;
+        }
+        Ok(())
+    }
+
     fn interact(&mut self, opts: &Opts) -> Result<bool> {
-        let line_names = opts
-            .line_values
-            .iter()
-            .map(|(l, _v)| l.to_owned())
-            .collect();
-        let mut rl = Editor::new(line_names, "gpiocdev-set> ")?;
+        let mut rl = Editor::new(self.group.lines().to_vec(), "gpiocdev-set> ")?;
         let mut clcmd = Command::new("gpiocdev")
             .no_binary_name(true)
             .disable_help_flag(true)
@@ -239,6 +789,27 @@ impl Setter {
             .subcommand(
                 Command::new("get")
                     .about("Display the current values of the given requested lines")
+                    .arg(
+                        Arg::new("cached")
+                            .short('c')
+                            .long("cached")
+                            .action(ArgAction::SetTrue),
+                    )
+                    // the values are now sensed by default
+                    .arg(
+                        Arg::new("sensed")
+                            .short('s')
+                            .long("sensed")
+                            .hide(true)
+                            .conflicts_with("cached")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("numeric")
+                            .short('n')
+                            .long("numeric")
+                            .action(ArgAction::SetTrue),
+                    )
                     .arg(
                         Arg::new("lines")
                             .required(false)
@@ -280,50 +851,101 @@ impl Setter {
                             .value_parser(parse_line),
                     ),
             )
+            .subcommand(
+                Command::new("config")
+                    .about(
+                        "Update the configuration of the given requested lines\n\
+            If no lines are specified then all requested lines are updated.",
+                    )
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("bias")
+                            .about("Set the bias of the lines")
+                            .arg(
+                                Arg::new("bias")
+                                    .required(true)
+                                    .ignore_case(true)
+                                    .value_parser(EnumValueParser::<common::BiasFlags>::new()),
+                            )
+                            .arg(
+                                Arg::new("lines")
+                                    .required(false)
+                                    .action(ArgAction::Append)
+                                    .value_parser(parse_line),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("drive")
+                            .about("Set the drive of the lines")
+                            .arg(
+                                Arg::new("drive")
+                                    .required(true)
+                                    .ignore_case(true)
+                                    .value_parser(EnumValueParser::<common::DriveFlags>::new()),
+                            )
+                            .arg(
+                                Arg::new("lines")
+                                    .required(false)
+                                    .action(ArgAction::Append)
+                                    .value_parser(parse_line),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("format")
+                    .about("Set the format of values displayed by get")
+                    .arg(
+                        Arg::new("format")
+                            .required(true)
+                            .value_parser(["numeric", "named"]),
+                    ),
+            )
             .subcommand(Command::new("version").about("Print version"))
             .subcommand(Command::new("exit").about("Exit the program").alias("quit"));
         loop {
-            match self.parse_command(&mut clcmd, &rl.readline()?) {
-                Ok(am) => {
-                    if let Err(e) = self.do_command(am, opts) {
-                        println!("{e}");
-                        // clean in case the error leaves dirty lines.
-                        self.clean();
-                        return Ok(true);
-                    }
-                }
+            let line = rl.readline()?;
+            let steps = match parse_steps(&line) {
+                Ok(steps) => steps,
                 Err(e) => {
                     println!("{e}");
+                    continue;
+                }
+            };
+            if let Err(e) = self.do_steps(&mut clcmd, &steps, opts) {
+                println!("{e}");
+                // clean in case the error leaves dirty lines.
+                self.clean();
+                if e.downcast_ref::<clap::Error>().is_none() {
+                    return Ok(true);
                 }
             }
         }
     }
 
-    fn parse_command(&self, cmd: &mut Command, line: &str) -> Result<clap::ArgMatches> {
-        let mut words = CommandWords::new(line);
-        let mut args = Vec::new();
-        while let Some(word) = &words.next() {
-            args.push(*word);
-        }
-        if words.inquote {
-            return Err(anyhow!(format!(
-                "missing closing quote in '{}'",
-                args.last().unwrap()
-            )));
+    // Execute the steps, aborting on the first error.
+    fn do_steps(&mut self, cmd: &mut Command, steps: &[Step<'_>], opts: &Opts) -> Result<()> {
+        for step in steps {
+            match step {
+                Step::Command(args) => {
+                    let am = cmd.try_get_matches_from_mut(args)?;
+                    self.do_command(am, opts)?;
+                }
+                Step::Repeat(count, steps) => {
+                    for _ in 0..*count {
+                        self.do_steps(cmd, steps, opts)?;
+                    }
+                }
+            }
         }
-        Ok(cmd.try_get_matches_from_mut(args)?)
+        Ok(())
     }
 
     fn do_command(&mut self, args: clap::ArgMatches, opts: &Opts) -> Result<()> {
         if let Some((cmd, am)) = args.subcommand() {
             match cmd {
                 "get" => {
-                    let lines: Vec<String> = am
-                        .get_many::<String>("lines")
-                        .unwrap_or_default()
-                        .cloned()
-                        .collect();
-                    self.do_get(lines.as_slice(), opts)
+                    let numeric = self.numeric || am.get_flag("numeric");
+                    self.do_get(&lines_arg(am), am.get_flag("cached"), numeric, opts)
                 }
                 "set" => {
                     let lvs: Vec<(String, LineValue)> = am
@@ -337,13 +959,26 @@ impl Setter {
                     let d: Duration = am.get_one::<Duration>("duration").unwrap().to_owned();
                     self.do_sleep(d)
                 }
-                "toggle" => {
-                    let lines: Vec<String> = am
-                        .get_many::<String>("lines")
-                        .unwrap_or_default()
-                        .cloned()
-                        .collect();
-                    self.do_toggle(lines.as_slice())
+                "toggle" => self.do_toggle(&lines_arg(am)),
+                "config" => match am.subcommand() {
+                    Some(("bias", am)) => {
+                        let bias = *am.get_one::<common::BiasFlags>("bias").unwrap();
+                        self.do_config(&lines_arg(am), |cfg| {
+                            cfg.with_bias(Some(bias.into()));
+                        })
+                    }
+                    Some(("drive", am)) => {
+                        let drive = *am.get_one::<common::DriveFlags>("drive").unwrap();
+                        self.do_config(&lines_arg(am), |cfg| {
+                            cfg.with_drive(drive.into());
+                        })
+                    }
+                    // subcommand is required, so any error returned by clap
+                    _ => Ok(()),
+                },
+                "format" => {
+                    self.numeric = am.get_one::<String>("format").unwrap() == "numeric";
+                    Ok(())
                 }
                 "exit" => Err(CmdError::Exit().into()),
                 "version" => {
@@ -365,33 +1000,176 @@ impl Setter {
         }
     }
 
-    fn do_get(&mut self, lines: &[String], opts: &Opts) -> Result<()> {
-        let mut print_values = Vec::new();
-        for id in lines {
-            match self.lines.get(id) {
-                Some(line) => {
-                    print_values.push(format_line_value(&opts.emit, id, line.value));
+    // The time the lines should be released, if the timeout started now.
+    //
+    // A deadline beyond the range of Instant is treated as no deadline.
+    fn deadline(&self, now: Instant) -> Option<Instant> {
+        self.timeout.and_then(|t| now.checked_add(t))
+    }
+
+    // Hold the lines until the timeout expires, or indefinitely if there is no
+    // timeout, or until a chip is removed.
+    fn hold_until_removed(&self) -> Result<bool> {
+        let deadline = self.deadline(Instant::now());
+        self.hold_until(deadline, None).map(|_| true)
+    }
+
+    // Hold the lines as per hold_until_removed, but reattach the lines if a chip
+    // is removed or on SIGHUP.
+    fn hold_and_reattach(&mut self, opts: &Opts) -> Result<bool> {
+        let deadline = self.deadline(Instant::now());
+        let mut hangup = common::Hangup::new()?;
+        loop {
+            match self.hold_until(deadline, Some(&mut hangup)) {
+                Ok(Held::Expired) => return Ok(true),
+                Ok(Held::Hangup) => {}
+                Err(e) if common::is_chip_removed(&e) => emit_error(&opts.emit, &e),
+                Err(e) => return Err(e),
+            }
+            self.reattach(opts);
+        }
+    }
+
+    // Hold the lines until the deadline, a SIGHUP, or until a chip is removed,
+    // which is returned as an error.
+    //
+    // Output requests have no edge detection, so blocking on reading edge events
+    // would only park the thread. Instead the requests are polled, as they only
+    // become readable if their chip is removed, which is then reported when the
+    // values are read.
+    fn hold_until(
+        &self,
+        deadline: Option<Instant>,
+        mut hangup: Option<&mut common::Hangup>,
+    ) -> Result<Held> {
+        let mut poll = Poll::new().context("failed to create poll")?;
+        for (idx, req) in self.group.requests().iter().enumerate() {
+            poll.registry()
+                .register(
+                    &mut SourceFd(&req.as_raw_fd()),
+                    Token(idx),
+                    Interest::READABLE,
+                )
+                .context("failed to register request with poll")?;
+        }
+        let hangup_token = Token(self.group.requests().len());
+        if let Some(hangup) = &hangup {
+            poll.registry()
+                .register(
+                    &mut SourceFd(&hangup.as_raw_fd()),
+                    hangup_token,
+                    Interest::READABLE,
+                )
+                .context("failed to register SIGHUP with poll")?;
+        }
+        let mut events = Events::with_capacity(self.group.requests().len() + 1);
+        loop {
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(Held::Expired);
+                    }
+                    Some(deadline - now)
                 }
-                None => bail!(CmdError::NotRequestedLine(id.into())),
+                None => None,
+            };
+            if let Err(e) = poll.poll(&mut events, timeout) {
+                if e.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(anyhow!(e).context("failed to poll requests"));
+                }
+                continue;
+            }
+            if events.iter().any(|e| e.token() == hangup_token) {
+                if let Some(hangup) = hangup.as_mut() {
+                    hangup.clear();
+                }
+                return Ok(Held::Hangup);
+            }
+            if !events.is_empty() {
+                self.group.values().context("hold failed")?;
+            }
+        }
+    }
+
+    // Release the lines, then request them again, with their last known values,
+    // once they can be found.
+    fn reattach(&mut self, opts: &Opts) {
+        let ids = self.group.lines().to_vec();
+        let values: HashMap<String, Value> = self
+            .lines
+            .iter()
+            .map(|(id, line)| (id.to_owned(), line.value))
+            .collect();
+        // the lines are still held after a SIGHUP, so must be released first.
+        self.group = RequestGroup::default();
+        common::reattach(&ids, || {
+            let mut r = common::Resolver::resolve_lines(&ids, &opts.line_opts, &opts.uapi_opts);
+            if !r.errors.is_empty() {
+                return Err(r.errors.remove(0));
+            }
+            self.lines.clear();
+            if !self.request(opts)? {
+                bail!("failed to request lines");
+            }
+            self.restore(&values)
+        });
+    }
+
+    fn do_get(&mut self, lines: &[String], cached: bool, numeric: bool, opts: &Opts) -> Result<()> {
+        for id in lines {
+            if !self.lines.contains_key(id) {
+                bail!(CmdError::NotRequestedLine(id.into()));
             }
         }
-        if print_values.is_empty() {
+        let ids = if lines.is_empty() {
             // no lines specified, so return all lines
-            for id in &self.line_ids {
-                let value = self.lines.get(id).unwrap().value;
-                print_values.push(format_line_value(&opts.emit, id, value));
+            self.group.lines()
+        } else {
+            lines
+        };
+        // the levels read back from the kernel, rather than the values last set,
+        // with each request read separately so a failure on one chip does not
+        // prevent displaying the lines on the others.
+        let mut sensed = Vec::new();
+        let mut err = None;
+        if !cached {
+            for req in self.group.requests() {
+                let mut values = Values::from_offsets(req.config().lines());
+                match req.values(&mut values) {
+                    Ok(()) => sensed.push(Some(values)),
+                    Err(e) => {
+                        err.get_or_insert(e);
+                        sensed.push(None);
+                    }
+                }
             }
         }
-        println!("{}", print_values.join(" "));
-
-        Ok(())
+        let value = |id: &str| {
+            if cached {
+                return Some(self.lines.get(id).unwrap().value);
+            }
+            let (idx, offset) = self.group.locate(id).unwrap();
+            sensed[idx].as_ref().and_then(|values| values.get(offset))
+        };
+        let print_values: Vec<String> = ids
+            .iter()
+            .filter_map(|id| value(id).map(|v| format_line_value(&opts.emit, id, v, numeric)))
+            .collect();
+        if !print_values.is_empty() {
+            println!("{}", print_values.join(" "));
+        }
+        match err {
+            Some(e) => Err(anyhow!(e).context("get failed:")),
+            None => Ok(()),
+        }
     }
 
     fn do_set(&mut self, changes: &[(String, LineValue)]) -> Result<()> {
         for (id, value) in changes {
             match self.lines.get_mut(id) {
                 Some(line) => {
-                    line.value = value.0;
+                    line.value = value.value(line.value);
                     line.dirty = true;
                 }
                 None => bail!(CmdError::NotRequestedLine(id.into())),
@@ -422,7 +1200,7 @@ impl Setter {
         for id in lines {
             match self.lines.get_mut(id) {
                 Some(line) => {
-                    line.value = line.value.not();
+                    line.value.toggle();
                     line.dirty = true;
                 }
                 None => bail!(CmdError::NotRequestedLine(id.into())),
@@ -438,89 +1216,325 @@ impl Setter {
         Ok(())
     }
 
+    fn do_config<F: Fn(&mut Config)>(&mut self, lines: &[String], apply: F) -> Result<()> {
+        let requests = self.group.requests();
+        let mut offsets: Vec<Vec<Offset>> = vec![Vec::new(); requests.len()];
+        for id in lines {
+            match self.group.locate(id) {
+                Some((idx, offset)) => offsets[idx].push(offset),
+                None => bail!(CmdError::NotRequestedLine(id.into())),
+            }
+        }
+        if lines.is_empty() {
+            // no lines specified, so update all lines
+            for id in self.group.lines() {
+                let (idx, offset) = self.group.locate(id).unwrap();
+                offsets[idx].push(offset);
+            }
+        }
+        for (idx, req) in requests.iter().enumerate() {
+            if offsets[idx].is_empty() {
+                continue;
+            }
+            let mut cfg = req.config();
+            // the requested config holds the initial values, so refresh them
+            for (id, line) in self.lines.iter() {
+                let (lidx, offset) = self.group.locate(id).unwrap();
+                if lidx == idx {
+                    cfg.with_line(offset).as_output(line.value);
+                }
+            }
+            cfg.with_lines(&offsets[idx]);
+            apply(&mut cfg);
+            req.reconfigure(&cfg).context("config failed:")?;
+        }
+        Ok(())
+    }
+
     fn clean(&mut self) {
         for line in self.lines.values_mut() {
             line.dirty = false;
         }
     }
 
-    fn toggle(&mut self, ts: &TimeSequence) -> Result<bool> {
+    fn toggle(&mut self, ts: &TimeSequence, loop_count: Option<u32>) -> Result<bool> {
         if ts.0.len() == 1 && ts.0[0].is_zero() {
             self.hold();
             return Ok(true);
         }
-        let mut count = 0;
+        let initial: HashMap<String, Value> = self
+            .lines
+            .iter()
+            .map(|(id, line)| (id.to_owned(), line.value))
+            .collect();
         let hold_period = self.hold_period.unwrap_or(Duration::ZERO);
-        loop {
-            thread::sleep(cmp::max(ts.0[count], hold_period));
-            count += 1;
-            if count == ts.0.len() - 1 && ts.0[count].is_zero() {
-                return Ok(true);
-            }
-            if count == ts.0.len() {
-                count = 0;
+        let mut steps = ts.steps(loop_count).peekable();
+        while let Some(period) = steps.next() {
+            thread::sleep(cmp::max(period, hold_period));
+            if steps.peek().is_none() {
+                break;
             }
             self.toggle_all_lines();
             self.update()?;
         }
-    }
-
-    fn toggle_all_lines(&mut self) {
-        for line in self.lines.values_mut() {
-            line.value = line.value.not();
-            line.dirty = true;
+        if loop_count.is_some() {
+            self.restore(&initial)?;
         }
+        Ok(true)
     }
 
-    fn update(&mut self) -> Result<bool> {
-        let mut updated = false;
-        for idx in 0..self.chips.len() {
-            let mut values = Values::default();
-            for line in self.lines.values_mut() {
-                if line.dirty && line.chip_idx == idx {
-                    values.set(line.offset, line.value);
-                    line.dirty = false;
+    fn run_sequence(&mut self, seq: &Sequence, repeat: Option<Repeat>) -> Result<bool> {
+        // the initial values were applied by the request
+        self.hold();
+        let mut steps = &seq.0[1..];
+        let mut count = 0;
+        loop {
+            for step in steps {
+                match step {
+                    SequenceStep::Set(values) => self.do_set(values)?,
+                    SequenceStep::Sleep(period) => self.do_sleep(*period)?,
                 }
             }
-            if !values.is_empty() {
-                self.requests[idx]
-                    .set_values(&values)
-                    .context("set failed:")?;
-                updated = true;
+            count += 1;
+            match repeat {
+                Some(Repeat::Forever) => {}
+                Some(Repeat::Count(n)) if count < n => {}
+                _ => return Ok(true),
             }
+            steps = &seq.0;
         }
-        Ok(updated)
     }
-}
 
-#[derive(Debug, Eq, PartialEq, thiserror::Error)]
-pub enum CmdError {
-    #[error("")]
-    Exit(),
+    fn pwm(&mut self, specs: &[PwmSpec]) -> Result<bool> {
+        for spec in specs {
+            if let Some(id) = &spec.line {
+                if !self.lines.contains_key(id) {
+                    bail!(CmdError::NotRequestedLine(id.into()));
+                }
+            }
+        }
+        let default_spec = specs.iter().find(|spec| spec.line.is_none());
+        let start = Instant::now();
+        let deadline = self.deadline(start);
+        let mut pwms = Vec::new();
+        for id in self.group.lines() {
+            let spec = match specs
+                .iter()
+                .find(|spec| spec.line.as_ref() == Some(id))
+                .or(default_spec)
+            {
+                Some(spec) => spec,
+                None => continue,
+            };
+            let (active, inactive) = spec.periods();
+            let line = self.lines.get_mut(id).unwrap();
+            if active.is_zero() || inactive.is_zero() {
+                // 0% or 100% duty, so the line is never toggled.
+                let value = match active.is_zero() {
+                    true => Value::Inactive,
+                    false => Value::Active,
+                };
+                if line.value != value {
+                    line.value = value;
+                    line.dirty = true;
+                }
+                continue;
+            }
+            let period = match line.value {
+                Value::Active => active,
+                Value::Inactive => inactive,
+            };
+            pwms.push(PwmLine {
+                id: id.to_owned(),
+                active,
+                inactive,
+                next: start + period,
+            });
+        }
+        self.update()?;
+        if pwms.is_empty() {
+            return self.hold_until_removed();
+        }
+        loop {
+            // the toggle times are absolute, so sleep latencies do not accumulate.
+            let next = pwms.iter().map(|pwm| pwm.next).min().unwrap();
+            if let Some(deadline) = deadline.filter(|d| *d <= next) {
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                return Ok(true);
+            }
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+            let now = Instant::now();
+            for pwm in pwms.iter_mut().filter(|pwm| pwm.next <= now) {
+                let line = self.lines.get_mut(&pwm.id).unwrap();
+                line.value.toggle();
+                line.dirty = true;
+                pwm.next += match line.value {
+                    Value::Active => pwm.active,
+                    Value::Inactive => pwm.inactive,
+                };
+            }
+            self.update()?;
+        }
+    }
+
+    fn restore(&mut self, values: &HashMap<String, Value>) -> Result<bool> {
+        for (id, line) in self.lines.iter_mut() {
+            if let Some(value) = values.get(id) {
+                if line.value != *value {
+                    line.value = *value;
+                    line.dirty = true;
+                }
+            }
+        }
+        self.update()
+    }
+
+    fn toggle_all_lines(&mut self) {
+        for line in self.lines.values_mut() {
+            line.value.toggle();
+            line.dirty = true;
+        }
+    }
+
+    fn update(&mut self) -> Result<bool> {
+        let mut values = HashMap::new();
+        for (id, line) in self.lines.iter_mut() {
+            if line.dirty {
+                values.insert(id.as_str(), line.value);
+                line.dirty = false;
+            }
+        }
+        if values.is_empty() {
+            return Ok(false);
+        }
+        self.group.set_values(&values).context("set failed:")?;
+        Ok(true)
+    }
+}
+
+// How holding the lines ended, other than by the removal of a chip.
+enum Held {
+    // the deadline passed
+    Expired,
+    // a SIGHUP was received
+    Hangup,
+}
+
+#[derive(Debug, Eq, PartialEq, thiserror::Error)]
+pub enum CmdError {
+    #[error("")]
+    Exit(),
 
     #[error("not a requested line: '{0}'")]
     NotRequestedLine(String),
 }
 
+// A step in an interactive command line.
+#[derive(Debug, Eq, PartialEq)]
+enum Step<'a> {
+    // A command, split into words.
+    Command(Vec<&'a str>),
+
+    // Repeat the steps the given number of times.
+    Repeat(u32, Vec<Step<'a>>),
+}
+
+// Split an interactive command line into the steps to execute.
+fn parse_steps(line: &str) -> Result<Vec<Step<'_>>> {
+    let mut words = CommandWords::new(line);
+    let mut cmds = vec![Vec::new()];
+    let mut last = "";
+    for word in words.by_ref() {
+        if word == ";" {
+            cmds.push(Vec::new());
+        } else {
+            cmds.last_mut().unwrap().push(word);
+        }
+        last = word;
+    }
+    if words.inquote {
+        bail!("missing closing quote in '{}'", last);
+    }
+    cmds.retain(|cmd| !cmd.is_empty());
+    steps_from_commands(cmds)
+}
+
+// A repeat applies to the remainder of the line, including subsequent commands.
+fn steps_from_commands(cmds: Vec<Vec<&str>>) -> Result<Vec<Step<'_>>> {
+    let mut steps = Vec::new();
+    let mut cmds = cmds.into_iter();
+    while let Some(cmd) = cmds.next() {
+        if cmd[0] != "repeat" {
+            steps.push(Step::Command(cmd));
+            continue;
+        }
+        let count = match cmd.get(1) {
+            Some(count) => count
+                .parse::<u32>()
+                .map_err(|_| anyhow!("invalid repeat count '{}'", count))?,
+            None => bail!("repeat requires a count"),
+        };
+        let mut body: Vec<Vec<&str>> = Vec::new();
+        if cmd.len() > 2 {
+            body.push(cmd[2..].to_vec());
+        }
+        body.extend(cmds);
+        if body.is_empty() {
+            bail!("repeat requires a command");
+        }
+        steps.push(Step::Repeat(count, steps_from_commands(body)?));
+        break;
+    }
+    Ok(steps)
+}
+
 fn interactive_help() -> String {
     let mut help = "COMMANDS:\n".to_owned();
 
     let cmds = [
         (
-            "get [line]...",
+            "get [-c|--cached] [-n|--numeric] [line]...",
             "Display the current values of the given requested lines\n\
-            Returns the last value set, not the physical value on the lines.",
+            The values are read back from the lines, unless --cached is\n\
+            specified, in which case the last values set are returned.\n\
+            For open-drain and open-source lines set to the released state\n\
+            the value read back depends on external pulls.\n\
+            Values are displayed as '0' or '1' if --numeric is specified.",
         ),
         (
             "set <line=value>...",
-            "Update the values of the given requested lines",
+            "Update the values of the given requested lines\n\
+            A value of as-is retains the current value and toggle inverts it.",
         ),
         (
             "toggle [line]...",
             "Toggle the values of the given requested lines\n\
             If no lines are specified then all requested lines are toggled.",
         ),
+        (
+            "config bias <bias> [line]...",
+            "Update the bias of the given requested lines\n\
+            The bias may be pull-up, pull-down or disabled.\n\
+            If no lines are specified then all requested lines are updated.",
+        ),
+        (
+            "config drive <drive> [line]...",
+            "Update the drive of the given requested lines\n\
+            The drive may be push-pull, open-drain or open-source.\n\
+            If no lines are specified then all requested lines are updated.",
+        ),
+        (
+            "format <numeric|named>",
+            "Set the format of values displayed by get\n\
+            Values are displayed as '0' or '1' if numeric, or as 'active'\n\
+            or 'inactive' if named, which is the default.",
+        ),
         ("sleep <period>", "Sleep for the specified period"),
+        (
+            "repeat <count> <command>...",
+            "Repeat the remainder of the line the specified number of times\n\
+            e.g. repeat 8 toggle CLK; sleep 1ms",
+        ),
         ("help", "Print this help"),
         ("version", "Print version"),
         ("exit", "Exit the program"),
@@ -533,34 +1547,22 @@ fn interactive_help() -> String {
             help.push_str(&desc_line);
         }
     }
+    help.push_str("\nMultiple commands may be entered on one line, separated by ';'.\n");
     help
 }
 
-fn print_banner(lines: &[String]) {
-    use std::io::Write;
-    if lines.len() > 1 {
-        print!("Setting lines ");
-
-        for l in lines.iter().take(lines.len() - 1) {
-            print!("'{}', ", l);
-        }
-
-        println!("and '{}'...", lines[lines.len() - 1]);
-    } else {
-        println!("Setting line '{}'...", lines[0]);
-    }
-    _ = std::io::stdout().flush();
-}
-
 #[derive(Debug, Default)]
 struct Line {
-    chip_idx: usize,
-    offset: Offset,
     value: Value,
     dirty: bool,
 }
 
-fn format_line_value(opts: &EmitOpts, id: &str, value: Value) -> String {
+fn format_line_value(opts: &EmitOpts, id: &str, value: Value, numeric: bool) -> String {
+    let value = if numeric {
+        u8::from(value).to_string()
+    } else {
+        value.to_string()
+    };
     if opts.quoted || id.contains(' ') {
         format!("\"{}\"={}", id, value)
     } else {
@@ -577,6 +1579,14 @@ fn unquoted(s: &str) -> &str {
     }
 }
 
+// the lines specified in a command, which may be empty
+fn lines_arg(am: &clap::ArgMatches) -> Vec<String> {
+    am.get_many::<String>("lines")
+        .unwrap_or_default()
+        .cloned()
+        .collect()
+}
+
 /// Parse a single line id
 fn parse_line(s: &str) -> std::result::Result<String, Box<dyn Error + Send + Sync + 'static>> {
     Ok(unquoted(s).to_string())
@@ -584,20 +1594,110 @@ fn parse_line(s: &str) -> std::result::Result<String, Box<dyn Error + Send + Syn
 
 /// Parse a single line=value pair
 fn parse_line_value(s: &str) -> std::result::Result<(String, LineValue), anyhow::Error> {
+    let (ln, v) = split_line_value(s)?;
+    Ok((ln, v.parse()?))
+}
+
+/// Parse a single line=value pair, with the value optionally followed by
+/// line attributes, e.g. line=value,attr1,attr2
+fn parse_line_setting(
+    s: &str,
+) -> std::result::Result<(String, LineValue, LineOverrides), anyhow::Error> {
+    let (ln, v) = split_line_value(s)?;
+    let mut parts = v.split(',');
+    // split always returns at least one part
+    let value = parts.next().unwrap().parse()?;
+    let mut overrides = LineOverrides::default();
+    for attr in parts {
+        overrides.add(attr)?;
+    }
+    Ok((ln, value, overrides))
+}
+
+/// Split a line=value pair into the unquoted line and the value.
+fn split_line_value(s: &str) -> Result<(String, &str)> {
     let pos = s
         .rfind('=')
         .ok_or_else(|| anyhow!("invalid line=value: no '=' found in '{}'", s))?;
     let ln = unquoted(&s[..pos]);
     if ln.contains('"') {
         bail!("invalid line=value: semi-quoted line name in '{}'", s)
-    } else {
-        Ok((ln.to_string(), s[pos + 1..].parse()?))
     }
+    Ok((ln.to_string(), &s[pos + 1..]))
 }
 
 #[derive(Clone, Debug)]
 struct TimeSequence(Vec<Duration>);
 
+impl TimeSequence {
+    // A single period at the initial values followed by a single toggle.
+    //
+    // The zero period after the toggle is extended by any hold period.
+    fn pulse(period: Duration) -> TimeSequence {
+        TimeSequence(vec![period, Duration::ZERO, Duration::ZERO])
+    }
+
+    // A final zero period indicates the sequence does not repeat.
+    fn is_terminated(&self) -> bool {
+        self.0.len() > 1 && self.0.last().is_some_and(|d| d.is_zero())
+    }
+
+    fn check_loop_count(&self, loop_count: Option<u32>) -> Result<()> {
+        // A single zero period is not terminated, as it toggles continuously,
+        // but still ends in a zero period, so is rejected too.
+        if loop_count.is_some() && self.0.last().is_some_and(|d| d.is_zero()) {
+            bail!("--loop cannot be used with a toggle sequence ending in a zero period");
+        }
+        Ok(())
+    }
+
+    // The periods to sleep before each toggle.
+    //
+    // The line values are toggled after each period except the last.
+    fn steps(&self, loop_count: Option<u32>) -> Steps<'_> {
+        let len = if self.is_terminated() {
+            self.0.len() - 1
+        } else {
+            self.0.len()
+        };
+        Steps {
+            periods: &self.0[..len],
+            idx: 0,
+            loops_remaining: if self.is_terminated() {
+                Some(1)
+            } else {
+                loop_count
+            },
+        }
+    }
+}
+
+struct Steps<'a> {
+    periods: &'a [Duration],
+    idx: usize,
+    // None implies the sequence repeats indefinitely.
+    loops_remaining: Option<u32>,
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if self.periods.is_empty() || self.loops_remaining == Some(0) {
+            return None;
+        }
+        let period = self.periods[self.idx];
+        self.idx += 1;
+        if self.idx == self.periods.len() {
+            self.idx = 0;
+            if let Some(loops) = &mut self.loops_remaining {
+                *loops -= 1;
+            }
+        }
+        Some(period)
+    }
+}
+
 fn parse_time_sequence(s: &str) -> std::result::Result<TimeSequence, ParseDurationError> {
     let mut ts = TimeSequence(Vec::new());
     for period in s.split(',') {
@@ -606,49 +1706,320 @@ fn parse_time_sequence(s: &str) -> std::result::Result<TimeSequence, ParseDurati
     Ok(ts)
 }
 
+// A step in a sequence of line values.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct LineValue(Value);
+enum SequenceStep {
+    // Set the lines to the values.
+    Set(Vec<(String, LineValue)>),
 
-impl FromStr for LineValue {
-    type Err = InvalidLineValue;
+    // Hold the lines at their current values for the period.
+    Sleep(Duration),
+}
 
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let lower_s = s.to_lowercase();
-        let v = match lower_s.as_str() {
-            "0" | "inactive" | "off" | "false" => Value::Inactive,
-            "1" | "active" | "on" | "true" => Value::Active,
-            _ => {
-                return Err(InvalidLineValue::new(s));
+// A sequence of line values and the periods to hold them.
+//
+// The sequence always starts with a Set, which includes all the lines
+// in the sequence.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Sequence(Vec<SequenceStep>);
+
+impl Sequence {
+    fn initial_values(&self) -> &[(String, LineValue)] {
+        match &self.0[0] {
+            SequenceStep::Set(values) => values,
+            SequenceStep::Sleep(_) => unreachable!(),
+        }
+    }
+}
+
+fn parse_sequence(s: &str) -> std::result::Result<Sequence, anyhow::Error> {
+    let mut steps = Vec::new();
+    for group in s.split(';').map(str::trim).filter(|g| !g.is_empty()) {
+        let step = if group.contains('=') {
+            let mut values = Vec::new();
+            for lv in group.split(',') {
+                values.push(parse_line_value(lv.trim())?);
             }
+            SequenceStep::Set(values)
+        } else {
+            SequenceStep::Sleep(common::parse_duration(group)?)
         };
-        Ok(LineValue(v))
+        let alternates = match steps.last() {
+            Some(SequenceStep::Set(_)) => matches!(step, SequenceStep::Sleep(_)),
+            Some(SequenceStep::Sleep(_)) => matches!(step, SequenceStep::Set(_)),
+            None => true,
+        };
+        if !alternates {
+            bail!(
+                "invalid sequence: groups must alternate between line values and periods, found '{}'",
+                group
+            );
+        }
+        steps.push(step);
+    }
+    let initial = match steps.first() {
+        Some(SequenceStep::Set(values)) => values,
+        _ => bail!("invalid sequence: must start with line values"),
+    };
+    for step in &steps[1..] {
+        if let SequenceStep::Set(values) = step {
+            for (id, _v) in values {
+                if !initial.iter().any(|(l, _v)| l == id) {
+                    bail!(
+                        "invalid sequence: line '{}' is not in the initial line values",
+                        id
+                    );
+                }
+            }
+        }
+    }
+    Ok(Sequence(steps))
+}
+
+// The number of times to run a sequence.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Repeat {
+    Count(u32),
+    Forever,
+}
+
+fn parse_repeat(s: &str) -> std::result::Result<Repeat, anyhow::Error> {
+    if s == "forever" {
+        return Ok(Repeat::Forever);
+    }
+    match s.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(Repeat::Count(n)),
+        _ => bail!(
+            "invalid repeat: expected a count or 'forever', found '{}'",
+            s
+        ),
+    }
+}
+
+// A pulse width modulated signal to drive a line.
+#[derive(Clone, Debug, PartialEq)]
+struct PwmSpec {
+    // The line to drive, or None for all lines without their own signal.
+    line: Option<String>,
+    // The frequency in Hz.
+    freq: f64,
+    // The percentage of each cycle the line is active.
+    duty: f64,
+}
+
+impl PwmSpec {
+    // The periods the line is active and inactive in each cycle.
+    //
+    // Both are rounded to the nearest nanosecond.
+    fn periods(&self) -> (Duration, Duration) {
+        let cycle = (1e9 / self.freq).round() as u64;
+        let active = (cycle as f64 * self.duty / 100.0).round() as u64;
+        (
+            Duration::from_nanos(active),
+            Duration::from_nanos(cycle - active),
+        )
+    }
+}
+
+// A line being driven by a PwmSpec.
+struct PwmLine {
+    id: String,
+    active: Duration,
+    inactive: Duration,
+    // The time the line is next toggled.
+    next: Instant,
+}
+
+fn parse_pwm(s: &str) -> std::result::Result<PwmSpec, anyhow::Error> {
+    let (line, signal) = match s.rfind('=') {
+        Some(pos) => (Some(unquoted(&s[..pos]).to_string()), &s[pos + 1..]),
+        None => (None, s),
+    };
+    let (freq, duty) = signal
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid pwm: no ':' found in '{}'", s))?;
+    let freq: f64 = freq
+        .parse()
+        .map_err(|_| anyhow!("invalid pwm frequency: '{}'", freq))?;
+    // periods must be at least 1ns
+    if !(freq > 0.0 && freq <= 1e9) {
+        bail!("invalid pwm frequency: '{}'", freq);
+    }
+    let duty_pc = duty.strip_suffix('%').unwrap_or(duty);
+    let duty: f64 = duty_pc
+        .parse()
+        .map_err(|_| anyhow!("invalid pwm duty cycle: '{}'", duty))?;
+    if !(0.0..=100.0).contains(&duty) {
+        bail!("invalid pwm duty cycle: '{}'", duty_pc);
+    }
+    let spec = PwmSpec { line, freq, duty };
+    // only a duty cycle of 0 or 100% may have a zero period
+    let (active, inactive) = spec.periods();
+    if duty > 0.0 && duty < 100.0 && (active.is_zero() || inactive.is_zero()) {
+        bail!("invalid pwm: '{}' has a period shorter than 1ns", signal);
+    }
+    Ok(spec)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum LineValue {
+    Value(Value),
+    // retain the current value
+    AsIs,
+    // invert the current value
+    Toggle,
+}
+
+impl LineValue {
+    // the value to set the line to, given its current value.
+    fn value(&self, current: Value) -> Value {
+        match self {
+            LineValue::Value(v) => *v,
+            LineValue::AsIs => current,
+            LineValue::Toggle => !current,
+        }
     }
 }
 
-#[derive(Debug)]
-struct InvalidLineValue {
-    value: String,
+// The attributes of an individual line that override the command line options.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct LineOverrides {
+    active_low: Option<bool>,
+    bias: Option<common::BiasFlags>,
+    drive: Option<common::DriveFlags>,
 }
 
-impl InvalidLineValue {
-    pub fn new<S: Into<String>>(value: S) -> InvalidLineValue {
-        InvalidLineValue {
-            value: value.into(),
+impl LineOverrides {
+    fn add(&mut self, attr: &str) -> Result<()> {
+        use common::{BiasFlags, DriveFlags};
+        match attr.to_lowercase().as_str() {
+            "active-low" => self.active_low = Some(true),
+            "active-high" => self.active_low = Some(false),
+            "pull-up" => self.bias = Some(BiasFlags::PullUp),
+            "pull-down" => self.bias = Some(BiasFlags::PullDown),
+            "bias-disabled" => self.bias = Some(BiasFlags::Disabled),
+            "push-pull" => self.drive = Some(DriveFlags::PushPull),
+            "open-drain" => self.drive = Some(DriveFlags::OpenDrain),
+            "open-source" => self.drive = Some(DriveFlags::OpenSource),
+            _ => bail!("invalid line attribute: '{}'", attr),
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self == &LineOverrides::default()
+    }
+
+    fn apply(&self, lc: &mut line::Config) {
+        if let Some(active_low) = self.active_low {
+            lc.active_low = active_low;
+        }
+        if let Some(bias) = self.bias {
+            lc.bias = Some(bias.into());
+        }
+        if let Some(drive) = self.drive {
+            lc.with_drive(drive.into());
         }
     }
 }
 
-impl fmt::Display for InvalidLineValue {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid line value: '{}'", self.value)
+impl FromStr for LineValue {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "as-is" => Ok(LineValue::AsIs),
+            "toggle" => Ok(LineValue::Toggle),
+            _ => Ok(LineValue::Value(s.parse()?)),
+        }
     }
 }
-impl Error for InvalidLineValue {}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn format_line_value() {
+        use super::format_line_value;
+        use Value::{Active, Inactive};
+        let mut opts = EmitOpts::default();
+        assert_eq!(format_line_value(&opts, "led", Active, false), "led=active");
+        assert_eq!(
+            format_line_value(&opts, "led", Inactive, false),
+            "led=inactive"
+        );
+        assert_eq!(format_line_value(&opts, "led", Active, true), "led=1");
+        assert_eq!(format_line_value(&opts, "led", Inactive, true), "led=0");
+        assert_eq!(format_line_value(&opts, "a b", Active, true), "\"a b\"=1");
+        opts.quoted = true;
+        assert_eq!(format_line_value(&opts, "led", Inactive, true), "\"led\"=0");
+    }
+
+    #[test]
+    fn line_value_value() {
+        use Value::{Active, Inactive};
+        assert_eq!(LineValue::Value(Active).value(Inactive), Active);
+        assert_eq!(LineValue::Value(Inactive).value(Active), Inactive);
+        assert_eq!(LineValue::AsIs.value(Active), Active);
+        assert_eq!(LineValue::AsIs.value(Inactive), Inactive);
+        assert_eq!(LineValue::Toggle.value(Active), Inactive);
+        assert_eq!(LineValue::Toggle.value(Inactive), Active);
+    }
+
+    #[test]
+    fn line_conflicts() {
+        use super::line_conflicts;
+        use gpiocdev::line::{Direction, Drive, EdgeDetection, Info};
+
+        let mut lc = line::Config::default();
+        lc.as_output(Value::Active);
+
+        // unused lines never conflict
+        let mut info = Info::default();
+        info.active_low = true;
+        assert!(line_conflicts("led", &info, &lc).is_empty());
+
+        let mut info = Info::default();
+        info.used = true;
+        info.consumer = "blinky".into();
+        info.direction = Direction::Output;
+        info.drive = Some(Drive::PushPull);
+        assert_eq!(
+            line_conflicts("led", &info, &lc),
+            vec!["line led is already requested by \"blinky\"".to_string()]
+        );
+
+        let mut info = Info::default();
+        info.used = true;
+        info.consumer = "watcher".into();
+        info.direction = Direction::Input;
+        info.active_low = true;
+        info.edge_detection = Some(EdgeDetection::BothEdges);
+        assert_eq!(
+            line_conflicts("led", &info, &lc),
+            vec![
+                "line led is already requested by \"watcher\"".to_string(),
+                "line led is an input with edge detection enabled".to_string(),
+                "line led is active-low, but active-high was requested".to_string(),
+            ]
+        );
+
+        let mut info = Info::default();
+        info.used = true;
+        info.consumer = "blinky".into();
+        info.direction = Direction::Output;
+        info.drive = Some(Drive::OpenDrain);
+        lc.with_drive(Drive::OpenSource);
+        assert_eq!(
+            line_conflicts("led", &info, &lc),
+            vec![
+                "line led is already requested by \"blinky\"".to_string(),
+                "line led is open-drain, but open-source was requested".to_string(),
+            ]
+        );
+    }
+
     mod parse {
         #[test]
         fn line() {
@@ -664,43 +2035,51 @@ mod tests {
             use gpiocdev::line::Value;
             assert_eq!(
                 parse_line_value("blah=0").unwrap(),
-                ("blah".to_string(), LineValue(Value::Inactive))
+                ("blah".to_string(), LineValue::Value(Value::Inactive))
             );
             assert_eq!(
                 parse_line_value("l=1").unwrap(),
-                ("l".to_string(), LineValue(Value::Active))
+                ("l".to_string(), LineValue::Value(Value::Active))
             );
             assert_eq!(
                 parse_line_value("l=active").unwrap(),
-                ("l".to_string(), LineValue(Value::Active))
+                ("l".to_string(), LineValue::Value(Value::Active))
             );
             assert_eq!(
                 parse_line_value("l=inactive").unwrap(),
-                ("l".to_string(), LineValue(Value::Inactive))
+                ("l".to_string(), LineValue::Value(Value::Inactive))
             );
             assert_eq!(
                 parse_line_value("l=on").unwrap(),
-                ("l".to_string(), LineValue(Value::Active))
+                ("l".to_string(), LineValue::Value(Value::Active))
             );
             assert_eq!(
                 parse_line_value("l=off").unwrap(),
-                ("l".to_string(), LineValue(Value::Inactive))
+                ("l".to_string(), LineValue::Value(Value::Inactive))
             );
             assert_eq!(
                 parse_line_value("l=true").unwrap(),
-                ("l".to_string(), LineValue(Value::Active))
+                ("l".to_string(), LineValue::Value(Value::Active))
             );
             assert_eq!(
                 parse_line_value("l=false").unwrap(),
-                ("l".to_string(), LineValue(Value::Inactive))
+                ("l".to_string(), LineValue::Value(Value::Inactive))
+            );
+            assert_eq!(
+                parse_line_value("l=as-is").unwrap(),
+                ("l".to_string(), LineValue::AsIs)
+            );
+            assert_eq!(
+                parse_line_value("l=Toggle").unwrap(),
+                ("l".to_string(), LineValue::Toggle)
             );
             assert_eq!(
                 parse_line_value("\"quoted\"=false").unwrap(),
-                ("quoted".to_string(), LineValue(Value::Inactive))
+                ("quoted".to_string(), LineValue::Value(Value::Inactive))
             );
             assert_eq!(
                 parse_line_value("\"quoted\\ name\"=1").unwrap(),
-                ("quoted\\ name".to_string(), LineValue(Value::Active))
+                ("quoted\\ name".to_string(), LineValue::Value(Value::Active))
             );
             assert_eq!(
                 parse_line_value("\"quoted=false")
@@ -726,6 +2105,123 @@ mod tests {
             );
         }
 
+        #[test]
+        fn line_setting() {
+            use super::{parse_line_setting, parse_line_value, LineOverrides, LineValue};
+            use crate::common::{BiasFlags, DriveFlags};
+            use gpiocdev::line::Value;
+            assert_eq!(
+                parse_line_setting("l=1").unwrap(),
+                (
+                    "l".to_string(),
+                    LineValue::Value(Value::Active),
+                    LineOverrides::default()
+                )
+            );
+            assert_eq!(
+                parse_line_setting("l=1,active-low").unwrap(),
+                (
+                    "l".to_string(),
+                    LineValue::Value(Value::Active),
+                    LineOverrides {
+                        active_low: Some(true),
+                        ..Default::default()
+                    }
+                )
+            );
+            assert_eq!(
+                parse_line_setting("l=toggle,Active-High,pull-up,open-drain").unwrap(),
+                (
+                    "l".to_string(),
+                    LineValue::Toggle,
+                    LineOverrides {
+                        active_low: Some(false),
+                        bias: Some(BiasFlags::PullUp),
+                        drive: Some(DriveFlags::OpenDrain),
+                    }
+                )
+            );
+            // later attributes override earlier ones
+            assert_eq!(
+                parse_line_setting("l=0,pull-down,bias-disabled,open-source,push-pull").unwrap(),
+                (
+                    "l".to_string(),
+                    LineValue::Value(Value::Inactive),
+                    LineOverrides {
+                        active_low: None,
+                        bias: Some(BiasFlags::Disabled),
+                        drive: Some(DriveFlags::PushPull),
+                    }
+                )
+            );
+            assert_eq!(
+                parse_line_setting("\"quoted=name\"=1,pull-down").unwrap(),
+                (
+                    "quoted=name".to_string(),
+                    LineValue::Value(Value::Active),
+                    LineOverrides {
+                        bias: Some(BiasFlags::PullDown),
+                        ..Default::default()
+                    }
+                )
+            );
+            assert_eq!(
+                parse_line_setting("l=1,inverted")
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                "invalid line attribute: 'inverted'"
+            );
+            assert_eq!(
+                parse_line_setting("l=1,").err().unwrap().to_string(),
+                "invalid line attribute: ''"
+            );
+            assert_eq!(
+                parse_line_setting("l=active-low")
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                "invalid line value: 'active-low'"
+            );
+            // attributes are only supported when requesting lines
+            assert_eq!(
+                parse_line_value("l=1,active-low")
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                "invalid line value: '1,active-low'"
+            );
+        }
+
+        #[test]
+        fn line_overrides() {
+            use super::LineOverrides;
+            use crate::common::{BiasFlags, DriveFlags};
+            use gpiocdev::line::{Bias, Config, Drive, Value};
+
+            let mut global = Config::default();
+            global.as_output(Value::Active);
+            global.active_low = true;
+            global.bias = Some(Bias::PullUp);
+            global.with_drive(Drive::OpenDrain);
+
+            // no overrides leave the config untouched
+            let mut lc = global.clone();
+            LineOverrides::default().apply(&mut lc);
+            assert_eq!(lc, global);
+
+            let o = LineOverrides {
+                active_low: Some(false),
+                bias: Some(BiasFlags::Disabled),
+                drive: Some(DriveFlags::PushPull),
+            };
+            o.apply(&mut lc);
+            assert!(!lc.active_low);
+            assert_eq!(lc.bias, Some(Bias::Disabled));
+            assert_eq!(lc.drive, Some(Drive::PushPull));
+            assert_eq!(lc.value, Some(Value::Active));
+        }
+
         #[test]
         fn time_sequence() {
             use super::parse_time_sequence;
@@ -764,6 +2260,12 @@ mod tests {
                 Duration::ZERO
             ]
             .iter()));
+            assert!(parse_time_sequence("1.5s,2m30s,0.5").unwrap().0.iter().eq([
+                Duration::from_millis(1500),
+                Duration::new(150, 0),
+                Duration::from_micros(500)
+            ]
+            .iter()));
             assert_eq!(
                 parse_time_sequence("5ns").unwrap_err(),
                 ParseDurationError::Units("5ns".to_string())
@@ -774,4 +2276,346 @@ mod tests {
             );
         }
     }
+
+    mod time_sequence {
+        use super::{parse_time_sequence, Duration};
+
+        fn ms(v: u64) -> Duration {
+            Duration::from_millis(v)
+        }
+
+        #[test]
+        fn steps_repeating() {
+            let ts = parse_time_sequence("1,2,3").unwrap();
+            assert!(ts
+                .steps(None)
+                .take(7)
+                .eq([ms(1), ms(2), ms(3), ms(1), ms(2), ms(3), ms(1)]));
+        }
+
+        #[test]
+        fn steps_terminated() {
+            let ts = parse_time_sequence("1,2,3,0").unwrap();
+            assert!(ts.steps(None).eq([ms(1), ms(2), ms(3)]));
+            let ts = parse_time_sequence("1,0").unwrap();
+            assert!(ts.steps(None).eq([ms(1)]));
+        }
+
+        #[test]
+        fn steps_loop_count() {
+            let ts = parse_time_sequence("1,2").unwrap();
+            assert!(ts.steps(Some(1)).eq([ms(1), ms(2)]));
+            assert!(ts
+                .steps(Some(3))
+                .eq([ms(1), ms(2), ms(1), ms(2), ms(1), ms(2)]));
+            let ts = parse_time_sequence("5").unwrap();
+            assert!(ts.steps(Some(2)).eq([ms(5), ms(5)]));
+        }
+
+        #[test]
+        fn steps_pulse() {
+            let ts = super::TimeSequence::pulse(ms(5));
+            assert!(ts.is_terminated());
+            assert!(ts.steps(None).eq([ms(5), ms(0)]));
+        }
+
+        #[test]
+        fn check_loop_count() {
+            let ts = parse_time_sequence("1,2").unwrap();
+            assert!(ts.check_loop_count(None).is_ok());
+            assert!(ts.check_loop_count(Some(2)).is_ok());
+            let ts = parse_time_sequence("1,2,0").unwrap();
+            assert!(ts.check_loop_count(None).is_ok());
+            assert_eq!(
+                ts.check_loop_count(Some(2)).unwrap_err().to_string(),
+                "--loop cannot be used with a toggle sequence ending in a zero period"
+            );
+            let ts = parse_time_sequence("0").unwrap();
+            assert!(!ts.is_terminated());
+            assert!(ts.check_loop_count(None).is_ok());
+            assert_eq!(
+                ts.check_loop_count(Some(2)).unwrap_err().to_string(),
+                "--loop cannot be used with a toggle sequence ending in a zero period"
+            );
+        }
+    }
+
+    mod pwm {
+        use super::{parse_pwm, Duration, PwmSpec};
+
+        fn periods(s: &str) -> (Duration, Duration) {
+            parse_pwm(s).unwrap().periods()
+        }
+
+        #[test]
+        fn parse() {
+            assert_eq!(
+                parse_pwm("50:25").unwrap(),
+                PwmSpec {
+                    line: None,
+                    freq: 50.0,
+                    duty: 25.0
+                }
+            );
+            assert_eq!(
+                parse_pwm("GPIO17=0.5:12.5%").unwrap(),
+                PwmSpec {
+                    line: Some("GPIO17".to_string()),
+                    freq: 0.5,
+                    duty: 12.5
+                }
+            );
+            assert_eq!(
+                parse_pwm("\"quoted=name\"=10:0").unwrap(),
+                PwmSpec {
+                    line: Some("quoted=name".to_string()),
+                    freq: 10.0,
+                    duty: 0.0
+                }
+            );
+            let err = |s| parse_pwm(s).unwrap_err().to_string();
+            assert_eq!(err("50"), "invalid pwm: no ':' found in '50'");
+            assert_eq!(err("l=fast:50"), "invalid pwm frequency: 'fast'");
+            assert_eq!(err("0:50"), "invalid pwm frequency: '0'");
+            assert_eq!(err("-5:50"), "invalid pwm frequency: '-5'");
+            assert_eq!(err("2e9:50"), "invalid pwm frequency: '2000000000'");
+            assert_eq!(err("50:half"), "invalid pwm duty cycle: 'half'");
+            assert_eq!(err("50:101%"), "invalid pwm duty cycle: '101'");
+            assert_eq!(err("50:-1"), "invalid pwm duty cycle: '-1'");
+            assert_eq!(
+                err("1e9:50"),
+                "invalid pwm: '1e9:50' has a period shorter than 1ns"
+            );
+            assert_eq!(
+                err("1e8:1"),
+                "invalid pwm: '1e8:1' has a period shorter than 1ns"
+            );
+        }
+
+        #[test]
+        fn periods_from_freq_and_duty() {
+            assert_eq!(
+                periods("1000:25"),
+                (Duration::from_micros(250), Duration::from_micros(750))
+            );
+            assert_eq!(
+                periods("1000:12.5%"),
+                (Duration::from_micros(125), Duration::from_micros(875))
+            );
+            assert_eq!(
+                periods("0.5:50"),
+                (Duration::from_secs(1), Duration::from_secs(1))
+            );
+            assert_eq!(
+                periods("10:0"),
+                (Duration::ZERO, Duration::from_millis(100))
+            );
+            assert_eq!(
+                periods("10:100"),
+                (Duration::from_millis(100), Duration::ZERO)
+            );
+            assert_eq!(
+                periods("5e8:50"),
+                (Duration::from_nanos(1), Duration::from_nanos(1))
+            );
+            assert_eq!(
+                periods("1e9:100"),
+                (Duration::from_nanos(1), Duration::ZERO)
+            );
+        }
+
+        #[test]
+        fn periods_rounding() {
+            // 333333333.3ns cycle rounds down, the half ns active rounds up
+            assert_eq!(
+                periods("3:50"),
+                (
+                    Duration::from_nanos(166666667),
+                    Duration::from_nanos(166666666)
+                )
+            );
+            // 142857142.9ns cycle rounds up, 47571428.6ns active rounds up
+            assert_eq!(
+                periods("7:33.3"),
+                (
+                    Duration::from_nanos(47571429),
+                    Duration::from_nanos(95285714)
+                )
+            );
+            // the periods always sum to the rounded cycle
+            let (active, inactive) = periods("3:33.3");
+            assert_eq!(active + inactive, Duration::from_nanos(333333333));
+        }
+    }
+
+    mod sequence {
+        use super::{parse_repeat, parse_sequence, Duration, LineValue, Repeat, SequenceStep};
+        use gpiocdev::line::Value;
+        use SequenceStep::*;
+
+        fn lv(id: &str, v: Value) -> (String, LineValue) {
+            (id.to_string(), LineValue::Value(v))
+        }
+
+        fn err(s: &str) -> String {
+            parse_sequence(s).unwrap_err().to_string()
+        }
+
+        #[test]
+        fn parse() {
+            let seq = parse_sequence("17=1,22=0; 500ms; 17=0,22=1; 1s; 17=0, 22=0").unwrap();
+            assert_eq!(
+                seq.0,
+                vec![
+                    Set(vec![lv("17", Value::Active), lv("22", Value::Inactive)]),
+                    Sleep(Duration::from_millis(500)),
+                    Set(vec![lv("17", Value::Inactive), lv("22", Value::Active)]),
+                    Sleep(Duration::from_secs(1)),
+                    Set(vec![lv("17", Value::Inactive), lv("22", Value::Inactive)]),
+                ]
+            );
+            assert_eq!(
+                seq.initial_values(),
+                &[lv("17", Value::Active), lv("22", Value::Inactive)]
+            );
+            // later groups may set a subset of the lines, and the sequence may
+            // end with a period.
+            let seq = parse_sequence("a=on,b=off;20;b=on;20;").unwrap();
+            assert_eq!(
+                seq.0,
+                vec![
+                    Set(vec![lv("a", Value::Active), lv("b", Value::Inactive)]),
+                    Sleep(Duration::from_millis(20)),
+                    Set(vec![lv("b", Value::Active)]),
+                    Sleep(Duration::from_millis(20)),
+                ]
+            );
+        }
+
+        #[test]
+        fn parse_invalid() {
+            assert_eq!(err(""), "invalid sequence: must start with line values");
+            assert_eq!(
+                err("500ms; 17=1"),
+                "invalid sequence: must start with line values"
+            );
+            assert_eq!(
+                err("17=1; 17=0"),
+                "invalid sequence: groups must alternate between line values and periods, found '17=0'"
+            );
+            assert_eq!(
+                err("17=1; 5ms; 10ms"),
+                "invalid sequence: groups must alternate between line values and periods, found '10ms'"
+            );
+            assert_eq!(
+                err("17=1; 5ms; 22=0"),
+                "invalid sequence: line '22' is not in the initial line values"
+            );
+            assert_eq!(
+                err("17=1; 5ns"),
+                "'5ns' unknown units - use 'h', 'm', 's', 'ms' or 'us'."
+            );
+            assert_eq!(err("17=2"), "invalid line value: '2'");
+        }
+
+        #[test]
+        fn repeat() {
+            assert_eq!(parse_repeat("3").unwrap(), Repeat::Count(3));
+            assert_eq!(parse_repeat("forever").unwrap(), Repeat::Forever);
+            assert_eq!(
+                parse_repeat("0").unwrap_err().to_string(),
+                "invalid repeat: expected a count or 'forever', found '0'"
+            );
+            assert_eq!(
+                parse_repeat("always").unwrap_err().to_string(),
+                "invalid repeat: expected a count or 'forever', found 'always'"
+            );
+        }
+    }
+
+    mod steps {
+        use super::{parse_steps, Step};
+        use Step::*;
+
+        fn err(line: &str) -> String {
+            parse_steps(line).unwrap_err().to_string()
+        }
+
+        #[test]
+        fn commands() {
+            assert_eq!(parse_steps("").unwrap(), vec![]);
+            assert_eq!(parse_steps(" ; ;").unwrap(), vec![]);
+            assert_eq!(
+                parse_steps("toggle CLK").unwrap(),
+                vec![Command(vec!["toggle", "CLK"])]
+            );
+            assert_eq!(
+                parse_steps("set CLK=1; sleep 1ms;toggle;").unwrap(),
+                vec![
+                    Command(vec!["set", "CLK=1"]),
+                    Command(vec!["sleep", "1ms"]),
+                    Command(vec!["toggle"])
+                ]
+            );
+            assert_eq!(
+                parse_steps("set \"a;b\"=1").unwrap(),
+                vec![Command(vec!["set", "\"a;b\"=1"])]
+            );
+            assert_eq!(err("set \"a;b=1"), "missing closing quote in '\"a;b=1'");
+        }
+
+        #[test]
+        fn repeat() {
+            assert_eq!(
+                parse_steps("repeat 8 toggle CLK; sleep 1ms").unwrap(),
+                vec![Repeat(
+                    8,
+                    vec![
+                        Command(vec!["toggle", "CLK"]),
+                        Command(vec!["sleep", "1ms"])
+                    ]
+                )]
+            );
+            assert_eq!(
+                parse_steps("set CLK=0; repeat 2; toggle").unwrap(),
+                vec![
+                    Command(vec!["set", "CLK=0"]),
+                    Repeat(2, vec![Command(vec!["toggle"])])
+                ]
+            );
+            assert_eq!(
+                parse_steps("repeat 0 toggle").unwrap(),
+                vec![Repeat(0, vec![Command(vec!["toggle"])])]
+            );
+        }
+
+        #[test]
+        fn nested_repeat() {
+            assert_eq!(
+                parse_steps("repeat 2 set DAT=1; repeat 3 toggle CLK; sleep 1ms").unwrap(),
+                vec![Repeat(
+                    2,
+                    vec![
+                        Command(vec!["set", "DAT=1"]),
+                        Repeat(
+                            3,
+                            vec![
+                                Command(vec!["toggle", "CLK"]),
+                                Command(vec!["sleep", "1ms"])
+                            ]
+                        )
+                    ]
+                )]
+            );
+        }
+
+        #[test]
+        fn repeat_errors() {
+            assert_eq!(err("repeat"), "repeat requires a count");
+            assert_eq!(err("repeat 3"), "repeat requires a command");
+            assert_eq!(err("repeat 3;"), "repeat requires a command");
+            assert_eq!(err("repeat x toggle"), "invalid repeat count 'x'");
+            assert_eq!(err("repeat -1 toggle"), "invalid repeat count '-1'");
+        }
+    }
 }
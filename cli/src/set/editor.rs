@@ -81,8 +81,9 @@ impl InteractiveHelper {
         let mut part_word = *line_values.last().unwrap();
         match part_word.split_once('=') {
             Some((_, part_value)) => {
-                const VALUES: [&str; 8] =
-                    ["active", "inactive", "on", "off", "true", "false", "1", "0"];
+                const VALUES: [&str; 10] = [
+                    "active", "inactive", "on", "off", "true", "false", "1", "0", "as-is", "toggle",
+                ];
                 pos -= part_value.len();
                 for value in VALUES.iter().filter(|v| v.starts_with(part_value)) {
                     candidates.push(base_pair(value))
@@ -129,6 +130,58 @@ impl InteractiveHelper {
         (pos, candidates)
     }
 
+    fn complete_config(&self, pos: usize, mut words: CommandWords) -> (usize, Vec<Pair>) {
+        const ATTRS: [&str; 2] = ["bias", "drive"];
+        const BIASES: [&str; 3] = ["pull-up", "pull-down", "disabled"];
+        const DRIVES: [&str; 3] = ["push-pull", "open-drain", "open-source"];
+        let attr = match words.next() {
+            Some(attr) => attr,
+            None => return (pos, ATTRS.iter().map(|a| base_pair(a)).collect()),
+        };
+        if words.partial {
+            let candidates = ATTRS
+                .iter()
+                .filter(|a| a.starts_with(attr))
+                .map(|a| base_pair(a))
+                .collect();
+            return (pos - attr.len(), candidates);
+        }
+        let values = match attr {
+            "bias" => BIASES,
+            "drive" => DRIVES,
+            _ => return (pos, vec![]),
+        };
+        let value = match words.next() {
+            Some(value) => value,
+            None => return (pos, values.iter().map(|v| base_pair(v)).collect()),
+        };
+        if words.partial {
+            let candidates = values
+                .iter()
+                .filter(|v| v.starts_with(value))
+                .map(|v| base_pair(v))
+                .collect();
+            return (pos - value.len(), candidates);
+        }
+        self.complete_lines(pos, words)
+    }
+
+    fn complete_format(&self, pos: usize, mut words: CommandWords) -> (usize, Vec<Pair>) {
+        const FORMATS: [&str; 2] = ["numeric", "named"];
+        match words.next() {
+            None => (pos, FORMATS.iter().map(|f| base_pair(f)).collect()),
+            Some(format) if words.partial => {
+                let candidates = FORMATS
+                    .iter()
+                    .filter(|f| f.starts_with(format))
+                    .map(|f| base_pair(f))
+                    .collect();
+                (pos - format.len(), candidates)
+            }
+            _ => (pos, vec![]),
+        }
+    }
+
     fn complete_lines(&self, pos: usize, mut words: CommandWords) -> (usize, Vec<Pair>) {
         let mut selected = Vec::new();
         while let Some(word) = &words.next() {
@@ -164,10 +217,20 @@ impl Completer for InteractiveHelper {
         pos: usize,
         _ctx: &rustyline::Context<'_>,
     ) -> Result<(usize, Vec<Pair>), ReadlineError> {
-        const CMD_SET: [&str; 7] = ["exit", "get", "help", "set", "sleep", "toggle", "version"];
-        let cmd_pos = line.len() - line.trim_start().len();
+        Ok(self.complete_command(line, command_start(&line[..pos]), pos))
+    }
+}
+
+impl InteractiveHelper {
+    fn complete_command(&self, line: &str, start: usize, pos: usize) -> (usize, Vec<Pair>) {
+        const CMD_SET: [&str; 10] = [
+            "config", "exit", "format", "get", "help", "repeat", "set", "sleep", "toggle",
+            "version",
+        ];
+        let cmd_line = &line[start..pos];
+        let cmd_pos = start + cmd_line.len() - cmd_line.trim_start().len();
         let mut words = CommandWords::new(&line[cmd_pos..pos]);
-        Ok(match words.next() {
+        match words.next() {
             Some(cmd) => {
                 if words.partial {
                     let mut candidates = Vec::new();
@@ -177,7 +240,10 @@ impl Completer for InteractiveHelper {
                     (cmd_pos, candidates)
                 } else {
                     match cmd {
+                        "config" => self.complete_config(pos, words),
+                        "format" => self.complete_format(pos, words),
                         "get" => self.complete_lines(pos, words),
+                        "repeat" => self.complete_repeat(line, cmd_pos, pos, words),
                         "set" => self.complete_set(pos, words),
                         "sleep" => self.complete_sleep(pos, words),
                         "toggle" => self.complete_lines(pos, words),
@@ -190,9 +256,26 @@ impl Completer for InteractiveHelper {
                 for display in CMD_SET.iter() {
                     candidates.push(base_pair(display))
                 }
-                (0, candidates)
+                (cmd_pos, candidates)
             }
-        })
+        }
+    }
+
+    fn complete_repeat(
+        &self,
+        line: &str,
+        cmd_pos: usize,
+        pos: usize,
+        mut words: CommandWords,
+    ) -> (usize, Vec<Pair>) {
+        match words.next() {
+            Some(count) if !words.partial => {
+                let start = cmd_pos + words.offset(count) + count.len();
+                self.complete_command(line, start, pos)
+            }
+            // no candidates for the count
+            _ => (pos, vec![]),
+        }
     }
 }
 
@@ -242,22 +325,43 @@ fn line_value_pair(candidate: &str) -> Pair {
     }
 }
 
+// Splits a command line into words.
+//
+// Words are separated by spaces, unless quoted.
+// An unquoted ';' separates commands and is returned as a word of its own.
 pub(super) struct CommandWords<'a> {
     line: &'a str,
-    liter: std::str::CharIndices<'a>,
+    liter: std::iter::Peekable<std::str::CharIndices<'a>>,
     pub(super) inquote: bool,
     partial: bool,
 }
 
 impl CommandWords<'_> {
-    pub fn new(line: &str) -> CommandWords {
+    pub fn new(line: &str) -> CommandWords<'_> {
         CommandWords {
             line,
-            liter: line.char_indices(),
+            liter: line.char_indices().peekable(),
             inquote: false,
             partial: false,
         }
     }
+
+    // The offset of a word returned by the iterator within the line.
+    pub(super) fn offset(&self, word: &str) -> usize {
+        word.as_ptr() as usize - self.line.as_ptr() as usize
+    }
+}
+
+// The offset of the start of the last command in the line.
+fn command_start(line: &str) -> usize {
+    let mut words = CommandWords::new(line);
+    let mut start = 0;
+    while let Some(word) = words.next() {
+        if word == ";" {
+            start = words.offset(word) + 1;
+        }
+    }
+    start
 }
 
 impl<'a> Iterator for CommandWords<'a> {
@@ -268,6 +372,10 @@ impl<'a> Iterator for CommandWords<'a> {
         loop {
             match self.liter.next() {
                 Some((_, ' ')) => {}
+                Some((pos, ';')) => {
+                    self.partial = self.liter.peek().is_none();
+                    return Some(&self.line[pos..pos + 1]);
+                }
                 Some((pos, c)) => {
                     start = pos;
                     if c == '"' {
@@ -279,6 +387,11 @@ impl<'a> Iterator for CommandWords<'a> {
             }
         }
         loop {
+            if let Some(&(pos, ';')) = self.liter.peek() {
+                if !self.inquote {
+                    return Some(&self.line[start..pos]);
+                }
+            }
             match self.liter.next() {
                 Some((_, '"')) if self.inquote => self.inquote = false,
                 Some((_, '"')) if !self.inquote => self.inquote = true,
@@ -299,6 +412,179 @@ impl<'a> Iterator for CommandWords<'a> {
 mod tests {
     use super::*;
 
+    mod complete_config {
+        use super::{CommandWords, InteractiveHelper};
+
+        fn complete(line: &str) -> (usize, Vec<String>) {
+            let helper = InteractiveHelper {
+                line_names: vec!["led0".to_string(), "led1".to_string()],
+            };
+            let mut words = CommandWords::new(line);
+            assert_eq!(words.next(), Some("config"));
+            let (pos, candidates) = helper.complete_config(line.len(), words);
+            (pos, candidates.into_iter().map(|c| c.display).collect())
+        }
+
+        #[test]
+        fn attrs() {
+            assert_eq!(
+                complete("config "),
+                (7, vec!["bias".into(), "drive".into()])
+            );
+            assert_eq!(complete("config b"), (7, vec!["bias".into()]));
+            assert_eq!(complete("config dr"), (7, vec!["drive".into()]));
+            assert_eq!(complete("config x"), (7, vec![]));
+        }
+
+        #[test]
+        fn bias() {
+            assert_eq!(
+                complete("config bias "),
+                (
+                    12,
+                    vec!["pull-up".into(), "pull-down".into(), "disabled".into()]
+                )
+            );
+            assert_eq!(
+                complete("config bias pull"),
+                (12, vec!["pull-up".into(), "pull-down".into()])
+            );
+            assert_eq!(complete("config bias d"), (12, vec!["disabled".into()]));
+        }
+
+        #[test]
+        fn drive() {
+            assert_eq!(
+                complete("config drive "),
+                (
+                    13,
+                    vec![
+                        "push-pull".into(),
+                        "open-drain".into(),
+                        "open-source".into()
+                    ]
+                )
+            );
+            assert_eq!(
+                complete("config drive open-"),
+                (13, vec!["open-drain".into(), "open-source".into()])
+            );
+            assert_eq!(complete("config drive p"), (13, vec!["push-pull".into()]));
+        }
+
+        #[test]
+        fn unknown_attr() {
+            assert_eq!(complete("config speed "), (13, vec![]));
+        }
+
+        #[test]
+        fn lines() {
+            assert_eq!(
+                complete("config bias pull-up "),
+                (20, vec!["led0".into(), "led1".into()])
+            );
+            assert_eq!(
+                complete("config bias pull-up led0 "),
+                (25, vec!["led1".into()])
+            );
+            assert_eq!(
+                complete("config drive push-pull led"),
+                (23, vec!["led0".into(), "led1".into()])
+            );
+        }
+    }
+
+    mod complete_format {
+        use super::{CommandWords, InteractiveHelper};
+
+        fn complete(line: &str) -> (usize, Vec<String>) {
+            let helper = InteractiveHelper {
+                line_names: vec!["led0".to_string(), "led1".to_string()],
+            };
+            let mut words = CommandWords::new(line);
+            assert_eq!(words.next(), Some("format"));
+            let (pos, candidates) = helper.complete_format(line.len(), words);
+            (pos, candidates.into_iter().map(|c| c.display).collect())
+        }
+
+        #[test]
+        fn formats() {
+            assert_eq!(
+                complete("format "),
+                (7, vec!["numeric".into(), "named".into()])
+            );
+            assert_eq!(
+                complete("format n"),
+                (7, vec!["numeric".into(), "named".into()])
+            );
+            assert_eq!(complete("format nu"), (7, vec!["numeric".into()]));
+            assert_eq!(complete("format x"), (7, vec![]));
+            assert_eq!(complete("format named "), (13, vec![]));
+        }
+    }
+
+    mod complete_command {
+        use super::InteractiveHelper;
+
+        fn complete(line: &str) -> (usize, Vec<String>) {
+            let helper = InteractiveHelper {
+                line_names: vec!["led0".to_string(), "led1".to_string()],
+            };
+            let (pos, candidates) =
+                helper.complete_command(line, super::command_start(line), line.len());
+            (pos, candidates.into_iter().map(|c| c.display).collect())
+        }
+
+        #[test]
+        fn commands() {
+            let (pos, candidates) = complete("");
+            assert_eq!(pos, 0);
+            assert_eq!(candidates.len(), 10);
+            assert!(candidates.contains(&"repeat".into()));
+            assert_eq!(complete("re"), (0, vec!["repeat".into()]));
+            assert_eq!(complete("  s"), (2, vec!["set".into(), "sleep".into()]));
+            assert_eq!(complete("fo"), (0, vec!["format".into()]));
+        }
+
+        #[test]
+        fn after_separator() {
+            assert_eq!(complete("toggle led0; sl"), (13, vec!["sleep".into()]));
+            assert_eq!(complete("toggle led0;sl"), (12, vec!["sleep".into()]));
+            assert_eq!(
+                complete("sleep 1ms; toggle "),
+                (18, vec!["led0".into(), "led1".into()])
+            );
+            let (pos, candidates) = complete("toggle led0; ");
+            assert_eq!(pos, 13);
+            assert_eq!(candidates.len(), 10);
+        }
+
+        #[test]
+        fn quoted_separator() {
+            // the ';' is part of the quoted word, so still completing the set command
+            assert_eq!(
+                complete("set \"a;b\"=1 l"),
+                (12, vec!["led0".into(), "led1".into()])
+            );
+        }
+
+        #[test]
+        fn repeat() {
+            assert_eq!(complete("repeat "), (7, vec![]));
+            assert_eq!(complete("repeat 8"), (8, vec![]));
+            assert_eq!(complete("repeat 8 tog"), (9, vec!["toggle".into()]));
+            assert_eq!(
+                complete("repeat 8 toggle "),
+                (16, vec!["led0".into(), "led1".into()])
+            );
+            assert_eq!(
+                complete("repeat 8 toggle led0; s"),
+                (22, vec!["set".into(), "sleep".into()])
+            );
+            assert_eq!(complete("repeat 2 repeat 3 g"), (18, vec!["get".into()]));
+        }
+    }
+
     mod command_words {
         use super::CommandWords;
         #[test]
@@ -341,6 +627,54 @@ mod tests {
             assert!(words.inquote);
         }
 
+        #[test]
+        fn separators() {
+            let words: Vec<&str> =
+                CommandWords::new("toggle CLK; sleep 1ms ;set a=1;; get").collect();
+            assert_eq!(
+                words,
+                vec!["toggle", "CLK", ";", "sleep", "1ms", ";", "set", "a=1", ";", ";", "get"]
+            );
+        }
+
+        #[test]
+        fn trailing_separator() {
+            let mut words = CommandWords::new("toggle CLK;");
+            assert_eq!(words.next(), Some("toggle"));
+            assert_eq!(words.next(), Some("CLK"));
+            assert!(!words.partial);
+            assert_eq!(words.next(), Some(";"));
+            assert!(words.partial);
+            assert_eq!(words.next(), None);
+        }
+
+        #[test]
+        fn quoted_separators() {
+            let mut words = CommandWords::new("set \"a;b\"=1; get \"c ;d");
+            assert_eq!(words.next(), Some("set"));
+            assert_eq!(words.next(), Some("\"a;b\"=1"));
+            assert_eq!(words.next(), Some(";"));
+            assert_eq!(words.next(), Some("get"));
+            assert_eq!(words.next(), Some("\"c ;d"));
+            assert!(words.partial);
+            assert!(words.inquote);
+            assert_eq!(words.next(), None);
+        }
+
+        #[test]
+        fn offset() {
+            let line = "toggle CLK; sleep 1ms";
+            let mut words = CommandWords::new(line);
+            let mut offsets = Vec::new();
+            while let Some(word) = words.next() {
+                offsets.push(words.offset(word));
+            }
+            assert_eq!(offsets, vec![0, 7, 10, 12, 18]);
+            assert_eq!(super::super::command_start(line), 11);
+            assert_eq!(super::super::command_start("toggle CLK"), 0);
+            assert_eq!(super::super::command_start("set \"a;b\"=1"), 0);
+        }
+
         #[test]
         fn quotes_mid_words() {
             let mut words = CommandWords::new("quoted \"comm\"and\" lines\" \"are awful");
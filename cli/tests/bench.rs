@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::process::{Command, Output};
+
+fn bench(sim: &gpiosim::Simpleton, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["bench", "--duration", "50ms", "--warmup", "10", "-c"])
+        .arg(sim.dev_path())
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn set_and_get() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = bench(&sim, &["1"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("bench "));
+    assert!(rows[1].starts_with("set "));
+    assert!(rows[2].starts_with("get "));
+}
+
+#[test]
+fn edge_without_loopback() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    // the sim does not loop outputs back to inputs, so no edges are detected.
+    let output = bench(&sim, &["--edge-timeout", "10ms", "1", "2"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "no edge event received on line 2 - is line 1 looped back to it?\n"
+    );
+}
+
+#[test]
+fn edge_sim() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = bench(&sim, &["--sim", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert!(rows[1].starts_with("set "));
+    assert!(rows[2].starts_with("get "));
+    assert!(rows[3].starts_with("edge "));
+}
+
+#[cfg(all(feature = "json", feature = "uapi_v1", feature = "uapi_v2"))]
+fn bench_json(abiv: &str) {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = bench(&sim, &["--json", "--abi-version", abiv, "3"]);
+    assert!(output.status.success());
+    let summaries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summaries = summaries.as_array().unwrap();
+    assert_eq!(summaries.len(), 2);
+    for (s, name) in summaries.iter().zip(["set", "get"]) {
+        assert_eq!(s["name"], name);
+        assert!(s["count"].as_u64().unwrap() > 0);
+        assert!(s["rate"].as_u64().unwrap() > 0);
+        let min = s["min_ns"].as_u64().unwrap();
+        let mean = s["mean_ns"].as_u64().unwrap();
+        let p99 = s["p99_ns"].as_u64().unwrap();
+        let max = s["max_ns"].as_u64().unwrap();
+        assert!(min <= mean && mean <= max);
+        assert!(min <= p99 && p99 <= max);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "uapi_v1", feature = "uapi_v2"))]
+fn json_v1() {
+    bench_json("v1");
+}
+
+#[test]
+#[cfg(all(feature = "json", feature = "uapi_v1", feature = "uapi_v2"))]
+fn json_v2() {
+    bench_json("v2");
+}
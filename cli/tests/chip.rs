@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+// Kills the child when dropped, so it does not outlive a failed test.
+struct Reaper(Child);
+
+impl Drop for Reaper {
+    fn drop(&mut self) {
+        _ = self.0.kill();
+        _ = self.0.wait();
+    }
+}
+
+#[test]
+fn watch() {
+    let mut child = Reaper(
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .args(["chip", "--watch"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    // allow the watch to be established
+    thread::sleep(Duration::from_millis(100));
+
+    let sim = gpiosim::builder()
+        .with_bank(&gpiosim::Bank::new(4, "chip watch"))
+        .live()
+        .unwrap();
+    let name = sim.chips()[0].chip_name.clone();
+    thread::sleep(Duration::from_millis(100));
+    drop(sim);
+    thread::sleep(Duration::from_millis(100));
+
+    let stdout = child.0.stdout.take().unwrap();
+    drop(child);
+    let lines: Vec<String> = BufReader::new(stdout).lines().map(|l| l.unwrap()).collect();
+    // other tests may be adding and removing chips concurrently
+    let added = format!("added {} [chip watch] (4 lines)", name);
+    let removed = format!("removed {}", name);
+    let added_idx = lines.iter().position(|l| l == &added).unwrap();
+    let removed_idx = lines.iter().position(|l| l == &removed).unwrap();
+    assert!(added_idx < removed_idx);
+}
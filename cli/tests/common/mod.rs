@@ -0,0 +1,37 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Helpers shared by the integration tests.
+//
+// Each test crate uses a subset of these.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+// Poll the condition until it holds, giving up after 2 seconds.
+pub fn wait_for<F: Fn() -> bool>(cond: F) -> bool {
+    for _ in 0..100 {
+        if cond() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+// Read the pid written to a pidfile.
+pub fn read_pid(path: &Path) -> Option<libc::pid_t> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// A sim with line 2 named, so the line can be found again after the sim is
+// recreated.
+pub fn named_sim(name: &str) -> gpiosim::Sim {
+    gpiosim::builder()
+        .with_bank(gpiosim::Bank::new(4, "reattach").name(2, name))
+        .live()
+        .unwrap()
+}
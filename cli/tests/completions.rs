@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiosim::Bank;
+use std::process::Command;
+
+fn complete_lines(prefix: &str) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["__complete-lines", prefix])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[test]
+fn complete_lines_matches_prefix() {
+    let _sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "complete_lines 1")
+                .name(3, "cl banana")
+                .name(6, "cl apple"),
+        )
+        .with_bank(
+            Bank::new(12, "complete_lines 2")
+                .name(3, "cl apricot")
+                .name(4, "cl banana")
+                .name(5, "cl cherry"),
+        )
+        .live()
+        .unwrap();
+
+    // sorted and without duplicates
+    assert_eq!(
+        complete_lines("cl "),
+        ["cl apple", "cl apricot", "cl banana", "cl cherry"]
+    );
+    assert_eq!(complete_lines("cl ap"), ["cl apple", "cl apricot"]);
+    assert_eq!(complete_lines("cl ch"), ["cl cherry"]);
+    assert!(complete_lines("cl nada").is_empty());
+}
+
+#[test]
+fn completions_include_line_helper() {
+    for shell in ["bash", "zsh", "fish"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .args(["completions", shell])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(script.contains("gpiocdev __complete-lines"));
+    }
+}
+
+#[test]
+fn completions_cover_line_commands() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["completions", "bash"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let script = String::from_utf8(output.stdout).unwrap();
+    let cmds = script
+        .lines()
+        .skip_while(|l| !l.contains("case \"${COMP_WORDS[1]}\" in"))
+        .nth(1)
+        .unwrap()
+        .trim()
+        .trim_end_matches(')');
+    let cmds: Vec<&str> = cmds.split('|').collect();
+    for cmd in ["get", "set", "edges", "line", "notify", "pulse", "mon", "s"] {
+        assert!(cmds.contains(&cmd), "{cmd} missing from {cmds:?}");
+    }
+    for cmd in ["bench", "chip", "platform", "completions"] {
+        assert!(!cmds.contains(&cmd), "{cmd} unexpected in {cmds:?}");
+    }
+}
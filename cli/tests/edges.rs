@@ -0,0 +1,542 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod common;
+use common::{named_sim, wait_for};
+
+use gpiocdev::chip::Chip;
+use gpiocdev::line::{Bias, EdgeDetection};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn all_lines_on_chip() {
+    let sim = gpiosim::Simpleton::new(8);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-F", "%o %E", "-n", "2", "-c"])
+        .arg(sim.dev_path())
+        .arg("all")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(
+        || (0..8).all(|offset| chip.line_info(offset).unwrap().used)
+    ));
+
+    sim.pullup(0).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pullup(7).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "0 rising\n7 rising\n"
+    );
+}
+
+#[test]
+fn stats() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    // the only report is the final one, when the event limit is reached
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--stats", "10s", "-n", "6", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+
+    for _ in 0..3 {
+        sim.pullup(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        sim.pulldown(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows.len(), 3, "unexpected output: {}", stdout);
+    assert_eq!(rows[0], ["line", "edge", "count", "min", "mean", "max"]);
+    // 3 edges of each kind provide 2 intervals
+    assert_eq!(rows[1][..3], ["1", "rising", "2"]);
+    assert_eq!(rows[2][..3], ["1", "falling", "2"]);
+}
+
+#[test]
+fn max_events() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-F", "%o %E", "--max-events", "5"])
+        .args(["--timeout", "5s", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+
+    for _ in 0..3 {
+        sim.pullup(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        sim.pulldown(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // the command exits after the fifth edge, so the sixth is not reported
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(!chip.line_info(1).unwrap().used);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1 rising\n1 falling\n1 rising\n1 falling\n1 rising\n"
+    );
+}
+
+#[test]
+fn timeout() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let start = std::time::Instant::now();
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-F", "%o %E", "--max-events", "5"])
+        .args(["--timeout", "300ms", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    sim.pullup(1).unwrap();
+
+    // the timeout expires before the event limit is reached
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1 rising\n");
+}
+
+#[test]
+fn overlapping_ranges() {
+    let sim = gpiosim::Simpleton::new(8);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-q", "--idle-timeout", "500ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["2-4", "3-5", "5"])
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+    for offset in 0..8 {
+        assert_eq!(
+            chip.line_info(offset).unwrap().used,
+            (2..=5).contains(&offset),
+            "offset {}",
+            offset
+        );
+    }
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn descending_range() {
+    let sim = gpiosim::Simpleton::new(8);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-c"])
+        .arg(sim.dev_path())
+        .arg("5-3")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("offset range '5-3' is descending"));
+}
+
+#[test]
+fn range_out_of_range() {
+    let sim = gpiosim::Simpleton::new(8);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-c", &sim.chip().chip_name, "6-4294967295"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "offset 4294967295 is out of range on chip '{}'\n",
+            sim.chip().chip_name
+        )
+    );
+}
+
+#[test]
+fn seqno() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--seqno", "-n", "2", "-c"])
+        .arg(sim.dev_path())
+        .args(["1", "2"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+    sim.pullup(1).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pullup(2).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("\tseqno=1 line_seqno=1\t"));
+    assert!(lines[1].contains("\tseqno=2 line_seqno=1\t"));
+    // no events were lost
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn read_on_event() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    // line 0 strobes the data on lines 1 and 2
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-e", "rising", "-n", "2", "--read-on-event", "2,1"])
+        .args(["--read-on-event", "0", "-c"])
+        .arg(sim.dev_path())
+        .arg("0")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(
+        || (0..3).all(|offset| chip.line_info(offset).unwrap().used)
+    ));
+    // the data lines are requested as inputs
+    assert_eq!(chip.line_info(1).unwrap().edge_detection, None);
+
+    sim.pullup(1).unwrap();
+    sim.pullup(0).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pulldown(0).unwrap();
+    sim.pulldown(1).unwrap();
+    sim.pullup(2).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pullup(0).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let values: Vec<&str> = stdout
+        .lines()
+        .map(|l| l.rsplit('\t').next().unwrap())
+        .collect();
+    assert_eq!(
+        values,
+        [
+            "2=inactive 1=active 0=active",
+            "2=active 1=inactive 0=active"
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn read_on_event_json() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--json", "-n", "1", "--read-on-event", "3", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(3).unwrap().used));
+    sim.pullup(3).unwrap();
+    sim.pullup(1).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let event: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(event["offset"], 1);
+    assert_eq!(event["values"], serde_json::json!({"3": "Active"}));
+}
+
+#[test]
+fn event_buffer_overflow() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args([
+            "edges",
+            "--seqno",
+            "--event-buffer-size",
+            "2",
+            "-n",
+            "2",
+            "-c",
+        ])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+
+    // stop the reader so a burst of events overflows the kernel buffer
+    let pid = child.id() as libc::pid_t;
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGSTOP) }, 0);
+    for _ in 0..3 {
+        sim.pullup(1).unwrap();
+        thread::sleep(Duration::from_millis(10));
+        sim.pulldown(1).unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGCONT) }, 0);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    // only the most recent events are retained...
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("\tseqno=5 line_seqno=5\t"));
+    assert!(lines[1].contains("\tseqno=6 line_seqno=6\t"));
+    // ...and the gap in the seqno is reported.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        format!("warning: 4 events lost on {}\n", sim.chip().chip_name)
+    );
+}
+
+#[test]
+fn config_file() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let config = std::env::temp_dir().join(format!("gpiocdev-edges-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        format!(
+            "consumer = \"pins\"\n\
+            [lines.button]\n\
+            chip = \"{0}\"\n\
+            offset = 2\n\
+            active_low = true\n\
+            bias = \"pull-up\"\n\
+            edge = \"falling\"\n\
+            [lines.door]\n\
+            chip = \"{0}\"\n\
+            offset = 3\n",
+            sim.dev_path().display()
+        ),
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--config"])
+        .arg(&config)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(3).unwrap().used));
+    let info = chip.line_info(2).unwrap();
+    assert!(info.used);
+    assert_eq!(info.consumer.as_str(), "pins");
+    assert!(info.active_low);
+    assert_eq!(info.bias, Some(Bias::PullUp));
+    assert_eq!(info.edge_detection, Some(EdgeDetection::FallingEdge));
+    // lines without an edge in the config default to both
+    let info = chip.line_info(3).unwrap();
+    assert_eq!(info.edge_detection, Some(EdgeDetection::BothEdges));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    _ = std::fs::remove_file(&config);
+}
+
+#[test]
+fn output_file() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let output = std::env::temp_dir().join(format!("gpiocdev-edges-{}.log", std::process::id()));
+    // existing content is preserved
+    std::fs::write(&output, "previous capture\n").unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-F", "%o %E", "-n", "3", "--output"])
+        .arg(&output)
+        .arg("-c")
+        .arg(sim.dev_path())
+        .args(["1", "2"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+
+    sim.pullup(1).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pullup(2).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pulldown(1).unwrap();
+
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    assert!(out.stdout.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "previous capture\n1 rising\n2 rising\n1 falling\n"
+    );
+    _ = std::fs::remove_file(&output);
+}
+
+#[test]
+fn output_file_unopenable() {
+    let output = std::env::temp_dir()
+        .join(format!("gpiocdev-edges-{}", std::process::id()))
+        .join("missing")
+        .join("events.log");
+    let sim = gpiosim::Simpleton::new(4);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--output"])
+        .arg(&output)
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("1")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .starts_with(&format!("failed to open '{}'", output.display())));
+}
+
+#[test]
+fn daemonize_reports_to_log_file() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let log_file = std::env::temp_dir().join(format!("gpiocdev-edges-{}.log", std::process::id()));
+    _ = std::fs::remove_file(&log_file);
+
+    // the parent exits once the line is requested and the daemon has started
+    let status = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-z", "-F", "%o %E", "-n", "2", "--log-file"])
+        .arg(&log_file)
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(chip.line_info(1).unwrap().used);
+
+    // edges after detaching are reported by the daemon
+    sim.pullup(1).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    sim.pulldown(1).unwrap();
+    let log = || std::fs::read_to_string(&log_file).unwrap_or_default();
+    assert!(wait_for(|| log() == "1 rising\n1 falling\n"));
+
+    // the daemon exits after the requested number of events
+    assert!(wait_for(|| !chip.line_info(1).unwrap().used));
+    _ = std::fs::remove_file(&log_file);
+}
+
+#[test]
+fn chip_removed() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    drop(chip);
+    // unplug the chip
+    drop(sim);
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("has been removed"), "stderr: {}", stderr);
+}
+
+#[test]
+fn auto_reattach() {
+    let name = format!("edges-reattach-{}", std::process::id());
+    let log = std::env::temp_dir().join(format!("{}.log", name));
+    let reattached = || {
+        std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .contains("reattached lines")
+    };
+    let sim = named_sim(&name);
+    let chip = Chip::from_path(sim.chips()[0].dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["edges", "--auto-reattach", "-F", "%o %E", "-n", "1"])
+        .arg(&name)
+        .stdout(Stdio::piped())
+        .stderr(std::fs::File::create(&log).unwrap())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+    drop(chip);
+    // unplug the chip, then replug it with the same line names
+    drop(sim);
+    let sim = named_sim(&name);
+    let c = &sim.chips()[0];
+    assert!(wait_for(reattached));
+    c.set_pull(2, gpiosim::Level::High).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = std::fs::read_to_string(&log).unwrap();
+    _ = std::fs::remove_file(&log);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2 rising\n");
+    assert!(stderr.contains("has been removed"), "stderr: {}", stderr);
+}
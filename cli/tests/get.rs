@@ -0,0 +1,617 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::chip::Chip;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn consumer_applied_while_held() {
+    let sim = gpiosim::Simpleton::new(4);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "--consumer", "contention test", "-p", "1s", "-c"])
+        .arg(sim.dev_path())
+        .arg("2")
+        .spawn()
+        .unwrap();
+
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let mut used = false;
+    for _ in 0..40 {
+        let info = chip.line_info(2).unwrap();
+        if info.used {
+            assert_eq!(info.consumer.as_str(), "contention test");
+            used = true;
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(used);
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(2).unwrap().used);
+}
+
+#[test]
+fn glob_expands_to_matching_lines() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            gpiosim::Bank::new(8, "get_glob")
+                .name(1, "gg LED2")
+                .name(3, "gg LED0")
+                .name(4, "gg LED1")
+                .name(6, "gg LEDX"),
+        )
+        .live()
+        .unwrap();
+    let chip = &sim.chips()[0];
+
+    let get = |patterns: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .args(["get", "--glob", "-c"])
+            .arg(chip.dev_path())
+            .args(patterns)
+            .output()
+            .unwrap()
+    };
+
+    // '*' - sorted by offset
+    let output = get(&["gg LED*"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "gg LED2=inactive gg LED0=inactive gg LED1=inactive gg LEDX=inactive\n"
+    );
+
+    // '?' and character classes
+    let output = get(&["gg LED[0-1]", "gg LED?"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "gg LED0=inactive gg LED1=inactive gg LED2=inactive gg LEDX=inactive\n"
+    );
+
+    let output = get(&["gg LED[!0-1]"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "gg LED2=inactive gg LEDX=inactive\n"
+    );
+
+    // ignoring case
+    let output = get(&["gg led[0-1]"]);
+    assert!(!output.status.success());
+    let output = get(&["--ignore-case", "gg led[0-1]"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "gg LED0=inactive gg LED1=inactive\n"
+    );
+
+    // no match
+    let output = get(&["gg LED[3-9]"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "no lines match pattern 'gg LED[3-9]'\n"
+    );
+}
+
+#[test]
+fn ignore_case() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            gpiosim::Bank::new(4, "get_ignore_case")
+                .name(1, "gic Gpio17")
+                .name(2, "gic LED")
+                .name(3, "gic led"),
+        )
+        .live()
+        .unwrap();
+    let chip = &sim.chips()[0];
+
+    let get = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .arg("get")
+            .args(args)
+            .arg("-c")
+            .arg(chip.dev_path())
+            .output()
+            .unwrap()
+    };
+
+    // names are case sensitive by default, with case variants suggested
+    let output = get(&["gic GPIO17"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "cannot find line 'gic GPIO17'; did you mean 'gic Gpio17'?\n"
+    );
+
+    let output = get(&["--ignore-case", "gic GPIO17"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "gic GPIO17=inactive\n"
+    );
+
+    // the first match is used unless --strict
+    let output = get(&["--ignore-case", "gic Led"]);
+    assert!(output.status.success());
+
+    let output = get(&["--ignore-case", "--strict", "gic Led"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "line 'gic Led' is not unique\n"
+    );
+
+    // names differing only in case are distinct without --ignore-case
+    let output = get(&["--strict", "gic LED"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn hold_rereads_on_enter() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "--hold", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "1=inactive\n");
+
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(chip.line_info(1).unwrap().used);
+
+    sim.pullup(1).unwrap();
+    stdin.write_all(b"\n").unwrap();
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "1=active\n");
+
+    // closing stdin exits and releases the line
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn watch_samples_until_interrupted() {
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    sim.pullup(2).unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "--watch", "--interval", "50ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["1", "2"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    thread::sleep(Duration::from_millis(275));
+    assert!(chip.line_info(1).unwrap().used);
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) },
+        0
+    );
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(!chip.line_info(1).unwrap().used);
+
+    // a row every interval, each with the time since the first row
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert!((4..=7).contains(&rows.len()), "{:?}", rows);
+    let mut last = -1.0;
+    for (idx, row) in rows.iter().enumerate() {
+        let (ts, values) = row.split_once('\t').unwrap();
+        assert_eq!(values, "1=inactive 2=active");
+        let ts: f64 = ts.parse().unwrap();
+        assert!(ts > last);
+        // samples are scheduled from the first, so do not drift
+        assert!((ts - idx as f64 * 0.05).abs() < 0.025, "{:?}", rows);
+        last = ts;
+    }
+}
+
+#[test]
+fn watch_raw_format() {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(12);
+    sim.pullup(2).unwrap();
+    sim.pullup(9).unwrap();
+    let start = monotonic_ns();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args([
+            "get",
+            "--watch",
+            "--interval",
+            "20ms",
+            "--format",
+            "raw",
+            "-c",
+        ])
+        .arg(sim.dev_path())
+        .args(["1", "2", "3", "4", "5", "6", "7", "8", "9"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // read several records from the pipe while sampling continues
+    let mut stdout = child.stdout.take().unwrap();
+    let mut last = start;
+    for _ in 0..4 {
+        // 8 bytes of timestamp and 2 bytes of values for the 9 lines
+        let mut record = [0; 10];
+        stdout.read_exact(&mut record).unwrap();
+        let ts = u64::from_le_bytes(record[..8].try_into().unwrap());
+        assert!(ts > last);
+        last = ts;
+        // lines 2 and 9 are the 2nd and 9th lines
+        assert_eq!(record[8], 0b0000_0010);
+        assert_eq!(record[9], 0b0000_0001);
+    }
+    assert!(last < monotonic_ns());
+
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) },
+        0
+    );
+    let mut rest = Vec::new();
+    stdout.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest.len() % 10, 0);
+    assert!(child.wait().unwrap().success());
+}
+
+// The current CLOCK_MONOTONIC time, as used to timestamp info change events.
+fn monotonic_ns() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    assert_eq!(
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) },
+        0
+    );
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+#[test]
+fn hold_for_settles_reads_then_holds() {
+    use gpiocdev::line::{Bias, InfoChangeKind};
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    chip.watch_line_info(1).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "--bias", "pull-up", "--settling-time", "100ms"])
+        .args(["--hold-for", "200ms", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let read_ns = monotonic_ns();
+    assert_eq!(line, "1=active\n");
+
+    // the bias remains applied after the values are read
+    let info = chip.line_info(1).unwrap();
+    assert!(info.used);
+    assert_eq!(info.bias, Some(Bias::PullUp));
+
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+
+    assert!(chip
+        .wait_line_info_change_event(Duration::from_millis(100))
+        .unwrap());
+    let requested = chip.read_line_info_change_event().unwrap();
+    assert_eq!(requested.kind, InfoChangeKind::Requested);
+    let released = chip.read_line_info_change_event().unwrap();
+    assert_eq!(released.kind, InfoChangeKind::Released);
+    // request, settle, read...
+    assert!(read_ns - requested.timestamp_ns >= 100_000_000);
+    // ...hold, then release - allowing for the latency of reading the output.
+    assert!(released.timestamp_ns - read_ns >= 180_000_000);
+}
+
+#[test]
+fn hold_until_signal() {
+    use gpiocdev::line::{Bias, InfoChangeKind};
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    chip.watch_line_info(2).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "--bias", "pull-down", "--hold-until-signal", "-c"])
+        .arg(sim.dev_path())
+        .arg("2")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let read_ns = monotonic_ns();
+    assert_eq!(line, "2=inactive\n");
+
+    // held indefinitely, with the bias applied
+    thread::sleep(Duration::from_millis(100));
+    assert!(child.try_wait().unwrap().is_none());
+    let info = chip.line_info(2).unwrap();
+    assert!(info.used);
+    assert_eq!(info.bias, Some(Bias::PullDown));
+
+    // until signalled, when the lines are released cleanly
+    let signal_ns = monotonic_ns();
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) },
+        0
+    );
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(2).unwrap().used);
+
+    let requested = chip.read_line_info_change_event().unwrap();
+    assert_eq!(requested.kind, InfoChangeKind::Requested);
+    assert!(requested.timestamp_ns < read_ns);
+    let released = chip.read_line_info_change_event().unwrap();
+    assert_eq!(released.kind, InfoChangeKind::Released);
+    assert!(released.timestamp_ns > signal_ns);
+}
+
+#[test]
+fn offset_out_of_range() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "-c", &sim.chip().chip_name, "999"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "offset 999 is out of range on chip '{}'\n",
+            sim.chip().chip_name
+        )
+    );
+}
+
+#[test]
+fn conflicting_config() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    // bias requires a direction, so cannot be applied as-is
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["get", "-v", "--as-is", "--bias", "pull-up", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "failed to request lines [1] from {}: \"{}\" line 1 has a conflicting config - bias requires the direction to be set.\n",
+            sim.chip().chip_name,
+            sim.dev_path().display()
+        )
+    );
+}
+
+#[test]
+fn retry_busy_line() {
+    let sim = gpiosim::Simpleton::new(4);
+    let held = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(2)
+        .as_input()
+        .request()
+        .unwrap();
+
+    let get = |retry: &str| {
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .args(["get", "--retry", retry, "--retry-delay", "20ms", "-c"])
+            .arg(sim.dev_path())
+            .arg("2")
+            .output()
+            .unwrap()
+    };
+
+    // busy without retries
+    let output = get("0");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("is busy"));
+
+    // released while retrying
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        drop(held);
+    });
+    let output = get("20");
+    releaser.join().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2=inactive\n");
+}
+
+#[test]
+fn active_low_inverts_value() {
+    let sim = gpiosim::Simpleton::new(4);
+    sim.pullup(1).unwrap();
+    sim.pulldown(3).unwrap();
+
+    let get = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .arg("get")
+            .args(args)
+            .arg("-c")
+            .arg(sim.dev_path())
+            .args(["1", "3"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(get(&[]), "1=active 3=inactive\n");
+    assert_eq!(get(&["--active-low"]), "1=inactive 3=active\n");
+    assert_eq!(get(&["-l", "--numeric"]), "0 1\n");
+
+    // only the interpretation changes - the physical levels are unchanged
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::High);
+    assert_eq!(sim.get_level(3).unwrap(), gpiosim::Level::Low);
+}
+
+#[test]
+fn as_is_reads_back_output() {
+    use gpiocdev::line::Value;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    // configure lines as outputs - the sim retains the direction and value when released
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(1)
+        .as_output(Value::Active)
+        .with_line(3)
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+    drop(req);
+
+    let get = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .arg("get")
+            .args(args)
+            .arg("-c")
+            .arg(sim.dev_path())
+            .args(["1", "3"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(get(&["--as-is"]), "1=active 3=inactive\n");
+    assert_eq!(
+        chip.line_info(1).unwrap().direction,
+        gpiocdev::line::Direction::Output
+    );
+    assert_eq!(get(&["--as-is", "--numeric"]), "1 0\n");
+    assert_eq!(get(&["--as-is", "--values-only"]), "active inactive\n");
+    assert_eq!(
+        get(&["--as-is", "--active-low", "--values-only"]),
+        "inactive active\n"
+    );
+
+    // without --as-is the lines are switched to input and follow the sim pulls
+    assert_eq!(get(&["--bias", "pull-down"]), "1=inactive 3=inactive\n");
+    assert_eq!(
+        chip.line_info(1).unwrap().direction,
+        gpiocdev::line::Direction::Input
+    );
+}
+
+#[test]
+fn unrequested_reads_held_lines() {
+    use gpiocdev::line::Value;
+
+    let sim = gpiosim::Simpleton::new(4);
+
+    // the outputs are held, so cannot be requested by the command
+    let _req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(1)
+        .as_output(Value::Active)
+        .with_line(3)
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+
+    let get = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .arg("get")
+            .args(args)
+            .arg("-c")
+            .arg(sim.dev_path())
+            .args(["1", "2", "3"])
+            .output()
+            .unwrap()
+    };
+
+    let output = get(&["--as-is"]);
+    assert!(!output.status.success());
+
+    let output = get(&["--unrequested"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // the output values are only known if reported by the kernel
+    assert!(
+        stdout == "1=active 2=unknown 3=inactive\n" || stdout == "1=unknown 2=unknown 3=unknown\n",
+        "unexpected output: {}",
+        stdout
+    );
+
+    // and the lines remain held
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn verbosity_controls_logging() {
+    let sim = gpiosim::Simpleton::new(4);
+    let get = |verbosity: &str| {
+        Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .args(["get", verbosity, "--consumer", "log test", "-c"])
+            .arg(sim.dev_path())
+            .arg("2")
+            .env_remove("RUST_LOG")
+            .output()
+            .unwrap()
+    };
+
+    // no library logging
+    let output = get("-v");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+
+    // requests and value reads
+    let output = get("-vv");
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let chip = format!("{:?}", sim.dev_path());
+    assert!(stderr.contains("DEBUG"));
+    assert!(stderr.contains(&format!("requested lines [2] on {}", chip)));
+    assert!(stderr.contains("consumer \"log test\""));
+    assert!(stderr.contains(&format!("lines [2] on {}: get values", chip)));
+}
@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiocdev::chip::Chip;
+use gpiocdev::Request;
+use std::process::Command;
+
+#[test]
+fn used() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip_name = Chip::from_path(sim.dev_path()).unwrap().name();
+    let _req = Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(2)
+        .with_consumer("auditee")
+        .as_input()
+        .request()
+        .unwrap();
+
+    let line = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+            .arg("line")
+            .args(args)
+            .arg("-c")
+            .arg(sim.dev_path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        line(&["--used"]),
+        format!(
+            "{} - 4 lines:\n\tline   2:\tunnamed         \tinput consumer=auditee\n",
+            chip_name
+        )
+    );
+
+    // all lines without --used
+    assert_eq!(line(&[]).lines().count(), 5);
+
+    let json = line(&["--used", "--json"]);
+    assert_eq!(json.matches("\"offset\":").count(), 1);
+    assert!(json.contains("\"offset\":2"));
+    assert!(json.contains("\"consumer\":\"auditee\""));
+}
+
+fn attrs_sim() -> (gpiosim::Sim, Vec<Request>) {
+    use gpiocdev::line::{Bias, Drive, EdgeDetection, Value};
+    use std::time::Duration;
+
+    let sim = gpiosim::builder()
+        .with_bank(
+            gpiosim::Bank::new(4, "line format")
+                .name(1, "lf banana")
+                .name(2, "lf apple"),
+        )
+        .live()
+        .unwrap();
+    let c = &sim.chips()[0];
+    let reqs = vec![
+        Request::builder()
+            .on_chip(c.dev_path())
+            .with_line(1)
+            .with_consumer("peeler")
+            .as_output(Value::Active)
+            .as_active_low()
+            .with_drive(Drive::OpenDrain)
+            .with_bias(Bias::PullUp)
+            .request()
+            .unwrap(),
+        Request::builder()
+            .on_chip(c.dev_path())
+            .with_line(2)
+            .with_consumer("core")
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .with_bias(Bias::PullDown)
+            .with_debounce_period(Duration::from_millis(2))
+            .request()
+            .unwrap(),
+    ];
+    (sim, reqs)
+}
+
+fn line_stdout(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .arg("line")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn format_json() {
+    let (sim, _reqs) = attrs_sim();
+    let chip_name = Chip::from_path(sim.chips()[0].dev_path()).unwrap().name();
+
+    let out = line_stdout(&["--format", "json", "lf banana", "lf apple"]);
+    let lines: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        lines,
+        serde_json::json!([
+            {
+                "chip": chip_name,
+                "offset": 1,
+                "name": "lf banana",
+                "direction": "output",
+                "active_low": true,
+                "drive": "open-drain",
+                "bias": "pull-up",
+                "edge_detection": null,
+                "event_clock": "monotonic",
+                "used": true,
+                "consumer": "peeler",
+            },
+            {
+                "chip": chip_name,
+                "offset": 2,
+                "name": "lf apple",
+                "direction": "input",
+                "active_low": false,
+                "drive": null,
+                "bias": "pull-down",
+                "edge_detection": "both",
+                "event_clock": "monotonic",
+                "debounce_period_us": 2000,
+                "used": true,
+                "consumer": "core",
+            },
+        ])
+    );
+
+    // one object per line
+    let out = line_stdout(&["--format", "json-lines", "lf banana", "lf apple"]);
+    let objs: Vec<serde_json::Value> = out
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(objs.len(), 2);
+    assert_eq!(objs[0], lines[0]);
+    assert_eq!(objs[1], lines[1]);
+}
+
+#[test]
+fn columns() {
+    let (sim, _reqs) = attrs_sim();
+    let chip_name = Chip::from_path(sim.chips()[0].dev_path()).unwrap().name();
+
+    assert_eq!(
+        line_stdout(&[
+            "--columns",
+            "name,offset,chip,consumer,direction,active-low,drive,bias,edges,debounce,used",
+            "lf banana",
+            "lf apple",
+        ]),
+        format!(
+            "lf banana\t1\t{chip_name}\tpeeler\toutput\ttrue\topen-drain\tpull-up\t-\t-\ttrue\n\
+            lf apple\t2\t{chip_name}\tcore\tinput\tfalse\t-\tpull-down\tboth\t2ms\ttrue\n"
+        )
+    );
+
+    let c = sim.chips()[0].dev_path().to_str().unwrap();
+    assert_eq!(
+        line_stdout(&[
+            "--columns",
+            "offset,name,used",
+            "--quoted",
+            "-c",
+            c,
+            "0",
+            "1"
+        ]),
+        "0\t-\tfalse\n1\t\"lf banana\"\ttrue\n"
+    );
+
+    // the default format may be explicitly selected
+    assert_eq!(
+        line_stdout(&["--format", "text", "--columns", "offset", "-c", c, "1"]),
+        "1\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn columns_non_text_format() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["line", "--format", "json", "--columns", "offset", "-c"])
+        .arg(sim.dev_path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "--columns only applies to the text format\n"
+    );
+}
+
+#[test]
+fn ignore_case() {
+    let (sim, _reqs) = attrs_sim();
+    let c = sim.chips()[0].dev_path().to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["line", "-c", c, "LF Banana"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    assert_eq!(
+        line_stdout(&[
+            "--ignore-case",
+            "--columns",
+            "offset,name",
+            "-c",
+            c,
+            "LF Banana"
+        ]),
+        "1\tlf banana\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "procfs")]
+fn resolve_consumer() {
+    let sim = gpiosim::Simpleton::new(4);
+    // kernels that do not report the lines in fdinfo fallback to the open chip
+    let _chip = Chip::from_path(sim.dev_path()).unwrap();
+    let _req = Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(2)
+        .with_consumer("auditee")
+        .as_input()
+        .request()
+        .unwrap();
+    let dev_path = sim.dev_path().to_str().unwrap();
+    let pid = std::process::id().to_string();
+
+    let out = line_stdout(&["--resolve-consumer", "-c", dev_path, "2"]);
+    assert!(out.contains("consumer=auditee"));
+    assert!(out.contains("held by PID "));
+    assert!(out.contains(&pid));
+
+    // unused lines have no holders
+    let out = line_stdout(&["--resolve-consumer", "-c", dev_path, "1"]);
+    assert!(!out.contains("held by"));
+
+    // implied by --verbose
+    let out = line_stdout(&["-v", "-c", dev_path, "2"]);
+    assert!(out.contains("held by PID "));
+    assert!(out.contains(&pid));
+
+    let out = line_stdout(&[
+        "--resolve-consumer",
+        "-c",
+        dev_path,
+        "--columns",
+        "offset,consumer",
+    ]);
+    let line2 = out.lines().find(|l| l.starts_with("2\t")).unwrap();
+    assert!(line2.starts_with("2\tauditee "));
+    assert!(line2.contains(&pid));
+}
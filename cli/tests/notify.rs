@@ -0,0 +1,123 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod common;
+use common::{read_pid, wait_for};
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+#[test]
+fn all_lines_on_chip() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["notify", "--banner", "-F", "%o %E", "-n", "2", "-c"])
+        .arg(sim.dev_path())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // the banner is emitted once all lines are being watched
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut banner = String::new();
+    stdout.read_line(&mut banner).unwrap();
+    assert_eq!(
+        banner,
+        format!("Watching all lines on {}...\n", sim.chip().chip_name)
+    );
+
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_lines(&[1, 3])
+        .as_input()
+        .request()
+        .unwrap();
+
+    let mut changes = String::new();
+    for line in stdout.lines() {
+        changes.push_str(&line.unwrap());
+        changes.push('\n');
+    }
+    assert!(child.wait().unwrap().success());
+    assert_eq!(changes, "1 requested\n3 requested\n");
+    drop(req);
+}
+
+#[test]
+fn reconfigured_reports_changes() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["notify", "--banner", "-F", "%o %E:%R", "-n", "2", "-c"])
+        .arg(sim.dev_path())
+        .arg("1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // wait for the line to be watched
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut banner = String::new();
+    stdout.read_line(&mut banner).unwrap();
+
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(1)
+        .as_input()
+        .request()
+        .unwrap();
+    let mut cfg = req.config();
+    cfg.with_bias(gpiocdev::line::Bias::PullUp).as_active_low();
+    req.reconfigure(&cfg).unwrap();
+
+    let mut changes = String::new();
+    for line in stdout.lines() {
+        changes.push_str(&line.unwrap());
+        changes.push('\n');
+    }
+    assert!(child.wait().unwrap().success());
+    assert_eq!(changes, "1 requested:\n1 reconfigured:bias, active_low\n");
+    drop(req);
+}
+
+#[test]
+fn daemonize_reports_to_log_file() {
+    let sim = gpiosim::Simpleton::new(4);
+    let tmp = std::env::temp_dir();
+    let pidfile = tmp.join(format!("gpiocdev-notify-{}.pid", std::process::id()));
+    let log_file = tmp.join(format!("gpiocdev-notify-{}.log", std::process::id()));
+    _ = std::fs::remove_file(&pidfile);
+    _ = std::fs::remove_file(&log_file);
+
+    // the parent exits once the line is watched and the daemon has started
+    let status = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["notify", "-z", "-F", "%o %E", "--pidfile"])
+        .arg(&pidfile)
+        .arg("--log-file")
+        .arg(&log_file)
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(wait_for(|| read_pid(&pidfile).is_some()));
+    let pid = read_pid(&pidfile).unwrap();
+
+    // changes after detaching are reported by the daemon
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(1)
+        .as_input()
+        .request()
+        .unwrap();
+    drop(req);
+    let log = || std::fs::read_to_string(&log_file).unwrap_or_default();
+    assert!(wait_for(|| log() == "1 requested\n1 released\n"));
+
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+    assert!(wait_for(|| !pidfile.exists()));
+    _ = std::fs::remove_file(&log_file);
+}
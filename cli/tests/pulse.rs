@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use gpiosim::Level;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn pulse_train() {
+    let sim = gpiosim::Simpleton::new(4);
+    let offset = 2;
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args([
+            "pulse", "--count", "5", "--width", "20ms", "--period", "50ms", "--stats", "-c",
+        ])
+        .arg(sim.dev_path())
+        .arg(offset.to_string())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The line cannot also be requested for edge detection while it is being
+    // driven, so sample the simulated output level to find the edges.
+    let mut edges = Vec::new();
+    let mut last = Level::Low;
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline {
+        if let Ok(level) = sim.get_level(offset) {
+            if level != last {
+                edges.push((level, Instant::now()));
+                last = level;
+            }
+        }
+        if edges.len() == 10 {
+            break;
+        }
+        thread::sleep(Duration::from_micros(100));
+    }
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // five pulses, each a rising then falling edge
+    assert_eq!(edges.len(), 10);
+    for (idx, (level, _)) in edges.iter().enumerate() {
+        let expected = if idx % 2 == 0 {
+            Level::High
+        } else {
+            Level::Low
+        };
+        assert_eq!(*level, expected);
+    }
+    let ms = Duration::from_millis;
+    for pulse in edges.chunks(2) {
+        let width = pulse[1].1 - pulse[0].1;
+        assert!(width > ms(15) && width < ms(35), "width {:?}", width);
+    }
+    for rises in edges.iter().step_by(2).collect::<Vec<_>>().windows(2) {
+        let period = rises[1].1 - rises[0].1;
+        assert!(period > ms(40) && period < ms(65), "period {:?}", period);
+    }
+    assert_eq!(sim.get_level(offset).unwrap(), Level::Low);
+
+    let stats = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stats.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "pulses: 5");
+    assert!(lines[1].starts_with("width: min "));
+    assert!(lines[2].starts_with("period: min "));
+    assert!(lines[3].starts_with("max lateness: "));
+}
+
+#[test]
+fn period_required() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["pulse", "--count", "3", "--width", "20ms", "line"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "--period is required when --count is greater than one\n"
+    );
+}
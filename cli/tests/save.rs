@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod common;
+use common::wait_for;
+
+use gpiocdev::chip::Chip;
+use gpiocdev::line::{Bias, Direction, Value};
+use gpiosim::Level::{High, Low};
+use std::process::{Command, Stdio};
+
+// configure lines as outputs - the sim retains the direction and value when released
+fn set_outputs(sim: &gpiosim::Simpleton, v1: Value, v2: Value) {
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(1)
+        .as_output(v1)
+        .with_line(2)
+        .as_output(v2)
+        .request()
+        .unwrap();
+    drop(req);
+}
+
+#[test]
+fn save_and_restore() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_line(0)
+        .as_input()
+        .with_bias(Bias::PullUp)
+        .request()
+        .unwrap();
+    drop(req);
+    set_outputs(&sim, Value::Active, Value::Inactive);
+    let holder = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("holder")
+        .with_line(3)
+        .as_output(Value::Active)
+        .request()
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["save", "-c"])
+        .arg(sim.dev_path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "warning: line 3 is an output held by \"holder\", so its value cannot be read and it is not saved\n"
+    );
+    let state = String::from_utf8(output.stdout).unwrap();
+    assert!(state.contains("[lines.0]"));
+    assert!(state.contains("[lines.1]"));
+    assert!(state.contains("[lines.2]"));
+    assert!(!state.contains("[lines.3]"));
+    // the lines are released after saving
+    assert!(!chip.line_info(1).unwrap().used);
+    drop(holder);
+
+    let path = std::env::temp_dir().join(format!("gpiocdev-save-{}.toml", std::process::id()));
+    std::fs::write(&path, &state).unwrap();
+
+    // change the state, and hold a line so it cannot be restored
+    set_outputs(&sim, Value::Inactive, Value::Active);
+    assert_eq!(sim.get_level(1).unwrap(), Low);
+    let holder = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("holder")
+        .with_line(2)
+        .as_is()
+        .request()
+        .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .arg("restore")
+        .arg(&path)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    let info = chip.line_info(1).unwrap();
+    assert_eq!(info.consumer.as_str(), "gpiocdev-restore");
+    assert_eq!(info.direction, Direction::Output);
+    assert_eq!(sim.get_level(1).unwrap(), High);
+    assert_eq!(sim.get_level(2).unwrap(), High);
+    let info = chip.line_info(0).unwrap();
+    assert!(!info.used);
+
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "warning: line 0 is an input, so is not restored\n\
+         warning: line 2 is held by \"holder\", so is not restored\n"
+    );
+    drop(holder);
+    _ = std::fs::remove_file(&path);
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn save_json() {
+    let sim = gpiosim::Simpleton::new(4);
+    set_outputs(&sim, Value::Active, Value::Inactive);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["save", "--json", "-c"])
+        .arg(sim.dev_path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let state: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(state["lines"]["0"]["direction"], "input");
+    assert_eq!(state["lines"]["1"]["direction"], "output");
+    assert_eq!(state["lines"]["1"]["value"], "active");
+    assert_eq!(state["lines"]["2"]["value"], "inactive");
+}
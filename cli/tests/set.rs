@@ -0,0 +1,775 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+mod common;
+use common::{named_sim, read_pid, wait_for};
+
+use gpiocdev::chip::Chip;
+use gpiocdev::line::{Direction, Drive};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn daemonize_pidfile_and_sigterm() {
+    let sim = gpiosim::Simpleton::new(4);
+    let pidfile = std::env::temp_dir().join(format!("gpiocdev-set-{}.pid", std::process::id()));
+    _ = std::fs::remove_file(&pidfile);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-z", "--pidfile"])
+        .arg(&pidfile)
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("1=1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // the pidfile is written by the daemon, so may lag the parent exiting
+    assert!(wait_for(|| read_pid(&pidfile).is_some()));
+    let pid = read_pid(&pidfile).unwrap();
+
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let info = chip.line_info(1).unwrap();
+    assert!(info.used);
+    assert_eq!(info.consumer.as_str(), "gpiocdev-set");
+
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+
+    assert!(wait_for(|| !chip.line_info(1).unwrap().used));
+    assert!(wait_for(|| !pidfile.exists()));
+}
+
+#[test]
+fn pulse_timeline() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--pulse", "400ms", "--hold-period", "400ms", "-c"])
+        .arg(sim.dev_path())
+        .arg("1=1")
+        .spawn()
+        .unwrap();
+
+    // requested values are applied first...
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::High);
+
+    // ...then inverted once, and held for the hold period...
+    thread::sleep(Duration::from_millis(500));
+    assert!(chip.line_info(1).unwrap().used);
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::Low);
+
+    // ...then the command exits.
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn sequence_waveform() {
+    use gpiosim::Level::{High, Low};
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let levels = || (sim.get_level(1).unwrap(), sim.get_level(2).unwrap());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-c"])
+        .arg(sim.dev_path())
+        .args([
+            "--sequence",
+            "1=1,2=0; 300ms; 1=0,2=1; 300ms; 1=0,2=0; 300ms",
+        ])
+        .spawn()
+        .unwrap();
+
+    // the initial values are applied by the request...
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    assert_eq!(levels(), (High, Low));
+
+    // ...then each group in turn...
+    assert!(wait_for(|| levels() == (Low, High)));
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(levels(), (Low, High));
+
+    assert!(wait_for(|| levels() == (Low, Low)));
+    assert!(chip.line_info(1).unwrap().used);
+
+    // ...then the command exits after the final period.
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn sequence_repeat() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--repeat", "2", "-c"])
+        .arg(sim.dev_path())
+        .args(["--sequence", "1=1; 200ms; 1=0; 200ms"])
+        .spawn()
+        .unwrap();
+
+    let level = || sim.get_level(1).unwrap();
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    for _ in 0..2 {
+        assert!(wait_for(|| level() == gpiosim::Level::High));
+        assert!(wait_for(|| level() == gpiosim::Level::Low));
+    }
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn stagger() {
+    use gpiosim::Level::{High, Low};
+    use std::time::Instant;
+
+    let sim = gpiosim::Simpleton::new(6);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let start = Instant::now();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--stagger", "100ms", "--timeout", "600ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["4=1", "2=0", "1=1", "5=1"])
+        .spawn()
+        .unwrap();
+
+    // record when each line is driven high
+    let lines = [4, 1, 5];
+    let mut activated = [None; 3];
+    while activated.iter().any(|a| a.is_none()) && start.elapsed() < Duration::from_secs(2) {
+        for (idx, offset) in lines.iter().enumerate() {
+            if activated[idx].is_none() && sim.get_level(*offset).unwrap() == High {
+                activated[idx] = Some(start.elapsed());
+            }
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(chip.line_info(2).unwrap().used);
+    assert_eq!(sim.get_level(2).unwrap(), Low);
+
+    // activated in command line order, spaced by the stagger period
+    let activated: Vec<Duration> = activated.iter().map(|a| a.unwrap()).collect();
+    for pair in activated.windows(2) {
+        let spacing = pair[1] - pair[0];
+        assert!(
+            spacing >= Duration::from_millis(95) && spacing < Duration::from_millis(150),
+            "{:?}",
+            activated
+        );
+    }
+
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn stagger_leaves_active_lines() {
+    use gpiosim::Level::{High, Low};
+    use std::time::Instant;
+
+    let sim = gpiosim::Simpleton::new(6);
+    // line 1 is already active, line 4 is not
+    sim.pullup(1).unwrap();
+
+    let start = Instant::now();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--stagger", "100ms", "--timeout", "300ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "4=1"])
+        .spawn()
+        .unwrap();
+
+    let mut activated = None;
+    while start.elapsed() < Duration::from_millis(250) {
+        // line 1 is never switched off
+        assert_eq!(sim.get_level(1).unwrap(), High);
+        if activated.is_none() && sim.get_level(4).unwrap() == High {
+            activated = Some(start.elapsed());
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    // line 4 is the only line staggered, so is activated without delay
+    assert!(activated.is_some());
+    assert!(child.wait().unwrap().success());
+    assert_eq!(sim.get_level(4).unwrap(), Low);
+}
+
+#[test]
+fn persist_warns_lines_may_revert() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--persist", "-c"])
+        .arg(sim.dev_path())
+        .arg("1=1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("lines may revert on exit"));
+    assert!(stderr.contains("--daemonize"));
+
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(!chip.line_info(1).unwrap().used);
+}
+
+#[test]
+fn check_conflicts() {
+    use gpiocdev::line::{EdgeDetection, Value};
+
+    let sim = gpiosim::Simpleton::new(4);
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("watcher")
+        .with_line(1)
+        .as_input()
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .with_line(2)
+        .as_output(Value::Active)
+        .request()
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--check-conflicts", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=0", "3=1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 1 is already requested by \"watcher\""));
+    assert!(stderr.contains("line 1 is an input with edge detection enabled"));
+    assert!(stderr.contains("line 2 is already requested by \"watcher\""));
+    assert!(!stderr.contains("line 3"));
+
+    // refused, so no lines are requested
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(!chip.line_info(3).unwrap().used);
+
+    // warnings only, so the request is attempted, and fails as the lines are busy
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--check-conflicts=warn", "--persist", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "3=1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("warning: line 1 is already requested by \"watcher\""));
+    assert!(stderr.contains("failed to request and set lines"));
+
+    // no conflicts once the lines are released
+    drop(req);
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--check-conflicts", "--persist", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "3=1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("requested by"));
+}
+
+#[test]
+fn wait_for_free() {
+    use gpiocdev::line::Value;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("holder")
+        .with_line(1)
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--wait-for-free", "--persist", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=1"])
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // blocked until the line is released
+    thread::sleep(Duration::from_millis(100));
+    assert!(child.try_wait().unwrap().is_none());
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(!chip.line_info(2).unwrap().used);
+
+    drop(req);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn wait_for_free_timeout() {
+    use gpiocdev::line::Value;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let _req = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("holder")
+        .with_lines(&[1, 3])
+        .as_output(Value::Inactive)
+        .request()
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--wait-for-free", "--wait-timeout", "50ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=1", "3=0"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "timed out waiting for line 1, line 3 to be released\n"
+    );
+}
+
+#[test]
+fn timeout_releases_lines() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--timeout", "200ms", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=0"])
+        .spawn()
+        .unwrap();
+
+    // held until the timeout expires
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    assert!(child.try_wait().unwrap().is_none());
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::High);
+
+    assert!(child.wait().unwrap().success());
+    assert!(!chip.line_info(1).unwrap().used);
+    assert!(!chip.line_info(2).unwrap().used);
+}
+
+#[test]
+fn ready_fd_notifies_before_toggling() {
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let mut fds = [0; 2];
+    // not O_CLOEXEC, so the write end is inherited by the child
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let mut rd = unsafe { std::fs::File::from_raw_fd(fds[0]) };
+    let wr = unsafe { std::fs::File::from_raw_fd(fds[1]) };
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--toggle", "500ms,0", "--ready-fd"])
+        .arg(fds[1].to_string())
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("1=1")
+        .spawn()
+        .unwrap();
+    // so the read sees EOF once the child closes its copy
+    drop(wr);
+
+    let mut ready = String::new();
+    rd.read_to_string(&mut ready).unwrap();
+    assert_eq!(ready, "READY=1\n");
+    // lines are requested and not yet toggled
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    assert!(chip.line_info(1).unwrap().used);
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::High);
+
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn ready_fd_not_notified_on_failure() {
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+
+    let sim = gpiosim::Simpleton::new(4);
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let mut rd = unsafe { std::fs::File::from_raw_fd(fds[0]) };
+    let wr = unsafe { std::fs::File::from_raw_fd(fds[1]) };
+
+    // offset out of range for the chip, so the request fails
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--ready-fd"])
+        .arg(fds[1].to_string())
+        .arg("-c")
+        .arg(sim.dev_path())
+        .arg("6=1")
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    drop(wr);
+
+    let mut ready = String::new();
+    rd.read_to_string(&mut ready).unwrap();
+    assert!(ready.is_empty());
+    assert!(!child.wait().unwrap().success());
+}
+
+#[test]
+fn ready_fd_invalid() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--ready-fd", "1000", "line=1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "ready fd 1000 is not open\n"
+    );
+}
+
+#[test]
+fn interactive_get_sensed_open_drain() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    sim.pulldown(1).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-i", "--drive", "open-drain", "-c"])
+        .arg(sim.dev_path())
+        .arg("1=1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"get --cached\nget\nget --sensed\nexit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // the requested value is released, so the sensed value follows the pull
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let values: Vec<&str> = stdout
+        .split("gpiocdev-set> ")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    assert_eq!(values, vec!["1=active", "1=inactive", "1=inactive"]);
+}
+
+#[test]
+fn interactive_get_reads_hardware() {
+    use std::io::{Read, Write};
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+    sim.pullup(1).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-i", "--drive", "open-drain", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    stdin.write_all(b"get\n").unwrap();
+    let mut output = String::new();
+    let mut buf = [0; 256];
+    while !output.contains("1=active 2=inactive\n") {
+        let n = stdout.read(&mut buf).unwrap();
+        assert_ne!(n, 0, "output: {}", output);
+        output.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+    }
+
+    // change the level of the released line behind the setter's back
+    sim.pulldown(1).unwrap();
+    stdin.write_all(b"get\nget --cached 1\nexit\n").unwrap();
+    drop(stdin);
+    stdout.read_to_string(&mut output).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let values: Vec<&str> = output
+        .split("gpiocdev-set> ")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    assert_eq!(
+        values,
+        vec!["1=active 2=inactive", "1=inactive 2=inactive", "1=active"]
+    );
+}
+
+#[test]
+fn interactive_get_numeric() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sim = gpiosim::Simpleton::new(4);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-i", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1", "2=0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"get -n\nformat numeric\nget\nformat named\nget 2\nexit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let values: Vec<&str> = stdout
+        .split("gpiocdev-set> ")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    assert_eq!(values, vec!["1=1 2=0", "1=1 2=0", "2=inactive"]);
+}
+
+#[test]
+fn config_file() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    let config = std::env::temp_dir().join(format!("gpiocdev-set-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        format!(
+            "consumer = \"pins\"\n\
+            [lines.led]\n\
+            chip = \"{}\"\n\
+            offset = 1\n\
+            value = \"on\"\n\
+            drive = \"open-source\"\n",
+            sim.dev_path().display()
+        ),
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--config"])
+        .arg(&config)
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    let info = chip.line_info(1).unwrap();
+    assert_eq!(info.consumer.as_str(), "pins");
+    assert_eq!(info.direction, Direction::Output);
+    assert_eq!(info.drive, Some(Drive::OpenSource));
+    assert_eq!(sim.get_level(1).unwrap(), gpiosim::Level::High);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // command line options override the config file
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-C", "override", "--drive", "push-pull", "--config"])
+        .arg(&config)
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    let info = chip.line_info(1).unwrap();
+    assert_eq!(info.consumer.as_str(), "override");
+    assert_eq!(info.drive, Some(Drive::PushPull));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    _ = std::fs::remove_file(&config);
+}
+
+#[test]
+// uAPI v1 requires all lines in a request to share a config.
+#[cfg(feature = "uapi_v2")]
+fn per_line_attributes() {
+    use gpiocdev::line::Bias;
+    use gpiosim::Level::{High, Low};
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--bias", "pull-up", "-c"])
+        .arg(sim.dev_path())
+        .args(["1=1,active-low,pull-down", "2=1"])
+        .spawn()
+        .unwrap();
+
+    // only the tagged line is active-low, and its bias overrides --bias.
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    let info = chip.line_info(1).unwrap();
+    assert!(info.active_low);
+    assert_eq!(info.bias, Some(Bias::PullDown));
+    assert_eq!(sim.get_level(1).unwrap(), Low);
+    let info = chip.line_info(2).unwrap();
+    assert!(!info.active_low);
+    assert_eq!(info.bias, Some(Bias::PullUp));
+    assert_eq!(sim.get_level(2).unwrap(), High);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+fn set_relative_values(abiv: &str) {
+    use gpiosim::Level::{High, Low};
+
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+    sim.pullup(1).unwrap();
+    sim.pullup(2).unwrap();
+    let levels = || {
+        (
+            sim.get_level(1).unwrap(),
+            sim.get_level(2).unwrap(),
+            sim.get_level(3).unwrap(),
+        )
+    };
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--abi-version", abiv, "-c"])
+        .arg(sim.dev_path())
+        .args(["1=as-is", "2=toggle", "3=1"])
+        .spawn()
+        .unwrap();
+
+    // the lines are driven relative to the values read at request time.
+    assert!(wait_for(|| chip.line_info(1).unwrap().used));
+    assert!(wait_for(|| levels() == (High, Low, High)));
+    for offset in 1..4 {
+        assert_eq!(chip.line_info(offset).unwrap().direction, Direction::Output);
+    }
+
+    // and the values are held, not reverted by the pulls.
+    sim.pulldown(1).unwrap();
+    sim.pullup(2).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(levels(), (High, Low, High));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+fn relative_values_v1() {
+    set_relative_values("v1");
+}
+
+#[test]
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+fn relative_values_v2() {
+    set_relative_values("v2");
+}
+
+#[test]
+fn chip_removed() {
+    let sim = gpiosim::Simpleton::new(4);
+    let chip = Chip::from_path(sim.dev_path()).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "-c"])
+        .arg(sim.dev_path())
+        .arg("2=1")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+    drop(chip);
+    // unplug the chip
+    drop(sim);
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("has been removed"), "stderr: {}", stderr);
+}
+
+#[test]
+fn auto_reattach() {
+    let name = format!("set-reattach-{}", std::process::id());
+    let log = std::env::temp_dir().join(format!("{}.log", name));
+    let reattached = || {
+        std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .matches("reattached lines")
+            .count()
+    };
+    let sim = named_sim(&name);
+    let c = &sim.chips()[0];
+    let chip = Chip::from_path(c.dev_path()).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--auto-reattach"])
+        .arg(format!("{}=1", name))
+        .stderr(std::fs::File::create(&log).unwrap())
+        .spawn()
+        .unwrap();
+
+    assert!(wait_for(|| chip.line_info(2).unwrap().used));
+    drop(chip);
+    // unplug the chip, then replug it with the same line names
+    drop(sim);
+    let sim = named_sim(&name);
+    let c = &sim.chips()[0];
+    let chip = Chip::from_path(c.dev_path()).unwrap();
+    assert!(wait_for(|| reattached() == 1));
+    let info = chip.line_info(2).unwrap();
+    assert!(info.used);
+    assert_eq!(info.consumer.as_str(), "gpiocdev-set");
+    assert_eq!(c.get_level(2).unwrap(), gpiosim::Level::High);
+
+    // SIGHUP reattaches the lines
+    assert_eq!(unsafe { libc::kill(child.id() as i32, libc::SIGHUP) }, 0);
+    assert!(wait_for(|| reattached() == 2));
+    assert!(child.try_wait().unwrap().is_none());
+    assert!(chip.line_info(2).unwrap().used);
+    assert_eq!(c.get_level(2).unwrap(), gpiosim::Level::High);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    let stderr = std::fs::read_to_string(&log).unwrap();
+    _ = std::fs::remove_file(&log);
+    assert!(stderr.contains("has been removed"), "stderr: {}", stderr);
+}
+
+#[test]
+fn auto_reattach_chip() {
+    let sim = gpiosim::Simpleton::new(4);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gpiocdev"))
+        .args(["set", "--auto-reattach", "-c"])
+        .arg(sim.dev_path())
+        .arg("2=1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "--chip cannot be used with --auto-reattach, as chip '{}' may be renumbered\n",
+            sim.dev_path().display()
+        )
+    );
+}
@@ -6,8 +6,11 @@
 //! [`gpiocdev::Request`] and therefore for any Linux platform supporting the
 //! GPIO character device.
 //!
-//! The requests contain only a single pin which must be configured as an
+//! The requests typically contain only a single pin which must be configured as an
 //! input or output.
+//! Alternatively, several pins may share a multi-line request, with each pin
+//! referencing the shared request and the offset of its line, such as
+//! via an [`Arc<Request>`](std::sync::Arc).
 //!
 //! Asynchronous wrappers providing [`embedded_hal_async::digital::Wait`]
 //! traits are available for various async reactors.
@@ -81,13 +84,57 @@ pub use r#async::tokio;
 
 /// Core common class for InputPin and OutputPin.
 #[derive(Debug)]
-struct Pin {
-    req: Request,
+struct Pin<R = Request> {
+    req: R,
     offset: Offset,
     config: Config,
 }
 
-impl Pin {
+impl<R: AsRef<Request>> Pin<R> {
+    fn from_request(req: R, offset: Offset, direction: Direction) -> Result<Self, Error> {
+        let line_config = req
+            .as_ref()
+            .line_config(offset)
+            .ok_or(Error::UnrequestedLine(offset))?;
+        if line_config.direction != Some(direction) {
+            return Err(match direction {
+                Direction::Input => Error::RequiresInputMode,
+                Direction::Output => Error::RequiresOutputMode,
+            });
+        }
+        Ok(Pin {
+            req,
+            offset,
+            config: line_config,
+        })
+    }
+
+    // Apply the pin config to the line, leaving any other lines in the request unaltered.
+    fn reconfigure(&self) -> Result<(), Error> {
+        let req = self.req.as_ref();
+        let mut config = req.config();
+        if config.num_lines() > 1 {
+            // The values of other output lines may have been changed since the
+            // request was last configured, so refresh them to avoid reverting them.
+            let mut values = gpiocdev::line::Values::default();
+            req.values(&mut values)?;
+            for offset in config.lines().clone() {
+                if offset == self.offset {
+                    continue;
+                }
+                let is_output = config
+                    .line_config(offset)
+                    .map_or(false, |lc| lc.direction == Some(Direction::Output));
+                if let (true, Some(value)) = (is_output, values.get(offset)) {
+                    config.with_line(offset).with_value(value);
+                }
+            }
+        }
+        config.with_line(self.offset).from_line_config(&self.config);
+        req.reconfigure(&config)?;
+        Ok(())
+    }
+
     #[inline]
     fn is_high(&mut self) -> Result<bool, Error> {
         Ok(self.req.as_ref().value(self.offset)?
@@ -114,8 +161,12 @@ impl From<Pin> for Request {
 /// Do NOT drop the [`InputPin`] until you are completely done with it.
 /// Dropping and re-requesting the line is far more expensive than getting the
 /// value.
+///
+/// The request may be shared with other pins by providing a shared reference to
+/// the request, such as an [`Arc<Request>`](std::sync::Arc), to
+/// [`from_request`](InputPin::from_request).
 #[derive(Debug)]
-pub struct InputPin(Pin);
+pub struct InputPin<R = Request>(Pin<R>);
 
 impl InputPin {
     /// Creates a new input pin for the given `offset` on the given `chip`.
@@ -144,22 +195,6 @@ impl InputPin {
         InputPin::try_from(req)
     }
 
-    /// Set this pin to output mode.
-    pub fn into_output_pin(mut self, state: PinState) -> Result<OutputPin, Error> {
-        let pin = &mut self.0;
-        let req = pin.req.as_ref();
-        let value = state_to_value(state, pin.config.active_low);
-        let mut config = req.config();
-        config.from_line_config(&pin.config).as_output(value);
-        req.reconfigure(&config)?;
-        // don't update the whole config - retain the input specific fields
-        // (edge_detection and debounce) in case the pin is switched to input.
-        pin.config.direction = Some(Direction::Output);
-        pin.config.value = Some(value);
-
-        Ok(OutputPin(self.0))
-    }
-
     /// Create an [`InputPin`] from a [`gpiocdev::FoundLine`].
     ///
     /// # Examples
@@ -210,6 +245,60 @@ impl InputPin {
     }
 }
 
+impl<R: AsRef<Request>> InputPin<R> {
+    /// Create an [`InputPin`] for a line in an existing request.
+    ///
+    /// The line must be an input.
+    ///
+    /// The request may contain other lines, and may be shared with other pins,
+    /// e.g. by passing an [`Arc<Request>`](std::sync::Arc) or `&Request`.
+    /// Each pin should be created for a distinct line.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev_embedded_hal::Error> {
+    /// use embedded_hal::digital::InputPin;
+    /// use std::sync::Arc;
+    ///
+    /// let req = Arc::new(
+    ///     gpiocdev::Request::builder()
+    ///         .on_chip("/dev/gpiochip0")
+    ///         .with_lines(&[3, 4])
+    ///         .as_input()
+    ///         .request()?,
+    /// );
+    /// let mut pin3 = gpiocdev_embedded_hal::InputPin::from_request(req.clone(), 3)?;
+    /// let mut pin4 = gpiocdev_embedded_hal::InputPin::from_request(req, 4)?;
+    /// if pin3.is_high()? && pin4.is_low()? {
+    ///     println!("Inputs differ.");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_request(req: R, offset: Offset) -> Result<Self, Error> {
+        Pin::from_request(req, offset, Direction::Input).map(InputPin)
+    }
+
+    /// Set this pin to output mode.
+    ///
+    /// Other lines in the request are unaltered.
+    pub fn into_output_pin(mut self, state: PinState) -> Result<OutputPin<R>, Error> {
+        let pin = &mut self.0;
+        let value = state_to_value(state, pin.config.active_low);
+        let prev = pin.config.clone();
+        // don't update the whole config - retain the input specific fields
+        // (edge_detection and debounce) in case the pin is switched to input.
+        pin.config.direction = Some(Direction::Output);
+        pin.config.value = Some(value);
+        if let Err(e) = pin.reconfigure() {
+            pin.config = prev;
+            return Err(e);
+        }
+
+        Ok(OutputPin(self.0))
+    }
+}
+
 impl TryFrom<Request> for InputPin {
     type Error = Error;
 
@@ -225,17 +314,7 @@ impl TryFrom<Request> for InputPin {
             return Err(Error::MultipleLinesRequested);
         }
         let offset = offsets[0];
-        // unwrap is safe as line config must exist.
-        let line_config = config.line_config(offset).unwrap().clone();
-        if line_config.direction != Some(Direction::Input) {
-            return Err(Error::RequiresInputMode);
-        }
-
-        Ok(InputPin(Pin {
-            req,
-            offset,
-            config: line_config,
-        }))
+        InputPin::from_request(req, offset)
     }
 }
 
@@ -246,7 +325,7 @@ impl From<InputPin> for Request {
     }
 }
 
-impl embedded_hal::digital::InputPin for InputPin {
+impl<R: AsRef<Request>> embedded_hal::digital::InputPin for InputPin<R> {
     #[inline]
     fn is_high(&mut self) -> Result<bool, Self::Error> {
         self.0.is_high()
@@ -258,7 +337,7 @@ impl embedded_hal::digital::InputPin for InputPin {
     }
 }
 
-impl embedded_hal::digital::ErrorType for InputPin {
+impl<R> embedded_hal::digital::ErrorType for InputPin<R> {
     /// Errors returned by [`InputPin`].
     type Error = Error;
 }
@@ -274,8 +353,12 @@ impl embedded_hal::digital::ErrorType for InputPin {
 /// itself.
 /// Dropping and re-requesting the line is also far more expensive than setting
 /// the value.
+///
+/// The request may be shared with other pins by providing a shared reference to
+/// the request, such as an [`Arc<Request>`](std::sync::Arc), to
+/// [`from_request`](OutputPin::from_request).
 #[derive(Debug)]
-pub struct OutputPin(Pin);
+pub struct OutputPin<R = Request>(Pin<R>);
 
 impl OutputPin {
     /// Creates a new output pin for the given `offset` on the given `chip`.
@@ -303,21 +386,6 @@ impl OutputPin {
         OutputPin::try_from(req)
     }
 
-    /// Set this pin to input mode.
-    pub fn into_input_pin(mut self) -> Result<InputPin, Error> {
-        let pin = &mut self.0;
-        let req = pin.req.as_ref();
-        let mut config = req.config();
-        config.from_line_config(&pin.config).as_input();
-        req.reconfigure(&config)?;
-        // don't update the whole config - retain the output specific fields
-        // (drive) in case the pin is switched back to output.
-        pin.config.direction = Some(Direction::Input);
-        pin.config.value = None;
-
-        Ok(InputPin(self.0))
-    }
-
     /// Create an [`OutputPin`] from a [`gpiocdev::FoundLine`].
     ///
     /// # Examples
@@ -366,6 +434,60 @@ impl OutputPin {
     }
 }
 
+impl<R: AsRef<Request>> OutputPin<R> {
+    /// Create an [`OutputPin`] for a line in an existing request.
+    ///
+    /// The line must be an output.
+    ///
+    /// The request may contain other lines, and may be shared with other pins,
+    /// e.g. by passing an [`Arc<Request>`](std::sync::Arc) or `&Request`.
+    /// Each pin should be created for a distinct line.
+    /// Setting the pin only alters its own line.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev_embedded_hal::Error> {
+    /// use embedded_hal::digital::{OutputPin, StatefulOutputPin};
+    /// use gpiocdev::line::Value;
+    /// use std::sync::Arc;
+    ///
+    /// let req = Arc::new(
+    ///     gpiocdev::Request::builder()
+    ///         .on_chip("/dev/gpiochip0")
+    ///         .with_lines(&[17, 18])
+    ///         .as_output(Value::Inactive)
+    ///         .request()?,
+    /// );
+    /// let mut led0 = gpiocdev_embedded_hal::OutputPin::from_request(req.clone(), 17)?;
+    /// let mut led1 = gpiocdev_embedded_hal::OutputPin::from_request(req, 18)?;
+    /// led0.set_high()?;
+    /// led1.toggle()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_request(req: R, offset: Offset) -> Result<Self, Error> {
+        Pin::from_request(req, offset, Direction::Output).map(OutputPin)
+    }
+
+    /// Set this pin to input mode.
+    ///
+    /// Other lines in the request are unaltered.
+    pub fn into_input_pin(mut self) -> Result<InputPin<R>, Error> {
+        let pin = &mut self.0;
+        let prev = pin.config.clone();
+        // don't update the whole config - retain the output specific fields
+        // (drive) in case the pin is switched back to output.
+        pin.config.direction = Some(Direction::Input);
+        pin.config.value = None;
+        if let Err(e) = pin.reconfigure() {
+            pin.config = prev;
+            return Err(e);
+        }
+
+        Ok(InputPin(self.0))
+    }
+}
+
 impl TryFrom<Request> for OutputPin {
     type Error = Error;
 
@@ -381,17 +503,7 @@ impl TryFrom<Request> for OutputPin {
             return Err(Error::MultipleLinesRequested);
         }
         let offset = offsets[0];
-        // unwrap is safe as line config must exist.
-        let line_config = config.line_config(offset).unwrap().clone();
-        if line_config.direction != Some(Direction::Output) {
-            return Err(Error::RequiresOutputMode);
-        }
-
-        Ok(OutputPin(Pin {
-            req,
-            offset,
-            config: line_config,
-        }))
+        OutputPin::from_request(req, offset)
     }
 }
 
@@ -402,7 +514,7 @@ impl From<OutputPin> for Request {
     }
 }
 
-impl embedded_hal::digital::InputPin for OutputPin {
+impl<R: AsRef<Request>> embedded_hal::digital::InputPin for OutputPin<R> {
     // Supporting InputPin is intentional to allow support for reading the
     // physical line value for output lines where supported by hardware,
     // e.g. to read an open drain line while not actively driving it.
@@ -420,7 +532,7 @@ impl embedded_hal::digital::InputPin for OutputPin {
     }
 }
 
-impl embedded_hal::digital::OutputPin for OutputPin {
+impl<R: AsRef<Request>> embedded_hal::digital::OutputPin for OutputPin<R> {
     #[inline]
     fn set_low(&mut self) -> Result<(), Self::Error> {
         self.set_state(PinState::Low)
@@ -442,7 +554,7 @@ impl embedded_hal::digital::OutputPin for OutputPin {
     }
 }
 
-impl embedded_hal::digital::StatefulOutputPin for OutputPin {
+impl<R: AsRef<Request>> embedded_hal::digital::StatefulOutputPin for OutputPin<R> {
     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
         Ok(self.0.config.value == Some(Value::Active))
     }
@@ -460,7 +572,7 @@ impl embedded_hal::digital::StatefulOutputPin for OutputPin {
     }
 }
 
-impl embedded_hal::digital::ErrorType for OutputPin {
+impl<R> embedded_hal::digital::ErrorType for OutputPin<R> {
     /// Errors returned by [`OutputPin`].
     type Error = Error;
 }
@@ -496,6 +608,10 @@ pub enum Error {
     #[error("Cannot find a line named '{0}'")]
     UnfoundLine(String),
 
+    /// The line is not contained in the request.
+    #[error("Line {0} is not contained in the request")]
+    UnrequestedLine(Offset),
+
     /// An error returned from an underlying gpiocdev call.
     #[error("gpiocdev returned: {0}")]
     Cdev(#[source] gpiocdev::Error),
@@ -153,6 +153,78 @@ mod try_from_request {
     }
 }
 
+mod from_request {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn shared() {
+        let s = Simpleton::new(5);
+        let req = Arc::new(
+            Request::builder()
+                .on_chip(s.dev_path())
+                .with_lines(&[1, 3])
+                .as_input()
+                .request()
+                .unwrap(),
+        );
+        let mut pin1 = InputPin::from_request(req.clone(), 1).unwrap();
+        let mut pin3 = InputPin::from_request(req, 3).unwrap();
+
+        assert!(pin1.is_low().unwrap());
+        assert!(pin3.is_low().unwrap());
+
+        s.pullup(3).unwrap();
+        wait_propagation_delay();
+        assert!(pin1.is_low().unwrap());
+        assert!(pin3.is_high().unwrap());
+    }
+
+    #[test]
+    fn into_output_pin() {
+        use embedded_hal::digital::PinState;
+
+        let s = Simpleton::new(5);
+        let req = Arc::new(
+            Request::builder()
+                .on_chip(s.dev_path())
+                .with_lines(&[1, 3])
+                .as_input()
+                .request()
+                .unwrap(),
+        );
+        let pin1 = InputPin::from_request(req.clone(), 1).unwrap();
+        let _pin3 = InputPin::from_request(req.clone(), 3).unwrap();
+
+        let _pin1 = pin1.into_output_pin(PinState::High).unwrap();
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::High);
+        let config = req.config();
+        assert_eq!(
+            config.line_config(1).unwrap().direction,
+            Some(Direction::Output)
+        );
+        assert_eq!(
+            config.line_config(3).unwrap().direction,
+            Some(Direction::Input)
+        );
+    }
+
+    #[test]
+    fn output_line() {
+        let s = Simpleton::new(5);
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(2)
+            .as_output(gpiocdev::line::Value::Active)
+            .request()
+            .unwrap();
+        assert_eq!(
+            InputPin::from_request(&req, 2).unwrap_err(),
+            gpiocdev_embedded_hal::Error::RequiresInputMode
+        );
+    }
+}
+
 #[test]
 fn into_request() {
     let s = Simpleton::new(5);
@@ -175,12 +247,11 @@ fn from_found_line() {
     let s = Simpleton::new(5);
 
     let offset = 2;
+    let mut info = gpiocdev::line::Info::default();
+    info.offset = offset;
     let fl = gpiocdev::FoundLine {
         chip: s.dev_path().clone(),
-        info: gpiocdev::line::Info {
-            offset,
-            ..Default::default()
-        },
+        info,
     };
     let pin = InputPin::from_found_line(fl).unwrap();
 
@@ -188,6 +188,111 @@ mod try_from_request {
     }
 }
 
+mod from_request {
+    use super::*;
+    use gpiocdev::line::Value;
+    use std::sync::Arc;
+
+    fn shared_request(s: &Simpleton) -> Arc<Request> {
+        Arc::new(
+            Request::builder()
+                .on_chip(s.dev_path())
+                .with_lines(&[1, 2])
+                .as_output(Value::Inactive)
+                .with_line(3)
+                .as_input()
+                .request()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn shared() {
+        use embedded_hal::digital::InputPin as _;
+
+        let s = Simpleton::new(5);
+        let req = shared_request(&s);
+        let mut pin1 = OutputPin::from_request(req.clone(), 1).unwrap();
+        let mut pin2 = OutputPin::from_request(req, 2).unwrap();
+
+        // set
+        pin1.set_high().unwrap();
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::Low);
+
+        // read back
+        assert!(pin1.is_high().unwrap());
+        assert!(pin1.is_set_high().unwrap());
+        assert!(pin2.is_low().unwrap());
+        assert!(pin2.is_set_low().unwrap());
+
+        // toggle
+        pin2.toggle().unwrap();
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+        pin1.toggle().unwrap();
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+    }
+
+    #[test]
+    fn borrowed() {
+        let s = Simpleton::new(5);
+        let req = shared_request(&s);
+        let mut pin = OutputPin::from_request(req.as_ref(), 2).unwrap();
+        pin.set_high().unwrap();
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+    }
+
+    #[test]
+    fn into_input_pin() {
+        let s = Simpleton::new(5);
+        let req = shared_request(&s);
+        let mut pin1 = OutputPin::from_request(req.clone(), 1).unwrap();
+        let pin2 = OutputPin::from_request(req.clone(), 2).unwrap();
+
+        pin1.set_high().unwrap();
+        let _pin2 = pin2.into_input_pin().unwrap();
+
+        // other lines are unaltered
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        let config = req.config();
+        assert_eq!(
+            config.line_config(2).unwrap().direction,
+            Some(Direction::Input)
+        );
+        assert_eq!(
+            config.line_config(1).unwrap().direction,
+            Some(Direction::Output)
+        );
+        assert_eq!(
+            config.line_config(3).unwrap().direction,
+            Some(Direction::Input)
+        );
+    }
+
+    #[test]
+    fn input_line() {
+        let s = Simpleton::new(5);
+        let req = shared_request(&s);
+        assert_eq!(
+            OutputPin::from_request(req, 3).unwrap_err(),
+            gpiocdev_embedded_hal::Error::RequiresOutputMode
+        );
+    }
+
+    #[test]
+    fn unrequested_line() {
+        let s = Simpleton::new(5);
+        let req = shared_request(&s);
+        assert_eq!(
+            OutputPin::from_request(req, 4).unwrap_err(),
+            gpiocdev_embedded_hal::Error::UnrequestedLine(4)
+        );
+    }
+}
+
 #[test]
 fn into_request() {
     let s = Simpleton::new(5);
@@ -210,12 +315,11 @@ fn from_found_line() {
     let s = Simpleton::new(5);
 
     let offset = 2;
+    let mut info = gpiocdev::line::Info::default();
+    info.offset = offset;
     let fl = gpiocdev::FoundLine {
         chip: s.dev_path().clone(),
-        info: gpiocdev::line::Info {
-            offset,
-            ..Default::default()
-        },
+        info,
     };
     let pin = OutputPin::from_found_line(fl, PinState::High).unwrap();
 
@@ -18,6 +18,15 @@ fn v1_benchmarks(c: &mut Criterion) {
     use gpiocdev::AbiVersion::V1;
     c.bench_function("uapi_v1 edge latency", |b| edge_latency(b, V1));
     c.bench_function("uapi_v1 ten edge events", |b| ten_edge_events(b, V1));
+    c.bench_function("uapi_v1 ten edge events looped", |b| {
+        ten_edge_events_looped(b, V1)
+    });
+    c.bench_function("uapi_v1 ten edge events batched", |b| {
+        ten_edge_events_batched(b, V1)
+    });
+    c.bench_function("uapi_v1 ten edge events iterated", |b| {
+        ten_edge_events_iterated(b, V1)
+    });
     c.bench_function("uapi_v1 edge event object", |b| edge_event_object(b, V1));
 }
 #[cfg(not(feature = "uapi_v1"))]
@@ -28,6 +37,15 @@ fn v2_benchmarks(c: &mut Criterion) {
     use gpiocdev::AbiVersion::V2;
     c.bench_function("uapi_v2 edge latency", |b| edge_latency(b, V2));
     c.bench_function("uapi_v2 ten edge events", |b| ten_edge_events(b, V2));
+    c.bench_function("uapi_v2 ten edge events looped", |b| {
+        ten_edge_events_looped(b, V2)
+    });
+    c.bench_function("uapi_v2 ten edge events batched", |b| {
+        ten_edge_events_batched(b, V2)
+    });
+    c.bench_function("uapi_v2 ten edge events iterated", |b| {
+        ten_edge_events_iterated(b, V2)
+    });
     c.bench_function("uapi_v2 edge event object", |b| edge_event_object(b, V2));
 }
 #[cfg(not(feature = "uapi_v2"))]
@@ -97,6 +115,113 @@ fn ten_edge_events(b: &mut Bencher, abiv: AbiVersion) {
     });
 }
 
+// determine time taken to read and return ten events individually.
+// overheads are 10 * toggle time and 1 * latency.
+// compare with ten_edge_events_batched.
+#[allow(unused_variables)]
+fn ten_edge_events_looped(b: &mut Bencher, abiv: AbiVersion) {
+    let s = Simpleton::new(10);
+    let offset = 1;
+
+    let mut builder = Request::builder();
+    builder.on_chip(s.dev_path());
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    builder.using_abi_version(abiv);
+    let req = builder
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let mut pull = Level::High;
+
+    b.iter(|| {
+        for _ in 0..10 {
+            s.set_pull(offset, pull).unwrap();
+            pull = match pull {
+                Level::High => Level::Low,
+                Level::Low => Level::High,
+            };
+        }
+        for _ in 0..10 {
+            let _ = req.read_edge_event().unwrap();
+        }
+    });
+}
+
+// determine time taken to read and return ten events with a single read.
+// overheads are 10 * toggle time and 1 * latency.
+// compare with ten_edge_events_looped.
+#[allow(unused_variables)]
+fn ten_edge_events_batched(b: &mut Bencher, abiv: AbiVersion) {
+    let s = Simpleton::new(10);
+    let offset = 1;
+
+    let mut builder = Request::builder();
+    builder.on_chip(s.dev_path());
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    builder.using_abi_version(abiv);
+    let req = builder
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let mut pull = Level::High;
+    let mut events = Vec::with_capacity(10);
+
+    b.iter(|| {
+        for _ in 0..10 {
+            s.set_pull(offset, pull).unwrap();
+            pull = match pull {
+                Level::High => Level::Low,
+                Level::Low => Level::High,
+            };
+        }
+        events.clear();
+        while events.len() < 10 {
+            let max = 10 - events.len();
+            req.read_edge_events_into(&mut events, max).unwrap();
+        }
+    });
+}
+
+// determine time taken to read and return ten events using the buffered iterator.
+// overheads are 10 * toggle time and 1 * latency.
+// the buffer is allocated once, outside the loop, so the iteration does not allocate.
+// compare with ten_edge_events_batched.
+#[allow(unused_variables)]
+fn ten_edge_events_iterated(b: &mut Bencher, abiv: AbiVersion) {
+    let s = Simpleton::new(10);
+    let offset = 1;
+
+    let mut builder = Request::builder();
+    builder.on_chip(s.dev_path());
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    builder.using_abi_version(abiv);
+    let req = builder
+        .with_line(offset)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .unwrap();
+
+    let mut pull = Level::High;
+    let mut buf = req.new_edge_event_buffer(10);
+
+    b.iter(|| {
+        for _ in 0..10 {
+            s.set_pull(offset, pull).unwrap();
+            pull = match pull {
+                Level::High => Level::Low,
+                Level::Low => Level::High,
+            };
+        }
+        for evt in buf.by_ref().take(10) {
+            let _ = evt.unwrap();
+        }
+    });
+}
+
 // determine the interrupt latency when returning an event
 // overheads are toggle time and edge latency.
 #[allow(unused_variables)]
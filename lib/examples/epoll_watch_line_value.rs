@@ -0,0 +1,56 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Example of watching for edge events on a single line using epoll directly,
+// as would be done when integrating with an external event loop.
+
+use anyhow::Context;
+use gpiocdev::line::EdgeDetection;
+use gpiocdev::Request;
+use std::os::unix::prelude::AsRawFd;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // request the line with edge detection to receive events
+    let req = Request::builder()
+        .on_chip("/dev/gpiochip0")
+        .with_consumer("epoll-watch-line-value")
+        .with_line(23)
+        .with_edge_detection(EdgeDetection::BothEdges)
+        .request()
+        .context("Failed to request line")?;
+
+    // the request fd is readable when an edge event is available
+    let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    if epfd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let mut ev = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: 23,
+    };
+    if unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, req.as_raw_fd(), &mut ev) } < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    loop {
+        let n = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), events.len() as i32, -1) };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        for ev in &events[..n as usize] {
+            // the key identifies the line, which would select the request in
+            // an event loop watching several
+            let line = ev.u64;
+            // drain the events, as the fd remains readable until all are read
+            while req.has_edge_event()? {
+                println!("line {}: {:?}", line, req.read_edge_event()?);
+            }
+        }
+    }
+}
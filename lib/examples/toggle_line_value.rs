@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         println!("{offset}={value}");
         std::thread::sleep(Duration::from_millis(500));
-        value = value.not();
+        value.toggle();
         req.set_lone_value(value)
             .context("Failed to set value")?;
     }
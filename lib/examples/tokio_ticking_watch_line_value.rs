@@ -23,6 +23,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut ticker = tokio::time::interval(std::time::Duration::from_millis(500));
     let now = Instant::now();
+    // tokio::select! expands to items newer than the library MSRV.
+    #[allow(clippy::incompatible_msrv)]
     loop {
         tokio::select! {
             Some(Ok(event)) = events.next() => {
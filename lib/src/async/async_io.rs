@@ -6,10 +6,12 @@ use crate::chip::Chip;
 use crate::line::{EdgeEvent, InfoChangeEvent};
 use crate::request::{EdgeEventBuffer, Request};
 use crate::Result;
-use async_io::Async;
+use async_io::{Async, Timer};
+use futures::future::{self, Either};
 use futures::task::{Context, Poll};
 use futures::{ready, Stream};
 use std::pin::Pin;
+use std::time::Duration;
 
 /// Async wrapper around [`Chip`] for the async-io reactor.
 ///
@@ -79,7 +81,7 @@ impl AsyncChip {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn info_change_events(&self) -> InfoChangeStream {
+    pub fn info_change_events(&self) -> InfoChangeStream<'_> {
         InfoChangeStream { chip: self }
     }
 }
@@ -180,6 +182,52 @@ impl AsyncRequest {
         }
     }
 
+    /// Async form of [`Request::wait_edge_event`].
+    ///
+    /// Returns true if an edge event is available to read, or false if the
+    /// timeout expires first.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// use gpiocdev::Request;
+    /// use gpiocdev::async_io::AsyncRequest;
+    /// use std::time::Duration;
+    ///
+    /// # async fn docfn() -> Result<()> {
+    /// let req = Request::builder()
+    ///    .on_chip("/dev/gpiochip0")
+    ///    .with_line(42)
+    ///    .as_input()
+    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
+    ///    .request()?;
+    /// let areq = AsyncRequest::new(req);
+    /// if areq.wait_edge_event(Duration::from_millis(100)).await? {
+    ///     let evt = areq.read_edge_event().await?;
+    ///     // process event...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_edge_event(&self, timeout: Duration) -> Result<bool> {
+        let ready = self.edge_event_ready();
+        futures::pin_mut!(ready);
+        match future::select(ready, Timer::after(timeout)).await {
+            Either::Left((res, _)) => res.map(|_| true),
+            Either::Right(_) => Ok(false),
+        }
+    }
+
+    // Wait until the request has an edge event available to read.
+    async fn edge_event_ready(&self) -> Result<()> {
+        loop {
+            self.0.readable().await?;
+            if self.0.get_ref().has_edge_event()? {
+                return Ok(());
+            }
+        }
+    }
+
     /// Async form of [`Request::read_edge_events_into_slice`].
     ///
     /// # Example
@@ -238,7 +286,7 @@ impl AsyncRequest {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream {
+    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream<'_> {
         EdgeEventStream {
             req: self,
             events: self.0.get_ref().new_edge_event_buffer(capacity),
@@ -269,7 +317,7 @@ impl AsyncRequest {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn edge_events(&self) -> EdgeEventStream {
+    pub fn edge_events(&self) -> EdgeEventStream<'_> {
         EdgeEventStream {
             req: self,
             events: self.0.get_ref().edge_events(),
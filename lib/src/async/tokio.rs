@@ -9,6 +9,7 @@ use crate::Result;
 use futures::ready;
 use futures::task::{Context, Poll};
 use std::pin::Pin;
+use std::time::Duration;
 use tokio::io::unix::AsyncFd;
 use tokio_stream::Stream;
 
@@ -82,7 +83,7 @@ impl AsyncChip {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn info_change_events(&self) -> InfoChangeStream {
+    pub fn info_change_events(&self) -> InfoChangeStream<'_> {
         InfoChangeStream { chip: self }
     }
 }
@@ -191,6 +192,53 @@ impl AsyncRequest {
         }
     }
 
+    /// Async form of [`Request::wait_edge_event`].
+    ///
+    /// Returns true if an edge event is available to read, or false if the
+    /// timeout expires first.
+    ///
+    /// The timeout uses the tokio timer, so the runtime must have time enabled.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpiocdev::Result;
+    /// use gpiocdev::Request;
+    /// use gpiocdev::tokio::AsyncRequest;
+    /// use std::time::Duration;
+    ///
+    /// # async fn docfn() -> Result<()> {
+    /// let req = Request::builder()
+    ///    .on_chip("/dev/gpiochip0")
+    ///    .with_line(42)
+    ///    .as_input()
+    ///    .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
+    ///    .request()?;
+    /// let areq = AsyncRequest::new(req);
+    /// if areq.wait_edge_event(Duration::from_millis(100)).await? {
+    ///     let evt = areq.read_edge_event().await?;
+    ///     // process event...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_edge_event(&self, timeout: Duration) -> Result<bool> {
+        match tokio::time::timeout(timeout, self.edge_event_ready()).await {
+            Ok(res) => res.map(|_| true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    // Wait until the request has an edge event available to read.
+    async fn edge_event_ready(&self) -> Result<()> {
+        loop {
+            let mut guard = self.0.readable().await?;
+            if self.0.get_ref().has_edge_event()? {
+                return Ok(());
+            }
+            guard.clear_ready();
+        }
+    }
+
     /// Async form of [`Request::read_edge_events_into_slice`].
     ///
     /// # Example
@@ -253,7 +301,7 @@ impl AsyncRequest {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream {
+    pub fn new_edge_event_stream(&self, capacity: usize) -> EdgeEventStream<'_> {
         EdgeEventStream {
             req: self,
             events: self.0.get_ref().new_edge_event_buffer(capacity),
@@ -283,7 +331,7 @@ impl AsyncRequest {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn edge_events(&self) -> EdgeEventStream {
+    pub fn edge_events(&self) -> EdgeEventStream<'_> {
         EdgeEventStream {
             req: self,
             events: self.0.get_ref().edge_events(),
@@ -16,6 +16,7 @@ use gpiocdev_uapi::{v1, v2};
 use serde_derive::{Deserialize, Serialize};
 #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
 use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
 use std::mem;
@@ -26,8 +27,14 @@ use std::os::linux::fs::MetadataExt;
 use std::os::android::fs::MetadataExt;
 use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, OsStrExt};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
 
+#[cfg(feature = "chip_watch")]
+mod watcher;
+#[cfg(feature = "chip_watch")]
+pub use watcher::{ChipEvent, ChipWatcher};
+
 const CHARDEV_MODE: u32 = 0x2000;
 
 /// Check if a path corresponds to a GPIO character device.
@@ -119,12 +126,50 @@ impl Iterator for LineInfoIterator<'_> {
 }
 
 /// A GPIO character device.
+///
+/// # Event Loop Integration
+///
+/// The chip implements [`AsFd`] and [`AsRawFd`], providing the file descriptor
+/// for the chip, so it can be registered with an external event loop such as
+/// mio, calloop, or epoll.
+///
+/// The file descriptor is readable when a line info change event is available for
+/// one of the lines being watched with [`watch_line_info`], and should be registered
+/// for read readiness only.
+/// The events should be read using [`read_line_info_change_event`], not by reading
+/// the file descriptor directly.
+///
+/// The file descriptor is owned by the chip and remains valid, and unchanged,
+/// for the lifetime of the chip.
+///
+/// # Line Info Caching
+///
+/// The line info can be cached, to avoid repeatedly reading info that has not
+/// changed, by calling [`enable_info_cache`].
+///
+/// Each line is watched when its info is first read with the cache enabled,
+/// and the cached info is updated from the info change events before each read.
+/// Only the lines accessed are watched, not all the lines on the chip.
+/// If a line cannot be watched, e.g. as it is already being watched with
+/// [`watch_line_info`], then its info is not cached and is read from the kernel.
+///
+/// As the cache consumes the info change events, [`read_line_info_change_event`]
+/// and [`info_change_events`] should not be used while the cache is enabled.
+///
+/// [`enable_info_cache`]: #method.enable_info_cache
+/// [`info_change_events`]: #method.info_change_events
+/// [`watch_line_info`]: #method.watch_line_info
+/// [`read_line_info_change_event`]: #method.read_line_info_change_event
 #[derive(Debug)]
 pub struct Chip {
     /// The resolved path of the GPIO character device.
     path: PathBuf,
     /// The open GPIO character device file.
     pub(crate) f: fs::File,
+    /// The chip info, which does not change while the chip is open.
+    info: Info,
+    /// The cache of line info, if enabled.
+    info_cache: Mutex<Option<InfoCache>>,
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     abiv: Cell<Option<AbiVersion>>,
 }
@@ -142,14 +187,7 @@ impl Chip {
     /// # }
     ///```
     pub fn from_path<P: AsRef<Path>>(p: P) -> Result<Chip> {
-        let path = is_chip(p.as_ref())?;
-        let f = fs::File::open(&path)?;
-        Ok(Chip {
-            path,
-            f,
-            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
-            abiv: Default::default(),
-        })
+        Self::open(is_chip(p.as_ref())?)
     }
 
     /// Constructs a Chip using the given name.
@@ -164,21 +202,60 @@ impl Chip {
     /// # }
     ///```
     pub fn from_name(n: &str) -> Result<Chip> {
-        let path = is_chip(format!("/dev/{}", n))?;
+        Self::open(is_chip(format!("/dev/{}", n))?)
+    }
+
+    /// Constructs a Chip using the given chip number.
+    ///
+    /// The number is the N in the gpiochipN name of the chip, and must
+    /// resolve to a valid GPIO character device.
+    ///
+    /// # Examples
+    ///```no_run
+    /// # fn example() -> gpiocdev::Result<gpiocdev::Chip>{
+    /// let chip = gpiocdev::Chip::from_number(0)?;
+    /// # Ok(chip)
+    /// # }
+    ///```
+    pub fn from_number(n: u32) -> Result<Chip> {
+        Self::from_name(&format!("gpiochip{}", n))
+    }
+
+    // open the chip at the resolved path and read the chip info.
+    fn open(path: PathBuf) -> Result<Chip> {
         let f = fs::File::open(&path)?;
+        let info =
+            Info::from(uapi::get_chip_info(&f).map_err(|e| Error::Uapi(UapiCall::GetChipInfo, e))?);
         Ok(Chip {
             path,
             f,
+            info,
+            info_cache: Default::default(),
             #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
             abiv: Default::default(),
         })
     }
 
     /// Get the information for the chip.
+    ///
+    /// The info is read when the chip is opened, as it does not change while
+    /// the chip is open, so this does not involve any system calls.
     pub fn info(&self) -> Result<Info> {
-        Ok(Info::from(
-            uapi::get_chip_info(&self.f).map_err(|e| Error::Uapi(UapiCall::GetChipInfo, e))?,
-        ))
+        Ok(self.info.clone())
+    }
+
+    /// Return the number of lines provided by the chip.
+    ///
+    /// This is taken from the [`Info`] read when the chip was opened.
+    pub fn num_lines(&self) -> usize {
+        self.info.num_lines as usize
+    }
+
+    /// Return the label of the chip.
+    ///
+    /// This is taken from the [`Info`] read when the chip was opened.
+    pub fn label(&self) -> &str {
+        &self.info.label
     }
 
     /// Return the name of the chip.
@@ -220,8 +297,40 @@ impl Chip {
     }
 
     /// Get the information for a line on the chip.
+    ///
+    /// If the info cache is enabled then the info is returned from the cache, if possible.
     pub fn line_info(&self, offset: Offset) -> Result<line::Info> {
-        self.do_line_info(offset)
+        match self.lock_info_cache().as_mut() {
+            Some(cache) => self.cached_line_info(cache, offset),
+            None => self.do_line_info(offset),
+        }
+    }
+    fn cached_line_info(&self, cache: &mut InfoCache, offset: Offset) -> Result<line::Info> {
+        // apply any changes to the cached lines
+        while self.has_line_info_change_event()? {
+            let evt = self.read_line_info_change_event()?;
+            if cache.watched.contains(&evt.info.offset) {
+                cache.info.insert(evt.info.offset, evt.info);
+            }
+        }
+        if let Some(info) = cache.info.get(&offset) {
+            return Ok(info.clone());
+        }
+        let info = if cache.watched.contains(&offset) {
+            // previously watched, but since invalidated
+            self.do_line_info(offset)?
+        } else {
+            match self.do_watch_line_info(offset) {
+                Ok(info) => {
+                    cache.watched.insert(offset);
+                    info
+                }
+                // changes to the line cannot be tracked, so it cannot be cached
+                Err(_) => return self.do_line_info(offset),
+            }
+        };
+        cache.info.insert(offset, info.clone());
+        Ok(info)
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_line_info(&self, offset: Offset) -> Result<line::Info> {
@@ -238,14 +347,89 @@ impl Chip {
             .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
     }
 
+    /// Get the value of an output line from its line info, without requesting the line.
+    ///
+    /// This provides a passive view of lines held by other processes, but is only
+    /// possible with uAPI v2, and only if the kernel reports the output values in
+    /// the line info, which current mainline kernels do not.
+    ///
+    /// Returns `None` if the line is not an output, or its value is not reported.
+    pub fn line_output_value(&self, offset: Offset) -> Result<Option<line::Value>> {
+        self.do_line_output_value(offset)
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_line_output_value(&self, offset: Offset) -> Result<Option<line::Value>> {
+        match self.actual_abi_version()? {
+            // v1 line info has no attributes, but the offset is still checked
+            V1 => v1::get_line_info(&self.f, offset).map(|_| None),
+            V2 => v2::get_line_info(&self.f, offset).map(|li| output_value(&li)),
+        }
+        .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn do_line_output_value(&self, offset: Offset) -> Result<Option<line::Value>> {
+        uapi::get_line_info(&self.f, offset)
+            .map(|li| output_value(&li))
+            .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn do_line_output_value(&self, offset: Offset) -> Result<Option<line::Value>> {
+        // v1 line info has no attributes, but the offset is still checked
+        uapi::get_line_info(&self.f, offset)
+            .map(|_| None)
+            .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+
+    /// Check if a line is in use, without decoding the full line info.
+    ///
+    /// The line is in use if it is requested by a consumer, either in userspace
+    /// or the kernel.
+    ///
+    /// The info is always read from the kernel, bypassing any info cache.
+    pub fn is_line_used(&self, offset: Offset) -> Result<bool> {
+        self.do_is_line_used(offset)
+    }
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn do_is_line_used(&self, offset: Offset) -> Result<bool> {
+        match self.actual_abi_version()? {
+            V1 => v1::get_line_info(&self.f, offset)
+                .map(|li| li.flags.contains(v1::LineInfoFlags::USED)),
+            V2 => {
+                v2::get_line_info(&self.f, offset).map(|li| li.flags.contains(v2::LineFlags::USED))
+            }
+        }
+        .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn do_is_line_used(&self, offset: Offset) -> Result<bool> {
+        uapi::get_line_info(&self.f, offset)
+            .map(|li| li.flags.contains(uapi::LineInfoFlags::USED))
+            .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn do_is_line_used(&self, offset: Offset) -> Result<bool> {
+        uapi::get_line_info(&self.f, offset)
+            .map(|li| li.flags.contains(uapi::LineFlags::USED))
+            .map_err(|e| Error::Uapi(UapiCall::GetLineInfo, e))
+    }
+
+    /// Get the information for all the lines on the chip.
+    ///
+    /// The returned vector is indexed by line offset.
+    ///
+    /// A failure to read the info for a particular line is returned in place
+    /// of the info for that line, rather than aborting the whole read.
+    pub fn line_info_all(&self) -> Result<Vec<Result<line::Info>>> {
+        Ok(self.line_info_iter()?.collect())
+    }
+
     /// An iterator that returns the info for each line on the chip.
-    pub fn line_info_iter(&self) -> Result<LineInfoIterator> {
-        let cinfo = self.info()?;
+    pub fn line_info_iter(&self) -> Result<LineInfoIterator<'_>> {
         Ok(LineInfoIterator {
             chip: self,
             offsets: Range {
                 start: 0,
-                end: cinfo.num_lines,
+                end: self.info.num_lines,
             },
         })
     }
@@ -274,11 +458,57 @@ impl Chip {
     /// Remove a watch for changes to the publicly available information on a line.
     ///
     /// This is a null operation if there is no existing watch on the line.
+    ///
+    /// If the info for the line is cached then it is removed from the cache.
     pub fn unwatch_line_info(&self, offset: Offset) -> Result<()> {
+        if let Some(cache) = self.lock_info_cache().as_mut() {
+            cache.watched.remove(&offset);
+            cache.info.remove(&offset);
+        }
         uapi::unwatch_line_info(&self.f, offset)
             .map_err(|e| Error::Uapi(UapiCall::UnwatchLineInfo, e))
     }
 
+    /// Enable caching of the line info returned by [`line_info`].
+    ///
+    /// This is a null operation if the cache is already enabled.
+    ///
+    /// Refer to [Line Info Caching](#line-info-caching) for details.
+    ///
+    /// [`line_info`]: #method.line_info
+    pub fn enable_info_cache(&mut self) -> &mut Self {
+        self.info_cache
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert_with(Default::default);
+        self
+    }
+
+    // Lock the info cache.
+    //
+    // If a panic poisoned the lock then the cached info may be inconsistent,
+    // so it is discarded and read from the kernel instead.
+    fn lock_info_cache(&self) -> MutexGuard<'_, Option<InfoCache>> {
+        self.info_cache.lock().unwrap_or_else(|e| {
+            let mut guard = e.into_inner();
+            if let Some(cache) = guard.as_mut() {
+                cache.info.clear();
+            }
+            guard
+        })
+    }
+
+    /// Discard the cached line info, so it is read from the kernel on the next access.
+    ///
+    /// The lines remain watched, and subsequent reads are cached again.
+    ///
+    /// This is a null operation if the cache is not enabled.
+    pub fn invalidate_info_cache(&self) {
+        if let Some(cache) = self.lock_info_cache().as_mut() {
+            cache.info.clear();
+        }
+    }
+
     /// Check if the request has at least one info change event available to read.
     pub fn has_line_info_change_event(&self) -> Result<bool> {
         gpiocdev_uapi::has_event(&self.f).map_err(|e| Error::Uapi(UapiCall::HasEvent, e))
@@ -297,29 +527,28 @@ impl Chip {
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
-        self.actual_abi_version()?;
-        // bbuf is statically sized to the greater of the v1/v2 size so it can be placed on the stack.
-        debug_assert!(
-            mem::size_of::<v2::LineInfoChangeEvent>() >= mem::size_of::<v1::LineInfoChangeEvent>()
-        );
-        let mut bbuf = [0_u64; mem::size_of::<v2::LineInfoChangeEvent>() / 8];
-        let evt_u64_size = self.line_info_change_event_u64_size();
-        // and dynamically sliced down to the required size, if necessary
-        let buf = &mut bbuf[0..evt_u64_size];
-        let n = gpiocdev_uapi::read_event(&self.f, buf)
-            .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?;
-        self.line_info_change_event_from_slice(&buf[0..n])
+        // the event is read by value, as it is converted immediately
+        Ok(match self.actual_abi_version()? {
+            V1 => InfoChangeEvent::from(
+                &v1::LineInfoChangeEvent::read_one(&self.f)
+                    .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?,
+            ),
+            V2 => InfoChangeEvent::from(
+                &v2::LineInfoChangeEvent::read_one(&self.f)
+                    .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?,
+            ),
+        })
     }
     #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
     fn do_read_line_info_change_event(&self) -> Result<InfoChangeEvent> {
-        let mut buf = [0_u64; mem::size_of::<uapi::LineInfoChangeEvent>() / 8];
-        let n = gpiocdev_uapi::read_event(&self.f, &mut buf)
-            .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?;
-        self.line_info_change_event_from_slice(&buf[0..n])
+        Ok(InfoChangeEvent::from(
+            &uapi::LineInfoChangeEvent::read_one(&self.f)
+                .map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))?,
+        ))
     }
 
     /// An iterator for info change events from the chip.
-    pub fn info_change_events(&self) -> InfoChangeIterator {
+    pub fn info_change_events(&self) -> InfoChangeIterator<'_> {
         InfoChangeIterator {
             chip: self,
             buf: vec![0_u64; self.line_info_change_event_u64_size()],
@@ -415,6 +644,16 @@ impl Chip {
     }
 }
 
+// The info for the lines accessed while the cache is enabled.
+//
+// The lines are watched so the cached info can be updated from the info change events.
+#[derive(Debug, Default)]
+struct InfoCache {
+    info: HashMap<Offset, line::Info>,
+    // the lines watched by the cache.
+    watched: HashSet<Offset>,
+}
+
 impl AsFd for Chip {
     #[inline]
     fn as_fd(&self) -> BorrowedFd<'_> {
@@ -436,6 +675,21 @@ impl AsRef<Chip> for Chip {
     }
 }
 
+// The output value reported in the attributes of the v2 line info, if any.
+#[cfg(feature = "uapi_v2")]
+fn output_value(li: &gpiocdev_uapi::v2::LineInfo) -> Option<line::Value> {
+    use gpiocdev_uapi::v2::{LineAttributeValue, LineFlags};
+
+    if !li.flags.contains(LineFlags::OUTPUT) {
+        return None;
+    }
+    (0..li.num_attrs as usize).find_map(|idx| match li.attr(idx).to_value() {
+        // the info describes a single line, so its value is bit 0.
+        Some(LineAttributeValue::Values(values)) => Some(line::Value::from(values & 1 != 0)),
+        _ => None,
+    })
+}
+
 /// The publicly available information for a GPIO chip.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
@@ -492,7 +746,7 @@ impl Iterator for InfoChangeIterator<'_> {
     }
 }
 
-/// Reasons a file cannot be opened as a GPIO character device.
+/// Reasons a file cannot be used as a GPIO character device.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ErrorKind {
     /// File is not a character device.
@@ -500,6 +754,9 @@ pub enum ErrorKind {
 
     /// File is not a GPIO character device.
     NotGpioDevice,
+
+    /// The chip has been removed since the file was opened.
+    Removed,
 }
 
 impl fmt::Display for ErrorKind {
@@ -507,6 +764,7 @@ impl fmt::Display for ErrorKind {
         let msg = match self {
             ErrorKind::NotCharacterDevice => "is not a character device",
             ErrorKind::NotGpioDevice => "is not a GPIO character device",
+            ErrorKind::Removed => "has been removed",
         };
         write!(f, "{}", msg)
     }
@@ -523,6 +781,20 @@ mod tests {
     // Chip, ChipIterator and InfoChangeIterator tests are all integration
     // tests as Chip construction requires GPIO chips.
 
+    #[test]
+    fn chip_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Chip>();
+    }
+
+    // The detected ABI version is held in a Cell if both ABIs are supported.
+    #[test]
+    #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
+    fn chip_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Chip>();
+    }
+
     mod info {
         use super::{uapi, Info};
 
@@ -540,6 +812,34 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "uapi_v2")]
+    fn output_value() {
+        use super::output_value;
+        use gpiocdev_uapi::v2::{LineFlags, LineInfo};
+
+        let mut li = LineInfo {
+            flags: LineFlags::OUTPUT,
+            num_attrs: 2,
+            ..Default::default()
+        };
+        li.attr_mut(0).set_debounce_period_us(10);
+        li.attr_mut(1).set_values(1);
+        assert_eq!(output_value(&li), Some(line::Value::Active));
+
+        li.attr_mut(1).set_values(0);
+        assert_eq!(output_value(&li), Some(line::Value::Inactive));
+
+        // only the attributes in use are considered
+        li.num_attrs = 1;
+        assert_eq!(output_value(&li), None);
+
+        // inputs never report a value
+        li.num_attrs = 2;
+        li.flags = LineFlags::INPUT;
+        assert_eq!(output_value(&li), None);
+    }
+
     #[test]
     fn path_compare() {
         use super::path_compare;
@@ -0,0 +1,550 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::Chip;
+use crate::{Error, Result, UapiCall};
+use std::collections::{HashSet, VecDeque};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OsStrExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A change to the set of GPIO chips available on the system.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChipEvent {
+    /// A chip has been added to the system, and may be opened.
+    Added(PathBuf),
+
+    /// A chip has been removed from the system.
+    Removed(PathBuf),
+}
+
+/// A watcher for GPIO chips being added to or removed from the system.
+///
+/// Chips present when the watcher is created are not reported.
+///
+/// Changes are deduplicated, so a chip is only reported as added if it was not
+/// already known, and only reported as removed if it was previously known.
+///
+/// A chip is only reported as added once its device node can be opened, which
+/// may be some time after the node is created, e.g. after udev has applied
+/// the node permissions.
+///
+/// Created by [`chips_watch`].
+///
+/// [`chips_watch`]: crate::chips_watch
+pub struct ChipWatcher {
+    // The source of changes to the device nodes.
+    source: Box<dyn NodeSource>,
+
+    // Check if a device node is a usable chip.
+    usable: fn(&Path) -> bool,
+
+    // List the chips present on the system.
+    scan: fn() -> Result<Vec<PathBuf>>,
+
+    // The initial delay between attempts to open a created chip.
+    retry_delay: Duration,
+
+    // The chips that have been reported as added, or were present at startup.
+    known: HashSet<PathBuf>,
+
+    // The chips that have been created, but were not usable.
+    pending: HashSet<PathBuf>,
+
+    // The events ready to be returned.
+    events: VecDeque<ChipEvent>,
+
+    // Changes have been lost, and the known chips are yet to be resynced.
+    resync: bool,
+}
+
+impl ChipWatcher {
+    /// Create a watcher for the chips in /dev.
+    pub(crate) fn new() -> Result<ChipWatcher> {
+        let dir = Path::new("/dev");
+        let source = Inotify::new(dir)?;
+        // the watch is added before the scan so no chips can be missed.
+        let known = super::chips()?.into_iter().collect();
+        Ok(ChipWatcher {
+            source: Box::new(source),
+            usable: |p| Chip::from_path(p).is_ok(),
+            scan: super::chips,
+            retry_delay: OPEN_RETRY_DELAY,
+            known,
+            pending: HashSet::new(),
+            events: VecDeque::new(),
+            resync: false,
+        })
+    }
+
+    /// Returns true if an event is available to read.
+    pub fn has_event(&mut self) -> Result<bool> {
+        self.wait_event(Duration::ZERO)
+    }
+
+    /// Wait for an event to be available.
+    ///
+    /// Returns true if an event is available to read, or false if the timeout
+    /// expired without an event becoming available.
+    pub fn wait_event(&mut self, timeout: Duration) -> Result<bool> {
+        // a deadline beyond the range of Instant is treated as no deadline
+        let deadline = Instant::now().checked_add(timeout);
+        while self.events.is_empty() {
+            let remaining = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => timeout,
+            };
+            // a pending resync does not wait for further changes
+            if !self.resync
+                && !gpiocdev_uapi::wait_event(self.source.file(), remaining)
+                    .map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))?
+            {
+                return Ok(false);
+            }
+            self.update()?;
+        }
+        Ok(true)
+    }
+
+    /// Read a single event.
+    ///
+    /// Will block until an event is available.
+    pub fn read_event(&mut self) -> Result<ChipEvent> {
+        loop {
+            if let Some(evt) = self.events.pop_front() {
+                return Ok(evt);
+            }
+            self.update()?;
+        }
+    }
+
+    // Read changes from the source and convert them to events.
+    //
+    // If a resync is pending then it is retried without reading further changes.
+    fn update(&mut self) -> Result<()> {
+        if !self.resync {
+            for change in self.source.read_changes()? {
+                self.apply(change);
+            }
+        }
+        if self.resync {
+            self.rescan()?;
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, change: NodeChange) {
+        match change {
+            NodeChange::Created(path) => {
+                if !self.known.contains(&path) {
+                    self.added(path);
+                }
+            }
+            NodeChange::Changed(path) => {
+                if self.pending.contains(&path) {
+                    self.added(path);
+                }
+            }
+            NodeChange::Removed(path) => {
+                self.pending.remove(&path);
+                if self.known.remove(&path) {
+                    self.events.push_back(ChipEvent::Removed(path));
+                }
+            }
+            // deferred until the remaining changes have been applied
+            NodeChange::Rescan => self.resync = true,
+        }
+    }
+
+    // Report a created chip once it is usable, else hold it pending a change.
+    fn added(&mut self, path: PathBuf) {
+        if self.wait_usable(&path) {
+            self.pending.remove(&path);
+            self.known.insert(path.clone());
+            self.events.push_back(ChipEvent::Added(path));
+        } else {
+            self.pending.insert(path);
+        }
+    }
+
+    // Retry opening the chip, with backoff, until it succeeds or the retries
+    // are exhausted.
+    fn wait_usable(&self, path: &Path) -> bool {
+        let mut delay = self.retry_delay;
+        for _ in 0..OPEN_RETRIES {
+            if (self.usable)(path) {
+                return true;
+            }
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+        (self.usable)(path)
+    }
+
+    // Resync with the chips present, after the source has lost changes.
+    //
+    // If the chips cannot be listed then the known chips are left unchanged,
+    // and the resync remains pending.
+    fn rescan(&mut self) -> Result<()> {
+        let present: HashSet<PathBuf> = (self.scan)()?.into_iter().collect();
+        self.resync = false;
+        let mut removed: Vec<PathBuf> = self.known.difference(&present).cloned().collect();
+        removed.sort_unstable_by(|a, b| super::path_compare(a, b));
+        for path in removed {
+            self.apply(NodeChange::Removed(path));
+        }
+        let mut added: Vec<PathBuf> = present.difference(&self.known).cloned().collect();
+        added.sort_unstable_by(|a, b| super::path_compare(a, b));
+        for path in added {
+            self.apply(NodeChange::Created(path));
+        }
+        Ok(())
+    }
+}
+
+impl AsFd for ChipWatcher {
+    /// The file descriptor of the underlying source of changes.
+    ///
+    /// A readable descriptor indicates changes are pending, but not all changes
+    /// result in events, so [`has_event`] should be used to confirm an event
+    /// is available before calling [`read_event`].
+    ///
+    /// [`has_event`]: #method.has_event
+    /// [`read_event`]: #method.read_event
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.source.file().as_fd()
+    }
+}
+
+impl AsRawFd for ChipWatcher {
+    #[inline]
+    fn as_raw_fd(&self) -> i32 {
+        self.source.file().as_raw_fd()
+    }
+}
+
+impl Iterator for ChipWatcher {
+    type Item = Result<ChipEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.read_event())
+    }
+}
+
+// The number of retries to open a created chip.
+const OPEN_RETRIES: usize = 5;
+
+// The initial delay between retries to open a created chip, doubling for each retry.
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+// A change to a chip device node, as reported by a NodeSource.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum NodeChange {
+    // The node has been created.
+    Created(PathBuf),
+
+    // The node attributes, such as permissions, have changed.
+    Changed(PathBuf),
+
+    // The node has been removed.
+    Removed(PathBuf),
+
+    // Changes have been lost, so the known chips should be resynced.
+    Rescan,
+}
+
+// A source of changes to chip device nodes.
+//
+// Abstracted so sources other than inotify, such as udev, may be added.
+trait NodeSource: Send {
+    // The file that becomes readable when changes are available.
+    fn file(&self) -> &File;
+
+    // Read the available changes, blocking until some are available.
+    fn read_changes(&mut self) -> Result<Vec<NodeChange>>;
+}
+
+// A NodeSource that watches a directory using inotify.
+struct Inotify {
+    f: File,
+    dir: PathBuf,
+}
+
+impl Inotify {
+    fn new(dir: &Path) -> Result<Inotify> {
+        let cdir = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|_| Error::InvalidArgument("path contains a nul byte.".into()))?;
+        // SAFETY: the fd returned by inotify_init1 is owned by the File.
+        let f = unsafe {
+            let fd = libc::inotify_init1(libc::IN_CLOEXEC);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            File::from_raw_fd(fd)
+        };
+        let mask = libc::IN_CREATE
+            | libc::IN_DELETE
+            | libc::IN_ATTRIB
+            | libc::IN_MOVED_TO
+            | libc::IN_MOVED_FROM;
+        // SAFETY: cdir is a valid nul terminated string.
+        if unsafe { libc::inotify_add_watch(f.as_raw_fd(), cdir.as_ptr(), mask) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Inotify {
+            f,
+            dir: dir.to_owned(),
+        })
+    }
+}
+
+impl NodeSource for Inotify {
+    fn file(&self) -> &File {
+        &self.f
+    }
+
+    fn read_changes(&mut self) -> Result<Vec<NodeChange>> {
+        // sized to hold at least one event with a maximal name.
+        let mut buf = [0; 4096];
+        loop {
+            match self.f.read(&mut buf) {
+                Ok(n) => return Ok(parse_inotify_events(&self.dir, &buf[..n])),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+// The size of the fixed portion of an inotify event - wd, mask, cookie and len.
+const INOTIFY_EVENT_HEADER_SIZE: usize = 16;
+
+// Convert a buffer of inotify events into changes to chip nodes.
+//
+// Events for nodes other than chips are dropped.
+fn parse_inotify_events(dir: &Path, mut buf: &[u8]) -> Vec<NodeChange> {
+    let field = |buf: &[u8], idx: usize| {
+        let mut word = [0; 4];
+        word.copy_from_slice(&buf[idx * 4..idx * 4 + 4]);
+        u32::from_ne_bytes(word)
+    };
+    let mut changes = Vec::new();
+    while buf.len() >= INOTIFY_EVENT_HEADER_SIZE {
+        let mask = field(buf, 1);
+        let len = field(buf, 3) as usize;
+        let end = INOTIFY_EVENT_HEADER_SIZE + len;
+        if buf.len() < end {
+            break;
+        }
+        let name = &buf[INOTIFY_EVENT_HEADER_SIZE..end];
+        // the name is nul padded
+        let name = &name[..name.iter().position(|&c| c == 0).unwrap_or(name.len())];
+        buf = &buf[end..];
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            changes.push(NodeChange::Rescan);
+            continue;
+        }
+        if !is_chip_name(name) {
+            continue;
+        }
+        let path = dir.join(std::ffi::OsStr::from_bytes(name));
+        if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+            changes.push(NodeChange::Created(path));
+        } else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+            changes.push(NodeChange::Removed(path));
+        } else if mask & libc::IN_ATTRIB != 0 {
+            changes.push(NodeChange::Changed(path));
+        }
+    }
+    changes
+}
+
+// Check if a device node name is of the form gpiochipN.
+fn is_chip_name(name: &[u8]) -> bool {
+    match name.strip_prefix(b"gpiochip") {
+        Some(num) => !num.is_empty() && num.iter().all(u8::is_ascii_digit),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(mask: u32, name: &str) -> Vec<u8> {
+        let mut name = name.as_bytes().to_vec();
+        // nul terminate and pad to a multiple of 4, as per the kernel
+        name.resize((name.len() / 4 + 1) * 4, 0);
+        let mut buf = Vec::new();
+        for field in [1, mask, 0, name.len() as u32] {
+            buf.extend_from_slice(&field.to_ne_bytes());
+        }
+        buf.extend_from_slice(&name);
+        buf
+    }
+
+    #[test]
+    fn is_chip_name() {
+        use super::is_chip_name;
+        assert!(is_chip_name(b"gpiochip0"));
+        assert!(is_chip_name(b"gpiochip42"));
+        assert!(!is_chip_name(b"gpiochip"));
+        assert!(!is_chip_name(b"gpiochip1a"));
+        assert!(!is_chip_name(b"gpiochip0.tmp"));
+        assert!(!is_chip_name(b"ttyS0"));
+    }
+
+    #[test]
+    fn parse_inotify_events() {
+        use super::parse_inotify_events;
+        let dir = Path::new("/dev");
+        let mut buf = event(libc::IN_CREATE, "gpiochip3");
+        buf.extend(event(libc::IN_CREATE, "ttyUSB0"));
+        buf.extend(event(libc::IN_ATTRIB, "gpiochip3"));
+        buf.extend(event(libc::IN_DELETE, "gpiochip12"));
+        buf.extend(event(libc::IN_MOVED_TO, "gpiochip4"));
+        buf.extend(event(libc::IN_MOVED_FROM, "gpiochip5"));
+        buf.extend(event(libc::IN_Q_OVERFLOW, ""));
+        assert_eq!(
+            parse_inotify_events(dir, &buf),
+            vec![
+                NodeChange::Created(PathBuf::from("/dev/gpiochip3")),
+                NodeChange::Changed(PathBuf::from("/dev/gpiochip3")),
+                NodeChange::Removed(PathBuf::from("/dev/gpiochip12")),
+                NodeChange::Created(PathBuf::from("/dev/gpiochip4")),
+                NodeChange::Removed(PathBuf::from("/dev/gpiochip5")),
+                NodeChange::Rescan,
+            ]
+        );
+
+        // truncated event
+        let buf = event(libc::IN_CREATE, "gpiochip3");
+        assert_eq!(parse_inotify_events(dir, &buf[..buf.len() - 1]), vec![]);
+    }
+
+    #[test]
+    fn inotify() {
+        let dir = std::env::temp_dir().join(format!("gpiocdev-inotify-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        let mut src = Inotify::new(&dir).unwrap();
+        let chip = dir.join("gpiochip7");
+        std::fs::write(dir.join("other"), "").unwrap();
+        std::fs::write(&chip, "").unwrap();
+        std::fs::remove_file(&chip).unwrap();
+        let mut changes = Vec::new();
+        while gpiocdev_uapi::has_event(src.file()).unwrap() {
+            changes.extend(src.read_changes().unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            changes,
+            vec![NodeChange::Created(chip.clone()), NodeChange::Removed(chip)]
+        );
+    }
+
+    // A NodeSource providing a canned sequence of changes.
+    struct Canned {
+        f: File,
+        changes: Vec<Vec<NodeChange>>,
+    }
+
+    impl NodeSource for Canned {
+        fn file(&self) -> &File {
+            &self.f
+        }
+
+        fn read_changes(&mut self) -> Result<Vec<NodeChange>> {
+            Ok(self.changes.remove(0))
+        }
+    }
+
+    fn watcher(changes: Vec<Vec<NodeChange>>, usable: fn(&Path) -> bool) -> ChipWatcher {
+        ChipWatcher {
+            source: Box::new(Canned {
+                f: File::open("/dev/null").unwrap(),
+                changes,
+            }),
+            usable,
+            scan: || Ok(vec![PathBuf::from("/dev/gpiochip0")]),
+            retry_delay: Duration::ZERO,
+            known: [PathBuf::from("/dev/gpiochip0")].into_iter().collect(),
+            pending: HashSet::new(),
+            events: VecDeque::new(),
+            resync: false,
+        }
+    }
+
+    #[test]
+    fn read_event() {
+        use NodeChange::*;
+        let c0 = PathBuf::from("/dev/gpiochip0");
+        let c1 = PathBuf::from("/dev/gpiochip1");
+        let mut w = watcher(
+            vec![
+                // known
+                vec![Created(c0.clone()), Changed(c0.clone())],
+                vec![Created(c1.clone()), Created(c1.clone())],
+                vec![Removed(c0.clone())],
+                vec![Removed(c0.clone()), Removed(c1.clone())],
+            ],
+            |_| true,
+        );
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Added(c1.clone()));
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Removed(c0));
+        assert_eq!(w.next().unwrap().unwrap(), ChipEvent::Removed(c1));
+    }
+
+    #[test]
+    fn read_event_pending() {
+        use NodeChange::*;
+        let c1 = PathBuf::from("/dev/gpiochip1");
+        let c2 = PathBuf::from("/dev/gpiochip2");
+        let mut w = watcher(
+            vec![
+                // gpiochip1 never usable and removed before becoming so
+                vec![
+                    Created(c1.clone()),
+                    Changed(c1.clone()),
+                    Removed(c1.clone()),
+                ],
+                vec![Changed(c1), Created(c2.clone())],
+            ],
+            |p| p == Path::new("/dev/gpiochip2"),
+        );
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Added(c2));
+        assert!(w.pending.is_empty());
+    }
+
+    #[test]
+    fn read_event_rescan() {
+        use NodeChange::*;
+        let c0 = PathBuf::from("/dev/gpiochip0");
+        let c1 = PathBuf::from("/dev/gpiochip1");
+        let c2 = PathBuf::from("/dev/gpiochip2");
+        let mut w = watcher(
+            vec![vec![Rescan, Created(c2.clone())], vec![Rescan]],
+            |_| true,
+        );
+        w.scan = || Ok(vec![PathBuf::from("/dev/gpiochip1")]);
+        // the rescan follows the other changes
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Added(c2.clone()));
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Removed(c0));
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Removed(c2));
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Added(c1.clone()));
+
+        // a failed scan leaves the known chips unchanged, and is retried
+        w.scan = || Err(Error::InvalidArgument("scan failed".into()));
+        assert!(w.read_event().is_err());
+        assert!(w.events.is_empty());
+        assert_eq!(w.known, [c1.clone()].into_iter().collect());
+        assert!(w.has_event().is_err());
+        w.scan = || Ok(Vec::new());
+        assert_eq!(w.read_event().unwrap(), ChipEvent::Removed(c1));
+        assert!(!w.resync);
+    }
+}
@@ -48,6 +48,37 @@
 //! # }
 //! ```
 //!
+//! # Async
+//!
+//! Edge events and info change events may also be awaited using the wrappers for
+//! the [tokio](https://tokio.rs) reactor, enabled by the `async_tokio` feature,
+//! or the [async-io](https://crates.io/crates/async-io) reactor used by smol and
+//! async-std, enabled by the `async_io` feature.
+//! The features are independent and may both be enabled.
+//!
+//! The wrappers provide the same API, so switching reactor only requires
+//! changing the feature and the import:
+//! ```no_run
+//! # #[cfg(any(feature = "async_tokio", feature = "async_io"))]
+//! # async fn example() -> Result<(), gpiocdev::Error> {
+//! #[cfg(feature = "async_tokio")]
+//! use gpiocdev::tokio::AsyncRequest;
+//! #[cfg(all(feature = "async_io", not(feature = "async_tokio")))]
+//! use gpiocdev::async_io::AsyncRequest;
+//!
+//! let req = gpiocdev::Request::builder()
+//!     .on_chip("/dev/gpiochip0")
+//!     .with_line(5)
+//!     .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
+//!     .request()?;
+//! let areq = AsyncRequest::new(req);
+//! loop {
+//!     let edge = areq.read_edge_event().await?;
+//!     println!("{edge:?}");
+//! }
+//! # }
+//! ```
+//!
 //! [`chip`]: module@chip
 //! [`request`]: module@request
 
@@ -63,6 +94,33 @@ use std::fmt;
 use std::ops::Range;
 use std::path::PathBuf;
 
+// Logging macros that forward to the log crate, or compile to nothing
+// if the log feature is disabled.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)+) => { log::debug!($($arg)+) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)+) => { log::trace!($($arg)+) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
 /// Types and functions specific to chips.
 pub mod chip;
 pub use chip::Chip;
@@ -70,6 +128,22 @@ pub use chip::Chip;
 /// Types specific to lines.
 pub mod line;
 
+/// Finding the processes holding lines, by scanning `/proc`.
+///
+/// The kernel does not identify the process holding a line, so this is best-effort.
+#[cfg(feature = "procfs")]
+pub mod procfs;
+
+/// Mapping between the global GPIO numbers of the deprecated sysfs interface
+/// and lines on chips, to assist migrating from sysfs.
+///
+/// The mapping is read from the chips in `/sys/class/gpio`, so this is best-effort
+/// and requires a kernel with the sysfs interface, which is deprecated, enabled.
+/// It is intended for identifying lines during migration, as global numbers are
+/// not stable and may change with the kernel version or hardware configuration.
+#[cfg(feature = "sysfs")]
+pub mod sysfs;
+
 /// Wrappers for various async reactors.
 #[cfg(any(feature = "async_tokio", feature = "async_io"))]
 mod r#async;
@@ -84,6 +158,60 @@ pub fn lines() -> Result<LineIterator> {
     LineIterator::new()
 }
 
+/// An iterator over all the GPIO chips in the system, opened.
+///
+/// The chips are returned in natural order of their paths, so `gpiochip10` follows
+/// `gpiochip2`.
+///
+/// A chip that cannot be opened, such as due to permissions, is returned as an error
+/// item rather than ending the iteration.
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// for chip in gpiocdev::chips()?.flatten() {
+///     let ci = chip.info()?;
+///     println!("{} [{}] ({} lines)", ci.name, ci.label, ci.num_lines);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn chips() -> Result<impl Iterator<Item = Result<Chip>>> {
+    Ok(open_chips(chip::chips()?))
+}
+
+/// Watch for GPIO chips being added to or removed from the system.
+///
+/// Detects hot-pluggable chips, such as USB GPIO expanders, coming and going.
+///
+/// Only chips added or removed after the watch is created are reported.
+/// Use [`chips`] to find the chips already present.
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// use gpiocdev::chip::ChipEvent;
+///
+/// for evt in gpiocdev::chips_watch()? {
+///     match evt? {
+///         ChipEvent::Added(path) => println!("added {}", path.display()),
+///         ChipEvent::Removed(path) => println!("removed {}", path.display()),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "chip_watch")]
+pub fn chips_watch() -> Result<chip::ChipWatcher> {
+    chip::ChipWatcher::new()
+}
+
+// Open the chips in path order.
+fn open_chips(mut paths: Vec<PathBuf>) -> impl Iterator<Item = Result<Chip>> {
+    paths.sort_unstable_by(|a, b| chip::path_compare(a, b));
+    paths.into_iter().map(Chip::from_path)
+}
+
 /// Find the chip hosting a named line, and the line offset on that chip.
 ///
 /// Stops at the first matching line, if one can be found.
@@ -198,6 +326,51 @@ pub fn find_named_lines<'a>(
     Ok(found)
 }
 
+/// Find all the lines that satisfy a predicate.
+///
+/// The predicate is passed the info for the chip and for the line, so lines may be
+/// selected by chip, such as by label, as well as by line name or attributes.
+///
+/// Each chip is opened once. Chips and lines that cannot be accessed are skipped.
+///
+/// Returns the matching lines, in chip then offset order.
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// // the unused LEDs on chips labelled "pinctrl-bcm2835"
+/// let leds = gpiocdev::lines_where(|ci, li| {
+///     ci.label == "pinctrl-bcm2835" && li.name.starts_with("LED_") && !li.used
+/// })?;
+/// # Ok(())
+/// # }
+///```
+pub fn lines_where<P>(predicate: P) -> Result<Vec<FoundLine>>
+where
+    P: Fn(&chip::Info, &line::Info) -> bool,
+{
+    let mut found = Vec::new();
+    for path in chip::chips()? {
+        let chip = match chip::Chip::from_path(&path) {
+            Ok(chip) => chip,
+            Err(_) => continue,
+        };
+        let (ci, liter) = match (chip.info(), chip.line_info_iter()) {
+            (Ok(ci), Ok(liter)) => (ci, liter),
+            _ => continue,
+        };
+        for li in liter.flatten() {
+            if predicate(&ci, &li) {
+                found.push(FoundLine {
+                    chip: chip.path().to_path_buf(),
+                    info: li,
+                });
+            }
+        }
+    }
+    Ok(found)
+}
+
 /// The info for a line discovered in the system.
 ///
 /// Identifies the chip hosting the line, and the line info.
@@ -418,6 +591,10 @@ pub enum Error {
     #[error(transparent)]
     Os(uapi::Errno),
 
+    /// A request for lines on a chip was refused.
+    #[error("\"{0}\" {1}.")]
+    Request(PathBuf, request::ErrorKind),
+
     /// An error returned from an underlying uAPI call.
     #[error("uAPI {0} returned: {1}")]
     Uapi(UapiCall, #[source] uapi::Error),
@@ -445,6 +622,7 @@ impl From<std::io::Error> for Error {
 #[doc(hidden)]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum UapiCall {
+    Close,
     GetChipInfo,
     GetLine,
     GetLineEvent,
@@ -465,6 +643,7 @@ pub enum UapiCall {
 impl fmt::Display for UapiCall {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let name = match self {
+            UapiCall::Close => "close",
             UapiCall::GetChipInfo => "get_chip_info",
             UapiCall::GetLine => "get_line",
             UapiCall::GetLineEvent => "get_line_event",
@@ -535,12 +714,15 @@ pub fn supports_abi_version(abiv: AbiVersion) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     mod uapi_call {
 
         #[test]
         fn display() {
             use super::UapiCall;
+            let uc = UapiCall::Close;
+            assert_eq!(format!("{}", uc), "close");
             let uc = UapiCall::GetChipInfo;
             assert_eq!(format!("{}", uc), "get_chip_info");
             let uc = UapiCall::GetLine;
@@ -574,6 +756,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn open_chips() {
+        let dir = std::env::temp_dir().join(format!("gpiocdev-open-chips-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| fs::canonicalize(&dir).unwrap().join(name);
+        for name in ["gpiochip10", "gpiochip2"] {
+            fs::write(path(name), b"").unwrap();
+        }
+        let paths = vec![path("gpiochip10"), path("gpiochip3"), path("gpiochip2")];
+
+        // numerically sorted, and continuing past chips that fail to open
+        let chips: Vec<Result<Chip>> = super::open_chips(paths).collect();
+        assert_eq!(chips.len(), 3);
+        assert_eq!(
+            chips[0].as_ref().unwrap_err(),
+            &Error::GpioChip(path("gpiochip2"), chip::ErrorKind::NotCharacterDevice)
+        );
+        assert!(matches!(chips[1], Err(Error::Os(_))));
+        assert_eq!(
+            chips[2].as_ref().unwrap_err(),
+            &Error::GpioChip(path("gpiochip10"), chip::ErrorKind::NotCharacterDevice)
+        );
+        _ = fs::remove_dir_all(&dir);
+    }
+
     mod abi_support_kind {
 
         #[test]
@@ -6,13 +6,13 @@ mod config;
 pub use self::config::Config;
 
 mod event;
-pub use self::event::{EdgeEvent, EdgeKind, InfoChangeEvent, InfoChangeKind};
+pub use self::event::{EdgeEvent, EdgeKind, EventTimestamp, InfoChangeEvent, InfoChangeKind};
 
 mod info;
 pub use self::info::Info;
 
 mod value;
-pub use self::value::{Value, Values};
+pub use self::value::{ParseValueError, Value, Values};
 
 #[cfg(feature = "uapi_v1")]
 use gpiocdev_uapi::v1;
@@ -252,6 +252,16 @@ impl From<v2::LineFlags> for EventClock {
     }
 }
 
+#[cfg(feature = "uapi_v1")]
+impl From<v1::EventClock> for EventClock {
+    fn from(clock: v1::EventClock) -> Self {
+        match clock {
+            v1::EventClock::Monotonic => EventClock::Monotonic,
+            v1::EventClock::Realtime => EventClock::Realtime,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,6 +420,23 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "uapi_v1")]
+    mod event_clock_v1 {
+        use super::{v1, EventClock};
+
+        #[test]
+        fn from_v1_event_clock() {
+            assert_eq!(
+                EventClock::from(v1::EventClock::Monotonic),
+                EventClock::Monotonic
+            );
+            assert_eq!(
+                EventClock::from(v1::EventClock::Realtime),
+                EventClock::Realtime
+            );
+        }
+    }
+
     mod offset_hasher {
         use super::OffsetHasher;
         use std::hash::Hasher;
@@ -65,6 +65,9 @@ impl Config {
     }
 
     /// Do not set the direction of the line.
+    ///
+    /// This is represented by a `direction` of `None`, and the request
+    /// leaves the direction flags unset in both uAPI versions.
     pub fn as_is(&mut self) -> &mut Self {
         self.direction = None;
         self.sanitize_input();
@@ -116,7 +119,6 @@ impl Config {
     }
 
     /// Check that two configs are equivalent, ignoring the line value.
-    #[cfg(feature = "uapi_v1")]
     pub(crate) fn equivalent(&self, right: &Config) -> bool {
         if (self.direction == right.direction)
             && (self.active_low == right.active_low)
@@ -362,7 +364,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "uapi_v1")]
     fn equivalent() {
         let mut lcfg: Config = Default::default();
         let mut rcfg: Config = Default::default();
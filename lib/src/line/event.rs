@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use super::{Info, Offset};
+use super::{EventClock, Info, Offset};
 #[cfg(all(feature = "uapi_v1", not(feature = "uapi_v2")))]
 use gpiocdev_uapi::v1 as uapi;
 #[cfg(feature = "uapi_v1")]
@@ -11,10 +11,14 @@ use gpiocdev_uapi::v1;
 use gpiocdev_uapi::{v2, v2 as uapi};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
+use std::ops::Sub;
+use std::time::{Duration, SystemTime};
 
 /// The details of an edge detected on an input line.
 ///
-/// ABI v1 does not provide the seqno nor line_seqno fields.
+/// ABI v1 does not provide the seqno nor line_seqno fields, so they are zero.
+/// With ABI v2 the sequence numbers start at 1, and gaps in the sequence
+/// indicate events that were discarded due to the kernel event buffer overflowing.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EdgeEvent {
@@ -28,8 +32,22 @@ pub struct EdgeEvent {
     ///
     /// **CLOCK_MONOTONIC** is intended for comparing times between events and
     /// should be converted to [`Duration`](std::time::Duration).
+    ///
+    /// The [`timestamp`] method provides the timestamp along with the clock
+    /// that provided it.
+    ///
+    /// [`timestamp`]: #method.timestamp
     pub timestamp_ns: u64,
 
+    /// The clock used as the source for the timestamp.
+    ///
+    /// This is the event clock configured for the line when the event was read.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "eventClock", default, skip_serializing_if = "is_monotonic")
+    )]
+    pub event_clock: EventClock,
+
     /// The event trigger identifier.
     pub kind: EdgeKind,
 
@@ -49,11 +67,57 @@ pub struct EdgeEvent {
     )]
     pub line_seqno: u32,
 }
+
+impl EdgeEvent {
+    /// The timestamp of the event, along with the clock that provided it.
+    pub fn timestamp(&self) -> EventTimestamp {
+        EventTimestamp {
+            clock: self.event_clock,
+            ns: self.timestamp_ns,
+        }
+    }
+
+    /// The number of events discarded by the kernel between the previous event
+    /// read from the request and this event.
+    ///
+    /// The kernel discards the oldest events when the kernel event buffer
+    /// overflows, which is detectable as a gap in the seqno.
+    /// The `prev` event must be the event read from the same request immediately
+    /// before this one, or None if this is the first event read from the request.
+    ///
+    /// Gaps in the line_seqno similarly indicate events discarded for a particular line.
+    ///
+    /// Returns None if the sequence numbers are unavailable, as is the case for ABI v1.
+    pub fn lost_since(&self, prev: Option<&EdgeEvent>) -> Option<u32> {
+        if self.seqno == 0 {
+            return None;
+        }
+        let prev_seqno = prev.map_or(0, |p| p.seqno);
+        Some(self.seqno.wrapping_sub(prev_seqno).wrapping_sub(1))
+    }
+}
+
+impl std::fmt::Display for EdgeEvent {
+    /// Formats the event as the edge kind, the line offset, and the raw timestamp.
+    ///
+    /// The timestamp is in nanoseconds, as read from the event clock configured
+    /// for the line - **CLOCK_MONOTONIC** unless otherwise configured.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} edge on line {} at {}ns",
+            self.kind, self.offset, self.timestamp_ns
+        )
+    }
+}
+
 #[cfg(feature = "uapi_v1")]
 impl From<&v1::LineEdgeEvent> for EdgeEvent {
     fn from(le: &v1::LineEdgeEvent) -> Self {
         EdgeEvent {
             timestamp_ns: le.timestamp_ns,
+            // populated by the request, which knows the line config
+            event_clock: EventClock::Monotonic,
             kind: EdgeKind::from(le.kind),
             // v1 doesn't provide the remaining fields...
             offset: 0,
@@ -67,6 +131,8 @@ impl From<&v2::LineEdgeEvent> for EdgeEvent {
     fn from(le: &v2::LineEdgeEvent) -> Self {
         EdgeEvent {
             timestamp_ns: le.timestamp_ns,
+            // populated by the request, which knows the line config
+            event_clock: EventClock::Monotonic,
             kind: EdgeKind::from(le.kind),
             offset: le.offset,
             seqno: le.seqno,
@@ -80,6 +146,63 @@ fn is_zero(u: &u32) -> bool {
     *u == 0
 }
 
+#[cfg(feature = "serde")]
+fn is_monotonic(clock: &EventClock) -> bool {
+    *clock == EventClock::Monotonic
+}
+
+/// The timestamp of an [`EdgeEvent`], and the clock that provided it.
+///
+/// The difference between two timestamps from the same clock is the period
+/// between the events, e.g. `evt2.timestamp() - evt1.timestamp()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EventTimestamp {
+    /// The clock used as the source for the timestamp.
+    pub clock: EventClock,
+
+    /// The timestamp, in nanoseconds.
+    pub ns: u64,
+}
+
+impl EventTimestamp {
+    /// The timestamp as the time since boot.
+    ///
+    /// Only applicable to **CLOCK_MONOTONIC** timestamps, which do not include
+    /// time the system spent suspended.
+    ///
+    /// Returns None for other clocks.
+    pub fn as_duration_since_boot(&self) -> Option<Duration> {
+        match self.clock {
+            EventClock::Monotonic => Some(Duration::from_nanos(self.ns)),
+            _ => None,
+        }
+    }
+
+    /// The timestamp as wall-clock time.
+    ///
+    /// Only applicable to **CLOCK_REALTIME** timestamps.
+    ///
+    /// Returns None for other clocks.
+    pub fn as_system_time(&self) -> Option<SystemTime> {
+        match self.clock {
+            EventClock::Realtime => Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(self.ns)),
+            _ => None,
+        }
+    }
+}
+
+impl Sub for EventTimestamp {
+    type Output = Duration;
+
+    /// The period between two timestamps.
+    ///
+    /// The timestamps should be from the same clock.
+    /// Returns zero if `rhs` is later than `self`.
+    fn sub(self, rhs: EventTimestamp) -> Duration {
+        Duration::from_nanos(self.ns.saturating_sub(rhs.ns))
+    }
+}
+
 /// The cause of an [`EdgeEvent`].
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -91,6 +214,16 @@ pub enum EdgeKind {
     Falling = 2,
 }
 
+impl std::fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            EdgeKind::Rising => "rising",
+            EdgeKind::Falling => "falling",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl From<uapi::LineEdgeEventKind> for EdgeKind {
     fn from(kind: uapi::LineEdgeEventKind) -> Self {
         match kind {
@@ -178,6 +311,7 @@ mod tests {
             };
             let ee = EdgeEvent::from(&v1event);
             assert_eq!(ee.timestamp_ns, 1234);
+            assert_eq!(ee.event_clock, EventClock::Monotonic);
             assert_eq!(ee.kind, EdgeKind::Falling);
             assert_eq!(ee.offset, 0);
             assert_eq!(ee.seqno, 0);
@@ -197,11 +331,110 @@ mod tests {
             };
             let ee = EdgeEvent::from(&v2event);
             assert_eq!(ee.timestamp_ns, 1234);
+            assert_eq!(ee.event_clock, EventClock::Monotonic);
             assert_eq!(ee.kind, EdgeKind::Rising);
             assert_eq!(ee.offset, 23);
             assert_eq!(ee.seqno, 2);
             assert_eq!(ee.line_seqno, 1);
         }
+
+        #[test]
+        fn display() {
+            let mut ee = EdgeEvent {
+                timestamp_ns: 1234,
+                event_clock: EventClock::Monotonic,
+                kind: EdgeKind::Rising,
+                offset: 3,
+                seqno: 2,
+                line_seqno: 1,
+            };
+            assert_eq!(ee.to_string(), "rising edge on line 3 at 1234ns");
+            ee.kind = EdgeKind::Falling;
+            assert_eq!(ee.to_string(), "falling edge on line 3 at 1234ns");
+        }
+
+        #[test]
+        fn lost_since() {
+            let event = |seqno| EdgeEvent {
+                timestamp_ns: 1234,
+                event_clock: EventClock::Monotonic,
+                kind: EdgeKind::Rising,
+                offset: 3,
+                seqno,
+                line_seqno: seqno,
+            };
+            // no seqnos
+            assert_eq!(event(0).lost_since(None), None);
+            assert_eq!(event(0).lost_since(Some(&event(0))), None);
+            // first event
+            assert_eq!(event(1).lost_since(None), Some(0));
+            assert_eq!(event(5).lost_since(None), Some(4));
+            // subsequent events
+            assert_eq!(event(2).lost_since(Some(&event(1))), Some(0));
+            assert_eq!(event(7).lost_since(Some(&event(3))), Some(3));
+            // wrapped
+            assert_eq!(event(1).lost_since(Some(&event(u32::MAX))), Some(1));
+        }
+    }
+
+    mod event_timestamp {
+        use super::*;
+
+        fn event(clock: EventClock, timestamp_ns: u64) -> EdgeEvent {
+            EdgeEvent {
+                timestamp_ns,
+                event_clock: clock,
+                kind: EdgeKind::Rising,
+                offset: 3,
+                seqno: 0,
+                line_seqno: 0,
+            }
+        }
+
+        #[test]
+        fn timestamp() {
+            let ts = event(EventClock::Realtime, 1234).timestamp();
+            assert_eq!(ts.clock, EventClock::Realtime);
+            assert_eq!(ts.ns, 1234);
+        }
+
+        #[test]
+        fn as_duration_since_boot() {
+            let ts = event(EventClock::Monotonic, 1_500_000_001).timestamp();
+            assert_eq!(
+                ts.as_duration_since_boot(),
+                Some(Duration::new(1, 500_000_001))
+            );
+            let ts = event(EventClock::Realtime, 1234).timestamp();
+            assert_eq!(ts.as_duration_since_boot(), None);
+            let ts = event(EventClock::Hte, 1234).timestamp();
+            assert_eq!(ts.as_duration_since_boot(), None);
+        }
+
+        #[test]
+        fn as_system_time() {
+            let ts = event(EventClock::Realtime, 1_700_000_123_456_789_012).timestamp();
+            assert_eq!(
+                ts.as_system_time(),
+                Some(SystemTime::UNIX_EPOCH + Duration::new(1_700_000_123, 456_789_012))
+            );
+            let ts = event(EventClock::Monotonic, 1234).timestamp();
+            assert_eq!(ts.as_system_time(), None);
+            let ts = event(EventClock::Hte, 1234).timestamp();
+            assert_eq!(ts.as_system_time(), None);
+        }
+
+        #[test]
+        fn sub() {
+            let evt1 = event(EventClock::Monotonic, 1_000_000_000);
+            let evt2 = event(EventClock::Monotonic, 3_500_000_000);
+            assert_eq!(
+                evt2.timestamp() - evt1.timestamp(),
+                Duration::from_millis(2500)
+            );
+            // clamped
+            assert_eq!(evt1.timestamp() - evt2.timestamp(), Duration::ZERO);
+        }
     }
 
     mod info_change_event {
@@ -12,7 +12,10 @@ use serde_derive::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// The publicly available information for a line.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+///
+/// The info is typically read from a chip, but may be constructed from
+/// [`Default`] and the public fields then set.
+#[derive(Clone, Debug, Default, Eq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -77,6 +80,94 @@ pub struct Info {
     /// None or a zero value means no debounce.
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub debounce_period: Option<Duration>,
+
+    /// Any flags reported by the kernel that are not represented by the other fields.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    unknown_flags: u64,
+
+    /// The flags exactly as reported by the kernel.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw_flags: u64,
+}
+
+// The raw flags are not compared, as their encoding depends on the uAPI ABI
+// version that reported them, and they are otherwise represented by the
+// other fields.
+impl PartialEq for Info {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+            && self.name == other.name
+            && self.consumer == other.consumer
+            && self.used == other.used
+            && self.active_low == other.active_low
+            && self.direction == other.direction
+            && self.bias == other.bias
+            && self.drive == other.drive
+            && self.edge_detection == other.edge_detection
+            && self.event_clock == other.event_clock
+            && self.debounce_period == other.debounce_period
+            && self.unknown_flags == other.unknown_flags
+    }
+}
+
+impl Info {
+    /// Any flags reported by the kernel that are not represented by the other fields.
+    ///
+    /// These are flags added by kernels more recent than this crate, and are in the
+    /// encoding of the uAPI ABI version that reported them.
+    ///
+    /// Zero if all the flags are known.
+    pub fn unknown_flags(&self) -> u64 {
+        self.unknown_flags
+    }
+
+    /// The flags exactly as reported by the kernel, for diagnostics.
+    ///
+    /// The flags are in the encoding of the uAPI ABI version that reported them,
+    /// i.e. `v1::LineInfoFlags` or `v2::LineFlags` from `gpiocdev-uapi`, and
+    /// include any flags not decoded into the other fields.
+    ///
+    /// Zero if the info was not read from the kernel.
+    pub fn raw_flags(&self) -> u64 {
+        self.raw_flags
+    }
+
+    /// The names of the attributes that differ from a previous read of the line info.
+    ///
+    /// Intended for identifying what changed when a line is reconfigured,
+    /// as an [`InfoChangeEvent`] only reports the updated info.
+    ///
+    /// The names are returned in the order direction, bias, drive, active_low,
+    /// edge, debounce and consumer, and only those that changed are included.
+    /// A debounce period of zero is considered the same as no debounce.
+    ///
+    /// [`InfoChangeEvent`]: crate::line::InfoChangeEvent
+    pub fn changed_fields(&self, previous: &Info) -> Vec<&'static str> {
+        let debounce = |info: &Info| info.debounce_period.filter(|d| !d.is_zero());
+        let mut changed = Vec::new();
+        if self.direction != previous.direction {
+            changed.push("direction");
+        }
+        if self.bias != previous.bias {
+            changed.push("bias");
+        }
+        if self.drive != previous.drive {
+            changed.push("drive");
+        }
+        if self.active_low != previous.active_low {
+            changed.push("active_low");
+        }
+        if self.edge_detection != previous.edge_detection {
+            changed.push("edge");
+        }
+        if debounce(self) != debounce(previous) {
+            changed.push("debounce");
+        }
+        if self.consumer != previous.consumer {
+            changed.push("consumer");
+        }
+        changed
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -84,6 +175,11 @@ fn is_false(b: &bool) -> bool {
     !b
 }
 
+#[cfg(feature = "serde")]
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
 #[cfg(feature = "uapi_v1")]
 impl From<&v1::LineInfo> for Info {
     fn from(li: &v1::LineInfo) -> Self {
@@ -96,6 +192,8 @@ impl From<&v1::LineInfo> for Info {
             direction: Direction::from(li.flags),
             bias: Bias::try_from(li.flags).ok(),
             drive: Drive::try_from(li.flags).ok(),
+            unknown_flags: u64::from(li.flags.bits() & !v1::LineInfoFlags::all().bits()),
+            raw_flags: u64::from(li.flags.bits()),
             // other fields are unknown to v1
             ..Default::default()
         }
@@ -128,6 +226,8 @@ impl From<&v2::LineInfo> for Info {
             edge_detection: ed,
             event_clock: ec,
             debounce_period,
+            unknown_flags: li.flags.bits() & !v2::LineFlags::all().bits(),
+            raw_flags: li.flags.bits(),
         }
     }
 }
@@ -136,6 +236,60 @@ impl From<&v2::LineInfo> for Info {
 mod tests {
     use super::*;
 
+    #[test]
+    fn changed_fields() {
+        let prev = Info {
+            offset: 17,
+            consumer: "watcher".into(),
+            used: true,
+            direction: Direction::Input,
+            bias: Some(Bias::PullUp),
+            edge_detection: Some(EdgeDetection::BothEdges),
+            event_clock: Some(EventClock::Monotonic),
+            ..Default::default()
+        };
+        assert!(prev.changed_fields(&prev).is_empty());
+
+        let info = Info {
+            bias: Some(Bias::PullDown),
+            debounce_period: Some(Duration::from_millis(5)),
+            ..prev.clone()
+        };
+        assert_eq!(info.changed_fields(&prev), vec!["bias", "debounce"]);
+        assert_eq!(prev.changed_fields(&info), vec!["bias", "debounce"]);
+
+        let info = Info {
+            direction: Direction::Output,
+            drive: Some(Drive::OpenDrain),
+            active_low: true,
+            edge_detection: None,
+            event_clock: None,
+            consumer: "driver".into(),
+            ..prev.clone()
+        };
+        assert_eq!(
+            info.changed_fields(&prev),
+            vec!["direction", "drive", "active_low", "edge", "consumer"]
+        );
+
+        // a zero debounce period is no debounce
+        let info = Info {
+            debounce_period: Some(Duration::ZERO),
+            ..prev.clone()
+        };
+        assert!(info.changed_fields(&prev).is_empty());
+
+        // fields that are not attributes are ignored
+        let info = Info {
+            offset: 18,
+            name: "GPIO18".into(),
+            used: false,
+            raw_flags: 0x1234,
+            ..prev.clone()
+        };
+        assert!(info.changed_fields(&prev).is_empty());
+    }
+
     #[test]
     #[cfg(feature = "uapi_v1")]
     fn info_from_v1_line_info() {
@@ -196,7 +350,266 @@ mod tests {
         assert!(info.edge_detection.is_none());
         assert!(info.event_clock.is_none());
         assert!(info.debounce_period.is_none());
+        assert_eq!(info.unknown_flags, 0);
     }
+
+    #[test]
+    #[cfg(feature = "uapi_v1")]
+    fn info_from_v1_line_info_flags() {
+        let info_from = |bits: u32| {
+            let info = Info::from(&v1::LineInfo {
+                flags: v1::LineInfoFlags::from_bits_retain(bits),
+                ..Default::default()
+            });
+            assert_eq!(info.raw_flags(), u64::from(bits));
+            info
+        };
+
+        // each flag individually
+        assert_eq!(
+            info_from(1),
+            Info {
+                used: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(2),
+            Info {
+                direction: Direction::Output,
+                drive: Some(Drive::PushPull),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(4),
+            Info {
+                active_low: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(8),
+            Info {
+                drive: Some(Drive::OpenDrain),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(16),
+            Info {
+                drive: Some(Drive::OpenSource),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(32),
+            Info {
+                bias: Some(Bias::PullUp),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(64),
+            Info {
+                bias: Some(Bias::PullDown),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(128),
+            Info {
+                bias: Some(Bias::Disabled),
+                ..Default::default()
+            }
+        );
+        for bit in 8..32 {
+            assert_eq!(
+                info_from(1 << bit),
+                Info {
+                    unknown_flags: 1 << bit,
+                    ..Default::default()
+                }
+            );
+        }
+
+        // combined
+        assert_eq!(
+            info_from(0x0000_0107),
+            Info {
+                used: true,
+                active_low: true,
+                direction: Direction::Output,
+                drive: Some(Drive::PushPull),
+                unknown_flags: 0x100,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(u32::MAX),
+            Info {
+                used: true,
+                active_low: true,
+                direction: Direction::Output,
+                drive: Some(Drive::OpenDrain),
+                bias: Some(Bias::PullUp),
+                unknown_flags: 0xffff_ff00,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
+    fn info_from_v2_line_info_flags() {
+        let info_from = |bits: u64| {
+            let info = Info::from(&v2::LineInfo {
+                flags: v2::LineFlags::from_bits_retain(bits),
+                ..Default::default()
+            });
+            assert_eq!(info.raw_flags(), bits);
+            info
+        };
+
+        // each flag individually
+        assert_eq!(
+            info_from(1),
+            Info {
+                used: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(2),
+            Info {
+                active_low: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(4),
+            Info {
+                direction: Direction::Input,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(8),
+            Info {
+                direction: Direction::Output,
+                drive: Some(Drive::PushPull),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(16),
+            Info {
+                edge_detection: Some(EdgeDetection::RisingEdge),
+                event_clock: Some(EventClock::Monotonic),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(32),
+            Info {
+                edge_detection: Some(EdgeDetection::FallingEdge),
+                event_clock: Some(EventClock::Monotonic),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(64),
+            Info {
+                drive: Some(Drive::OpenDrain),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(128),
+            Info {
+                drive: Some(Drive::OpenSource),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(256),
+            Info {
+                bias: Some(Bias::PullUp),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(512),
+            Info {
+                bias: Some(Bias::PullDown),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(1024),
+            Info {
+                bias: Some(Bias::Disabled),
+                ..Default::default()
+            }
+        );
+        // the event clock is only reported for lines with edge detection
+        assert_eq!(info_from(2048), Info::default());
+        assert_eq!(info_from(4096), Info::default());
+        assert_eq!(
+            info_from(16 | 2048),
+            Info {
+                edge_detection: Some(EdgeDetection::RisingEdge),
+                event_clock: Some(EventClock::Realtime),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(32 | 4096),
+            Info {
+                edge_detection: Some(EdgeDetection::FallingEdge),
+                event_clock: Some(EventClock::Hte),
+                ..Default::default()
+            }
+        );
+        for bit in 13..64 {
+            assert_eq!(
+                info_from(1 << bit),
+                Info {
+                    unknown_flags: 1 << bit,
+                    ..Default::default()
+                }
+            );
+        }
+
+        // combined
+        assert_eq!(
+            info_from(0x0000_2235),
+            Info {
+                used: true,
+                direction: Direction::Input,
+                bias: Some(Bias::PullDown),
+                edge_detection: Some(EdgeDetection::BothEdges),
+                event_clock: Some(EventClock::Monotonic),
+                unknown_flags: 0x2000,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            info_from(u64::MAX),
+            Info {
+                used: true,
+                active_low: true,
+                direction: Direction::Output,
+                drive: Some(Drive::OpenDrain),
+                bias: Some(Bias::PullUp),
+                edge_detection: Some(EdgeDetection::BothEdges),
+                event_clock: Some(EventClock::Realtime),
+                unknown_flags: !0x1fff,
+                ..Default::default()
+            }
+        );
+    }
+
     #[test]
     #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
     fn info_from_v2_line_info() {
@@ -288,5 +701,6 @@ mod tests {
         assert_eq!(info.edge_detection, Some(EdgeDetection::RisingEdge));
         assert_eq!(info.event_clock, Some(EventClock::Monotonic));
         assert!(info.debounce_period.is_none());
+        assert_eq!(info.unknown_flags, 0);
     }
 }
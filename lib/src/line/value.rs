@@ -10,6 +10,8 @@ use gpiocdev_uapi::v2;
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
 
 /// The logical level of a line.
 ///
@@ -39,6 +41,33 @@ impl Value {
             Value::Inactive => Value::Active,
         }
     }
+
+    /// Toggle the value in place.
+    ///
+    /// The in-place equivalent of [`not`](#method.not).
+    pub fn toggle(&mut self) {
+        *self = self.not();
+    }
+}
+
+impl std::ops::Not for Value {
+    type Output = Value;
+
+    fn not(self) -> Value {
+        Value::not(&self)
+    }
+}
+
+impl PartialEq<bool> for Value {
+    fn eq(&self, other: &bool) -> bool {
+        bool::from(*self) == *other
+    }
+}
+
+impl PartialEq<Value> for bool {
+    fn eq(&self, other: &Value) -> bool {
+        *self == bool::from(*other)
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -87,6 +116,42 @@ impl From<u8> for Value {
     }
 }
 
+/// Parse a value from a string.
+///
+/// Values may be "inactive", "off", "false" or "0", or "active", "on", "true"
+/// or "1", ignoring case.
+impl FromStr for Value {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Value, ParseValueError> {
+        match s.to_ascii_lowercase().as_str() {
+            "0" | "inactive" | "off" | "false" => Ok(Value::Inactive),
+            "1" | "active" | "on" | "true" => Ok(Value::Active),
+            _ => Err(ParseValueError(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<&str> for Value {
+    type Error = ParseValueError;
+
+    fn try_from(s: &str) -> Result<Value, ParseValueError> {
+        s.parse()
+    }
+}
+
+/// The error returned when a string cannot be parsed as a [`Value`].
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("invalid line value: '{0}'")]
+pub struct ParseValueError(String);
+
+impl ParseValueError {
+    /// The string that could not be parsed.
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
 /// The value of a particular line.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -100,7 +165,7 @@ pub struct LineValue {
 /// A  collection of line values.
 ///
 /// Lines are identified by their offset.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Values(Vec<LineValue>);
 impl Values {
@@ -191,12 +256,12 @@ impl Values {
 
     // v1 values are a contiguous list.  If a list shorter than offsets
     // is presented to the kernel then the missing lines default to zero.
-    // Build the complete values list here with any missing values being zero filled.
+    // Overwrite the values in dst for the lines present in self, leaving the
+    // missing lines unchanged, so the caller can pre-fill them as required.
     ///
     /// Both sets of offsets must be sorted.
     #[cfg(feature = "uapi_v1")]
-    pub(crate) fn to_v1(&self, offsets: &[Offset]) -> v1::LineValues {
-        let mut dst: v1::LineValues = Default::default();
+    pub(crate) fn overlay_v1(&self, offsets: &[Offset], dst: &mut v1::LineValues) {
         let mut didx = 0;
         for lv in self.0.iter() {
             while didx < offsets.len() {
@@ -211,14 +276,13 @@ impl Values {
                         break;
                     }
                     Ordering::Greater => {
-                        // offset is in offsets, but not values, so it defaults
+                        // offset is in offsets, but not values, so it is left unchanged
                         didx += 1;
                         continue;
                     }
                 }
             }
         }
-        dst
     }
 
     // v2 values are a bitmap in offsets ordering
@@ -288,10 +352,21 @@ impl Values {
         self
     }
 
+    /// Set the values of all the lines in `other`.
+    ///
+    /// Values for lines contained in both sets are overwritten by those in `other`.
+    /// Values for lines not contained in `other` are left unchanged.
+    pub fn set_all(&mut self, other: &Values) -> &mut Self {
+        for lv in other.0.iter() {
+            self.set(lv.offset, lv.value);
+        }
+        self
+    }
+
     /// Toggle all values.
     pub fn not(&mut self) -> &mut Self {
         for lv in self.0.iter_mut() {
-            lv.value = lv.value.not();
+            lv.value.toggle();
         }
         self
     }
@@ -303,8 +378,7 @@ impl Values {
     pub fn toggle(&mut self, offset: Offset) {
         match self.0.binary_search_by(|lv| lv.offset.cmp(&offset)) {
             Ok(idx) => {
-                let lv = self.0.get_mut(idx).unwrap();
-                lv.value = lv.value.not();
+                self.0.get_mut(idx).unwrap().value.toggle();
             }
             Err(idx) => self.0.insert(
                 idx,
@@ -316,6 +390,46 @@ impl Values {
         }
     }
 
+    /// Toggle the values of a set of lines.
+    ///
+    /// Lines not already set are set active, as per [`toggle`](#method.toggle).
+    pub fn toggle_lines(&mut self, offsets: &[Offset]) -> &mut Self {
+        for offset in offsets {
+            self.toggle(*offset);
+        }
+        self
+    }
+
+    /// Set all the lines in the collection to the same value.
+    pub fn fill(&mut self, value: Value) -> &mut Self {
+        for lv in self.0.iter_mut() {
+            lv.value = value;
+        }
+        self
+    }
+
+    /// The merge of this set and `other`.
+    ///
+    /// Values for lines contained in both sets are taken from `other`.
+    ///
+    /// The non-mutating equivalent of [`set_all`](#method.set_all).
+    pub fn overridden_by(&self, other: &Values) -> Values {
+        let mut values = self.clone();
+        values.set_all(other);
+        values
+    }
+
+    /// The subset of values for the lines in `offsets`.
+    ///
+    /// Lines in `offsets` that are not contained in this set are ignored.
+    pub fn filter(&self, offsets: &[Offset]) -> Values {
+        self.0
+            .iter()
+            .filter(|lv| offsets.contains(&lv.offset))
+            .map(|lv| (lv.offset, lv.value))
+            .collect()
+    }
+
     /// Remove any value setting for a line.
     #[inline]
     pub fn unset(&mut self, offset: Offset) {
@@ -346,6 +460,13 @@ impl Values {
         offsets.iter().collect()
     }
 
+    /// Construct a `Values` collection from a map of offsets to values.
+    pub fn from_map<S>(map: &HashMap<Offset, Value, S>) -> Values {
+        map.iter()
+            .map(|(offset, value)| (*offset, *value))
+            .collect()
+    }
+
     /// An iterator to visit all values.
     pub fn iter(&self) -> std::slice::Iter<'_, LineValue> {
         self.0.iter()
@@ -416,6 +537,39 @@ mod tests {
         assert_eq!(Value::Inactive.not(), Value::Active);
     }
 
+    #[test]
+    fn not_operator() {
+        assert_eq!(!Value::Active, Value::Inactive);
+        assert_eq!(!Value::Inactive, Value::Active);
+    }
+
+    #[test]
+    fn eq_bool() {
+        assert_eq!(Value::Active, true);
+        assert_eq!(Value::Inactive, false);
+        assert_ne!(Value::Active, false);
+        assert_ne!(Value::Inactive, true);
+        assert_eq!(true, Value::Active);
+        assert_eq!(false, Value::Inactive);
+        assert_ne!(false, Value::Active);
+        assert_ne!(true, Value::Inactive);
+    }
+
+    #[test]
+    fn toggle() {
+        let mut v = Value::Active;
+        v.toggle();
+        assert_eq!(v, Value::Inactive);
+        v.toggle();
+        assert_eq!(v, Value::Active);
+
+        let mut v = Value::Inactive;
+        v.toggle();
+        assert_eq!(v, Value::Active);
+        v.toggle();
+        assert_eq!(v, Value::Inactive);
+    }
+
     #[test]
     fn from_bool() {
         assert_eq!(Value::from(true), Value::Active);
@@ -445,6 +599,31 @@ mod tests {
         assert_eq!(u, 0);
     }
 
+    #[test]
+    fn from_str() {
+        for s in ["0", "inactive", "off", "false", "Inactive", "OFF", "False"] {
+            assert_eq!(s.parse::<Value>(), Ok(Value::Inactive), "{}", s);
+        }
+        for s in ["1", "active", "on", "true", "Active", "ON", "True"] {
+            assert_eq!(s.parse::<Value>(), Ok(Value::Active), "{}", s);
+        }
+        for s in ["", "2", "as-is", "toggle", "high", " on"] {
+            let err = s.parse::<Value>().unwrap_err();
+            assert_eq!(err.value(), s);
+            assert_eq!(err.to_string(), format!("invalid line value: '{}'", s));
+        }
+    }
+
+    #[test]
+    fn try_from_str() {
+        assert_eq!(Value::try_from("on"), Ok(Value::Active));
+        assert_eq!(Value::try_from("0"), Ok(Value::Inactive));
+        assert_eq!(
+            Value::try_from("blah"),
+            Err(ParseValueError("blah".to_string()))
+        );
+    }
+
     mod values {
         use super::*;
 
@@ -511,18 +690,20 @@ mod tests {
 
         #[test]
         #[cfg(feature = "uapi_v1")]
-        fn to_v1() {
+        fn overlay_v1_zeroed() {
             // offsets must be sorted.
             let offsets = Vec::from([1, 3, 5, 8]);
             // both empty
             let mut src = Values::default();
-            let dst = src.to_v1(&[]);
+            let mut dst = v1::LineValues::default();
+            src.overlay_v1(&[], &mut dst);
             assert_eq!(dst.get(0), 0); // 1
             assert_eq!(dst.get(1), 0); // 5
             assert_eq!(dst.get(2), 0); // 3
             assert_eq!(dst.get(3), 0); // 8
 
-            let dst = src.to_v1(&offsets);
+            let mut dst = v1::LineValues::default();
+            src.overlay_v1(&offsets, &mut dst);
             assert_eq!(dst.get(0), 0); // 1
             assert_eq!(dst.get(1), 0); // 5
             assert_eq!(dst.get(2), 0); // 3
@@ -533,13 +714,42 @@ mod tests {
             src.set(3, Value::Inactive);
             src.set(7, Value::Active); // should be ignored
             src.set(8, Value::Active);
-            let dst = src.to_v1(&offsets);
+            let mut dst = v1::LineValues::default();
+            src.overlay_v1(&offsets, &mut dst);
             assert_eq!(dst.get(0), 1); // 1
             assert_eq!(dst.get(1), 0); // 5
             assert_eq!(dst.get(2), 0); // 3
             assert_eq!(dst.get(3), 1); // 8
         }
 
+        #[test]
+        #[cfg(feature = "uapi_v1")]
+        fn overlay_v1() {
+            // offsets must be sorted.
+            let offsets = Vec::from([1, 3, 5, 8]);
+            let mut dst = v1::LineValues::default();
+            dst.set(1, 1);
+            dst.set(2, 1);
+
+            // empty leaves dst unchanged
+            let mut src = Values::default();
+            src.overlay_v1(&offsets, &mut dst);
+            assert_eq!(dst.get(0), 0); // 1
+            assert_eq!(dst.get(1), 1); // 3
+            assert_eq!(dst.get(2), 1); // 5
+            assert_eq!(dst.get(3), 0); // 8
+
+            // values intersects offsets
+            src.set(1, Value::Active);
+            src.set(3, Value::Inactive);
+            src.set(7, Value::Active); // should be ignored
+            src.overlay_v1(&offsets, &mut dst);
+            assert_eq!(dst.get(0), 1); // 1
+            assert_eq!(dst.get(1), 0); // 3
+            assert_eq!(dst.get(2), 1); // 5
+            assert_eq!(dst.get(3), 0); // 8
+        }
+
         #[test]
         #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
         fn to_v2() {
@@ -668,6 +878,147 @@ mod tests {
             assert_eq!(vv.get(3), Some(Value::Active));
         }
 
+        #[test]
+        fn toggle_lines() {
+            let mut vv: Values = [(1, Value::Active), (2, Value::Inactive), (3, Value::Active)]
+                .into_iter()
+                .collect();
+            vv.toggle_lines(&[1, 2, 5]);
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            assert_eq!(vv.get(2), Some(Value::Active));
+            assert_eq!(vv.get(3), Some(Value::Active));
+            // missing lines are set active
+            assert_eq!(vv.get(5), Some(Value::Active));
+            assert_eq!(vv.len(), 4);
+        }
+
+        #[test]
+        fn fill() {
+            let mut vv: Values = [(1, Value::Active), (2, Value::Inactive), (3, Value::Active)]
+                .into_iter()
+                .collect();
+            vv.fill(Value::Active);
+            assert_eq!(
+                vv,
+                [(1, Value::Active), (2, Value::Active), (3, Value::Active)]
+                    .into_iter()
+                    .collect()
+            );
+            vv.fill(Value::Inactive);
+            assert_eq!(vv, Values::from_offsets(&[1, 2, 3]));
+
+            let mut vv = Values::default();
+            vv.fill(Value::Active);
+            assert!(vv.is_empty());
+        }
+
+        #[test]
+        fn overridden_by() {
+            let lhs: Values = [(1, Value::Active), (2, Value::Inactive), (3, Value::Active)]
+                .into_iter()
+                .collect();
+            let rhs: Values = [(2, Value::Active), (3, Value::Inactive), (4, Value::Active)]
+                .into_iter()
+                .collect();
+            assert_eq!(
+                lhs.overridden_by(&rhs),
+                [
+                    (1, Value::Active),
+                    (2, Value::Active),
+                    (3, Value::Inactive),
+                    (4, Value::Active)
+                ]
+                .into_iter()
+                .collect()
+            );
+            assert_eq!(
+                rhs.overridden_by(&lhs),
+                [
+                    (1, Value::Active),
+                    (2, Value::Inactive),
+                    (3, Value::Active),
+                    (4, Value::Active)
+                ]
+                .into_iter()
+                .collect()
+            );
+            assert_eq!(lhs.overridden_by(&Values::default()), lhs);
+            assert_eq!(Values::default().overridden_by(&lhs), lhs);
+        }
+
+        #[test]
+        fn filter() {
+            let vv: Values = [(1, Value::Active), (2, Value::Inactive), (3, Value::Active)]
+                .into_iter()
+                .collect();
+            assert_eq!(
+                vv.filter(&[3, 1, 5]),
+                [(1, Value::Active), (3, Value::Active)]
+                    .into_iter()
+                    .collect()
+            );
+            assert!(vv.filter(&[]).is_empty());
+            assert!(vv.filter(&[4, 5]).is_empty());
+        }
+
+        // The values for offsets 0..6 selected by mask, and with values from bits.
+        fn values_from_bits(mask: u32, bits: u32) -> Values {
+            (0..6)
+                .filter(|offset| mask & (1 << offset) != 0)
+                .map(|offset| (offset, Value::from(bits & (1 << offset) != 0)))
+                .collect()
+        }
+
+        #[test]
+        fn toggle_twice_is_identity() {
+            for mask in 0..64 {
+                for bits in 0..64 {
+                    let vv = values_from_bits(mask, bits);
+                    let offsets: Vec<Offset> = vv.iter().map(|lv| lv.offset).collect();
+
+                    let mut toggled = vv.clone();
+                    toggled.not().not();
+                    assert_eq!(toggled, vv);
+
+                    toggled.toggle_lines(&offsets).toggle_lines(&offsets);
+                    assert_eq!(toggled, vv);
+                }
+            }
+        }
+
+        #[test]
+        fn overridden_by_is_associative_on_disjoint_sets() {
+            use Value::{Active, Inactive};
+
+            let vv = |lvs: &[(Offset, Value)]| -> Values { lvs.iter().copied().collect() };
+            // (a, b, c) with disjoint offsets
+            let cases = [
+                // interleaved
+                (
+                    vv(&[(1, Active), (4, Inactive)]),
+                    vv(&[(2, Inactive), (5, Active)]),
+                    vv(&[(3, Active), (6, Inactive)]),
+                ),
+                // contiguous, in reverse order
+                (
+                    vv(&[(5, Active), (6, Active)]),
+                    vv(&[(3, Inactive), (4, Inactive)]),
+                    vv(&[(1, Active), (2, Inactive)]),
+                ),
+                // empty sets
+                (vv(&[(2, Active)]), Values::default(), vv(&[(1, Active)])),
+                (Values::default(), Values::default(), vv(&[(3, Inactive)])),
+            ];
+            for (a, b, c) in cases {
+                let abc = a.overridden_by(&b).overridden_by(&c);
+                assert_eq!(abc, a.overridden_by(&b.overridden_by(&c)));
+                assert_eq!(abc.len(), a.len() + b.len() + c.len());
+                // disjoint, so the order is immaterial
+                assert_eq!(a.overridden_by(&b), b.overridden_by(&a));
+                assert_eq!(abc, c.overridden_by(&b).overridden_by(&a));
+            }
+        }
+
         #[test]
         fn from_offset_iterator() {
             let vv: Values = [1, 2, 3].iter().collect();
@@ -686,6 +1037,58 @@ mod tests {
             assert_eq!(vv.get(3), Some(Value::Active));
         }
 
+        #[test]
+        fn from_line_value_iterator_round_trip() {
+            let mut vv = Values::from_offsets(&[1, 2, 3, 5]);
+            vv.set(2, Value::Active).set(5, Value::Active);
+            let rt: Values = vv.iter().map(|lv| (lv.offset, lv.value)).collect();
+            assert_eq!(rt, vv);
+        }
+
+        #[test]
+        fn from_map() {
+            let mut map = HashMap::new();
+            map.insert(3, Value::Active);
+            map.insert(1, Value::Inactive);
+            map.insert(2, Value::Active);
+            let vv = Values::from_map(&map);
+            assert_eq!(vv.len(), 3);
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            assert_eq!(vv.get(2), Some(Value::Active));
+            assert_eq!(vv.get(3), Some(Value::Active));
+            // sorted by offset
+            assert!(vv.iter().map(|lv| lv.offset).eq([1, 2, 3]));
+
+            let map: crate::line::OffsetMap<Value> = [(7, Value::Active)].into_iter().collect();
+            assert_eq!(Values::from_map(&map).get(7), Some(Value::Active));
+        }
+
+        #[test]
+        fn set_all() {
+            let mut vv = Values::from_offsets(&[1, 2, 3]);
+            let mut other = Values::default();
+            other
+                .set(2, Value::Active)
+                .set(3, Value::Inactive)
+                .set(4, Value::Active);
+            vv.set(3, Value::Active);
+            vv.set_all(&other);
+            assert_eq!(vv.len(), 4);
+            // unchanged
+            assert_eq!(vv.get(1), Some(Value::Inactive));
+            // overwritten
+            assert_eq!(vv.get(2), Some(Value::Active));
+            assert_eq!(vv.get(3), Some(Value::Inactive));
+            // added
+            assert_eq!(vv.get(4), Some(Value::Active));
+            assert!(vv.iter().map(|lv| lv.offset).eq([1, 2, 3, 4]));
+
+            // empty other is a no-op
+            let before: Values = vv.iter().map(|lv| (lv.offset, lv.value)).collect();
+            vv.set_all(&Values::default());
+            assert_eq!(vv, before);
+        }
+
         #[test]
         fn len() {
             assert_eq!(Values::default().len(), 0);
@@ -0,0 +1,511 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::Offset;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+
+// The root of the procfs tree.
+const PROC_ROOT: &str = "/proc";
+
+// The link target of a line request file descriptor.
+const LINE_REQUEST_TARGET: &str = "anon_inode:gpio-line";
+
+/// The processes that may be holding a line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Holders {
+    /// True if the processes are known to hold the line.
+    ///
+    /// Otherwise the processes only have the chip open, and so may hold the line.
+    pub exact: bool,
+
+    /// The processes, in PID order.
+    pub processes: Vec<Holder>,
+}
+
+impl fmt::Display for Holders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let procs: Vec<String> = self
+            .processes
+            .iter()
+            .map(|h| match &h.name {
+                Some(name) => format!("{} ({})", h.pid, name),
+                None => h.pid.to_string(),
+            })
+            .collect();
+        let label = if procs.len() == 1 { "PID" } else { "PIDs" };
+        let prefix = if self.exact {
+            "held by"
+        } else {
+            "possibly held by"
+        };
+        write!(f, "{} {} {}", prefix, label, procs.join(", "))
+    }
+}
+
+/// A process that may be holding a line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Holder {
+    /// The process ID.
+    pub pid: u32,
+
+    /// The process name, if it could be read.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+}
+
+// Identifies the file a file descriptor refers to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum FileId {
+    // a character device, identified by its major/minor
+    Device(u64),
+    // any other file, identified by its filesystem and inode
+    Inode(u64, u64),
+}
+
+impl From<&Metadata> for FileId {
+    fn from(m: &Metadata) -> Self {
+        if m.file_type().is_char_device() {
+            FileId::Device(m.rdev())
+        } else {
+            FileId::Inode(m.dev(), m.ino())
+        }
+    }
+}
+
+// The GPIO related files a process has open.
+#[derive(Debug, Default)]
+struct Process {
+    pid: u32,
+    name: Option<String>,
+    // the files the process has open
+    files: HashSet<FileId>,
+    // the lines in line requests, as (chip name, offset), as reported in fdinfo
+    lines: HashSet<(String, Offset)>,
+}
+
+/// A snapshot of the GPIO chips and lines held open by the processes in the system.
+///
+/// The kernel only reports the consumer label for a requested line, not the
+/// process holding it, so `/proc` is scanned for the line requests and chips
+/// each process has open.
+///
+/// This is best-effort. The file descriptors of processes owned by other users
+/// are typically not visible unless run as root, and older kernels do not report
+/// the lines in a request, so the result may be incomplete.
+///
+/// The processes are scanned once, so finding the holders of many lines does
+/// not rescan them for each line.
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// let chip = gpiocdev::Chip::from_name("gpiochip0")?;
+/// let pt = gpiocdev::procfs::ProcessTable::scan();
+/// if let Some(holders) = pt.holders(chip.path(), &chip.name(), 3) {
+///     println!("line 3 is {}", holders);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ProcessTable {
+    procs: Vec<Process>,
+    // the entries that could not be read, other than processes that exited
+    errors: Vec<String>,
+}
+
+impl ProcessTable {
+    /// Scan `/proc` for the GPIO chips and lines each process has open.
+    ///
+    /// The process performing the scan is ignored.
+    pub fn scan() -> ProcessTable {
+        ProcessTable::scan_in(Path::new(PROC_ROOT))
+    }
+
+    /// The descriptions of any entries that could not be read during the scan.
+    ///
+    /// Processes that exited during the scan are not reported.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    // Scan a procfs style tree for the files each process has open.
+    fn scan_in(proc_root: &Path) -> ProcessTable {
+        let mut pt = ProcessTable::default();
+        let entries = match fs::read_dir(proc_root) {
+            Ok(entries) => entries,
+            Err(e) => {
+                pt.errors
+                    .push(format!("unable to read {}: {}", proc_root.display(), e));
+                return pt;
+            }
+        };
+        let this = std::process::id();
+        for entry in entries.flatten() {
+            let pid = match entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.parse::<u32>().ok())
+            {
+                Some(pid) => pid,
+                None => continue,
+            };
+            if pid == this || !entry.file_type().map_or(false, |ft| ft.is_dir()) {
+                continue;
+            }
+            if let Some(p) = pt.scan_process(&entry.path(), pid) {
+                pt.procs.push(p);
+            }
+        }
+        pt.procs.sort_unstable_by_key(|p| p.pid);
+        pt
+    }
+
+    fn scan_process(&mut self, dir: &Path, pid: u32) -> Option<Process> {
+        let fd_dir = dir.join("fd");
+        let fds = match fs::read_dir(&fd_dir) {
+            Ok(fds) => fds,
+            Err(e) => {
+                self.push_error(&fd_dir, e);
+                return None;
+            }
+        };
+        let mut p = Process {
+            pid,
+            ..Default::default()
+        };
+        for fd in fds.flatten() {
+            let target = match fs::read_link(fd.path()) {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+            if target.as_os_str() == LINE_REQUEST_TARGET {
+                let fdinfo = dir.join("fdinfo").join(fd.file_name());
+                match fs::read_to_string(&fdinfo) {
+                    Ok(info) => p.lines.extend(parse_fdinfo(&info)),
+                    Err(e) => self.push_error(&fdinfo, e),
+                }
+            } else if let Ok(m) = fs::metadata(fd.path()) {
+                p.files.insert(FileId::from(&m));
+            }
+        }
+        if p.files.is_empty() && p.lines.is_empty() {
+            return None;
+        }
+        p.name = fs::read_to_string(dir.join("comm"))
+            .ok()
+            .map(|n| n.trim_end().to_string());
+        Some(p)
+    }
+
+    fn push_error(&mut self, path: &Path, e: io::Error) {
+        // processes may exit while being scanned
+        if e.kind() != io::ErrorKind::NotFound {
+            self.errors
+                .push(format!("unable to read {}: {}", path.display(), e));
+        }
+    }
+
+    /// Find the processes holding a line.
+    ///
+    /// Prefers the processes with a request for the line, as reported by the kernel.
+    /// If no such process is found then falls back to the processes with the chip
+    /// open, as any one of them may have requested the line.
+    ///
+    /// Returns None if no process is found.
+    ///
+    /// * `chip_path` - The path to the chip containing the line.
+    /// * `chip_name` - The name of the chip, as reported in its info.
+    /// * `offset` - The offset of the line on the chip.
+    pub fn holders<P: AsRef<Path>>(
+        &self,
+        chip_path: P,
+        chip_name: &str,
+        offset: Offset,
+    ) -> Option<Holders> {
+        let key = (chip_name.to_string(), offset);
+        let processes = self.matching(|p| p.lines.contains(&key));
+        if !processes.is_empty() {
+            return Some(Holders {
+                exact: true,
+                processes,
+            });
+        }
+        let chip_id = FileId::from(&fs::metadata(chip_path.as_ref()).ok()?);
+        let processes = self.matching(|p| p.files.contains(&chip_id));
+        if processes.is_empty() {
+            return None;
+        }
+        Some(Holders {
+            exact: false,
+            processes,
+        })
+    }
+
+    fn matching<F: Fn(&Process) -> bool>(&self, f: F) -> Vec<Holder> {
+        self.procs
+            .iter()
+            .filter(|p| f(p))
+            .map(|p| Holder {
+                pid: p.pid,
+                name: p.name.clone(),
+            })
+            .collect()
+    }
+}
+
+// Parse the lines from the fdinfo of a line request.
+//
+// e.g.
+//     pos:    0
+//     flags:  02000002
+//     mnt_id: 16
+//     ino:    1063
+//     gpio-chip:  gpiochip0
+//     gpio-line:  3
+//     gpio-line:  7
+fn parse_fdinfo(info: &str) -> Vec<(String, Offset)> {
+    let mut chip = None;
+    let mut lines = Vec::new();
+    for l in info.lines() {
+        match l.split_once(':') {
+            Some(("gpio-chip", name)) => chip = Some(name.trim().to_string()),
+            Some(("gpio-line", offset)) => {
+                if let Ok(offset) = offset.trim().parse::<Offset>() {
+                    lines.push(offset);
+                }
+            }
+            _ => {}
+        }
+    }
+    match chip {
+        Some(chip) => lines.into_iter().map(|o| (chip.clone(), o)).collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    struct Tree {
+        root: PathBuf,
+    }
+
+    impl Tree {
+        fn new(name: &str) -> Tree {
+            let root = std::env::temp_dir().join(format!(
+                "gpiocdev-procfs-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("proc")).unwrap();
+            Tree { root }
+        }
+
+        fn proc_root(&self) -> PathBuf {
+            self.root.join("proc")
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            let path = self.root.join(name);
+            fs::write(&path, "").unwrap();
+            path
+        }
+
+        fn process(&self, pid: &str, comm: Option<&str>) -> PathBuf {
+            let dir = self.proc_root().join(pid);
+            fs::create_dir_all(dir.join("fd")).unwrap();
+            fs::create_dir_all(dir.join("fdinfo")).unwrap();
+            if let Some(comm) = comm {
+                fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+            }
+            dir
+        }
+
+        fn add_fd(&self, pid: &str, fd: u32, target: &Path) {
+            let dir = self.proc_root().join(pid);
+            symlink(target, dir.join("fd").join(fd.to_string())).unwrap();
+        }
+
+        fn add_request(&self, pid: &str, fd: u32, info: &str) {
+            let dir = self.proc_root().join(pid);
+            symlink(LINE_REQUEST_TARGET, dir.join("fd").join(fd.to_string())).unwrap();
+            fs::write(dir.join("fdinfo").join(fd.to_string()), info).unwrap();
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn holder(pid: u32, name: Option<&str>) -> Holder {
+        Holder {
+            pid,
+            name: name.map(str::to_string),
+        }
+    }
+
+    fn exact(processes: Vec<Holder>) -> Option<Holders> {
+        Some(Holders {
+            exact: true,
+            processes,
+        })
+    }
+
+    fn possible(processes: Vec<Holder>) -> Option<Holders> {
+        Some(Holders {
+            exact: false,
+            processes,
+        })
+    }
+
+    #[test]
+    fn fdinfo() {
+        assert_eq!(
+            parse_fdinfo(
+                "pos:\t0\nflags:\t02000002\ngpio-chip:\tgpiochip1\ngpio-line:\t3\ngpio-line:\t7\n"
+            ),
+            vec![("gpiochip1".to_string(), 3), ("gpiochip1".to_string(), 7)]
+        );
+        // older kernels do not report the lines
+        assert!(parse_fdinfo("pos:\t0\nflags:\t02000002\n").is_empty());
+        // lines without a chip
+        assert!(parse_fdinfo("gpio-line:\t3\n").is_empty());
+        // unparsable lines are ignored
+        assert_eq!(
+            parse_fdinfo("gpio-chip:\tgpiochip0\ngpio-line:\tbad\ngpio-line:\t4\n"),
+            vec![("gpiochip0".to_string(), 4)]
+        );
+    }
+
+    #[test]
+    fn holders_from_requests() {
+        let t = Tree::new("requests");
+        let chip = t.file("gpiochip0");
+        t.process("10", Some("blinky"));
+        t.add_fd("10", 3, &chip);
+        t.add_request(
+            "10",
+            4,
+            "gpio-chip:\tgpiochip0\ngpio-line:\t3\ngpio-line:\t5\n",
+        );
+        t.process("20", Some("other"));
+        t.add_fd("20", 3, &chip);
+        t.add_request("20", 5, "gpio-chip:\tgpiochip0\ngpio-line:\t4\n");
+        // same offset on a different chip
+        t.process("30", None);
+        t.add_request("30", 6, "gpio-chip:\tgpiochip1\ngpio-line:\t3\n");
+
+        let pt = ProcessTable::scan_in(&t.proc_root());
+        assert!(pt.errors.is_empty());
+        assert_eq!(
+            pt.holders(&chip, "gpiochip0", 3),
+            exact(vec![holder(10, Some("blinky"))])
+        );
+        assert_eq!(
+            pt.holders(&chip, "gpiochip0", 4),
+            exact(vec![holder(20, Some("other"))])
+        );
+        assert_eq!(
+            pt.holders(&chip, "gpiochip1", 3),
+            exact(vec![holder(30, None)])
+        );
+        // no request for the line, so fallback to processes with the chip open
+        assert_eq!(
+            pt.holders(&chip, "gpiochip0", 6),
+            possible(vec![holder(10, Some("blinky")), holder(20, Some("other"))])
+        );
+    }
+
+    #[test]
+    fn holders_from_open_chip() {
+        let t = Tree::new("open-chip");
+        let chip = t.file("gpiochip0");
+        let other = t.file("gpiochip1");
+        t.process("1", Some("init"));
+        t.add_fd("1", 3, &chip);
+        t.add_fd("1", 4, &other);
+        // holding only a different chip
+        t.process("20", Some("twenty"));
+        t.add_fd("20", 0, &other);
+        // holding the chip more than once
+        t.process("300", Some("three hundred"));
+        t.add_fd("300", 5, &chip);
+        t.add_fd("300", 6, &chip);
+        // not a pid
+        t.process("self", Some("self"));
+        t.add_fd("self", 3, &chip);
+        // dangling fd link
+        t.process("4000", None);
+        t.add_fd("4000", 7, &t.root.join("missing"));
+        // no fd directory
+        fs::create_dir_all(t.proc_root().join("50000")).unwrap();
+        // not a directory
+        fs::write(t.proc_root().join("600000"), "").unwrap();
+
+        let pt = ProcessTable::scan_in(&t.proc_root());
+        assert_eq!(
+            pt.holders(&chip, "gpiochip0", 1),
+            possible(vec![
+                holder(1, Some("init")),
+                holder(300, Some("three hundred"))
+            ])
+        );
+        assert_eq!(
+            pt.holders(&other, "gpiochip1", 1),
+            possible(vec![holder(1, Some("init")), holder(20, Some("twenty"))])
+        );
+        assert_eq!(pt.holders(t.root.join("missing"), "gpiochip2", 1), None);
+        // the missing fd directories are not reported as errors
+        assert!(pt.errors.is_empty());
+    }
+
+    #[test]
+    fn unreadable_root() {
+        let t = Tree::new("unreadable");
+        let pt = ProcessTable::scan_in(&t.root.join("missing"));
+        assert!(pt.procs.is_empty());
+        assert_eq!(pt.errors.len(), 1);
+        assert!(pt.errors[0].starts_with("unable to read "));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            exact(vec![holder(1234, Some("blinky"))])
+                .unwrap()
+                .to_string(),
+            "held by PID 1234 (blinky)"
+        );
+        assert_eq!(
+            possible(vec![holder(1234, Some("blinky")), holder(5678, None)])
+                .unwrap()
+                .to_string(),
+            "possibly held by PIDs 1234 (blinky), 5678"
+        );
+    }
+}
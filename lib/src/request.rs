@@ -6,27 +6,38 @@ mod builder;
 pub use self::builder::Builder;
 
 mod config;
-pub use self::config::Config;
+pub use self::config::{Config, ConfigChange, ConfigDelta, Conflict, LineDelta};
 
 mod edge_event_buffer;
 pub use self::edge_event_buffer::EdgeEventBuffer;
 
-use crate::line::{self, EdgeEvent, Offset, Value, Values};
-#[cfg(feature = "uapi_v1")]
-use crate::AbiVersion;
-use crate::{Error, Result, UapiCall};
+mod group;
+pub use self::group::{GroupBuilder, NamedValues, RequestGroup};
+
+mod watcher;
+pub use self::watcher::ValueWatcher;
+
+use crate::chip;
+use crate::line::{self, Direction, EdgeEvent, Offset, Value, Values};
+use crate::{AbiVersion, Error, Result, UapiCall};
 #[cfg(not(feature = "uapi_v2"))]
 use gpiocdev_uapi::v1 as uapi;
 #[cfg(feature = "uapi_v1")]
 use gpiocdev_uapi::v1;
 #[cfg(feature = "uapi_v2")]
 use gpiocdev_uapi::{v2, v2 as uapi};
+use std::fmt;
 use std::fs::File;
 use std::mem;
-use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd};
-use std::sync::{Arc, RwLock};
+use std::os::unix::prelude::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
+/// The maximum number of events the kernel will buffer for a request.
+///
+/// This is the most events that can be returned by a single read.
+pub const KERNEL_EVENT_BUFFER_SIZE_MAX: usize = gpiocdev_uapi::NUM_LINES_MAX * 16;
+
 /// An active request of a set of lines.
 ///
 /// Requests are built by the [`Builder`], which itself can be constructed by [`builder`](#method.builder).
@@ -56,6 +67,9 @@ use std::time::Duration;
 /// by reading multiple events from the kernel at once.  The [`edge_events`] iterator uses an
 /// EdgeEventBuffer, the size of which is controlled by [`with_user_event_buffer_size`]
 ///
+/// The [`read_edge_events_into`] method reads a batch of events from the kernel at once
+/// and appends them to a `Vec`, for when the events are to be processed in bulk.
+///
 /// It is also possible to read multiple events into a user specified location using
 /// [`read_edge_events_into_slice`].
 /// As with [`EdgeEventBuffer`] this may reduce read overheads when reading a burst of events
@@ -68,10 +82,52 @@ use std::time::Duration;
 /// and hardware support and so cannot be guaranteed to work, though frequently it does.
 /// Test with your particular hardware to be sure.
 ///
+/// The values read back are the levels sensed on the lines, not the values last set.
+/// So an open-drain line set active, or an open-source line set inactive, is not driven
+/// and reads back the level determined by any external pull.
+///
+/// # Event Loop Integration
+///
+/// The request implements [`AsFd`] and [`AsRawFd`], providing the file descriptor
+/// for the request, so it can be registered with an external event loop such as
+/// mio, calloop, or epoll.
+///
+/// The file descriptor is readable when an edge event is available to read,
+/// and should be registered for read readiness only.
+/// The events should be read using the request methods, such as [`read_edge_event`],
+/// not by reading the file descriptor directly.
+///
+/// The file descriptor is owned by the request and remains valid, and unchanged,
+/// for the lifetime of the request, including across calls to [`reconfigure`].
+/// Use [`try_clone_fd`] to obtain an independently owned duplicate, e.g. to register
+/// with an event loop running in another thread.
+///
+/// # Releasing Lines
+///
+/// The lines are released when the request is dropped.
+/// Dropping is infallible, so any error reported by the kernel when closing
+/// the request is discarded.
+/// Use [`release`] instead where the release should be checked, such as after
+/// setting the final output values in a daemon.
+///
+/// # Chip Removal
+///
+/// If the chip is removed while the lines are requested, such as when a USB GPIO
+/// expander is unplugged, the request remains valid but methods that access the
+/// lines fail with an [`Error::GpioChip`] of kind [`Removed`].
+/// The file descriptor becomes permanently readable, so an event loop should
+/// stop polling the request once that error is returned.
+///
 /// [`edge_events`]: #method.edge_events
 /// [`edge_event_size`]: #method.edge_event_size
+/// [`Error::GpioChip`]: crate::Error::GpioChip
+/// [`Removed`]: crate::chip::ErrorKind::Removed
 /// [`read_edge_event`]: #method.read_edge_event
+/// [`read_edge_events_into`]: #method.read_edge_events_into
 /// [`read_edge_events_into_slice`]: #method.read_edge_events_into_slice
+/// [`reconfigure`]: #method.reconfigure
+/// [`release`]: #method.release
+/// [`try_clone_fd`]: #method.try_clone_fd
 /// [`with_kernel_event_buffer_size`]: struct.Builder.html#method.with_kernel_event_buffer_size
 /// [`with_user_event_buffer_size`]: struct.Builder.html#method.with_user_event_buffer_size
 /// [`value`]: #method.value
@@ -93,6 +149,13 @@ pub struct Request {
     /// The ABI version used to create the request, and so determines how to decode events.
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     abiv: AbiVersion,
+
+    /// The buffer for raw events read by `read_edge_events_into`, reused between reads.
+    event_buf: Mutex<Vec<u64>>,
+
+    /// The clock detected as the source of v1 event timestamps, once an event has been read.
+    #[cfg(feature = "uapi_v1")]
+    detected_clock: Mutex<Option<line::EventClock>>,
 }
 
 impl Request {
@@ -142,6 +205,9 @@ impl Request {
     /// Keys that are not requested offsets are ignored.
     /// If no keys are set then all requested lines are returned.
     ///
+    /// With uAPI v2 only the lines indicated by the keys are read from the kernel.
+    /// With uAPI v1 all requested lines are read, but only the keyed values are updated.
+    ///
     /// # Examples
     /// ```no_run
     /// # fn example() -> Result<(), gpiocdev::Error> {
@@ -160,7 +226,12 @@ impl Request {
     /// # }
     /// ```
     pub fn values(&self, values: &mut Values) -> Result<()> {
-        self.do_values(values)
+        let res = self.do_values(values);
+        match &res {
+            Ok(_) => debug!("{}: get values {:?}", self.log_id(), values),
+            Err(e) => debug!("{}: get values failed: {}", self.log_id(), e),
+        }
+        res
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_values(&self, values: &mut Values) -> Result<()> {
@@ -182,14 +253,14 @@ impl Request {
         let mut vals = v1::LineValues::default();
         v1::get_line_values(&self.f, &mut vals)
             .map(|_| values.update_from_v1(&self.offsets, &vals))
-            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))
+            .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))
     }
     #[cfg(feature = "uapi_v2")]
     fn do_values_v2(&self, values: &mut Values) -> Result<()> {
         let mut vals = values.to_v2(&self.offsets);
         v2::get_line_values(&self.f, &mut vals)
             .map(|_| values.update_from_v2(&self.offsets, &vals))
-            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))
+            .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))
     }
 
     /// Get the value for one line in the request.
@@ -212,7 +283,12 @@ impl Request {
             .iter()
             .position(|v| v == &offset)
             .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".into()))?;
-        self.do_value(idx)
+        let res = self.do_value(idx);
+        match &res {
+            Ok(value) => debug!("{}: get value {} {:?}", self.log_id(), offset, value),
+            Err(e) => debug!("{}: get value {} failed: {}", self.log_id(), offset, e),
+        }
+        res
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_value(&self, idx: usize) -> Result<Value> {
@@ -233,7 +309,7 @@ impl Request {
     fn do_value_v1(&self, idx: usize) -> Result<Value> {
         let mut vals = v1::LineValues::default();
         v1::get_line_values(&self.f, &mut vals)
-            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))?;
+            .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))?;
         Ok(vals.get(idx).into())
     }
     #[cfg(feature = "uapi_v2")]
@@ -243,7 +319,7 @@ impl Request {
             ..Default::default()
         };
         v2::get_line_values(&self.f, &mut vals)
-            .map_err(|e| Error::Uapi(UapiCall::GetLineValues, e))?;
+            .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))?;
         Ok(vals.get(idx).unwrap().into())
     }
 
@@ -275,6 +351,19 @@ impl Request {
     }
 
     /// Set the values for a subset of the requested lines.
+    ///
+    /// Only the requested lines contained in `values` are set.
+    /// Other requested lines are left unchanged.
+    /// Keys that are not requested offsets are ignored.
+    ///
+    /// If no keys are set then all requested lines are set inactive.
+    ///
+    /// The uAPI v1 can only set all requested lines at once, so when setting
+    /// a subset of the lines the current values of the remaining lines are read
+    /// and written back unchanged.
+    /// That read-modify-write is not atomic, so any concurrent change to those
+    /// lines by another thread may be lost.
+    ///
     /// # Examples
     /// ```no_run
     /// # fn example() -> Result<(), gpiocdev::Error> {
@@ -290,7 +379,23 @@ impl Request {
     /// # Ok(())
     /// # }
     pub fn set_values(&self, values: &Values) -> Result<()> {
-        self.do_set_values(values)
+        // no keys means all requested lines, inactive.
+        let all;
+        let values = if values.is_empty() {
+            all = Values::from_offsets(&self.offsets);
+            &all
+        } else {
+            values
+        };
+        let res = self.do_set_values(values);
+        match &res {
+            Ok(_) => {
+                self.update_values(values);
+                debug!("{}: set values {:?}", self.log_id(), values)
+            }
+            Err(e) => debug!("{}: set values {:?} failed: {}", self.log_id(), values, e),
+        }
+        res
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_set_values(&self, values: &Values) -> Result<()> {
@@ -309,14 +414,19 @@ impl Request {
     }
     #[cfg(feature = "uapi_v1")]
     fn do_set_values_v1(&self, values: &Values) -> Result<()> {
+        let mut vals = v1::LineValues::default();
         if !values.contains_keys(&self.offsets) {
-            return Err(Error::AbiLimitation(
-                AbiVersion::V1,
-                "requires all requested lines".into(),
-            ));
+            if !self.offsets.iter().any(|o| values.get(*o).is_some()) {
+                return Err(Error::InvalidArgument(
+                    "no requested lines in set values.".into(),
+                ));
+            }
+            // fill the gaps with the current values
+            v1::get_line_values(&self.f, &mut vals)
+                .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))?;
         }
-        v1::set_line_values(&self.f, &values.to_v1(&self.offsets))
-            .map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
+        values.overlay_v1(&self.offsets, &mut vals);
+        v1::set_line_values(&self.f, &vals).map_err(|e| self.uapi_error(UapiCall::SetLineValues, e))
     }
     #[cfg(feature = "uapi_v2")]
     fn do_set_values_v2(&self, values: &Values) -> Result<()> {
@@ -326,11 +436,16 @@ impl Request {
                 "no requested lines in set values.".into(),
             ));
         }
-        v2::set_line_values(&self.f, lv).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
+        v2::set_line_values(&self.f, lv).map_err(|e| self.uapi_error(UapiCall::SetLineValues, e))
     }
 
     /// Set the value for one line in the request.
     ///
+    /// The other requested lines are left unchanged.
+    ///
+    /// As with [`set_values`], for multi-line requests on uAPI v1 this requires
+    /// a non-atomic read-modify-write of all the requested lines.
+    ///
     /// # Examples
     /// ```no_run
     /// # fn example() -> Result<(), gpiocdev::Error> {
@@ -343,13 +458,30 @@ impl Request {
     /// req.set_value(5,Value::Inactive)?;
     /// # Ok(())
     /// # }
+    /// ```
+    ///
+    /// [`set_values`]: method.set_values
     pub fn set_value(&self, offset: Offset, value: Value) -> Result<()> {
         let idx = self
             .offsets
             .iter()
             .position(|v| v == &offset)
             .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".into()))?;
-        self.do_set_value(idx, value)
+        let res = self.do_set_value(idx, value);
+        match &res {
+            Ok(_) => {
+                self.update_values(Values::default().set(offset, value));
+                debug!("{}: set value {} {:?}", self.log_id(), offset, value)
+            }
+            Err(e) => debug!(
+                "{}: set value {} {:?} failed: {}",
+                self.log_id(),
+                offset,
+                value,
+                e
+            ),
+        }
+        res
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_set_value(&self, idx: usize, value: Value) -> Result<()> {
@@ -368,15 +500,14 @@ impl Request {
     }
     #[cfg(feature = "uapi_v1")]
     fn do_set_value_v1(&self, idx: usize, value: Value) -> Result<()> {
+        let mut vals = v1::LineValues::default();
         if self.offsets.len() > 1 {
-            return Err(Error::AbiLimitation(
-                AbiVersion::V1,
-                "requires all requested lines".into(),
-            ));
+            // fill the other lines with their current values
+            v1::get_line_values(&self.f, &mut vals)
+                .map_err(|e| self.uapi_error(UapiCall::GetLineValues, e))?;
         }
-        let mut vals = v1::LineValues::default();
         vals.set(idx, value.into());
-        v1::set_line_values(&self.f, &vals).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
+        v1::set_line_values(&self.f, &vals).map_err(|e| self.uapi_error(UapiCall::SetLineValues, e))
     }
     #[cfg(feature = "uapi_v2")]
     fn do_set_value_v2(&self, idx: usize, value: Value) -> Result<()> {
@@ -386,7 +517,7 @@ impl Request {
             vals.bits |= mask;
         }
         vals.mask |= mask;
-        v2::set_line_values(&self.f, &vals).map_err(|e| Error::Uapi(UapiCall::SetLineValues, e))
+        v2::set_line_values(&self.f, &vals).map_err(|e| self.uapi_error(UapiCall::SetLineValues, e))
     }
 
     /// Set the value for the lone line in the request.
@@ -414,7 +545,125 @@ impl Request {
         if self.offsets.len() != 1 {
             return Err(Error::InvalidArgument("request contains multiple lines.".into()))?;
         }
-        self.do_set_value(0, value)
+        self.do_set_value(0, value)?;
+        self.update_values(Values::default().set(self.offsets[0], value));
+        Ok(())
+    }
+
+    // Record the values set on the output lines in the config.
+    fn update_values(&self, values: &Values) {
+        self.cfg
+            .write()
+            .expect("failed to acquire write lock on config")
+            .update_values(values);
+    }
+
+    // The value last set on an output line, if known.
+    fn output_value(&self, offset: Offset) -> Option<Value> {
+        self.cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .line_config(offset)
+            .filter(|lc| lc.direction == Some(Direction::Output))
+            .and_then(|lc| lc.value)
+    }
+
+    /// Toggle the values of all the lines in the request.
+    ///
+    /// The values toggled are those last set on the output lines, not the values
+    /// read back, as for open-drain and open-source lines the values read back are
+    /// the levels sensed, which may differ from those set.
+    /// Lines without a known output value, such as those requested as-is, are read
+    /// back, so this is a read-modify-write and is not atomic.
+    ///
+    /// Intended for output lines.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use gpiocdev::line::Value;
+    /// let req = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[5,6])
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// // lines 5 and 6 are now inactive
+    /// req.toggle_values()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn toggle_values(&self) -> Result<()> {
+        let set: Values = self
+            .offsets
+            .iter()
+            .filter_map(|o| self.output_value(*o).map(|v| (*o, v)))
+            .collect();
+        let mut values = Values::default();
+        if set.len() < self.offsets.len() {
+            // lines without a known output value are read back
+            self.values(&mut values)?;
+        }
+        values.set_all(&set);
+        self.set_values(values.not())
+    }
+
+    /// Toggle the value of one line in the request.
+    ///
+    /// The other requested lines are left unchanged.
+    ///
+    /// As with [`toggle_values`], the value toggled is the value last set on the line,
+    /// if known, otherwise the value read back.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use gpiocdev::line::Value;
+    /// let req = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[5,6])
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// // line 5 is now inactive, line 6 remains active
+    /// req.toggle_line(5)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`toggle_values`]: #method.toggle_values
+    pub fn toggle_line(&self, offset: Offset) -> Result<()> {
+        let value = match self.output_value(offset) {
+            Some(value) => value,
+            None => self.value(offset)?,
+        };
+        self.set_value(offset, value.not())
+    }
+
+    /// Release the requested lines, returning any error reported by the kernel.
+    ///
+    /// This is equivalent to dropping the request, which silently discards any
+    /// error, but allows the caller to check that the release succeeded.
+    ///
+    /// The lines are only released once any duplicates of the file descriptor,
+    /// such as those created by [`try_clone_fd`], are also closed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use gpiocdev::line::Value;
+    /// let req = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(3)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// req.set_lone_value(Value::Inactive)?;
+    /// req.release()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`try_clone_fd`]: #method.try_clone_fd
+    pub fn release(self) -> Result<()> {
+        gpiocdev_uapi::close(self.f).map_err(|e| Error::Uapi(UapiCall::Close, e))
     }
 
     /// Return the path of the chip for this request.
@@ -426,9 +675,45 @@ impl Request {
             .clone()
     }
 
+    // The ABI version used by the request, as detected if not specified.
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    pub(crate) fn abi_version(&self) -> AbiVersion {
+        self.abiv
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    pub(crate) fn abi_version(&self) -> AbiVersion {
+        AbiVersion::V1
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    pub(crate) fn abi_version(&self) -> AbiVersion {
+        AbiVersion::V2
+    }
+
+    // Identifies the request in log records.
+    fn log_id(&self) -> String {
+        format!("lines {:?} on {:?}", self.offsets, self.chip_path())
+    }
+
+    // Map an error from a uAPI call on the request.
+    //
+    // The kernel returns ENODEV once the chip has been removed, such as when
+    // a USB GPIO expander is unplugged.
+    fn uapi_error(&self, call: UapiCall, e: gpiocdev_uapi::Error) -> Error {
+        match e {
+            gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::ENODEV)) => self.removed_error(),
+            e => Error::Uapi(call, e),
+        }
+    }
+
+    // The error returned once the chip has been removed.
+    fn removed_error(&self) -> Error {
+        Error::GpioChip(self.chip_path(), chip::ErrorKind::Removed)
+    }
+
     /// Get a snapshot of the requested configuration.
     ///
-    /// This is the configuration currently applied to the hardware.
+    /// This is the configuration currently applied to the hardware, including
+    /// the values last set on the output lines.
     pub fn config(&self) -> Config {
         self.cfg
             .read()
@@ -452,13 +737,22 @@ impl Request {
     /// Note that lines cannot be added or removed from the request.
     /// Any additional lines in `new_cfg` will be ignored, and any missing
     /// lines will retain their existing configuration.
+    ///
+    /// Edge detection may be changed with uAPI v2, but not with v1.
+    /// Edge events buffered in the kernel before the reconfigure are retained
+    /// and may still be read from the request.
     pub fn reconfigure(&self, new_cfg: &Config) -> Result<()> {
         let cfg = self
             .cfg
             .read()
             .expect("failed to acquire read lock on config")
             .overlay(new_cfg);
-        self.do_reconfigure(&cfg)?;
+        cfg.validate()?;
+        if let Err(e) = self.do_reconfigure(&cfg) {
+            debug!("{}: reconfigure failed: {}", self.log_id(), e);
+            return Err(e);
+        }
+        debug!("{}: reconfigured {:?}", self.log_id(), cfg.line_configs());
         // only update request config if reconfigure succeeds.
         self.cfg
             .write()
@@ -466,6 +760,82 @@ impl Request {
             .update(cfg);
         Ok(())
     }
+
+    /// Switch the direction of one line in the request.
+    ///
+    /// The other lines in the request are unchanged, as are the settings of the
+    /// line that apply to both directions, such as bias and active low.
+    /// Settings specific to the previous direction are removed.
+    ///
+    /// A line switched to output is driven inactive.  To switch to output with
+    /// a particular value use [`reconfigure`] with [`Config::as_output`].
+    ///
+    /// The line is not reconfigured if it already has the requested direction.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// use gpiocdev::line::{Direction, Value};
+    ///
+    /// let req = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(5)
+    ///     .as_output(Value::Active)
+    ///     .request()?;
+    /// req.set_direction(5, Direction::Input)?;
+    /// let value = req.value(5)?;
+    /// req.set_direction(5, Direction::Output)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`reconfigure`]: #method.reconfigure
+    pub fn set_direction(&self, offset: Offset, direction: Direction) -> Result<()> {
+        let mut lc = self
+            .line_config(offset)
+            .ok_or_else(|| Error::InvalidArgument("offset is not a requested line.".into()))?;
+        if lc.direction == Some(direction) {
+            return Ok(());
+        }
+        match direction {
+            Direction::Input => lc.as_input(),
+            Direction::Output => lc.as_output(Value::Inactive),
+        };
+        let mut cfg = Config::default();
+        cfg.with_line(offset).from_line_config(&lc);
+        self.reconfigure(&cfg)
+    }
+
+    /// Reconfigure the request with an updated configuration, if it differs
+    /// from the current configuration.
+    ///
+    /// Unlike [`reconfigure`], `new_cfg` must describe all the lines in the request,
+    /// as lines cannot be added or removed from the request.
+    ///
+    /// The reconfigure is skipped if the configuration is unchanged, avoiding any
+    /// glitches the driver may produce when lines are reconfigured.
+    ///
+    /// Returns true if the request was reconfigured.
+    ///
+    /// [`reconfigure`]: #method.reconfigure
+    pub fn reconfigure_if_changed(&self, new_cfg: &Config) -> Result<bool> {
+        let delta = self
+            .cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .diff(new_cfg);
+        if delta.is_membership_change() {
+            return Err(Error::InvalidArgument(
+                "cannot add or remove lines from a request".into(),
+            ));
+        }
+        if delta.is_empty() {
+            return Ok(false);
+        }
+        self.reconfigure(new_cfg)?;
+        Ok(true)
+    }
+
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_reconfigure(&self, cfg: &Config) -> Result<()> {
         match self.abiv {
@@ -490,10 +860,10 @@ impl Request {
                     ));
                 }
                 v1::set_line_config(&self.f, cfg.to_v1()?)
-                    .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
+                    .map_err(|e| self.uapi_error(UapiCall::SetLineConfig, e))
             }
             AbiVersion::V2 => v2::set_line_config(&self.f, cfg.to_v2()?)
-                .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e)),
+                .map_err(|e| self.uapi_error(UapiCall::SetLineConfig, e)),
         }
     }
     #[cfg(not(feature = "uapi_v2"))]
@@ -518,12 +888,12 @@ impl Request {
             ));
         }
         v1::set_line_config(&self.f, cfg.to_v1()?)
-            .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
+            .map_err(|e| self.uapi_error(UapiCall::SetLineConfig, e))
     }
     #[cfg(not(feature = "uapi_v1"))]
     fn do_reconfigure(&self, cfg: &Config) -> Result<()> {
         v2::set_line_config(&self.f, cfg.to_v2()?)
-            .map_err(|e| Error::Uapi(UapiCall::SetLineConfig, e))
+            .map_err(|e| self.uapi_error(UapiCall::SetLineConfig, e))
     }
 
     /// An iterator for events from the request.
@@ -531,7 +901,11 @@ impl Request {
     /// By default the events are read from the kernel individually.
     ///
     /// The iterator can be backed by a user space buffer using the
-    /// [`Builder.with_user_event_buffer_size`] option in the builder.
+    /// [`Builder.with_user_event_buffer_size`] option in the builder,
+    /// or by creating the buffer directly using [`new_edge_event_buffer`].
+    ///
+    /// The iterator blocks until an event is available, and only ends if the end of file
+    /// is reached reading the request.
     ///
     /// # Examples
     /// ```no_run
@@ -553,7 +927,8 @@ impl Request {
     /// ```
     ///
     /// [`Builder.with_user_event_buffer_size`]: struct.Builder.html#method.with_user_event_buffer_size
-    pub fn edge_events(&self) -> EdgeEventBuffer {
+    /// [`new_edge_event_buffer`]: #method.new_edge_event_buffer
+    pub fn edge_events(&self) -> EdgeEventBuffer<'_> {
         self.new_edge_event_buffer(self.user_event_buffer_size)
     }
 
@@ -561,7 +936,7 @@ impl Request {
     ///
     /// [`read_edge_event`]: #method.read_edge_event
     pub fn has_edge_event(&self) -> Result<bool> {
-        gpiocdev_uapi::has_event(&self.f).map_err(|e| Error::Uapi(UapiCall::HasEvent, e))
+        gpiocdev_uapi::has_event(&self.f).map_err(|e| self.uapi_error(UapiCall::HasEvent, e))
     }
 
     /// Wait for an edge event to be available.
@@ -570,7 +945,8 @@ impl Request {
     ///
     /// [`read_edge_event`]: #method.read_edge_event
     pub fn wait_edge_event(&self, timeout: Duration) -> Result<bool> {
-        gpiocdev_uapi::wait_event(&self.f, timeout).map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))
+        gpiocdev_uapi::wait_event(&self.f, timeout)
+            .map_err(|e| self.uapi_error(UapiCall::WaitEvent, e))
     }
 
     /// Read a single edge event from the request.
@@ -593,19 +969,105 @@ impl Request {
         // and dynamically sliced down to the required size, if necessary
         let buf = &mut bbuf[0..self.edge_event_u64_size()];
         let n = self.read_edge_events_into_slice(buf)?;
+        if n == 0 {
+            return Err(self.removed_error());
+        }
         self.do_edge_event_from_slice(&buf[0..n])
     }
     #[cfg(not(all(feature = "uapi_v1", feature = "uapi_v2")))]
     fn do_read_edge_event(&self) -> Result<EdgeEvent> {
         let mut buf = [0; mem::size_of::<uapi::LineEdgeEvent>()];
         let n = self.read_edge_events_into_slice(&mut buf)?;
+        if n == 0 {
+            return Err(self.removed_error());
+        }
         self.do_edge_event_from_slice(&buf[0..n])
     }
 
+    /// Read a batch of edge events from the request.
+    ///
+    /// Reads up to `max` events from the kernel with a single read and appends
+    /// them to `buf`.
+    /// This reduces the overheads of reading a burst of events compared to
+    /// reading them individually with [`read_edge_event`].
+    ///
+    /// The raw events are read into a buffer held by the request, which is
+    /// reused by subsequent calls, and decoded from there.
+    ///
+    /// Will block until an edge event is available.
+    ///
+    /// Returns the number of events appended to `buf`.
+    ///
+    /// * `buf` - The vector to append the events to.
+    /// * `max` - The maximum number of events to read.
+    ///   This is limited to the size of the kernel event buffer, so larger
+    ///   values are clamped to [`KERNEL_EVENT_BUFFER_SIZE_MAX`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use gpiocdev::Request;
+    /// # use gpiocdev::line::EdgeDetection;
+    /// let req = Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_line(23)
+    ///     .with_edge_detection(EdgeDetection::BothEdges)
+    ///     .request()?;
+    ///
+    /// let mut events = Vec::new();
+    /// loop {
+    ///     events.clear();
+    ///     req.read_edge_events_into(&mut events, 64)?;
+    ///     for event in &events {
+    ///         println!("{:?}", event);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`read_edge_event`]: #method.read_edge_event
+    pub fn read_edge_events_into(&self, buf: &mut Vec<EdgeEvent>, max: usize) -> Result<usize> {
+        if max == 0 {
+            return Ok(0);
+        }
+        let event_u64_size = self.edge_event_u64_size();
+        let len = max
+            .min(KERNEL_EVENT_BUFFER_SIZE_MAX)
+            .checked_mul(event_u64_size)
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("{} events exceeds the addressable size.", max))
+            })?;
+        let mut raw = self
+            .event_buf
+            .lock()
+            .expect("failed to acquire lock on event buffer");
+        if raw.len() < len {
+            raw.resize(len, 0);
+        }
+        let n = self.read_edge_events_into_slice(&mut raw[..len])?;
+        if n == 0 {
+            return Err(self.removed_error());
+        }
+        // The kernel only returns whole events, so a partial event means the
+        // read has been truncated.
+        if n % event_u64_size != 0 {
+            return Err(Error::UnexpectedResponse(format!(
+                "read a partial edge event - {} bytes read for {} byte events.",
+                n * 8,
+                event_u64_size * 8
+            )));
+        }
+        buf.reserve(n / event_u64_size);
+        for evt in raw[..n].chunks_exact(event_u64_size) {
+            buf.push(self.edge_event_from_slice(evt)?);
+        }
+        Ok(n / event_u64_size)
+    }
+
     /// Create an edge event buffer.
     ///
     /// * `capacity` - The number of events that can be buffered.
-    pub fn new_edge_event_buffer(&self, capacity: usize) -> EdgeEventBuffer {
+    pub fn new_edge_event_buffer(&self, capacity: usize) -> EdgeEventBuffer<'_> {
         EdgeEventBuffer::new(self, self.edge_event_size(), capacity)
     }
 
@@ -630,7 +1092,7 @@ impl Request {
     ///
     /// [`edge_event_size`]: #method.edge_event_size
     pub fn read_edge_events_into_slice(&self, buf: &mut [u64]) -> Result<usize> {
-        gpiocdev_uapi::read_event(&self.f, buf).map_err(|e| Error::Uapi(UapiCall::ReadEvent, e))
+        gpiocdev_uapi::read_event(&self.f, buf).map_err(|e| self.uapi_error(UapiCall::ReadEvent, e))
     }
 
     /// Read an edge event from a `[u64]` slice.
@@ -646,40 +1108,98 @@ impl Request {
     ///
     /// [`read_edge_events_into_slice`]: #method.read_edge_events_into_slice
     pub fn edge_event_from_slice(&self, buf: &[u64]) -> Result<EdgeEvent> {
-        self.do_edge_event_from_slice(buf)
+        let res = self.do_edge_event_from_slice(buf);
+        if let Ok(ee) = &res {
+            trace!("{}: edge event {:?}", self.log_id(), ee);
+        }
+        res
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_edge_event_from_slice(&self, buf: &[u64]) -> Result<EdgeEvent> {
-        Ok(match self.abiv {
+        let ee = match self.abiv {
             AbiVersion::V1 => {
                 let mut ee = EdgeEvent::from(
                     v1::LineEdgeEvent::from_slice(buf)
                         .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
                 );
-                // populate offset for v1
+                // populate offset and clock for v1
                 ee.offset = self.offsets[0];
+                ee.event_clock = self.v1_event_clock(ee.timestamp_ns);
                 ee
             }
-            AbiVersion::V2 => EdgeEvent::from(
-                uapi::LineEdgeEvent::from_slice(buf)
-                    .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
-            ),
-        })
+            AbiVersion::V2 => {
+                let mut ee = EdgeEvent::from(
+                    uapi::LineEdgeEvent::from_slice(buf)
+                        .map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
+                );
+                ee.event_clock = self.event_clock(ee.offset);
+                ee
+            }
+        };
+        Ok(ee)
     }
     #[cfg(not(feature = "uapi_v2"))]
     fn do_edge_event_from_slice(&self, buf: &[u64]) -> Result<EdgeEvent> {
         let mut ee = EdgeEvent::from(
             v1::LineEdgeEvent::from_slice(buf).map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
         );
-        // populate offset for v1
+        // populate offset and clock for v1
         ee.offset = self.offsets[0]; // there can be only one
+        ee.event_clock = self.v1_event_clock(ee.timestamp_ns);
         Ok(ee)
     }
     #[cfg(not(feature = "uapi_v1"))]
     fn do_edge_event_from_slice(&self, buf: &[u64]) -> Result<EdgeEvent> {
-        Ok(EdgeEvent::from(
+        let mut ee = EdgeEvent::from(
             v2::LineEdgeEvent::from_slice(buf).map_err(|e| Error::Uapi(UapiCall::LEEFromBuf, e))?,
-        ))
+        );
+        ee.event_clock = self.event_clock(ee.offset);
+        Ok(ee)
+    }
+
+    /// The clock detected as the source of edge event timestamps.
+    ///
+    /// uAPI v1 cannot select the event clock, and the clock used depends on the
+    /// kernel - **CLOCK_REALTIME** prior to Linux 5.7 and **CLOCK_MONOTONIC** since.
+    /// For v1 requests the clock is detected from the first edge event read,
+    /// and is used as the [`EdgeEvent::event_clock`] for all events read from the request.
+    ///
+    /// Returns None for v2 requests, which use the configured event clock,
+    /// or if no events have been read.
+    pub fn detected_event_clock(&self) -> Option<line::EventClock> {
+        #[cfg(feature = "uapi_v1")]
+        return *self
+            .detected_clock
+            .lock()
+            .expect("failed to acquire lock on detected clock");
+        #[cfg(not(feature = "uapi_v1"))]
+        None
+    }
+
+    // The clock used for v1 event timestamps, detected from the first event.
+    #[cfg(feature = "uapi_v1")]
+    fn v1_event_clock(&self, timestamp_ns: u64) -> line::EventClock {
+        let mut clock = self
+            .detected_clock
+            .lock()
+            .expect("failed to acquire lock on detected clock");
+        *clock.get_or_insert_with(|| {
+            let clock = line::EventClock::from(v1::detect_event_clock(timestamp_ns));
+            debug!("{}: detected v1 event clock {:?}", self.log_id(), clock);
+            clock
+        })
+    }
+
+    // The event clock configured for the line, which determines the source
+    // of the timestamps of edge events on that line.
+    #[cfg(feature = "uapi_v2")]
+    fn event_clock(&self, offset: Offset) -> line::EventClock {
+        self.cfg
+            .read()
+            .expect("failed to acquire read lock on config")
+            .line_config(offset)
+            .and_then(|lc| lc.event_clock)
+            .unwrap_or_default()
     }
 
     /// The number of u64s required to buffer a single event read from the request.
@@ -710,6 +1230,72 @@ impl Request {
     fn do_edge_event_size(&self) -> usize {
         mem::size_of::<uapi::LineEdgeEvent>()
     }
+
+    /// Watch the values of the requested lines by periodically polling them.
+    ///
+    /// This is intended for lines that do not support edge detection, such as
+    /// lines on some GPIO expanders. For lines that do, edge detection is
+    /// preferable, as it does not miss changes occurring between polls.
+    ///
+    /// The values are read when the watch starts, then every `poll_interval`,
+    /// and the `callback` is called with the offset and value of each line
+    /// whose value has changed since the previous poll.
+    ///
+    /// The callback is called from a background thread that continues until
+    /// the returned [`ValueWatcher`] is stopped or dropped.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn example() -> Result<(), gpiocdev::Error> {
+    /// # use std::time::Duration;
+    /// let req = gpiocdev::Request::builder()
+    ///     .on_chip("/dev/gpiochip0")
+    ///     .with_lines(&[3,5])
+    ///     .request()?;
+    /// let watcher = req.watch_values(Duration::from_millis(50), |offset, value| {
+    ///     println!("line {} is now {}", offset, value);
+    /// })?;
+    /// // ...
+    /// watcher.stop()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch_values<F>(&self, poll_interval: Duration, callback: F) -> Result<ValueWatcher>
+    where
+        F: FnMut(Offset, Value) + Send + 'static,
+    {
+        let req = self.try_clone()?;
+        let mut values = Values::from_offsets(&self.offsets);
+        req.values(&mut values)?;
+        Ok(ValueWatcher::new(req, values, poll_interval, callback))
+    }
+
+    /// Duplicate the file descriptor for the request.
+    ///
+    /// The returned file descriptor refers to the same request, and so becomes readable
+    /// when edge events are available, but is owned by the caller and may outlive the
+    /// request.  Closing it does not release the lines.
+    ///
+    /// This is useful for registering the request with an event loop in one thread
+    /// while reading events in another.
+    pub fn try_clone_fd(&self) -> Result<OwnedFd> {
+        Ok(self.f.as_fd().try_clone_to_owned()?)
+    }
+
+    // A duplicate of the request that refers to the same lines.
+    fn try_clone(&self) -> Result<Request> {
+        Ok(Request {
+            f: self.f.try_clone()?,
+            offsets: self.offsets.clone(),
+            cfg: self.cfg.clone(),
+            user_event_buffer_size: self.user_event_buffer_size,
+            event_buf: Default::default(),
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            abiv: self.abiv,
+            #[cfg(feature = "uapi_v1")]
+            detected_clock: Mutex::new(self.detected_event_clock()),
+        })
+    }
 }
 
 impl AsFd for Request {
@@ -733,6 +1319,47 @@ impl AsRef<Request> for Request {
     }
 }
 
+/// Reasons a request for lines can be refused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The line is already requested by another requester.
+    ///
+    /// The current consumer of the line can be found in the line [`Info`](line::Info).
+    LineBusy(Offset),
+
+    /// The offset is beyond the lines provided by the chip.
+    OffsetOutOfRange {
+        /// The requested offset.
+        offset: Offset,
+        /// The number of lines provided by the chip.
+        num_lines: u32,
+    },
+
+    /// The configuration of a line contains conflicting settings.
+    InvalidConfig {
+        /// The offset of the line.
+        offset: Offset,
+        /// The settings in conflict.
+        conflict: Conflict,
+    },
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::LineBusy(offset) => write!(f, "line {} is busy", offset),
+            ErrorKind::OffsetOutOfRange { offset, num_lines } => write!(
+                f,
+                "offset {} is out of range - the chip has {} lines",
+                offset, num_lines
+            ),
+            ErrorKind::InvalidConfig { offset, conflict } => {
+                write!(f, "line {} has a conflicting config - {}", offset, conflict)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Request;
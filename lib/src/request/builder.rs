@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 use crate::chip::Chip;
 use crate::line::{self, Bias, Direction, Drive, EdgeDetection, EventClock, Offset, Value, Values};
-use crate::request::{Config, Request};
+use crate::request::{Config, ErrorKind, Request};
 #[cfg(feature = "uapi_v1")]
 use crate::AbiVersion;
 use crate::{Error, Result, UapiCall};
@@ -11,7 +11,7 @@ use crate::{Error, Result, UapiCall};
 use gpiocdev_uapi::v1;
 #[cfg(feature = "uapi_v2")]
 use gpiocdev_uapi::v2;
-use gpiocdev_uapi::NUM_LINES_MAX;
+use gpiocdev_uapi::{self as uapi, NUM_LINES_MAX};
 use std::cmp::max;
 use std::collections::HashMap;
 use std::fs::File;
@@ -106,6 +106,9 @@ pub struct Builder {
     pub(super) consumer: String,
     pub(super) kernel_event_buffer_size: u32,
     pub(super) user_event_buffer_size: usize,
+    retry_count: u32,
+    retry_delay: Duration,
+    skip_validation: bool,
     err: Option<Error>,
     /// The ABI version used to create the request, and so determines how to decode events.
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
@@ -126,16 +129,94 @@ impl Builder {
     /// Sends the request to the kernel using the appropriate uAPI call.
     ///
     /// On success returns the [`Request`] that provides access to the requested lines.
+    ///
+    /// The kernel limits a request to at most [`NUM_LINES_MAX`] lines.
+    /// To request more lines from a chip, use a [`GroupBuilder`] with
+    /// [`allow_splitting`] set.
+    ///
+    /// Offsets beyond the lines provided by the chip are rejected with
+    /// [`ErrorKind::OffsetOutOfRange`] before any request is made to the kernel.
+    ///
+    /// [`NUM_LINES_MAX`]: gpiocdev_uapi::NUM_LINES_MAX
+    /// [`GroupBuilder`]: struct.GroupBuilder.html
+    /// [`allow_splitting`]: struct.GroupBuilder.html#method.allow_splitting
     pub fn request(&mut self) -> Result<Request> {
+        let res = self.try_request();
+        match &res {
+            Ok(req) => debug!(
+                "requested lines {:?} on {:?} using {:?}, consumer {:?}, config {:?}",
+                self.cfg.offsets,
+                self.cfg.chip,
+                req.abi_version(),
+                self.consumer,
+                self.cfg.line_configs()
+            ),
+            Err(e) => debug!(
+                "request for lines {:?} on {:?} failed: {}",
+                self.cfg.offsets, self.cfg.chip, e
+            ),
+        }
+        res
+    }
+
+    fn try_request(&mut self) -> Result<Request> {
         if let Some(e) = &self.err {
             return Err(e.clone());
         }
         if self.cfg.chip.as_os_str().is_empty() {
             return Err(Error::InvalidArgument("No chip specified.".into()));
         }
+        if !self.skip_validation {
+            self.cfg.validate()?;
+        }
         let chip = Chip::from_path(&self.cfg.chip)?;
+        self.check_offsets(&chip)?;
         self.cfg.offsets.sort_unstable();
-        self.do_request(&chip).map(|f| self.to_request(f))
+        let mut retries = self.retry_count;
+        loop {
+            match self.do_request(&chip) {
+                Ok(f) => return Ok(self.to_request(f)),
+                Err(Error::Uapi(_, uapi::Error::Os(uapi::Errno(libc::EBUSY)))) if retries > 0 => {
+                    retries -= 1;
+                    std::thread::sleep(self.retry_delay);
+                }
+                Err(e) => return Err(self.identify_busy_line(&chip, e)),
+            }
+        }
+    }
+
+    // The kernel only returns EINVAL for an offset beyond the lines provided by
+    // the chip, so check the offsets up front to identify the offending line.
+    fn check_offsets(&self, chip: &Chip) -> Result<()> {
+        let num_lines = chip.num_lines() as u32;
+        match self.cfg.offsets.iter().find(|&&offset| offset >= num_lines) {
+            Some(&offset) => Err(Error::Request(
+                chip.path().to_owned(),
+                ErrorKind::OffsetOutOfRange { offset, num_lines },
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // The kernel does not identify which line is busy, so check the
+    // requested lines to find one that is in use.
+    fn identify_busy_line(&self, chip: &Chip, e: Error) -> Error {
+        match e {
+            Error::Uapi(_, uapi::Error::Os(uapi::Errno(libc::EBUSY))) => {
+                match self
+                    .cfg
+                    .offsets
+                    .iter()
+                    .find(|&&offset| chip.line_info(offset).map_or(false, |li| li.used))
+                {
+                    Some(&offset) => {
+                        Error::Request(chip.path().to_owned(), ErrorKind::LineBusy(offset))
+                    }
+                    None => e,
+                }
+            }
+            _ => e,
+        }
     }
     #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
     fn do_request(&mut self, chip: &Chip) -> Result<File> {
@@ -178,8 +259,11 @@ impl Builder {
             offsets: self.cfg.offsets.clone(),
             cfg: Arc::new(RwLock::new(self.cfg.clone())),
             user_event_buffer_size: max(self.user_event_buffer_size, 1),
+            event_buf: Default::default(),
             #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
             abiv: self.abiv.unwrap(),
+            #[cfg(feature = "uapi_v1")]
+            detected_clock: Default::default(),
         }
     }
 
@@ -241,11 +325,54 @@ impl Builder {
     /// Buffering is provided in the kernel to reduce the likelihood of event loss when
     /// user space is slow servicing events.
     /// In all cases the events are provided to user space as quickly as user space allows.
+    ///
+    /// When the buffer overflows the oldest events are discarded, which may be
+    /// detected using [`EdgeEvent::lost_since`].
+    ///
+    /// This is only supported by uAPI v2.
+    ///
+    /// [`EdgeEvent::lost_since`]: crate::line::EdgeEvent::lost_since
     pub fn with_kernel_event_buffer_size(&mut self, event_buffer_size: u32) -> &mut Self {
         self.kernel_event_buffer_size = event_buffer_size;
         self
     }
 
+    /// Retry the request if it fails because a line is busy.
+    ///
+    /// The request is retried up to `count` times, waiting `delay` before each retry.
+    ///
+    /// This is intended for lines that may be held briefly by another consumer,
+    /// such as a driver that is about to release the line during boot.
+    ///
+    /// Only failures due to a line being busy are retried.
+    /// All other errors are returned immediately.
+    ///
+    /// By default requests are not retried.
+    pub fn with_retry(&mut self, count: u32, delay: Duration) -> &mut Self {
+        self.retry_count = count;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Set whether the configuration is validated before the request is made.
+    ///
+    /// Validation rejects line configurations with conflicting settings, such as
+    /// open-drain drive on an input, identifying the line and the conflict,
+    /// rather than leaving the kernel to reject the request with a bare EINVAL.
+    /// Refer to [`Config::validate`] for the rules applied.
+    ///
+    /// Disabling validation passes the configuration through to the kernel unchecked.
+    /// It does not affect [`Request::reconfigure`], which always validates.
+    ///
+    /// By default the configuration is validated.
+    ///
+    /// [`Config::validate`]: struct.Config.html#method.validate
+    /// [`Request::reconfigure`]: struct.Request.html#method.reconfigure
+    pub fn with_config_validation(&mut self, validate: bool) -> &mut Self {
+        self.skip_validation = !validate;
+        self
+    }
+
     /// Set the event buffer size for edge events buffered in user space.
     ///
     /// This method is only required in unusual circumstances.
@@ -331,6 +458,12 @@ impl Builder {
     }
 
     /// Do not set the direction of the selected lines.
+    ///
+    /// The lines retain the direction they had prior to the request,
+    /// so an existing output continues to drive its current value.
+    ///
+    /// As with any output, reading the value of an output line requested as-is
+    /// returns the level sensed on the line, not the value being driven.
     pub fn as_is(&mut self) -> &mut Self {
         self.cfg.as_is();
         self
@@ -388,13 +521,25 @@ impl Builder {
 
     /// Set the edge detection for the selected lines.
     ///
-    /// Implicitly sets the lines as inputs and removes any output specific settings.
+    /// Implicitly sets the lines as inputs and removes any output specific settings,
+    /// as the kernel only supports edge detection on inputs, so there is no need to
+    /// also call [`as_input`].
+    ///
+    /// [`as_input`]: #method.as_input
     pub fn with_edge_detection<E: Into<Option<EdgeDetection>>>(&mut self, edge: E) -> &mut Self {
         self.cfg.with_edge_detection(edge);
         self
     }
 
     /// Set the clock source for edge events on the selected lines.
+    ///
+    /// Selecting the clock source is not supported by uAPI ABI v1, so the request
+    /// will fail with an [`AbiLimitation`] if the clock is set and ABI v1 is used.
+    ///
+    /// The [`EventClock::Hte`] source requires kernel and hardware support, so the
+    /// request may fail if that is not available.
+    ///
+    /// [`AbiLimitation`]: crate::Error::AbiLimitation
     pub fn with_event_clock<E: Into<Option<EventClock>>>(&mut self, event_clock: E) -> &mut Self {
         self.cfg.with_event_clock(event_clock);
         self
@@ -510,7 +655,8 @@ impl Builder {
         }
         if self.cfg.offsets.len() > NUM_LINES_MAX {
             return Err(Error::InvalidArgument(format!(
-                "Requested {} lines is greater than the maximum of {}.",
+                "Requested {} lines is greater than the maximum of {} per request. \
+                Use a RequestGroup that allows splitting to request more lines.",
                 self.cfg.offsets.len(),
                 NUM_LINES_MAX,
             )));
@@ -658,6 +804,74 @@ mod tests {
         assert_eq!(res.err().unwrap().to_string(), "No chip specified.");
     }
 
+    #[test]
+    fn request_conflicting_config() {
+        let conflicts = [
+            (
+                line::Config {
+                    bias: Some(Bias::PullUp),
+                    ..Default::default()
+                },
+                "bias requires the direction to be set",
+            ),
+            (
+                line::Config {
+                    direction: Some(Direction::Output),
+                    edge_detection: Some(EdgeDetection::BothEdges),
+                    ..Default::default()
+                },
+                "edge detection requires the line to be an input",
+            ),
+            (
+                line::Config {
+                    direction: Some(Direction::Input),
+                    drive: Some(Drive::OpenDrain),
+                    ..Default::default()
+                },
+                "open-drain and open-source drive require the line to be an output",
+            ),
+            (
+                line::Config {
+                    drive: Some(Drive::OpenSource),
+                    ..Default::default()
+                },
+                "open-drain and open-source drive require the line to be an output",
+            ),
+        ];
+        for (lc, conflict) in conflicts {
+            let mut b = Builder::default();
+            b.on_chip("/dev/gpiochip_nonexistent")
+                .with_line(2)
+                .from_line_config(&lc);
+            // rejected before the chip is opened
+            assert_eq!(
+                b.request().err().unwrap().to_string(),
+                format!(
+                    "\"/dev/gpiochip_nonexistent\" line 2 has a conflicting config - {}.",
+                    conflict
+                )
+            );
+            // passed through to the chip
+            b.with_config_validation(false);
+            assert_eq!(
+                b.request().err().unwrap().to_string(),
+                "No such file or directory (os error 2)"
+            );
+        }
+    }
+
+    #[test]
+    fn with_config_validation() {
+        let mut b = Builder::default();
+        assert!(!b.skip_validation);
+
+        b.with_config_validation(false);
+        assert!(b.skip_validation);
+
+        b.with_config_validation(true);
+        assert!(!b.skip_validation);
+    }
+
     #[test]
     fn request_multiple_chips() {
         let res = Builder::default()
@@ -698,6 +912,17 @@ mod tests {
         assert_eq!(b.consumer.as_str(), "builder test");
     }
 
+    #[test]
+    fn with_retry() {
+        let mut b = Builder::default();
+        assert_eq!(b.retry_count, 0);
+        assert_eq!(b.retry_delay, Duration::ZERO);
+
+        b.with_retry(3, Duration::from_millis(20));
+        assert_eq!(b.retry_count, 3);
+        assert_eq!(b.retry_delay, Duration::from_millis(20));
+    }
+
     #[test]
     fn with_kernel_event_buffer_size() {
         let mut b = Builder::default();
@@ -1028,6 +1253,14 @@ mod tests {
         let res = Builder::default().to_uapi();
         assert!(res.is_err());
         assert_eq!(res.err().unwrap().to_string(), "No lines specified.");
+
+        let offsets: Vec<Offset> = (0..65).collect();
+        let res = Builder::default().with_lines(&offsets).to_uapi();
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Requested 65 lines is greater than the maximum of 64 per request. \
+            Use a RequestGroup that allows splitting to request more lines."
+        );
     }
 
     #[test]
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use super::ErrorKind;
 use crate::line::{
     self, Bias, Direction, Drive, EdgeDetection, EventClock, Offset, OffsetMap, Offsets, Value,
     Values,
@@ -12,6 +13,7 @@ use gpiocdev_uapi::v1;
 #[cfg(feature = "uapi_v2")]
 use gpiocdev_uapi::v2;
 use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -75,6 +77,19 @@ impl Config {
         self.lcfg = cfg.lcfg;
     }
 
+    /// Update the values of the output lines to those that have been set.
+    ///
+    /// Values for lines that are not outputs are ignored.
+    pub(super) fn update_values(&mut self, values: &Values) {
+        for lv in values.iter() {
+            if let Some(lc) = self.lcfg.get_mut(&lv.offset) {
+                if lc.direction == Some(Direction::Output) {
+                    lc.value = Some(lv.value);
+                }
+            }
+        }
+    }
+
     /// Set the chip from which to request lines.
     ///
     /// This applies to all lines in the request. It is not possible to request lines
@@ -108,6 +123,10 @@ impl Config {
     }
 
     /// Do not set the direction of the selected lines.
+    ///
+    /// The lines retain the direction they had prior to the request.
+    /// As with any output, reading the value of an output line requested as-is
+    /// returns the level sensed on the line, not the value being driven.
     pub fn as_is(&mut self) -> &mut Self {
         for cfg in self.selected_iter() {
             cfg.as_is();
@@ -218,6 +237,14 @@ impl Config {
     }
 
     /// Set the clock source for edge events on the selected lines.
+    ///
+    /// Selecting the clock source is not supported by uAPI ABI v1, so the request
+    /// will fail with an [`AbiLimitation`] if the clock is set and ABI v1 is used.
+    ///
+    /// The [`EventClock::Hte`] source requires kernel and hardware support, so the
+    /// request may fail if that is not available.
+    ///
+    /// [`AbiLimitation`]: crate::Error::AbiLimitation
     pub fn with_event_clock<E: Into<Option<EventClock>>>(&mut self, event_clock: E) -> &mut Self {
         let event_clock = event_clock.into();
         for cfg in self.selected_iter() {
@@ -359,7 +386,7 @@ impl Config {
         self
     }
 
-    fn selected_iter(&mut self) -> SelectedIterator {
+    fn selected_iter(&mut self) -> SelectedIterator<'_> {
         SelectedIterator {
             cfg: self,
             index: 0,
@@ -388,6 +415,90 @@ impl Config {
         self.lcfg.len()
     }
 
+    /// Determine the changes required to convert this config into `other`.
+    ///
+    /// Only the configuration of the lines is compared, not the chip nor the
+    /// base configuration.
+    pub fn diff(&self, other: &Config) -> ConfigDelta {
+        let mut delta = ConfigDelta::default();
+        for offset in &self.offsets {
+            let from = self.lcfg.get(offset).unwrap();
+            match other.lcfg.get(offset) {
+                Some(to) => {
+                    if from != to {
+                        let ld = LineDelta {
+                            offset: *offset,
+                            from: from.clone(),
+                            to: to.clone(),
+                        };
+                        if ld.is_flags_change() || ld.is_value_change() {
+                            delta.changed.push(ld);
+                        }
+                    }
+                }
+                None => delta.removed.push(*offset),
+            }
+        }
+        for offset in &other.offsets {
+            if !self.lcfg.contains_key(offset) {
+                delta.added.push(*offset);
+            }
+        }
+        delta
+    }
+
+    /// Check the configuration of each line is self-consistent.
+    ///
+    /// Returns an error identifying the first line with a conflict that the
+    /// kernel would reject, such as edge detection on an output.
+    ///
+    /// The rules mirror those applied by the kernel for uAPI v2, which are as
+    /// strict as those for v1.
+    /// Conflicts the kernel tolerates are reported by [`warnings`] instead.
+    ///
+    /// This is called by [`Builder.request`] and [`Request.reconfigure`] before
+    /// the configuration is passed to the kernel.
+    ///
+    /// [`warnings`]: #method.warnings
+    /// [`Builder.request`]: struct.Builder.html#method.request
+    /// [`Request.reconfigure`]: struct.Request.html#method.reconfigure
+    pub fn validate(&self) -> Result<()> {
+        match self.conflicts().find(|(_, c)| c.is_error()) {
+            Some((offset, conflict)) => Err(Error::Request(
+                self.chip.clone(),
+                ErrorKind::InvalidConfig { offset, conflict },
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The settings that the kernel will ignore for each line.
+    ///
+    /// These conflicts do not prevent a request, but the settings have no effect,
+    /// such as a debounce period on an output.
+    ///
+    /// Lines are in the order they were added.
+    pub fn warnings(&self) -> Vec<(Offset, Conflict)> {
+        self.conflicts().filter(|(_, c)| !c.is_error()).collect()
+    }
+
+    // The conflicts within each line config.
+    fn conflicts(&self) -> impl Iterator<Item = (Offset, Conflict)> + '_ {
+        self.offsets.iter().flat_map(move |offset| {
+            Conflict::find(self.lcfg.get(offset).unwrap())
+                .into_iter()
+                .map(move |c| (*offset, c))
+        })
+    }
+
+    /// The configs of the lines, in the order the lines were added.
+    pub(crate) fn line_configs(&self) -> Vec<(Offset, &line::Config)> {
+        self.offsets
+            .iter()
+            .filter_map(|offset| self.lcfg.get(offset).map(|lc| (*offset, lc)))
+            .collect()
+    }
+
     /// Returns the config that applies to all lines, or an error if the lines have
     /// distinct configurations.
     #[cfg(feature = "uapi_v1")]
@@ -557,6 +668,230 @@ impl Config {
     }
 }
 
+/// The changes between two [`Config`]s, as returned by [`Config::diff`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigDelta {
+    /// The lines present in the new config but not the old.
+    pub added: Vec<Offset>,
+
+    /// The lines present in the old config but not the new.
+    pub removed: Vec<Offset>,
+
+    /// The lines present in both configs with differing configurations,
+    /// in the order the lines were added to the old config.
+    pub changed: Vec<LineDelta>,
+}
+
+impl ConfigDelta {
+    /// Returns true if the configs are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns true if lines have been added or removed.
+    ///
+    /// Changes to line membership cannot be applied to an existing request.
+    pub fn is_membership_change(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty()
+    }
+
+    /// The lines with changes to flags, i.e. configuration other than output value.
+    pub fn flags_changes(&self) -> impl Iterator<Item = &LineDelta> {
+        self.changed.iter().filter(|ld| ld.is_flags_change())
+    }
+
+    /// The lines with changes to output value.
+    pub fn value_changes(&self) -> impl Iterator<Item = &LineDelta> {
+        self.changed.iter().filter(|ld| ld.is_value_change())
+    }
+}
+
+/// The change to the configuration of a line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LineDelta {
+    /// The offset of the line.
+    pub offset: Offset,
+
+    /// The old configuration of the line.
+    pub from: line::Config,
+
+    /// The new configuration of the line.
+    pub to: line::Config,
+}
+
+impl LineDelta {
+    /// Returns true if the configuration, other than the output value, has changed.
+    pub fn is_flags_change(&self) -> bool {
+        !self.from.equivalent(&self.to)
+    }
+
+    /// Returns true if the effective output value has changed.
+    pub fn is_value_change(&self) -> bool {
+        self.from.value() != self.to.value()
+    }
+
+    /// The individual attributes that have changed, in line config field order.
+    pub fn changes(&self) -> Vec<ConfigChange> {
+        let (from, to) = (&self.from, &self.to);
+        let mut changes = Vec::new();
+        if from.direction != to.direction {
+            changes.push(ConfigChange::Direction(from.direction, to.direction));
+        }
+        if from.active_low != to.active_low {
+            changes.push(ConfigChange::ActiveLow(from.active_low, to.active_low));
+        }
+        if from.bias != to.bias {
+            changes.push(ConfigChange::Bias(from.bias, to.bias));
+        }
+        if from.drive != to.drive {
+            changes.push(ConfigChange::Drive(from.drive, to.drive));
+        }
+        if from.edge_detection != to.edge_detection {
+            changes.push(ConfigChange::EdgeDetection(
+                from.edge_detection,
+                to.edge_detection,
+            ));
+        }
+        if from.event_clock != to.event_clock {
+            changes.push(ConfigChange::EventClock(from.event_clock, to.event_clock));
+        }
+        if from.debounce_period != to.debounce_period {
+            changes.push(ConfigChange::DebouncePeriod(
+                from.debounce_period,
+                to.debounce_period,
+            ));
+        }
+        if self.is_value_change() {
+            changes.push(ConfigChange::Value(from.value(), to.value()));
+        }
+        changes
+    }
+}
+
+/// A change to a single attribute of a line configuration, as returned by [`LineDelta::changes`].
+///
+/// Each variant contains the old and new settings, in that order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigChange {
+    /// The direction setting has changed.
+    Direction(Option<Direction>, Option<Direction>),
+
+    /// The active low setting has changed.
+    ActiveLow(bool, bool),
+
+    /// The bias setting has changed.
+    Bias(Option<Bias>, Option<Bias>),
+
+    /// The drive setting has changed.
+    Drive(Option<Drive>, Option<Drive>),
+
+    /// The edge detection setting has changed.
+    EdgeDetection(Option<EdgeDetection>, Option<EdgeDetection>),
+
+    /// The event clock setting has changed.
+    EventClock(Option<EventClock>, Option<EventClock>),
+
+    /// The debounce period has changed.
+    DebouncePeriod(Option<Duration>, Option<Duration>),
+
+    /// The effective output value has changed.
+    Value(Value, Value),
+}
+
+/// A conflict between settings in a line configuration, as returned by
+/// [`Config::validate`] and [`Config::warnings`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// Bias is set but the direction is not.
+    ///
+    /// This is rejected by the kernel.
+    BiasWithoutDirection,
+
+    /// Edge detection is set but the line is not an input.
+    ///
+    /// This is rejected by the kernel.
+    EdgeDetectionWithoutInput,
+
+    /// Open-drain or open-source drive is set but the line is not an output.
+    ///
+    /// This is rejected by the kernel.
+    DriveWithoutOutput,
+
+    /// A debounce period is set but the line is not an input.
+    ///
+    /// This is ignored by the kernel.
+    DebounceWithoutInput,
+
+    /// An event clock is set but edge detection is not.
+    ///
+    /// This is ignored by the kernel.
+    EventClockWithoutEdgeDetection,
+
+    /// A value is set but the line is not an output.
+    ///
+    /// This is ignored by the kernel.
+    ValueWithoutOutput,
+}
+
+impl Conflict {
+    /// Returns true if the kernel rejects the conflict, rather than ignoring it.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Conflict::BiasWithoutDirection
+                | Conflict::EdgeDetectionWithoutInput
+                | Conflict::DriveWithoutOutput
+        )
+    }
+
+    // The conflicts within a line config.
+    fn find(lc: &line::Config) -> Vec<Conflict> {
+        let input = lc.direction == Some(Direction::Input);
+        let output = lc.direction == Some(Direction::Output);
+        let mut conflicts = Vec::new();
+        if lc.bias.is_some() && lc.direction.is_none() {
+            conflicts.push(Conflict::BiasWithoutDirection);
+        }
+        if lc.edge_detection.is_some() && !input {
+            conflicts.push(Conflict::EdgeDetectionWithoutInput);
+        }
+        // push-pull has no flag, so is not passed to the kernel
+        if matches!(lc.drive, Some(Drive::OpenDrain) | Some(Drive::OpenSource)) && !output {
+            conflicts.push(Conflict::DriveWithoutOutput);
+        }
+        if lc.debounce_period.is_some() && !input {
+            conflicts.push(Conflict::DebounceWithoutInput);
+        }
+        if lc.event_clock.is_some() && lc.edge_detection.is_none() {
+            conflicts.push(Conflict::EventClockWithoutEdgeDetection);
+        }
+        if lc.value.is_some() && !output {
+            conflicts.push(Conflict::ValueWithoutOutput);
+        }
+        conflicts
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Conflict::BiasWithoutDirection => "bias requires the direction to be set",
+            Conflict::EdgeDetectionWithoutInput => {
+                "edge detection requires the line to be an input"
+            }
+            Conflict::DriveWithoutOutput => {
+                "open-drain and open-source drive require the line to be an output"
+            }
+            Conflict::DebounceWithoutInput => "debounce is ignored unless the line is an input",
+            Conflict::EventClockWithoutEdgeDetection => {
+                "event clock is ignored without edge detection"
+            }
+            Conflict::ValueWithoutOutput => "value is ignored unless the line is an output",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 /// An iterator over the currently selected lines in a Config.
 // This is strictly internal as external usage could invalidate the safety contract.
 struct SelectedIterator<'a> {
@@ -1032,6 +1367,27 @@ mod tests {
         assert_eq!(cfg.lcfg.get(&5).unwrap().value, Some(Value::Inactive));
     }
 
+    #[test]
+    fn update_values() {
+        let mut cfg = Config::default();
+        cfg.with_line(1)
+            .as_input()
+            .with_lines(&[2, 3])
+            .as_output(Inactive)
+            .with_line(4)
+            .as_is();
+        let vv: Values = [(1, Active), (3, Active), (4, Active), (5, Active)]
+            .into_iter()
+            .collect();
+        cfg.update_values(&vv);
+        // only outputs are updated
+        assert_eq!(cfg.lcfg.get(&1).unwrap().value, None);
+        assert_eq!(cfg.lcfg.get(&2).unwrap().value, Some(Inactive));
+        assert_eq!(cfg.lcfg.get(&3).unwrap().value, Some(Active));
+        assert_eq!(cfg.lcfg.get(&4).unwrap().value, None);
+        assert!(!cfg.lcfg.contains_key(&5));
+    }
+
     #[test]
     fn with_value() {
         let mut cfg = Config::default();
@@ -1154,6 +1510,163 @@ mod tests {
         assert_eq!(cfg.unique().unwrap(), lc.unwrap());
     }
 
+    fn single_line(lc: line::Config) -> Config {
+        let mut cfg = Config::default();
+        cfg.on_chip("/dev/gpiochip0")
+            .with_line(3)
+            .from_line_config(&lc);
+        cfg
+    }
+
+    #[test]
+    fn validate() {
+        // mutators keep the config consistent
+        let mut cfg = Config::default();
+        cfg.on_chip("/dev/gpiochip0")
+            .with_lines(&[1, 2])
+            .with_edge_detection(BothEdges)
+            .with_event_clock(Realtime)
+            .with_debounce_period(Duration::from_millis(5))
+            .with_bias(PullUp)
+            .with_line(3)
+            .with_drive(OpenDrain)
+            .with_bias(PullDown)
+            .with_line(4)
+            .as_is()
+            .as_active_low();
+        assert!(cfg.validate().is_ok());
+        assert!(cfg.warnings().is_empty());
+
+        // the first line in conflict is identified
+        cfg.lcfg.get_mut(&4).unwrap().bias = Some(Disabled);
+        cfg.lcfg.get_mut(&3).unwrap().edge_detection = Some(RisingEdge);
+        assert_eq!(
+            cfg.validate().unwrap_err(),
+            Error::Request(
+                "/dev/gpiochip0".into(),
+                ErrorKind::InvalidConfig {
+                    offset: 3,
+                    conflict: Conflict::EdgeDetectionWithoutInput
+                }
+            )
+        );
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "\"/dev/gpiochip0\" line 3 has a conflicting config - edge detection requires the line to be an input."
+        );
+    }
+
+    #[test]
+    fn validate_bias() {
+        let mut lc = line::Config {
+            bias: Some(PullUp),
+            ..Default::default()
+        };
+        assert_eq!(
+            single_line(lc.clone()).validate().unwrap_err(),
+            Error::Request(
+                "/dev/gpiochip0".into(),
+                ErrorKind::InvalidConfig {
+                    offset: 3,
+                    conflict: Conflict::BiasWithoutDirection
+                }
+            )
+        );
+        lc.direction = Some(Input);
+        assert!(single_line(lc.clone()).validate().is_ok());
+        lc.direction = Some(Output);
+        assert!(single_line(lc).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_edge_detection() {
+        let mut lc = line::Config {
+            edge_detection: Some(FallingEdge),
+            ..Default::default()
+        };
+        let err = Error::Request(
+            "/dev/gpiochip0".into(),
+            ErrorKind::InvalidConfig {
+                offset: 3,
+                conflict: Conflict::EdgeDetectionWithoutInput,
+            },
+        );
+        assert_eq!(single_line(lc.clone()).validate().unwrap_err(), err);
+        lc.direction = Some(Output);
+        assert_eq!(single_line(lc.clone()).validate().unwrap_err(), err);
+        lc.direction = Some(Input);
+        assert!(single_line(lc).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_drive() {
+        for drive in [OpenDrain, OpenSource] {
+            let mut lc = line::Config {
+                drive: Some(drive),
+                ..Default::default()
+            };
+            let err = Error::Request(
+                "/dev/gpiochip0".into(),
+                ErrorKind::InvalidConfig {
+                    offset: 3,
+                    conflict: Conflict::DriveWithoutOutput,
+                },
+            );
+            assert_eq!(single_line(lc.clone()).validate().unwrap_err(), err);
+            lc.direction = Some(Input);
+            assert_eq!(single_line(lc.clone()).validate().unwrap_err(), err);
+            lc.direction = Some(Output);
+            assert!(single_line(lc).validate().is_ok());
+        }
+
+        // push-pull is not passed to the kernel
+        let lc = line::Config {
+            direction: Some(Input),
+            drive: Some(PushPull),
+            ..Default::default()
+        };
+        assert!(single_line(lc).validate().is_ok());
+    }
+
+    #[test]
+    fn warnings() {
+        let lc = line::Config {
+            direction: Some(Output),
+            debounce_period: Some(Duration::from_millis(10)),
+            event_clock: Some(Realtime),
+            ..Default::default()
+        };
+        let cfg = single_line(lc);
+        assert!(cfg.validate().is_ok());
+        assert_eq!(
+            cfg.warnings(),
+            [
+                (3, Conflict::DebounceWithoutInput),
+                (3, Conflict::EventClockWithoutEdgeDetection)
+            ]
+        );
+
+        let lc = line::Config {
+            direction: Some(Input),
+            debounce_period: Some(Duration::from_millis(10)),
+            value: Some(Active),
+            ..Default::default()
+        };
+        let cfg = single_line(lc);
+        assert!(cfg.validate().is_ok());
+        assert_eq!(cfg.warnings(), [(3, Conflict::ValueWithoutOutput)]);
+    }
+
+    #[test]
+    fn conflict_is_error() {
+        assert!(Conflict::BiasWithoutDirection.is_error());
+        assert!(Conflict::EdgeDetectionWithoutInput.is_error());
+        assert!(Conflict::DriveWithoutOutput.is_error());
+        assert!(!Conflict::DebounceWithoutInput.is_error());
+        assert!(!Conflict::EventClockWithoutEdgeDetection.is_error());
+        assert!(!Conflict::ValueWithoutOutput.is_error());
+    }
+
     #[test]
     fn overlay() {
         let mut bottom = Config::default();
@@ -1175,6 +1688,148 @@ mod tests {
         assert!(lc.active_low);
     }
 
+    mod diff {
+        use super::*;
+
+        #[test]
+        fn unchanged() {
+            let mut cfg = Config::default();
+            cfg.with_lines(&[1, 4])
+                .as_output(Value::Active)
+                .with_bias(Bias::PullUp);
+            let delta = cfg.diff(&cfg.clone());
+            assert!(delta.is_empty());
+            assert!(!delta.is_membership_change());
+            assert_eq!(delta, ConfigDelta::default());
+        }
+
+        #[test]
+        fn ignores_chip_and_selection() {
+            let mut from = Config::default();
+            from.on_chip("/dev/gpiochip0")
+                .with_lines(&[1, 4])
+                .as_input();
+            let mut to = from.clone();
+            to.on_chip("/dev/gpiochip1").with_line(1);
+            assert!(from.diff(&to).is_empty());
+        }
+
+        #[test]
+        fn membership() {
+            let mut from = Config::default();
+            from.with_lines(&[1, 4, 7]).as_input();
+            let mut to = Config::default();
+            to.with_lines(&[3, 4, 9]).as_input();
+            let delta = from.diff(&to);
+            assert!(!delta.is_empty());
+            assert!(delta.is_membership_change());
+            assert_eq!(delta.added, vec![3, 9]);
+            assert_eq!(delta.removed, vec![1, 7]);
+            assert!(delta.changed.is_empty());
+        }
+
+        #[test]
+        fn flags() {
+            let mut from = Config::default();
+            from.with_lines(&[1, 4])
+                .as_output(Value::Active)
+                .with_bias(Bias::PullUp);
+            let mut to = from.clone();
+            to.with_line(4).with_bias(Bias::PullDown);
+            let delta = from.diff(&to);
+            assert!(!delta.is_empty());
+            assert!(!delta.is_membership_change());
+            assert_eq!(delta.changed.len(), 1);
+            let ld = &delta.changed[0];
+            assert_eq!(ld.offset, 4);
+            assert_eq!(ld.from.bias, Some(Bias::PullUp));
+            assert_eq!(ld.to.bias, Some(Bias::PullDown));
+            assert!(ld.is_flags_change());
+            assert!(!ld.is_value_change());
+            assert_eq!(delta.flags_changes().count(), 1);
+            assert_eq!(delta.value_changes().count(), 0);
+            assert_eq!(
+                ld.changes(),
+                vec![ConfigChange::Bias(Some(Bias::PullUp), Some(Bias::PullDown))]
+            );
+        }
+
+        #[test]
+        fn values() {
+            let mut from = Config::default();
+            from.with_lines(&[1, 4]).as_output(Value::Active);
+            let mut to = from.clone();
+            to.with_line(1).with_value(Value::Inactive);
+            let delta = from.diff(&to);
+            assert!(!delta.is_empty());
+            assert!(!delta.is_membership_change());
+            assert_eq!(delta.changed.len(), 1);
+            let ld = &delta.changed[0];
+            assert_eq!(ld.offset, 1);
+            assert!(!ld.is_flags_change());
+            assert!(ld.is_value_change());
+            assert_eq!(delta.flags_changes().count(), 0);
+            assert_eq!(delta.value_changes().count(), 1);
+        }
+
+        #[test]
+        fn effective_values() {
+            // an unset output value is inactive
+            let mut from = Config::default();
+            from.with_line(1).as_output(Value::Inactive);
+            let mut to = Config::default();
+            to.with_line(1).with_direction(Direction::Output);
+            assert!(from.diff(&to).is_empty());
+        }
+
+        #[test]
+        fn flags_and_values() {
+            let mut from = Config::default();
+            from.with_lines(&[1, 4]).as_output(Value::Active);
+            let mut to = from.clone();
+            to.with_line(4).as_active_low().with_value(Value::Inactive);
+            let delta = from.diff(&to);
+            assert_eq!(delta.changed.len(), 1);
+            let ld = &delta.changed[0];
+            assert_eq!(ld.offset, 4);
+            assert!(ld.is_flags_change());
+            assert!(ld.is_value_change());
+            assert_eq!(
+                ld.changes(),
+                vec![
+                    ConfigChange::ActiveLow(false, true),
+                    ConfigChange::Value(Value::Active, Value::Inactive)
+                ]
+            );
+        }
+
+        #[test]
+        fn debounce() {
+            let mut from = Config::default();
+            from.with_lines(&[1, 4])
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .with_debounce_period(Duration::from_millis(5));
+            let mut to = from.clone();
+            to.with_line(1)
+                .with_debounce_period(Duration::from_millis(10));
+            assert_ne!(from, to);
+            let delta = from.diff(&to);
+            assert!(!delta.is_membership_change());
+            assert_eq!(delta.changed.len(), 1);
+            let ld = &delta.changed[0];
+            assert_eq!(ld.offset, 1);
+            assert!(ld.is_flags_change());
+            assert!(!ld.is_value_change());
+            assert_eq!(
+                ld.changes(),
+                vec![ConfigChange::DebouncePeriod(
+                    Some(Duration::from_millis(5)),
+                    Some(Duration::from_millis(10))
+                )]
+            );
+        }
+    }
+
     #[test]
     #[cfg(feature = "uapi_v1")]
     fn to_v1() {
@@ -2,8 +2,9 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use crate::chip;
 use crate::line::EdgeEvent;
-use crate::{Request, Result};
+use crate::{Error, Request, Result};
 use std::cmp::max;
 use std::time::Duration;
 
@@ -11,6 +12,9 @@ use std::time::Duration;
 ///
 /// Reads edge events from the kernel in bulk, where possible, while providing them
 /// serially to the caller.
+///
+/// The buffer is allocated once, when the buffer is created, so reading events,
+/// either directly or via the [`Iterator`], does not allocate.
 pub struct EdgeEventBuffer<'a> {
     req: &'a Request,
 
@@ -28,7 +32,7 @@ pub struct EdgeEventBuffer<'a> {
 }
 
 impl EdgeEventBuffer<'_> {
-    pub(super) fn new(req: &Request, event_size: usize, capacity: usize) -> EdgeEventBuffer {
+    pub(super) fn new(req: &Request, event_size: usize, capacity: usize) -> EdgeEventBuffer<'_> {
         debug_assert!(event_size % 8 == 0);
         let event_u64_size = event_size / 8;
         EdgeEventBuffer {
@@ -77,23 +81,42 @@ impl EdgeEventBuffer<'_> {
     /// [`has_event`]: #method.has_event
     /// [`wait_event`]: #method.wait_event
     pub fn read_event(&mut self) -> Result<EdgeEvent> {
-        if self.read < self.filled {
-            let evt_end = self.read + self.event_u64_size;
-            let evt = &self.buf[self.read..evt_end];
-            self.read = evt_end;
-            return self.req.edge_event_from_slice(evt);
+        if self.is_empty() && self.fill()? == 0 {
+            // end of file, which only occurs if the chip has been removed.
+            return Err(Error::GpioChip(
+                self.req.chip_path(),
+                chip::ErrorKind::Removed,
+            ));
         }
+        self.next_buffered()
+    }
+
+    // Refill the buffer from the request, returning the number of u64s read.
+    //
+    // Zero indicates the end of file has been reached.
+    fn fill(&mut self) -> Result<usize> {
         self.read = 0;
         self.filled = 0;
         let n = self.req.read_edge_events_into_slice(&mut self.buf)?;
-        // Could turn these into run-time errors, but they should never happen
-        // so make them asserts to keep it simple.
-        assert!(n > 0);
-        assert_eq!(n % (self.event_u64_size), 0);
+        // The kernel only returns whole events, so a partial event means the
+        // read has been truncated.
+        if n % self.event_u64_size != 0 {
+            return Err(Error::UnexpectedResponse(format!(
+                "read a partial edge event - {} bytes read for {} byte events.",
+                n * 8,
+                self.event_u64_size * 8
+            )));
+        }
         self.filled = n;
-        self.read = self.event_u64_size;
-        self.req
-            .edge_event_from_slice(&self.buf[0..self.event_u64_size])
+        Ok(n)
+    }
+
+    // Return the next event from the buffer, which must not be empty.
+    fn next_buffered(&mut self) -> Result<EdgeEvent> {
+        let evt_end = self.read + self.event_u64_size;
+        let evt = &self.buf[self.read..evt_end];
+        self.read = evt_end;
+        self.req.edge_event_from_slice(evt)
     }
 
     /// Wait for an edge event from the request.
@@ -105,11 +128,22 @@ impl EdgeEventBuffer<'_> {
     }
 }
 
+/// Iterates over the edge events from the request, blocking until events are available.
+///
+/// Reads interrupted by a signal are retried.
+/// The iteration only ends if the end of file is reached reading the request.
 impl Iterator for EdgeEventBuffer<'_> {
     type Item = Result<EdgeEvent>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        Some(self.read_event())
+        if self.is_empty() {
+            match self.fill() {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(self.next_buffered())
     }
 }
@@ -0,0 +1,926 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{
+    self, Bias, Direction, Drive, EdgeDetection, EdgeEvent, EventClock, Offset, Value, Values,
+};
+use crate::request::{Config, Request};
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+use crate::AbiVersion;
+use crate::{Error, FoundLine, Result, UapiCall};
+use gpiocdev_uapi as uapi;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A group of requests that together provide access to a set of lines
+/// that may span multiple chips.
+///
+/// The lines are identified by name, or by the identifier provided with
+/// a pre-resolved line, and the group issues a request for each combination
+/// of chip and consumer label.
+///
+/// With uAPI v1, where all the lines in a request must share the same configuration,
+/// lines are further split into separate requests by configuration.
+///
+/// If [`allow_splitting`] is set then lines on a chip beyond the kernel limit of
+/// lines per request are placed in additional requests.
+///
+/// Edge events from all the requests are merged, and returned in timestamp order.
+/// For the merged events to be coherent, the lines should use the same event clock.
+///
+/// Groups are built by the [`GroupBuilder`], which itself can be constructed
+/// by [`builder`](#method.builder).
+///
+/// [`allow_splitting`]: struct.GroupBuilder.html#method.allow_splitting
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// use gpiocdev::line::Value;
+/// use gpiocdev::request::RequestGroup;
+///
+/// let grp = RequestGroup::builder()
+///     .with_lines(&["LED0", "LED1"])
+///     .as_output(Value::Inactive)
+///     .with_line("BUTTON0")
+///     .as_input()
+///     .request()?;
+/// let value = grp.value("BUTTON0")?;
+/// grp.set_value("LED1", value)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct RequestGroup {
+    // The identifiers of the lines, in the order they were added.
+    ids: Vec<String>,
+
+    // Map from line identifier to the index of the request containing the line,
+    // and the offset of the line on the chip.
+    lines: HashMap<String, (usize, Offset)>,
+
+    // The requests, one for each chip, consumer and, for uAPI v1, configuration.
+    requests: Vec<Request>,
+
+    // The next edge event read from each request, but not yet returned.
+    pending: Mutex<Vec<Option<EdgeEvent>>>,
+}
+
+impl RequestGroup {
+    /// Start building a new request group.
+    pub fn builder() -> GroupBuilder {
+        GroupBuilder::default()
+    }
+
+    /// The identifiers of the requested lines, in the order they were added.
+    pub fn lines(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// The requests underlying the group.
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    /// Find the request containing a line.
+    ///
+    /// Returns the index of the request in [`requests`](#method.requests) and
+    /// the offset of the line on the chip.
+    pub fn locate(&self, id: &str) -> Option<(usize, Offset)> {
+        self.lines.get(id).copied()
+    }
+
+    /// Get the value of a line.
+    pub fn value(&self, id: &str) -> Result<Value> {
+        let (idx, offset) = self.try_locate(id)?;
+        self.requests[idx].value(offset)
+    }
+
+    /// Get the values of all the lines in the group.
+    ///
+    /// Returns a map from line identifier to value.
+    pub fn values(&self) -> Result<HashMap<String, Value>> {
+        let mut values = self.named_values();
+        self.read_named_values(&mut values)?;
+        Ok(values.to_map())
+    }
+
+    /// Create an empty set of values for the lines in the group.
+    ///
+    /// The values are keyed by line identifier, and can be read and set using
+    /// [`read_named_values`] and [`set_named_values`].
+    ///
+    /// [`read_named_values`]: #method.read_named_values
+    /// [`set_named_values`]: #method.set_named_values
+    pub fn named_values(&self) -> NamedValues {
+        NamedValues {
+            lines: self.lines.clone(),
+            values: self.requests.iter().map(|_| Values::default()).collect(),
+        }
+    }
+
+    /// Read the values of a set of lines.
+    ///
+    /// If no values are set then all the lines in the group are read,
+    /// else only the lines with values are read.
+    ///
+    /// The values must have been created by [`named_values`] for this group.
+    ///
+    /// [`named_values`]: #method.named_values
+    pub fn read_named_values(&self, values: &mut NamedValues) -> Result<()> {
+        self.check_named_values(values)?;
+        let all = values.is_empty();
+        for (req, vals) in self.requests.iter().zip(values.values.iter_mut()) {
+            if all || !vals.is_empty() {
+                req.values(vals)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the values of a set of output lines.
+    ///
+    /// Lines without values are left unchanged.
+    ///
+    /// The values must have been created by [`named_values`] for this group.
+    ///
+    /// [`named_values`]: #method.named_values
+    pub fn set_named_values(&self, values: &NamedValues) -> Result<()> {
+        self.check_named_values(values)?;
+        for (req, vals) in self.requests.iter().zip(values.values.iter()) {
+            if !vals.is_empty() {
+                req.set_values(vals)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the value of an output line.
+    pub fn set_value(&self, id: &str, value: Value) -> Result<()> {
+        let (idx, offset) = self.try_locate(id)?;
+        self.requests[idx].set_value(offset, value)
+    }
+
+    /// Set the values of a set of output lines, keyed by line identifier.
+    ///
+    /// Lines not included in `values` are left unchanged.
+    ///
+    /// All the lines must be in the group, else no lines are set.
+    pub fn set_values<S, H>(&self, values: &HashMap<S, Value, H>) -> Result<()>
+    where
+        S: AsRef<str>,
+        H: BuildHasher,
+    {
+        let mut vals = self.named_values();
+        for (id, value) in values {
+            vals.set(id.as_ref(), *value)?;
+        }
+        self.set_named_values(&vals)
+    }
+
+    /// Reconfigure a set of lines, keyed by line identifier.
+    ///
+    /// Lines not included in `cfgs` are left unchanged.
+    ///
+    /// All the lines must be in the group, else no lines are reconfigured.
+    ///
+    /// With uAPI v1, lines that share a request must be reconfigured to the same configuration.
+    pub fn reconfigure<S, H>(&self, cfgs: &HashMap<S, line::Config, H>) -> Result<()>
+    where
+        S: AsRef<str>,
+        H: BuildHasher,
+    {
+        let mut new_cfgs: Vec<Option<Config>> = self.requests.iter().map(|_| None).collect();
+        for (id, lc) in cfgs {
+            let (idx, offset) = self.try_locate(id.as_ref())?;
+            new_cfgs[idx]
+                .get_or_insert_with(|| self.requests[idx].config())
+                .with_line(offset)
+                .from_line_config(lc);
+        }
+        for (req, cfg) in self.requests.iter().zip(new_cfgs) {
+            if let Some(cfg) = cfg {
+                req.reconfigure(&cfg)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true when the group has an edge event available to read.
+    pub fn has_edge_event(&self) -> Result<bool> {
+        self.wait_edge_event(Duration::ZERO)
+    }
+
+    /// Wait for an edge event to be available on any line in the group.
+    ///
+    /// Returns true if [`read_edge_event`] will return an event without blocking.
+    ///
+    /// [`read_edge_event`]: #method.read_edge_event
+    pub fn wait_edge_event(&self, timeout: Duration) -> Result<bool> {
+        if self.pending.lock().unwrap().iter().any(|e| e.is_some()) {
+            return Ok(true);
+        }
+        let files: Vec<&File> = self.requests.iter().map(|r| &r.f).collect();
+        uapi::wait_events(&files, Some(timeout))
+            .map(|ready| !ready.is_empty())
+            .map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))
+    }
+
+    /// Read the next edge event from any line in the group.
+    ///
+    /// Of the events available, the event with the earliest timestamp is returned.
+    ///
+    /// Blocks until an event is available.
+    ///
+    /// Returns the identifier of the line and the event.
+    pub fn read_edge_event(&self) -> Result<(&str, EdgeEvent)> {
+        let mut pending = self.pending.lock().unwrap();
+        loop {
+            // top up from the requests without a pending event
+            let (idxs, files): (Vec<usize>, Vec<&File>) = self
+                .requests
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| pending[*idx].is_none())
+                .map(|(idx, r)| (idx, &r.f))
+                .unzip();
+            let ready = uapi::wait_events(&files, Some(Duration::ZERO))
+                .map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))?;
+            for fidx in ready {
+                let idx = idxs[fidx];
+                pending[idx] = Some(self.requests[idx].read_edge_event()?);
+            }
+
+            let earliest = pending
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, e)| e.as_ref().map(|e| (idx, e.timestamp_ns)))
+                .min_by_key(|(_, ts)| *ts)
+                .map(|(idx, _)| idx);
+            if let Some(idx) = earliest {
+                let evt = pending[idx].take().unwrap();
+                let id = self.id_of(idx, evt.offset).ok_or_else(|| {
+                    Error::UnexpectedResponse(format!(
+                        "Edge event for offset {} is not for a line in the group.",
+                        evt.offset
+                    ))
+                })?;
+                return Ok((id, evt));
+            }
+
+            let files: Vec<&File> = self.requests.iter().map(|r| &r.f).collect();
+            uapi::wait_events(&files, None).map_err(|e| Error::Uapi(UapiCall::WaitEvent, e))?;
+        }
+    }
+
+    // The identifier of the line with the offset in the request.
+    //
+    // The builder rejects lines added more than once, so the identifier is unique.
+    fn id_of(&self, idx: usize, offset: Offset) -> Option<&str> {
+        self.lines
+            .iter()
+            .find(|(_, loc)| **loc == (idx, offset))
+            .map(|(id, _)| id.as_str())
+    }
+
+    fn check_named_values(&self, values: &NamedValues) -> Result<()> {
+        if values.lines != self.lines || values.values.len() != self.requests.len() {
+            return Err(Error::InvalidArgument(
+                "Values are not for this group.".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn try_locate(&self, id: &str) -> Result<(usize, Offset)> {
+        self.locate(id)
+            .ok_or_else(|| Error::InvalidArgument(format!("Line '{id}' is not in the group.")))
+    }
+}
+
+/// The values of a set of lines in a [`RequestGroup`], keyed by line identifier.
+///
+/// Created by [`RequestGroup::named_values`], the values are routed to the
+/// request and offset of each line, so library users need not track which
+/// chip and offset correspond to each line.
+///
+/// Unlike the maps used by [`RequestGroup::values`] and [`RequestGroup::set_values`],
+/// the lines are located once, when the values are set, rather than on every
+/// access, and the same values may be reused for repeated reads and writes,
+/// such as when polling or driving a set of lines in a loop.
+/// The map based methods are implemented using `NamedValues`.
+///
+/// [`RequestGroup::named_values`]: struct.RequestGroup.html#method.named_values
+/// [`RequestGroup::values`]: struct.RequestGroup.html#method.values
+/// [`RequestGroup::set_values`]: struct.RequestGroup.html#method.set_values
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// use gpiocdev::line::Value;
+/// use gpiocdev::request::RequestGroup;
+///
+/// let grp = RequestGroup::builder()
+///     .with_lines(&["LED0", "LED1"])
+///     .as_output(Value::Inactive)
+///     .request()?;
+/// let mut values = grp.named_values();
+/// values.set("LED0", Value::Active)?.set("LED1", Value::Active)?;
+/// grp.set_named_values(&values)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NamedValues {
+    // Map from line identifier to the index of the request containing the line,
+    // and the offset of the line on the chip.
+    lines: HashMap<String, (usize, Offset)>,
+
+    // The values for the lines in each request.
+    values: Vec<Values>,
+}
+
+impl NamedValues {
+    /// Get the value of a line.
+    ///
+    /// Returns None if the line has no value or is not in the group.
+    pub fn get(&self, id: &str) -> Option<Value> {
+        let (idx, offset) = self.lines.get(id)?;
+        self.values[*idx].get(*offset)
+    }
+
+    /// Set the value of a line.
+    ///
+    /// The line must be in the group.
+    pub fn set(&mut self, id: &str, value: Value) -> Result<&mut Self> {
+        let (idx, offset) = self
+            .lines
+            .get(id)
+            .ok_or_else(|| Error::InvalidArgument(format!("Line '{id}' is not in the group.")))?;
+        self.values[*idx].set(*offset, value);
+        Ok(self)
+    }
+
+    /// The number of lines with values.
+    pub fn len(&self) -> usize {
+        self.values.iter().map(|v| v.len()).sum()
+    }
+
+    /// Returns true if no lines have values.
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(|v| v.is_empty())
+    }
+
+    // The values keyed by line identifier.
+    fn to_map(&self) -> HashMap<String, Value> {
+        self.lines
+            .iter()
+            .filter_map(|(id, (idx, offset))| {
+                self.values[*idx].get(*offset).map(|v| (id.to_owned(), v))
+            })
+            .collect()
+    }
+}
+
+/// A builder of request groups.
+///
+/// Lines are added by name, using [`with_line`](#method.with_line), or as pre-resolved
+/// lines, using [`with_found_line`](#method.with_found_line).
+///
+/// Configuration mutators follow the same selection rules as the [`Builder`].
+/// They apply to the most recently added lines, or to the base configuration if no
+/// lines have been added.
+///
+/// [`Builder`]: struct.Builder.html
+#[derive(Clone, Debug, Default)]
+pub struct GroupBuilder {
+    // The identifiers of the lines, in the order they were added.
+    ids: Vec<String>,
+
+    // The found line for lines added with a resolved location.
+    found: HashMap<String, FoundLine>,
+
+    // The line configuration, with lines indexed by their position in ids.
+    cfg: Config,
+
+    // The consumer for each line, indexed by position in ids.
+    consumers: Vec<String>,
+
+    // The consumer for lines subsequently added.
+    consumer: String,
+
+    // The number of times, and delay between, retries of busy requests.
+    retry: (u32, Duration),
+
+    // Allow lines on a chip to be split across requests to avoid the kernel limit.
+    allow_splitting: bool,
+
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    abiv: Option<AbiVersion>,
+}
+
+impl GroupBuilder {
+    /// Perform the requests.
+    ///
+    /// Lines added by name are located by searching all the chips in the system,
+    /// and the names must be unique.
+    ///
+    /// A request is made for each combination of chip and consumer, and with
+    /// uAPI v1 also for each line configuration.
+    /// If any request fails then no lines remain requested.
+    ///
+    /// It is an error for the same line to be added under different identifiers.
+    pub fn request(&mut self) -> Result<RequestGroup> {
+        if self.ids.is_empty() {
+            return Err(Error::InvalidArgument("No lines specified.".into()));
+        }
+        let split_by_config = self.split_by_config()?;
+        let names: Vec<&str> = self
+            .ids
+            .iter()
+            .filter(|id| !self.found.contains_key(*id))
+            .map(|id| id.as_str())
+            .collect();
+        let named = if names.is_empty() {
+            HashMap::new()
+        } else {
+            crate::find_named_lines(&names, true)?
+        };
+
+        // the chip, consumer and, when splitting by config, config for each request
+        let mut keys: Vec<(PathBuf, &str, Option<&line::Config>)> = Vec::new();
+        let mut cfgs: Vec<Config> = Vec::new();
+        let mut lines = HashMap::new();
+        // the identifier of the line at each chip and offset
+        let mut located: HashMap<(&Path, Offset), &str> = HashMap::new();
+        for (idx, id) in self.ids.iter().enumerate() {
+            let fl = match self.found.get(id) {
+                Some(fl) => fl,
+                None => named
+                    .get(id.as_str())
+                    .ok_or_else(|| Error::InvalidArgument(format!("Line '{id}' not found.")))?,
+            };
+            if let Some(prev) = located.insert((&fl.chip, fl.info.offset), id) {
+                return Err(Error::InvalidArgument(format!(
+                    "Lines '{prev}' and '{id}' are the same line."
+                )));
+            }
+            let lc = self.cfg.line_config(idx as Offset).unwrap();
+            let consumer = self.consumers[idx].as_str();
+            let kcfg = if split_by_config { Some(lc) } else { None };
+            let ridx = match keys
+                .iter()
+                .enumerate()
+                .position(|(ridx, (chip, kconsumer, klc))| {
+                    !(self.allow_splitting && cfgs[ridx].num_lines() >= uapi::NUM_LINES_MAX)
+                        && *chip == fl.chip
+                        && *kconsumer == consumer
+                        && match (klc, kcfg) {
+                            (Some(klc), Some(lc)) => klc.equivalent(lc),
+                            _ => true,
+                        }
+                }) {
+                Some(ridx) => ridx,
+                None => {
+                    keys.push((fl.chip.clone(), consumer, kcfg));
+                    cfgs.push(Config::default());
+                    keys.len() - 1
+                }
+            };
+            cfgs[ridx].with_line(fl.info.offset).from_line_config(lc);
+            lines.insert(id.to_owned(), (ridx, fl.info.offset));
+        }
+
+        let mut requests = Vec::new();
+        for ((chip, consumer, _), cfg) in keys.iter().zip(cfgs) {
+            let mut bld = Request::from_config(cfg);
+            bld.on_chip(chip)
+                .with_consumer(*consumer)
+                .with_retry(self.retry.0, self.retry.1);
+            #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+            if let Some(abiv) = self.abiv {
+                bld.using_abi_version(abiv);
+            }
+            requests.push(bld.request()?);
+        }
+        Ok(RequestGroup {
+            ids: self.ids.clone(),
+            lines,
+            pending: Mutex::new(requests.iter().map(|_| None).collect()),
+            requests,
+        })
+    }
+
+    // With uAPI v1 all lines in a request must share the same configuration.
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn split_by_config(&mut self) -> Result<bool> {
+        if self.abiv.is_none() {
+            self.abiv = Some(crate::detect_abi_version()?);
+        }
+        Ok(self.abiv == Some(AbiVersion::V1))
+    }
+    #[cfg(not(feature = "uapi_v2"))]
+    fn split_by_config(&self) -> Result<bool> {
+        Ok(true)
+    }
+    #[cfg(not(feature = "uapi_v1"))]
+    fn split_by_config(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Set the consumer label for the selected lines.
+    ///
+    /// If no lines are selected then sets the consumer label for lines subsequently added.
+    ///
+    /// Lines with different consumer labels are placed in separate requests.
+    ///
+    /// As per [`Builder::with_consumer`].
+    ///
+    /// [`Builder::with_consumer`]: struct.Builder.html#method.with_consumer
+    pub fn with_consumer<N: Into<String>>(&mut self, consumer: N) -> &mut Self {
+        let consumer = consumer.into();
+        if self.cfg.selected.is_empty() {
+            self.consumer = consumer;
+        } else {
+            for idx in &self.cfg.selected {
+                self.consumers[*idx as usize] = consumer.clone();
+            }
+        }
+        self
+    }
+
+    /// Allow the lines on a chip to be split across several requests.
+    ///
+    /// The kernel limits a request to at most [`NUM_LINES_MAX`] lines.
+    /// If splitting is allowed then lines beyond that limit are placed in
+    /// additional requests.  Otherwise requesting more lines from a chip
+    /// than that limit fails.
+    ///
+    /// Note that setting values on lines spread across several requests is
+    /// not atomic, so splitting is not allowed by default.
+    ///
+    /// [`NUM_LINES_MAX`]: gpiocdev_uapi::NUM_LINES_MAX
+    pub fn allow_splitting(&mut self, allow: bool) -> &mut Self {
+        self.allow_splitting = allow;
+        self
+    }
+
+    /// Retry each request if it fails because a line is busy.
+    ///
+    /// As per [`Builder::with_retry`].
+    ///
+    /// [`Builder::with_retry`]: struct.Builder.html#method.with_retry
+    pub fn with_retry(&mut self, count: u32, delay: Duration) -> &mut Self {
+        self.retry = (count, delay);
+        self
+    }
+
+    /// Select the ABI version to use when requesting the lines and for subsequent operations.
+    ///
+    /// As per [`Builder::using_abi_version`].
+    ///
+    /// [`Builder::using_abi_version`]: struct.Builder.html#method.using_abi_version
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    pub fn using_abi_version(&mut self, abiv: AbiVersion) -> &mut Self {
+        self.abiv = Some(abiv);
+        self
+    }
+
+    /// Add a line, identified by name, to the group.
+    ///
+    /// Note that all configuration mutators applied subsequently only
+    /// apply to this line.
+    pub fn with_line(&mut self, name: &str) -> &mut Self {
+        let idx = self.index(name);
+        self.cfg.with_line(idx);
+        self
+    }
+
+    /// Add a set of lines, identified by name, to the group.
+    ///
+    /// Note that all configuration mutators applied subsequently only
+    /// apply to this subset of lines.
+    pub fn with_lines(&mut self, names: &[&str]) -> &mut Self {
+        let idxs: Vec<Offset> = names.iter().map(|name| self.index(name)).collect();
+        self.cfg.with_lines(&idxs);
+        self
+    }
+
+    /// Add a found line to the group.
+    ///
+    /// The line is identified within the group by `id`, which need not be the line name.
+    ///
+    /// Note that all configuration mutators applied subsequently only
+    /// apply to this line.
+    pub fn with_found_line(&mut self, id: &str, line: &FoundLine) -> &mut Self {
+        let idx = self.index(id);
+        self.found.insert(id.to_owned(), line.clone());
+        self.cfg.with_line(idx);
+        self
+    }
+
+    /// Set the selected lines to input.
+    pub fn as_input(&mut self) -> &mut Self {
+        self.cfg.as_input();
+        self
+    }
+
+    /// Set the selected lines to output with the given value.
+    pub fn as_output(&mut self, value: Value) -> &mut Self {
+        self.cfg.as_output(value);
+        self
+    }
+
+    /// Set the selected lines to active low.
+    pub fn as_active_low(&mut self) -> &mut Self {
+        self.cfg.as_active_low();
+        self
+    }
+
+    /// Set the selected lines to active high.
+    pub fn as_active_high(&mut self) -> &mut Self {
+        self.cfg.as_active_high();
+        self
+    }
+
+    /// Set the bias setting for the selected lines.
+    pub fn with_bias<B: Into<Option<Bias>>>(&mut self, bias: B) -> &mut Self {
+        self.cfg.with_bias(bias);
+        self
+    }
+
+    /// Set the debounce period for the selected lines.
+    pub fn with_debounce_period(&mut self, period: Duration) -> &mut Self {
+        self.cfg.with_debounce_period(period);
+        self
+    }
+
+    /// Set the direction of the selected lines.
+    pub fn with_direction(&mut self, direction: Direction) -> &mut Self {
+        self.cfg.with_direction(direction);
+        self
+    }
+
+    /// Set the drive setting for the selected lines.
+    pub fn with_drive(&mut self, drive: Drive) -> &mut Self {
+        self.cfg.with_drive(drive);
+        self
+    }
+
+    /// Set the edge detection for the selected lines.
+    pub fn with_edge_detection<E: Into<Option<EdgeDetection>>>(&mut self, edge: E) -> &mut Self {
+        self.cfg.with_edge_detection(edge);
+        self
+    }
+
+    /// Set the clock source for edge events on the selected lines.
+    pub fn with_event_clock<E: Into<Option<EventClock>>>(&mut self, event_clock: E) -> &mut Self {
+        self.cfg.with_event_clock(event_clock);
+        self
+    }
+
+    /// Set the value of the selected lines.
+    pub fn with_value(&mut self, value: Value) -> &mut Self {
+        self.cfg.with_value(value);
+        self
+    }
+
+    /// Apply the configuration based on a snapshot from a single line.
+    pub fn from_line_config(&mut self, cfg: &line::Config) -> &mut Self {
+        self.cfg.from_line_config(cfg);
+        self
+    }
+
+    // The index of the line in ids, adding it if necessary.
+    fn index(&mut self, id: &str) -> Offset {
+        let idx = match self.ids.iter().position(|x| x == id) {
+            Some(idx) => idx,
+            None => {
+                self.ids.push(id.to_owned());
+                self.consumers.push(self.consumer.clone());
+                self.ids.len() - 1
+            }
+        };
+        idx as Offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_selection() {
+        let mut bld = RequestGroup::builder();
+        bld.as_active_low()
+            .with_lines(&["a", "b"])
+            .as_output(Value::Active)
+            .with_line("c")
+            .as_input()
+            .with_bias(Bias::PullUp)
+            .with_line("a")
+            .with_value(Value::Inactive);
+        assert_eq!(bld.ids, vec!["a", "b", "c"]);
+
+        let lc = bld.cfg.line_config(0).unwrap();
+        assert!(lc.active_low);
+        assert_eq!(lc.direction, Some(Direction::Output));
+        assert_eq!(lc.value, Some(Value::Inactive));
+
+        let lc = bld.cfg.line_config(1).unwrap();
+        assert!(lc.active_low);
+        assert_eq!(lc.direction, Some(Direction::Output));
+        assert_eq!(lc.value, Some(Value::Active));
+
+        let lc = bld.cfg.line_config(2).unwrap();
+        assert!(lc.active_low);
+        assert_eq!(lc.direction, Some(Direction::Input));
+        assert_eq!(lc.bias, Some(Bias::PullUp));
+    }
+
+    #[test]
+    fn with_consumer() {
+        let mut bld = RequestGroup::builder();
+        bld.with_consumer("base")
+            .with_lines(&["a", "b"])
+            .with_consumer("ab")
+            .with_line("c")
+            .with_line("b")
+            .with_consumer("b");
+        assert_eq!(bld.consumers, vec!["ab", "b", "base"]);
+        assert_eq!(bld.consumer, "base");
+    }
+
+    #[test]
+    fn with_retry() {
+        let mut bld = RequestGroup::builder();
+        assert_eq!(bld.retry, (0, Duration::ZERO));
+        bld.with_retry(3, Duration::from_millis(5));
+        assert_eq!(bld.retry, (3, Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn allow_splitting() {
+        let mut bld = RequestGroup::builder();
+        assert!(!bld.allow_splitting);
+        bld.allow_splitting(true);
+        assert!(bld.allow_splitting);
+        bld.allow_splitting(false);
+        assert!(!bld.allow_splitting);
+    }
+
+    #[test]
+    fn with_found_line() {
+        let mut bld = RequestGroup::builder();
+        let mut info = line::Info::default();
+        info.offset = 3;
+        let fl = FoundLine {
+            chip: "/dev/gpiochip0".into(),
+            info,
+        };
+        bld.with_found_line("led", &fl).as_output(Value::Active);
+        assert_eq!(bld.ids, vec!["led"]);
+        assert_eq!(bld.found.get("led"), Some(&fl));
+        assert_eq!(
+            bld.cfg.line_config(0).unwrap().direction,
+            Some(Direction::Output)
+        );
+    }
+
+    #[test]
+    fn request_no_lines() {
+        assert_eq!(
+            RequestGroup::builder().request().unwrap_err(),
+            Error::InvalidArgument("No lines specified.".into())
+        );
+    }
+
+    #[test]
+    fn request_same_line() {
+        let mut bld = RequestGroup::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        bld.using_abi_version(crate::AbiVersion::V2);
+        let mut info = line::Info::default();
+        info.offset = 3;
+        let fl = FoundLine {
+            chip: "/dev/gpiochip0".into(),
+            info,
+        };
+        bld.with_found_line("led", &fl)
+            .with_found_line("alias", &fl)
+            .as_output(Value::Active);
+        assert_eq!(
+            bld.request().unwrap_err(),
+            Error::InvalidArgument("Lines 'led' and 'alias' are the same line.".into())
+        );
+    }
+
+    #[test]
+    fn id_of() {
+        let grp = RequestGroup {
+            lines: [("banana", (0, 3)), ("grape", (1, 3))]
+                .into_iter()
+                .map(|(id, loc)| (id.to_string(), loc))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(grp.id_of(0, 3), Some("banana"));
+        assert_eq!(grp.id_of(1, 3), Some("grape"));
+        assert_eq!(grp.id_of(1, 2), None);
+    }
+
+    #[test]
+    fn named_values() {
+        let mut values = NamedValues {
+            lines: [("banana", (0, 3)), ("grape", (1, 2)), ("mango", (1, 5))]
+                .into_iter()
+                .map(|(id, loc)| (id.to_string(), loc))
+                .collect(),
+            values: vec![Values::default(), Values::default()],
+        };
+        assert!(values.is_empty());
+        assert_eq!(values.get("banana"), None);
+
+        values
+            .set("banana", Value::Active)
+            .unwrap()
+            .set("mango", Value::Inactive)
+            .unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get("banana"), Some(Value::Active));
+        assert_eq!(values.get("grape"), None);
+        assert_eq!(values.get("mango"), Some(Value::Inactive));
+        // routed to the request and offset of the line
+        assert_eq!(values.values[0].get(3), Some(Value::Active));
+        assert_eq!(values.values[1].get(5), Some(Value::Inactive));
+        assert_eq!(values.values[1].get(2), None);
+
+        values.set("grape", Value::Active).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.values[1].get(2), Some(Value::Active));
+
+        assert_eq!(
+            values.set("nada", Value::Active).unwrap_err(),
+            Error::InvalidArgument("Line 'nada' is not in the group.".into())
+        );
+        assert_eq!(values.get("nada"), None);
+
+        let map = values.to_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("banana"), Some(&Value::Active));
+        assert_eq!(map.get("grape"), Some(&Value::Active));
+        assert_eq!(map.get("mango"), Some(&Value::Inactive));
+    }
+
+    #[test]
+    fn check_named_values() {
+        let grp = RequestGroup::default();
+        assert!(grp.check_named_values(&grp.named_values()).is_ok());
+
+        // same number of requests, but from a different group
+        let values = NamedValues {
+            lines: [("banana".to_string(), (0, 3))].into_iter().collect(),
+            values: Vec::new(),
+        };
+        assert_eq!(
+            grp.check_named_values(&values).unwrap_err(),
+            Error::InvalidArgument("Values are not for this group.".into())
+        );
+    }
+
+    #[test]
+    fn unknown_line() {
+        let grp = RequestGroup::default();
+        assert_eq!(grp.locate("nada"), None);
+        assert_eq!(
+            grp.value("nada"),
+            Err(Error::InvalidArgument(
+                "Line 'nada' is not in the group.".into()
+            ))
+        );
+        let mut values = HashMap::new();
+        values.insert("nada", Value::Active);
+        assert_eq!(
+            grp.set_values(&values),
+            Err(Error::InvalidArgument(
+                "Line 'nada' is not in the group.".into()
+            ))
+        );
+
+        let values = NamedValues {
+            values: vec![Values::default()],
+            ..Default::default()
+        };
+        assert_eq!(
+            grp.set_named_values(&values),
+            Err(Error::InvalidArgument(
+                "Values are not for this group.".into()
+            ))
+        );
+    }
+}
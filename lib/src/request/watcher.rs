@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::line::{Offset, Value, Values};
+use crate::request::Request;
+use crate::Result;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A handle to a background thread polling the values of requested lines.
+///
+/// Created by [`Request::watch_values`].
+///
+/// The watch continues until the handle is stopped or dropped.
+///
+/// [`Request::watch_values`]: struct.Request.html#method.watch_values
+#[derive(Debug)]
+pub struct ValueWatcher {
+    // Dropping the sender stops the polling thread.
+    done: Option<Sender<()>>,
+
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl ValueWatcher {
+    pub(super) fn new<F>(
+        req: Request,
+        mut values: Values,
+        poll_interval: Duration,
+        mut callback: F,
+    ) -> ValueWatcher
+    where
+        F: FnMut(Offset, Value) + Send + 'static,
+    {
+        let (done, rx) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut prev = snapshot(&values);
+            while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(poll_interval) {
+                req.values(&mut values)?;
+                for lv in values.iter() {
+                    if prev.get(lv.offset) != Some(lv.value) {
+                        callback(lv.offset, lv.value);
+                    }
+                }
+                prev = snapshot(&values);
+            }
+            Ok(())
+        });
+        ValueWatcher {
+            done: Some(done),
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop watching the values.
+    ///
+    /// Returns the error that terminated the watch, if any.
+    ///
+    /// If the callback panicked then the panic is propagated.
+    pub fn stop(mut self) -> Result<()> {
+        self.done.take();
+        match self.thread.take() {
+            Some(t) => t.join().unwrap_or_else(|e| std::panic::resume_unwind(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if the watch has terminated, such as due to an error reading values.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().map_or(true, |t| t.is_finished())
+    }
+}
+
+impl Drop for ValueWatcher {
+    fn drop(&mut self) {
+        self.done.take();
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+fn snapshot(values: &Values) -> Values {
+    values.iter().map(|lv| (lv.offset, lv.value)).collect()
+}
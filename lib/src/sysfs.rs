@@ -0,0 +1,262 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use crate::chip::Chip;
+use crate::line::Offset;
+use crate::{Error, Result};
+use std::fs;
+use std::path::Path;
+
+// The root of the sysfs GPIO interface.
+const SYSFS_GPIO: &str = "/sys/class/gpio";
+
+/// Find the chip and offset corresponding to a global GPIO number.
+///
+/// The global number is the number used to identify the line in the sysfs
+/// interface, such as 17 in `/sys/class/gpio/gpio17`.
+///
+/// Returns the opened chip and the offset of the line on that chip.
+///
+/// # Examples
+/// ```no_run
+/// # fn example() -> Result<(), gpiocdev::Error> {
+/// # use gpiocdev::line::Value;
+/// let (chip, offset) = gpiocdev::sysfs::resolve_global(472)?;
+/// let req = gpiocdev::Request::builder()
+///     .on_chip(chip.path())
+///     .with_line(offset)
+///     .as_output(Value::Active)
+///     .request()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn resolve_global(number: u32) -> Result<(Chip, Offset)> {
+    let (name, offset) = resolve_global_in(Path::new(SYSFS_GPIO), number)?;
+    Ok((Chip::from_name(&name)?, offset))
+}
+
+/// Find the global GPIO number corresponding to a line on a chip.
+///
+/// This is the inverse of [`resolve_global`].
+pub fn global_number(chip: &Chip, offset: Offset) -> Result<u32> {
+    global_number_in(Path::new(SYSFS_GPIO), &chip.name(), offset)
+}
+
+// A chip as described by the sysfs interface.
+#[derive(Debug, Eq, PartialEq)]
+struct SysfsChip {
+    // the name of the character device, e.g. gpiochip0
+    name: String,
+    // the global number of the first line on the chip
+    base: u32,
+    // the number of lines on the chip
+    ngpio: u32,
+}
+
+fn resolve_global_in(root: &Path, number: u32) -> Result<(String, Offset)> {
+    sysfs_chips(root)?
+        .into_iter()
+        .find(|c| number >= c.base && number - c.base < c.ngpio)
+        .map(|c| (c.name, number - c.base))
+        .ok_or_else(|| Error::InvalidArgument(format!("GPIO {} not found in sysfs.", number)))
+}
+
+fn global_number_in(root: &Path, chip: &str, offset: Offset) -> Result<u32> {
+    sysfs_chips(root)?
+        .into_iter()
+        .find(|c| c.name == chip && offset < c.ngpio)
+        .map(|c| c.base + offset)
+        .ok_or_else(|| {
+            Error::InvalidArgument(format!("Line {} on {} not found in sysfs.", offset, chip))
+        })
+}
+
+// Read the chips from a sysfs style tree.
+//
+// Chips whose attributes cannot be read, or whose character device cannot be
+// identified, are skipped.
+fn sysfs_chips(root: &Path) -> Result<Vec<SysfsChip>> {
+    let mut chips = Vec::new();
+    for entry in fs::read_dir(root)?.flatten() {
+        // exported lines are gpioN, so only the chips are gpiochipN
+        if !entry.file_name().to_string_lossy().starts_with("gpiochip") {
+            continue;
+        }
+        let path = entry.path();
+        if let (Some(name), Some(base), Some(ngpio)) = (
+            cdev_name(&path),
+            read_u32(&path.join("base")),
+            read_u32(&path.join("ngpio")),
+        ) {
+            chips.push(SysfsChip { name, base, ngpio });
+        }
+    }
+    Ok(chips)
+}
+
+fn read_u32(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// The name of the character device corresponding to a sysfs chip.
+//
+// The sysfs chip device is the character device in recent kernels, while in
+// older kernels it is the parent of the character device.
+fn cdev_name(sysfs_chip: &Path) -> Option<String> {
+    let device = fs::canonicalize(sysfs_chip.join("device")).ok()?;
+    let name = device.file_name()?.to_string_lossy();
+    if is_cdev_name(&name) {
+        return Some(name.into_owned());
+    }
+    let mut names = fs::read_dir(&device)
+        .ok()?
+        .flatten()
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| is_cdev_name(n));
+    match (names.next(), names.next()) {
+        (Some(name), None) => Some(name),
+        // a parent with several chips is ambiguous
+        _ => None,
+    }
+}
+
+fn is_cdev_name(name: &str) -> bool {
+    name.strip_prefix("gpiochip").map_or(false, |n| {
+        !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    // A synthetic sysfs tree, removed when dropped.
+    struct Sysfs {
+        root: PathBuf,
+    }
+
+    impl Sysfs {
+        fn new(name: &str) -> Sysfs {
+            let root = std::env::temp_dir().join(format!(
+                "gpiocdev-sysfs-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("class/gpio")).unwrap();
+            fs::write(root.join("class/gpio/export"), "").unwrap();
+            fs::write(root.join("class/gpio/unexport"), "").unwrap();
+            Sysfs { root }
+        }
+
+        fn gpio(&self) -> PathBuf {
+            self.root.join("class/gpio")
+        }
+
+        // Add a sysfs chip, with the device at the path relative to the root.
+        fn add_chip(&self, base: u32, ngpio: u32, device: &str) {
+            let dir = self.gpio().join(format!("gpiochip{}", base));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("base"), format!("{}\n", base)).unwrap();
+            fs::write(dir.join("ngpio"), format!("{}\n", ngpio)).unwrap();
+            let device = self.root.join(device);
+            fs::create_dir_all(&device).unwrap();
+            symlink(&device, dir.join("device")).unwrap();
+        }
+    }
+
+    impl Drop for Sysfs {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn resolve_global_in() {
+        let s = Sysfs::new("resolve");
+        // recent kernels - the device is the chip
+        s.add_chip(512, 54, "devices/platform/soc/gpio/gpiochip0");
+        s.add_chip(566, 8, "devices/platform/soc/expander/gpiochip1");
+        // older kernels - the device is the parent of the chip
+        s.add_chip(480, 32, "devices/platform/legacy");
+        fs::create_dir_all(s.root.join("devices/platform/legacy/gpiochip2")).unwrap();
+        // an exported line is not a chip
+        fs::create_dir_all(s.gpio().join("gpio530")).unwrap();
+
+        let resolve = |n| super::resolve_global_in(&s.gpio(), n);
+        assert_eq!(resolve(512), Ok(("gpiochip0".into(), 0)));
+        assert_eq!(resolve(529), Ok(("gpiochip0".into(), 17)));
+        assert_eq!(resolve(565), Ok(("gpiochip0".into(), 53)));
+        assert_eq!(resolve(566), Ok(("gpiochip1".into(), 0)));
+        assert_eq!(resolve(573), Ok(("gpiochip1".into(), 7)));
+        assert_eq!(resolve(480), Ok(("gpiochip2".into(), 0)));
+        assert_eq!(resolve(511), Ok(("gpiochip2".into(), 31)));
+        for n in [0, 17, 479, 574] {
+            assert_eq!(
+                resolve(n),
+                Err(Error::InvalidArgument(format!(
+                    "GPIO {} not found in sysfs.",
+                    n
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn global_number_in() {
+        let s = Sysfs::new("global");
+        s.add_chip(512, 54, "devices/gpiochip0");
+        s.add_chip(566, 8, "devices/gpiochip1");
+
+        let global = |chip, offset| super::global_number_in(&s.gpio(), chip, offset);
+        assert_eq!(global("gpiochip0", 0), Ok(512));
+        assert_eq!(global("gpiochip0", 17), Ok(529));
+        assert_eq!(global("gpiochip1", 7), Ok(573));
+        assert_eq!(
+            global("gpiochip1", 8),
+            Err(Error::InvalidArgument(
+                "Line 8 on gpiochip1 not found in sysfs.".into()
+            ))
+        );
+        assert!(global("gpiochip2", 0).is_err());
+    }
+
+    #[test]
+    fn sysfs_chips_skips_unidentified() {
+        let s = Sysfs::new("skip");
+        s.add_chip(512, 8, "devices/gpiochip0");
+        // no character device
+        s.add_chip(520, 8, "devices/platform/empty");
+        // ambiguous character device
+        s.add_chip(528, 8, "devices/platform/multi");
+        fs::create_dir_all(s.root.join("devices/platform/multi/gpiochip3")).unwrap();
+        fs::create_dir_all(s.root.join("devices/platform/multi/gpiochip4")).unwrap();
+        // unreadable attribute
+        s.add_chip(536, 8, "devices/gpiochip5");
+        fs::write(s.gpio().join("gpiochip536/ngpio"), "banana").unwrap();
+
+        assert_eq!(
+            sysfs_chips(&s.gpio()).unwrap(),
+            vec![SysfsChip {
+                name: "gpiochip0".into(),
+                base: 512,
+                ngpio: 8
+            }]
+        );
+        // no sysfs interface
+        assert!(sysfs_chips(&s.root.join("missing")).is_err());
+    }
+
+    #[test]
+    fn is_cdev_name() {
+        assert!(super::is_cdev_name("gpiochip0"));
+        assert!(super::is_cdev_name("gpiochip42"));
+        assert!(!super::is_cdev_name("gpiochip"));
+        assert!(!super::is_cdev_name("gpiochipx"));
+        assert!(!super::is_cdev_name("gpio17"));
+        assert!(!super::is_cdev_name("platform"));
+    }
+}
@@ -190,6 +190,7 @@ mod request {
             gpiocdev::AbiVersion::V1,
             from_request,
             read_edge_event,
+            wait_edge_event,
             read_edge_events_into_slice,
             new_edge_event_stream,
             edge_events
@@ -201,6 +202,7 @@ mod request {
         common_tests! {
             gpiocdev::AbiVersion::V2,            read_edge_event,
             from_request,
+            wait_edge_event,
             read_edge_events_into_slice,
             new_edge_event_stream,
             edge_events
@@ -258,6 +260,38 @@ mod request {
         })
     }
 
+    fn wait_edge_event(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let req = AsyncRequest::new(new_request(s.dev_path(), offset, abiv));
+
+        async_io::block_on(async {
+            assert!(!req
+                .wait_edge_event(Duration::from_millis(10))
+                .await
+                .unwrap());
+
+            s.pullup(offset).unwrap();
+            assert!(req
+                .wait_edge_event(Duration::from_millis(100))
+                .await
+                .unwrap());
+            // the event remains available until read
+            assert!(req
+                .wait_edge_event(Duration::from_millis(10))
+                .await
+                .unwrap());
+            let evt = req.read_edge_event().await.unwrap();
+            assert_eq!(evt.kind, EdgeKind::Rising);
+
+            assert!(!req
+                .wait_edge_event(Duration::from_millis(10))
+                .await
+                .unwrap());
+        })
+    }
+
     fn read_edge_events_into_slice(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(3);
         let offset = 1;
@@ -180,6 +180,7 @@ mod request {
             gpiocdev::AbiVersion::V1,
             from_request,
             read_edge_event,
+            wait_edge_event,
             read_edge_events_into_slice,
             new_edge_event_stream,
             edge_events
@@ -192,6 +193,7 @@ mod request {
             gpiocdev::AbiVersion::V2,
             from_request,
             read_edge_event,
+            wait_edge_event,
             read_edge_events_into_slice,
             new_edge_event_stream,
             edge_events
@@ -247,6 +249,36 @@ mod request {
         assert!(res.is_err());
     }
 
+    async fn wait_edge_event(abiv: gpiocdev::AbiVersion) {
+        let s = gpiosim::Simpleton::new(4);
+        let offset = 2;
+
+        let req = AsyncRequest::new(new_request(s.dev_path(), offset, abiv));
+
+        assert!(!req
+            .wait_edge_event(Duration::from_millis(10))
+            .await
+            .unwrap());
+
+        s.pullup(offset).unwrap();
+        assert!(req
+            .wait_edge_event(Duration::from_millis(100))
+            .await
+            .unwrap());
+        // the event remains available until read
+        assert!(req
+            .wait_edge_event(Duration::from_millis(10))
+            .await
+            .unwrap());
+        let evt = req.read_edge_event().await.unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+
+        assert!(!req
+            .wait_edge_event(Duration::from_millis(10))
+            .await
+            .unwrap());
+    }
+
     async fn read_edge_events_into_slice(abiv: gpiocdev::AbiVersion) {
         let s = gpiosim::Simpleton::new(3);
         let offset = 1;
@@ -68,7 +68,7 @@ fn is_chip_nonexistent() {
     let path = PathBuf::from("/dev/gpiochip_nonexistent");
     assert_eq!(
         gpiocdev::chip::is_chip(path),
-        Err(gpiocdev::Error::Os(gpiocdev_uapi::Errno(2)))
+        Err(gpiocdev::Error::Os(gpiocdev_uapi::Errno(libc::ENOENT)))
     );
 }
 
@@ -155,6 +155,32 @@ mod chip {
         }
     }
 
+    #[test]
+    fn from_name_nonexistent() {
+        assert_eq!(
+            Chip::from_name("gpiochip_nonexistent").unwrap_err(),
+            gpiocdev::Error::Os(gpiocdev_uapi::Errno(libc::ENOENT))
+        );
+    }
+
+    #[test]
+    fn from_number() {
+        let s = bag_of_chips();
+        for c in s.chips() {
+            let n: u32 = c.chip_name.trim_start_matches("gpiochip").parse().unwrap();
+            let chip = Chip::from_number(n).unwrap();
+            assert_eq!(chip.path(), c.dev_path());
+        }
+    }
+
+    #[test]
+    fn from_number_nonexistent() {
+        assert_eq!(
+            Chip::from_number(u32::MAX).unwrap_err(),
+            gpiocdev::Error::Os(gpiocdev_uapi::Errno(libc::ENOENT))
+        );
+    }
+
     #[test]
     fn from_path() {
         let s = bag_of_chips();
@@ -182,7 +208,7 @@ mod chip {
         let path = PathBuf::from("/dev/gpiochip_nonexistent");
         assert_eq!(
             Chip::from_path(path).unwrap_err(),
-            gpiocdev::Error::Os(gpiocdev_uapi::Errno(2))
+            gpiocdev::Error::Os(gpiocdev_uapi::Errno(libc::ENOENT))
         );
     }
 
@@ -216,6 +242,74 @@ mod chip {
         }
     }
 
+    #[test]
+    #[cfg(feature = "mockable")]
+    fn info_is_cached() {
+        use gpiocdev_uapi::mock::{install, FaultInjector};
+        use std::rc::Rc;
+
+        let s = bag_of_chips();
+        let sc = &s.chips()[1];
+        let c = Chip::from_path(sc.dev_path()).unwrap();
+
+        // any ioctls would now be recorded
+        let fi = Rc::new(FaultInjector::default());
+        let _guard = install(fi.clone());
+        for _ in 0..3 {
+            let info = c.info().unwrap();
+            assert_eq!(info.num_lines, 12);
+            assert_eq!(info.label.as_str(), "babel");
+            assert_eq!(c.num_lines(), 12);
+            assert_eq!(c.label(), "babel");
+        }
+        assert!(fi.calls().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "mockable")]
+    fn info_cache_avoids_ioctls() {
+        use gpiocdev_uapi::mock::{install, FaultInjector};
+        use std::rc::Rc;
+
+        let s = Simpleton::new(4);
+        let mut c = Chip::from_path(s.dev_path()).unwrap();
+        c.enable_info_cache();
+        let info = c.line_info(1).unwrap();
+
+        // any ioctls or reads would now be recorded
+        let fi = Rc::new(FaultInjector::default());
+        let _guard = install(fi.clone());
+        for _ in 0..3 {
+            assert_eq!(c.line_info(1).unwrap(), info);
+        }
+        assert!(fi.calls().is_empty());
+
+        // read from the kernel once invalidated
+        c.invalidate_info_cache();
+        assert_eq!(c.line_info(1).unwrap(), info);
+        assert_eq!(fi.calls().len(), 1);
+        assert_eq!(c.line_info(1).unwrap(), info);
+        assert_eq!(fi.calls().len(), 1);
+    }
+
+    #[test]
+    fn num_lines() {
+        let s = bag_of_chips();
+        for sc in s.chips() {
+            let c = Chip::from_path(sc.dev_path()).unwrap();
+            assert_eq!(c.num_lines(), sc.config().num_lines as usize);
+        }
+    }
+
+    #[test]
+    fn label() {
+        let s = bag_of_chips();
+        for sc in s.chips() {
+            let c = Chip::from_path(sc.dev_path()).unwrap();
+            assert_eq!(c.label(), sc.config().label);
+        }
+    }
+
     #[test]
     fn name() {
         let s = bag_of_chips();
@@ -250,6 +344,26 @@ mod chip {
         }
     }
 
+    fn line_info_all(abiv: gpiocdev::AbiVersion) {
+        let s = detailed_sim();
+        for sc in s.chips() {
+            let c = new_chip(sc.dev_path(), abiv);
+            let infos = c.line_info_all().unwrap();
+            assert_eq!(infos.len(), sc.config().num_lines as usize);
+            for (offset, info) in infos.iter().enumerate() {
+                let info = info.as_ref().unwrap();
+                let offset = offset as gpiocdev::line::Offset;
+                assert_eq!(info.offset, offset);
+                // named and unnamed lines
+                match sc.config().names.get(&offset) {
+                    Some(name) => assert_eq!(&info.name, name),
+                    None => assert!(info.name.is_empty()),
+                }
+                assert_eq!(info.used, sc.config().hogs.contains_key(&offset));
+            }
+        }
+    }
+
     fn line_info(abiv: gpiocdev::AbiVersion) {
         let s = detailed_sim();
         for sc in s.chips() {
@@ -293,7 +407,7 @@ mod chip {
             res,
             Err(gpiocdev::Error::Uapi(
                 gpiocdev::UapiCall::GetLineInfo,
-                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::EINVAL))
             ))
         );
 
@@ -435,11 +549,250 @@ mod chip {
         }
     }
 
+    fn line_output_value(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::Value;
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        assert!(c.line_output_value(4).is_err());
+
+        // the outputs are held by another request, so could not be requested
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_output(Value::Active)
+            .with_line(2)
+            .as_output(Value::Inactive)
+            .with_line(3)
+            .as_input()
+            .request()
+            .unwrap();
+
+        // the values are reported only if the kernel provides them,
+        // which only v2 can, but must match if provided.
+        for (offset, value) in [(1, Value::Active), (2, Value::Inactive)] {
+            if let Some(v) = c.line_output_value(offset).unwrap() {
+                assert_eq!(abiv, gpiocdev::AbiVersion::V2);
+                assert_eq!(v, value);
+            }
+        }
+        // inputs and unrequested lines never report a value
+        assert_eq!(c.line_output_value(3).unwrap(), None);
+        assert_eq!(c.line_output_value(0).unwrap(), None);
+
+        drop(req);
+    }
+
+    fn is_line_used(abiv: gpiocdev::AbiVersion) {
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        assert!(c.is_line_used(4).is_err());
+        assert!(!c.is_line_used(2).unwrap());
+
+        // held by a second handle
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(2)
+            .as_input()
+            .request()
+            .unwrap();
+        assert!(c.is_line_used(2).unwrap());
+        assert!(!c.is_line_used(1).unwrap());
+
+        drop(req);
+        assert!(!c.is_line_used(2).unwrap());
+    }
+
+    fn info_cache(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::{Direction, Value};
+
+        let s = Simpleton::new(4);
+        let mut c = new_chip(s.dev_path(), abiv);
+        c.enable_info_cache();
+
+        let info = c.line_info(1).unwrap();
+        assert!(!info.used);
+        assert_eq!(info.direction, Direction::Input);
+
+        // reconfigured externally, so updated by the info change event
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_consumer("cached")
+            .with_line(1)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+        let info = c.line_info(1).unwrap();
+        assert!(info.used);
+        assert_eq!(info.consumer.as_str(), "cached");
+        assert_eq!(info.direction, Direction::Output);
+
+        // the cached info matches the kernel
+        c.invalidate_info_cache();
+        assert_eq!(c.line_info(1).unwrap(), info);
+
+        drop(req);
+        assert!(!c.line_info(1).unwrap().used);
+
+        // lines that cannot be watched by the cache are not cached
+        assert!(c.watch_line_info(2).is_ok());
+        assert!(!c.line_info(2).unwrap().used);
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(2)
+            .as_input()
+            .request()
+            .unwrap();
+        assert!(c.line_info(2).unwrap().used);
+        drop(req);
+
+        // unwatched lines are removed from the cache
+        c.unwatch_line_info(1).unwrap();
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_input()
+            .request()
+            .unwrap();
+        assert!(c.line_info(1).unwrap().used);
+        drop(req);
+    }
+
+    fn line_info_flags(abiv: gpiocdev::AbiVersion) {
+        use gpiocdev::line::{Bias, Drive, Value};
+
+        let s = Simpleton::new(4);
+        let c = new_chip(s.dev_path(), abiv);
+        let f = std::fs::File::open(s.dev_path()).unwrap();
+
+        // unrequested
+        let info = c.line_info(0).unwrap();
+        check_flags(abiv, &f, &info);
+        assert!(!info.used);
+
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_output(Value::Active)
+            .as_active_low()
+            .with_drive(Drive::OpenDrain)
+            .with_bias(Bias::PullUp)
+            .with_line(2)
+            .as_input()
+            .with_bias(Bias::PullDown)
+            .with_line(3)
+            .as_output(Value::Inactive)
+            .with_drive(Drive::OpenSource)
+            .with_bias(Bias::Disabled)
+            .request()
+            .unwrap();
+
+        for offset in 1..4 {
+            let info = c.line_info(offset).unwrap();
+            check_flags(abiv, &f, &info);
+            assert!(info.used);
+        }
+        assert!(c.line_info(1).unwrap().active_low);
+
+        drop(req);
+    }
+
+    // check the raw flags match those reported by the kernel, and the decoded
+    // fields are consistent with them.
+    #[allow(unreachable_patterns)]
+    fn check_flags(abiv: gpiocdev::AbiVersion, f: &std::fs::File, info: &gpiocdev::line::Info) {
+        use gpiocdev::line::{Bias, Direction, Drive};
+
+        match abiv {
+            #[cfg(feature = "uapi_v1")]
+            gpiocdev::AbiVersion::V1 => {
+                use gpiocdev_uapi::v1::LineInfoFlags;
+
+                let flags = gpiocdev_uapi::v1::get_line_info(f, info.offset)
+                    .unwrap()
+                    .flags;
+                assert_eq!(info.raw_flags(), u64::from(flags.bits()));
+                assert_eq!(info.used, flags.contains(LineInfoFlags::USED));
+                assert_eq!(info.active_low, flags.contains(LineInfoFlags::ACTIVE_LOW));
+                assert_eq!(
+                    info.direction == Direction::Output,
+                    flags.contains(LineInfoFlags::OUTPUT)
+                );
+                assert_eq!(
+                    info.drive == Some(Drive::OpenDrain),
+                    flags.contains(LineInfoFlags::OPEN_DRAIN)
+                );
+                assert_eq!(
+                    info.drive == Some(Drive::OpenSource),
+                    flags.contains(LineInfoFlags::OPEN_SOURCE)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::PullUp),
+                    flags.contains(LineInfoFlags::BIAS_PULL_UP)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::PullDown),
+                    flags.contains(LineInfoFlags::BIAS_PULL_DOWN)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::Disabled),
+                    flags.contains(LineInfoFlags::BIAS_DISABLED)
+                );
+            }
+            #[cfg(feature = "uapi_v2")]
+            gpiocdev::AbiVersion::V2 => {
+                use gpiocdev_uapi::v2::LineFlags;
+
+                let flags = gpiocdev_uapi::v2::get_line_info(f, info.offset)
+                    .unwrap()
+                    .flags;
+                assert_eq!(info.raw_flags(), flags.bits());
+                assert_eq!(info.used, flags.contains(LineFlags::USED));
+                assert_eq!(info.active_low, flags.contains(LineFlags::ACTIVE_LOW));
+                assert_eq!(
+                    info.direction == Direction::Input,
+                    flags.contains(LineFlags::INPUT)
+                );
+                assert_eq!(
+                    info.direction == Direction::Output,
+                    flags.contains(LineFlags::OUTPUT)
+                );
+                assert_eq!(
+                    info.drive == Some(Drive::OpenDrain),
+                    flags.contains(LineFlags::OPEN_DRAIN)
+                );
+                assert_eq!(
+                    info.drive == Some(Drive::OpenSource),
+                    flags.contains(LineFlags::OPEN_SOURCE)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::PullUp),
+                    flags.contains(LineFlags::BIAS_PULL_UP)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::PullDown),
+                    flags.contains(LineFlags::BIAS_PULL_DOWN)
+                );
+                assert_eq!(
+                    info.bias == Some(Bias::Disabled),
+                    flags.contains(LineFlags::BIAS_DISABLED)
+                );
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(info.unknown_flags(), 0);
+    }
+
     #[cfg(feature = "uapi_v1")]
     mod uapi_v1 {
         common_tests! {
             gpiocdev::AbiVersion::V1,
             line_info,
+            line_info_all,
+            line_output_value,
+            is_line_used,
+            line_info_flags,
+            info_cache,
             watch_line_info,
             has_line_info_change_event,
             read_line_info_change_event,
@@ -453,6 +806,11 @@ mod chip {
         common_tests! {
             gpiocdev::AbiVersion::V2,
             line_info,
+            line_info_all,
+            line_output_value,
+            is_line_used,
+            line_info_flags,
+            info_cache,
             watch_line_info,
             has_line_info_change_event,
             read_line_info_change_event,
@@ -470,7 +828,7 @@ mod chip {
             c.watch_line_info(5),
             Err(gpiocdev::Error::Uapi(
                 gpiocdev::UapiCall::WatchLineInfo,
-                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::EINVAL))
             ))
         );
 
@@ -483,13 +841,11 @@ mod chip {
             .request()
             .unwrap();
 
-        let xinfo = gpiocdev::line::Info {
-            offset,
-            bias: Some(gpiocdev::line::Bias::PullUp),
-            consumer: "watch info".into(),
-            used: true,
-            ..Default::default()
-        };
+        let mut xinfo = gpiocdev::line::Info::default();
+        xinfo.offset = offset;
+        xinfo.bias = Some(gpiocdev::line::Bias::PullUp);
+        xinfo.consumer = "watch info".into();
+        xinfo.used = true;
         let res = c.watch_line_info(offset);
         assert_eq!(res, Ok(xinfo));
         assert_eq!(c.has_line_info_change_event(), Ok(false));
@@ -508,7 +864,7 @@ mod chip {
             c.unwatch_line_info(5),
             Err(gpiocdev::Error::Uapi(
                 gpiocdev::UapiCall::UnwatchLineInfo,
-                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
+                gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::EINVAL))
             ))
         );
 
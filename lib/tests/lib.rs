@@ -117,6 +117,133 @@ fn find_named_lines() {
     assert!(!found.contains_key(&"fls nada"));
 }
 
+#[test]
+fn find_named_lines_duplicated_on_chip() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "find_dup 1")
+                .name(2, "fld cherry")
+                .name(5, "fld cherry")
+                .name(6, "fld grape"),
+        )
+        .live()
+        .unwrap();
+
+    // strict detects names repeated at multiple offsets on the one chip
+    let found = gpiocdev::find_named_lines(&["fld grape", "fld cherry"], true);
+    assert_eq!(
+        found,
+        Err(gpiocdev::Error::NonuniqueLineName("fld cherry".to_string()))
+    );
+
+    // otherwise the first is returned
+    let found = gpiocdev::find_named_lines(&["fld grape", "fld cherry"], false).unwrap();
+    assert_eq!(found.len(), 2);
+    let l = found.get(&"fld cherry").unwrap();
+    assert_eq!(&l.chip, sim.chips()[0].dev_path());
+    assert_eq!(l.info.offset, 2);
+}
+
+#[test]
+fn lines_where() {
+    let sim = gpiosim::builder()
+        .with_bank(
+            Bank::new(8, "lines_where 1")
+                .name(3, "lw LED_1")
+                .name(6, "lw LED_2")
+                .name(7, "lw button"),
+        )
+        .with_bank(
+            Bank::new(42, "lines_where 2")
+                .name(3, "lw LED_1")
+                .name(4, "lw LED_3"),
+        )
+        .live()
+        .unwrap();
+    let c0 = sim.chips()[0].dev_path();
+    let c1 = sim.chips()[1].dev_path();
+
+    // by line name, across chips
+    let found = gpiocdev::lines_where(|_, li| li.name.starts_with("lw LED_")).unwrap();
+    assert_eq!(found.len(), 4);
+    let offsets = |chip: &std::path::Path| -> Vec<u32> {
+        found
+            .iter()
+            .filter(|l| l.chip == chip)
+            .map(|l| l.info.offset)
+            .collect()
+    };
+    assert_eq!(offsets(c0), [3, 6]);
+    assert_eq!(offsets(c1), [3, 4]);
+
+    // by chip and line
+    let held = gpiocdev::Request::builder()
+        .on_chip(c0)
+        .with_line(6)
+        .as_input()
+        .request()
+        .unwrap();
+    let found = gpiocdev::lines_where(|ci, li| {
+        ci.label == "lines_where 1" && li.name.starts_with("lw LED_") && !li.used
+    })
+    .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(&found[0].chip, c0);
+    assert_eq!(found[0].info.offset, 3);
+    assert_eq!(found[0].info.name, "lw LED_1");
+    drop(held);
+
+    // no matches
+    let found = gpiocdev::lines_where(|_, li| li.name == "lw nada").unwrap();
+    assert!(found.is_empty());
+}
+
+#[test]
+fn chips() {
+    let sim = gpiosim::builder()
+        .with_bank(&Bank::new(4, "chips 1"))
+        .with_bank(&Bank::new(8, "chips 2"))
+        .live()
+        .unwrap();
+
+    let chips: Vec<gpiocdev::Chip> = gpiocdev::chips().unwrap().flatten().collect();
+    for c in sim.chips() {
+        assert!(chips.iter().any(|chip| chip.path() == c.dev_path()));
+    }
+    for pair in chips.windows(2) {
+        assert_eq!(path_compare(pair[0].path(), pair[1].path()), Ordering::Less);
+    }
+}
+
+#[test]
+#[cfg(feature = "chip_watch")]
+fn chips_watch() {
+    use gpiocdev::chip::ChipEvent;
+    use std::time::{Duration, Instant};
+
+    let mut watcher = gpiocdev::chips_watch().unwrap();
+    // other tests may be adding and removing chips concurrently
+    let mut wait_for = |expected: ChipEvent| {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            assert!(watcher.wait_event(remaining).unwrap(), "{:?}", expected);
+            if watcher.read_event().unwrap() == expected {
+                return;
+            }
+        }
+    };
+
+    let sim = gpiosim::Simpleton::new(4);
+    let path = sim.dev_path().to_owned();
+    wait_for(ChipEvent::Added(path.clone()));
+    // usable when reported
+    assert!(gpiocdev::Chip::from_path(&path).is_ok());
+
+    drop(sim);
+    wait_for(ChipEvent::Removed(path));
+}
+
 #[test]
 fn detect_abi_version() {
     // assumes a kernel with both v1 and v2 supported.
@@ -50,7 +50,10 @@ mod builder {
             request_found_lines,
             request_output_lines,
             request_mixed_config,
-            request_invalid_offset
+            request_invalid_offset,
+            request_busy_line,
+            request_retry_busy_line,
+            request_retry_invalid_offset
         }
 
         #[test]
@@ -181,7 +184,10 @@ mod builder {
             request_found_lines,
             request_output_lines,
             request_mixed_config,
-            request_invalid_offset
+            request_invalid_offset,
+            request_busy_line,
+            request_retry_busy_line,
+            request_retry_invalid_offset
         }
 
         #[test]
@@ -229,6 +235,38 @@ mod builder {
             drop(req);
         }
 
+        #[test]
+        fn request_edge_detection() {
+            let s = Simpleton::new(10);
+            let c = Chip::from_path(s.dev_path()).unwrap();
+            let offset = 1;
+
+            // switch the line to output - sim maintains direction when released.
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .as_output(Value::Active)
+                .request()
+                .unwrap();
+            drop(req);
+            assert_eq!(c.line_info(offset).unwrap().direction, Direction::Output);
+
+            // edge detection implies input
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .request()
+                .unwrap();
+
+            let info = c.line_info(offset).unwrap();
+            assert_eq!(info.direction, Direction::Input);
+            assert_eq!(info.edge_detection, Some(EdgeDetection::BothEdges));
+            assert_eq!(info.event_clock, Some(EventClock::Monotonic));
+
+            drop(req);
+        }
+
         #[test]
         fn request_debounced_zero() {
             let s = Simpleton::new(10);
@@ -301,7 +339,7 @@ mod builder {
                 res.unwrap_err(),
                 gpiocdev::Error::Uapi(
                     gpiocdev::UapiCall::GetLine,
-                    gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(95))
+                    gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(libc::EOPNOTSUPP))
                 )
             );
         }
@@ -575,7 +613,7 @@ mod builder {
         let req = builder
             .on_chip(s.dev_path())
             .with_line(l_out)
-            .as_output(Value::Inactive)
+            .as_output(Value::Active)
             .request()
             .unwrap();
         drop(req);
@@ -590,6 +628,9 @@ mod builder {
         assert_eq!(info.direction, Direction::Input);
         let info = c.line_info(l_out).unwrap();
         assert_eq!(info.direction, Direction::Output);
+        // the sensed level of a push-pull output is the value it drives
+        assert_eq!(s.get_level(l_out).unwrap(), gpiosim::Level::High);
+        assert_eq!(req.value(l_out), Ok(Value::Active));
 
         drop(req);
     }
@@ -606,12 +647,11 @@ mod builder {
             .unwrap();
         let s = &sim.chips()[0];
 
+        let mut info = Info::default();
+        info.offset = 3;
         let line = FoundLine {
             chip: s.dev_path().clone(),
-            info: Info {
-                offset: 3,
-                ..Default::default()
-            },
+            info,
         };
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
@@ -628,31 +668,17 @@ mod builder {
     fn request_found_lines(abiv: AbiVersion) {
         let s = Simpleton::new(8);
 
-        let mut lines: HashMap<&str, FoundLine> = [
-            (
-                "three",
-                FoundLine {
-                    chip: s.dev_path().clone(),
-                    info: Info {
-                        offset: 3,
-                        ..Default::default()
-                    },
-                },
-            ),
-            (
-                "five",
-                FoundLine {
-                    chip: s.dev_path().clone(),
-                    info: Info {
-                        offset: 5,
-                        ..Default::default()
-                    },
-                },
-            ),
-        ]
-        .iter()
-        .cloned()
-        .collect();
+        let found = |offset| {
+            let mut info = Info::default();
+            info.offset = offset;
+            FoundLine {
+                chip: s.dev_path().clone(),
+                info,
+            }
+        };
+        let mut lines: HashMap<&str, FoundLine> = [("three", found(3)), ("five", found(5))]
+            .into_iter()
+            .collect();
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
         builder.using_abi_version(abiv);
@@ -744,42 +770,145 @@ mod builder {
         }
     }
 
+    #[allow(unused_variables)]
     fn request_invalid_offset(abiv: AbiVersion) {
-        let s = Simpleton::new(3);
+        let s = Simpleton::new(4);
+        let out_of_range = gpiocdev::Error::Request(
+            s.dev_path().to_owned(),
+            gpiocdev::request::ErrorKind::OffsetOutOfRange {
+                offset: 999,
+                num_lines: 4,
+            },
+        );
 
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
         builder.using_abi_version(abiv);
-        builder.on_chip(s.dev_path()).with_line(5).as_input();
+        builder
+            .on_chip(s.dev_path())
+            .with_lines(&[1, 999])
+            .as_input();
         let res = builder.request().unwrap_err();
-        if abiv == AbiVersion::V2 {
-            assert_eq!(
-                res,
-                gpiocdev::Error::Uapi(
-                    gpiocdev::UapiCall::GetLine,
-                    gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
-                )
-            );
-        } else {
-            assert_eq!(
-                res,
-                gpiocdev::Error::Uapi(
-                    gpiocdev::UapiCall::GetLineHandle,
-                    gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
-                )
-            );
-            let res = builder
-                .with_edge_detection(EdgeDetection::BothEdges)
-                .request()
-                .unwrap_err();
-            assert_eq!(
-                res,
-                gpiocdev::Error::Uapi(
-                    gpiocdev::UapiCall::GetLineEvent,
-                    gpiocdev_uapi::Error::Os(gpiocdev_uapi::Errno(22))
-                )
-            );
-        }
+        assert_eq!(res, out_of_range);
+        assert_eq!(
+            res.to_string(),
+            format!(
+                "\"{}\" offset 999 is out of range - the chip has 4 lines.",
+                s.dev_path().display()
+            )
+        );
+        let res = builder
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap_err();
+        assert_eq!(res, out_of_range);
+
+        // the first offset beyond the chip
+        let res = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(4)
+            .as_input()
+            .request()
+            .unwrap_err();
+        assert_eq!(
+            res,
+            gpiocdev::Error::Request(
+                s.dev_path().to_owned(),
+                gpiocdev::request::ErrorKind::OffsetOutOfRange {
+                    offset: 4,
+                    num_lines: 4
+                },
+            )
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn request_busy_line(abiv: AbiVersion) {
+        let s = Simpleton::new(5);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let _held = builder
+            .on_chip(s.dev_path())
+            .with_line(3)
+            .as_input()
+            .request()
+            .unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let res = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[1, 2, 3])
+            .as_input()
+            .request()
+            .unwrap_err();
+        assert_eq!(
+            res,
+            gpiocdev::Error::Request(
+                s.dev_path().to_owned(),
+                gpiocdev::request::ErrorKind::LineBusy(3)
+            )
+        );
+        assert_eq!(
+            res.to_string(),
+            format!("\"{}\" line 3 is busy.", s.dev_path().display())
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn request_retry_busy_line(abiv: AbiVersion) {
+        let s = Simpleton::new(5);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let held = builder
+            .on_chip(s.dev_path())
+            .with_line(3)
+            .as_input()
+            .request()
+            .unwrap();
+
+        // release the line after the first attempt has failed
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(30));
+            drop(held);
+        });
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[2, 3])
+            .as_input()
+            .with_retry(10, Duration::from_millis(20))
+            .request()
+            .unwrap();
+        releaser.join().unwrap();
+        assert_eq!(req.config().lines(), &[2, 3]);
+    }
+
+    #[allow(unused_variables)]
+    fn request_retry_invalid_offset(abiv: AbiVersion) {
+        let s = Simpleton::new(5);
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let start = std::time::Instant::now();
+        let res = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[2, 6])
+            .as_input()
+            .with_retry(5, Duration::from_secs(1))
+            .request();
+        // not retried
+        assert!(res.is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
     }
 
     #[test]
@@ -805,7 +934,7 @@ mod builder {
             .request();
         assert_eq!(
             res.unwrap_err(),
-            gpiocdev::Error::Os(gpiocdev_uapi::Errno(2))
+            gpiocdev::Error::Os(gpiocdev_uapi::Errno(libc::ENOENT))
         );
     }
 
@@ -876,12 +1005,56 @@ mod request {
             set_value,
             set_lone_value,
             set_values,
+            set_values_leaves_others,
+            toggle_values,
+            toggle_values_open_drain,
+            chip_removed,
+            release,
+            value_sensed_open_drain,
+            value_sensed_open_source,
             reconfigure,
+            reconfigure_if_changed,
+            set_direction,
             has_edge_event,
             wait_edge_event,
             read_edge_event,
+            detected_event_clock,
             new_edge_event_buffer,
-            read_edge_events_into_slice
+            read_edge_events_into_slice,
+            read_edge_events_into,
+            epoll,
+            poll,
+            watch_values
+        }
+
+        #[test]
+        fn edge_event_seqnos_increment() {
+            let s = Simpleton::new(3);
+            let offsets = &[1, 2];
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_lines(offsets)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .request()
+                .unwrap();
+
+            for _ in 0..5 {
+                for offset in offsets {
+                    s.toggle(*offset).unwrap();
+                    wait_propagation_delay();
+                }
+            }
+
+            let mut iter = req.edge_events();
+            for seqno in 1..=10 {
+                let evt = iter.next().unwrap().unwrap();
+                assert_eq!(evt.offset, offsets[(seqno as usize - 1) % 2]);
+                assert_eq!(evt.seqno, seqno);
+                assert_eq!(evt.line_seqno, (seqno + 1) / 2);
+            }
+            assert!(!req.has_edge_event().unwrap());
         }
 
         #[test]
@@ -898,6 +1071,37 @@ mod request {
             assert_eq!(req.chip_path().as_os_str(), s.dev_path());
         }
 
+        #[test]
+        fn set_values_two_lines() {
+            use gpiocdev::line::{Value, Values};
+            use gpiosim::Level;
+
+            let s = Simpleton::new(3);
+            let offsets = &[0, 2];
+
+            let mut builder = Request::builder();
+            #[cfg(feature = "uapi_v2")]
+            builder.using_abi_version(V1);
+            let req = builder
+                .on_chip(s.dev_path())
+                .with_lines(offsets)
+                .as_output(Value::Active)
+                .request()
+                .unwrap();
+
+            let mut vals = Values::default();
+            vals.set(2, Value::Inactive);
+            assert!(req.set_values(&vals).is_ok());
+            assert_eq!(s.get_level(0).unwrap(), Level::High);
+            assert_eq!(s.get_level(2).unwrap(), Level::Low);
+
+            let mut vals = Values::default();
+            vals.set(0, Value::Inactive);
+            assert!(req.set_values(&vals).is_ok());
+            assert_eq!(s.get_level(0).unwrap(), Level::Low);
+            assert_eq!(s.get_level(2).unwrap(), Level::Low);
+        }
+
         #[test]
         fn reconfigure_edge_detection_change() {
             let s = Simpleton::new(20);
@@ -1059,6 +1263,7 @@ mod request {
     #[cfg(feature = "uapi_v2")]
     mod uapi_v2 {
         use crate::common::wait_propagation_delay;
+        use crate::EVENT_WAIT_TIMEOUT;
         use gpiocdev::line::{EdgeDetection, EdgeKind, Offset};
         use gpiocdev::request::Request;
         use gpiosim::Simpleton;
@@ -1072,12 +1277,26 @@ mod request {
             set_value,
             set_lone_value,
             set_values,
+            set_values_leaves_others,
+            toggle_values,
+            toggle_values_open_drain,
+            chip_removed,
+            release,
+            value_sensed_open_drain,
+            value_sensed_open_source,
             reconfigure,
+            reconfigure_if_changed,
+            set_direction,
             has_edge_event,
             wait_edge_event,
             read_edge_event,
+            detected_event_clock,
             new_edge_event_buffer,
-            read_edge_events_into_slice
+            read_edge_events_into_slice,
+            read_edge_events_into,
+            epoll,
+            poll,
+            watch_values
         }
 
         #[test]
@@ -1104,6 +1323,153 @@ mod request {
             );
         }
 
+        #[test]
+        fn read_edge_event_after_overflow() {
+            let s = Simpleton::new(3);
+            let offset = 1;
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .with_kernel_event_buffer_size(2)
+                .request()
+                .unwrap();
+
+            // overflow the kernel buffer
+            for _ in 0..3 {
+                s.pullup(offset).unwrap();
+                wait_propagation_delay();
+                s.pulldown(offset).unwrap();
+                wait_propagation_delay();
+            }
+
+            // the oldest events were discarded
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(evt.seqno, 5);
+            assert_eq!(evt.line_seqno, 5);
+            assert_eq!(evt.lost_since(None), Some(4));
+            let evt2 = req.read_edge_event().unwrap();
+            assert_eq!(evt2.seqno, 6);
+            assert_eq!(evt2.lost_since(Some(&evt)), Some(0));
+            assert_eq!(req.has_edge_event(), Ok(false));
+        }
+
+        #[test]
+        #[cfg(feature = "mockable")]
+        fn read_edge_events_into_partial_event() {
+            use gpiocdev_uapi::mock::{install, FaultInjector, Response};
+            use std::rc::Rc;
+
+            let s = Simpleton::new(3);
+            let offset = 1;
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .request()
+                .unwrap();
+
+            let fi = Rc::new(FaultInjector::default());
+            let _guard = install(fi.clone());
+            // one and a bit events
+            let size = req.edge_event_size();
+            fi.push_response(Response::Data(vec![0; size + 8]));
+            let mut events = Vec::new();
+            assert_eq!(
+                req.read_edge_events_into(&mut events, 2),
+                Err(gpiocdev::Error::UnexpectedResponse(format!(
+                    "read a partial edge event - {} bytes read for {} byte events.",
+                    size + 8,
+                    size
+                )))
+            );
+            assert!(events.is_empty());
+        }
+
+        #[test]
+        fn read_edge_event_realtime() {
+            use gpiocdev::line::EventClock;
+            use std::time::SystemTime;
+
+            let s = Simpleton::new(3);
+            let offset = 1;
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .with_event_clock(EventClock::Realtime)
+                .request()
+                .unwrap();
+
+            let start = SystemTime::now();
+            s.pullup(offset).unwrap();
+            assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+            let evt = req.read_edge_event().unwrap();
+            s.pulldown(offset).unwrap();
+            assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+            let evt2 = req.read_edge_event().unwrap();
+            let end = SystemTime::now();
+
+            let ts = evt.timestamp();
+            assert_eq!(ts.clock, EventClock::Realtime);
+            assert_eq!(ts.as_duration_since_boot(), None);
+            let t = ts.as_system_time().unwrap();
+            assert!(t >= start);
+            assert!(t <= end);
+            let t2 = evt2.timestamp().as_system_time().unwrap();
+            assert!(t2 >= t);
+            assert!(t2 <= end);
+            assert_eq!(evt2.timestamp() - ts, t2.duration_since(t).unwrap());
+        }
+
+        #[test]
+        fn reconfigure_edge_detection_retains_events() {
+            let s = Simpleton::new(3);
+            let offset = 1;
+
+            let req = Request::builder()
+                .on_chip(s.dev_path())
+                .with_line(offset)
+                .as_input()
+                .with_edge_detection(EdgeDetection::BothEdges)
+                .request()
+                .unwrap();
+
+            // create two events
+            s.pullup(offset).unwrap();
+            wait_propagation_delay();
+            s.pulldown(offset).unwrap();
+            wait_propagation_delay();
+
+            let mut cfg = req.config();
+            cfg.with_edge_detection(EdgeDetection::FallingEdge);
+            assert!(req.reconfigure(&cfg).is_ok());
+
+            // events buffered before the reconfigure are still available
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(evt.offset, offset);
+            assert_eq!(evt.kind, EdgeKind::Rising);
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(evt.offset, offset);
+            assert_eq!(evt.kind, EdgeKind::Falling);
+            assert_eq!(req.has_edge_event(), Ok(false));
+
+            // and subsequent events follow the new edge detection
+            s.pullup(offset).unwrap();
+            assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(false));
+            s.pulldown(offset).unwrap();
+            assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(evt.offset, offset);
+            assert_eq!(evt.kind, EdgeKind::Falling);
+        }
+
         #[test]
         fn reconfigure_ignores_unrequested_lines() {
             let s = Simpleton::new(20);
@@ -1449,28 +1815,23 @@ mod request {
             .request()
             .unwrap();
 
-        if abiv == AbiVersion::V2 {
-            for offset in offsets {
-                assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::Low);
+        for offset in offsets {
+            assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::Low);
 
-                assert!(req.set_value(*offset, Value::Active).is_ok());
-                assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::High);
+            assert!(req.set_value(*offset, Value::Active).is_ok());
+            assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::High);
 
-                assert!(req.set_value(*offset, Value::Inactive).is_ok());
-                assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::Low);
-            }
-        } else {
-            for offset in offsets {
-                assert_eq!(
-                    req.set_value(*offset, Value::Active).unwrap_err(),
-                    gpiocdev::Error::AbiLimitation(
-                        AbiVersion::V1,
-                        "requires all requested lines".into(),
-                    )
-                );
-            }
+            assert!(req.set_value(*offset, Value::Inactive).is_ok());
+            assert_eq!(s.get_level(*offset).unwrap(), gpiosim::Level::Low);
         }
 
+        // other lines left unchanged
+        assert!(req.set_value(0, Value::Active).is_ok());
+        assert!(req.set_value(2, Value::Active).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), gpiosim::Level::High);
+        assert_eq!(s.get_level(1).unwrap(), gpiosim::Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), gpiosim::Level::High);
+
         // invalid offset
         let res = req.set_value(3, Value::Active);
         assert_eq!(
@@ -1520,6 +1881,7 @@ mod request {
             );
     }
 
+    #[allow(unused_variables)]
     fn set_values(abiv: AbiVersion) {
         use gpiosim::Level;
 
@@ -1559,64 +1921,39 @@ mod request {
         assert_eq!(s.get_level(1).unwrap(), Level::High);
         assert_eq!(s.get_level(3).unwrap(), Level::Low);
 
-        if abiv == AbiVersion::V2 {
-            // subset
-            let mut vals = Values::default();
-            vals.set(1, Value::Inactive);
-            vals.set(3, Value::Active);
-            assert!(req.set_values(&vals).is_ok());
-            assert_eq!(s.get_level(0).unwrap(), Level::High);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::High);
-
-            // singular
-            let mut vals = Values::default();
-            vals.set(3, Value::Inactive);
-            assert!(req.set_values(&vals).is_ok());
-            assert_eq!(s.get_level(0).unwrap(), Level::High);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::Low);
+        // subset
+        let mut vals = Values::default();
+        vals.set(1, Value::Inactive);
+        vals.set(3, Value::Active);
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), Level::High);
 
-            // empty set
-            let vals = Values::default();
-            assert!(req.set_values(&vals).is_ok());
-            assert_eq!(s.get_level(0).unwrap(), Level::Low);
-            assert_eq!(s.get_level(1).unwrap(), Level::Low);
-            assert_eq!(s.get_level(3).unwrap(), Level::Low);
-        } else {
-            // subset
-            let mut vals = Values::default();
-            vals.set(1, Value::Inactive);
-            vals.set(3, Value::Active);
-            assert_eq!(
-                req.set_values(&vals).unwrap_err(),
-                gpiocdev::Error::AbiLimitation(
-                    AbiVersion::V1,
-                    "requires all requested lines".into(),
-                )
-            );
+        // singular
+        let mut vals = Values::default();
+        vals.set(3, Value::Inactive);
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), Level::Low);
 
-            // singular
-            let mut vals = Values::default();
-            vals.set(3, Value::Inactive);
-            assert_eq!(
-                req.set_values(&vals).unwrap_err(),
-                gpiocdev::Error::AbiLimitation(
-                    AbiVersion::V1,
-                    "requires all requested lines".into(),
-                )
-            );
+        // empty set - all lines inactive
+        let vals = Values::default();
+        assert!(req.set_values(&vals).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), Level::Low);
+        assert_eq!(s.get_level(3).unwrap(), Level::Low);
 
-            // empty set
-            let vals = Values::default();
-            assert_eq!(
-                req.set_values(&vals),
-                Err(gpiocdev::Error::AbiLimitation(
-                    AbiVersion::V1,
-                    "requires all requested lines".into()
-                ))
-            );
-        }
+        // no requested lines
+        let mut vals = Values::default();
+        vals.set(2, Value::Active);
+        assert_eq!(
+            req.set_values(&vals),
+            Err(gpiocdev::Error::InvalidArgument(
+                "no requested lines in set values.".into()
+            ))
+        );
 
         // invalid offset - ignored
         let mut vals = Values::from_offsets(&[0, 1, 2, 3]);
@@ -1628,6 +1965,323 @@ mod request {
         assert_eq!(s.get_level(3).unwrap(), Level::Low);
     }
 
+    #[allow(unused_variables)]
+    fn set_values_leaves_others(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(3);
+        let offsets = &[0, 1, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        for offset in offsets {
+            let mut vals = Values::default();
+            vals.set(*offset, Value::Inactive);
+            assert!(req.set_values(&vals).is_ok());
+            for o in offsets {
+                let level = if o <= offset { Level::Low } else { Level::High };
+                assert_eq!(s.get_level(*o).unwrap(), level);
+            }
+        }
+
+        // values subset only reads the subset
+        let mut vals = Values::default();
+        vals.set(1, Value::Active);
+        assert!(req.set_values(&vals).is_ok());
+        let mut vals = Values::from_offsets(&[1]);
+        assert!(req.values(&mut vals).is_ok());
+        assert_eq!(vals.get(0), None);
+        assert_eq!(vals.get(1), Some(Value::Active));
+        assert_eq!(vals.get(2), None);
+        assert_eq!(s.get_level(0).unwrap(), Level::Low);
+        assert_eq!(s.get_level(1).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::Low);
+    }
+
+    #[allow(unused_variables)]
+    fn toggle_values(abiv: AbiVersion) {
+        use gpiosim::Level;
+
+        let s = Simpleton::new(3);
+        let offsets = &[0, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        // all lines
+        assert!(req.toggle_values().is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+
+        // one line
+        assert!(req.toggle_line(2).is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::High);
+        assert_eq!(s.get_level(2).unwrap(), Level::Low);
+
+        // lines with differing values
+        assert!(req.toggle_values().is_ok());
+        assert_eq!(s.get_level(0).unwrap(), Level::Low);
+        assert_eq!(s.get_level(2).unwrap(), Level::High);
+
+        // invalid offset
+        assert_eq!(
+            req.toggle_line(1),
+            Err(gpiocdev::Error::InvalidArgument(
+                "offset is not a requested line.".into()
+            ))
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn toggle_values_open_drain(abiv: AbiVersion) {
+        use gpiocdev::line::Drive;
+        use gpiosim::Level;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Active)
+            .with_drive(Drive::OpenDrain)
+            .request()
+            .unwrap();
+
+        // released, so senses the pull, but toggles the value set
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset), Ok(Value::Inactive));
+        assert!(req.toggle_values().is_ok());
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        assert_eq!(
+            req.config().line_config(offset).unwrap().value,
+            Some(Value::Inactive)
+        );
+
+        // driven low, so toggles back to released
+        assert!(req.toggle_line(offset).is_ok());
+        assert_eq!(
+            req.config().line_config(offset).unwrap().value,
+            Some(Value::Active)
+        );
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+    }
+
+    #[allow(unused_variables)]
+    fn chip_removed(abiv: AbiVersion) {
+        use gpiocdev::chip::ErrorKind;
+
+        let s = Simpleton::new(3);
+        let path = s.dev_path().to_owned();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let oreq = builder
+            .on_chip(&path)
+            .with_line(1)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+        let ireq = builder
+            .on_chip(&path)
+            .with_line(2)
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        // unplug the chip
+        drop(s);
+
+        let removed = Err(gpiocdev::Error::GpioChip(path, ErrorKind::Removed));
+        assert_eq!(oreq.set_value(1, Value::Active), removed);
+        assert_eq!(oreq.value(1).map(|_| ()), removed);
+        // the request is readable, but only to report the removal
+        assert_eq!(ireq.wait_edge_event(Duration::from_millis(10)), Ok(true));
+        assert_eq!(ireq.read_edge_event().map(|_| ()), removed);
+        assert_eq!(ireq.edge_events().read_event().map(|_| ()), removed);
+    }
+
+    #[allow(unused_variables)]
+    fn release(abiv: AbiVersion) {
+        use gpiocdev::chip::Chip;
+
+        let s = Simpleton::new(3);
+        let c = Chip::from_path(s.dev_path()).unwrap();
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        builder
+            .on_chip(s.dev_path())
+            .with_line(1)
+            .as_output(Value::Active);
+
+        let req = builder.request().unwrap();
+        assert!(c.line_info(1).unwrap().used);
+        assert_eq!(req.release(), Ok(()));
+        assert!(!c.line_info(1).unwrap().used);
+
+        // the line is free to be requested again
+        let req = builder.request().unwrap();
+        assert!(c.line_info(1).unwrap().used);
+        assert_eq!(req.release(), Ok(()));
+    }
+
+    #[allow(unused_variables)]
+    fn value_sensed_open_drain(abiv: AbiVersion) {
+        use gpiocdev::line::Drive;
+        use gpiosim::Level;
+
+        let s = Simpleton::new(4);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Active)
+            .with_drive(Drive::OpenDrain)
+            .request()
+            .unwrap();
+
+        // released, so follows the pull
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset), Ok(Value::Inactive));
+        assert_eq!(
+            req.config().line_config(offset).unwrap().value,
+            Some(Value::Active)
+        );
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset), Ok(Value::Active));
+
+        // driven low, so ignores the pull
+        req.set_value(offset, Value::Inactive).unwrap();
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+        assert_eq!(req.value(offset), Ok(Value::Inactive));
+    }
+
+    #[allow(unused_variables)]
+    fn value_sensed_open_source(abiv: AbiVersion) {
+        use gpiocdev::line::Drive;
+        use gpiosim::Level;
+
+        let s = Simpleton::new(4);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_output(Value::Inactive)
+            .with_drive(Drive::OpenSource)
+            .request()
+            .unwrap();
+
+        // released, so follows the pull
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset), Ok(Value::Active));
+        assert_eq!(
+            req.config().line_config(offset).unwrap().value,
+            Some(Value::Inactive)
+        );
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset), Ok(Value::Inactive));
+
+        // driven high, so ignores the pull
+        req.set_value(offset, Value::Active).unwrap();
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+        assert_eq!(req.value(offset), Ok(Value::Active));
+    }
+
+    #[allow(unused_variables)]
+    fn watch_values(abiv: AbiVersion) {
+        use std::sync::mpsc;
+
+        let s = Simpleton::new(4);
+        let offsets = &[1, 2];
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(offsets)
+            .as_input()
+            .request()
+            .unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = req
+            .watch_values(Duration::from_millis(5), move |offset, value| {
+                tx.send((offset, value)).unwrap()
+            })
+            .unwrap();
+        assert!(!watcher.is_finished());
+
+        // no change
+        assert!(rx.recv_timeout(Duration::from_millis(30)).is_err());
+
+        s.pullup(2).unwrap();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(100)),
+            Ok((2, Value::Active))
+        );
+        // only once per change
+        assert!(rx.recv_timeout(Duration::from_millis(30)).is_err());
+
+        s.pulldown(2).unwrap();
+        s.pullup(1).unwrap();
+        let mut changes = vec![
+            rx.recv_timeout(Duration::from_millis(100)).unwrap(),
+            rx.recv_timeout(Duration::from_millis(100)).unwrap(),
+        ];
+        changes.sort_by_key(|(offset, _)| *offset);
+        assert_eq!(changes, vec![(1, Value::Active), (2, Value::Inactive)]);
+
+        assert_eq!(watcher.stop(), Ok(()));
+        // callback dropped with the watcher thread
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(30)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
     #[allow(unused_variables)]
     fn reconfigure(abiv: AbiVersion) {
         use gpiocdev::line::{Bias, Direction, Drive};
@@ -1676,14 +2330,248 @@ mod request {
     }
 
     #[allow(unused_variables)]
-    fn has_edge_event(abiv: AbiVersion) {
+    fn set_direction(abiv: AbiVersion) {
+        use gpiocdev::line::{Bias, Direction, Value};
+        use gpiosim::Level;
+
+        let s = Simpleton::new(5);
+        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .with_bias(Bias::PullDown)
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+
+        let info = c.line_info(offset).unwrap();
+        assert_eq!(info.direction, Direction::Output);
+        assert_eq!(info.bias, Some(Bias::PullDown));
+        assert_eq!(s.get_level(offset).unwrap(), Level::High);
+
+        // to input
+        assert!(req.set_direction(offset, Direction::Input).is_ok());
+        let info = c.line_info(offset).unwrap();
+        assert_eq!(info.direction, Direction::Input);
+        assert_eq!(info.bias, Some(Bias::PullDown));
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset).unwrap(), Value::Active);
+        s.pulldown(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.value(offset).unwrap(), Value::Inactive);
+
+        // unchanged
+        assert!(req.set_direction(offset, Direction::Input).is_ok());
+        assert_eq!(
+            req.line_config(offset).unwrap().direction,
+            Some(Direction::Input)
+        );
+
+        // back to output
+        s.pullup(offset).unwrap();
+        assert!(req.set_direction(offset, Direction::Output).is_ok());
+        let info = c.line_info(offset).unwrap();
+        assert_eq!(info.direction, Direction::Output);
+        assert_eq!(info.bias, Some(Bias::PullDown));
+        assert_eq!(s.get_level(offset).unwrap(), Level::Low);
+
+        // not a requested line
+        assert_eq!(
+            req.set_direction(1, Direction::Input)
+                .unwrap_err()
+                .to_string(),
+            "offset is not a requested line."
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn reconfigure_if_changed(abiv: AbiVersion) {
+        use gpiocdev::line::{Bias, InfoChangeKind};
+
+        let s = Simpleton::new(5);
+        let c = gpiocdev::chip::Chip::from_path(s.dev_path()).unwrap();
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_lines(&[offset, 3])
+            .as_output(Value::Active)
+            .with_bias(Bias::PullDown)
+            .request()
+            .unwrap();
+        assert!(c.watch_line_info(offset).is_ok());
+
+        // unchanged
+        let mut cfg = req.config();
+        assert_eq!(req.reconfigure_if_changed(&cfg), Ok(false));
+        wait_propagation_delay();
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+
+        // membership change
+        cfg.with_line(2);
+        assert_eq!(
+            req.reconfigure_if_changed(&cfg),
+            Err(gpiocdev::Error::InvalidArgument(
+                "cannot add or remove lines from a request".into()
+            ))
+        );
+        wait_propagation_delay();
+        assert_eq!(c.has_line_info_change_event(), Ok(false));
+
+        // flags change
+        cfg.without_line(2)
+            .with_line(offset)
+            .with_bias(Bias::PullUp);
+        assert_eq!(req.reconfigure_if_changed(&cfg), Ok(true));
+        wait_propagation_delay();
+        assert_eq!(c.has_line_info_change_event(), Ok(true));
+        let evt = c.read_line_info_change_event().unwrap();
+        assert_eq!(evt.kind, InfoChangeKind::Reconfigured);
+        assert_eq!(evt.info.bias, Some(Bias::PullUp));
+    }
+
+    #[allow(unused_variables)]
+    fn has_edge_event(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        assert_eq!(req.has_edge_event(), Ok(false));
+
+        s.pullup(offset).unwrap();
+        wait_propagation_delay();
+        assert_eq!(req.has_edge_event(), Ok(true));
+    }
+
+    #[allow(unused_variables)]
+    fn wait_edge_event(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(false));
+
+        s.pullup(offset).unwrap();
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+    }
+
+    #[allow(unused_variables)]
+    fn read_edge_event(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+
+        s.pullup(offset).unwrap();
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        let evt = req.read_edge_event().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        assert_eq!(evt.offset, offset);
+        if abiv == AbiVersion::V2 {
+            assert_eq!(evt.line_seqno, 1);
+            assert_eq!(evt.seqno, 1);
+        } else {
+            assert_eq!(evt.line_seqno, 0);
+            assert_eq!(evt.seqno, 0);
+        }
+
+        s.pulldown(offset).unwrap();
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        let evt = req.read_edge_event().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Falling);
+        if abiv == AbiVersion::V2 {
+            assert_eq!(evt.line_seqno, 2);
+            assert_eq!(evt.seqno, 2);
+        } else {
+            assert_eq!(evt.line_seqno, 0);
+            assert_eq!(evt.seqno, 0);
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn detected_event_clock(abiv: AbiVersion) {
+        let s = Simpleton::new(3);
+        let offset = 1;
+
+        let mut builder = Request::builder();
+        #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+        builder.using_abi_version(abiv);
+
+        let req = builder
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        assert_eq!(req.detected_event_clock(), None);
+
+        s.pullup(offset).unwrap();
+        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        let evt = req.read_edge_event().unwrap();
+        if abiv == AbiVersion::V2 {
+            assert_eq!(req.detected_event_clock(), None);
+            assert_eq!(evt.event_clock, gpiocdev::line::EventClock::Monotonic);
+        } else {
+            // v1 clock is detected from the first event, and applies to all.
+            assert_eq!(req.detected_event_clock(), Some(evt.event_clock));
+            s.pulldown(offset).unwrap();
+            assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+            let evt = req.read_edge_event().unwrap();
+            assert_eq!(req.detected_event_clock(), Some(evt.event_clock));
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn read_edge_events_into_slice(abiv: AbiVersion) {
         let s = Simpleton::new(3);
         let offset = 2;
 
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
         builder.using_abi_version(abiv);
-
         let req = builder
             .on_chip(s.dev_path())
             .with_line(offset)
@@ -1691,15 +2579,33 @@ mod request {
             .with_edge_detection(EdgeDetection::BothEdges)
             .request()
             .unwrap();
-        assert_eq!(req.has_edge_event(), Ok(false));
 
-        s.pullup(offset).unwrap();
+        let evt_size64 = req.edge_event_size() / 8;
+        let mut buf = vec![0_u64; evt_size64 * 3];
+
+        // create four events
+        s.toggle(offset).unwrap();
         wait_propagation_delay();
-        assert_eq!(req.has_edge_event(), Ok(true));
+        s.toggle(offset).unwrap();
+        wait_propagation_delay();
+        s.toggle(offset).unwrap();
+        wait_propagation_delay();
+        s.toggle(offset).unwrap();
+        wait_propagation_delay();
+
+        // read a buffer full
+        let wlen = req.read_edge_events_into_slice(buf.as_mut_slice()).unwrap();
+        assert_eq!(wlen, buf.capacity());
+
+        // read remaining event
+        let wlen = req.read_edge_events_into_slice(buf.as_mut_slice()).unwrap();
+        assert_eq!(wlen, req.edge_event_u64_size());
     }
 
     #[allow(unused_variables)]
-    fn wait_edge_event(abiv: AbiVersion) {
+    fn poll(abiv: AbiVersion) {
+        use std::os::unix::prelude::AsRawFd;
+
         let s = Simpleton::new(3);
         let offset = 2;
 
@@ -1715,16 +2621,33 @@ mod request {
             .request()
             .unwrap();
 
-        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(false));
+        let mut pfd = libc::pollfd {
+            fd: req.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // not readable until an edge event is available
+        assert_eq!(unsafe { libc::poll(&mut pfd, 1, 0) }, 0);
 
         s.pullup(offset).unwrap();
-        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        let timeout = EVENT_WAIT_TIMEOUT.as_millis() as libc::c_int;
+        assert_eq!(unsafe { libc::poll(&mut pfd, 1, timeout) }, 1);
+        assert_eq!(pfd.revents & libc::POLLIN, libc::POLLIN);
+        let evt = req.read_edge_event().unwrap();
+        assert_eq!(evt.kind, EdgeKind::Rising);
+        assert_eq!(evt.offset, offset);
+
+        // and no longer readable once the event is read
+        pfd.revents = 0;
+        assert_eq!(unsafe { libc::poll(&mut pfd, 1, 0) }, 0);
     }
 
     #[allow(unused_variables)]
-    fn read_edge_event(abiv: AbiVersion) {
+    fn epoll(abiv: AbiVersion) {
+        use std::os::unix::prelude::{AsRawFd, FromRawFd, OwnedFd};
+
         let s = Simpleton::new(3);
-        let offset = 1;
+        let offset = 2;
 
         let mut builder = Request::builder();
         #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
@@ -1738,34 +2661,62 @@ mod request {
             .request()
             .unwrap();
 
+        let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        assert!(epfd >= 0);
+        let epfd = unsafe { OwnedFd::from_raw_fd(epfd) };
+        // register a duplicate, as would be done to wait in another thread
+        let fd = req.try_clone_fd().unwrap();
+        assert_ne!(fd.as_raw_fd(), req.as_raw_fd());
+        let mut ev = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: 42,
+        };
+        assert_eq!(
+            unsafe {
+                libc::epoll_ctl(
+                    epfd.as_raw_fd(),
+                    libc::EPOLL_CTL_ADD,
+                    fd.as_raw_fd(),
+                    &mut ev,
+                )
+            },
+            0
+        );
+
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 2];
+        let timeout = EVENT_WAIT_TIMEOUT.as_millis() as i32;
+        let wait = |events: &mut [libc::epoll_event]| unsafe {
+            libc::epoll_wait(
+                epfd.as_raw_fd(),
+                events.as_mut_ptr(),
+                events.len() as i32,
+                timeout,
+            )
+        };
+        assert_eq!(wait(&mut events), 0);
+
         s.pullup(offset).unwrap();
-        assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
+        assert_eq!(wait(&mut events), 1);
+        // copy the fields out, as epoll_event is packed
+        let (key, flags) = (events[0].u64, events[0].events);
+        assert_eq!(key, 42);
+        assert_ne!(flags & libc::EPOLLIN as u32, 0);
+
+        // the event is read via the request, draining the fd
         let evt = req.read_edge_event().unwrap();
         assert_eq!(evt.kind, EdgeKind::Rising);
-        assert_eq!(evt.offset, offset);
-        if abiv == AbiVersion::V2 {
-            assert_eq!(evt.line_seqno, 1);
-            assert_eq!(evt.seqno, 1);
-        } else {
-            assert_eq!(evt.line_seqno, 0);
-            assert_eq!(evt.seqno, 0);
-        }
+        assert_eq!(wait(&mut events), 0);
 
+        // closing the duplicate leaves the request intact
+        drop(fd);
         s.pulldown(offset).unwrap();
         assert_eq!(req.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(true));
         let evt = req.read_edge_event().unwrap();
         assert_eq!(evt.kind, EdgeKind::Falling);
-        if abiv == AbiVersion::V2 {
-            assert_eq!(evt.line_seqno, 2);
-            assert_eq!(evt.seqno, 2);
-        } else {
-            assert_eq!(evt.line_seqno, 0);
-            assert_eq!(evt.seqno, 0);
-        }
     }
 
     #[allow(unused_variables)]
-    fn read_edge_events_into_slice(abiv: AbiVersion) {
+    fn read_edge_events_into(abiv: AbiVersion) {
         let s = Simpleton::new(3);
         let offset = 2;
 
@@ -1780,26 +2731,54 @@ mod request {
             .request()
             .unwrap();
 
-        let evt_size64 = req.edge_event_size() / 8;
-        let mut buf = vec![0_u64; evt_size64 * 3];
+        let mut events = Vec::new();
+        assert_eq!(req.read_edge_events_into(&mut events, 0), Ok(0));
+        assert!(events.is_empty());
 
-        // create four events
-        s.toggle(offset).unwrap();
-        wait_propagation_delay();
-        s.toggle(offset).unwrap();
-        wait_propagation_delay();
+        // create seven events
+        for _ in 0..7 {
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+        }
+
+        // read in batches that do not divide the burst evenly
+        assert_eq!(req.read_edge_events_into(&mut events, 3), Ok(3));
+        assert_eq!(req.read_edge_events_into(&mut events, 3), Ok(3));
+        assert_eq!(req.read_edge_events_into(&mut events, 3), Ok(1));
+        assert_eq!(req.has_edge_event(), Ok(false));
+
+        // none lost or duplicated
+        assert_eq!(events.len(), 7);
+        for (idx, evt) in events.iter().enumerate() {
+            assert_eq!(evt.offset, offset);
+            let kind = if idx % 2 == 0 {
+                EdgeKind::Rising
+            } else {
+                EdgeKind::Falling
+            };
+            assert_eq!(evt.kind, kind);
+            if abiv == AbiVersion::V2 {
+                assert_eq!(evt.seqno, idx as u32 + 1);
+                assert_eq!(evt.line_seqno, idx as u32 + 1);
+            }
+            if idx > 0 {
+                assert!(evt.timestamp_ns > events[idx - 1].timestamp_ns);
+            }
+        }
+
+        // appends to existing events
         s.toggle(offset).unwrap();
         wait_propagation_delay();
+        assert_eq!(req.read_edge_events_into(&mut events, 16), Ok(1));
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[7].kind, EdgeKind::Rising);
+
+        // max is clamped to the kernel event buffer size
         s.toggle(offset).unwrap();
         wait_propagation_delay();
-
-        // read a buffer full
-        let wlen = req.read_edge_events_into_slice(buf.as_mut_slice()).unwrap();
-        assert_eq!(wlen, buf.capacity());
-
-        // read remaining event
-        let wlen = req.read_edge_events_into_slice(buf.as_mut_slice()).unwrap();
-        assert_eq!(wlen, req.edge_event_u64_size());
+        assert_eq!(req.read_edge_events_into(&mut events, usize::MAX), Ok(1));
+        assert_eq!(events.len(), 9);
+        assert_eq!(events[8].kind, EdgeKind::Falling);
     }
 
     #[allow(unused_variables)]
@@ -2019,6 +2998,469 @@ mod edge_event_buffer {
             assert_eq!(evt.seqno, 2);
         }
     }
+
+    #[test]
+    fn iterator() {
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        let mut buf = req.new_edge_event_buffer(4);
+
+        // more events than the buffer can hold, so it must be refilled
+        let num_events = 10;
+        for _ in 0..num_events {
+            s.toggle(offset).unwrap();
+            wait_propagation_delay();
+        }
+        let mut last_ts = 0;
+        for (i, evt) in buf.by_ref().take(num_events).enumerate() {
+            let evt = evt.unwrap();
+            let kind = if i % 2 == 0 {
+                EdgeKind::Rising
+            } else {
+                EdgeKind::Falling
+            };
+            assert_eq!(evt.kind, kind);
+            assert_eq!(evt.offset, offset);
+            assert!(evt.timestamp_ns >= last_ts);
+            last_ts = evt.timestamp_ns;
+            #[cfg(feature = "uapi_v2")]
+            {
+                assert_eq!(evt.line_seqno, i as u32 + 1);
+                assert_eq!(evt.seqno, i as u32 + 1);
+            }
+        }
+        assert!(buf.is_empty());
+        assert_eq!(buf.has_event(), Ok(false));
+        // the buffer is reused, not reallocated
+        assert_eq!(buf.capacity(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "mockable")]
+    fn end_of_file() {
+        use gpiocdev_uapi::mock::{install, FaultInjector, Response};
+        use std::rc::Rc;
+
+        let s = Simpleton::new(3);
+        let offset = 2;
+
+        let req = Request::builder()
+            .on_chip(s.dev_path())
+            .with_line(offset)
+            .as_input()
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        let mut buf = req.new_edge_event_buffer(4);
+
+        let fi = Rc::new(FaultInjector::default());
+        let _guard = install(fi.clone());
+        // the iterator ends...
+        fi.push_response(Response::Data(vec![]));
+        assert!(buf.next().is_none());
+        // ...while a direct read is an error.
+        fi.push_response(Response::Data(vec![]));
+        assert_eq!(
+            buf.read_event(),
+            Err(gpiocdev::Error::GpioChip(
+                s.dev_path().to_owned(),
+                gpiocdev::chip::ErrorKind::Removed
+            ))
+        );
+    }
+}
+
+mod group {
+    use super::*;
+    use gpiocdev::request::RequestGroup;
+    use gpiosim::{Bank, Level};
+    use std::collections::HashMap;
+
+    // lines are named uniquely across tests to avoid collisions with
+    // other tests running in parallel.
+    fn two_chip_sim(prefix: &str) -> gpiosim::Sim {
+        gpiosim::builder()
+            .with_bank(
+                Bank::new(8, format!("{prefix} 1"))
+                    .name(3, format!("{prefix} banana"))
+                    .name(6, format!("{prefix} apple")),
+            )
+            .with_bank(
+                Bank::new(12, format!("{prefix} 2"))
+                    .name(2, format!("{prefix} grape"))
+                    .name(5, format!("{prefix} mango")),
+            )
+            .live()
+            .unwrap()
+    }
+
+    #[test]
+    fn request_by_name() {
+        let sim = two_chip_sim("rg req");
+        let c0 = &sim.chips()[0];
+        let c1 = &sim.chips()[1];
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg req banana", "rg req grape"])
+            .as_output(Value::Active)
+            .with_line("rg req mango")
+            .as_input()
+            .request()
+            .unwrap();
+        assert_eq!(
+            grp.lines(),
+            &["rg req banana", "rg req grape", "rg req mango"]
+        );
+        assert_eq!(grp.requests().len(), 2);
+        assert_eq!(&grp.requests()[0].chip_path(), c0.dev_path());
+        assert_eq!(&grp.requests()[1].chip_path(), c1.dev_path());
+        assert_eq!(grp.locate("rg req banana"), Some((0, 3)));
+        assert_eq!(grp.locate("rg req grape"), Some((1, 2)));
+        assert_eq!(grp.locate("rg req mango"), Some((1, 5)));
+        assert_eq!(grp.locate("rg req apple"), None);
+
+        assert_eq!(c0.get_level(3).unwrap(), Level::High);
+        assert_eq!(c1.get_level(2).unwrap(), Level::High);
+    }
+
+    #[test]
+    fn request_unfound_line() {
+        let _sim = two_chip_sim("rg unf");
+
+        assert_eq!(
+            RequestGroup::builder()
+                .with_lines(&["rg unf banana", "rg unf kiwi"])
+                .request()
+                .unwrap_err(),
+            gpiocdev::Error::InvalidArgument("Line 'rg unf kiwi' not found.".into())
+        );
+    }
+
+    #[test]
+    fn values() {
+        let sim = two_chip_sim("rg vals");
+        let c0 = &sim.chips()[0];
+        let c1 = &sim.chips()[1];
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg vals apple", "rg vals mango"])
+            .as_input()
+            .request()
+            .unwrap();
+
+        c0.pullup(6).unwrap();
+        c1.pulldown(5).unwrap();
+        let values = grp.values().unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get("rg vals apple"), Some(&Value::Active));
+        assert_eq!(values.get("rg vals mango"), Some(&Value::Inactive));
+        assert_eq!(grp.value("rg vals apple"), Ok(Value::Active));
+
+        c0.pulldown(6).unwrap();
+        c1.pullup(5).unwrap();
+        let values = grp.values().unwrap();
+        assert_eq!(values.get("rg vals apple"), Some(&Value::Inactive));
+        assert_eq!(values.get("rg vals mango"), Some(&Value::Active));
+        assert_eq!(grp.value("rg vals mango"), Ok(Value::Active));
+    }
+
+    #[test]
+    fn set_values() {
+        let sim = two_chip_sim("rg set");
+        let c0 = &sim.chips()[0];
+        let c1 = &sim.chips()[1];
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg set banana", "rg set apple", "rg set grape"])
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+
+        let mut values = HashMap::new();
+        values.insert("rg set apple", Value::Active);
+        values.insert("rg set grape", Value::Active);
+        grp.set_values(&values).unwrap();
+        assert_eq!(c0.get_level(3).unwrap(), Level::Low);
+        assert_eq!(c0.get_level(6).unwrap(), Level::High);
+        assert_eq!(c1.get_level(2).unwrap(), Level::High);
+
+        grp.set_value("rg set grape", Value::Inactive).unwrap();
+        assert_eq!(c1.get_level(2).unwrap(), Level::Low);
+
+        // unrequested lines leave all lines unchanged
+        values.insert("rg set mango", Value::Active);
+        values.insert("rg set banana", Value::Active);
+        assert!(grp.set_values(&values).is_err());
+        assert_eq!(c0.get_level(3).unwrap(), Level::Low);
+        assert_eq!(c1.get_level(2).unwrap(), Level::Low);
+    }
+
+    #[test]
+    fn named_values() {
+        let sim = two_chip_sim("rg named");
+        let c0 = &sim.chips()[0];
+        let c1 = &sim.chips()[1];
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg named banana", "rg named grape"])
+            .as_output(Value::Inactive)
+            .with_lines(&["rg named apple", "rg named mango"])
+            .as_input()
+            .request()
+            .unwrap();
+
+        // set by name
+        let mut values = grp.named_values();
+        values.set("rg named grape", Value::Active).unwrap();
+        grp.set_named_values(&values).unwrap();
+        assert_eq!(c0.get_level(3).unwrap(), Level::Low);
+        assert_eq!(c1.get_level(2).unwrap(), Level::High);
+
+        values.set("rg named banana", Value::Active).unwrap();
+        values.set("rg named grape", Value::Inactive).unwrap();
+        grp.set_named_values(&values).unwrap();
+        assert_eq!(c0.get_level(3).unwrap(), Level::High);
+        assert_eq!(c1.get_level(2).unwrap(), Level::Low);
+
+        // get by name, for only the selected lines
+        c0.pullup(6).unwrap();
+        c1.pulldown(5).unwrap();
+        let mut values = grp.named_values();
+        values.set("rg named mango", Value::Active).unwrap();
+        grp.read_named_values(&mut values).unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values.get("rg named mango"), Some(Value::Inactive));
+        assert_eq!(values.get("rg named apple"), None);
+
+        // or all lines
+        let mut values = grp.named_values();
+        grp.read_named_values(&mut values).unwrap();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values.get("rg named banana"), Some(Value::Active));
+        assert_eq!(values.get("rg named grape"), Some(Value::Inactive));
+        assert_eq!(values.get("rg named apple"), Some(Value::Active));
+        assert_eq!(values.get("rg named mango"), Some(Value::Inactive));
+
+        assert!(values.set("rg named kiwi", Value::Active).is_err());
+    }
+
+    #[test]
+    fn per_line_consumers() {
+        let sim = two_chip_sim("rg cons");
+        let c0 = gpiocdev::Chip::from_path(sim.chips()[0].dev_path()).unwrap();
+        let c1 = gpiocdev::Chip::from_path(sim.chips()[1].dev_path()).unwrap();
+
+        // consumers apply to the selected lines, else become the default
+        let grp = RequestGroup::builder()
+            .with_consumer("rg-default")
+            .with_line("rg cons banana")
+            .as_output(Value::Active)
+            .with_consumer("led-status")
+            .with_line("rg cons apple")
+            .as_output(Value::Inactive)
+            .with_consumer("led-error")
+            .with_lines(&["rg cons grape", "rg cons mango"])
+            .as_input()
+            .request()
+            .unwrap();
+        // banana and apple are on the same chip, but with different consumers
+        assert_eq!(grp.requests().len(), 3);
+        assert_eq!(grp.locate("rg cons banana"), Some((0, 3)));
+        assert_eq!(grp.locate("rg cons apple"), Some((1, 6)));
+        assert_eq!(grp.locate("rg cons grape"), Some((2, 2)));
+        assert_eq!(grp.locate("rg cons mango"), Some((2, 5)));
+
+        assert_eq!(c0.line_info(3).unwrap().consumer, "led-status");
+        assert_eq!(c0.line_info(6).unwrap().consumer, "led-error");
+        assert_eq!(c1.line_info(2).unwrap().consumer, "rg-default");
+        assert_eq!(c1.line_info(5).unwrap().consumer, "rg-default");
+
+        // values are merged across the requests
+        let values = grp.values().unwrap();
+        assert_eq!(values.get("rg cons banana"), Some(&Value::Active));
+        assert_eq!(values.get("rg cons apple"), Some(&Value::Inactive));
+        assert_eq!(values.get("rg cons grape"), Some(&Value::Inactive));
+
+        let mut values = HashMap::new();
+        values.insert("rg cons banana", Value::Inactive);
+        values.insert("rg cons apple", Value::Active);
+        grp.set_values(&values).unwrap();
+        assert_eq!(sim.chips()[0].get_level(3).unwrap(), Level::Low);
+        assert_eq!(sim.chips()[0].get_level(6).unwrap(), Level::High);
+    }
+
+    #[test]
+    #[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+    fn split_by_config_v1() {
+        let sim = two_chip_sim("rg v1");
+
+        let grp = RequestGroup::builder()
+            .using_abi_version(gpiocdev::AbiVersion::V1)
+            .with_lines(&["rg v1 banana", "rg v1 apple"])
+            .as_output(Value::Active)
+            .with_line("rg v1 apple")
+            .with_value(Value::Inactive)
+            .with_line("rg v1 grape")
+            .as_input()
+            .with_line("rg v1 mango")
+            .as_input()
+            .as_active_low()
+            .request()
+            .unwrap();
+        // values do not split requests, but other configuration does
+        assert_eq!(grp.requests().len(), 3);
+        assert_eq!(grp.locate("rg v1 banana"), Some((0, 3)));
+        assert_eq!(grp.locate("rg v1 apple"), Some((0, 6)));
+        assert_eq!(grp.locate("rg v1 grape"), Some((1, 2)));
+        assert_eq!(grp.locate("rg v1 mango"), Some((2, 5)));
+        assert_eq!(sim.chips()[0].get_level(3).unwrap(), Level::High);
+        assert_eq!(sim.chips()[0].get_level(6).unwrap(), Level::Low);
+    }
+
+    #[test]
+    fn reconfigure() {
+        use gpiocdev::line::Bias;
+
+        let sim = two_chip_sim("rg reconf");
+        let c1 = gpiocdev::Chip::from_path(sim.chips()[1].dev_path()).unwrap();
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg reconf banana", "rg reconf grape", "rg reconf mango"])
+            .as_input()
+            .with_bias(Bias::PullDown)
+            .request()
+            .unwrap();
+
+        let mut lc = grp.requests()[1].line_config(2).unwrap();
+        lc.bias = Some(Bias::PullUp);
+        let mut cfgs = HashMap::new();
+        cfgs.insert("rg reconf grape", lc);
+        grp.reconfigure(&cfgs).unwrap();
+        assert_eq!(c1.line_info(2).unwrap().bias, Some(Bias::PullUp));
+        assert_eq!(c1.line_info(5).unwrap().bias, Some(Bias::PullDown));
+        assert_eq!(grp.value("rg reconf grape"), Ok(Value::Active));
+
+        cfgs.insert("rg reconf kiwi", gpiocdev::line::Config::default());
+        assert!(grp.reconfigure(&cfgs).is_err());
+    }
+
+    #[test]
+    fn edge_events() {
+        let sim = two_chip_sim("rg edges");
+        let c0 = &sim.chips()[0];
+        let c1 = &sim.chips()[1];
+
+        let grp = RequestGroup::builder()
+            .with_lines(&["rg edges apple", "rg edges grape"])
+            .with_edge_detection(EdgeDetection::BothEdges)
+            .request()
+            .unwrap();
+        assert_eq!(grp.has_edge_event(), Ok(false));
+        assert_eq!(grp.wait_edge_event(EVENT_WAIT_TIMEOUT), Ok(false));
+
+        c1.pullup(2).unwrap();
+        wait_propagation_delay();
+        c0.pullup(6).unwrap();
+        wait_propagation_delay();
+        c1.pulldown(2).unwrap();
+        wait_propagation_delay();
+        assert_eq!(grp.has_edge_event(), Ok(true));
+
+        // events are returned in timestamp order, regardless of request
+        let (id, evt1) = grp.read_edge_event().unwrap();
+        assert_eq!(id, "rg edges grape");
+        assert_eq!(evt1.kind, EdgeKind::Rising);
+        assert_eq!(evt1.offset, 2);
+        let (id, evt2) = grp.read_edge_event().unwrap();
+        assert_eq!(id, "rg edges apple");
+        assert_eq!(evt2.kind, EdgeKind::Rising);
+        assert_eq!(evt2.offset, 6);
+        let (id, evt3) = grp.read_edge_event().unwrap();
+        assert_eq!(id, "rg edges grape");
+        assert_eq!(evt3.kind, EdgeKind::Falling);
+        assert!(evt1.timestamp_ns < evt2.timestamp_ns);
+        assert!(evt2.timestamp_ns < evt3.timestamp_ns);
+        assert_eq!(grp.has_edge_event(), Ok(false));
+    }
+
+    #[test]
+    fn allow_splitting() {
+        let mut bank = Bank::new(80, "rg split");
+        for offset in 0..80 {
+            bank.name(offset, format!("rg split {offset}"));
+        }
+        let sim = gpiosim::builder().with_bank(&bank).live().unwrap();
+        let c = &sim.chips()[0];
+        let names: Vec<String> = (0..80).map(|offset| format!("rg split {offset}")).collect();
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+
+        // limited to 64 lines per request
+        let err = RequestGroup::builder()
+            .with_lines(&names)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap_err();
+        assert!(err.to_string().contains("greater than the maximum of 64"));
+
+        let grp = RequestGroup::builder()
+            .allow_splitting(true)
+            .with_lines(&names)
+            .as_output(Value::Inactive)
+            .request()
+            .unwrap();
+        assert_eq!(grp.requests().len(), 2);
+        assert_eq!(grp.requests()[0].config().num_lines(), 64);
+        assert_eq!(grp.requests()[1].config().num_lines(), 16);
+        assert_eq!(grp.locate("rg split 63"), Some((0, 63)));
+        assert_eq!(grp.locate("rg split 64"), Some((1, 64)));
+
+        // values span the requests
+        let mut values = HashMap::new();
+        values.insert("rg split 3", Value::Active);
+        values.insert("rg split 70", Value::Active);
+        grp.set_values(&values).unwrap();
+        assert_eq!(c.get_level(3).unwrap(), Level::High);
+        assert_eq!(c.get_level(70).unwrap(), Level::High);
+        assert_eq!(c.get_level(69).unwrap(), Level::Low);
+        let values = grp.values().unwrap();
+        assert_eq!(values.len(), 80);
+        assert_eq!(values.get("rg split 70"), Some(&Value::Active));
+        assert_eq!(values.get("rg split 71"), Some(&Value::Inactive));
+    }
+
+    #[test]
+    fn with_found_line() {
+        let sim = two_chip_sim("rg found");
+        let c1 = &sim.chips()[1];
+
+        let mut info = gpiocdev::line::Info::default();
+        info.offset = 7;
+        let fl = gpiocdev::FoundLine {
+            chip: c1.dev_path().clone(),
+            info,
+        };
+        let grp = RequestGroup::builder()
+            .with_found_line("seven", &fl)
+            .as_output(Value::Active)
+            .with_line("rg found banana")
+            .as_output(Value::Active)
+            .request()
+            .unwrap();
+        assert_eq!(grp.locate("seven"), Some((0, 7)));
+        assert_eq!(grp.locate("rg found banana"), Some((1, 3)));
+        assert_eq!(c1.get_level(7).unwrap(), Level::High);
+
+        grp.set_value("seven", Value::Inactive).unwrap();
+        assert_eq!(c1.get_level(7).unwrap(), Level::Low);
+    }
 }
 
 struct Symlink {
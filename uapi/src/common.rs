@@ -2,50 +2,106 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+#[cfg(unix)]
 use std::ffi::OsStr;
+#[cfg(feature = "ioctl")]
 use std::fs::File;
-use std::os::unix::prelude::{AsRawFd, OsStrExt};
+#[cfg(unix)]
+use std::os::unix::prelude::OsStrExt;
+#[cfg(feature = "ioctl")]
+use std::os::unix::prelude::{AsRawFd, IntoRawFd, RawFd};
+#[cfg(feature = "ioctl")]
 use std::ptr;
+#[cfg(unix)]
 use std::slice;
-use std::time::Duration;
+#[cfg(feature = "ioctl")]
+use std::time::{Duration, Instant};
 
 /// Check if the file has an event available to read.
 ///
 /// For gpiochip files the events are LineInfoChangeEvent.
 /// For line request files the events are LineEdgeEvent.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn has_event(f: &File) -> Result<bool> {
     wait_event(f, Duration::ZERO)
 }
 
 // workaround musl and android libc::ioctl() having a different signature
-#[cfg(any(target_env = "musl", target_os = "android"))]
+#[cfg(all(feature = "ioctl", any(target_env = "musl", target_os = "android")))]
 pub(crate) type IoctlRequestType = libc::c_int;
-#[cfg(not(any(target_env = "musl", target_os = "android")))]
+#[cfg(all(
+    feature = "ioctl",
+    not(any(target_env = "musl", target_os = "android"))
+))]
 pub(crate) type IoctlRequestType = libc::c_ulong;
 
+/// Perform an ioctl on the file descriptor.
+///
+/// Calls are routed through the installed [`mock::UapiCalls`](crate::mock::UapiCalls)
+/// when the `mockable` feature is enabled.
+#[cfg(feature = "ioctl")]
+#[inline]
+pub(crate) unsafe fn ioctl<T>(fd: RawFd, request: IoctlRequestType, arg: *const T) -> libc::c_int {
+    #[cfg(feature = "mockable")]
+    return crate::mock::ioctl(fd, request, arg as *mut libc::c_void);
+    #[cfg(not(feature = "mockable"))]
+    libc::ioctl(fd, request, arg)
+}
+
+/// Read from the file descriptor.
+///
+/// Calls are routed through the installed [`mock::UapiCalls`](crate::mock::UapiCalls)
+/// when the `mockable` feature is enabled.
+#[cfg(feature = "ioctl")]
+#[inline]
+pub(crate) unsafe fn read(fd: RawFd, buf: *mut libc::c_void, count: usize) -> isize {
+    #[cfg(feature = "mockable")]
+    return crate::mock::read(fd, buf, count);
+    #[cfg(not(feature = "mockable"))]
+    libc::read(fd, buf, count)
+}
+
+#[cfg(feature = "ioctl")]
 macro_rules! ior {
     ($nr:expr, $dty:ty) => {
         ioctl_sys::ior!(IOCTL_MAGIC, $nr, std::mem::size_of::<$dty>()) as IoctlRequestType
     };
 }
 
+#[cfg(feature = "ioctl")]
 macro_rules! iorw {
     ($nr:expr, $dty:ty) => {
         ioctl_sys::iorw!(IOCTL_MAGIC, $nr, std::mem::size_of::<$dty>()) as IoctlRequestType
     };
 }
+#[cfg(feature = "ioctl")]
 pub(crate) use iorw;
 
 /// Read an event from a chip or request file descriptor.
 ///
 /// Returns the number of u64 words read.
+///
+/// If the file is [non-blocking] and no event is available then
+/// [`Error::WouldBlock`] is returned.
+///
+/// The read is resumed if interrupted by a signal.
+///
+/// [non-blocking]: set_nonblocking
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn read_event(f: &File, buf: &mut [u64]) -> Result<usize> {
-    unsafe {
-        let bufptr: *mut libc::c_void = std::ptr::addr_of_mut!(*buf) as *mut libc::c_void;
-        match libc::read(f.as_raw_fd(), bufptr, buf.len() * 8) {
-            -1 => Err(Error::from_errno()),
+    loop {
+        let res = unsafe {
+            let bufptr: *mut libc::c_void = std::ptr::addr_of_mut!(*buf) as *mut libc::c_void;
+            read(f.as_raw_fd(), bufptr, buf.len() * 8)
+        };
+        return match res {
+            -1 => match Error::from_errno() {
+                Error::Os(Errno(libc::EINTR)) => continue,
+                Error::Os(Errno(libc::EAGAIN)) => Err(Error::WouldBlock),
+                e => Err(e),
+            },
             x => {
                 let size: usize = x.try_into().unwrap();
                 if size % 8 == 0 {
@@ -58,17 +114,190 @@ pub fn read_event(f: &File, buf: &mut [u64]) -> Result<usize> {
                     )))
                 }
             }
-        }
+        };
+    }
+}
+
+/// Read whole records from a chip or request file descriptor.
+///
+/// Retries reads interrupted by a signal.
+///
+/// Returns the number of u64 words read, which is always a non-zero multiple
+/// of the record size.
+#[cfg(feature = "ioctl")]
+pub(crate) fn read_records(
+    obj: &'static str,
+    f: &File,
+    buf: &mut [u64],
+    record_size: usize,
+) -> Result<usize> {
+    debug_assert!(record_size % 8 == 0);
+    if buf.len() * 8 < record_size {
+        return Err(Error::from(UnderReadError::new(
+            obj,
+            record_size,
+            buf.len() * 8,
+        )));
+    }
+    loop {
+        let res = unsafe {
+            let bufptr: *mut libc::c_void = std::ptr::addr_of_mut!(*buf) as *mut libc::c_void;
+            read(f.as_raw_fd(), bufptr, buf.len() * 8)
+        };
+        return match res {
+            -1 => match Error::from_errno() {
+                Error::Os(Errno(libc::EINTR)) => continue,
+                Error::Os(Errno(libc::EAGAIN)) => Err(Error::WouldBlock),
+                e => Err(e),
+            },
+            x => {
+                let size: usize = x.try_into().unwrap();
+                if size == 0 || size % record_size != 0 {
+                    // round up to the nearest whole record
+                    let expected = (size / record_size + 1) * record_size;
+                    Err(Error::from(UnderReadError::new(obj, expected, size)))
+                } else {
+                    Ok(size / 8)
+                }
+            }
+        };
     }
 }
 
 /// Wait for the file to have an event available to read.
+///
+/// The wait is resumed if interrupted by a signal, so returns false only if
+/// the timeout expired.
+#[cfg(feature = "ioctl")]
+#[inline]
 pub fn wait_event(f: &File, d: Duration) -> Result<bool> {
+    wait_readable(f, Some(d))
+}
+
+/// Wait for the file to have an event available to read.
+///
+/// If the timeout is `None` then waits indefinitely.
+///
+/// The wait is resumed if interrupted by a signal, so returns false only if
+/// the timeout expired.
+#[cfg(feature = "ioctl")]
+pub fn wait_readable(f: &File, d: Option<Duration>) -> Result<bool> {
+    let deadline = d.map(|d| Instant::now() + d);
     let mut pfd = libc::pollfd {
         fd: f.as_raw_fd(),
         events: libc::POLLIN,
         revents: 0,
     };
+    loop {
+        let timeout =
+            deadline.map(|deadline| timespec(deadline.saturating_duration_since(Instant::now())));
+        let tptr = match &timeout {
+            Some(t) => t as *const libc::timespec,
+            None => ptr::null(),
+        };
+        unsafe {
+            match libc::ppoll(std::ptr::addr_of_mut!(pfd), 1, tptr, ptr::null()) {
+                -1 => match Error::from_errno() {
+                    Error::Os(Errno(libc::EINTR)) => continue,
+                    e => return Err(e),
+                },
+                0 => return Ok(false),
+                _ => return Ok(true),
+            }
+        }
+    }
+}
+
+/// Close a chip or request file, returning any error reported by the kernel.
+///
+/// Dropping a File also closes it, but silently discards any error.
+///
+/// The file descriptor is released even if an error is returned, so the
+/// close is not retried if interrupted by a signal.
+#[cfg(feature = "ioctl")]
+pub fn close(f: File) -> Result<()> {
+    match unsafe { libc::close(f.into_raw_fd()) } {
+        0 => Ok(()),
+        _ => Err(Error::from_errno()),
+    }
+}
+
+/// Enable or disable non-blocking reads on a chip or request file.
+///
+/// When enabled, [`read_event`] returns [`Error::WouldBlock`] rather than
+/// blocking if no event is available.
+#[cfg(feature = "ioctl")]
+pub fn set_nonblocking(f: &File, enabled: bool) -> Result<()> {
+    let fd = f.as_raw_fd();
+    unsafe {
+        let flags = match libc::fcntl(fd, libc::F_GETFL) {
+            -1 => return Err(Error::from_errno()),
+            flags => flags,
+        };
+        let flags = if enabled {
+            flags | libc::O_NONBLOCK
+        } else {
+            flags & !libc::O_NONBLOCK
+        };
+        match libc::fcntl(fd, libc::F_SETFL, flags) {
+            -1 => Err(Error::from_errno()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Wait for any of the files to have an event available to read.
+///
+/// If the timeout is `None` then waits indefinitely.
+///
+/// Returns the indices of the files with events available, which is empty
+/// if the timeout expired.
+///
+/// The wait is resumed if interrupted by a signal.
+#[cfg(feature = "ioctl")]
+pub fn wait_events(files: &[&File], d: Option<Duration>) -> Result<Vec<usize>> {
+    let deadline = d.map(|d| Instant::now() + d);
+    let mut pfds: Vec<libc::pollfd> = files
+        .iter()
+        .map(|f| libc::pollfd {
+            fd: f.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    loop {
+        let timeout =
+            deadline.map(|deadline| timespec(deadline.saturating_duration_since(Instant::now())));
+        let tptr = match &timeout {
+            Some(t) => t as *const libc::timespec,
+            None => ptr::null(),
+        };
+        unsafe {
+            match libc::ppoll(
+                pfds.as_mut_ptr(),
+                pfds.len() as libc::nfds_t,
+                tptr,
+                ptr::null(),
+            ) {
+                -1 => match Error::from_errno() {
+                    Error::Os(Errno(libc::EINTR)) => continue,
+                    e => return Err(e),
+                },
+                _ => {
+                    return Ok(pfds
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, pfd)| pfd.revents != 0)
+                        .map(|(idx, _)| idx)
+                        .collect())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ioctl")]
+fn timespec(d: Duration) -> libc::timespec {
     // prevent musl builds complaining about use of deprecated time_t
     #[cfg(not(target_env = "musl"))]
     use libc::time_t as TimeT;
@@ -76,26 +305,16 @@ pub fn wait_event(f: &File, d: Duration) -> Result<bool> {
     use std::primitive::i32 as TimeT;
     #[cfg(all(target_env = "musl", target_pointer_width = "64"))]
     use std::primitive::i64 as TimeT;
-    let timeout = libc::timespec {
+    libc::timespec {
         tv_sec: d.as_secs() as TimeT,
         tv_nsec: d.subsec_nanos() as libc::c_long,
-    };
-    unsafe {
-        match libc::ppoll(
-            std::ptr::addr_of_mut!(pfd),
-            1,
-            std::ptr::addr_of!(timeout),
-            ptr::null(),
-        ) {
-            -1 => Err(Error::from_errno()),
-            0 => Ok(false),
-            _ => Ok(true),
-        }
     }
 }
 
+#[cfg(feature = "ioctl")]
 pub(crate) const IOCTL_MAGIC: u8 = 0xb4;
 
+#[cfg(feature = "ioctl")]
 #[repr(u8)]
 enum Ioctl {
     GetChipInfo = 1,
@@ -121,10 +340,11 @@ pub struct ChipInfo {
 /// Get the publicly available information for a chip.
 ///
 /// * `cf` - The open gpiochip device file.
+#[cfg(feature = "ioctl")]
 pub fn get_chip_info(cf: &File) -> Result<ChipInfo> {
     let mut chip = std::mem::MaybeUninit::<ChipInfo>::uninit();
     unsafe {
-        match libc::ioctl(
+        match ioctl(
             cf.as_raw_fd(),
             ior!(Ioctl::GetChipInfo, ChipInfo),
             chip.as_mut_ptr(),
@@ -141,8 +361,9 @@ pub fn get_chip_info(cf: &File) -> Result<ChipInfo> {
 /// * `offset` - The offset of the line to unwatch.
 ///
 /// [`LineInfo`]: struct.LineInfo.html
+#[cfg(feature = "ioctl")]
 pub fn unwatch_line_info(cf: &File, offset: Offset) -> Result<()> {
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::UnwatchLineInfo, u32), &offset) } {
+    match unsafe { ioctl(cf.as_raw_fd(), iorw!(Ioctl::UnwatchLineInfo, u32), &offset) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -187,17 +408,33 @@ pub enum Error {
     /// An error validating an data structure retuned from the kernel
     #[error(transparent)]
     Validation(#[from] ValidationError),
+
+    /// A request for more lines than the kernel supports in a single request.
+    #[error("Requested {0} lines is greater than the maximum of {NUM_LINES_MAX}.")]
+    TooManyLines(u32),
+
+    /// A read from a non-blocking file found no event available.
+    #[error("No event available to read.")]
+    WouldBlock,
+
+    /// A line index is beyond the number of lines in the request.
+    #[error("Line index {0} is out of range for a request of {1} lines.")]
+    IndexOutOfRange(usize, usize),
+
+    /// A config requires more attributes than the ABI supports.
+    #[error("Config requires more attributes than the ABI supports.")]
+    TooManyAttrs,
 }
 
 impl Error {
     /// Create an error from the current errno value.
     #[inline]
-    #[cfg(target_os = "linux")]
+    #[cfg(all(feature = "ioctl", target_os = "linux"))]
     pub fn from_errno() -> Error {
         Error::Os(Errno(unsafe { *libc::__errno_location() }))
     }
     #[inline]
-    #[cfg(target_os = "android")]
+    #[cfg(all(feature = "ioctl", target_os = "android"))]
     pub fn from_errno() -> Error {
         Error::Os(Errno(std::io::Error::last_os_error().raw_os_error().unwrap()))
     }
@@ -228,6 +465,22 @@ impl UnderReadError {
     }
 }
 
+/// Copy a byte buffer into an aligned word buffer.
+///
+/// Fails if the byte buffer is too short to fill the word buffer.
+pub(crate) fn copy_to_words(obj: &'static str, buf: &[u8], d: &mut [u64]) -> Result<()> {
+    let expected = d.len() * 8;
+    if buf.len() < expected {
+        return Err(Error::from(UnderReadError::new(obj, expected, buf.len())));
+    }
+    for (w, b) in d.iter_mut().zip(buf.chunks_exact(8)) {
+        let mut word = [0; 8];
+        word.copy_from_slice(b);
+        *w = u64::from_ne_bytes(word);
+    }
+    Ok(())
+}
+
 /// A failure to validate a struct returned from a system call.
 //
 // Should only be seen if a kernel update adds an enum value we are unaware of.
@@ -273,6 +526,7 @@ impl Name {
 
     /// Convert the contained name to a OsString slice.
     #[inline]
+    #[cfg(unix)]
     pub fn as_os_str(&self) -> &OsStr {
         unsafe { OsStr::from_bytes(slice::from_raw_parts(&self.0[0], self.strlen())) }
     }
@@ -304,7 +558,7 @@ impl Name {
 }
 impl From<&Name> for String {
     fn from(s: &Name) -> Self {
-        String::from(s.as_os_str().to_string_lossy())
+        String::from_utf8_lossy(&s.0[..s.strlen()]).into_owned()
     }
 }
 impl From<&str> for Name {
@@ -330,6 +584,8 @@ pub struct Offsets([Offset; NUM_LINES_MAX]);
 
 impl Offsets {
     /// Create offsets from an iterable list.
+    ///
+    /// Offsets beyond [`NUM_LINES_MAX`] are ignored.
     pub fn from_slice(s: &[u32]) -> Self {
         let mut n: Offsets = Default::default();
         for (src, dst) in s.iter().zip(n.0.iter_mut()) {
@@ -351,9 +607,22 @@ impl Offsets {
     }
 
     /// Copy offsets from an iterable list.
+    ///
+    /// Offsets beyond [`NUM_LINES_MAX`] are ignored.
     pub fn copy_from_slice(&mut self, s: &[u32]) {
         let extent = std::cmp::min(NUM_LINES_MAX, s.len());
-        self.0[0..extent].copy_from_slice(s);
+        self.0[0..extent].copy_from_slice(&s[0..extent]);
+    }
+
+    /// An iterator over the first `num_lines` offsets in the set.
+    ///
+    /// The set does not know how many of its offsets are valid, so the
+    /// number of lines, typically from the containing request, must be provided.
+    ///
+    /// Lines beyond [`NUM_LINES_MAX`] are ignored.
+    #[inline]
+    pub fn iter(&self, num_lines: usize) -> std::slice::Iter<'_, Offset> {
+        self.0[..std::cmp::min(NUM_LINES_MAX, num_lines)].iter()
     }
 }
 
@@ -459,6 +728,123 @@ impl LineEdgeEventKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "ioctl")]
+    use std::io::Write;
+    #[cfg(feature = "ioctl")]
+    use std::os::unix::io::FromRawFd;
+
+    #[cfg(feature = "ioctl")]
+    fn pipe() -> (File, File) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn wait_events() {
+        let (r1, _w1) = pipe();
+        let (r2, mut w2) = pipe();
+        let (r3, mut w3) = pipe();
+        let files = [&r1, &r2, &r3];
+
+        let d = Some(Duration::from_millis(1));
+        assert_eq!(super::wait_events(&files, d), Ok(vec![]));
+
+        w3.write_all(b"x").unwrap();
+        assert_eq!(super::wait_events(&files, d), Ok(vec![2]));
+        assert_eq!(super::wait_events(&files, None), Ok(vec![2]));
+
+        w2.write_all(b"x").unwrap();
+        assert_eq!(super::wait_events(&files, d), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn wait_readable() {
+        let (r, mut w) = pipe();
+
+        let d = Some(Duration::from_millis(1));
+        assert_eq!(super::wait_readable(&r, d), Ok(false));
+
+        w.write_all(b"x").unwrap();
+        assert_eq!(super::wait_readable(&r, d), Ok(true));
+        assert_eq!(super::wait_readable(&r, None), Ok(true));
+    }
+
+    // Interrupt a thread, blocked in a read or wait, with a signal whose
+    // handler does not restart system calls.
+    #[cfg(feature = "ioctl")]
+    fn interrupt(t: &std::thread::JoinHandle<Result<usize>>) {
+        extern "C" fn noop(_: libc::c_int) {}
+        use std::os::unix::thread::JoinHandleExt;
+        unsafe {
+            let mut sa: libc::sigaction = std::mem::zeroed();
+            sa.sa_sigaction = noop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            assert_eq!(libc::sigaction(libc::SIGUSR1, &sa, ptr::null_mut()), 0);
+            assert_eq!(libc::pthread_kill(t.as_pthread_t(), libc::SIGUSR1), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn read_event_interrupted() {
+        let (r, mut w) = pipe();
+        let t = std::thread::spawn(move || {
+            let mut buf = [0_u64; 1];
+            read_event(&r, &mut buf)
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        interrupt(&t);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!t.is_finished());
+
+        w.write_all(&[0; 8]).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(1));
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn wait_event_interrupted() {
+        let (r, mut w) = pipe();
+        let t = std::thread::spawn(move || {
+            wait_event(&r, Duration::from_secs(5)).map(|ready| ready as usize)
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        interrupt(&t);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!t.is_finished());
+
+        w.write_all(b"x").unwrap();
+        assert_eq!(t.join().unwrap(), Ok(1));
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn close() {
+        let (r, w) = pipe();
+        assert_eq!(super::close(r), Ok(()));
+        assert_eq!(super::close(w), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "ioctl")]
+    fn set_nonblocking() {
+        let (r, mut w) = pipe();
+        let is_nonblocking =
+            |f: &File| unsafe { libc::fcntl(f.as_raw_fd(), libc::F_GETFL) } & libc::O_NONBLOCK != 0;
+        let mut buf = [0_u64; 1];
+
+        assert_eq!(super::set_nonblocking(&r, true), Ok(()));
+        assert!(is_nonblocking(&r));
+        assert_eq!(read_event(&r, &mut buf), Err(Error::WouldBlock));
+
+        w.write_all(&[0; 8]).unwrap();
+        assert_eq!(read_event(&r, &mut buf), Ok(1));
+
+        assert_eq!(super::set_nonblocking(&r, false), Ok(()));
+        assert!(!is_nonblocking(&r));
+    }
 
     #[test]
     fn size_of_chip_info() {
@@ -637,6 +1023,37 @@ mod tests {
         assert_eq!(a.0, x);
     }
 
+    #[test]
+    fn offsets_from_slice_truncates() {
+        let x: Vec<u32> = (0..NUM_LINES_MAX as u32 + 3).collect();
+        let a = Offsets::from_slice(&x);
+        assert_eq!(&a.0[..], &x[..NUM_LINES_MAX]);
+    }
+
+    #[test]
+    fn offsets_copy_from_slice() {
+        let mut a = Offsets::from_slice(&[7, 8, 9, 10]);
+        a.copy_from_slice(&[1, 2]);
+        assert_eq!(&a.0[..5], &[1, 2, 9, 10, 0]);
+
+        // overflow is ignored
+        let x: Vec<u32> = (1..NUM_LINES_MAX as u32 + 3).collect();
+        a.copy_from_slice(&x);
+        assert_eq!(&a.0[..], &x[..NUM_LINES_MAX]);
+    }
+
+    #[test]
+    fn offsets_iter() {
+        let a = Offsets::from_slice(&[3, 1, 2]);
+        assert_eq!(a.iter(0).count(), 0);
+        let x: Vec<u32> = a.iter(3).copied().collect();
+        assert_eq!(x, [3, 1, 2]);
+        let x: Vec<u32> = a.iter(4).copied().collect();
+        assert_eq!(x, [3, 1, 2, 0]);
+        assert_eq!(a.iter(NUM_LINES_MAX).count(), NUM_LINES_MAX);
+        assert_eq!(a.iter(NUM_LINES_MAX + 1).count(), NUM_LINES_MAX);
+    }
+
     #[test]
     fn offsets_default() {
         assert_eq!(Offsets::default().0, [0u32; NUM_LINES_MAX]);
@@ -0,0 +1,512 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use super::{v1, v2};
+use std::fmt;
+
+/// A feature of an ABI v2 structure that cannot be represented in ABI v1.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Feature {
+    /// The line is in use.
+    ///
+    /// Only reported in line info, so cannot be requested.
+    Used,
+
+    /// Edge detection, which v1 line info does not report.
+    EdgeDetection,
+
+    /// Edge event timestamps from the realtime clock.
+    EventClockRealtime,
+
+    /// Edge event timestamps from the hardware timestamp engine.
+    EventClockHte,
+
+    /// A debounce period.
+    Debounce,
+
+    /// Flags unknown to this crate.
+    UnknownFlags(u64),
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Used => write!(f, "used"),
+            Feature::EdgeDetection => write!(f, "edge detection"),
+            Feature::EventClockRealtime => write!(f, "realtime event clock"),
+            Feature::EventClockHte => write!(f, "HTE event clock"),
+            Feature::Debounce => write!(f, "debounce"),
+            Feature::UnknownFlags(bits) => write!(f, "unknown flags {:#x}", bits),
+        }
+    }
+}
+
+/// The features dropped when converting an ABI v2 structure to ABI v1.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("ABI v1 does not support {}.", join(.0))]
+pub struct UnsupportedFeatures(pub Vec<Feature>);
+
+fn join(features: &[Feature]) -> String {
+    features
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// The flags common to v1 HandleRequestFlags and v2 LineFlags.
+const HANDLE_FLAGS: [(v1::HandleRequestFlags, v2::LineFlags); 8] = [
+    (v1::HandleRequestFlags::INPUT, v2::LineFlags::INPUT),
+    (v1::HandleRequestFlags::OUTPUT, v2::LineFlags::OUTPUT),
+    (
+        v1::HandleRequestFlags::ACTIVE_LOW,
+        v2::LineFlags::ACTIVE_LOW,
+    ),
+    (
+        v1::HandleRequestFlags::OPEN_DRAIN,
+        v2::LineFlags::OPEN_DRAIN,
+    ),
+    (
+        v1::HandleRequestFlags::OPEN_SOURCE,
+        v2::LineFlags::OPEN_SOURCE,
+    ),
+    (
+        v1::HandleRequestFlags::BIAS_PULL_UP,
+        v2::LineFlags::BIAS_PULL_UP,
+    ),
+    (
+        v1::HandleRequestFlags::BIAS_PULL_DOWN,
+        v2::LineFlags::BIAS_PULL_DOWN,
+    ),
+    (
+        v1::HandleRequestFlags::BIAS_DISABLED,
+        v2::LineFlags::BIAS_DISABLED,
+    ),
+];
+
+// The flags common to v1 EventRequestFlags and v2 LineFlags.
+const EVENT_FLAGS: [(v1::EventRequestFlags, v2::LineFlags); 2] = [
+    (
+        v1::EventRequestFlags::RISING_EDGE,
+        v2::LineFlags::EDGE_RISING,
+    ),
+    (
+        v1::EventRequestFlags::FALLING_EDGE,
+        v2::LineFlags::EDGE_FALLING,
+    ),
+];
+
+// The flags common to v1 LineInfoFlags and v2 LineFlags.
+const INFO_FLAGS: [(v1::LineInfoFlags, v2::LineFlags); 8] = [
+    (v1::LineInfoFlags::USED, v2::LineFlags::USED),
+    (v1::LineInfoFlags::OUTPUT, v2::LineFlags::OUTPUT),
+    (v1::LineInfoFlags::ACTIVE_LOW, v2::LineFlags::ACTIVE_LOW),
+    (v1::LineInfoFlags::OPEN_DRAIN, v2::LineFlags::OPEN_DRAIN),
+    (v1::LineInfoFlags::OPEN_SOURCE, v2::LineFlags::OPEN_SOURCE),
+    (v1::LineInfoFlags::BIAS_PULL_UP, v2::LineFlags::BIAS_PULL_UP),
+    (
+        v1::LineInfoFlags::BIAS_PULL_DOWN,
+        v2::LineFlags::BIAS_PULL_DOWN,
+    ),
+    (
+        v1::LineInfoFlags::BIAS_DISABLED,
+        v2::LineFlags::BIAS_DISABLED,
+    ),
+];
+
+impl From<v1::HandleRequestFlags> for v2::LineFlags {
+    fn from(hf: v1::HandleRequestFlags) -> Self {
+        let mut flags = v2::LineFlags::default();
+        for (f1, f2) in HANDLE_FLAGS {
+            flags.set(f2, hf.contains(f1));
+        }
+        flags
+    }
+}
+
+impl From<v1::EventRequestFlags> for v2::LineFlags {
+    /// The edge flags, and the input flag that ABI v2 requires for edge detection,
+    /// but which ABI v1 implies for event requests.
+    fn from(ef: v1::EventRequestFlags) -> Self {
+        let mut flags = v2::LineFlags::default();
+        for (f1, f2) in EVENT_FLAGS {
+            flags.set(f2, ef.contains(f1));
+        }
+        if !flags.is_empty() {
+            flags |= v2::LineFlags::INPUT;
+        }
+        flags
+    }
+}
+
+impl From<(v1::HandleRequestFlags, v1::EventRequestFlags)> for v2::LineFlags {
+    /// The flags for an [`v1::EventRequest`], from its handle and event flags.
+    fn from((hf, ef): (v1::HandleRequestFlags, v1::EventRequestFlags)) -> Self {
+        v2::LineFlags::from(hf) | v2::LineFlags::from(ef)
+    }
+}
+
+impl TryFrom<v2::LineFlags> for (v1::HandleRequestFlags, v1::EventRequestFlags) {
+    type Error = UnsupportedFeatures;
+
+    /// The handle and event flags for a request with the v2 flags.
+    ///
+    /// Returns [`UnsupportedFeatures`] if the flags include the used flag, an event
+    /// clock, or unknown flags.
+    fn try_from(lf: v2::LineFlags) -> Result<Self, Self::Error> {
+        let mut dropped = Vec::new();
+        if lf.contains(v2::LineFlags::USED) {
+            dropped.push(Feature::Used);
+        }
+        push_event_clocks(lf, &mut dropped);
+        push_unknown_flags(lf, &mut dropped);
+        if !dropped.is_empty() {
+            return Err(UnsupportedFeatures(dropped));
+        }
+        let mut hf = v1::HandleRequestFlags::default();
+        for (f1, f2) in HANDLE_FLAGS {
+            hf.set(f1, lf.contains(f2));
+        }
+        let mut ef = v1::EventRequestFlags::default();
+        for (f1, f2) in EVENT_FLAGS {
+            ef.set(f1, lf.contains(f2));
+        }
+        Ok((hf, ef))
+    }
+}
+
+fn push_event_clocks(lf: v2::LineFlags, dropped: &mut Vec<Feature>) {
+    if lf.contains(v2::LineFlags::EVENT_CLOCK_REALTIME) {
+        dropped.push(Feature::EventClockRealtime);
+    }
+    if lf.contains(v2::LineFlags::EVENT_CLOCK_HTE) {
+        dropped.push(Feature::EventClockHte);
+    }
+}
+
+fn push_unknown_flags(lf: v2::LineFlags, dropped: &mut Vec<Feature>) {
+    let unknown = lf.bits() & !v2::LineFlags::all().bits();
+    if unknown != 0 {
+        dropped.push(Feature::UnknownFlags(unknown));
+    }
+}
+
+impl From<&v1::LineInfo> for v2::LineInfo {
+    fn from(li: &v1::LineInfo) -> Self {
+        let mut flags = v2::LineFlags::default();
+        for (f1, f2) in INFO_FLAGS {
+            flags.set(f2, li.flags.contains(f1));
+        }
+        // v1 has no input flag - lines are inputs unless outputs.
+        if !flags.contains(v2::LineFlags::OUTPUT) {
+            flags |= v2::LineFlags::INPUT;
+        }
+        v2::LineInfo {
+            name: li.name.clone(),
+            consumer: li.consumer.clone(),
+            offset: li.offset,
+            flags,
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&v2::LineInfo> for v1::LineInfo {
+    type Error = UnsupportedFeatures;
+
+    /// Returns [`UnsupportedFeatures`] if the info includes edge detection,
+    /// an event clock, a debounce period, or unknown flags.
+    fn try_from(li: &v2::LineInfo) -> Result<Self, Self::Error> {
+        let mut dropped = Vec::new();
+        if li
+            .flags
+            .intersects(v2::LineFlags::EDGE_RISING | v2::LineFlags::EDGE_FALLING)
+        {
+            dropped.push(Feature::EdgeDetection);
+        }
+        push_event_clocks(li.flags, &mut dropped);
+        let num_attrs = std::cmp::min(li.num_attrs as usize, v2::NUM_ATTRS_MAX);
+        if (0..num_attrs).any(|idx| {
+            matches!(li.attr(idx).to_value(),
+                Some(v2::LineAttributeValue::DebouncePeriod(d)) if !d.is_zero())
+        }) {
+            dropped.push(Feature::Debounce);
+        }
+        push_unknown_flags(li.flags, &mut dropped);
+        if !dropped.is_empty() {
+            return Err(UnsupportedFeatures(dropped));
+        }
+        let mut flags = v1::LineInfoFlags::default();
+        for (f1, f2) in INFO_FLAGS {
+            flags.set(f1, li.flags.contains(f2));
+        }
+        Ok(v1::LineInfo {
+            offset: li.offset,
+            flags,
+            name: li.name.clone(),
+            consumer: li.consumer.clone(),
+        })
+    }
+}
+
+impl From<&v1::HandleConfig> for v2::LineConfig {
+    /// The config applies to all lines in the request, so the output values
+    /// are masked for all lines.
+    fn from(hc: &v1::HandleConfig) -> Self {
+        let mut lc = v2::LineConfig {
+            flags: v2::LineFlags::from(hc.flags),
+            ..Default::default()
+        };
+        if hc.flags.contains(v1::HandleRequestFlags::OUTPUT) {
+            let mut values = v2::LineValues {
+                bits: 0,
+                mask: u64::MAX,
+            };
+            for idx in 0..64 {
+                if hc.values.get(idx) != 0 {
+                    values.bits |= 1 << idx;
+                }
+            }
+            lc.add_values(&values);
+        }
+        lc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn handle_flags() {
+        for (f1, f2) in HANDLE_FLAGS {
+            assert_eq!(v2::LineFlags::from(f1), f2, "{:?}", f1);
+            assert_eq!(
+                <(v1::HandleRequestFlags, v1::EventRequestFlags)>::try_from(f2),
+                Ok((f1, v1::EventRequestFlags::default())),
+                "{:?}",
+                f2
+            );
+        }
+        assert_eq!(
+            v2::LineFlags::from(v1::HandleRequestFlags::all()),
+            HANDLE_FLAGS
+                .iter()
+                .fold(v2::LineFlags::default(), |a, f| a | f.1)
+        );
+        assert!(v2::LineFlags::from(v1::HandleRequestFlags::default()).is_empty());
+    }
+
+    #[test]
+    fn event_flags() {
+        for (f1, f2) in EVENT_FLAGS {
+            assert_eq!(
+                v2::LineFlags::from(f1),
+                f2 | v2::LineFlags::INPUT,
+                "{:?}",
+                f1
+            );
+            assert_eq!(
+                <(v1::HandleRequestFlags, v1::EventRequestFlags)>::try_from(f2),
+                Ok((v1::HandleRequestFlags::default(), f1)),
+                "{:?}",
+                f2
+            );
+        }
+        assert_eq!(
+            v2::LineFlags::from(v1::EventRequestFlags::BOTH_EDGES),
+            v2::LineFlags::EDGE_RISING | v2::LineFlags::EDGE_FALLING | v2::LineFlags::INPUT
+        );
+        assert!(v2::LineFlags::from(v1::EventRequestFlags::default()).is_empty());
+    }
+
+    #[test]
+    fn event_request_flags() {
+        let hf = v1::HandleRequestFlags::ACTIVE_LOW | v1::HandleRequestFlags::BIAS_PULL_UP;
+        let ef = v1::EventRequestFlags::FALLING_EDGE;
+        let lf = v2::LineFlags::from((hf, ef));
+        assert_eq!(
+            lf,
+            v2::LineFlags::INPUT
+                | v2::LineFlags::ACTIVE_LOW
+                | v2::LineFlags::BIAS_PULL_UP
+                | v2::LineFlags::EDGE_FALLING
+        );
+        assert_eq!(
+            <(v1::HandleRequestFlags, v1::EventRequestFlags)>::try_from(lf),
+            Ok((hf | v1::HandleRequestFlags::INPUT, ef))
+        );
+    }
+
+    #[test]
+    fn unsupported_flags() {
+        let try_from = <(v1::HandleRequestFlags, v1::EventRequestFlags)>::try_from;
+        assert_eq!(
+            try_from(v2::LineFlags::USED | v2::LineFlags::INPUT),
+            Err(UnsupportedFeatures(vec![Feature::Used]))
+        );
+        assert_eq!(
+            try_from(v2::LineFlags::EVENT_CLOCK_REALTIME),
+            Err(UnsupportedFeatures(vec![Feature::EventClockRealtime]))
+        );
+        assert_eq!(
+            try_from(v2::LineFlags::EVENT_CLOCK_HTE),
+            Err(UnsupportedFeatures(vec![Feature::EventClockHte]))
+        );
+        assert_eq!(
+            try_from(v2::LineFlags::from_bits_retain(0x10000)),
+            Err(UnsupportedFeatures(vec![Feature::UnknownFlags(0x10000)]))
+        );
+        let err = try_from(
+            v2::LineFlags::USED
+                | v2::LineFlags::EVENT_CLOCK_HTE
+                | v2::LineFlags::from_bits_retain(0x30000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            UnsupportedFeatures(vec![
+                Feature::Used,
+                Feature::EventClockHte,
+                Feature::UnknownFlags(0x30000)
+            ])
+        );
+        assert_eq!(
+            err.to_string(),
+            "ABI v1 does not support used, HTE event clock, unknown flags 0x30000."
+        );
+    }
+
+    fn v2_info(flags: v2::LineFlags) -> v2::LineInfo {
+        v2::LineInfo {
+            name: "banana".into(),
+            consumer: "peeler".into(),
+            offset: 3,
+            flags,
+            ..Default::default()
+        }
+    }
+
+    fn v1_info(flags: v1::LineInfoFlags) -> v1::LineInfo {
+        v1::LineInfo {
+            offset: 3,
+            flags,
+            name: "banana".into(),
+            consumer: "peeler".into(),
+        }
+    }
+
+    #[test]
+    fn info_flags() {
+        for (f1, f2) in INFO_FLAGS {
+            let mut expected = f2;
+            if f1 != v1::LineInfoFlags::OUTPUT {
+                expected |= v2::LineFlags::INPUT;
+            }
+            assert_eq!(
+                v2::LineInfo::from(&v1_info(f1)),
+                v2_info(expected),
+                "{:?}",
+                f1
+            );
+            assert_eq!(
+                v1::LineInfo::try_from(&v2_info(f2)),
+                Ok(v1_info(f1)),
+                "{:?}",
+                f2
+            );
+        }
+        // v1 has no input flag
+        assert_eq!(
+            v1::LineInfo::try_from(&v2_info(v2::LineFlags::INPUT)),
+            Ok(v1_info(v1::LineInfoFlags::default()))
+        );
+        assert_eq!(
+            v2::LineInfo::from(&v1_info(v1::LineInfoFlags::default())),
+            v2_info(v2::LineFlags::INPUT)
+        );
+    }
+
+    #[test]
+    fn info_unsupported() {
+        for (flags, feature) in [
+            (v2::LineFlags::EDGE_RISING, Feature::EdgeDetection),
+            (v2::LineFlags::EDGE_FALLING, Feature::EdgeDetection),
+            (
+                v2::LineFlags::EVENT_CLOCK_REALTIME,
+                Feature::EventClockRealtime,
+            ),
+            (v2::LineFlags::EVENT_CLOCK_HTE, Feature::EventClockHte),
+            (
+                v2::LineFlags::from_bits_retain(0x8000),
+                Feature::UnknownFlags(0x8000),
+            ),
+        ] {
+            assert_eq!(
+                v1::LineInfo::try_from(&v2_info(flags | v2::LineFlags::INPUT)),
+                Err(UnsupportedFeatures(vec![feature])),
+                "{:?}",
+                flags
+            );
+        }
+
+        let mut li = v2_info(
+            v2::LineFlags::INPUT | v2::LineFlags::EDGE_RISING | v2::LineFlags::EDGE_FALLING,
+        );
+        li.num_attrs = 1;
+        li.attr_mut(0).set_debounce_period_us(1000);
+        let err = v1::LineInfo::try_from(&li).unwrap_err();
+        assert_eq!(
+            err,
+            UnsupportedFeatures(vec![Feature::EdgeDetection, Feature::Debounce])
+        );
+        assert_eq!(
+            err.to_string(),
+            "ABI v1 does not support edge detection, debounce."
+        );
+
+        // a zero debounce period is no debounce
+        let mut li = v2_info(v2::LineFlags::INPUT);
+        li.num_attrs = 1;
+        li.attr_mut(0).set_debounce_period_us(0);
+        assert_eq!(
+            v1::LineInfo::try_from(&li),
+            Ok(v1_info(v1::LineInfoFlags::default()))
+        );
+        assert_eq!(
+            li.attr(0).to_value(),
+            Some(v2::LineAttributeValue::DebouncePeriod(Duration::ZERO))
+        );
+    }
+
+    #[test]
+    fn handle_config() {
+        let hc = v1::HandleConfig {
+            flags: v1::HandleRequestFlags::INPUT | v1::HandleRequestFlags::BIAS_PULL_DOWN,
+            ..Default::default()
+        };
+        let lc = v2::LineConfig::from(&hc);
+        assert_eq!(
+            lc.flags,
+            v2::LineFlags::INPUT | v2::LineFlags::BIAS_PULL_DOWN
+        );
+        assert_eq!(lc.num_attrs, 0);
+
+        let hc = v1::HandleConfig {
+            flags: v1::HandleRequestFlags::OUTPUT | v1::HandleRequestFlags::OPEN_DRAIN,
+            values: v1::LineValues::from_slice(&[1, 0, 2, 0, 1]),
+            ..Default::default()
+        };
+        let lc = v2::LineConfig::from(&hc);
+        assert_eq!(lc.flags, v2::LineFlags::OUTPUT | v2::LineFlags::OPEN_DRAIN);
+        assert_eq!(lc.num_attrs, 1);
+        let lca = lc.attr(0);
+        assert_eq!(lca.mask, u64::MAX);
+        assert_eq!(
+            lca.attr.to_value(),
+            Some(v2::LineAttributeValue::Values(0b10101))
+        );
+    }
+}
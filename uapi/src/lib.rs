@@ -6,12 +6,20 @@
 
 pub(crate) mod common;
 
+pub use common::{Errno, Error, Name, Result, ValidationError, NAME_LEN_MAX, NUM_LINES_MAX};
 // move ops into v1/v2??
+#[cfg(feature = "ioctl")]
 pub use common::{
-    has_event, read_event, wait_event, Errno, Error, Name, Result, ValidationError, NAME_LEN_MAX,
-    NUM_LINES_MAX,
+    close, has_event, read_event, set_nonblocking, wait_event, wait_events, wait_readable,
 };
 
+/// This module provides the indirection used to mock the system calls made by
+/// the uAPI wrapper functions, allowing error paths to be tested without a kernel.
+///
+/// Intended for testing only.
+#[cfg(feature = "mockable")]
+pub mod mock;
+
 /// This module implements GPIO ABI v1 which was released in Linux v4.8.
 ///
 /// This ABI version is deprecated.
@@ -25,3 +33,11 @@ pub mod v1;
 /// released in Linux v5.10.
 #[cfg(any(feature = "uapi_v2", not(feature = "uapi_v1")))]
 pub mod v2;
+
+/// Conversions between the GPIO ABI v1 and v2 structures, to assist migrating
+/// from v1 to v2.
+///
+/// Conversions from v2 to v1 fail if the v2 structure uses features that v1
+/// does not support.
+#[cfg(all(feature = "uapi_v1", feature = "uapi_v2"))]
+pub mod compat;
@@ -0,0 +1,225 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::os::unix::prelude::RawFd;
+use std::rc::Rc;
+
+/// The type of the request parameter passed to ioctl.
+pub type IoctlRequestType = crate::common::IoctlRequestType;
+
+/// The system calls made by the uAPI wrapper functions.
+///
+/// The calls follow libc semantics, so a failed call sets errno and returns -1.
+pub trait UapiCalls {
+    /// Perform an ioctl on the file descriptor.
+    ///
+    /// # Safety
+    ///
+    /// The `arg` must point to memory appropriate for the `request`.
+    unsafe fn ioctl(
+        &self,
+        fd: RawFd,
+        request: IoctlRequestType,
+        arg: *mut libc::c_void,
+    ) -> libc::c_int;
+
+    /// Read up to `count` bytes from the file descriptor into `buf`.
+    ///
+    /// # Safety
+    ///
+    /// The `buf` must be valid for writes of `count` bytes.
+    unsafe fn read(&self, fd: RawFd, buf: *mut libc::c_void, count: usize) -> isize;
+}
+
+/// The default implementation, which passes calls directly to libc.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Libc;
+
+impl UapiCalls for Libc {
+    unsafe fn ioctl(
+        &self,
+        fd: RawFd,
+        request: IoctlRequestType,
+        arg: *mut libc::c_void,
+    ) -> libc::c_int {
+        libc::ioctl(fd, request, arg)
+    }
+
+    unsafe fn read(&self, fd: RawFd, buf: *mut libc::c_void, count: usize) -> isize {
+        libc::read(fd, buf, count)
+    }
+}
+
+thread_local! {
+    static CALLS: RefCell<Option<Rc<dyn UapiCalls>>> = RefCell::new(None);
+}
+
+/// Install the calls to be used by the uAPI wrapper functions on the current thread.
+///
+/// The calls remain installed until the returned guard is dropped, at which point
+/// the previously installed calls are restored.
+pub fn install(calls: Rc<dyn UapiCalls>) -> Installed {
+    let prev = CALLS.with(|c| c.borrow_mut().replace(calls));
+    Installed { prev }
+}
+
+/// A guard that uninstalls the calls when dropped.
+#[must_use = "the calls are uninstalled when the guard is dropped"]
+pub struct Installed {
+    prev: Option<Rc<dyn UapiCalls>>,
+}
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CALLS.with(|c| *c.borrow_mut() = prev);
+    }
+}
+
+fn installed() -> Option<Rc<dyn UapiCalls>> {
+    CALLS.with(|c| c.borrow().clone())
+}
+
+pub(crate) unsafe fn ioctl(
+    fd: RawFd,
+    request: IoctlRequestType,
+    arg: *mut libc::c_void,
+) -> libc::c_int {
+    match installed() {
+        Some(calls) => calls.ioctl(fd, request, arg),
+        None => Libc.ioctl(fd, request, arg),
+    }
+}
+
+pub(crate) unsafe fn read(fd: RawFd, buf: *mut libc::c_void, count: usize) -> isize {
+    match installed() {
+        Some(calls) => calls.read(fd, buf, count),
+        None => Libc.read(fd, buf, count),
+    }
+}
+
+/// Set errno for the current thread.
+pub fn set_errno(errno: i32) {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        *libc::__errno_location() = errno;
+    }
+    #[cfg(target_os = "android")]
+    unsafe {
+        *libc::__errno() = errno;
+    }
+}
+
+/// A call made via the [`FaultInjector`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Call {
+    /// An ioctl call.
+    Ioctl {
+        /// The file descriptor.
+        fd: RawFd,
+        /// The ioctl request.
+        request: IoctlRequestType,
+    },
+    /// A read call.
+    Read {
+        /// The file descriptor.
+        fd: RawFd,
+        /// The size of the buffer.
+        count: usize,
+    },
+}
+
+/// The response to a call made via the [`FaultInjector`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Response {
+    /// Pass the call through to libc.
+    Passthrough,
+
+    /// Fail the call with the errno.
+    Errno(i32),
+
+    /// Succeed, copying the data into the ioctl argument or read buffer.
+    ///
+    /// For reads, the data is truncated to the size of the buffer and the
+    /// length copied is returned.
+    ///
+    /// For ioctls, the data must match the size of the ioctl argument, as
+    /// encoded in the ioctl request, else the call panics.
+    Data(Vec<u8>),
+}
+
+/// A [`UapiCalls`] implementation that records calls and responds with
+/// queued responses.
+///
+/// Calls made when the queue is empty are passed through to libc.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    responses: RefCell<VecDeque<Response>>,
+    calls: RefCell<Vec<Call>>,
+}
+
+impl FaultInjector {
+    /// Queue a response for a subsequent call.
+    pub fn push_response(&self, response: Response) -> &Self {
+        self.responses.borrow_mut().push_back(response);
+        self
+    }
+
+    /// The calls made so far.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.borrow().clone()
+    }
+
+    fn next_response(&self, call: Call) -> Response {
+        self.calls.borrow_mut().push(call);
+        self.responses
+            .borrow_mut()
+            .pop_front()
+            .unwrap_or(Response::Passthrough)
+    }
+}
+
+impl UapiCalls for FaultInjector {
+    unsafe fn ioctl(
+        &self,
+        fd: RawFd,
+        request: IoctlRequestType,
+        arg: *mut libc::c_void,
+    ) -> libc::c_int {
+        match self.next_response(Call::Ioctl { fd, request }) {
+            Response::Passthrough => Libc.ioctl(fd, request, arg),
+            Response::Errno(errno) => {
+                set_errno(errno);
+                -1
+            }
+            Response::Data(d) => {
+                let size = ioctl_sys::ioc_size(request as u32) as usize;
+                assert_eq!(
+                    d.len(),
+                    size,
+                    "ioctl response data does not match the size of the ioctl argument"
+                );
+                std::ptr::copy_nonoverlapping(d.as_ptr(), arg as *mut u8, size);
+                0
+            }
+        }
+    }
+
+    unsafe fn read(&self, fd: RawFd, buf: *mut libc::c_void, count: usize) -> isize {
+        match self.next_response(Call::Read { fd, count }) {
+            Response::Passthrough => Libc.read(fd, buf, count),
+            Response::Errno(errno) => {
+                set_errno(errno);
+                -1
+            }
+            Response::Data(d) => {
+                let len = std::cmp::min(d.len(), count);
+                std::ptr::copy_nonoverlapping(d.as_ptr(), buf as *mut u8, len);
+                len as isize
+            }
+        }
+    }
+}
@@ -3,12 +3,15 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use bitflags::bitflags;
+#[cfg(feature = "ioctl")]
 use std::fs::File;
+#[cfg(feature = "ioctl")]
 use std::os::unix::prelude::{AsRawFd, FromRawFd};
 
 // common to ABI v1 and v2.
 pub use super::common::*;
 
+#[cfg(feature = "ioctl")]
 #[repr(u8)]
 enum Ioctl {
     GetLineInfo = 2,
@@ -83,6 +86,7 @@ bitflags! {
 ///
 /// * 'cf' - The open gpiochip device file.
 /// * `offset` - The offset of the line.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
     let li = LineInfo {
@@ -90,7 +94,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
+    match unsafe { ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
         0 => Ok(li),
         _ => Err(Error::from_errno()),
     }
@@ -104,6 +108,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
 ///
 /// * 'cf' - The open gpiochip device file.
 /// * `offset` - The offset of the line to watch.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
     let li = LineInfo {
@@ -111,7 +116,7 @@ pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
+    match unsafe { ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
         0 => Ok(li),
         _ => Err(Error::from_errno()),
     }
@@ -162,12 +167,134 @@ impl LineInfoChangeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineInfoChangeEvent from a byte buffer.
+    ///
+    /// Unlike [`from_slice`](Self::from_slice), the buffer need not be aligned,
+    /// as the event is copied out of the buffer rather than referenced in place.
+    ///
+    /// The buffer is assumed to have been populated by a read of the chip File,
+    /// so the content is validated before being returned.
+    pub fn read_from(buf: &[u8]) -> Result<LineInfoChangeEvent> {
+        let mut d = [0_u64; std::mem::size_of::<LineInfoChangeEvent>() / 8];
+        copy_to_words("LineInfoChangeEvent", buf, &mut d)?;
+        Self::from_slice(&d).cloned()
+    }
+
+    /// Read a single LineInfoChangeEvent from the chip file, returning it by value.
+    ///
+    /// Exactly one event is read, so any further queued events remain available
+    /// to subsequent reads.
+    ///
+    /// The event is read into a buffer on the stack and copied out, so it may
+    /// outlive the read, e.g. be returned from a helper function.
+    /// Prefer [`read_info_change_event`], or [`read_info_change_events`] for bursts
+    /// of events, where the event can be decoded in place from a longer lived buffer.
+    ///
+    /// Reads interrupted by a signal are retried.
+    ///
+    /// If the file is [non-blocking] and no event is available then
+    /// [`Error::WouldBlock`] is returned.
+    ///
+    /// * `cf` - The open gpiochip device file.
+    ///
+    /// [non-blocking]: set_nonblocking
+    #[cfg(feature = "ioctl")]
+    pub fn read_one(cf: &File) -> Result<LineInfoChangeEvent> {
+        let mut buf = [0_u64; std::mem::size_of::<LineInfoChangeEvent>() / 8];
+        read_info_change_event(cf, &mut buf).cloned()
+    }
+
     /// The number of u64 words required to store a LineInfoChangeEvent.
     pub fn u64_size() -> usize {
         std::mem::size_of::<LineInfoChangeEvent>() / 8
     }
 }
 
+/// The size of a LineInfoChangeEvent, in bytes.
+pub const LINE_INFO_CHANGE_EVENT_SIZE: usize = std::mem::size_of::<LineInfoChangeEvent>();
+
+/// Read a single LineInfoChangeEvent from the chip file.
+///
+/// The event is read into, and returned from, the start of `buf`, which must be
+/// at least [`LineInfoChangeEvent::u64_size`] words.
+///
+/// Reads interrupted by a signal are retried.
+///
+/// If the file is [non-blocking] and no event is available then
+/// [`Error::WouldBlock`] is returned.
+///
+/// * `cf` - The open gpiochip device file.
+/// * `buf` - The buffer to read the event into.
+///
+/// [non-blocking]: set_nonblocking
+#[cfg(feature = "ioctl")]
+pub fn read_info_change_event<'a>(
+    cf: &File,
+    buf: &'a mut [u64],
+) -> Result<&'a LineInfoChangeEvent> {
+    // limit the read to a single event
+    let size = LineInfoChangeEvent::u64_size().min(buf.len());
+    let buf = &mut buf[..size];
+    read_records("LineInfoChangeEvent", cf, buf, LINE_INFO_CHANGE_EVENT_SIZE)?;
+    LineInfoChangeEvent::from_slice(buf)
+}
+
+/// Read as many LineInfoChangeEvents from the chip file as will fit in `buf`.
+///
+/// The events are returned as an iterator over the portion of `buf` populated
+/// by the read.
+///
+/// Reads interrupted by a signal are retried.
+///
+/// If the file is [non-blocking] and no event is available then
+/// [`Error::WouldBlock`] is returned.
+///
+/// * `cf` - The open gpiochip device file.
+/// * `buf` - The buffer to read the events into.
+///
+/// [non-blocking]: set_nonblocking
+#[cfg(feature = "ioctl")]
+pub fn read_info_change_events<'a>(
+    cf: &File,
+    buf: &'a mut [u64],
+) -> Result<InfoChangeEventIter<'a>> {
+    let n = read_records("LineInfoChangeEvent", cf, buf, LINE_INFO_CHANGE_EVENT_SIZE)?;
+    Ok(InfoChangeEventIter::new(&buf[..n]))
+}
+
+/// An iterator over the LineInfoChangeEvents contained in a buffer.
+///
+/// The buffer is assumed to have been populated by a read of the chip File,
+/// so each event is validated before being returned.
+///
+/// Iteration stops after the first invalid or partial event.
+#[derive(Clone, Debug)]
+pub struct InfoChangeEventIter<'a> {
+    buf: &'a [u64],
+}
+
+impl<'a> InfoChangeEventIter<'a> {
+    /// Create an iterator over the events contained in `buf`.
+    pub fn new(buf: &'a [u64]) -> InfoChangeEventIter<'a> {
+        InfoChangeEventIter { buf }
+    }
+}
+
+impl<'a> Iterator for InfoChangeEventIter<'a> {
+    type Item = Result<&'a LineInfoChangeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let size = LineInfoChangeEvent::u64_size().min(self.buf.len());
+        let (event, rest) = self.buf.split_at(size);
+        let res = LineInfoChangeEvent::from_slice(event);
+        self.buf = if res.is_ok() { rest } else { &[] };
+        Some(res)
+    }
+}
+
 /// Information about a GPIO line handle request.
 #[repr(C)]
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -206,6 +333,15 @@ pub struct HandleRequest {
     pub fd: i32,
 }
 
+impl HandleRequest {
+    /// An iterator over the requested offsets.
+    ///
+    /// Only the first `num_lines` offsets are yielded.
+    pub fn requested_offsets(&self) -> std::slice::Iter<'_, Offset> {
+        self.offsets.iter(self.num_lines as usize)
+    }
+}
+
 bitflags! {
     /// Configuration flags for requested lines.
     ///
@@ -244,11 +380,17 @@ bitflags! {
 ///
 /// * 'cf' - The open gpiochip device file.
 /// * `hr` - The line handle request.
+///
+/// Returns [`Error::TooManyLines`] if the request contains more than [`NUM_LINES_MAX`] lines.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_handle(cf: &File, hr: HandleRequest) -> Result<File> {
+    if hr.num_lines as usize > NUM_LINES_MAX {
+        return Err(Error::TooManyLines(hr.num_lines));
+    }
     // SAFETY: hr is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(
+        match ioctl(
             cf.as_raw_fd(),
             iorw!(Ioctl::GetLineHandle, HandleRequest),
             &hr,
@@ -283,11 +425,12 @@ pub struct HandleConfig {
 ///
 /// * `lf` - The request file returned by [`get_line_handle`].
 /// * `hc` - The configuration to be applied.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn set_line_config(lf: &File, hc: HandleConfig) -> Result<()> {
     // SAFETY: hc is consumed.
     unsafe {
-        match libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetConfig, HandleConfig), &hc) {
+        match ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetConfig, HandleConfig), &hc) {
             0 => Ok(()),
             _ => Err(Error::from_errno()),
         }
@@ -326,7 +469,7 @@ impl LineValues {
     /// Copy values from an iterable list - in order of requested offsets.
     pub fn copy_from_slice(&mut self, s: &[u8]) {
         let extent = std::cmp::min(64usize, s.len());
-        self.0[0..extent].copy_from_slice(s);
+        self.0[0..extent].copy_from_slice(&s[0..extent]);
     }
 
     /// Return the value of a line.
@@ -366,11 +509,12 @@ impl Default for LineValues {
 ///
 /// * `lf` - The request file returned by [`get_line_handle`] or [`get_line_event`].
 /// * `vals` - The line values to be populated.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_values(lf: &File, vals: &mut LineValues) -> Result<()> {
     // SAFETY: vals are raw integers that are safe to decode.
     match unsafe {
-        libc::ioctl(
+        ioctl(
             lf.as_raw_fd(),
             iorw!(Ioctl::GetLineValues, LineValues),
             vals.0.as_mut_ptr(),
@@ -385,11 +529,12 @@ pub fn get_line_values(lf: &File, vals: &mut LineValues) -> Result<()> {
 ///
 /// * `lf` - The request file returned by [`get_line_handle`].
 /// * `vals` - The line values to be set.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn set_line_values(lf: &File, vals: &LineValues) -> Result<()> {
     // SAFETY: vals is not modified.
     match unsafe {
-        libc::ioctl(
+        ioctl(
             lf.as_raw_fd(),
             iorw!(Ioctl::SetLineValues, LineValues),
             vals.0.as_ptr(),
@@ -447,11 +592,12 @@ bitflags! {
 ///
 /// * 'cf' - The open gpiochip device file.
 /// * `er` - The line event request.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_event(cf: &File, er: EventRequest) -> Result<File> {
     // SAFETY: er is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(
+        match ioctl(
             cf.as_raw_fd(),
             iorw!(Ioctl::GetLineEvent, EventRequest),
             &er,
@@ -464,7 +610,7 @@ pub fn get_line_event(cf: &File, er: EventRequest) -> Result<File> {
 
 /// Information about an edge event on a requested line.
 #[repr(C)]
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct LineEdgeEvent {
     /// The best estimate of time of event occurrence, in nanoseconds.
     pub timestamp_ns: u64,
@@ -499,16 +645,231 @@ impl LineEdgeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineEdgeEvent from a byte buffer.
+    ///
+    /// Unlike [`from_slice`](Self::from_slice), the buffer need not be aligned,
+    /// as the event is copied out of the buffer rather than referenced in place.
+    ///
+    /// The buffer is assumed to have been populated by a read of the line request File,
+    /// so the content is validated before being returned.
+    pub fn read_from(buf: &[u8]) -> Result<LineEdgeEvent> {
+        let mut d = [0_u64; std::mem::size_of::<LineEdgeEvent>() / 8];
+        copy_to_words("LineEdgeEvent", buf, &mut d)?;
+        Self::from_slice(&d).cloned()
+    }
+
+    /// Read a single LineEdgeEvent from the line request file, returning it by value.
+    ///
+    /// Exactly one event is read, so any further queued events remain available
+    /// to subsequent reads.
+    ///
+    /// The event is small enough to be copied out of a buffer on the stack, so
+    /// it may outlive the read, e.g. be returned from a helper function.
+    /// Prefer [`read_event`] into a longer lived buffer, decoded in place using
+    /// [`from_slice`](Self::from_slice), when reading bursts of events.
+    ///
+    /// Reads interrupted by a signal are retried.
+    ///
+    /// If the file is [non-blocking] and no event is available then
+    /// [`Error::WouldBlock`] is returned.
+    ///
+    /// * `lf` - The file returned by the line request.
+    ///
+    /// [non-blocking]: set_nonblocking
+    #[cfg(feature = "ioctl")]
+    pub fn read_one(lf: &File) -> Result<LineEdgeEvent> {
+        let mut buf = [0_u64; std::mem::size_of::<LineEdgeEvent>() / 8];
+        read_records(
+            "LineEdgeEvent",
+            lf,
+            &mut buf,
+            std::mem::size_of::<LineEdgeEvent>(),
+        )?;
+        Self::from_slice(&buf).copied()
+    }
+
     /// The number of u64 words required to store a LineEdgeEvent.
     pub fn u64_size() -> usize {
         std::mem::size_of::<LineEdgeEvent>() / 8
     }
 }
 
+/// The clock used by the kernel to timestamp [`LineEdgeEvent`]s.
+///
+/// Kernels prior to 5.7 timestamp events using **CLOCK_REALTIME**, while
+/// later kernels use **CLOCK_MONOTONIC**.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EventClock {
+    /// The timestamps are from **CLOCK_MONOTONIC**.
+    Monotonic,
+
+    /// The timestamps are from **CLOCK_REALTIME**.
+    Realtime,
+}
+
+/// The minimum separation between the clocks for a timestamp to be classified.
+const CLOCK_SEPARATION_MIN_NS: u64 = 3600 * 1_000_000_000;
+
+/// Determine the clock used to timestamp an edge event.
+///
+/// The timestamp is compared with the current **CLOCK_MONOTONIC** and
+/// **CLOCK_REALTIME**, so should be from a recent event, such as the first
+/// event read from a request.
+///
+/// If the clocks are too close to distinguish, as is the case for systems
+/// without a real-time clock that have not set the time, then the clock is
+/// determined from the kernel version, falling back to **CLOCK_MONOTONIC**.
+#[cfg(feature = "ioctl")]
+pub fn detect_event_clock(timestamp_ns: u64) -> EventClock {
+    classify_event_clock(
+        timestamp_ns,
+        clock_ns(libc::CLOCK_MONOTONIC),
+        clock_ns(libc::CLOCK_REALTIME),
+    )
+    .or_else(kernel_event_clock)
+    .unwrap_or(EventClock::Monotonic)
+}
+
+/// Classify a timestamp as being from the clock with the closest reading.
+///
+/// * `monotonic_ns` - A recent reading of **CLOCK_MONOTONIC**.
+/// * `realtime_ns` - A reading of **CLOCK_REALTIME** taken at the same time.
+///
+/// Returns None if the clocks are too close to be distinguished.
+pub fn classify_event_clock(
+    timestamp_ns: u64,
+    monotonic_ns: u64,
+    realtime_ns: u64,
+) -> Option<EventClock> {
+    if monotonic_ns.abs_diff(realtime_ns) < CLOCK_SEPARATION_MIN_NS {
+        return None;
+    }
+    if timestamp_ns.abs_diff(monotonic_ns) <= timestamp_ns.abs_diff(realtime_ns) {
+        Some(EventClock::Monotonic)
+    } else {
+        Some(EventClock::Realtime)
+    }
+}
+
+/// Determine the clock used to timestamp edge events from the running kernel version.
+///
+/// Returns None if the kernel version cannot be determined.
+#[cfg(feature = "ioctl")]
+pub fn kernel_event_clock() -> Option<EventClock> {
+    // SAFETY: utsname is plain data and is populated by uname.
+    let release = unsafe {
+        let mut uts: libc::utsname = std::mem::zeroed();
+        if libc::uname(&mut uts) != 0 {
+            return None;
+        }
+        std::ffi::CStr::from_ptr(uts.release.as_ptr())
+            .to_string_lossy()
+            .into_owned()
+    };
+    release_event_clock(&release)
+}
+
+// The event clock used by the kernel release, such as "5.4.0-150-generic".
+#[cfg_attr(not(feature = "ioctl"), allow(dead_code))]
+fn release_event_clock(release: &str) -> Option<EventClock> {
+    let mut parts = release.split(|c: char| !c.is_ascii_digit());
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor: u32 = parts.next()?.parse().ok()?;
+    if (major, minor) < (5, 7) {
+        Some(EventClock::Realtime)
+    } else {
+        Some(EventClock::Monotonic)
+    }
+}
+
+// The current time of the clock, in nanoseconds.
+#[cfg(feature = "ioctl")]
+fn clock_ns(clock: libc::clockid_t) -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is populated by clock_gettime, which cannot fail for these clocks.
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "ioctl")]
+    fn pipe() -> (File, File) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+    }
+
+    mod event_clock {
+        use super::{classify_event_clock, release_event_clock, EventClock};
+
+        const SEC: u64 = 1_000_000_000;
+
+        #[test]
+        fn classify() {
+            // a system up for a day, in 2024
+            let mono = 86_400 * SEC;
+            let real = 1_700_000_000 * SEC;
+
+            assert_eq!(
+                classify_event_clock(mono, mono, real),
+                Some(EventClock::Monotonic)
+            );
+            // events are read some time after they occur
+            assert_eq!(
+                classify_event_clock(mono - 5 * SEC, mono, real),
+                Some(EventClock::Monotonic)
+            );
+            assert_eq!(
+                classify_event_clock(real, mono, real),
+                Some(EventClock::Realtime)
+            );
+            assert_eq!(
+                classify_event_clock(real - 5 * SEC, mono, real),
+                Some(EventClock::Realtime)
+            );
+            // a freshly booted system
+            assert_eq!(
+                classify_event_clock(SEC, 2 * SEC, real),
+                Some(EventClock::Monotonic)
+            );
+        }
+
+        #[test]
+        fn classify_indistinguishable() {
+            // the realtime clock was never set, so tracks monotonic closely
+            let mono = 120 * SEC;
+            let real = 125 * SEC;
+            assert_eq!(classify_event_clock(mono, mono, real), None);
+            assert_eq!(classify_event_clock(real, mono, real), None);
+        }
+
+        #[test]
+        fn release() {
+            assert_eq!(
+                release_event_clock("4.19.0-6-amd64"),
+                Some(EventClock::Realtime)
+            );
+            assert_eq!(release_event_clock("5.6.19"), Some(EventClock::Realtime));
+            assert_eq!(release_event_clock("5.7.0"), Some(EventClock::Monotonic));
+            assert_eq!(
+                release_event_clock("5.10.160-v7+"),
+                Some(EventClock::Monotonic)
+            );
+            assert_eq!(
+                release_event_clock("6.1.0-rpi7-rpi-v8"),
+                Some(EventClock::Monotonic)
+            );
+            assert_eq!(release_event_clock("banana"), None);
+            assert_eq!(release_event_clock("6"), None);
+        }
+    }
+
     mod line_info {
         use super::LineInfo;
 
@@ -560,11 +921,100 @@ mod tests {
                 assert!(a.validate().is_ok());
             }
         }
+
+        #[test]
+        fn read_from() {
+            use super::{Error, LineInfo, LineInfoChangeKind, UnderReadError};
+
+            let mut raw = vec![0_u8; 104];
+            raw[0..4].copy_from_slice(&5_u32.to_ne_bytes());
+            raw[72..80].copy_from_slice(&1234_u64.to_ne_bytes());
+            raw[80..84].copy_from_slice(&2_u32.to_ne_bytes());
+            let ev = LineInfoChangeEvent {
+                info: LineInfo {
+                    offset: 5,
+                    ..Default::default()
+                },
+                timestamp_ns: 1234,
+                kind: LineInfoChangeKind::Released,
+                padding: Default::default(),
+            };
+
+            // misaligned
+            let mut buf = vec![0_u8; 1];
+            buf.extend_from_slice(&raw);
+            assert_eq!(LineInfoChangeEvent::read_from(&buf[1..]).unwrap(), ev);
+
+            // truncated
+            assert_eq!(
+                LineInfoChangeEvent::read_from(&buf[1..104]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineInfoChangeEvent", 104, 103))
+            );
+
+            // invalid
+            buf[81] = 4;
+            assert!(matches!(
+                LineInfoChangeEvent::read_from(&buf[1..]),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        #[test]
+        #[cfg(feature = "ioctl")]
+        fn read_one() {
+            use super::{pipe, read_event, set_nonblocking, Error, LineInfoChangeKind};
+            use std::io::Write;
+
+            let size = LineInfoChangeEvent::u64_size() * 8;
+            let (r, mut w) = pipe();
+            for (ts, kind) in [(1234_u64, 1_u32), (5678, 3)] {
+                let mut raw = vec![0_u8; size];
+                let ts_pos = size - 32;
+                raw[ts_pos..ts_pos + 8].copy_from_slice(&ts.to_ne_bytes());
+                raw[ts_pos + 8..ts_pos + 12].copy_from_slice(&kind.to_ne_bytes());
+                w.write_all(&raw).unwrap();
+            }
+
+            // only the first event is consumed
+            let ev = LineInfoChangeEvent::read_one(&r).unwrap();
+            assert_eq!(ev.timestamp_ns, 1234);
+            assert_eq!(ev.kind, LineInfoChangeKind::Requested);
+
+            // leaving the second intact
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 2];
+            assert_eq!(
+                read_event(&r, &mut buf),
+                Ok(LineInfoChangeEvent::u64_size())
+            );
+            let ev = LineInfoChangeEvent::from_slice(&buf).unwrap();
+            assert_eq!(ev.timestamp_ns, 5678);
+            assert_eq!(ev.kind, LineInfoChangeKind::Reconfigured);
+
+            set_nonblocking(&r, true).unwrap();
+            assert_eq!(LineInfoChangeEvent::read_one(&r), Err(Error::WouldBlock));
+        }
     }
 
     mod handle_request {
         use super::HandleRequest;
 
+        #[test]
+        fn requested_offsets() {
+            use super::{Offsets, NUM_LINES_MAX};
+
+            let mut hr = HandleRequest {
+                offsets: Offsets::from_slice(&[4, 2, 7, 9]),
+                ..Default::default()
+            };
+            assert_eq!(hr.requested_offsets().count(), 0);
+            hr.num_lines = 3;
+            let x: Vec<u32> = hr.requested_offsets().copied().collect();
+            assert_eq!(x, [4, 2, 7]);
+            // capped at the size of the array
+            hr.num_lines = 100;
+            assert_eq!(hr.requested_offsets().count(), NUM_LINES_MAX);
+        }
+
         #[test]
         fn size() {
             assert_eq!(
@@ -636,6 +1086,67 @@ mod tests {
                 assert!(a.validate().is_ok());
             }
         }
+
+        #[test]
+        fn read_from() {
+            use super::{Error, LineEdgeEventKind, UnderReadError};
+
+            let mut buf = vec![0_u8; 1];
+            buf.extend_from_slice(&1234_u64.to_ne_bytes());
+            buf.extend_from_slice(&2_u32.to_ne_bytes());
+            buf.resize(17, 0);
+            let ev = LineEdgeEvent {
+                timestamp_ns: 1234,
+                kind: LineEdgeEventKind::FallingEdge,
+            };
+
+            // misaligned
+            assert_eq!(LineEdgeEvent::read_from(&buf[1..]).unwrap(), ev);
+
+            // truncated
+            assert_eq!(
+                LineEdgeEvent::read_from(&buf[1..16]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineEdgeEvent", 16, 15))
+            );
+
+            // invalid
+            buf[9] = 3;
+            assert!(matches!(
+                LineEdgeEvent::read_from(&buf[1..]),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        #[test]
+        #[cfg(feature = "ioctl")]
+        fn read_one() {
+            use super::{pipe, read_event, set_nonblocking, Error, LineEdgeEventKind};
+            use std::io::Write;
+
+            let (r, mut w) = pipe();
+            for (ts, kind) in [(1234_u64, 1_u32), (5678, 2)] {
+                let mut raw = Vec::new();
+                raw.extend_from_slice(&ts.to_ne_bytes());
+                raw.extend_from_slice(&kind.to_ne_bytes());
+                raw.resize(16, 0);
+                w.write_all(&raw).unwrap();
+            }
+
+            // only the first event is consumed
+            let ev = LineEdgeEvent::read_one(&r).unwrap();
+            assert_eq!(ev.timestamp_ns, 1234);
+            assert_eq!(ev.kind, LineEdgeEventKind::RisingEdge);
+
+            // leaving the second intact
+            let mut buf = vec![0_u64; LineEdgeEvent::u64_size() * 2];
+            assert_eq!(read_event(&r, &mut buf), Ok(LineEdgeEvent::u64_size()));
+            let ev = LineEdgeEvent::from_slice(&buf).unwrap();
+            assert_eq!(ev.timestamp_ns, 5678);
+            assert_eq!(ev.kind, LineEdgeEventKind::FallingEdge);
+
+            set_nonblocking(&r, true).unwrap();
+            assert_eq!(LineEdgeEvent::read_one(&r), Err(Error::WouldBlock));
+        }
     }
 
     mod line_values {
@@ -4,13 +4,16 @@
 
 use bitflags::bitflags;
 use std::fmt;
+#[cfg(feature = "ioctl")]
 use std::fs::File;
+#[cfg(feature = "ioctl")]
 use std::os::unix::prelude::{AsRawFd, FromRawFd};
 use std::time::Duration;
 
 // common to ABI v1 and v2.
 pub use super::common::*;
 
+#[cfg(feature = "ioctl")]
 #[repr(u8)]
 enum Ioctl {
     GetLineInfo = 5,
@@ -164,10 +167,11 @@ impl LineValues {
 ///
 /// * `lf` - The request file returned by [`get_line`].
 /// * `lv` - The line values to be populated.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_values(lf: &File, lv: &mut LineValues) -> Result<()> {
     // SAFETY: returned struct contains raw byte arrays and bitfields that are safe to decode.
-    match unsafe { libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::GetLineValues, LineValues), lv) } {
+    match unsafe { ioctl(lf.as_raw_fd(), iorw!(Ioctl::GetLineValues, LineValues), lv) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -179,10 +183,11 @@ pub fn get_line_values(lf: &File, lv: &mut LineValues) -> Result<()> {
 ///
 /// * `lf` - The request file returned by [`get_line`].
 /// * `lv` - The line values to be set.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn set_line_values(lf: &File, lv: &LineValues) -> Result<()> {
     // SAFETY: lv is not modified.
-    match unsafe { libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineValues, LineValues), lv) } {
+    match unsafe { ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineValues, LineValues), lv) } {
         0 => Ok(()),
         _ => Err(Error::from_errno()),
     }
@@ -223,6 +228,7 @@ impl TryFrom<u32> for LineAttributeKind {
 
 impl LineAttributeKind {
     /// Confirm that the value read from the kernel is valid in Rust.
+    #[cfg_attr(not(feature = "ioctl"), allow(dead_code))]
     fn validate(&self) -> std::result::Result<(), String> {
         LineAttributeKind::try_from(*self as u32).map(|_i| ())
     }
@@ -440,17 +446,57 @@ impl LineConfig {
         lca.attr.set_values(values.bits);
         self.num_attrs += 1;
     }
+
+    /// Set the output values for a subset of the requested lines.
+    ///
+    /// The values are merged into the existing values attribute, if any,
+    /// else a new attribute is added to the config.
+    ///
+    /// Returns [`Error::IndexOutOfRange`] if `values` contains a line with an index
+    /// not less than `num_lines`, or [`Error::TooManyAttrs`] if a new attribute is
+    /// required and all the attributes are already in use.
+    /// The config is unaltered in either case.
+    ///
+    /// * `num_lines` - The number of lines in the request.
+    /// * `values` - The values to set, with lines identified by the mask.
+    pub fn set_output_values(&mut self, num_lines: usize, values: &LineValues) -> Result<()> {
+        if num_lines < NUM_LINES_MAX && values.mask >> num_lines != 0 {
+            let idx = 63 - values.mask.leading_zeros() as usize;
+            return Err(Error::IndexOutOfRange(idx, num_lines));
+        }
+        let num_attrs = self.num_attrs as usize;
+        if let Some(lca) = self.attrs.0[..num_attrs]
+            .iter_mut()
+            .find(|lca| lca.attr.kind == LineAttributeKind::Values)
+        {
+            // SAFETY: kind has been checked.
+            let bits = unsafe { lca.attr.value.values };
+            lca.attr
+                .set_values((bits & !values.mask) | (values.bits & values.mask));
+            lca.mask |= values.mask;
+            return Ok(());
+        }
+        if num_attrs >= NUM_ATTRS_MAX {
+            return Err(Error::TooManyAttrs);
+        }
+        self.add_values(&LineValues {
+            bits: values.bits & values.mask,
+            mask: values.mask,
+        });
+        Ok(())
+    }
 }
 
 /// Update the configuration of an existing line request.
 ///
 /// * `lf` - The request file returned by [`get_line`].
 /// * `lc` - The configuration to be applied.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn set_line_config(lf: &File, lc: LineConfig) -> Result<()> {
     // SAFETY: lc is consumed.
     unsafe {
-        match libc::ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineConfig, LineConfig), &lc) {
+        match ioctl(lf.as_raw_fd(), iorw!(Ioctl::SetLineConfig, LineConfig), &lc) {
             0 => Ok(()),
             _ => Err(Error::from_errno()),
         }
@@ -495,15 +541,30 @@ pub struct LineRequest {
     pub fd: i32,
 }
 
+impl LineRequest {
+    /// An iterator over the requested offsets.
+    ///
+    /// Only the first `num_lines` offsets are yielded.
+    pub fn requested_offsets(&self) -> std::slice::Iter<'_, Offset> {
+        self.offsets.iter(self.num_lines as usize)
+    }
+}
+
 /// Request a line or set of lines for exclusive access.
 ///
 /// * `cf` - The open gpiochip device file.
 /// * `lr` - The line request.
+///
+/// Returns [`Error::TooManyLines`] if the request contains more than [`NUM_LINES_MAX`] lines.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line(cf: &File, lr: LineRequest) -> Result<File> {
+    if lr.num_lines as usize > NUM_LINES_MAX {
+        return Err(Error::TooManyLines(lr.num_lines));
+    }
     // SAFETY: lr is consumed and the returned file is drawn from the returned fd.
     unsafe {
-        match libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLine, LineRequest), &lr) {
+        match ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLine, LineRequest), &lr) {
             0 => Ok(File::from_raw_fd(lr.fd)),
             _ => Err(Error::from_errno()),
         }
@@ -563,7 +624,15 @@ impl LineInfo {
     pub fn attr(&self, idx: usize) -> &LineAttribute {
         &self.attrs.0[idx]
     }
+
+    /// The nth attribute in the attrs
+    #[inline]
+    pub fn attr_mut(&mut self, idx: usize) -> &mut LineAttribute {
+        &mut self.attrs.0[idx]
+    }
+
     /// Check that a LineInfo read from the kernel is valid in Rust.
+    #[cfg_attr(not(feature = "ioctl"), allow(dead_code))]
     fn validate(&self) -> ValidationResult {
         if self.num_attrs > NUM_ATTRS_MAX as u32 {
             return Err(ValidationError::new(
@@ -587,6 +656,7 @@ impl LineInfo {
 ///
 /// * `cf` - The open gpiochip device file.
 /// * `offset` - The offset of the line.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
     let li = LineInfo {
@@ -594,7 +664,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct is explicitly validated before being returned.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
+    match unsafe { ioctl(cf.as_raw_fd(), iorw!(Ioctl::GetLineInfo, LineInfo), &li) } {
         0 => li.validate().map(|_| li).map_err(Error::from),
         _ => Err(Error::from_errno()),
     }
@@ -609,6 +679,7 @@ pub fn get_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
 ///
 /// * `cf` - The open gpiochip device file.
 /// * `offset` - The offset of the line to watch.
+#[cfg(feature = "ioctl")]
 #[inline]
 pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
     let li = LineInfo {
@@ -616,7 +687,7 @@ pub fn watch_line_info(cf: &File, offset: Offset) -> Result<LineInfo> {
         ..Default::default()
     };
     // SAFETY: returned struct is explicitly validated before being returned.
-    match unsafe { libc::ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
+    match unsafe { ioctl(cf.as_raw_fd(), iorw!(Ioctl::WatchLineInfo, LineInfo), &li) } {
         0 => li.validate().map(|_| li).map_err(Error::from),
         _ => Err(Error::from_errno()),
     }
@@ -667,15 +738,137 @@ impl LineInfoChangeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineInfoChangeEvent from a byte buffer.
+    ///
+    /// Unlike [`from_slice`](Self::from_slice), the buffer need not be aligned,
+    /// as the event is copied out of the buffer rather than referenced in place.
+    ///
+    /// The buffer is assumed to have been populated by a read of the chip File,
+    /// so the content is validated before being returned.
+    pub fn read_from(buf: &[u8]) -> Result<LineInfoChangeEvent> {
+        let mut d = [0_u64; std::mem::size_of::<LineInfoChangeEvent>() / 8];
+        copy_to_words("LineInfoChangeEvent", buf, &mut d)?;
+        Self::from_slice(&d).cloned()
+    }
+
+    /// Read a single LineInfoChangeEvent from the chip file, returning it by value.
+    ///
+    /// Exactly one event is read, so any further queued events remain available
+    /// to subsequent reads.
+    ///
+    /// The event is read into a buffer on the stack and copied out, so it may
+    /// outlive the read, e.g. be returned from a helper function.
+    /// Prefer [`read_info_change_event`], or [`read_info_change_events`] for bursts
+    /// of events, where the event can be decoded in place from a longer lived buffer.
+    ///
+    /// Reads interrupted by a signal are retried.
+    ///
+    /// If the file is [non-blocking] and no event is available then
+    /// [`Error::WouldBlock`] is returned.
+    ///
+    /// * `cf` - The open gpiochip device file.
+    ///
+    /// [non-blocking]: set_nonblocking
+    #[cfg(feature = "ioctl")]
+    pub fn read_one(cf: &File) -> Result<LineInfoChangeEvent> {
+        let mut buf = [0_u64; std::mem::size_of::<LineInfoChangeEvent>() / 8];
+        read_info_change_event(cf, &mut buf).cloned()
+    }
+
     /// The number of u64 words required to store a LineInfoChangeEvent.
     pub fn u64_size() -> usize {
         std::mem::size_of::<LineInfoChangeEvent>() / 8
     }
 }
 
+/// The size of a LineInfoChangeEvent, in bytes.
+pub const LINE_INFO_CHANGE_EVENT_SIZE: usize = std::mem::size_of::<LineInfoChangeEvent>();
+
+/// Read a single LineInfoChangeEvent from the chip file.
+///
+/// The event is read into, and returned from, the start of `buf`, which must be
+/// at least [`LineInfoChangeEvent::u64_size`] words.
+///
+/// Reads interrupted by a signal are retried.
+///
+/// If the file is [non-blocking] and no event is available then
+/// [`Error::WouldBlock`] is returned.
+///
+/// * `cf` - The open gpiochip device file.
+/// * `buf` - The buffer to read the event into.
+///
+/// [non-blocking]: set_nonblocking
+#[cfg(feature = "ioctl")]
+pub fn read_info_change_event<'a>(
+    cf: &File,
+    buf: &'a mut [u64],
+) -> Result<&'a LineInfoChangeEvent> {
+    // limit the read to a single event
+    let size = LineInfoChangeEvent::u64_size().min(buf.len());
+    let buf = &mut buf[..size];
+    read_records("LineInfoChangeEvent", cf, buf, LINE_INFO_CHANGE_EVENT_SIZE)?;
+    LineInfoChangeEvent::from_slice(buf)
+}
+
+/// Read as many LineInfoChangeEvents from the chip file as will fit in `buf`.
+///
+/// The events are returned as an iterator over the portion of `buf` populated
+/// by the read.
+///
+/// Reads interrupted by a signal are retried.
+///
+/// If the file is [non-blocking] and no event is available then
+/// [`Error::WouldBlock`] is returned.
+///
+/// * `cf` - The open gpiochip device file.
+/// * `buf` - The buffer to read the events into.
+///
+/// [non-blocking]: set_nonblocking
+#[cfg(feature = "ioctl")]
+pub fn read_info_change_events<'a>(
+    cf: &File,
+    buf: &'a mut [u64],
+) -> Result<InfoChangeEventIter<'a>> {
+    let n = read_records("LineInfoChangeEvent", cf, buf, LINE_INFO_CHANGE_EVENT_SIZE)?;
+    Ok(InfoChangeEventIter::new(&buf[..n]))
+}
+
+/// An iterator over the LineInfoChangeEvents contained in a buffer.
+///
+/// The buffer is assumed to have been populated by a read of the chip File,
+/// so each event is validated before being returned.
+///
+/// Iteration stops after the first invalid or partial event.
+#[derive(Clone, Debug)]
+pub struct InfoChangeEventIter<'a> {
+    buf: &'a [u64],
+}
+
+impl<'a> InfoChangeEventIter<'a> {
+    /// Create an iterator over the events contained in `buf`.
+    pub fn new(buf: &'a [u64]) -> InfoChangeEventIter<'a> {
+        InfoChangeEventIter { buf }
+    }
+}
+
+impl<'a> Iterator for InfoChangeEventIter<'a> {
+    type Item = Result<&'a LineInfoChangeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let size = LineInfoChangeEvent::u64_size().min(self.buf.len());
+        let (event, rest) = self.buf.split_at(size);
+        let res = LineInfoChangeEvent::from_slice(event);
+        self.buf = if res.is_ok() { rest } else { &[] };
+        Some(res)
+    }
+}
+
 /// Information about an edge event on a requested line.
 #[repr(C)]
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct LineEdgeEvent {
     /// The best estimate of time of event occurrence, in nanoseconds.
     ///
@@ -734,6 +927,49 @@ impl LineEdgeEvent {
             .map_err(|e| ValidationError::new("kind", e))
     }
 
+    /// Read a LineEdgeEvent from a byte buffer.
+    ///
+    /// Unlike [`from_slice`](Self::from_slice), the buffer need not be aligned,
+    /// as the event is copied out of the buffer rather than referenced in place.
+    ///
+    /// The buffer is assumed to have been populated by a read of the line request File,
+    /// so the content is validated before being returned.
+    pub fn read_from(buf: &[u8]) -> Result<LineEdgeEvent> {
+        let mut d = [0_u64; std::mem::size_of::<LineEdgeEvent>() / 8];
+        copy_to_words("LineEdgeEvent", buf, &mut d)?;
+        Self::from_slice(&d).cloned()
+    }
+
+    /// Read a single LineEdgeEvent from the line request file, returning it by value.
+    ///
+    /// Exactly one event is read, so any further queued events remain available
+    /// to subsequent reads.
+    ///
+    /// The event is small enough to be copied out of a buffer on the stack, so
+    /// it may outlive the read, e.g. be returned from a helper function.
+    /// Prefer [`read_event`] into a longer lived buffer, decoded in place using
+    /// [`from_slice`](Self::from_slice), when reading bursts of events.
+    ///
+    /// Reads interrupted by a signal are retried.
+    ///
+    /// If the file is [non-blocking] and no event is available then
+    /// [`Error::WouldBlock`] is returned.
+    ///
+    /// * `lf` - The file returned by the line request.
+    ///
+    /// [non-blocking]: set_nonblocking
+    #[cfg(feature = "ioctl")]
+    pub fn read_one(lf: &File) -> Result<LineEdgeEvent> {
+        let mut buf = [0_u64; std::mem::size_of::<LineEdgeEvent>() / 8];
+        read_records(
+            "LineEdgeEvent",
+            lf,
+            &mut buf,
+            std::mem::size_of::<LineEdgeEvent>(),
+        )?;
+        Self::from_slice(&buf).copied()
+    }
+
     /// The number of u64 words required to store a LineEdgeEvent.
     pub fn u64_size() -> usize {
         std::mem::size_of::<LineEdgeEvent>() / 8
@@ -744,6 +980,13 @@ impl LineEdgeEvent {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "ioctl")]
+    fn pipe() -> (File, File) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+    }
+
     mod line_attribute {
         use super::LineAttribute;
 
@@ -784,7 +1027,7 @@ mod tests {
     }
 
     mod line_config {
-        use super::LineConfig;
+        use super::{Error, LineAttributeKind, LineConfig, LineFlags, LineValues, NUM_ATTRS_MAX};
 
         #[test]
         fn line_config() {
@@ -794,6 +1037,70 @@ mod tests {
                 concat!("Size of: ", stringify!(LineConfig))
             );
         }
+
+        #[test]
+        fn set_output_values() {
+            let mut lc = LineConfig::default();
+            lc.add_flags(LineFlags::OUTPUT, 0x02);
+            let values = LineValues::from_slice(&[true, false, true]);
+            assert!(lc.set_output_values(3, &values).is_ok());
+            assert_eq!(lc.num_attrs, 2);
+            let lca = lc.attr(1);
+            assert_eq!(lca.mask, 0x07);
+            assert_eq!(lca.attr.kind, LineAttributeKind::Values);
+            assert_eq!(unsafe { lca.attr.value.values }, 0x05);
+
+            // merged into the existing attr
+            let mut values = LineValues::default();
+            values.set(0, false);
+            values.set(3, true);
+            assert!(lc.set_output_values(4, &values).is_ok());
+            assert_eq!(lc.num_attrs, 2);
+            let lca = lc.attr(1);
+            assert_eq!(lca.mask, 0x0f);
+            assert_eq!(unsafe { lca.attr.value.values }, 0x0c);
+
+            // unmasked bits are ignored
+            let values = LineValues {
+                bits: 0x0f,
+                mask: 0,
+            };
+            assert!(lc.set_output_values(4, &values).is_ok());
+            assert_eq!(lc.attr(1).mask, 0x0f);
+            assert_eq!(unsafe { lc.attr(1).attr.value.values }, 0x0c);
+
+            // all lines
+            let mut lc = LineConfig::default();
+            let values = LineValues {
+                bits: u64::MAX,
+                mask: u64::MAX,
+            };
+            assert!(lc.set_output_values(64, &values).is_ok());
+            assert_eq!(lc.attr(0).mask, u64::MAX);
+        }
+
+        #[test]
+        fn set_output_values_out_of_range() {
+            let mut lc = LineConfig::default();
+            let values = LineValues::from_slice(&[true, false, true]);
+            assert_eq!(
+                lc.set_output_values(2, &values),
+                Err(Error::IndexOutOfRange(2, 2))
+            );
+            assert_eq!(lc.num_attrs, 0);
+            assert_eq!(lc.attr(0).attr.kind, LineAttributeKind::Unused);
+        }
+
+        #[test]
+        fn set_output_values_too_many_attrs() {
+            let mut lc = LineConfig::default();
+            for i in 0..NUM_ATTRS_MAX {
+                lc.add_debounce(10, 0x01 << i);
+            }
+            let values = LineValues::from_slice(&[true, false, true]);
+            assert_eq!(lc.set_output_values(3, &values), Err(Error::TooManyAttrs));
+            assert_eq!(lc.num_attrs, NUM_ATTRS_MAX as u32);
+        }
     }
 
     mod line_request {
@@ -807,6 +1114,19 @@ mod tests {
                 concat!("Size of: ", stringify!(LineRequest))
             );
         }
+
+        #[test]
+        fn requested_offsets() {
+            use super::Offsets;
+
+            let lr = LineRequest {
+                offsets: Offsets::from_slice(&[4, 2, 7, 9]),
+                num_lines: 2,
+                ..Default::default()
+            };
+            let x: Vec<u32> = lr.requested_offsets().copied().collect();
+            assert_eq!(x, [4, 2]);
+        }
     }
 
     mod line_values {
@@ -968,6 +1288,78 @@ mod tests {
                 concat!("Size of: ", stringify!(LineInfoChangeEvent))
             );
         }
+
+        #[test]
+        fn read_from() {
+            use super::{Error, LineInfo, LineInfoChangeKind, UnderReadError};
+
+            let mut raw = vec![0_u8; 288];
+            raw[64..68].copy_from_slice(&5_u32.to_ne_bytes());
+            raw[256..264].copy_from_slice(&1234_u64.to_ne_bytes());
+            raw[264..268].copy_from_slice(&2_u32.to_ne_bytes());
+            let ev = LineInfoChangeEvent {
+                info: LineInfo {
+                    offset: 5,
+                    ..Default::default()
+                },
+                timestamp_ns: 1234,
+                kind: LineInfoChangeKind::Released,
+                padding: Default::default(),
+            };
+
+            // misaligned
+            let mut buf = vec![0_u8; 1];
+            buf.extend_from_slice(&raw);
+            assert_eq!(LineInfoChangeEvent::read_from(&buf[1..]).unwrap(), ev);
+
+            // truncated
+            assert_eq!(
+                LineInfoChangeEvent::read_from(&buf[1..288]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineInfoChangeEvent", 288, 287))
+            );
+
+            // invalid
+            buf[265] = 4;
+            assert!(matches!(
+                LineInfoChangeEvent::read_from(&buf[1..]),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        #[test]
+        #[cfg(feature = "ioctl")]
+        fn read_one() {
+            use super::{pipe, read_event, set_nonblocking, Error};
+            use std::io::Write;
+
+            let size = LineInfoChangeEvent::u64_size() * 8;
+            let (r, mut w) = pipe();
+            for (ts, kind) in [(1234_u64, 1_u32), (5678, 3)] {
+                let mut raw = vec![0_u8; size];
+                let ts_pos = size - 32;
+                raw[ts_pos..ts_pos + 8].copy_from_slice(&ts.to_ne_bytes());
+                raw[ts_pos + 8..ts_pos + 12].copy_from_slice(&kind.to_ne_bytes());
+                w.write_all(&raw).unwrap();
+            }
+
+            // only the first event is consumed
+            let ev = LineInfoChangeEvent::read_one(&r).unwrap();
+            assert_eq!(ev.timestamp_ns, 1234);
+            assert_eq!(ev.kind, LineInfoChangeKind::Requested);
+
+            // leaving the second intact
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 2];
+            assert_eq!(
+                read_event(&r, &mut buf),
+                Ok(LineInfoChangeEvent::u64_size())
+            );
+            let ev = LineInfoChangeEvent::from_slice(&buf).unwrap();
+            assert_eq!(ev.timestamp_ns, 5678);
+            assert_eq!(ev.kind, LineInfoChangeKind::Reconfigured);
+
+            set_nonblocking(&r, true).unwrap();
+            assert_eq!(LineInfoChangeEvent::read_one(&r), Err(Error::WouldBlock));
+        }
     }
 
     mod line_event {
@@ -1011,5 +1403,75 @@ mod tests {
                 concat!("Size of: ", stringify!(LineEdgeEvent))
             );
         }
+
+        #[test]
+        fn read_from() {
+            use super::{Error, UnderReadError};
+
+            let mut raw = Vec::new();
+            raw.extend_from_slice(&1234_u64.to_ne_bytes());
+            raw.extend_from_slice(&2_u32.to_ne_bytes());
+            raw.extend_from_slice(&5_u32.to_ne_bytes());
+            raw.extend_from_slice(&3_u32.to_ne_bytes());
+            raw.extend_from_slice(&2_u32.to_ne_bytes());
+            raw.resize(48, 0);
+            let ev = LineEdgeEvent {
+                timestamp_ns: 1234,
+                kind: LineEdgeEventKind::FallingEdge,
+                offset: 5,
+                seqno: 3,
+                line_seqno: 2,
+                padding: Default::default(),
+            };
+
+            // misaligned
+            let mut buf = vec![0_u8; 1];
+            buf.extend_from_slice(&raw);
+            assert_eq!(LineEdgeEvent::read_from(&buf[1..]).unwrap(), ev);
+
+            // truncated
+            assert_eq!(
+                LineEdgeEvent::read_from(&buf[1..48]).unwrap_err(),
+                Error::UnderRead(UnderReadError::new("LineEdgeEvent", 48, 47))
+            );
+
+            // invalid
+            buf[9] = 3;
+            assert!(matches!(
+                LineEdgeEvent::read_from(&buf[1..]),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        #[test]
+        #[cfg(feature = "ioctl")]
+        fn read_one() {
+            use super::{pipe, read_event, set_nonblocking, Error};
+            use std::io::Write;
+
+            let (r, mut w) = pipe();
+            for (ts, kind) in [(1234_u64, 1_u32), (5678, 2)] {
+                let mut raw = Vec::new();
+                raw.extend_from_slice(&ts.to_ne_bytes());
+                raw.extend_from_slice(&kind.to_ne_bytes());
+                raw.resize(48, 0);
+                w.write_all(&raw).unwrap();
+            }
+
+            // only the first event is consumed
+            let ev = LineEdgeEvent::read_one(&r).unwrap();
+            assert_eq!(ev.timestamp_ns, 1234);
+            assert_eq!(ev.kind, LineEdgeEventKind::RisingEdge);
+
+            // leaving the second intact
+            let mut buf = vec![0_u64; LineEdgeEvent::u64_size() * 2];
+            assert_eq!(read_event(&r, &mut buf), Ok(LineEdgeEvent::u64_size()));
+            let ev = LineEdgeEvent::from_slice(&buf).unwrap();
+            assert_eq!(ev.timestamp_ns, 5678);
+            assert_eq!(ev.kind, LineEdgeEventKind::FallingEdge);
+
+            set_nonblocking(&r, true).unwrap();
+            assert_eq!(LineEdgeEvent::read_one(&r), Err(Error::WouldBlock));
+        }
     }
 }
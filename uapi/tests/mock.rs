@@ -0,0 +1,461 @@
+// SPDX-FileCopyrightText: 2024 Kent Gibson <warthog618@gmail.com>
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Fault injection tests using the mockable system calls.
+//
+// None of these require a GPIO chip, as calls are intercepted before
+// reaching the kernel.
+
+use gpiocdev_uapi::mock::{install, Call, FaultInjector, Response};
+use gpiocdev_uapi::{Errno, Error};
+use std::fs::File;
+use std::os::unix::prelude::AsRawFd;
+use std::rc::Rc;
+
+fn dev_null() -> File {
+    File::open("/dev/null").unwrap()
+}
+
+fn injector() -> Rc<FaultInjector> {
+    Rc::new(FaultInjector::default())
+}
+
+fn words_as_bytes(w: &[u64]) -> Vec<u8> {
+    w.iter().flat_map(|w| w.to_ne_bytes()).collect()
+}
+
+#[test]
+fn passthrough_when_not_installed() {
+    let f = dev_null();
+    let mut buf = [0_u64; 4];
+    assert_eq!(gpiocdev_uapi::read_event(&f, &mut buf), Ok(0));
+}
+
+#[test]
+fn uninstalled_on_drop() {
+    let f = dev_null();
+    let fi = injector();
+    {
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::EIO));
+        let mut buf = [0_u64; 4];
+        assert_eq!(
+            gpiocdev_uapi::read_event(&f, &mut buf),
+            Err(Error::Os(Errno(libc::EIO)))
+        );
+    }
+    fi.push_response(Response::Errno(libc::EIO));
+    let mut buf = [0_u64; 4];
+    assert_eq!(gpiocdev_uapi::read_event(&f, &mut buf), Ok(0));
+    assert_eq!(fi.calls().len(), 1);
+}
+
+mod read_event {
+    use super::*;
+
+    #[test]
+    fn eintr() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::EINTR));
+        fi.push_response(Response::Data(words_as_bytes(&[1])));
+
+        let mut buf = [0_u64; 4];
+        assert_eq!(gpiocdev_uapi::read_event(&f, &mut buf), Ok(1));
+        let read = Call::Read {
+            fd: f.as_raw_fd(),
+            count: 32,
+        };
+        assert_eq!(fi.calls(), vec![read.clone(), read]);
+    }
+
+    #[test]
+    fn eagain() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::EAGAIN));
+
+        let mut buf = [0_u64; 4];
+        assert_eq!(
+            gpiocdev_uapi::read_event(&f, &mut buf),
+            Err(Error::WouldBlock)
+        );
+    }
+
+    #[test]
+    fn short_read() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Data(vec![0; 12]));
+
+        let mut buf = [0_u64; 4];
+        let err = gpiocdev_uapi::read_event(&f, &mut buf).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Reading read_event returned 12 bytes, expected 32."
+        );
+    }
+
+    #[test]
+    fn words() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Data(words_as_bytes(&[1, 2, 3])));
+
+        let mut buf = [0_u64; 4];
+        assert_eq!(gpiocdev_uapi::read_event(&f, &mut buf), Ok(3));
+        assert_eq!(buf, [1, 2, 3, 0]);
+    }
+}
+
+mod get_chip_info {
+    use super::*;
+
+    #[test]
+    fn enotty() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::ENOTTY));
+
+        assert_eq!(
+            gpiocdev_uapi::v2::get_chip_info(&f),
+            Err(Error::Os(Errno(libc::ENOTTY)))
+        );
+        assert!(matches!(
+            fi.calls()[..],
+            [Call::Ioctl { fd, .. }] if fd == f.as_raw_fd()
+        ));
+    }
+
+    #[test]
+    fn data() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        let mut d = vec![0_u8; 68];
+        d[..5].copy_from_slice(b"mock0");
+        d[32..36].copy_from_slice(b"lbl0");
+        d[64..68].copy_from_slice(&42_u32.to_ne_bytes());
+        fi.push_response(Response::Data(d));
+
+        let ci = gpiocdev_uapi::v2::get_chip_info(&f).unwrap();
+        assert_eq!(ci.name.as_os_str(), "mock0");
+        assert_eq!(ci.label.as_os_str(), "lbl0");
+        assert_eq!(ci.num_lines, 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match the size of the ioctl argument")]
+    fn data_size_mismatch() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Data(vec![0_u8; 69]));
+
+        _ = gpiocdev_uapi::v2::get_chip_info(&f);
+    }
+}
+
+#[cfg(feature = "uapi_v1")]
+mod v1 {
+    use super::*;
+    use gpiocdev_uapi::v1::HandleRequest;
+
+    #[test]
+    fn get_line_handle_too_many_lines() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+
+        let hr = HandleRequest {
+            num_lines: 65,
+            ..Default::default()
+        };
+        assert_eq!(
+            gpiocdev_uapi::v1::get_line_handle(&f, hr).unwrap_err(),
+            Error::TooManyLines(65)
+        );
+        // rejected before reaching the kernel
+        assert!(fi.calls().is_empty());
+    }
+}
+
+#[cfg(feature = "uapi_v2")]
+mod v2 {
+    use super::*;
+    use gpiocdev_uapi::v2::{
+        read_info_change_event, read_info_change_events, InfoChangeEventIter, LineEdgeEvent,
+        LineInfoChangeEvent, LineInfoChangeKind, LineValues, LINE_INFO_CHANGE_EVENT_SIZE,
+    };
+
+    // info change events for the line at offset, with the given kinds
+    fn info_change_events(offset: u32, kinds: &[u32]) -> Vec<u8> {
+        let mut d = Vec::new();
+        for kind in kinds {
+            let mut event = vec![0_u8; LINE_INFO_CHANGE_EVENT_SIZE];
+            // info.offset follows the name and consumer
+            event[64..68].copy_from_slice(&offset.to_ne_bytes());
+            // kind follows the info and timestamp
+            event[264..268].copy_from_slice(&kind.to_ne_bytes());
+            d.extend_from_slice(&event);
+        }
+        d
+    }
+
+    #[test]
+    fn get_line_too_many_lines() {
+        use gpiocdev_uapi::v2::LineRequest;
+
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+
+        let lr = LineRequest {
+            num_lines: 80,
+            ..Default::default()
+        };
+        let err = gpiocdev_uapi::v2::get_line(&f, lr).unwrap_err();
+        assert_eq!(err, Error::TooManyLines(80));
+        assert_eq!(
+            err.to_string(),
+            "Requested 80 lines is greater than the maximum of 64."
+        );
+        // rejected before reaching the kernel
+        assert!(fi.calls().is_empty());
+    }
+
+    #[test]
+    fn get_line_values() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::EPERM));
+        fi.push_response(Response::Data(words_as_bytes(&[0b1010, 0b1110])));
+
+        let mut lv = LineValues::default();
+        assert_eq!(
+            gpiocdev_uapi::v2::get_line_values(&f, &mut lv),
+            Err(Error::Os(Errno(libc::EPERM)))
+        );
+        assert!(gpiocdev_uapi::v2::get_line_values(&f, &mut lv).is_ok());
+        assert_eq!(lv.bits, 0b1010);
+        assert_eq!(lv.mask, 0b1110);
+    }
+
+    #[test]
+    fn set_line_values_ebusy() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        fi.push_response(Response::Errno(libc::EBUSY));
+
+        let lv = LineValues::default();
+        assert_eq!(
+            gpiocdev_uapi::v2::set_line_values(&f, &lv),
+            Err(Error::Os(Errno(libc::EBUSY)))
+        );
+    }
+
+    #[test]
+    fn invalid_edge_event() {
+        let f = dev_null();
+        let fi = injector();
+        let _guard = install(fi.clone());
+        // timestamp, kind, offset, seqno, line_seqno, padding
+        let mut d = Vec::new();
+        d.extend_from_slice(&1234_u64.to_ne_bytes());
+        d.extend_from_slice(&3_u32.to_ne_bytes());
+        d.extend_from_slice(&[0; 36]);
+        fi.push_response(Response::Data(d));
+
+        let mut buf = [0_u64; 6];
+        assert_eq!(gpiocdev_uapi::read_event(&f, &mut buf), Ok(6));
+        assert_eq!(
+            LineEdgeEvent::from_slice(&buf).unwrap_err().to_string(),
+            "Kernel returned invalid kind: invalid value: 3"
+        );
+    }
+
+    mod read_info_change_event {
+        use super::*;
+
+        #[test]
+        fn eintr() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Errno(libc::EINTR));
+            fi.push_response(Response::Data(info_change_events(3, &[1])));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 2];
+            let event = read_info_change_event(&f, &mut buf).unwrap();
+            assert_eq!(event.info.offset, 3);
+            assert_eq!(event.kind, LineInfoChangeKind::Requested);
+            // retried, and limited to a single event
+            let read = Call::Read {
+                fd: f.as_raw_fd(),
+                count: LINE_INFO_CHANGE_EVENT_SIZE,
+            };
+            assert_eq!(fi.calls(), vec![read.clone(), read]);
+        }
+
+        #[test]
+        fn eagain() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Errno(libc::EAGAIN));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size()];
+            assert_eq!(
+                read_info_change_event(&f, &mut buf).unwrap_err(),
+                Error::WouldBlock
+            );
+        }
+
+        #[test]
+        fn zero_length() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Data(Vec::new()));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size()];
+            assert_eq!(
+                read_info_change_event(&f, &mut buf)
+                    .unwrap_err()
+                    .to_string(),
+                "Reading LineInfoChangeEvent returned 0 bytes, expected 288."
+            );
+        }
+
+        #[test]
+        fn short_read() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Data(vec![0; 100]));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size()];
+            assert_eq!(
+                read_info_change_event(&f, &mut buf)
+                    .unwrap_err()
+                    .to_string(),
+                "Reading LineInfoChangeEvent returned 100 bytes, expected 288."
+            );
+        }
+
+        #[test]
+        fn buffer_too_small() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+
+            let mut buf = vec![0_u64; 4];
+            assert_eq!(
+                read_info_change_event(&f, &mut buf)
+                    .unwrap_err()
+                    .to_string(),
+                "Reading LineInfoChangeEvent returned 32 bytes, expected 288."
+            );
+            // rejected before reaching the kernel
+            assert!(fi.calls().is_empty());
+        }
+
+        #[test]
+        fn invalid_kind() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Data(info_change_events(3, &[7])));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size()];
+            assert_eq!(
+                read_info_change_event(&f, &mut buf)
+                    .unwrap_err()
+                    .to_string(),
+                "Kernel returned invalid kind: invalid value: 7"
+            );
+        }
+    }
+
+    mod read_info_change_events {
+        use super::*;
+
+        #[test]
+        fn multiple() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            fi.push_response(Response::Data(info_change_events(5, &[1, 3, 2])));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 4];
+            let kinds: Vec<LineInfoChangeKind> = read_info_change_events(&f, &mut buf)
+                .unwrap()
+                .map(|e| e.unwrap().kind)
+                .collect();
+            assert_eq!(
+                kinds,
+                vec![
+                    LineInfoChangeKind::Requested,
+                    LineInfoChangeKind::Reconfigured,
+                    LineInfoChangeKind::Released
+                ]
+            );
+        }
+
+        #[test]
+        fn partial_event() {
+            let f = dev_null();
+            let fi = injector();
+            let _guard = install(fi.clone());
+            let mut d = info_change_events(5, &[1, 2]);
+            d.truncate(LINE_INFO_CHANGE_EVENT_SIZE + 8);
+            fi.push_response(Response::Data(d));
+
+            let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 2];
+            assert_eq!(
+                read_info_change_events(&f, &mut buf)
+                    .unwrap_err()
+                    .to_string(),
+                "Reading LineInfoChangeEvent returned 296 bytes, expected 576."
+            );
+        }
+
+        #[test]
+        fn iter_stops_at_invalid_event() {
+            let d = info_change_events(5, &[1, 9, 2]);
+            let buf: Vec<u64> = d
+                .chunks_exact(8)
+                .map(|w| u64::from_ne_bytes(w.try_into().unwrap()))
+                .collect();
+            let mut iter = InfoChangeEventIter::new(&buf);
+            assert_eq!(
+                iter.next().unwrap().unwrap().kind,
+                LineInfoChangeKind::Requested
+            );
+            assert_eq!(
+                iter.next().unwrap().unwrap_err().to_string(),
+                "Kernel returned invalid kind: invalid value: 9"
+            );
+            assert!(iter.next().is_none());
+        }
+
+        #[test]
+        fn iter_partial_event() {
+            let buf = vec![0_u64; 2];
+            let mut iter = InfoChangeEventIter::new(&buf);
+            assert_eq!(
+                iter.next().unwrap().unwrap_err().to_string(),
+                "Reading LineInfoChangeEvent returned 16 bytes, expected 288."
+            );
+            assert!(iter.next().is_none());
+        }
+    }
+}
@@ -390,3 +390,31 @@ fn with_drive_flags() {
         Error::Os(Errno(libc::EINVAL))
     );
 }
+
+#[test]
+fn nonblocking() {
+    let s = Simpleton::new(4);
+    let f = fs::File::open(s.dev_path()).unwrap();
+    let offset = 2;
+    let er = EventRequest {
+        offset,
+        consumer: "nonblocking".into(),
+        eventflags: EventRequestFlags::BOTH_EDGES,
+        ..Default::default()
+    };
+
+    let l = get_line_event(&f, er).unwrap();
+    set_nonblocking(&l, true).unwrap();
+
+    let mut buf = vec![0_u64; LineEdgeEvent::u64_size()];
+    assert_eq!(read_event(&l, &mut buf), Err(Error::WouldBlock));
+    assert!(!wait_readable(&l, Some(EVENT_WAIT_TIMEOUT)).unwrap());
+
+    s.pullup(offset).unwrap();
+    assert!(wait_readable(&l, Some(EVENT_WAIT_TIMEOUT)).unwrap());
+    assert_eq!(read_event(&l, &mut buf), Ok(LineEdgeEvent::u64_size()));
+    let event = LineEdgeEvent::from_slice(&buf).unwrap();
+    assert_eq!(event.kind, LineEdgeEventKind::RisingEdge);
+
+    assert_eq!(read_event(&l, &mut buf), Err(Error::WouldBlock));
+}
@@ -69,6 +69,70 @@ fn watch() {
     drop(l);
 }
 
+#[test]
+fn read_info_change_events() {
+    use gpiocdev_uapi::v1::{
+        read_info_change_event, read_info_change_events, LineInfoChangeEvent, LineInfoChangeKind,
+    };
+    let s = Simpleton::new(4);
+    let f = fs::File::open(s.dev_path()).unwrap();
+    let offset = 1;
+
+    watch_line_info(&f, offset).unwrap();
+
+    let mut hr = HandleRequest {
+        num_lines: 1,
+        consumer: "as_input".into(),
+        flags: HandleRequestFlags::INPUT,
+        ..Default::default()
+    };
+    hr.offsets.set(0, offset);
+
+    // request and release, leaving both events queued
+    let l: fs::File = get_line_handle(&f, hr.clone()).unwrap();
+    drop(l);
+    assert!(wait_event(&f, EVENT_WAIT_TIMEOUT).unwrap());
+
+    let mut buf = vec![0_u64; LineInfoChangeEvent::u64_size() * 4];
+    let events: Vec<LineInfoChangeEvent> = read_info_change_events(&f, &mut buf)
+        .unwrap()
+        .map(|e| e.unwrap().clone())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].info.offset, offset);
+    assert_eq!(events[0].kind, LineInfoChangeKind::Requested);
+    assert_eq!(events[1].info.offset, offset);
+    assert_eq!(events[1].kind, LineInfoChangeKind::Released);
+
+    // request and release, reading the events individually
+    let l: fs::File = get_line_handle(&f, hr.clone()).unwrap();
+    drop(l);
+    assert!(wait_event(&f, EVENT_WAIT_TIMEOUT).unwrap());
+
+    let event = read_info_change_event(&f, &mut buf).unwrap();
+    assert_eq!(event.info.offset, offset);
+    assert_eq!(event.kind, LineInfoChangeKind::Requested);
+    assert!(has_event(&f).unwrap());
+    let event = read_info_change_event(&f, &mut buf).unwrap();
+    assert_eq!(event.info.offset, offset);
+    assert_eq!(event.kind, LineInfoChangeKind::Released);
+    assert!(!has_event(&f).unwrap());
+
+    // request and release, reading the events by value
+    let l: fs::File = get_line_handle(&f, hr).unwrap();
+    drop(l);
+    assert!(wait_event(&f, EVENT_WAIT_TIMEOUT).unwrap());
+
+    let event = LineInfoChangeEvent::read_one(&f).unwrap();
+    assert_eq!(event.info.offset, offset);
+    assert_eq!(event.kind, LineInfoChangeKind::Requested);
+    assert!(has_event(&f).unwrap());
+    let event = LineInfoChangeEvent::read_one(&f).unwrap();
+    assert_eq!(event.info.offset, offset);
+    assert_eq!(event.kind, LineInfoChangeKind::Released);
+    assert!(!has_event(&f).unwrap());
+}
+
 #[test]
 fn with_multiple_watchers() {
     let s = Simpleton::new(4);
@@ -242,6 +242,30 @@ fn with_output_values() {
     drop(l);
 }
 
+#[test]
+fn with_set_output_values() {
+    let s = Simpleton::new(4);
+    let f = fs::File::open(s.dev_path()).unwrap();
+    let mut lr = LineRequest {
+        num_lines: 3,
+        consumer: "with_set_output_values".into(),
+        config: LineConfig {
+            flags: LineFlags::OUTPUT,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    lr.offsets.copy_from_slice(&[2, 0, 3]);
+    let values = LineValues::from_slice(&[true, false, true]);
+    lr.config.set_output_values(3, &values).unwrap();
+
+    let l = get_line(&f, lr).unwrap();
+    assert_eq!(s.get_level(2).unwrap(), Level::High);
+    assert_eq!(s.get_level(0).unwrap(), Level::Low);
+    assert_eq!(s.get_level(3).unwrap(), Level::High);
+    drop(l);
+}
+
 #[test]
 fn with_both_edges() {
     let s = Simpleton::new(4);
@@ -707,3 +731,35 @@ fn with_drive_without_output() {
         Error::Os(Errno(libc::EINVAL))
     );
 }
+
+#[test]
+fn nonblocking() {
+    let s = Simpleton::new(4);
+    let f = fs::File::open(s.dev_path()).unwrap();
+    let offset = 2;
+    let mut lr = LineRequest {
+        num_lines: 1,
+        consumer: "nonblocking".into(),
+        config: LineConfig {
+            flags: LineFlags::INPUT | LineFlags::EDGE_RISING | LineFlags::EDGE_FALLING,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    lr.offsets.set(0, offset);
+
+    let l = get_line(&f, lr).unwrap();
+    set_nonblocking(&l, true).unwrap();
+
+    let mut buf = vec![0_u64; LineEdgeEvent::u64_size()];
+    assert_eq!(read_event(&l, &mut buf), Err(Error::WouldBlock));
+    assert!(!wait_readable(&l, Some(EVENT_WAIT_TIMEOUT)).unwrap());
+
+    s.pullup(offset).unwrap();
+    assert!(wait_readable(&l, Some(EVENT_WAIT_TIMEOUT)).unwrap());
+    assert_eq!(read_event(&l, &mut buf), Ok(LineEdgeEvent::u64_size()));
+    let event = LineEdgeEvent::from_slice(&buf).unwrap();
+    assert_eq!(event.kind, LineEdgeEventKind::RisingEdge);
+
+    assert_eq!(read_event(&l, &mut buf), Err(Error::WouldBlock));
+}